@@ -0,0 +1,76 @@
+use std::borrow::Cow;
+
+use criterion::Criterion;
+use valence::client::{broadcast_packet_cached, ClientBundle, ClientBundleArgs};
+use valence::packet::encode::{PacketEncoder, WritePacket};
+use valence::packet::s2c::play::player_list::{Actions, Entry};
+use valence::packet::s2c::play::PlayerListS2c;
+use valence::prelude::*;
+
+use crate::many_clients::DiscardConnection;
+
+const CLIENT_COUNT: usize = 1000;
+const ENTRY_COUNT: usize = 50;
+
+/// Benches writing a player list update to many clients, once by encoding the
+/// packet for every client and once by encoding it a single time with
+/// [`broadcast_packet_cached`].
+pub fn broadcast(c: &mut Criterion) {
+    let mut clients: Vec<_> = (0..CLIENT_COUNT)
+        .map(|i| {
+            let mut enc = PacketEncoder::new();
+            enc.set_compression(Some(256));
+
+            ClientBundle::new(ClientBundleArgs {
+                username: format!("bot_{i}"),
+                uuid: Uuid::from_u128(i as u128),
+                ip: "127.0.0.1".parse().unwrap(),
+                properties: vec![],
+                conn: Box::new(DiscardConnection),
+                enc,
+                send_queue_limit: SendQueueLimit::default(),
+            })
+            .client
+        })
+        .collect();
+
+    let usernames: Vec<_> = (0..ENTRY_COUNT).map(|i| format!("player_{i}")).collect();
+
+    let entries: Vec<_> = usernames
+        .iter()
+        .enumerate()
+        .map(|(i, username)| Entry {
+            player_uuid: Uuid::from_u128(i as u128),
+            username,
+            ping: i as i32 * 10,
+            display_name: Some(Cow::Owned(username.clone().color(Color::GOLD))),
+            ..Default::default()
+        })
+        .collect();
+
+    let packet = PlayerListS2c {
+        actions: Actions::new()
+            .with_update_latency(true)
+            .with_update_display_name(true),
+        entries: Cow::Borrowed(&entries),
+    };
+
+    c.bench_function("broadcast_player_list_per_client", |b| {
+        b.iter(|| {
+            for client in &mut clients {
+                client.write_packet(&packet);
+                client.flush_packets().unwrap();
+            }
+        });
+    });
+
+    c.bench_function("broadcast_player_list_cached", |b| {
+        b.iter(|| {
+            broadcast_packet_cached(&packet, &mut clients);
+
+            for client in &mut clients {
+                client.flush_packets().unwrap();
+            }
+        });
+    });
+}
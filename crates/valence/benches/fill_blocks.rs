@@ -0,0 +1,83 @@
+use std::hint::black_box;
+
+use criterion::Criterion;
+use valence::instance::FillEvents;
+use valence::prelude::*;
+
+/// Benches filling a 100x50x100 region with [`Instance::fill_blocks`] against
+/// calling [`Instance::set_block`] for every position. A tick is run after
+/// every fill so that the cost of sending the changes is included.
+pub fn fill_blocks(c: &mut Criterion) {
+    let mut app = App::new();
+
+    app.add_plugins(DefaultPlugins);
+    app.add_startup_system(setup);
+
+    // Run startup schedule.
+    app.update();
+
+    let mut stone = false;
+
+    c.bench_function("Instance::fill_blocks", |b| {
+        b.iter(|| {
+            stone = !stone;
+            let block = if stone {
+                BlockState::STONE
+            } else {
+                BlockState::AIR
+            };
+
+            let mut instance = app
+                .world
+                .query::<&mut Instance>()
+                .single_mut(&mut app.world);
+
+            black_box(instance.fill_blocks([0, 0, 0], [99, 49, 99], block, FillEvents::Region));
+
+            app.update();
+        });
+    });
+
+    c.bench_function("Instance::set_block (100x50x100)", |b| {
+        b.iter(|| {
+            stone = !stone;
+            let block = if stone {
+                BlockState::STONE
+            } else {
+                BlockState::AIR
+            };
+
+            let mut instance = app
+                .world
+                .query::<&mut Instance>()
+                .single_mut(&mut app.world);
+
+            for y in 0..50 {
+                for z in 0..100 {
+                    for x in 0..100 {
+                        black_box(instance.set_block([x, y, z], block));
+                    }
+                }
+            }
+
+            app.update();
+        });
+    });
+}
+
+fn setup(
+    mut commands: Commands,
+    dimensions: Query<&DimensionType>,
+    biomes: Query<&Biome>,
+    server: Res<Server>,
+) {
+    let mut instance = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+    for z in -1..8 {
+        for x in -1..8 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    commands.spawn(instance);
+}
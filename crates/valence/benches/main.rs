@@ -2,8 +2,11 @@ use criterion::{criterion_group, criterion_main};
 
 mod anvil;
 mod block;
+mod broadcast;
 mod decode_array;
+mod fill_blocks;
 mod idle;
+mod many_clients;
 mod packet;
 mod var_int;
 mod var_long;
@@ -12,8 +15,11 @@ criterion_group! {
     benches,
     anvil::load,
     block::block,
+    broadcast::broadcast,
     decode_array::decode_array,
+    fill_blocks::fill_blocks,
     idle::idle_update,
+    many_clients::many_clients,
     packet::packet,
     var_int::var_int,
     var_long::var_long,
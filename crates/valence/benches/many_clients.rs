@@ -0,0 +1,114 @@
+use bytes::BytesMut;
+use criterion::Criterion;
+use valence::client::{ClientBundle, ClientBundleArgs, ClientConnection, ReceivedPacket};
+use valence::entity::cow::CowEntityBundle;
+use valence::packet::encode::PacketEncoder;
+use valence::prelude::*;
+use valence::uuid::UniqueId;
+
+const CLIENT_COUNT: usize = 300;
+const COW_COUNT: usize = 300;
+
+/// Benches a server tick with many clients and entities walking around, which
+/// is dominated by assembling the packets of every client.
+///
+/// The clients are updated in parallel on the compute task pool, which uses
+/// all available cores. Run the bench restricted to a single core (e.g. with
+/// `taskset -c 0`) to compare against sequential updates.
+pub fn many_clients(c: &mut Criterion) {
+    let mut app = App::new();
+
+    app.add_plugins(DefaultPlugins);
+    app.add_startup_system(setup);
+
+    // Run startup schedule.
+    app.update();
+
+    let mut tick = 0;
+
+    c.bench_function("many_clients", |b| {
+        b.iter(|| {
+            tick += 1;
+            let offset = (tick % 16) as f64 * 2.0;
+
+            for (mut pos, uuid) in app
+                .world
+                .query::<(&mut Position, &UniqueId)>()
+                .iter_mut(&mut app.world)
+            {
+                let i = uuid.0.as_u128() as usize;
+                let x = (i % 20) as f64 * 8.0 - 80.0 + offset;
+                let z = (i / 20 % 20) as f64 * 8.0 - 80.0;
+
+                pos.set([x, 65.0, z]);
+            }
+
+            app.update();
+        });
+    });
+}
+
+fn setup(
+    mut commands: Commands,
+    dimensions: Query<&DimensionType>,
+    biomes: Query<&Biome>,
+    server: Res<Server>,
+) {
+    let mut instance = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+    for z in -10..10 {
+        for x in -10..10 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    for z in -160..160 {
+        for x in -160..160 {
+            instance.set_block([x, 64, z], BlockState::GRASS_BLOCK);
+        }
+    }
+
+    let instance = commands.spawn(instance).id();
+
+    for i in 0..CLIENT_COUNT {
+        let mut client = ClientBundle::new(ClientBundleArgs {
+            username: format!("bot_{i}"),
+            uuid: Uuid::from_u128(i as u128),
+            ip: "127.0.0.1".parse().unwrap(),
+            properties: vec![],
+            conn: Box::new(DiscardConnection),
+            enc: PacketEncoder::new(),
+            send_queue_limit: SendQueueLimit::default(),
+        });
+
+        client.player.location.0 = instance;
+
+        commands.spawn(client);
+    }
+
+    for i in 0..COW_COUNT {
+        commands.spawn(CowEntityBundle {
+            location: Location(instance),
+            uuid: UniqueId(Uuid::from_u128((CLIENT_COUNT + i) as u128)),
+            ..Default::default()
+        });
+    }
+}
+
+/// A synthetic client connection which discards all packets sent to it and
+/// never sends anything.
+pub struct DiscardConnection;
+
+impl ClientConnection for DiscardConnection {
+    fn try_send(&mut self, _bytes: BytesMut) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn try_recv(&mut self) -> anyhow::Result<Option<ReceivedPacket>> {
+        Ok(None)
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use valence::prelude::*;
-use valence_advancement::bevy_hierarchy::{BuildChildren, Children, Parent};
+use valence_advancement::bevy_hierarchy::BuildChildren;
 use valence_advancement::ForceTabUpdate;
 use valence_client::SpawnClientsSet;
 
@@ -71,66 +71,60 @@ fn setup(
 
     let root_advancement = commands
         .spawn((
-            AdvancementBundle {
-                advancement: Advancement::new(ident!("custom:root").into()),
-                requirements: AdvancementRequirements(vec![vec![root_criteria]]),
-                cached_bytes: Default::default(),
-            },
-            AdvancementDisplay {
-                title: "Root".into(),
-                description: "Toggles when you sneak".into(),
-                icon: Some(ItemStack::new(ItemKind::Stone, 1, None)),
-                frame_type: AdvancementFrameType::Task,
-                show_toast: true,
-                hidden: false,
-                background_texture: Some(ident!("textures/block/stone.png").into()),
-                x_coord: 0.0,
-                y_coord: 0.0,
-            },
+            AdvancementBundle::new(ident!("custom:root"))
+                .with_requirements(AdvancementRequirements::all([root_criteria])),
+            AdvancementDisplay::new(
+                "Root",
+                "Toggles when you sneak",
+                ItemStack::new(ItemKind::Stone, 1, None),
+                AdvancementFrameType::Task,
+            )
+            .with_background_texture(ident!("textures/block/stone.png")),
             RootAdvancement,
         ))
         .add_child(root_criteria)
         .id();
 
+    let first_advancement = commands
+        .spawn((
+            AdvancementBundle::new(ident!("custom:first")),
+            AdvancementDisplay::new(
+                "First",
+                "First advancement",
+                ItemStack::new(ItemKind::OakWood, 1, None),
+                AdvancementFrameType::Task,
+            )
+            .with_show_toast(false)
+            .with_position(1.0, -0.5),
+        ))
+        .set_parent(root_advancement)
+        .id();
+
     commands
         .spawn((
-            AdvancementBundle {
-                advancement: Advancement::new(ident!("custom:first").into()),
-                requirements: AdvancementRequirements::default(),
-                cached_bytes: Default::default(),
-            },
-            AdvancementDisplay {
-                title: "First".into(),
-                description: "First advancement".into(),
-                icon: Some(ItemStack::new(ItemKind::OakWood, 1, None)),
-                frame_type: AdvancementFrameType::Task,
-                show_toast: false,
-                hidden: false,
-                background_texture: None,
-                x_coord: 1.0,
-                y_coord: -0.5,
-            },
+            AdvancementBundle::new(ident!("custom:first_child")),
+            AdvancementDisplay::new(
+                "Child of First",
+                "An advancement deeper in the tree",
+                ItemStack::new(ItemKind::OakPlanks, 1, None),
+                AdvancementFrameType::Goal,
+            )
+            .with_show_toast(false)
+            .with_position(2.0, -0.5),
         ))
-        .set_parent(root_advancement);
+        .set_parent(first_advancement);
 
     commands
         .spawn((
-            AdvancementBundle {
-                advancement: Advancement::new(ident!("custom:second").into()),
-                requirements: AdvancementRequirements::default(),
-                cached_bytes: Default::default(),
-            },
-            AdvancementDisplay {
-                title: "Second".into(),
-                description: "Second advancement".into(),
-                icon: Some(ItemStack::new(ItemKind::AcaciaWood, 1, None)),
-                frame_type: AdvancementFrameType::Task,
-                show_toast: false,
-                hidden: false,
-                background_texture: None,
-                x_coord: 1.0,
-                y_coord: 0.5,
-            },
+            AdvancementBundle::new(ident!("custom:second")),
+            AdvancementDisplay::new(
+                "Second",
+                "Second advancement",
+                ItemStack::new(ItemKind::AcaciaWood, 1, None),
+                AdvancementFrameType::Task,
+            )
+            .with_show_toast(false)
+            .with_position(1.0, 0.5),
         ))
         .set_parent(root_advancement);
 
@@ -143,22 +137,15 @@ fn setup(
 
     commands
         .spawn((
-            AdvancementBundle {
-                advancement: Advancement::new(ident!("custom:root2").into()),
-                requirements: AdvancementRequirements(vec![vec![root2_criteria]]),
-                cached_bytes: Default::default(),
-            },
-            AdvancementDisplay {
-                title: "Root2".into(),
-                description: "Go to this tab 5 times to earn this advancement".into(),
-                icon: Some(ItemStack::new(ItemKind::IronSword, 1, None)),
-                frame_type: AdvancementFrameType::Challenge,
-                show_toast: false,
-                hidden: false,
-                background_texture: Some(Ident::new("textures/block/andesite.png").unwrap()),
-                x_coord: 0.0,
-                y_coord: 0.0,
-            },
+            AdvancementBundle::new(ident!("custom:root2"))
+                .with_requirements(AdvancementRequirements::all([root2_criteria])),
+            AdvancementDisplay::new(
+                "Root2",
+                "Go to this tab 5 times to earn this advancement",
+                ItemStack::new(ItemKind::IronSword, 1, None),
+                AdvancementFrameType::Challenge,
+            )
+            .with_background_texture(ident!("textures/block/andesite.png")),
         ))
         .add_child(root2_criteria);
 }
@@ -190,6 +177,8 @@ fn load_clients(
     }
 }
 
+/// Sends the saved progress. The advancements themselves are sent to new
+/// clients automatically.
 fn init_advancements(
     mut clients: Query<
         (
@@ -199,27 +188,17 @@ fn init_advancements(
         ),
         Added<AdvancementClientUpdate>,
     >,
-    root_advancement_query: Query<Entity, (Without<Parent>, With<Advancement>)>,
-    children_query: Query<&Children>,
-    advancement_check_query: Query<(), With<Advancement>>,
     root2_criteria: Query<Entity, With<Root2Criteria>>,
     root_criteria: Query<Entity, With<RootCriteria>>,
 ) {
     let root_c = root_criteria.single();
     let root2_c = root2_criteria.single();
     for (mut advancement_client_update, root_criteria, tab_change) in clients.iter_mut() {
-        for root_advancement in root_advancement_query.iter() {
-            advancement_client_update.send_advancements(
-                root_advancement,
-                &children_query,
-                &advancement_check_query,
-            );
-            if root_criteria.0 {
-                advancement_client_update.criteria_done(root_c);
-            }
-            if tab_change.0 > 5 {
-                advancement_client_update.criteria_done(root2_c);
-            }
+        if root_criteria.0 {
+            advancement_client_update.criteria_done(root_c);
+        }
+        if tab_change.0 > 5 {
+            advancement_client_update.criteria_done(root2_c);
         }
     }
 }
@@ -1,16 +1,20 @@
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::HashSet;
 use std::path::PathBuf;
-use std::thread;
 
 use clap::Parser;
-use flume::{Receiver, Sender};
 use tracing::warn;
-use valence::anvil::{AnvilChunk, AnvilWorld};
+use valence::anvil::{
+    AnvilLevel, AnvilWorld, ChunkLoadFailedEvent, ChunkLoadedEvent, LoadChunkError, ParsedEntity,
+};
+use valence::entity::entity::{CustomName, NameVisible, NoGravity, Silent};
+use valence::entity::{
+    EntityAnimations, EntityId, EntityStatuses, ObjectData, OnGround, PacketByteRange, TrackedData,
+    Velocity,
+};
 use valence::prelude::*;
 
-const SPAWN_POS: DVec3 = DVec3::new(0.0, 256.0, 0.0);
-const SECTION_COUNT: usize = 24;
+/// Where clients spawn if the world has no `level.dat` file.
+const DEFAULT_SPAWN_POS: DVec3 = DVec3::new(0.0, 256.0, 0.0);
 
 #[derive(Parser)]
 #[clap(author, version, about)]
@@ -20,17 +24,15 @@ struct Cli {
 }
 
 #[derive(Resource)]
-struct GameState {
-    /// Chunks that need to be generated. Chunks without a priority have already
-    /// been sent to the anvil thread.
-    pending: HashMap<ChunkPos, Option<Priority>>,
-    sender: Sender<ChunkPos>,
-    receiver: Receiver<(ChunkPos, Chunk)>,
-}
+struct WorldPath(PathBuf);
+
+#[derive(Resource)]
+struct SpawnPos(DVec3);
 
-/// The order in which chunks should be processed by anvil worker. Smaller
-/// values are sent first.
-type Priority = u64;
+/// Chunks whose entities have been spawned. Entities are not despawned when
+/// their chunk is unloaded, so they are only spawned once.
+#[derive(Resource, Default)]
+struct SpawnedEntities(HashSet<ChunkPos>);
 
 pub fn main() {
     tracing_subscriber::fmt().init();
@@ -46,34 +48,25 @@ pub fn main() {
         return;
     }
 
-    let anvil = AnvilWorld::new(dir);
-
-    let (finished_sender, finished_receiver) = flume::unbounded();
-    let (pending_sender, pending_receiver) = flume::unbounded();
-
-    // Process anvil chunks in a different thread to avoid blocking the main tick
-    // loop.
-    thread::spawn(move || anvil_worker(pending_receiver, finished_sender, anvil));
-
-    let game_state = GameState {
-        pending: HashMap::new(),
-        sender: pending_sender,
-        receiver: finished_receiver,
+    // Spawn clients at the world spawn point.
+    let spawn_pos = match AnvilWorld::new(&dir).read_level_dat() {
+        Ok(level_dat) => {
+            let pos = level_dat.spawn_pos;
+            DVec3::new(pos.x as f64 + 0.5, pos.y as f64, pos.z as f64 + 0.5)
+        }
+        Err(e) => {
+            warn!("Failed to read level.dat: {e}.");
+            DEFAULT_SPAWN_POS
+        }
     };
 
     App::new()
         .add_plugins(DefaultPlugins)
-        .insert_resource(game_state)
+        .insert_resource(SpawnPos(spawn_pos))
+        .insert_resource(WorldPath(dir))
+        .init_resource::<SpawnedEntities>()
         .add_startup_system(setup)
-        .add_systems(
-            (
-                init_clients,
-                remove_unviewed_chunks,
-                update_client_views,
-                send_recv_chunks,
-            )
-                .chain(),
-        )
+        .add_systems((init_clients, spawn_loaded_entities, report_failed_chunks))
         .add_system(despawn_disconnected_clients)
         .run();
 }
@@ -83,120 +76,89 @@ fn setup(
     dimensions: Query<&DimensionType>,
     biomes: Query<&Biome>,
     server: Res<Server>,
+    path: Res<WorldPath>,
 ) {
-    let instance = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
-    commands.spawn(instance);
+    let mut instance = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+    // Unload the chunks which are no longer in view of any client.
+    instance.set_chunk_retention(ChunkRetention::UnloadAfter { ticks: 0 });
+
+    // The chunks in view of the clients are loaded from the Anvil world in the
+    // background.
+    commands.spawn((instance, AnvilLevel::new(&path.0)));
 }
 
 fn init_clients(
     mut clients: Query<(&mut Location, &mut Position, &mut GameMode, &mut IsFlat), Added<Client>>,
     instances: Query<Entity, With<Instance>>,
+    spawn_pos: Res<SpawnPos>,
 ) {
     for (mut loc, mut pos, mut game_mode, mut is_flat) in &mut clients {
         loc.0 = instances.single();
-        pos.set(SPAWN_POS);
+        pos.set(spawn_pos.0);
         *game_mode = GameMode::Creative;
         is_flat.0 = true;
     }
 }
 
-fn remove_unviewed_chunks(mut instances: Query<&mut Instance>) {
-    instances
-        .single_mut()
-        .retain_chunks(|_, chunk| chunk.is_viewed_mut());
-}
-
-fn update_client_views(
-    mut instances: Query<&mut Instance>,
-    mut clients: Query<(&mut Client, View, OldView)>,
-    mut state: ResMut<GameState>,
-) {
-    let instance = instances.single_mut();
-
-    for (client, view, old_view) in &mut clients {
-        let view = view.get();
-        let queue_pos = |pos| {
-            if instance.chunk(pos).is_none() {
-                match state.pending.entry(pos) {
-                    Entry::Occupied(mut oe) => {
-                        if let Some(priority) = oe.get_mut() {
-                            let dist = view.pos.distance_squared(pos);
-                            *priority = (*priority).min(dist);
-                        }
-                    }
-                    Entry::Vacant(ve) => {
-                        let dist = view.pos.distance_squared(pos);
-                        ve.insert(Some(dist));
-                    }
-                }
-            }
-        };
-
-        // Queue all the new chunks in the view to be sent to the anvil worker.
-        if client.is_added() {
-            view.iter().for_each(queue_pos);
-        } else {
-            let old_view = old_view.get();
-            if old_view != view {
-                view.diff(old_view).for_each(queue_pos);
-            }
-        }
-    }
-}
-
-fn send_recv_chunks(mut instances: Query<&mut Instance>, state: ResMut<GameState>) {
-    let mut instance = instances.single_mut();
-    let state = state.into_inner();
-
-    // Insert the chunks that are finished loading into the instance.
-    for (pos, chunk) in state.receiver.drain() {
-        instance.insert_chunk(pos, chunk);
-        assert!(state.pending.remove(&pos).is_some());
-    }
-
-    // Collect all the new chunks that need to be loaded this tick.
-    let mut to_send = vec![];
-
-    for (pos, priority) in &mut state.pending {
-        if let Some(pri) = priority.take() {
-            to_send.push((pri, pos));
+fn report_failed_chunks(mut events: EventReader<ChunkLoadFailedEvent>) {
+    for event in events.iter() {
+        // Missing chunks are left empty.
+        if !matches!(event.error, LoadChunkError::Missing) {
+            warn!(
+                "Failed to load chunk at ({}, {}): {}.",
+                event.pos.x, event.pos.z, event.error
+            );
         }
     }
-
-    // Sort chunks by ascending priority.
-    to_send.sort_unstable_by_key(|(pri, _)| *pri);
-
-    // Send the sorted chunks to be loaded.
-    for (_, pos) in to_send {
-        let _ = state.sender.try_send(*pos);
-    }
 }
 
-fn anvil_worker(
-    receiver: Receiver<ChunkPos>,
-    sender: Sender<(ChunkPos, Chunk)>,
-    mut world: AnvilWorld,
+fn spawn_loaded_entities(
+    mut commands: Commands,
+    mut loaded_events: EventReader<ChunkLoadedEvent>,
+    mut spawned: ResMut<SpawnedEntities>,
 ) {
-    while let Ok(pos) = receiver.recv() {
-        match get_chunk(pos, &mut world) {
-            Ok(chunk) => {
-                if let Some(chunk) = chunk {
-                    let _ = sender.try_send((pos, chunk));
-                }
+    for event in loaded_events.iter() {
+        if spawned.0.insert(event.pos) {
+            for entity in &event.parsed_entities {
+                spawn_entity(&mut commands, event.instance, entity);
             }
-            Err(e) => warn!("Failed to get chunk at ({}, {}): {e:#}.", pos.x, pos.z),
         }
     }
 }
 
-fn get_chunk(pos: ChunkPos, world: &mut AnvilWorld) -> anyhow::Result<Option<Chunk>> {
-    let Some(AnvilChunk { data, .. }) = world.read_chunk(pos.x, pos.z)? else {
-        return Ok(None)
-    };
-
-    let mut chunk = Chunk::new(SECTION_COUNT);
-
-    valence_anvil::to_valence(&data, &mut chunk, 4, |_| BiomeId::default())?;
-
-    Ok(Some(chunk))
+/// Spawns an entity with the components shared by all entity kinds. The
+/// components specific to its kind are left out, so the client uses their
+/// default values.
+fn spawn_entity(commands: &mut Commands, instance: Entity, entity: &ParsedEntity) {
+    commands.spawn((
+        (
+            entity.kind,
+            EntityId::default(),
+            entity.uuid.map(UniqueId).unwrap_or_default(),
+            Location(instance),
+            OldLocation::new(instance),
+            Position(entity.position),
+            OldPosition::new(entity.position),
+            Look::new(entity.yaw, entity.pitch),
+            HeadYaw(entity.yaw),
+            OnGround(entity.on_ground),
+            Velocity(entity.velocity),
+        ),
+        (
+            EntityStatuses::default(),
+            EntityAnimations::default(),
+            ObjectData::default(),
+            TrackedData::default(),
+            PacketByteRange::default(),
+            CustomName(entity.custom_name.clone()),
+            NameVisible(entity.custom_name_visible),
+            NoGravity(entity.no_gravity),
+            Silent(entity.silent),
+        ),
+    ));
+
+    for passenger in &entity.passengers {
+        spawn_entity(commands, instance, passenger);
+    }
 }
@@ -4,7 +4,7 @@ use std::time::Instant;
 
 use valence::instance::{Chunk, Instance};
 use valence::prelude::*;
-use valence_network::ConnectionMode;
+use valence_network::{ConnectionLimits, ConnectionMode};
 
 const SPAWN_Y: i32 = 64;
 
@@ -21,7 +21,10 @@ fn main() {
         })
         .insert_resource(NetworkSettings {
             connection_mode: ConnectionMode::Offline,
-            max_connections: 50_000,
+            connection_limits: ConnectionLimits {
+                max_connections: 50_000,
+                ..ConnectionLimits::UNLIMITED
+            },
             ..Default::default()
         })
         .add_plugins(DefaultPlugins)
@@ -1,11 +1,12 @@
 #![allow(clippy::type_complexity)]
 
 use valence::client::misc::{ChatMessage, InteractBlock};
+use valence::client::sign::UpdateSign;
 use valence::nbt::{compound, List};
 use valence::prelude::*;
 
 const FLOOR_Y: i32 = 64;
-const SIGN_POS: [i32; 3] = [3, FLOOR_Y + 1, 2];
+const SIGN_POS: BlockPos = BlockPos::new(3, FLOOR_Y + 1, 2);
 const SKULL_POS: BlockPos = BlockPos::new(3, FLOOR_Y + 1, 3);
 
 pub fn main() {
@@ -73,9 +74,10 @@ fn init_clients(
 }
 
 fn event_handler(
-    clients: Query<(&Username, &Properties, &UniqueId)>,
+    mut clients: Query<(&mut Client, &Username, &Properties, &UniqueId)>,
     mut messages: EventReader<ChatMessage>,
     mut block_interacts: EventReader<InteractBlock>,
+    mut sign_updates: EventReader<UpdateSign>,
     mut instances: Query<&mut Instance>,
 ) {
     let mut instance = instances.single_mut();
@@ -83,7 +85,7 @@ fn event_handler(
         client, message, ..
     } in messages.iter()
     {
-        let Ok((username, _, _)) = clients.get(*client) else {
+        let Ok((_, username, _, _)) = clients.get(*client) else {
             continue
         };
 
@@ -100,8 +102,14 @@ fn event_handler(
         ..
     } in block_interacts.iter()
     {
+        if *hand == Hand::Main && *position == SIGN_POS {
+            if let Ok((mut client, _, _, _)) = clients.get_mut(*client) {
+                client.open_sign_editor(SIGN_POS);
+            }
+        }
+
         if *hand == Hand::Main && *position == SKULL_POS {
-            let Ok((_, properties, uuid)) = clients.get(*client) else {
+            let Ok((_, _, properties, uuid)) = clients.get(*client) else {
                 continue
             };
 
@@ -123,4 +131,11 @@ fn event_handler(
             };
         }
     }
+
+    for event in sign_updates.iter() {
+        if event.position == SIGN_POS {
+            let mut sign = instance.block_mut(SIGN_POS).unwrap();
+            event.write_nbt(sign.nbt_mut().unwrap());
+        }
+    }
 }
@@ -0,0 +1,123 @@
+#![allow(clippy::type_complexity)]
+
+use valence::client::book::{EditBook, WrittenBook};
+use valence::nbt::{compound, List};
+use valence::prelude::*;
+
+const SPAWN_Y: i32 = 64;
+
+pub fn main() {
+    tracing_subscriber::fmt().init();
+
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_systems((init_clients, give_books, open_rules, handle_book_edits))
+        .add_system(despawn_disconnected_clients)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    server: Res<Server>,
+    dimensions: Query<&DimensionType>,
+    biomes: Query<&Biome>,
+) {
+    let mut instance = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+    for z in -5..5 {
+        for x in -5..5 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    for z in -25..25 {
+        for x in -25..25 {
+            instance.set_block([x, SPAWN_Y, z], BlockState::BOOKSHELF);
+        }
+    }
+
+    commands.spawn(instance);
+}
+
+fn init_clients(
+    mut clients: Query<(&mut Client, &mut Location, &mut Position), Added<Client>>,
+    instances: Query<Entity, With<Instance>>,
+) {
+    for (mut client, mut loc, mut pos) in &mut clients {
+        loc.0 = instances.single();
+        pos.set([0.5, SPAWN_Y as f64 + 1.0, 0.5]);
+
+        client.send_message("Sneak to read the rules, or write in the book and quill.".italic());
+    }
+}
+
+/// Gives the rules and an empty book and quill to new clients.
+fn give_books(mut clients: Query<&mut Inventory, (With<Client>, Added<Inventory>)>) {
+    for mut inventory in &mut clients {
+        let rules = WrittenBook {
+            title: "Rules".into(),
+            author: "Server".into(),
+            pages: vec![
+                "Rules".bold() + "\n\n1. Be nice.\n2. Have fun.",
+                "Write in the book and quill on the next slot, it is logged by the server."
+                    .color(Color::DARK_GRAY),
+            ],
+        };
+
+        inventory.set_slot(36, Some(rules.into()));
+        inventory.set_slot(37, Some(ItemStack::new(ItemKind::WritableBook, 1, None)));
+    }
+}
+
+fn open_rules(mut clients: Query<&mut Client>, mut events: EventReader<Sneaking>) {
+    for event in events.iter() {
+        if event.state == SneakState::Start {
+            if let Ok(mut client) = clients.get_mut(event.client) {
+                // Only shows the rules if the client is holding them.
+                client.open_book(Hand::Main);
+            }
+        }
+    }
+}
+
+fn handle_book_edits(
+    mut clients: Query<(&Username, &mut Inventory)>,
+    mut events: EventReader<EditBook>,
+) {
+    for event in events.iter() {
+        let Ok((username, mut inventory)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        tracing::info!("{username} edited their book: {:?}", event.pages);
+
+        // The client expects the server to write the pages into the book.
+        let slot = match event.slot {
+            0..=8 => 36 + event.slot as u16,
+            _ => 45,
+        };
+
+        let stack = match &event.title {
+            Some(title) => {
+                tracing::info!("{username} signed their book \"{title}\"");
+
+                WrittenBook {
+                    title: title.clone(),
+                    author: username.0.clone(),
+                    pages: event.pages.iter().cloned().map(Text::text).collect(),
+                }
+                .into()
+            }
+            None => ItemStack::new(
+                ItemKind::WritableBook,
+                1,
+                Some(compound! {
+                    "pages" => List::String(event.pages.clone()),
+                }),
+            ),
+        };
+
+        inventory.set_slot(slot, Some(stack));
+    }
+}
@@ -0,0 +1,115 @@
+#![allow(clippy::type_complexity)]
+
+use valence::prelude::*;
+
+const SPAWN_Y: i32 = 64;
+/// The length of the countdown, in ticks.
+const COUNTDOWN_TICKS: u32 = 30 * 20;
+
+pub fn main() {
+    tracing_subscriber::fmt().init();
+
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_systems((init_clients, update_countdown))
+        .add_system(despawn_disconnected_clients)
+        .run();
+}
+
+/// The number of ticks left before the countdown restarts.
+#[derive(Component)]
+struct Countdown(u32);
+
+fn setup(
+    mut commands: Commands,
+    server: Res<Server>,
+    dimensions: Query<&DimensionType>,
+    biomes: Query<&Biome>,
+) {
+    let mut instance = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+    for z in -5..5 {
+        for x in -5..5 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    for z in -25..25 {
+        for x in -25..25 {
+            instance.set_block([x, SPAWN_Y, z], BlockState::GRASS_BLOCK);
+        }
+    }
+
+    let instance = commands.spawn(instance).id();
+
+    // Everyone in the instance sees the countdown.
+    commands.spawn((
+        BossBarBundle {
+            style: BossBarStyle {
+                color: BossBarColor::Green,
+                division: BossBarDivision::SixNotches,
+            },
+            viewers: BossBarViewers {
+                instance: Some(instance),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Countdown(COUNTDOWN_TICKS),
+    ));
+}
+
+fn init_clients(
+    mut clients: Query<(&mut Client, &mut Location, &mut Position), Added<Client>>,
+    instances: Query<Entity, With<Instance>>,
+) {
+    for (mut client, mut loc, mut pos) in &mut clients {
+        loc.0 = instances.single();
+        pos.set([0.5, SPAWN_Y as f64 + 1.0, 0.5]);
+
+        client.send_message("The game starts when the countdown ends.".italic());
+    }
+}
+
+fn update_countdown(
+    mut boss_bars: Query<(
+        &mut Countdown,
+        &mut BossBarTitle,
+        &mut BossBarHealth,
+        &mut BossBarStyle,
+    )>,
+    mut clients: Query<&mut Client>,
+) {
+    for (mut countdown, mut title, mut health, mut style) in &mut boss_bars {
+        if countdown.0 == 0 {
+            for mut client in &mut clients {
+                client.send_message("Go!".color(Color::GREEN).bold());
+            }
+
+            countdown.0 = COUNTDOWN_TICKS;
+        } else {
+            countdown.0 -= 1;
+        }
+
+        let seconds_left = countdown.0.div_ceil(20);
+        let new_title = format!("Starting in {seconds_left} seconds").into_text();
+
+        // Avoid sending the same title again every tick.
+        if title.0 != new_title {
+            title.0 = new_title;
+        }
+
+        health.0 = countdown.0 as f32 / COUNTDOWN_TICKS as f32;
+
+        let color = if seconds_left <= 5 {
+            BossBarColor::Red
+        } else {
+            BossBarColor::Green
+        };
+
+        if style.color != color {
+            style.color = color;
+        }
+    }
+}
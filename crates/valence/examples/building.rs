@@ -1,7 +1,7 @@
 #![allow(clippy::type_complexity)]
 
 use valence::client::misc::InteractBlock;
-use valence::inventory::ClientInventoryState;
+use valence::instance::BlockChangeCause;
 use valence::prelude::*;
 
 const SPAWN_Y: i32 = 64;
@@ -86,7 +86,13 @@ fn digging_creative_mode(
             continue;
         };
         if *game_mode == GameMode::Creative && event.state == DiggingState::Start {
-            instance.set_block(event.position, BlockState::AIR);
+            instance.set_block_with_cause(
+                event.position,
+                BlockState::AIR,
+                BlockChangeCause::PlayerBreak {
+                    client: event.client,
+                },
+            );
         }
     }
 }
@@ -103,20 +109,26 @@ fn digging_survival_mode(
             continue;
         };
         if *game_mode == GameMode::Survival && event.state == DiggingState::Stop {
-            instance.set_block(event.position, BlockState::AIR);
+            instance.set_block_with_cause(
+                event.position,
+                BlockState::AIR,
+                BlockChangeCause::PlayerBreak {
+                    client: event.client,
+                },
+            );
         }
     }
 }
 
 fn place_blocks(
-    mut clients: Query<(&mut Inventory, &GameMode, &ClientInventoryState)>,
+    mut clients: Query<(&mut Inventory, &GameMode, &HeldItem)>,
     mut instances: Query<&mut Instance>,
     mut events: EventReader<InteractBlock>,
 ) {
     let mut instance = instances.single_mut();
 
     for event in events.iter() {
-        let Ok((mut inventory, game_mode, inv_state)) = clients.get_mut(event.client) else {
+        let Ok((mut inventory, game_mode, held)) = clients.get_mut(event.client) else {
             continue;
         };
         if event.hand != Hand::Main {
@@ -124,7 +136,7 @@ fn place_blocks(
         }
 
         // get the held item
-        let slot_id = inv_state.held_item_slot();
+        let slot_id = held.slot();
         let Some(stack) = inventory.slot(slot_id) else {
             // no item in the slot
             continue;
@@ -146,6 +158,12 @@ fn place_blocks(
             }
         }
         let real_pos = event.position.get_in_direction(event.face);
-        instance.set_block(real_pos, block_kind.to_state());
+        instance.set_block_with_cause(
+            real_pos,
+            block_kind.to_state(),
+            BlockChangeCause::PlayerPlace {
+                client: event.client,
+            },
+        );
     }
 }
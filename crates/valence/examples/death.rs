@@ -64,24 +64,26 @@ fn init_clients(
     }
 }
 
-fn squat_and_die(mut clients: Query<&mut Client>, mut events: EventReader<Sneaking>) {
+fn squat_and_die(mut events: EventReader<Sneaking>, mut kills: EventWriter<KillClient>) {
     for event in events.iter() {
         if event.state == SneakState::Start {
-            if let Ok(mut client) = clients.get_mut(event.client) {
-                client.kill(None, "Squatted too hard.");
-            }
+            kills.send(KillClient {
+                client: event.client,
+                killer: None,
+                message: "Squatted too hard.".into(),
+            });
         }
     }
 }
 
 fn necromancy(
-    mut clients: Query<(&mut Position, &mut Look, &mut Location)>,
+    mut commands: Commands,
+    mut clients: Query<(&mut Look, &Location)>,
     mut events: EventReader<Respawn>,
     instances: Query<Entity, With<Instance>>,
 ) {
     for event in events.iter() {
-        if let Ok((mut pos, mut look, mut loc)) = clients.get_mut(event.client) {
-            pos.set([0.0, SPAWN_Y as f64 + 1.0, 0.0]);
+        if let Ok((mut look, loc)) = clients.get_mut(event.client) {
             look.yaw = 0.0;
             look.pitch = 0.0;
 
@@ -90,7 +92,11 @@ fn necromancy(
 
             let count = instances.iter().len();
 
-            loc.0 = instances.into_iter().nth((idx + 1) % count).unwrap();
+            commands.add(RespawnClient {
+                client: event.client,
+                instance: instances.into_iter().nth((idx + 1) % count).unwrap(),
+                position: DVec3::new(0.0, SPAWN_Y as f64 + 1.0, 0.0),
+            });
         }
     }
 }
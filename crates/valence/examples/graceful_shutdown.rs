@@ -0,0 +1,88 @@
+#![allow(clippy::type_complexity)]
+
+use std::time::Duration;
+
+use flume::{Receiver, Sender};
+use tokio::runtime::Runtime;
+use valence::prelude::*;
+use valence_network::ShutdownServer;
+
+const SPAWN_Y: i32 = 64;
+
+pub fn main() {
+    tracing_subscriber::fmt().init();
+
+    // The runtime is shared with the network plugin so that ctrl-c can be
+    // awaited on it.
+    let runtime = Runtime::new().unwrap();
+
+    let (ctrl_c_send, ctrl_c_recv) = flume::bounded(1);
+    runtime.spawn(wait_for_ctrl_c(ctrl_c_send));
+
+    App::new()
+        .insert_resource(NetworkSettings {
+            tokio_handle: Some(runtime.handle().clone()),
+            ..Default::default()
+        })
+        .insert_resource(CtrlC(ctrl_c_recv))
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_systems((init_clients, shutdown_on_ctrl_c))
+        .add_system(despawn_disconnected_clients)
+        .run();
+
+    println!("The server was shut down.");
+}
+
+#[derive(Resource)]
+struct CtrlC(Receiver<()>);
+
+async fn wait_for_ctrl_c(send: Sender<()>) {
+    if tokio::signal::ctrl_c().await.is_ok() {
+        let _ = send.send_async(()).await;
+    }
+}
+
+fn shutdown_on_ctrl_c(ctrl_c: Res<CtrlC>, mut shutdown: EventWriter<ShutdownServer>) {
+    if ctrl_c.0.try_recv().is_ok() {
+        shutdown.send(ShutdownServer {
+            reason: "The server was stopped. See you soon!".color(Color::GOLD),
+            timeout: Duration::from_secs(10),
+        });
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    server: Res<Server>,
+    dimensions: Query<&DimensionType>,
+    biomes: Query<&Biome>,
+) {
+    let mut instance = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+    for z in -5..5 {
+        for x in -5..5 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    for z in -25..25 {
+        for x in -25..25 {
+            instance.set_block([x, SPAWN_Y, z], BlockState::GRASS_BLOCK);
+        }
+    }
+
+    commands.spawn(instance);
+}
+
+fn init_clients(
+    mut clients: Query<(&mut Client, &mut Location, &mut Position), Added<Client>>,
+    instances: Query<Entity, With<Instance>>,
+) {
+    for (mut client, mut loc, mut pos) in &mut clients {
+        loc.0 = instances.single();
+        pos.set([0.5, SPAWN_Y as f64 + 1.0, 0.5]);
+
+        client.send_message("Press ctrl-c in the server console to stop the server.".italic());
+    }
+}
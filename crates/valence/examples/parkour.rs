@@ -5,7 +5,6 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use rand::seq::SliceRandom;
 use rand::Rng;
-use valence::packet::s2c::play::TitleFadeS2c;
 use valence::prelude::*;
 use valence::sound::{Sound, SoundCategory};
 
@@ -179,15 +178,9 @@ fn manage_blocks(mut clients: Query<(&mut Client, &Position, &mut GameState, &mu
                     pitch,
                 );
 
-                client.set_title(
-                    "",
-                    state.score.to_string().color(Color::LIGHT_PURPLE).bold(),
-                    TitleFadeS2c {
-                        fade_in: 0,
-                        stay: 7,
-                        fade_out: 4,
-                    },
-                );
+                client.set_subtitle(state.score.to_string().color(Color::LIGHT_PURPLE).bold());
+                client.set_title_times(0, 7, 4);
+                client.set_title("");
             }
         }
     }
@@ -199,11 +192,13 @@ fn manage_chunks(mut clients: Query<(&Position, &OldPosition, &mut Instance), Wi
         let view = ChunkView::new(pos.chunk_pos(), VIEW_DIST);
 
         if old_view != view {
-            for pos in old_view.diff(view) {
+            let (entering, leaving) = ChunkView::diff(old_view, view);
+
+            for pos in leaving {
                 instance.remove_chunk(pos);
             }
 
-            for pos in view.diff(old_view) {
+            for pos in entering {
                 instance.chunk_entry(pos).or_default();
             }
         }
@@ -0,0 +1,148 @@
+#![allow(clippy::type_complexity)]
+
+use valence::prelude::*;
+
+const OVERWORLD_Y: i32 = 64;
+const NETHER_Y: i32 = 32;
+
+/// The number of ticks after a transfer during which clients aren't
+/// transferred again, so they can walk out of the portal they arrived in.
+const PORTAL_COOLDOWN: u32 = 40;
+
+pub fn main() {
+    tracing_subscriber::fmt().init();
+
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(init_clients)
+        .add_system(despawn_disconnected_clients)
+        .add_system(enter_portals)
+        .run();
+}
+
+/// The instances connected by the portals.
+#[derive(Resource)]
+struct Worlds {
+    overworld: Entity,
+    nether: Entity,
+}
+
+/// Ticks until the client can use a portal again.
+#[derive(Component, Default)]
+struct PortalCooldown(u32);
+
+fn setup(
+    mut commands: Commands,
+    server: Res<Server>,
+    dimensions: Query<&DimensionType>,
+    biomes: Query<&Biome>,
+) {
+    let mut overworld = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
+    let mut nether = Instance::new(ident!("the_nether"), &dimensions, &biomes, &server);
+
+    for z in -5..5 {
+        for x in -5..5 {
+            overworld.insert_chunk([x, z], Chunk::default());
+            nether.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    for z in -25..25 {
+        for x in -25..25 {
+            overworld.set_block([x, OVERWORLD_Y, z], BlockState::GRASS_BLOCK);
+            nether.set_block([x, NETHER_Y, z], BlockState::NETHERRACK);
+        }
+    }
+
+    build_portal(&mut overworld, BlockPos::new(0, OVERWORLD_Y, 5));
+    build_portal(&mut nether, BlockPos::new(0, NETHER_Y, 5));
+
+    let overworld = commands.spawn(overworld).id();
+    let nether = commands.spawn(nether).id();
+
+    commands.insert_resource(Worlds { overworld, nether });
+}
+
+/// Builds an obsidian frame filled with portal blocks on top of `floor`.
+fn build_portal(instance: &mut Instance, floor: BlockPos) {
+    for y in 0..5 {
+        for x in -2..2 {
+            let pos = BlockPos::new(floor.x + x, floor.y + y, floor.z);
+
+            let block = if x == -2 || x == 1 || y == 0 || y == 4 {
+                BlockState::OBSIDIAN
+            } else {
+                BlockState::NETHER_PORTAL
+            };
+
+            instance.set_block(pos, block);
+        }
+    }
+}
+
+fn init_clients(
+    mut clients: Query<
+        (
+            Entity,
+            &mut Client,
+            &mut Location,
+            &mut Position,
+            &mut GameMode,
+        ),
+        Added<Client>,
+    >,
+    worlds: Res<Worlds>,
+    mut commands: Commands,
+) {
+    for (entity, mut client, mut loc, mut pos, mut game_mode) in &mut clients {
+        *game_mode = GameMode::Creative;
+        loc.0 = worlds.overworld;
+        pos.set([0.5, OVERWORLD_Y as f64 + 1.0, 0.5]);
+
+        commands.entity(entity).insert(PortalCooldown::default());
+
+        client.send_message("Walk into the portal to travel to the nether and back.".italic());
+    }
+}
+
+/// Transfers clients standing in a portal to the portal in the other
+/// instance.
+fn enter_portals(
+    mut clients: Query<(&mut Client, &Location, &Position, &mut PortalCooldown)>,
+    instances: Query<&Instance>,
+    worlds: Res<Worlds>,
+) {
+    for (mut client, loc, pos, mut cooldown) in &mut clients {
+        if cooldown.0 > 0 {
+            cooldown.0 -= 1;
+            continue;
+        }
+
+        let Ok(instance) = instances.get(loc.0) else {
+            continue;
+        };
+
+        let in_portal = instance.block(BlockPos::at(pos.0)).map_or(false, |block| {
+            block.state().to_kind() == BlockKind::NetherPortal
+        });
+
+        if !in_portal {
+            continue;
+        }
+
+        let (destination, y) = if loc.0 == worlds.overworld {
+            (worlds.nether, NETHER_Y)
+        } else {
+            (worlds.overworld, OVERWORLD_Y)
+        };
+
+        // Arrive in front of the other portal, facing away from it.
+        client.transfer_to(
+            destination,
+            [0.0, y as f64 + 1.0, 3.5],
+            Look::new(180.0, 0.0),
+        );
+        cooldown.0 = PORTAL_COOLDOWN;
+    }
+}
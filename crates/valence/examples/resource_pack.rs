@@ -1,12 +1,15 @@
 #![allow(clippy::type_complexity)]
 
 use valence::client::misc::{ResourcePackStatus, ResourcePackStatusChange};
+use valence::client::resource_pack::ResourcePackHash;
 use valence::entity::player::PlayerEntityBundle;
 use valence::entity::sheep::SheepEntityBundle;
 use valence::packet::c2s::play::player_interact_entity::EntityInteraction;
 use valence::prelude::*;
 
 const SPAWN_Y: i32 = 64;
+/// The SHA-1 hash of the resource pack, as a hexadecimal string.
+const PACK_HASH: &str = "d7c6108849fb190ec2a49f2d38b7f1f897d9ce9f";
 
 pub fn main() {
     tracing_subscriber::fmt().init();
@@ -75,7 +78,7 @@ fn prompt_on_punch(mut clients: Query<&mut Client>, mut events: EventReader<Inte
             if event.interact == EntityInteraction::Attack {
                 client.set_resource_pack(
                     "https://github.com/valence-rs/valence/raw/main/assets/example_pack.zip",
-                    "d7c6108849fb190ec2a49f2d38b7f1f897d9ce9f",
+                    PACK_HASH.parse::<ResourcePackHash>().unwrap(),
                     false,
                     None,
                 );
@@ -0,0 +1,122 @@
+#![allow(clippy::type_complexity)]
+
+use valence::prelude::*;
+
+const SPAWN_Y: i32 = 64;
+
+pub fn main() {
+    tracing_subscriber::fmt().init();
+
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_systems((init_clients, count_sneaks, remove_disconnected_scores))
+        .add_system(despawn_disconnected_clients)
+        .run();
+}
+
+/// The objective listing the number of sneaks of every player.
+#[derive(Component)]
+struct Leaderboard;
+
+fn setup(
+    mut commands: Commands,
+    server: Res<Server>,
+    dimensions: Query<&DimensionType>,
+    biomes: Query<&Biome>,
+) {
+    let mut instance = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+    for z in -5..5 {
+        for x in -5..5 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    for z in -25..25 {
+        for x in -25..25 {
+            instance.set_block([x, SPAWN_Y, z], BlockState::GRASS_BLOCK);
+        }
+    }
+
+    let instance = commands.spawn(instance).id();
+
+    // Everyone in the instance sees the sneaks of every player in the player
+    // list.
+    commands.spawn((
+        ObjectiveBundle {
+            name: ObjectiveName("leaderboard".into()),
+            display_slot: ObjectiveDisplaySlot(Some(DisplaySlot::List)),
+            viewers: ObjectiveViewers {
+                instance: Some(instance),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Leaderboard,
+    ));
+}
+
+fn init_clients(
+    mut commands: Commands,
+    mut clients: Query<(Entity, &mut Client, &mut Location, &mut Position), Added<Client>>,
+    instances: Query<Entity, With<Instance>>,
+) {
+    for (entity, mut client, mut loc, mut pos) in &mut clients {
+        loc.0 = instances.single();
+        pos.set([0.5, SPAWN_Y as f64 + 1.0, 0.5]);
+
+        client.send_message("Sneak to increase your score.".italic());
+
+        // Every player has their own sidebar. The objective is inserted into
+        // the client entity so that it goes away along with the client.
+        commands.entity(entity).insert(ObjectiveBundle {
+            name: ObjectiveName("sidebar".into()),
+            display: ObjectiveDisplay {
+                display_name: "Your stats".color(Color::GOLD).bold(),
+                ..Default::default()
+            },
+            display_slot: ObjectiveDisplaySlot(Some(DisplaySlot::Sidebar)),
+            scores: ObjectiveScores([("Sneaks".into(), 0)].into()),
+            viewers: ObjectiveViewers {
+                clients: [entity].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+    }
+}
+
+fn count_sneaks(
+    mut sidebars: Query<(&Username, &mut ObjectiveScores), Without<Leaderboard>>,
+    mut leaderboards: Query<&mut ObjectiveScores, With<Leaderboard>>,
+    mut events: EventReader<Sneaking>,
+) {
+    let mut leaderboard = leaderboards.single_mut();
+
+    for event in events.iter() {
+        if event.state != SneakState::Start {
+            continue;
+        }
+
+        let Ok((username, mut scores)) = sidebars.get_mut(event.client) else {
+            continue;
+        };
+
+        let sneaks = scores.0.entry("Sneaks".into()).or_default();
+        *sneaks += 1;
+
+        leaderboard.0.insert(username.0.clone(), *sneaks);
+    }
+}
+
+fn remove_disconnected_scores(
+    disconnected: Query<&Username, Added<Despawned>>,
+    mut leaderboards: Query<&mut ObjectiveScores, With<Leaderboard>>,
+) {
+    let mut leaderboard = leaderboards.single_mut();
+
+    for username in &disconnected {
+        leaderboard.0.remove(&username.0);
+    }
+}
@@ -4,7 +4,10 @@ use std::net::SocketAddr;
 
 use rand::Rng;
 use valence::prelude::*;
-use valence_network::{async_trait, CleanupFn, ConnectionMode, PlayerSampleEntry, ServerListPing};
+use valence::{MINECRAFT_VERSION, PROTOCOL_VERSION};
+use valence_network::{
+    async_trait, CleanupFn, ConnectionMode, HandshakeData, PlayerSampleEntry, ServerListPing,
+};
 
 pub fn main() {
     tracing_subscriber::fmt().init();
@@ -27,10 +30,25 @@ impl NetworkCallbacks for MyCallbacks {
         &self,
         _shared: &SharedNetworkState,
         remote_addr: SocketAddr,
-        _protocol_version: i32,
+        handshake: &HandshakeData,
     ) -> ServerListPing {
         let max_players = 420;
 
+        // Older clients are asked to update. Newer clients see the server as
+        // compatible, like when a proxy such as ViaVersion translates between
+        // versions.
+        if handshake.protocol_version < PROTOCOL_VERSION {
+            return ServerListPing::RespondWithoutPlayers {
+                description: "Please update to Minecraft ".color(Color::RED)
+                    + MINECRAFT_VERSION.color(Color::GOLD),
+                favicon_png: include_bytes!("../../../assets/logo-64x64.png"),
+                version_name: format!("Valence {MINECRAFT_VERSION}"),
+                protocol_version: PROTOCOL_VERSION,
+                enforces_secure_chat: false,
+                previews_chat: false,
+            };
+        }
+
         ServerListPing::Respond {
             online_players: rand::thread_rng().gen_range(0..=max_players),
             max_players,
@@ -41,6 +59,10 @@ impl NetworkCallbacks for MyCallbacks {
             description: "Your IP address is ".into_text()
                 + remote_addr.to_string().color(Color::GOLD),
             favicon_png: include_bytes!("../../../assets/logo-64x64.png"),
+            version_name: MINECRAFT_VERSION.to_owned(),
+            protocol_version: handshake.protocol_version,
+            enforces_secure_chat: false,
+            previews_chat: false,
         }
     }
 
@@ -158,7 +158,8 @@ fn update_client_views(
         } else {
             let old_view = old_view.get();
             if old_view != view {
-                view.diff(old_view).for_each(queue_pos);
+                let (entering, _) = ChunkView::diff(old_view, view);
+                entering.for_each(queue_pos);
             }
         }
     }
@@ -0,0 +1,92 @@
+#![allow(clippy::type_complexity)]
+
+use valence::prelude::*;
+use valence_network::{async_trait, ConnectionMode};
+
+const SPAWN_Y: i32 = 64;
+
+/// The file containing the usernames of the players allowed to join, one per
+/// line. It is read again for every login, so it can be edited while the
+/// server is running.
+const WHITELIST_PATH: &str = "whitelist.txt";
+
+pub fn main() {
+    tracing_subscriber::fmt().init();
+
+    App::new()
+        .insert_resource(NetworkSettings {
+            connection_mode: ConnectionMode::Offline,
+            callbacks: WhitelistCallbacks.into(),
+            ..Default::default()
+        })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(init_clients)
+        .add_system(despawn_disconnected_clients)
+        .run();
+}
+
+struct WhitelistCallbacks;
+
+#[async_trait]
+impl NetworkCallbacks for WhitelistCallbacks {
+    async fn check_login(
+        &self,
+        _shared: &SharedNetworkState,
+        info: &NewClientInfo,
+    ) -> Result<(), Text> {
+        // Reading the file asynchronously does not block the other logins.
+        let whitelist = match tokio::fs::read_to_string(WHITELIST_PATH).await {
+            Ok(whitelist) => whitelist,
+            Err(e) => {
+                tracing::error!("failed to read {WHITELIST_PATH}: {e}");
+                return Err("The whitelist could not be read".color(Color::RED));
+            }
+        };
+
+        if whitelist
+            .lines()
+            .any(|line| line.trim().eq_ignore_ascii_case(&info.username))
+        {
+            Ok(())
+        } else {
+            Err("You are not whitelisted on this server!".color(Color::RED)
+                + format!("\nAdd \"{}\" to {WHITELIST_PATH} to join.", info.username))
+        }
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    server: Res<Server>,
+    dimensions: Query<&DimensionType>,
+    biomes: Query<&Biome>,
+) {
+    let mut instance = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+    for z in -5..5 {
+        for x in -5..5 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    for z in -25..25 {
+        for x in -25..25 {
+            instance.set_block([x, SPAWN_Y, z], BlockState::GRASS_BLOCK);
+        }
+    }
+
+    commands.spawn(instance);
+}
+
+fn init_clients(
+    mut clients: Query<(&mut Client, &mut Location, &mut Position), Added<Client>>,
+    instances: Query<Entity, With<Instance>>,
+) {
+    for (mut client, mut loc, mut pos) in &mut clients {
+        loc.0 = instances.single();
+        pos.set([0.5, SPAWN_Y as f64 + 1.0, 0.5]);
+
+        client.send_message("Welcome, you are on the whitelist!".color(Color::GREEN));
+    }
+}
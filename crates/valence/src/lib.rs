@@ -23,23 +23,41 @@
 
 use bevy_app::{PluginGroup, PluginGroupBuilder};
 
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 #[cfg(test)]
 mod tests;
 
+pub use bevy_app as app;
+pub use bevy_ecs as ecs;
+pub use glam;
 #[cfg(feature = "anvil")]
 pub use valence_anvil as anvil;
+pub use valence_biome as biome;
+pub use valence_block as block;
+#[cfg(feature = "boss_bar")]
+pub use valence_boss_bar as boss_bar;
+#[cfg(feature = "chat")]
+pub use valence_chat as chat;
+pub use valence_client as client;
+#[cfg(feature = "command")]
+pub use valence_command as command;
 pub use valence_core::*;
+pub use valence_dimension as dimension;
+pub use valence_entity as entity;
+pub use valence_instance as instance;
 #[cfg(feature = "inventory")]
 pub use valence_inventory as inventory;
+pub use valence_nbt as nbt;
 #[cfg(feature = "network")]
 pub use valence_network as network;
 #[cfg(feature = "player_list")]
 pub use valence_player_list as player_list;
-pub use {
-    bevy_app as app, bevy_ecs as ecs, glam, valence_biome as biome, valence_block as block,
-    valence_client as client, valence_dimension as dimension, valence_entity as entity,
-    valence_instance as instance, valence_nbt as nbt, valence_registry as registry,
-};
+pub use valence_registry as registry;
+#[cfg(feature = "schem")]
+pub use valence_schem as schem;
+#[cfg(feature = "scoreboard")]
+pub use valence_scoreboard as scoreboard;
 
 /// Contains the most frequently used items in Valence projects.
 ///
@@ -59,20 +77,45 @@ pub mod prelude {
     pub use biome::{Biome, BiomeId, BiomeRegistry};
     pub use block::{BlockKind, BlockState, PropName, PropValue};
     pub use block_pos::BlockPos;
+    #[cfg(feature = "boss_bar")]
+    pub use boss_bar::{
+        BossBarBundle, BossBarColor, BossBarDivision, BossBarFlags, BossBarHealth, BossBarId,
+        BossBarStyle, BossBarTitle, BossBarViewers, OldBossBarViewers,
+    };
+    pub use chat::{ChatSender, ChatType};
+    #[cfg(feature = "chat")]
+    pub use chat::{ChatSettings, ChatState, ChatVerificationFailure, SignedChatMessage};
     pub use chunk_pos::{ChunkPos, ChunkView};
+    pub use client::abilities::{FlyState, Flying, PlayerAbilities};
     pub use client::action::*;
+    pub use client::block_override::BlockOverrides;
+    pub use client::camera::Camera;
     pub use client::command::*;
+    pub use client::cooldown::Cooldowns;
+    pub use client::death::{KillClient, RespawnClient};
     pub use client::event_loop::{EventLoopSchedule, EventLoopSet};
+    pub use client::experience::{
+        ExperienceLevel, ExperienceProgress, ExperienceQuery, TotalExperience,
+    };
+    pub use client::health::{Food, Saturation};
     pub use client::interact_entity::*;
+    pub use client::statistics::{Statistic, Statistics};
+    pub use client::vehicle::{ClientControlledVehicle, VehicleInput, VehicleMove};
     pub use client::{
-        despawn_disconnected_clients, Client, CompassPos, DeathLocation, HasRespawnScreen,
-        HashedSeed, Ip, IsDebug, IsFlat, IsHardcore, OldView, OldViewDistance, OpLevel,
-        PrevGameMode, Properties, ReducedDebugInfo, Username, View, ViewDistance,
+        despawn_disconnected_clients, Client, CompassPos, DeathLocation, Degraded,
+        HasRespawnScreen, HashedSeed, Ip, IsDebug, IsFlat, IsHardcore, MaxViewDistance,
+        NetworkStats, OldView, OldViewDistance, OpLevel, OverloadPolicy, PrevGameMode, Properties,
+        ReducedDebugInfo, SendQueueLimit, Username, View, ViewDistance,
     };
+    #[cfg(feature = "command")]
+    pub use command::event::{CommandExecution, CompletionRequest};
+    #[cfg(feature = "command")]
+    pub use command::{CommandNode, CommandTree};
     pub use despawn::Despawned;
     pub use dimension::{DimensionType, DimensionTypeRegistry};
     pub use direction::Direction;
     pub use ecs::prelude::*;
+    pub use enchantment::Enchantment;
     pub use entity::{
         EntityAnimation, EntityKind, EntityManager, EntityStatus, HeadYaw, Location, Look,
         OldLocation, OldPosition, Position,
@@ -81,12 +124,13 @@ pub mod prelude {
     pub use glam::{DVec2, DVec3, Vec2, Vec3};
     pub use hand::Hand;
     pub use ident::Ident;
-    pub use instance::{Block, BlockMut, BlockRef, Chunk, Instance};
+    pub use instance::{Block, BlockMut, BlockRef, Chunk, ChunkRetention, Instance};
     #[cfg(feature = "inventory")]
     pub use inventory::{
-        CursorItem, Inventory, InventoryKind, InventoryWindow, InventoryWindowMut, OpenInventory,
+        CursorItem, HeldItem, Inventory, InventoryKind, InventoryWindow, InventoryWindowMut,
+        OpenInventory,
     };
-    pub use item::{ItemKind, ItemStack};
+    pub use item::{HideFlags, ItemKind, ItemStack};
     pub use nbt::Compound;
     #[cfg(feature = "network")]
     pub use network::{
@@ -96,6 +140,17 @@ pub mod prelude {
     pub use packet::s2c::play::particle::Particle;
     #[cfg(feature = "player_list")]
     pub use player_list::{PlayerList, PlayerListEntry};
+    #[cfg(feature = "scoreboard")]
+    pub use scoreboard::team::{
+        CollisionRule, NameTagVisibility, TeamBundle, TeamColor, TeamDisplay, TeamMembers,
+        TeamName, TeamRules,
+    };
+    #[cfg(feature = "scoreboard")]
+    pub use scoreboard::{
+        DisplaySlot, ObjectiveBundle, ObjectiveDisplay, ObjectiveDisplaySlot, ObjectiveName,
+        ObjectiveRenderType, ObjectiveScores, ObjectiveViewers,
+    };
+    pub use statistic::CustomStatistic;
     pub use text::{Color, Text, TextFormat};
     #[cfg(feature = "advancement")]
     pub use valence_advancement::{
@@ -149,7 +204,27 @@ impl PluginGroup for DefaultPlugins {
 
         #[cfg(feature = "anvil")]
         {
-            // No plugin... yet.
+            group = group.add(valence_anvil::AnvilPlugin);
+        }
+
+        #[cfg(feature = "boss_bar")]
+        {
+            group = group.add(valence_boss_bar::BossBarPlugin);
+        }
+
+        #[cfg(feature = "scoreboard")]
+        {
+            group = group.add(valence_scoreboard::ScoreboardPlugin);
+        }
+
+        #[cfg(feature = "command")]
+        {
+            group = group.add(valence_command::CommandPlugin);
+        }
+
+        #[cfg(feature = "chat")]
+        {
+            group = group.add(valence_chat::ChatPlugin);
         }
 
         #[cfg(feature = "advancement")]
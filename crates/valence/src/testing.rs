@@ -0,0 +1,532 @@
+//! A headless harness for testing plugins and game logic against fake
+//! clients, without any networking.
+//!
+//! [`ScenarioBuilder`] sets up an [`App`] with the [`DefaultPlugins`], an
+//! instance, and a client connected to a [`MockClient`]. The mock client can
+//! inject packets as if the client sent them, and collect the packets the
+//! server sent to it.
+//!
+//! This module requires the `testing` feature.
+//!
+//! ```
+//! use valence::packet::c2s::play::{PositionAndOnGround, TeleportConfirmC2s};
+//! use valence::packet::s2c::play::{ChunkDataS2c, GameJoinS2c, PlayerPositionLookS2c};
+//! use valence::prelude::*;
+//! use valence::testing::ScenarioBuilder;
+//!
+//! let mut scenario = ScenarioBuilder::new().with_chunk_radius(2).build();
+//!
+//! // The client joins on the first tick.
+//! scenario.tick();
+//!
+//! let sent = scenario.mock_client.collect_sent();
+//! sent.assert_contains::<GameJoinS2c>();
+//! sent.assert_contains::<ChunkDataS2c>();
+//!
+//! // Movement is ignored until the client confirms the teleport sent on join.
+//! let teleport = sent.first::<PlayerPositionLookS2c>().unwrap();
+//!
+//! scenario.mock_client.send(&TeleportConfirmC2s {
+//!     teleport_id: teleport.teleport_id,
+//! });
+//!
+//! scenario.mock_client.send(&PositionAndOnGround {
+//!     position: DVec3::new(12.0, 64.0, 0.0),
+//!     on_ground: true,
+//! });
+//!
+//! scenario.tick();
+//!
+//! let pos = scenario.app.world.get::<Position>(scenario.client).unwrap();
+//! assert_eq!(pos.0, DVec3::new(12.0, 64.0, 0.0));
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bytes::{Buf, BufMut, BytesMut};
+use glam::DVec3;
+use uuid::Uuid;
+use valence_client::{
+    ClientBundle, ClientBundleArgs, ClientConnection, ReceivedPacket, SendQueueLimit,
+};
+use valence_core::packet::decode::{decode_packet, PacketDecoder};
+use valence_core::packet::encode::PacketEncoder;
+use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_core::packet::var_int::VarInt;
+use valence_core::packet::Packet;
+use valence_core::{ident, CoreSettings, Server};
+use valence_instance::{Chunk, Instance};
+
+use crate::DefaultPlugins;
+
+/// Builds a [`Scenario`].
+#[derive(Clone, Debug)]
+pub struct ScenarioBuilder {
+    username: String,
+    position: DVec3,
+    chunk_radius: i32,
+}
+
+impl ScenarioBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the username of the client. Defaults to `"test"`.
+    pub fn with_username(mut self, username: impl Into<String>) -> Self {
+        self.username = username.into();
+        self
+    }
+
+    /// Sets the initial position of the client. Defaults to the origin.
+    pub fn with_position(mut self, position: impl Into<DVec3>) -> Self {
+        self.position = position.into();
+        self
+    }
+
+    /// Fills the instance with empty chunks, from `-radius` (inclusive) to
+    /// `radius` (exclusive) on both axes. Defaults to zero, which leaves the
+    /// instance without any chunks.
+    pub fn with_chunk_radius(mut self, radius: i32) -> Self {
+        self.chunk_radius = radius;
+        self
+    }
+
+    /// Sets up the app and spawns the instance and the client.
+    ///
+    /// The app is not updated yet, so more plugins and systems can be added to
+    /// it and the instance can be modified before the first [tick]. The client
+    /// joins during the first tick.
+    ///
+    /// [tick]: Scenario::tick
+    pub fn build(self) -> Scenario {
+        let mut app = App::new();
+
+        // The mock clients do not decompress packets.
+        app.insert_resource(CoreSettings {
+            compression_threshold: None,
+            ..Default::default()
+        });
+
+        #[cfg(feature = "network")]
+        app.add_plugins(
+            DefaultPlugins
+                .build()
+                .disable::<valence_network::NetworkPlugin>(),
+        );
+        #[cfg(not(feature = "network"))]
+        app.add_plugins(DefaultPlugins);
+
+        let server = app.world.resource::<Server>();
+        let mut instance = Instance::new_unit_testing(ident!("overworld"), server);
+
+        for z in -self.chunk_radius..self.chunk_radius {
+            for x in -self.chunk_radius..self.chunk_radius {
+                instance.insert_chunk([x, z], Chunk::default());
+            }
+        }
+
+        let instance = app.world.spawn(instance).id();
+
+        let (client, mock_client) = spawn_mock_client(
+            &mut app,
+            instance,
+            self.username,
+            Uuid::from_u128(0),
+            self.position,
+        );
+
+        Scenario {
+            app,
+            instance,
+            client,
+            mock_client,
+            next_uuid: 1,
+        }
+    }
+}
+
+impl Default for ScenarioBuilder {
+    fn default() -> Self {
+        Self {
+            username: "test".into(),
+            position: DVec3::ZERO,
+            chunk_radius: 0,
+        }
+    }
+}
+
+/// An app with an instance and a client connected to a [`MockClient`]. Created
+/// with a [`ScenarioBuilder`].
+pub struct Scenario {
+    pub app: App,
+    /// The entity of the instance the clients are spawned in.
+    pub instance: Entity,
+    /// The entity of the client created by the [`ScenarioBuilder`].
+    pub client: Entity,
+    /// The mock client connected to [`Self::client`].
+    pub mock_client: MockClient,
+    next_uuid: u128,
+}
+
+impl Scenario {
+    /// Runs a single tick of the app.
+    pub fn tick(&mut self) {
+        self.app.update();
+    }
+
+    /// Runs the given number of ticks.
+    pub fn tick_n(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    /// Spawns another client in the instance at the given position. The client
+    /// joins during the next tick.
+    pub fn add_client(
+        &mut self,
+        username: impl Into<String>,
+        position: impl Into<DVec3>,
+    ) -> (Entity, MockClient) {
+        // UUIDs are assigned in order so that the packets are the same between
+        // runs.
+        let uuid = Uuid::from_u128(self.next_uuid);
+        self.next_uuid += 1;
+
+        spawn_mock_client(&mut self.app, self.instance, username, uuid, position)
+    }
+}
+
+fn spawn_mock_client(
+    app: &mut App,
+    instance: Entity,
+    username: impl Into<String>,
+    uuid: Uuid,
+    position: impl Into<DVec3>,
+) -> (Entity, MockClient) {
+    let (mut bundle, mock_client) = create_mock_client(username, uuid);
+
+    bundle.player.location.0 = instance;
+    bundle.player.position.0 = position.into();
+
+    (app.world.spawn(bundle).id(), mock_client)
+}
+
+/// Creates a client bundle which is connected to a [`MockClient`] instead of
+/// the network.
+///
+/// Returns the bundle, and the mock client to inject packets as if the client
+/// sent them and collect the packets sent to it.
+pub fn create_mock_client(username: impl Into<String>, uuid: Uuid) -> (ClientBundle, MockClient) {
+    let conn = MockClientConnection::new();
+
+    let bundle = ClientBundle::new(ClientBundleArgs {
+        username: username.into(),
+        uuid,
+        ip: "127.0.0.1".parse().unwrap(),
+        properties: vec![],
+        conn: Box::new(conn.clone()),
+        enc: PacketEncoder::new(),
+        // The mock connection has no limit of its own.
+        send_queue_limit: SendQueueLimit {
+            max_bytes: usize::MAX,
+            ..Default::default()
+        },
+    });
+
+    (bundle, MockClient::new(conn))
+}
+
+/// A mock client connection which keeps the packets sent to it in memory.
+///
+/// Safe to clone, but note that the clone will share the same buffers.
+#[derive(Clone)]
+pub struct MockClientConnection {
+    inner: Arc<Mutex<MockClientConnectionInner>>,
+}
+
+struct MockClientConnectionInner {
+    /// The queue of packets to receive from the client to be processed by the
+    /// server.
+    recv_buf: VecDeque<ReceivedPacket>,
+    /// The queue of packets to send from the server to the client.
+    send_buf: BytesMut,
+    /// The packets which were not sent yet, as if the connection was stalled.
+    /// `None` if the connection is not stalled.
+    stalled_buf: Option<BytesMut>,
+}
+
+impl MockClientConnection {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(MockClientConnectionInner {
+                recv_buf: VecDeque::new(),
+                send_buf: BytesMut::new(),
+                stalled_buf: None,
+            })),
+        }
+    }
+
+    /// Injects a (Packet ID + data) frame to be received by the server.
+    pub fn inject_recv(&mut self, mut bytes: BytesMut) {
+        let id = VarInt::decode_partial((&mut bytes).reader()).expect("failed to decode packet ID");
+
+        self.inner
+            .lock()
+            .unwrap()
+            .recv_buf
+            .push_back(ReceivedPacket {
+                timestamp: Instant::now(),
+                id,
+                data: bytes.freeze(),
+            });
+    }
+
+    /// Takes the bytes sent to the client so far.
+    pub fn take_sent(&mut self) -> BytesMut {
+        self.inner.lock().unwrap().send_buf.split()
+    }
+
+    pub fn clear_sent(&mut self) {
+        self.inner.lock().unwrap().send_buf.clear();
+    }
+
+    /// Stops sending packets to the client, until [`Self::resume`] is called.
+    pub fn stall(&mut self) {
+        self.inner
+            .lock()
+            .unwrap()
+            .stalled_buf
+            .get_or_insert_with(BytesMut::new);
+    }
+
+    /// Sends the packets queued while the connection was stalled.
+    pub fn resume(&mut self) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(bytes) = inner.stalled_buf.take() {
+            inner.send_buf.unsplit(bytes);
+        }
+    }
+}
+
+impl Default for MockClientConnection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientConnection for MockClientConnection {
+    fn try_send(&mut self, bytes: BytesMut) -> anyhow::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+
+        match &mut inner.stalled_buf {
+            Some(stalled_buf) => stalled_buf.unsplit(bytes),
+            None => inner.send_buf.unsplit(bytes),
+        }
+
+        Ok(())
+    }
+
+    fn try_recv(&mut self) -> anyhow::Result<Option<ReceivedPacket>> {
+        Ok(self.inner.lock().unwrap().recv_buf.pop_front())
+    }
+
+    fn len(&self) -> usize {
+        self.inner.lock().unwrap().recv_buf.len()
+    }
+
+    fn send_queue_len(&self) -> usize {
+        self.inner
+            .lock()
+            .unwrap()
+            .stalled_buf
+            .as_ref()
+            .map_or(0, |buf| buf.len())
+    }
+}
+
+/// The client side of a [`MockClientConnection`]. Injects packets as if the
+/// client sent them and collects the packets sent to the client.
+pub struct MockClient {
+    conn: MockClientConnection,
+    dec: PacketDecoder,
+    scratch: BytesMut,
+}
+
+impl MockClient {
+    pub fn new(conn: MockClientConnection) -> Self {
+        Self {
+            conn,
+            dec: PacketDecoder::new(),
+            scratch: BytesMut::new(),
+        }
+    }
+
+    /// Returns the connection of this client.
+    pub fn connection_mut(&mut self) -> &mut MockClientConnection {
+        &mut self.conn
+    }
+
+    /// Injects a packet to be treated as a packet inbound to the server. It is
+    /// processed during the next tick. Panics if the packet cannot be encoded.
+    pub fn send<'a>(&mut self, packet: &impl Packet<'a>) {
+        packet
+            .encode_packet((&mut self.scratch).writer())
+            .expect("failed to encode packet");
+
+        self.conn.inject_recv(self.scratch.split());
+    }
+
+    /// Collects all the packets sent to the client since the last time they
+    /// were collected or cleared.
+    pub fn collect_sent(&mut self) -> SentPackets {
+        self.dec.queue_bytes(self.conn.take_sent());
+
+        let mut frames = vec![];
+
+        while let Some(frame) = self
+            .dec
+            .try_next_packet()
+            .expect("failed to decode packet frame")
+        {
+            frames.push(frame);
+        }
+
+        SentPackets { frames }
+    }
+
+    /// Discards all the packets sent to the client so far.
+    pub fn clear_sent(&mut self) {
+        self.conn.clear_sent();
+    }
+}
+
+/// Packets sent to a [`MockClient`]. Returned by [`MockClient::collect_sent`].
+///
+/// Packets are looked up by type, so the type must have a statically known
+/// packet ID. [`S2cPlayPacket`] can be used through [`Self::all`] to match on
+/// any packet.
+#[derive(Clone, Debug)]
+pub struct SentPackets {
+    frames: Vec<BytesMut>,
+}
+
+impl SentPackets {
+    /// Returns the number of packets.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Decodes all the packets.
+    pub fn all(&self) -> Vec<S2cPlayPacket> {
+        self.frames
+            .iter()
+            .map(|frame| decode_packet(frame).expect("failed to decode packet"))
+            .collect()
+    }
+
+    /// Decodes all the packets of type `P`, in the order they were sent.
+    pub fn of<'a, P: Packet<'a>>(&'a self) -> Vec<P> {
+        self.frames_with_id(packet_id::<P>())
+            .map(|frame| decode_packet(frame).expect("failed to decode packet"))
+            .collect()
+    }
+
+    /// Decodes the first packet of type `P`.
+    pub fn first<'a, P: Packet<'a>>(&'a self) -> Option<P> {
+        self.frames_with_id(packet_id::<P>())
+            .next()
+            .map(|frame| decode_packet(frame).expect("failed to decode packet"))
+    }
+
+    /// Returns the number of packets of type `P`.
+    pub fn count<'a, P: Packet<'a>>(&self) -> usize {
+        self.frames_with_id(packet_id::<P>()).count()
+    }
+
+    /// Returns the index of the first packet of type `P`.
+    pub fn position<'a, P: Packet<'a>>(&self) -> Option<usize> {
+        self.frames
+            .iter()
+            .position(|frame| frame_packet_id(frame) == Some(packet_id::<P>()))
+    }
+
+    /// Panics if there is no packet of type `P`.
+    #[track_caller]
+    pub fn assert_contains<'a, P: Packet<'a>>(&self) {
+        assert!(
+            self.count::<P>() > 0,
+            "expected a {} packet\n{}",
+            std::any::type_name::<P>(),
+            self.packet_names()
+        );
+    }
+
+    /// Panics if there is a packet of type `P`.
+    #[track_caller]
+    pub fn assert_not_contains<'a, P: Packet<'a>>(&self) {
+        assert!(
+            self.count::<P>() == 0,
+            "expected no {} packets\n{}",
+            std::any::type_name::<P>(),
+            self.packet_names()
+        );
+    }
+
+    /// Panics if the number of packets of type `P` is not `count`.
+    #[track_caller]
+    pub fn assert_count<'a, P: Packet<'a>>(&self, count: usize) {
+        let actual = self.count::<P>();
+
+        assert!(
+            actual == count,
+            "expected {count} {} packets, got {actual}\n{}",
+            std::any::type_name::<P>(),
+            self.packet_names()
+        );
+    }
+
+    fn frames_with_id(&self, id: i32) -> impl Iterator<Item = &BytesMut> {
+        self.frames
+            .iter()
+            .filter(move |frame| frame_packet_id(frame) == Some(id))
+    }
+
+    /// Lists the names of all the packets, for assertion messages.
+    fn packet_names(&self) -> String {
+        let names: Vec<_> = self
+            .frames
+            .iter()
+            .map(|frame| match decode_packet::<S2cPlayPacket>(frame) {
+                Ok(pkt) => pkt.packet_name().to_owned(),
+                Err(_) => "<invalid>".to_owned(),
+            })
+            .collect();
+
+        format!("packets actually found: [{}]", names.join(", "))
+    }
+}
+
+fn packet_id<'a, P: Packet<'a>>() -> i32 {
+    assert!(
+        P::PACKET_ID >= 0,
+        "{} does not have a statically known packet ID",
+        std::any::type_name::<P>()
+    );
+
+    P::PACKET_ID
+}
+
+fn frame_packet_id(mut frame: &[u8]) -> Option<i32> {
+    VarInt::decode_partial(&mut frame).ok()
+}
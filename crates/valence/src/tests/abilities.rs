@@ -0,0 +1,154 @@
+use valence_client::abilities::{FlyState, Flying, PlayerAbilities};
+use valence_core::game_mode::GameMode;
+use valence_core::packet::c2s::play::UpdatePlayerAbilitiesC2s;
+use valence_core::packet::s2c::play::{PlayerAbilitiesS2c, S2cPlayPacket};
+
+use super::*;
+
+/// Returns the last abilities packet sent to the client.
+fn last_abilities(sent_packets: &[S2cPlayPacket]) -> Option<PlayerAbilitiesS2c> {
+    sent_packets.iter().rev().find_map(|pkt| match pkt {
+        S2cPlayPacket::PlayerAbilitiesS2c(pkt) => Some(pkt.clone()),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_abilities_follow_game_mode() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // The abilities are sent when joining.
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::PlayerAbilitiesS2c(_));
+
+    // Changing the game mode resets the abilities.
+    *app.world.get_mut::<GameMode>(client_ent).unwrap() = GameMode::Creative;
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::GameStateChangeS2c(_),
+        S2cPlayPacket::PlayerAbilitiesS2c(_)
+    );
+
+    let pkt = last_abilities(&sent_packets).unwrap();
+
+    assert!(pkt.flags.allow_flying());
+    assert!(pkt.flags.instant_break());
+    assert!(pkt.flags.invulnerable());
+    assert!(!pkt.flags.flying());
+
+    // Abilities modified in the same tick as the game mode are kept.
+    *app.world.get_mut::<GameMode>(client_ent).unwrap() = GameMode::Adventure;
+
+    let mut abilities = app.world.get_mut::<PlayerAbilities>(client_ent).unwrap();
+    abilities.fly_speed = 0.2;
+    abilities.walk_speed = 0.3;
+
+    app.update();
+
+    let pkt = last_abilities(&client_helper.collect_sent().all()).unwrap();
+
+    assert!(pkt.flags.allow_flying());
+    assert_eq!(pkt.flying_speed, 0.2);
+    assert_eq!(pkt.fov_modifier, 0.3);
+
+    // Nothing is sent while nothing changes.
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::PlayerAbilitiesS2c(_));
+
+    // The abilities are sent again after respawning.
+    let server = app.world.resource::<Server>();
+    let instance = Instance::new_unit_testing(ident!("overworld"), server);
+    let instance_ent = app.world.spawn(instance).id();
+
+    app.world.get_mut::<Location>(client_ent).unwrap().0 = instance_ent;
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlayerRespawnS2c(_),
+        S2cPlayPacket::PlayerAbilitiesS2c(_)
+    );
+    assert_eq!(last_abilities(&sent_packets).unwrap().flying_speed, 0.2);
+}
+
+#[test]
+fn test_flying() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    app.world
+        .get_mut::<PlayerAbilities>(client_ent)
+        .unwrap()
+        .allow_flying = true;
+
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&UpdatePlayerAbilitiesC2s::StartFlying);
+
+    app.update();
+
+    let events = app.world.resource::<Events<Flying>>();
+    let events = events.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(
+        events,
+        [&Flying {
+            client: client_ent,
+            state: FlyState::Start,
+        }]
+    );
+
+    assert!(app.world.get::<PlayerAbilities>(client_ent).unwrap().flying);
+
+    // The client already knows it is flying.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::PlayerAbilitiesS2c(_));
+
+    // Clients which are not allowed to fly are told to stop.
+    let mut abilities = app.world.get_mut::<PlayerAbilities>(client_ent).unwrap();
+    abilities.allow_flying = false;
+    abilities.flying = false;
+
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&UpdatePlayerAbilitiesC2s::StartFlying);
+
+    app.update();
+
+    assert!(!app.world.get::<PlayerAbilities>(client_ent).unwrap().flying);
+
+    let pkt = last_abilities(&client_helper.collect_sent().all()).unwrap();
+
+    assert!(!pkt.flags.flying());
+}
@@ -0,0 +1,181 @@
+use bevy_app::App;
+use valence_advancement::bevy_hierarchy::BuildWorldChildren;
+use valence_advancement::{
+    AdvancementBundle, AdvancementClientUpdate, AdvancementCriteria, AdvancementDisplay,
+    AdvancementFrameType, AdvancementRequirements,
+};
+use valence_core::despawn::Despawned;
+use valence_core::item::{ItemKind, ItemStack};
+use valence_core::packet::s2c::play::AdvancementUpdateS2c;
+
+use super::*;
+
+/// Returns the advancement update packets sent to the client.
+/// Spawns a root advancement with one criterion and a child advancement.
+/// Returns the root, the criterion and the child.
+fn spawn_tree(app: &mut App) -> (Entity, Entity, Entity) {
+    let criteria = app
+        .world
+        .spawn(AdvancementCriteria::new(
+            ident!("test:root_criteria").into(),
+        ))
+        .id();
+
+    let root = app
+        .world
+        .spawn((
+            AdvancementBundle::new(ident!("test:root"))
+                .with_requirements(AdvancementRequirements::all([criteria])),
+            AdvancementDisplay::new(
+                "Root",
+                "The root",
+                ItemStack::new(ItemKind::Stone, 1, None),
+                AdvancementFrameType::Task,
+            )
+            .with_background_texture(ident!("textures/block/stone.png")),
+        ))
+        .push_children(&[criteria])
+        .id();
+
+    let child = app
+        .world
+        .spawn((
+            AdvancementBundle::new(ident!("test:child")),
+            AdvancementDisplay::new(
+                "Child",
+                "The child",
+                ItemStack::new(ItemKind::Dirt, 1, None),
+                AdvancementFrameType::Goal,
+            )
+            .with_position(1.0, 0.0),
+        ))
+        .id();
+
+    app.world.entity_mut(root).push_children(&[child]);
+
+    (root, criteria, child)
+}
+
+#[test]
+fn test_advancements_sent_on_join() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    spawn_tree(&mut app);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let updates = sent.of::<AdvancementUpdateS2c>();
+
+    assert_eq!(updates.len(), 1);
+    assert!(updates[0].reset);
+
+    let mut idents = updates[0]
+        .advancement_mapping
+        .iter()
+        .map(|(ident, _)| ident.as_str().to_owned())
+        .collect::<Vec<_>>();
+
+    idents.sort();
+
+    assert_eq!(idents, ["test:child", "test:root"]);
+
+    for (ident, adv) in &updates[0].advancement_mapping {
+        if ident.as_str() == "test:child" {
+            assert_eq!(
+                adv.parent_id.as_ref().map(|p| p.as_str()),
+                Some("test:root")
+            );
+        } else {
+            assert_eq!(adv.criteria.len(), 1);
+            assert_eq!(adv.requirements.len(), 1);
+        }
+    }
+
+    // Nothing is sent while nothing changes.
+    app.update();
+
+    client_helper
+        .collect_sent()
+        .assert_not_contains::<AdvancementUpdateS2c>();
+
+    assert!(
+        !app.world
+            .get::<AdvancementClientUpdate>(client_ent)
+            .unwrap()
+            .reset
+    );
+}
+
+#[test]
+fn test_advancement_progress_and_removal() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let (root, criteria, child) = spawn_tree(&mut app);
+
+    app.update();
+    client_helper.clear_sent();
+
+    // Granting a criterion sends the progress without resetting, so the client
+    // shows a toast.
+    app.world
+        .get_mut::<AdvancementClientUpdate>(client_ent)
+        .unwrap()
+        .criteria_done(criteria);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let updates = sent.of::<AdvancementUpdateS2c>();
+
+    assert_eq!(updates.len(), 1);
+    assert!(!updates[0].reset);
+    assert_eq!(updates[0].progress_mapping.len(), 1);
+    assert_eq!(updates[0].progress_mapping[0].0.as_str(), "test:root");
+    assert!(updates[0].progress_mapping[0].1[0]
+        .criterion_progress
+        .is_some());
+
+    // Advancements spawned later are sent to the clients in the game.
+    let new_child = app
+        .world
+        .spawn(AdvancementBundle::new(ident!("test:new_child")))
+        .id();
+
+    app.world.entity_mut(root).push_children(&[new_child]);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let updates = sent.of::<AdvancementUpdateS2c>();
+
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].advancement_mapping.len(), 1);
+    assert_eq!(
+        updates[0].advancement_mapping[0].0.as_str(),
+        "test:new_child"
+    );
+
+    // Despawned advancements are removed from the clients.
+    app.world.entity_mut(child).insert(Despawned);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let updates = sent.of::<AdvancementUpdateS2c>();
+
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].identifiers.len(), 1);
+    assert_eq!(updates[0].identifiers[0].as_str(), "test:child");
+    assert!(app.world.get_entity(child).is_none());
+}
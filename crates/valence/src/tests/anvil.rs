@@ -0,0 +1,188 @@
+use std::thread;
+use std::time::Duration;
+
+use bevy_app::App;
+use bevy_ecs::event::{Events, ManualEventReader};
+use valence_anvil::{
+    from_valence, AnvilLevel, AnvilWorld, ChunkLoadFailedEvent, ChunkLoadedEvent, LoadChunkError,
+};
+use valence_block::BlockState;
+use valence_core::chunk_pos::ChunkPos;
+use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_instance::Chunk;
+use valence_nbt::Compound;
+
+use super::*;
+
+/// Writes a chunk with a stone block at the bottom to the world.
+fn write_stone_chunk(world: &mut AnvilWorld, pos: ChunkPos) {
+    let mut chunk = Chunk::new(24);
+    chunk.set_block_state(0, 0, 0, BlockState::STONE);
+
+    let mut nbt = Compound::new();
+    from_valence(&chunk, pos, &mut nbt, 4, |_| ident!("plains").into()).unwrap();
+
+    world.write_chunk(pos.x, pos.z, &nbt).unwrap();
+}
+
+/// Updates the app until no chunks are requested from the level, returning
+/// the loaded events and the positions of the missing chunks.
+fn update_until_loaded(
+    app: &mut App,
+    instance_ent: Entity,
+) -> (Vec<ChunkLoadedEvent>, Vec<ChunkPos>) {
+    let mut loaded_reader = ManualEventReader::<ChunkLoadedEvent>::default();
+    let mut failed_reader = ManualEventReader::<ChunkLoadFailedEvent>::default();
+
+    let mut loaded = vec![];
+    let mut failed = vec![];
+
+    for _ in 0..1000 {
+        app.update();
+
+        let events = app.world.resource::<Events<ChunkLoadedEvent>>();
+        loaded.extend(loaded_reader.iter(events).cloned());
+
+        let events = app.world.resource::<Events<ChunkLoadFailedEvent>>();
+        for event in failed_reader.iter(events) {
+            assert_eq!(event.instance, instance_ent);
+            assert!(
+                matches!(event.error, LoadChunkError::Missing),
+                "failed to load chunk: {}",
+                event.error
+            );
+            failed.push(event.pos);
+        }
+
+        let level = app.world.get::<AnvilLevel>(instance_ent).unwrap();
+
+        if level.requested_chunks().next().is_none() {
+            return (loaded, failed);
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    panic!("chunks were not loaded in time");
+}
+
+fn instance_entity(app: &mut App) -> Entity {
+    app.world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world)
+}
+
+#[test]
+fn requested_chunks_loaded() {
+    let Scenario { mut app, .. } = ScenarioBuilder::new().build();
+    let instance_ent = instance_entity(&mut app);
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut world = AnvilWorld::new(dir.path());
+    write_stone_chunk(&mut world, ChunkPos::new(100, 100));
+    write_stone_chunk(&mut world, ChunkPos::new(-100, 100));
+
+    let mut level = AnvilLevel::new(dir.path());
+    level.set_load_viewed_chunks(false);
+
+    assert!(level.request_chunk([100, 100]));
+    assert!(level.request_chunk([-100, 100]));
+    assert!(level.request_chunk([200, 200]));
+    // Duplicate requests are ignored.
+    assert!(!level.request_chunk([100, 100]));
+
+    app.world.entity_mut(instance_ent).insert(level);
+
+    let (loaded, failed) = update_until_loaded(&mut app, instance_ent);
+
+    let mut loaded_pos: Vec<_> = loaded.iter().map(|e| e.pos).collect();
+    loaded_pos.sort_by_key(|pos| pos.x);
+    assert_eq!(
+        loaded_pos,
+        [ChunkPos::new(-100, 100), ChunkPos::new(100, 100)]
+    );
+    assert!(loaded.iter().all(|e| e.instance == instance_ent));
+
+    // Missing chunks are reported so they can be generated.
+    assert_eq!(failed, [ChunkPos::new(200, 200)]);
+
+    let instance = app.world.get::<Instance>(instance_ent).unwrap();
+    let chunk = instance.chunk([100, 100]).expect("chunk should be loaded");
+    assert_eq!(chunk.block_state(0, 0, 0), BlockState::STONE);
+    assert!(instance.chunk([-100, 100]).is_some());
+    assert!(instance.chunk([200, 200]).is_none());
+}
+
+#[test]
+fn cancelled_chunk_not_inserted() {
+    let Scenario { mut app, .. } = ScenarioBuilder::new().build();
+    let instance_ent = instance_entity(&mut app);
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut world = AnvilWorld::new(dir.path());
+    write_stone_chunk(&mut world, ChunkPos::new(100, 100));
+    write_stone_chunk(&mut world, ChunkPos::new(101, 100));
+
+    let mut level = AnvilLevel::new(dir.path());
+    level.set_load_viewed_chunks(false);
+    level.request_chunk([100, 100]);
+    app.world.entity_mut(instance_ent).insert(level);
+
+    // Send the chunk to be loaded.
+    app.update();
+
+    let mut level = app.world.get_mut::<AnvilLevel>(instance_ent).unwrap();
+    assert!(level.cancel_chunk([100, 100]));
+    assert!(!level.cancel_chunk([100, 100]));
+    // The cancelled chunk is loaded first, so it is received before this one.
+    level.request_chunk([101, 100]);
+
+    let (loaded, failed) = update_until_loaded(&mut app, instance_ent);
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].pos, ChunkPos::new(101, 100));
+    assert!(failed.is_empty());
+
+    let instance = app.world.get::<Instance>(instance_ent).unwrap();
+    assert!(instance.chunk([100, 100]).is_none());
+    assert!(instance.chunk([101, 100]).is_some());
+}
+
+#[test]
+fn viewed_chunks_loaded() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+    let instance_ent = instance_entity(&mut app);
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut world = AnvilWorld::new(dir.path());
+    write_stone_chunk(&mut world, ChunkPos::new(0, 0));
+    write_stone_chunk(&mut world, ChunkPos::new(100, 100));
+
+    app.world
+        .entity_mut(instance_ent)
+        .insert(AnvilLevel::new(dir.path()));
+
+    let (loaded, failed) = update_until_loaded(&mut app, instance_ent);
+
+    // Only the chunks in view of the client are requested.
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].pos, ChunkPos::new(0, 0));
+    assert!(!failed.is_empty());
+    assert!(failed
+        .iter()
+        .all(|pos| pos.distance_squared(ChunkPos::new(0, 0)) < 100));
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert!(sent_packets.iter().any(|pkt| matches!(
+        pkt,
+        S2cPlayPacket::ChunkDataS2c(pkt) if pkt.pos == ChunkPos::new(0, 0)
+    )));
+}
@@ -0,0 +1,198 @@
+use bevy_app::App;
+use valence_block::BlockState;
+use valence_core::block_pos::BlockPos;
+use valence_instance::{
+    BlockChangeCause, BlockChangeEvent, BlockRegionChangeEvent, Clipboard, FillEvents,
+};
+
+use super::*;
+
+fn block_events(app: &App) -> Vec<BlockChangeEvent> {
+    app.world
+        .resource::<Events<BlockChangeEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect()
+}
+
+fn region_events(app: &App) -> Vec<BlockRegionChangeEvent> {
+    app.world
+        .resource::<Events<BlockRegionChangeEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect()
+}
+
+fn setup() -> (App, Entity, Entity) {
+    let mut scenario = ScenarioBuilder::new().with_chunk_radius(1).build();
+
+    scenario.tick();
+
+    (scenario.app, scenario.instance, scenario.client)
+}
+
+#[test]
+fn set_block_sends_events() {
+    let (mut app, instance_ent, client_ent) = setup();
+
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    instance.set_block([1, 2, 3], BlockState::STONE);
+    instance.set_block_with_cause(
+        [-1, 2, -3],
+        BlockState::DIRT,
+        BlockChangeCause::PlayerPlace { client: client_ent },
+    );
+    // Setting a block to the same state is not a change.
+    instance.set_block([5, 5, 5], BlockState::AIR);
+    // Neither is setting a block in an unloaded chunk.
+    instance.set_block([100, 0, 100], BlockState::STONE);
+
+    app.update();
+
+    assert_eq!(
+        block_events(&app),
+        [
+            BlockChangeEvent {
+                instance: instance_ent,
+                position: BlockPos::new(1, 2, 3),
+                old_state: BlockState::AIR,
+                new_state: BlockState::STONE,
+                cause: BlockChangeCause::Api,
+            },
+            BlockChangeEvent {
+                instance: instance_ent,
+                position: BlockPos::new(-1, 2, -3),
+                old_state: BlockState::AIR,
+                new_state: BlockState::DIRT,
+                cause: BlockChangeCause::PlayerPlace { client: client_ent },
+            }
+        ]
+    );
+
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    instance.set_block_with_cause(
+        [1, 2, 3],
+        BlockState::AIR,
+        BlockChangeCause::PlayerBreak { client: client_ent },
+    );
+
+    app.update();
+
+    let events = block_events(&app);
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].old_state, BlockState::STONE);
+    assert_eq!(
+        events[0].cause,
+        BlockChangeCause::PlayerBreak { client: client_ent }
+    );
+}
+
+#[test]
+fn fill_blocks_sends_events() {
+    let (mut app, instance_ent, _) = setup();
+
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    instance.set_block([0, 0, 0], BlockState::STONE);
+    instance.fill_blocks(
+        [-2, 0, -2],
+        [1, 0, 1],
+        BlockState::STONE,
+        FillEvents::Region,
+    );
+
+    app.update();
+
+    assert_eq!(block_events(&app).len(), 1);
+    assert_eq!(
+        region_events(&app),
+        [BlockRegionChangeEvent {
+            instance: instance_ent,
+            min: BlockPos::new(-2, 0, -2),
+            max: BlockPos::new(1, 0, 1),
+            new_state: BlockState::STONE,
+        }]
+    );
+
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    // Only the blocks whose state changed are reported.
+    instance.set_block([0, 1, 0], BlockState::GLASS);
+    app.update();
+
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    instance.fill_blocks(
+        [1, 1, 1],
+        [-2, 0, -2],
+        BlockState::GLASS,
+        FillEvents::PerBlock,
+    );
+
+    app.update();
+
+    let events = block_events(&app);
+
+    assert!(region_events(&app).is_empty());
+    assert_eq!(events.len(), 31);
+    assert!(events
+        .iter()
+        .all(|event| event.new_state == BlockState::GLASS));
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| event.old_state == BlockState::STONE)
+            .count(),
+        16
+    );
+    assert!(!events
+        .iter()
+        .any(|event| event.position == BlockPos::new(0, 1, 0)));
+
+    // Nothing is sent when the box is entirely in unloaded chunks.
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    instance.fill_blocks(
+        [100, 0, 100],
+        [101, 0, 101],
+        BlockState::STONE,
+        FillEvents::Region,
+    );
+
+    app.update();
+
+    assert!(region_events(&app).is_empty());
+}
+
+#[test]
+fn paste_sends_events() {
+    let (mut app, instance_ent, _) = setup();
+
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    instance.set_block([0, 0, 0], BlockState::STONE);
+    instance.set_block([1, 0, 0], BlockState::DIRT);
+
+    let clipboard = Clipboard::copy(&instance, [0, 0, 0], [1, 0, 0]);
+
+    app.update();
+
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    clipboard.paste(&mut instance, [1, 0, 0], true);
+
+    app.update();
+
+    let events = block_events(&app);
+
+    // The dirt at (1, 0, 0) is replaced with stone and (2, 0, 0) with dirt.
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].position, BlockPos::new(1, 0, 0));
+    assert_eq!(events[0].old_state, BlockState::DIRT);
+    assert_eq!(events[0].new_state, BlockState::STONE);
+    assert_eq!(events[1].position, BlockPos::new(2, 0, 0));
+    assert_eq!(events[1].new_state, BlockState::DIRT);
+}
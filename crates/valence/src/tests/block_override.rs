@@ -0,0 +1,158 @@
+use bevy_app::App;
+use valence_block::BlockState;
+use valence_client::block_override::BlockOverrides;
+use valence_core::block_pos::BlockPos;
+use valence_core::packet::s2c::play::{BlockUpdateS2c, S2cPlayPacket};
+use valence_entity::Position;
+use valence_instance::Chunk;
+
+use super::*;
+
+/// Returns the block updates sent to the client.
+fn block_updates(sent_packets: &[S2cPlayPacket]) -> Vec<(BlockPos, BlockState)> {
+    sent_packets
+        .iter()
+        .filter_map(|pkt| match pkt {
+            S2cPlayPacket::BlockUpdateS2c(BlockUpdateS2c { position, block_id }) => {
+                Some((*position, BlockState::from_raw(block_id.0 as u16).unwrap()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Loads the chunks around the origin, except for the chunk at `[1, 0]`.
+fn setup() -> (App, Entity, MockClient) {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    for z in -10..10 {
+        for x in -10..10 {
+            if [x, z] != [1, 0] {
+                instance.insert_chunk([x, z], Chunk::default());
+            }
+        }
+    }
+
+    (app, client_ent, client_helper)
+}
+
+#[test]
+fn test_block_override_sent_with_chunks() {
+    let (mut app, client_ent, mut client_helper) = setup();
+
+    app.world
+        .get_mut::<BlockOverrides>(client_ent)
+        .unwrap()
+        .set([0, 0, 0], BlockState::STONE);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::ChunkDataS2c(_),
+        S2cPlayPacket::BlockUpdateS2c(_)
+    );
+    assert_eq!(
+        block_updates(&sent_packets),
+        [(BlockPos::new(0, 0, 0), BlockState::STONE)]
+    );
+
+    // Nothing is sent again while nothing changes.
+    app.update();
+
+    assert!(block_updates(&client_helper.collect_sent().all()).is_empty());
+
+    // Changes to the real block don't replace the override.
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.set_block([0, 0, 0], BlockState::DIRT);
+
+    app.update();
+
+    assert_eq!(
+        block_updates(&client_helper.collect_sent().all()),
+        [
+            (BlockPos::new(0, 0, 0), BlockState::DIRT),
+            (BlockPos::new(0, 0, 0), BlockState::STONE)
+        ]
+    );
+
+    // Removing the override shows the real block again.
+    app.world
+        .get_mut::<BlockOverrides>(client_ent)
+        .unwrap()
+        .remove([0, 0, 0]);
+
+    app.update();
+
+    assert_eq!(
+        block_updates(&client_helper.collect_sent().all()),
+        [(BlockPos::new(0, 0, 0), BlockState::DIRT)]
+    );
+}
+
+#[test]
+fn test_block_override_applied_lazily() {
+    let (mut app, client_ent, mut client_helper) = setup();
+
+    app.update();
+    client_helper.clear_sent();
+
+    // Overrides in chunks which are not loaded or not in view are not sent.
+    let mut overrides = app.world.get_mut::<BlockOverrides>(client_ent).unwrap();
+    overrides.set([16, 0, 0], BlockState::STONE);
+    overrides.set([128, 0, 0], BlockState::GLASS);
+
+    app.update();
+
+    assert!(block_updates(&client_helper.collect_sent().all()).is_empty());
+
+    // The override is sent when the chunk is loaded.
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.insert_chunk([1, 0], Chunk::default());
+
+    app.update();
+
+    assert_eq!(
+        block_updates(&client_helper.collect_sent().all()),
+        [(BlockPos::new(16, 0, 0), BlockState::STONE)]
+    );
+
+    // The override is sent when the chunk enters the view of the client.
+    app.world.get_mut::<Position>(client_ent).unwrap().0 = [128.0, 0.0, 0.0].into();
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::ChunkDataS2c(_),
+        S2cPlayPacket::BlockUpdateS2c(_)
+    );
+    assert_eq!(
+        block_updates(&sent_packets),
+        [(BlockPos::new(128, 0, 0), BlockState::GLASS)]
+    );
+}
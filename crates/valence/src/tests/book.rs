@@ -0,0 +1,114 @@
+use valence_client::book::{EditBook, WrittenBook, MAX_BOOK_PAGES};
+use valence_client::Client;
+use valence_core::hand::Hand;
+use valence_core::item::ItemKind;
+use valence_core::packet::c2s::play::BookUpdateC2s;
+use valence_core::packet::s2c::play::OpenWrittenBookS2c;
+use valence_core::packet::var_int::VarInt;
+use valence_core::text::Text;
+use valence_nbt::{List, Value};
+
+use super::*;
+
+#[test]
+fn test_open_book() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    let mut client = app.world.get_mut::<Client>(client_ent).unwrap();
+    client.open_book(Hand::Off);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::OpenWrittenBookS2c(OpenWrittenBookS2c { hand: Hand::Off })
+    );
+}
+
+#[test]
+fn test_written_book_nbt() {
+    let book = WrittenBook {
+        title: "Rules".into(),
+        author: "Server".into(),
+        pages: vec!["Be nice.".into(), "Have fun.".into()],
+    };
+
+    let stack = book.to_item_stack();
+    assert_eq!(stack.item, ItemKind::WrittenBook);
+
+    let nbt = stack.nbt.unwrap();
+
+    assert_eq!(nbt.get("title"), Some(&Value::String("Rules".into())));
+    assert_eq!(nbt.get("author"), Some(&Value::String("Server".into())));
+
+    // Pages are stored as JSON text.
+    let Some(Value::List(List::String(pages))) = nbt.get("pages") else {
+        panic!("pages should be a list of strings");
+    };
+
+    assert_eq!(pages.len(), 2);
+    assert_eq!(
+        Value::String(pages[0].clone()),
+        Value::from(Text::text("Be nice."))
+    );
+}
+
+#[test]
+fn test_edit_book() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    client_helper.send(&BookUpdateC2s {
+        slot: VarInt(2),
+        entries: vec!["First page", "Second page"],
+        title: Some("Journal"),
+    });
+
+    // Books with too many pages are rejected.
+    client_helper.send(&BookUpdateC2s {
+        slot: VarInt(0),
+        entries: vec![""; MAX_BOOK_PAGES + 1],
+        title: None,
+    });
+
+    // Books in slots outside the hotbar are rejected.
+    client_helper.send(&BookUpdateC2s {
+        slot: VarInt(20),
+        entries: vec!["Page"],
+        title: None,
+    });
+
+    app.update();
+
+    let events = app.world.resource::<Events<EditBook>>();
+    let events = events.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        *events[0],
+        EditBook {
+            client: client_ent,
+            slot: 2,
+            pages: vec!["First page".into(), "Second page".into()],
+            title: Some("Journal".into()),
+        }
+    );
+}
@@ -0,0 +1,221 @@
+use valence_boss_bar::{
+    BossBarBundle, BossBarColor, BossBarDivision, BossBarHealth, BossBarStyle, BossBarTitle,
+    BossBarViewers, OldBossBarViewers,
+};
+use valence_client::Client;
+use valence_core::despawn::Despawned;
+use valence_core::packet::s2c::play::boss_bar::{Action, BossBarS2c};
+use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_core::text::Text;
+
+use super::*;
+
+#[test]
+fn test_boss_bar_viewed_by_client() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let boss_bar = app
+        .world
+        .spawn(BossBarBundle {
+            title: BossBarTitle(Text::text("Boss")),
+            viewers: BossBarViewers {
+                clients: [client_ent].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .id();
+
+    // The boss bar is sent once the client has joined.
+    app.update();
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::BossBarS2c(BossBarS2c {
+            action: Action::Add { .. },
+            ..
+        })
+    );
+
+    // Only the changed fields are sent.
+    let mut bar = app.world.entity_mut(boss_bar);
+    bar.get_mut::<BossBarHealth>().unwrap().0 = 0.5;
+    bar.insert(BossBarStyle {
+        color: BossBarColor::Red,
+        division: BossBarDivision::TenNotches,
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 2, S2cPlayPacket::BossBarS2c(_));
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::BossBarS2c(BossBarS2c {
+            action: Action::UpdateHealth(_),
+            ..
+        })
+    );
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::BossBarS2c(BossBarS2c {
+            action: Action::UpdateStyle(BossBarColor::Red, BossBarDivision::TenNotches),
+            ..
+        })
+    );
+
+    // The boss bar is removed from the client's screen.
+    app.world
+        .get_mut::<BossBarViewers>(boss_bar)
+        .unwrap()
+        .clients
+        .clear();
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::BossBarS2c(BossBarS2c {
+            action: Action::Remove,
+            ..
+        })
+    );
+    assert!(app
+        .world
+        .get::<OldBossBarViewers>(boss_bar)
+        .unwrap()
+        .get()
+        .is_empty());
+}
+
+#[test]
+fn test_boss_bar_viewed_by_instance() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    let boss_bar = app
+        .world
+        .spawn(BossBarBundle {
+            viewers: BossBarViewers {
+                instance: Some(instance_ent),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::BossBarS2c(BossBarS2c {
+            action: Action::Add { .. },
+            ..
+        })
+    );
+
+    // The client disconnects, and no packet is sent to it.
+    app.world.entity_mut(client_ent).remove::<Client>();
+    app.update();
+
+    assert!(client_helper.collect_sent().is_empty());
+    assert!(app
+        .world
+        .get::<OldBossBarViewers>(boss_bar)
+        .unwrap()
+        .get()
+        .is_empty());
+
+    // The client reconnects as a new entity in the same instance.
+    let (client, mut client_helper) = create_mock_client("test", Uuid::from_u128(1));
+    let client_ent = app.world.spawn(client).id();
+    app.world.get_mut::<Location>(client_ent).unwrap().0 = instance_ent;
+
+    app.update();
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::BossBarS2c(BossBarS2c {
+            action: Action::Add { .. },
+            ..
+        })
+    );
+}
+
+#[test]
+fn test_boss_bar_despawned() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let boss_bar = app
+        .world
+        .spawn(BossBarBundle {
+            viewers: BossBarViewers {
+                clients: [client_ent].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+    app.update();
+    client_helper.clear_sent();
+
+    app.world.entity_mut(boss_bar).insert(Despawned);
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::BossBarS2c(BossBarS2c {
+            action: Action::Remove,
+            ..
+        })
+    );
+    assert!(app.world.get_entity(boss_bar).is_none());
+}
@@ -0,0 +1,189 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::process::Command;
+
+use bevy_tasks::{ComputeTaskPool, TaskPoolBuilder};
+use bytes::BytesMut;
+use glam::DVec3;
+use valence_core::packet::decode::PacketDecoder;
+use valence_core::packet::s2c::play::KeepAliveS2c;
+use valence_core::packet::Packet;
+use valence_core::uuid::UniqueId;
+use valence_entity::cow::CowEntityBundle;
+use valence_entity::Position;
+
+use super::*;
+
+const CLIENT_COUNT: usize = 32;
+const COW_COUNT: usize = 64;
+const TICKS: usize = 20;
+
+/// The environment variable with the file [`sequential_broadcast_scenario`]
+/// writes its results to.
+const SEQUENTIAL_OUTPUT_VAR: &str = "VALENCE_SEQUENTIAL_BROADCAST_OUTPUT";
+
+/// Runs a few ticks of a scenario where many clients and entities move across
+/// cell boundaries, which loads and unloads chunks and spawns and despawns
+/// entities. Returns a digest of the packets sent to each client in each tick.
+fn run_broadcast_scenario() -> Vec<Vec<u64>> {
+    let mut scenario = ScenarioBuilder::new().with_chunk_radius(8).build();
+
+    // The connections share their buffers with the mock clients.
+    let mut clients = vec![(
+        scenario.client,
+        scenario.mock_client.connection_mut().clone(),
+    )];
+
+    for i in 1..CLIENT_COUNT {
+        let (client, mut mock_client) = scenario.add_client(format!("test_{i}"), DVec3::ZERO);
+        clients.push((client, mock_client.connection_mut().clone()));
+    }
+
+    let cows: Vec<_> = (0..COW_COUNT)
+        .map(|i| {
+            scenario
+                .app
+                .world
+                .spawn(CowEntityBundle {
+                    location: Location(scenario.instance),
+                    uuid: UniqueId(Uuid::from_u128((CLIENT_COUNT + i) as u128)),
+                    ..Default::default()
+                })
+                .id()
+        })
+        .collect();
+
+    let mut digests = vec![vec![]; CLIENT_COUNT];
+
+    for tick in 0..TICKS {
+        let offset = tick as f64 * 5.0;
+
+        for (i, (client_ent, _)) in clients.iter().enumerate() {
+            let x = (i % 8) as f64 * 12.0 - 48.0 + offset;
+            let z = (i / 8) as f64 * 24.0 - 48.0;
+
+            scenario
+                .app
+                .world
+                .get_mut::<Position>(*client_ent)
+                .unwrap()
+                .set([x, 0.0, z]);
+        }
+
+        for (i, cow) in cows.iter().enumerate() {
+            // Every other cow walks in the opposite direction.
+            let dir = if i % 2 == 0 { 1.0 } else { -1.0 };
+            let x = (i % 16) as f64 * 8.0 - 64.0;
+            let z = (i / 16) as f64 * 16.0 - 32.0 + offset * dir;
+
+            scenario
+                .app
+                .world
+                .get_mut::<Position>(*cow)
+                .unwrap()
+                .set([x, 0.0, z]);
+        }
+
+        scenario.tick();
+
+        for (i, (_, conn)) in clients.iter_mut().enumerate() {
+            let bytes = conn.take_sent();
+
+            assert!(!bytes.is_empty(), "nothing was sent to client {i}");
+            digests[i].push(digest(bytes));
+        }
+    }
+
+    digests
+}
+
+/// Hashes the packets sent to a client in a tick. Systems which aren't ordered
+/// relative to each other can write their packets in a different order in
+/// every process, so the order of the packets is ignored. Keepalive packets
+/// are left out because their IDs are random.
+fn digest(bytes: BytesMut) -> u64 {
+    let mut dec = PacketDecoder::new();
+    dec.queue_bytes(bytes);
+
+    let mut frames = vec![];
+
+    while let Some(frame) = dec.try_next_packet().unwrap() {
+        let mut r = &frame[..];
+
+        if VarInt::decode_partial(&mut r).unwrap() != KeepAliveS2c::PACKET_ID {
+            frames.push(frame);
+        }
+    }
+
+    frames.sort_unstable();
+
+    // The keys of the default hasher are fixed, so the digests of both
+    // processes can be compared.
+    let mut hasher = DefaultHasher::new();
+    frames.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Runs the scenario with a single thread in the compute task pool, which makes
+/// the parallel queries iterate sequentially. The task pool is global and is
+/// already initialized by the other tests, so this only does anything in the
+/// child process started by [`parallel_client_updates_match_sequential`].
+#[test]
+fn sequential_broadcast_scenario() {
+    let Some(path) = std::env::var_os(SEQUENTIAL_OUTPUT_VAR) else {
+        return;
+    };
+
+    let pool = ComputeTaskPool::init(|| TaskPoolBuilder::new().num_threads(1).build());
+    assert_eq!(pool.thread_num(), 1);
+
+    let output: String = run_broadcast_scenario()
+        .iter()
+        .map(|digests| {
+            let line: Vec<_> = digests.iter().map(u64::to_string).collect();
+            line.join(" ") + "\n"
+        })
+        .collect();
+
+    std::fs::write(path, output).unwrap();
+}
+
+#[test]
+fn parallel_client_updates_match_sequential() {
+    // The clients are updated in parallel, so the order in which they are
+    // processed differs between runs. The packets sent to every client must be
+    // the same as when they are updated one after another.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sequential");
+
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args([
+            "tests::broadcast::sequential_broadcast_scenario",
+            "--exact",
+            "--test-threads=1",
+        ])
+        .env(SEQUENTIAL_OUTPUT_VAR, &path)
+        .status()
+        .unwrap();
+
+    assert!(status.success(), "sequential run failed");
+
+    let sequential: Vec<Vec<u64>> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| line.split(' ').map(|d| d.parse().unwrap()).collect())
+        .collect();
+
+    let parallel = run_broadcast_scenario();
+
+    assert_eq!(parallel.len(), sequential.len());
+
+    for (i, (parallel, sequential)) in parallel.iter().zip(&sequential).enumerate() {
+        for (tick, (p, s)) in parallel.iter().zip(sequential).enumerate() {
+            assert_eq!(
+                p, s,
+                "packets sent to client {i} in tick {tick} differ from the sequential run"
+            );
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use valence_client::camera::{Camera, SpectatorTeleport};
+use valence_core::despawn::Despawned;
+use valence_core::game_mode::GameMode;
+use valence_core::packet::c2s::play::SpectatorTeleportC2s;
+use valence_core::packet::s2c::play::{S2cPlayPacket, SetCameraEntityS2c};
+use valence_core::packet::var_int::VarInt;
+use valence_entity::cow::CowEntityBundle;
+use valence_entity::EntityId;
+
+use super::*;
+
+#[test]
+fn test_camera_target() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    let cow_ent = app
+        .world
+        .spawn(CowEntityBundle {
+            location: Location(instance_ent),
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+
+    let cow_id = app.world.get::<EntityId>(cow_ent).unwrap().get();
+
+    client_helper.clear_sent();
+
+    *app.world.get_mut::<Camera>(client_ent).unwrap() =
+        Camera::new(Some(cow_ent)).with_force_spectator(true);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::SetCameraEntityS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::SetCameraEntityS2c(pkt) = pkt {
+            assert_eq!(pkt.entity_id.0, cow_id);
+        }
+    }
+
+    assert_eq!(
+        *app.world.get::<GameMode>(client_ent).unwrap(),
+        GameMode::Spectator
+    );
+
+    // Nothing is sent while the camera does not change.
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::SetCameraEntityS2c(_));
+
+    // The camera is reset when the target is despawned.
+    app.world.entity_mut(cow_ent).insert(Despawned);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::SetCameraEntityS2c(SetCameraEntityS2c {
+            entity_id: VarInt(0)
+        })
+    );
+    assert_eq!(app.world.get::<Camera>(client_ent).unwrap().target, None);
+    assert_eq!(
+        *app.world.get::<GameMode>(client_ent).unwrap(),
+        GameMode::Survival
+    );
+}
+
+#[test]
+fn test_spectator_teleport() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    let target = Uuid::from_u128(42);
+
+    client_helper.send(&SpectatorTeleportC2s { target });
+
+    app.update();
+
+    let events = app.world.resource::<Events<SpectatorTeleport>>();
+    let events = events.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(
+        events,
+        [&SpectatorTeleport {
+            client: client_ent,
+            target
+        }]
+    );
+}
@@ -0,0 +1,558 @@
+use bevy_app::{App, CoreSchedule};
+use bevy_ecs::schedule::ExecutorKind;
+use rsa::pkcs8::EncodePublicKey;
+use rsa::{PaddingScheme, RsaPrivateKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use valence_chat::{
+    ChatSettings, ChatState, ChatVerificationError, ChatVerificationFailure, SignedChatMessage,
+};
+use valence_client::misc::ChatMessage;
+use valence_client::Client;
+use valence_core::chat::{ChatSender, ChatType};
+use valence_core::packet::c2s::play::{ChatMessageC2s, PlayerSessionC2s};
+use valence_core::packet::s2c::play::{ChatMessageS2c, GameMessageS2c, S2cPlayPacket};
+use valence_core::uuid::UniqueId;
+use valence_nbt::{List, Value};
+use valence_registry::{ChatDecoration, ChatParameter, ChatTypeEntry, RegistryCodec};
+
+use super::*;
+
+fn send_chat_message(client_helper: &mut MockClient, message: &str) {
+    client_helper.send(&ChatMessageC2s {
+        message,
+        timestamp: 0,
+        salt: 0,
+        signature: None,
+        message_count: VarInt(0),
+        acknowledgement: [0; 3],
+    });
+}
+
+fn chat_message_count(app: &App) -> usize {
+    app.world
+        .resource::<Events<ChatMessage>>()
+        .iter_current_update_events()
+        .count()
+}
+
+#[test]
+fn test_send_chat_messages() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    let mut client = app.world.get_mut::<Client>(client_ent).unwrap();
+
+    client.send_system_message("system");
+    client.send_overlay_message("overlay");
+    client.send_player_chat(
+        &ChatSender::new(Uuid::nil(), "Steve"),
+        "chat",
+        ChatType::EMOTE_COMMAND,
+    );
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::GameMessageS2c(GameMessageS2c { overlay: false, .. }),
+        S2cPlayPacket::GameMessageS2c(GameMessageS2c { overlay: true, .. }),
+        S2cPlayPacket::ChatMessageS2c(ChatMessageS2c {
+            message: "chat",
+            chat_type: VarInt(1),
+            unsigned_content: None,
+            ..
+        })
+    );
+}
+
+#[test]
+fn custom_chat_type() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let shout = app
+        .world
+        .resource_mut::<RegistryCodec>()
+        .add_chat_type(
+            ident!("custom:shout"),
+            ChatTypeEntry::new(ChatDecoration::new(
+                "%s shouts: %s",
+                [ChatParameter::Sender, ChatParameter::Content],
+            )),
+        )
+        .unwrap();
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    let Some(S2cPlayPacket::GameJoinS2c(game_join)) = sent_packets.first() else {
+        panic!("missing game join packet");
+    };
+
+    let Some(Value::List(List::Compound(chat_types))) = game_join
+        .registry_codec
+        .get("minecraft:chat_type")
+        .and_then(Value::as_compound)
+        .and_then(|reg| reg.get("value"))
+    else {
+        panic!("missing chat type registry");
+    };
+
+    assert!(chat_types.iter().any(|value| {
+        value.get("name") == Some(&Value::String("custom:shout".into()))
+            && value.get("id") == Some(&Value::Int(shout.0))
+    }));
+
+    client_helper.clear_sent();
+
+    app.world
+        .get_mut::<Client>(client_ent)
+        .unwrap()
+        .send_player_chat(&ChatSender::new(Uuid::nil(), "Steve"), "hi", shout);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ChatMessageS2c(ChatMessageS2c {
+            chat_type: VarInt(7),
+            ..
+        })
+    );
+}
+
+#[test]
+fn test_valid_chat_message() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    send_chat_message(&mut client_helper, "Hello, world!");
+
+    app.update();
+
+    assert_eq!(chat_message_count(&app), 1);
+    assert!(app.world.get::<Client>(client_ent).is_some());
+}
+
+#[test]
+fn test_chat_message_with_illegal_characters() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    send_chat_message(&mut client_helper, "§cHello");
+
+    app.update();
+
+    assert_eq!(chat_message_count(&app), 0);
+    assert!(app.world.get::<Client>(client_ent).is_none());
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::DisconnectS2c(_));
+}
+
+#[test]
+fn test_chat_message_too_long() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    send_chat_message(&mut client_helper, &"a".repeat(257));
+
+    app.update();
+
+    assert_eq!(chat_message_count(&app), 0);
+    assert!(app.world.get::<Client>(client_ent).is_none());
+}
+
+/// A client with a chat session, which signs its chat messages.
+struct SigningClient {
+    uuid: Uuid,
+    session_id: Uuid,
+    key: RsaPrivateKey,
+    /// The signature of the public key, made with the session key standing in
+    /// for the one of Mojang.
+    key_signature: Vec<u8>,
+    index: i32,
+}
+
+impl SigningClient {
+    fn new(app: &App, client_ent: Entity, session_key: &RsaPrivateKey) -> Self {
+        let uuid = app.world.get::<UniqueId>(client_ent).unwrap().0;
+        let key = RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap();
+        let public_key = key.to_public_key().to_public_key_der().unwrap();
+
+        let hash = Sha1::new()
+            .chain_update(uuid.as_bytes())
+            .chain_update(i64::MAX.to_be_bytes())
+            .chain_update(public_key.as_bytes())
+            .finalize();
+
+        let key_signature = session_key
+            .sign(PaddingScheme::new_pkcs1v15_sign::<Sha1>(), &hash)
+            .unwrap();
+
+        Self {
+            uuid,
+            session_id: Uuid::from_u128(rand::random()),
+            key,
+            key_signature,
+            index: 0,
+        }
+    }
+
+    fn send_session(&self, client_helper: &mut MockClient) {
+        let public_key = self.key.to_public_key().to_public_key_der().unwrap();
+
+        client_helper.send(&PlayerSessionC2s {
+            session_id: self.session_id,
+            expires_at: i64::MAX,
+            public_key_data: public_key.as_bytes(),
+            key_signature: &self.key_signature,
+        });
+    }
+
+    /// Signs and sends a message. `last_seen` are the signatures of the
+    /// messages received since the last message, which move the window forward
+    /// and are acknowledged at the end of it.
+    fn send_message(
+        &mut self,
+        client_helper: &mut MockClient,
+        message: &str,
+        last_seen: &[[u8; 256]],
+    ) {
+        let timestamp = 1_000_000;
+        let salt = 42_u64;
+
+        let mut hasher = Sha256::new()
+            .chain_update(1_i32.to_be_bytes())
+            .chain_update(self.uuid.as_bytes())
+            .chain_update(self.session_id.as_bytes())
+            .chain_update(self.index.to_be_bytes())
+            .chain_update(salt.to_be_bytes())
+            .chain_update((timestamp / 1000_u64).to_be_bytes())
+            .chain_update((message.len() as i32).to_be_bytes())
+            .chain_update(message.as_bytes())
+            .chain_update((last_seen.len() as i32).to_be_bytes());
+
+        for sig in last_seen {
+            hasher.update(sig);
+        }
+
+        let signature: [u8; 256] = self
+            .key
+            .sign(
+                PaddingScheme::new_pkcs1v15_sign::<Sha256>(),
+                &hasher.finalize(),
+            )
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let acknowledged = ((1_u32 << last_seen.len()) - 1) << (20 - last_seen.len());
+        let [a, b, c, _] = acknowledged.to_le_bytes();
+
+        client_helper.send(&ChatMessageC2s {
+            message,
+            timestamp,
+            salt,
+            signature: Some(&signature),
+            message_count: VarInt(last_seen.len() as i32),
+            acknowledgement: [a, b, c],
+        });
+
+        self.index += 1;
+    }
+}
+
+/// Generates a key to sign the keys of players with instead of the one of
+/// Mojang.
+fn session_key() -> RsaPrivateKey {
+    RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap()
+}
+
+/// Enables secure chat and returns the key the keys of players have to be
+/// signed with.
+fn enable_secure_chat(app: &mut App) -> RsaPrivateKey {
+    let key = session_key();
+
+    let mut settings = app.world.resource_mut::<ChatSettings>();
+    settings.secure = true;
+    settings.session_public_key = Some(key.to_public_key());
+
+    key
+}
+
+fn signed_messages(app: &App) -> Vec<SignedChatMessage> {
+    app.world
+        .resource::<Events<SignedChatMessage>>()
+        .iter_current_update_events()
+        .cloned()
+        .collect()
+}
+
+fn verification_errors(app: &App) -> Vec<ChatVerificationError> {
+    app.world
+        .resource::<Events<ChatVerificationFailure>>()
+        .iter_current_update_events()
+        .map(|event| event.error)
+        .collect()
+}
+
+#[test]
+fn test_secure_chat_disabled_by_default() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    let mut signer = SigningClient::new(&app, client_ent, &session_key());
+    signer.send_session(&mut client_helper);
+    signer.send_message(&mut client_helper, "Hello", &[]);
+
+    app.update();
+
+    assert!(signed_messages(&app).is_empty());
+    assert!(verification_errors(&app).is_empty());
+    assert_eq!(chat_message_count(&app), 1);
+    assert!(app
+        .world
+        .get::<ChatState>(client_ent)
+        .unwrap()
+        .session()
+        .is_none());
+}
+
+#[test]
+fn test_signed_chat_message() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let session_key = enable_secure_chat(&mut app);
+    app.update();
+    client_helper.clear_sent();
+
+    let mut signer = SigningClient::new(&app, client_ent, &session_key);
+    signer.send_session(&mut client_helper);
+
+    app.update();
+
+    // The session is sent to the player list of the clients.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_eq!(
+        sent_packets
+            .iter()
+            .filter(|pkt| matches!(
+                pkt,
+                S2cPlayPacket::PlayerListS2c(pkt) if pkt.actions.initialize_chat()
+            ))
+            .count(),
+        1
+    );
+    assert!(app
+        .world
+        .get::<ChatState>(client_ent)
+        .unwrap()
+        .session()
+        .is_some());
+
+    signer.send_message(&mut client_helper, "Hello", &[]);
+
+    app.update();
+
+    let messages = signed_messages(&app);
+
+    assert!(verification_errors(&app).is_empty());
+    assert_eq!(messages.len(), 1);
+    assert_eq!(&*messages[0].message, "Hello");
+    assert_eq!(messages[0].index, 0);
+
+    // Relay the message back to the client.
+    client_helper.clear_sent();
+
+    let mut query = app.world.query::<(&mut Client, &mut ChatState)>();
+    let (mut client, mut state) = query.get_mut(&mut app.world, client_ent).unwrap();
+
+    state.write_chat_message(
+        &mut *client,
+        &messages[0],
+        &ChatSender::new(signer.uuid, "Steve"),
+        ChatType::CHAT,
+    );
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ChatMessageS2c(ChatMessageS2c {
+            index: VarInt(0),
+            message_signature: Some(_),
+            ..
+        })
+    );
+
+    // The next message references the relayed message.
+    signer.send_message(&mut client_helper, "World", &[*messages[0].signature]);
+
+    app.update();
+
+    let messages = signed_messages(&app);
+
+    assert!(verification_errors(&app).is_empty());
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].index, 1);
+    assert_eq!(messages[0].last_seen.len(), 1);
+}
+
+#[test]
+fn test_invalid_chat_signature() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let session_key = enable_secure_chat(&mut app);
+    app.update();
+
+    // Messages without a session are rejected.
+    send_chat_message(&mut client_helper, "Hello");
+
+    app.update();
+
+    assert!(signed_messages(&app).is_empty());
+    assert_eq!(
+        verification_errors(&app),
+        [ChatVerificationError::MissingSession]
+    );
+
+    let mut signer = SigningClient::new(&app, client_ent, &session_key);
+    signer.send_session(&mut client_helper);
+
+    // Skipping an index invalidates the signature and breaks the chain.
+    signer.index = 1;
+    signer.send_message(&mut client_helper, "Hello", &[]);
+    signer.send_message(&mut client_helper, "World", &[]);
+
+    app.update();
+
+    assert!(signed_messages(&app).is_empty());
+    assert_eq!(
+        verification_errors(&app),
+        [
+            ChatVerificationError::InvalidSignature,
+            ChatVerificationError::BrokenChain
+        ]
+    );
+
+    // Acknowledging messages which were never sent is rejected.
+    signer.send_session(&mut client_helper);
+    signer.index = 0;
+    signer.send_message(&mut client_helper, "Hello", &[[0; 256]]);
+
+    app.update();
+
+    assert!(signed_messages(&app).is_empty());
+    assert_eq!(
+        verification_errors(&app),
+        [ChatVerificationError::InvalidAcknowledgement]
+    );
+}
+
+#[test]
+fn test_session_signed_with_other_key() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    enable_secure_chat(&mut app);
+    app.update();
+
+    let signer = SigningClient::new(&app, client_ent, &session_key());
+    signer.send_session(&mut client_helper);
+
+    app.update();
+
+    assert_eq!(
+        verification_errors(&app),
+        [ChatVerificationError::InvalidSessionSignature]
+    );
+    assert!(app
+        .world
+        .get::<ChatState>(client_ent)
+        .unwrap()
+        .session()
+        .is_none());
+}
+
+#[test]
+#[should_panic(expected = "session_public_key")]
+fn test_secure_chat_requires_session_key() {
+    let Scenario { mut app, .. } = ScenarioBuilder::new().build();
+
+    // Panics in the multithreaded executor abort instead of unwinding.
+    app.edit_schedule(CoreSchedule::Main, |schedule| {
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    });
+
+    app.world.resource_mut::<ChatSettings>().secure = true;
+    app.update();
+}
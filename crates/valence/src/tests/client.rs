@@ -2,19 +2,35 @@ use std::collections::BTreeSet;
 
 use bevy_app::App;
 use bevy_ecs::world::EntityMut;
-use valence_client::ViewDistance;
+use glam::DVec3;
+use valence_client::settings::{Arm, ChatMode, DisplayedSkinParts};
+use valence_client::{
+    Client, Degraded, MaxViewDistance, NetworkStats, OpLevel, OverloadPolicy, SendQueueLimit,
+    ViewDistance,
+};
 use valence_core::chunk_pos::ChunkView;
-use valence_core::packet::s2c::play::{ChunkDataS2c, S2cPlayPacket, UnloadChunkS2c};
-use valence_entity::Position;
+use valence_core::packet::c2s::play::ClientSettingsC2s;
+use valence_core::packet::decode::PacketDecoder;
+use valence_core::packet::s2c::play::particle::Particle;
+use valence_core::packet::s2c::play::{
+    ChunkDataS2c, ChunkLoadDistanceS2c, EntityPositionS2c, EntityStatusS2c, MoveRelative,
+    S2cPlayPacket, UnloadChunkS2c,
+};
+use valence_core::packet::var_int::VarInt;
+use valence_entity::cow::CowEntityBundle;
+use valence_entity::{EntityId, Position};
 use valence_instance::Chunk;
 
 use super::*;
 
 #[test]
 fn client_chunk_view_change() {
-    let mut app = App::new();
-
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     let mut instance = app
         .world
@@ -38,7 +54,7 @@ fn client_chunk_view_change() {
 
     let mut loaded_chunks = BTreeSet::new();
 
-    for pkt in client_helper.collect_sent() {
+    for pkt in client_helper.collect_sent().all() {
         if let S2cPlayPacket::ChunkDataS2c(ChunkDataS2c { pos, .. }) = pkt {
             assert!(loaded_chunks.insert(pos), "({pos:?})");
         }
@@ -57,7 +73,7 @@ fn client_chunk_view_change() {
     app.update();
     let client = app.world.entity_mut(client_ent);
 
-    for pkt in client_helper.collect_sent() {
+    for pkt in client_helper.collect_sent().all() {
         match pkt {
             S2cPlayPacket::ChunkDataS2c(ChunkDataS2c { pos, .. }) => {
                 assert!(loaded_chunks.insert(pos), "({pos:?})");
@@ -74,6 +90,381 @@ fn client_chunk_view_change() {
     }
 }
 
+#[test]
+fn client_network_stats() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Send the initial packets, including chunks from the instance.
+    app.update();
+
+    let sent = client_helper.connection_mut().take_sent();
+    let sent_bytes = sent.len() as u64;
+
+    let mut dec = PacketDecoder::new();
+    dec.queue_bytes(sent);
+    let mut sent_packets = 0;
+    while dec.try_next_packet().unwrap().is_some() {
+        sent_packets += 1;
+    }
+
+    let stats = *app.world.get::<NetworkStats>(client_ent).unwrap();
+
+    assert!(sent_packets > 0);
+    assert_eq!(stats.packets_sent, sent_packets);
+    assert_eq!(stats.bytes_sent, sent_bytes);
+    // Compression is disabled, so only the length prefixes are missing.
+    assert!(stats.uncompressed_bytes_sent < sent_bytes);
+
+    app.update();
+
+    let sent_bytes = sent_bytes + client_helper.connection_mut().take_sent().len() as u64;
+
+    let stats = *app.world.get::<NetworkStats>(client_ent).unwrap();
+    assert_eq!(stats.bytes_sent, sent_bytes);
+}
+
+#[test]
+fn client_disconnected_when_send_queue_limit_exceeded() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    // The client stops reading its packets.
+    client_helper.connection_mut().stall();
+    *app.world.get_mut::<SendQueueLimit>(client_ent).unwrap() = SendQueueLimit {
+        max_bytes: 1024,
+        policy: OverloadPolicy::Disconnect,
+    };
+
+    let mut ticks = 0;
+
+    while app.world.get::<Client>(client_ent).is_some() {
+        assert!(ticks < 20, "client was not disconnected");
+        ticks += 1;
+
+        app.world
+            .get_mut::<Client>(client_ent)
+            .unwrap()
+            .send_message("a".repeat(100));
+
+        app.update();
+    }
+
+    client_helper.connection_mut().resume();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    // The messages of the last tick were dropped for the disconnect message.
+    let messages = sent_packets
+        .iter()
+        .filter(|p| matches!(p, S2cPlayPacket::GameMessageS2c(_)))
+        .count();
+
+    assert_eq!(messages, ticks - 1);
+    assert!(matches!(
+        sent_packets.last(),
+        Some(S2cPlayPacket::DisconnectS2c(_))
+    ));
+}
+
+#[test]
+fn degraded_client_skips_non_essential_packets() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    for z in -5..5 {
+        for x in -5..5 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    let near_cow = app
+        .world
+        .spawn(CowEntityBundle {
+            location: Location(instance_ent),
+            position: Position::new([8.0, 0.0, 8.0]),
+            ..Default::default()
+        })
+        .id();
+
+    let far_cow = app
+        .world
+        .spawn(CowEntityBundle {
+            location: Location(instance_ent),
+            position: Position::new([56.0, 0.0, 8.0]),
+            ..Default::default()
+        })
+        .id();
+
+    let mut client = app.world.entity_mut(client_ent);
+
+    client.get_mut::<Position>().unwrap().set([8.0, 0.0, 8.0]);
+    client.get_mut::<ViewDistance>().unwrap().set(6);
+    *client.get_mut::<SendQueueLimit>().unwrap() = SendQueueLimit {
+        max_bytes: usize::MAX,
+        policy: OverloadPolicy::Degrade {
+            threshold: 0,
+            entity_distance: 1,
+        },
+    };
+
+    app.update();
+    client_helper.clear_sent();
+
+    // The client enters degraded mode once packets are stuck in the queue.
+    client_helper.connection_mut().stall();
+    app.world
+        .get_mut::<Client>(client_ent)
+        .unwrap()
+        .send_message("stuck");
+    app.update();
+
+    assert!(app.world.get::<Degraded>(client_ent).is_some());
+
+    let near_cow_id = VarInt(app.world.get::<EntityId>(near_cow).unwrap().get());
+    let far_cow_id = VarInt(app.world.get::<EntityId>(far_cow).unwrap().get());
+
+    let move_cows_and_play_particle = |app: &mut App| {
+        for cow in [near_cow, far_cow] {
+            let mut pos = app.world.get_mut::<Position>(cow).unwrap();
+            let new_pos = pos.0 + DVec3::X;
+            pos.set(new_pos);
+        }
+
+        app.world
+            .get_mut::<Instance>(instance_ent)
+            .unwrap()
+            .play_particle(&Particle::Flame, false, [8.0, 0.0, 8.0], [0.0; 3], 0.0, 1);
+
+        app.update();
+    };
+
+    move_cows_and_play_particle(&mut app);
+
+    client_helper.connection_mut().resume();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::ParticleS2c(_));
+    assert_eq!(count_moves(&sent_packets, near_cow_id), 1);
+    assert_eq!(count_moves(&sent_packets, far_cow_id), 0);
+
+    // The queue is drained, so the client leaves degraded mode and the
+    // position of the far entity is sent again.
+    app.update();
+
+    assert!(app.world.get::<Degraded>(client_ent).is_none());
+
+    move_cows_and_play_particle(&mut app);
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    let far_cow_teleports = sent_packets
+        .iter()
+        .filter(|p| {
+            matches!(
+                p,
+                S2cPlayPacket::EntityPositionS2c(EntityPositionS2c { entity_id, .. })
+                    if *entity_id == far_cow_id
+            )
+        })
+        .count();
+
+    assert_eq!(far_cow_teleports, 1);
+    assert_eq!(count_moves(&sent_packets, far_cow_id), 1);
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::ParticleS2c(_));
+}
+
+#[test]
+fn client_op_level() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    // The initial op level is sent when joining.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_eq!(op_level_statuses(&sent_packets), [24]);
+
+    // Levels above 4 are clamped.
+    app.world.get_mut::<OpLevel>(client_ent).unwrap().set(10);
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_eq!(op_level_statuses(&sent_packets), [28]);
+
+    // The op level is sent again after respawning.
+    let server = app.world.resource::<Server>();
+    let instance = Instance::new_unit_testing(ident!("overworld"), server);
+    let instance_ent = app.world.spawn(instance).id();
+
+    app.world.get_mut::<Location>(client_ent).unwrap().0 = instance_ent;
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlayerRespawnS2c(_),
+        S2cPlayPacket::EntityStatusS2c(EntityStatusS2c {
+            entity_id: 0,
+            entity_status: 28
+        })
+    );
+    assert_eq!(op_level_statuses(&sent_packets), [28]);
+}
+
+#[test]
+fn client_max_view_distance() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    for z in -15..15 {
+        for x in -15..15 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    app.update();
+    client_helper.clear_sent();
+
+    // The view distance requested by the client is used when it is allowed.
+    client_helper.send(&ClientSettingsC2s {
+        locale: "en_us",
+        view_distance: 10,
+        chat_mode: ChatMode::Enabled,
+        chat_colors: true,
+        displayed_skin_parts: DisplayedSkinParts::new(),
+        main_arm: Arm::Right,
+        enable_text_filtering: false,
+        allow_server_listings: true,
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_eq!(app.world.get::<ViewDistance>(client_ent).unwrap().get(), 10);
+    assert_eq!(chunk_load_distances(&sent_packets), [10]);
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::UnloadChunkS2c(_));
+    assert!(sent_packets
+        .iter()
+        .any(|p| matches!(p, S2cPlayPacket::ChunkDataS2c(_))));
+
+    // Lowering the maximum unloads the chunks out of view.
+    app.world
+        .get_mut::<MaxViewDistance>(client_ent)
+        .unwrap()
+        .set(4);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_eq!(app.world.get::<ViewDistance>(client_ent).unwrap().get(), 4);
+    assert_eq!(chunk_load_distances(&sent_packets), [4]);
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::ChunkDataS2c(_));
+    assert!(sent_packets
+        .iter()
+        .any(|p| matches!(p, S2cPlayPacket::UnloadChunkS2c(_))));
+
+    // Changes within a tick settle on the final value.
+    let mut max_view_dist = app.world.get_mut::<MaxViewDistance>(client_ent).unwrap();
+    max_view_dist.set(16);
+    max_view_dist.set(4);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert!(chunk_load_distances(&sent_packets).is_empty());
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::ChunkDataS2c(_));
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::UnloadChunkS2c(_));
+}
+
+/// Returns the view distances sent to the client.
+fn chunk_load_distances(sent_packets: &[S2cPlayPacket]) -> Vec<i32> {
+    sent_packets
+        .iter()
+        .filter_map(|p| match p {
+            S2cPlayPacket::ChunkLoadDistanceS2c(ChunkLoadDistanceS2c { view_distance }) => {
+                Some(view_distance.0)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the entity statuses setting the op level of the client.
+fn op_level_statuses(sent_packets: &[S2cPlayPacket]) -> Vec<u8> {
+    sent_packets
+        .iter()
+        .filter_map(|p| match p {
+            S2cPlayPacket::EntityStatusS2c(EntityStatusS2c {
+                entity_id: 0,
+                entity_status,
+            }) if (24..=28).contains(entity_status) => Some(*entity_status),
+            _ => None,
+        })
+        .collect()
+}
+
+fn count_moves(sent_packets: &[S2cPlayPacket], id: VarInt) -> usize {
+    sent_packets
+        .iter()
+        .filter(|p| {
+            matches!(
+                p,
+                S2cPlayPacket::MoveRelative(MoveRelative { entity_id, .. }) if *entity_id == id
+            )
+        })
+        .count()
+}
+
 fn view(client: &EntityMut) -> ChunkView {
     let chunk_pos = client.get::<Position>().unwrap().chunk_pos();
     let view_dist = client.get::<ViewDistance>().unwrap().get();
@@ -0,0 +1,145 @@
+use bevy_app::App;
+use glam::IVec3;
+use valence_biome::BiomeId;
+use valence_block::{BlockState, Mirror, PropName, PropValue, Rotation};
+use valence_instance::{Block, Chunk, Clipboard};
+use valence_nbt::compound;
+
+use super::*;
+
+/// Inserts a chunk with stairs facing north at the origin, a chest next to it
+/// in the positive X direction and a biome other than the default around
+/// them.
+fn setup() -> App {
+    let Scenario { mut app, .. } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.insert_chunk([0, 0], Chunk::default());
+    instance
+        .chunk_mut([0, 0])
+        .unwrap()
+        .set_biome(0, 16, 0, BiomeId(1));
+    instance.set_block([0, 0, 0], BlockState::OAK_STAIRS);
+    instance.set_block(
+        [1, 0, 0],
+        Block::with_nbt(
+            BlockState::CHEST,
+            compound! { "CustomName" => r#"{"text":"Loot"}"# },
+        ),
+    );
+
+    app
+}
+
+#[test]
+fn clipboard_copy() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    let clipboard = Clipboard::copy(instance, [1, 1, 0], [0, 0, 0]);
+
+    assert_eq!(clipboard.size(), IVec3::new(2, 2, 1));
+    assert_eq!(clipboard.offset(), IVec3::ZERO);
+    assert_eq!(
+        clipboard.block_state([0, 0, 0]),
+        Some(BlockState::OAK_STAIRS)
+    );
+    assert_eq!(clipboard.block_state([1, 0, 0]), Some(BlockState::CHEST));
+    assert_eq!(clipboard.block_state([0, 1, 0]), Some(BlockState::AIR));
+    assert_eq!(clipboard.block_state([2, 0, 0]), None);
+    assert_eq!(
+        clipboard.block_entity([1, 0, 0]),
+        Some(&compound! { "CustomName" => r#"{"text":"Loot"}"# })
+    );
+    assert_eq!(clipboard.biome([0, 0, 0]), Some(BiomeId(1)));
+
+    // Positions in unloaded chunks are left out.
+    let clipboard = Clipboard::copy(instance, [15, 0, 0], [16, 0, 0]);
+
+    assert_eq!(clipboard.block_state([0, 0, 0]), Some(BlockState::AIR));
+    assert_eq!(clipboard.block_state([1, 0, 0]), None);
+}
+
+#[test]
+fn clipboard_rotate_paste_undo() {
+    let mut app = setup();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    let mut clipboard = Clipboard::copy(&instance, [0, 0, 0], [1, 1, 0]);
+    clipboard.rotate(Rotation::Clockwise90);
+
+    // The clipboard extends along the Z axis after the rotation.
+    assert_eq!(clipboard.size(), IVec3::new(1, 2, 2));
+    assert_eq!(clipboard.offset(), IVec3::ZERO);
+
+    instance.set_block([8, 1, 8], BlockState::STONE);
+
+    let undo = clipboard.paste(&mut instance, [8, 0, 8], false);
+
+    assert_eq!(
+        instance.block([8, 0, 8]).unwrap().state(),
+        BlockState::OAK_STAIRS.set(PropName::Facing, PropValue::East)
+    );
+
+    let chest = instance.block([8, 0, 9]).unwrap();
+    assert_eq!(
+        chest.state(),
+        BlockState::CHEST.set(PropName::Facing, PropValue::East)
+    );
+    assert_eq!(
+        chest.nbt(),
+        Some(&compound! { "CustomName" => r#"{"text":"Loot"}"# })
+    );
+
+    // Air isn't pasted.
+    assert_eq!(
+        instance.block([8, 1, 8]).unwrap().state(),
+        BlockState::STONE
+    );
+
+    assert_eq!(instance.chunk([0, 0]).unwrap().biome(2, 16, 2), BiomeId(1));
+
+    undo.paste(&mut instance, [8, 0, 8], true);
+
+    assert_eq!(instance.block([8, 0, 8]).unwrap().state(), BlockState::AIR);
+    assert_eq!(instance.block([8, 0, 9]).unwrap().state(), BlockState::AIR);
+    assert_eq!(
+        instance.block([8, 1, 8]).unwrap().state(),
+        BlockState::STONE
+    );
+    assert_eq!(instance.chunk([0, 0]).unwrap().biome(2, 16, 2), BiomeId(0));
+}
+
+#[test]
+fn clipboard_mirror() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    let mut clipboard = Clipboard::copy(instance, [0, 0, 0], [1, 0, 0]);
+    clipboard.mirror(Mirror::FrontBack);
+
+    assert_eq!(clipboard.offset(), IVec3::new(-1, 0, 0));
+    assert_eq!(
+        clipboard.block_state([0, 0, 0]),
+        Some(BlockState::OAK_STAIRS)
+    );
+    assert_eq!(clipboard.block_state([-1, 0, 0]), Some(BlockState::CHEST));
+    assert!(clipboard.block_entity([-1, 0, 0]).is_some());
+
+    clipboard.mirror(Mirror::LeftRight);
+
+    assert_eq!(
+        clipboard.block_state([0, 0, 0]),
+        Some(BlockState::OAK_STAIRS.set(PropName::Facing, PropValue::South))
+    );
+}
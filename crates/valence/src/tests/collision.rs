@@ -0,0 +1,202 @@
+use bevy_app::App;
+use glam::{BVec3, DVec3};
+use valence_block::{BlockState, PropName, PropValue};
+use valence_core::aabb::Aabb;
+use valence_instance::UnloadedChunkCollision;
+
+use super::*;
+
+/// Returns the bounding box of a player standing at `pos`.
+fn player_box(pos: impl Into<DVec3>) -> Aabb {
+    Aabb::from_bottom_size(pos, [0.6, 1.8, 0.6])
+}
+
+/// Loads the chunks around the origin and places some partial blocks at
+/// `y = 0` and a wall along `x = 1`.
+fn setup() -> App {
+    let Scenario { mut app, .. } = ScenarioBuilder::new().with_chunk_radius(1).build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.set_block([1, 0, 1], BlockState::FARMLAND);
+    instance.set_block([3, 0, 1], BlockState::DIRT_PATH);
+    instance.set_block(
+        [5, 0, 1],
+        BlockState::SNOW.set(PropName::Layers, PropValue::_3),
+    );
+    instance.set_block(
+        [7, 0, 1],
+        BlockState::SNOW.set(PropName::Layers, PropValue::_1),
+    );
+    instance.set_block([9, 0, 1], BlockState::OAK_FENCE);
+
+    for y in 0..2 {
+        for z in 4..8 {
+            instance.set_block([1, y, z], BlockState::STONE);
+        }
+    }
+
+    app
+}
+
+#[test]
+fn sweep_partial_blocks() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    let fall = |x| instance.sweep(player_box([x, 2.0, 1.5]), [0.0, -3.0, 0.0]);
+
+    // Farmland and dirt paths are 15/16 of a block high.
+    assert_eq!(
+        fall(1.5),
+        (
+            DVec3::new(0.0, 0.9375 - 2.0, 0.0),
+            BVec3::new(false, true, false)
+        )
+    );
+    assert_eq!(
+        fall(3.5),
+        (
+            DVec3::new(0.0, 0.9375 - 2.0, 0.0),
+            BVec3::new(false, true, false)
+        )
+    );
+
+    // Snow layers are 1/8 of a block high for each layer except the first.
+    assert_eq!(
+        fall(5.5),
+        (
+            DVec3::new(0.0, 0.25 - 2.0, 0.0),
+            BVec3::new(false, true, false)
+        )
+    );
+    assert_eq!(fall(7.5), (DVec3::new(0.0, -3.0, 0.0), BVec3::FALSE));
+
+    // Fences are 1.5 blocks high.
+    assert_eq!(
+        fall(9.5),
+        (DVec3::new(0.0, -0.5, 0.0), BVec3::new(false, true, false))
+    );
+
+    // Nothing is hit while standing on a block and walking away from it.
+    let (allowed, collisions) = instance.sweep(player_box([1.5, 0.9375, 1.5]), [0.0, 0.5, 0.0]);
+
+    assert_eq!(allowed, DVec3::new(0.0, 0.5, 0.0));
+    assert_eq!(collisions, BVec3::FALSE);
+}
+
+#[test]
+fn sweep_slides_along_walls() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    let (allowed, collisions) = instance.sweep(player_box([0.5, 0.0, 5.5]), [1.0, 0.0, 0.5]);
+
+    assert!(allowed.abs_diff_eq(DVec3::new(0.2, 0.0, 0.5), 1e-9));
+    assert_eq!(collisions, BVec3::new(true, false, false));
+
+    // Moving away from the wall.
+    let (allowed, collisions) = instance.sweep(player_box([0.5, 0.0, 5.5]), [-1.0, 0.0, 0.5]);
+
+    assert_eq!(allowed, DVec3::new(-1.0, 0.0, 0.5));
+    assert_eq!(collisions, BVec3::FALSE);
+
+    // Jumping over the wall.
+    let (allowed, collisions) = instance.sweep(player_box([0.5, 2.0, 5.5]), [1.0, 0.0, 0.0]);
+
+    assert_eq!(allowed, DVec3::new(1.0, 0.0, 0.0));
+    assert_eq!(collisions, BVec3::FALSE);
+}
+
+#[test]
+fn collides_with_blocks() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    // Standing on blocks doesn't collide with them.
+    assert!(!instance.collides(player_box([1.5, 0.9375, 1.5])));
+    assert!(instance.collides(player_box([1.5, 0.9, 1.5])));
+
+    assert!(!instance.collides(player_box([9.5, 1.5, 1.5])));
+    assert!(instance.collides(player_box([9.5, 1.25, 1.5])));
+
+    // Touching the wall.
+    assert!(!instance.collides(player_box([0.7, 0.0, 5.5])));
+    assert!(instance.collides(player_box([0.8, 0.0, 5.5])));
+
+    // The box is below the bottom of the instance.
+    assert!(!instance.collides(player_box([1.5, -70.0, 1.5])));
+}
+
+#[test]
+fn collision_in_unloaded_chunks() {
+    let mut app = setup();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    // The chunk at [1, 0] isn't loaded.
+    let aabb = player_box([20.5, 2.0, 1.5]);
+
+    assert_eq!(
+        instance.unloaded_chunk_collision(),
+        UnloadedChunkCollision::Solid
+    );
+    assert!(instance.collides(aabb));
+
+    // Blocks which already overlap the box don't stop it, but the block below
+    // it does.
+    assert_eq!(
+        instance.sweep(aabb, [0.0, -3.0, 0.0]),
+        (DVec3::ZERO, BVec3::new(false, true, false))
+    );
+
+    // Moving from a loaded chunk into an unloaded one.
+    let (allowed, collisions) = instance.sweep(player_box([15.5, 0.0, 1.5]), [2.0, 0.0, 0.0]);
+
+    assert!(allowed.abs_diff_eq(DVec3::new(0.2, 0.0, 0.0), 1e-9));
+    assert_eq!(collisions, BVec3::new(true, false, false));
+
+    instance.set_unloaded_chunk_collision(UnloadedChunkCollision::PassThrough);
+
+    assert!(!instance.collides(aabb));
+    assert_eq!(
+        instance.sweep(aabb, [0.0, -3.0, 0.0]),
+        (DVec3::new(0.0, -3.0, 0.0), BVec3::FALSE)
+    );
+}
+
+#[test]
+fn sweep_far_and_non_finite() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    let aabb = player_box([0.5, 100.0, 1.5]);
+
+    // Long movements are cut short.
+    assert_eq!(
+        instance.sweep(aabb, [0.0, 1.0e9, 0.0]),
+        (DVec3::new(0.0, 64.0, 0.0), BVec3::new(false, true, false))
+    );
+
+    // Non-finite movements are ignored.
+    assert_eq!(
+        instance.sweep(aabb, [f64::NAN, f64::INFINITY, 1.0]),
+        (DVec3::new(0.0, 0.0, 1.0), BVec3::new(true, true, false))
+    );
+
+    // Huge boxes don't check every block they cover. The blocks checked here
+    // are in unloaded chunks.
+    let huge = Aabb::new([-1.0e9, 0.0, -1.0e9], [1.0e9, 2.0, 1.0e9]);
+
+    assert!(instance.collides(huge));
+}
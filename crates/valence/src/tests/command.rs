@@ -0,0 +1,390 @@
+use std::borrow::Cow;
+
+use valence_client::{Client, OpLevel};
+use valence_command::event::{CommandExecution, CompletionRequest};
+use valence_command::{CommandNode, CommandTree, Parser, StringArg, Suggestion};
+use valence_core::ident;
+use valence_core::packet::c2s::play::{CommandExecutionC2s, RequestCommandCompletionsC2s};
+use valence_core::packet::s2c::play::command_suggestions::Match;
+use valence_core::packet::s2c::play::command_tree::NodeData;
+use valence_core::packet::s2c::play::{CommandTreeS2c, S2cPlayPacket};
+use valence_core::packet::var_int::VarInt;
+use valence_core::packet::{Decode, Encode};
+
+use super::*;
+
+fn teleport_tree() -> CommandTree {
+    CommandTree::new()
+        .with_command(
+            CommandNode::literal("teleport").requires(2).then(
+                CommandNode::argument(
+                    "target",
+                    Parser::Entity {
+                        single: true,
+                        only_players: false,
+                    },
+                )
+                .executes(),
+            ),
+        )
+        .with_command(
+            CommandNode::literal("tp")
+                .requires(2)
+                .redirect(["teleport"]),
+        )
+        .with_command(
+            CommandNode::literal("msg").then(
+                CommandNode::argument("message", Parser::String(StringArg::GreedyPhrase))
+                    .suggests(Suggestion::AskServer)
+                    .executes(),
+            ),
+        )
+}
+
+/// Returns the number of nodes in the first command tree packet.
+fn command_tree_len(sent_packets: &[S2cPlayPacket]) -> Option<usize> {
+    sent_packets.iter().find_map(|pkt| match pkt {
+        S2cPlayPacket::CommandTreeS2c(pkt) => Some(pkt.commands.len()),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_command_tree_flattening() {
+    let tree = teleport_tree();
+
+    let pkt = tree.to_packet(2).unwrap();
+
+    assert_eq!(pkt.root_index, VarInt(0));
+    assert_eq!(pkt.commands.len(), 6);
+
+    let root = &pkt.commands[0];
+    assert_eq!(root.data, NodeData::Root);
+    assert_eq!(root.children, [VarInt(1), VarInt(3), VarInt(4)]);
+
+    let teleport = &pkt.commands[1];
+    assert_eq!(teleport.data, NodeData::Literal { name: "teleport" });
+    assert_eq!(teleport.children, [VarInt(2)]);
+    assert!(!teleport.executable);
+
+    assert!(pkt.commands[2].executable);
+
+    let tp = &pkt.commands[3];
+    assert_eq!(tp.data, NodeData::Literal { name: "tp" });
+    assert_eq!(tp.redirect_node, Some(VarInt(1)));
+
+    // Commands requiring a higher op level are left out.
+    let pkt = tree.to_packet(0).unwrap();
+
+    assert_eq!(pkt.commands.len(), 3);
+    assert_eq!(pkt.commands[0].children, [VarInt(1)]);
+    assert_eq!(pkt.commands[1].data, NodeData::Literal { name: "msg" });
+
+    // Redirects to nodes which do not exist are an error.
+    let tree = CommandTree::new().with_command(CommandNode::literal("a").redirect(["b"]));
+    assert!(tree.to_packet(0).is_err());
+
+    // An empty path redirects to the root.
+    let tree =
+        CommandTree::new().with_command(CommandNode::literal("run").redirect::<[&str; 0]>([]));
+    assert_eq!(
+        tree.to_packet(0).unwrap().commands[1].redirect_node,
+        Some(VarInt(0))
+    );
+}
+
+#[test]
+fn test_command_tree_round_trip() {
+    let parsers = [
+        Parser::Bool,
+        Parser::Float {
+            min: Some(-1.5),
+            max: None,
+        },
+        Parser::Double {
+            min: None,
+            max: Some(2.5),
+        },
+        Parser::Integer {
+            min: Some(0),
+            max: Some(10),
+        },
+        Parser::Long {
+            min: None,
+            max: None,
+        },
+        Parser::String(StringArg::QuotablePhrase),
+        Parser::Entity {
+            single: false,
+            only_players: true,
+        },
+        Parser::GameProfile,
+        Parser::BlockPos,
+        Parser::ColumnPos,
+        Parser::Vec3,
+        Parser::Vec2,
+        Parser::BlockState,
+        Parser::BlockPredicate,
+        Parser::ItemStack,
+        Parser::ItemPredicate,
+        Parser::Color,
+        Parser::Component,
+        Parser::Message,
+        Parser::NbtCompoundTag,
+        Parser::NbtTag,
+        Parser::NbtPath,
+        Parser::Objective,
+        Parser::ObjectiveCriteria,
+        Parser::Operation,
+        Parser::Particle,
+        Parser::Angle,
+        Parser::Rotation,
+        Parser::ScoreboardSlot,
+        Parser::ScoreHolder {
+            allow_multiple: true,
+        },
+        Parser::Swizzle,
+        Parser::Team,
+        Parser::ItemSlot,
+        Parser::ResourceLocation,
+        Parser::Function,
+        Parser::EntityAnchor,
+        Parser::IntRange,
+        Parser::FloatRange,
+        Parser::Dimension,
+        Parser::GameMode,
+        Parser::Time,
+        Parser::ResourceOrTag {
+            registry: ident!("worldgen/biome").into(),
+        },
+        Parser::ResourceOrTagKey {
+            registry: ident!("worldgen/structure").into(),
+        },
+        Parser::Resource {
+            registry: ident!("mob_effect").into(),
+        },
+        Parser::ResourceKey {
+            registry: ident!("worldgen/feature").into(),
+        },
+        Parser::TemplateMirror,
+        Parser::TemplateRotation,
+        Parser::Heightmap,
+        Parser::Uuid,
+    ];
+
+    let mut command = CommandNode::literal("all");
+
+    for (i, parser) in parsers.into_iter().enumerate() {
+        command = command.then(
+            CommandNode::argument(format!("arg{i}"), parser)
+                .suggests(Suggestion::SummonableEntities)
+                .executes(),
+        );
+    }
+
+    let tree = teleport_tree().with_command(command);
+    let pkt = tree.to_packet(4).unwrap();
+
+    let mut buf = vec![];
+    pkt.encode(&mut buf).unwrap();
+
+    let mut r = buf.as_slice();
+    let decoded = CommandTreeS2c::decode(&mut r).unwrap();
+
+    assert!(r.is_empty());
+    assert_eq!(decoded, pkt);
+
+    // Registries are decoded with their namespace.
+    assert!(decoded.commands.iter().any(|node| matches!(
+        &node.data,
+        NodeData::Argument {
+            parser: Parser::Resource { registry },
+            ..
+        } if registry.as_str() == "minecraft:mob_effect"
+    )));
+}
+
+#[test]
+fn test_command_tree_sent_to_clients() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.world.entity_mut(client_ent).insert(teleport_tree());
+
+    // The tree is sent once the client has joined.
+    app.update();
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::CommandTreeS2c(_));
+
+    // Nothing is sent while nothing changes.
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::CommandTreeS2c(_));
+
+    // The tree is sent again when the op level changes.
+    app.world.get_mut::<OpLevel>(client_ent).unwrap().set(2);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::CommandTreeS2c(_));
+    assert_eq!(command_tree_len(&sent_packets), Some(6));
+
+    // The tree is sent again when it changes.
+    app.world
+        .get_mut::<CommandTree>(client_ent)
+        .unwrap()
+        .remove_command("msg");
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::CommandTreeS2c(_));
+    assert_eq!(command_tree_len(&sent_packets), Some(4));
+}
+
+#[test]
+fn test_command_events() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    client_helper.send(&CommandExecutionC2s {
+        command: "msg Steve hi",
+        timestamp: 1234,
+        salt: 0,
+        argument_signatures: vec![],
+        message_count: VarInt(0),
+        acknowledgement: [0; 3],
+    });
+
+    client_helper.send(&RequestCommandCompletionsC2s {
+        transaction_id: VarInt(7),
+        text: "/msg Ste",
+    });
+
+    app.update();
+
+    let executions = app.world.resource::<Events<CommandExecution>>();
+    let executions = executions.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(
+        executions,
+        [&CommandExecution {
+            client: client_ent,
+            command: "msg Steve hi".into(),
+            timestamp: 1234,
+        }]
+    );
+
+    let requests = app.world.resource::<Events<CompletionRequest>>();
+    let requests = requests.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].client, client_ent);
+    assert_eq!(requests[0].transaction_id, 7);
+    assert_eq!(requests[0].argument(), "Ste");
+    assert_eq!(requests[0].argument_start(), 5);
+    assert_eq!(requests[0].argument_len(), 3);
+}
+
+#[test]
+fn test_completion_request_argument() {
+    let request = |text: &str| CompletionRequest {
+        client: Entity::PLACEHOLDER,
+        transaction_id: 0,
+        text: text.into(),
+    };
+
+    // The name of the command starts after the slash.
+    let req = request("/tel");
+    assert_eq!(req.argument(), "tel");
+    assert_eq!(req.argument_start(), 1);
+
+    // A trailing space starts a new empty argument.
+    let req = request("/msg ");
+    assert_eq!(req.argument(), "");
+    assert_eq!(req.argument_start(), 5);
+    assert_eq!(req.argument_len(), 0);
+
+    // Positions are counted in UTF-16 code units.
+    let req = request("/msg \u{1f600} Ste\u{e9}");
+    assert_eq!(req.argument(), "Ste\u{e9}");
+    assert_eq!(req.argument_start(), 8);
+    assert_eq!(req.argument_len(), 4);
+}
+
+#[test]
+fn test_send_completions() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    // Complete the argument being typed in "/msg Ste".
+    app.world
+        .get_mut::<Client>(client_ent)
+        .unwrap()
+        .send_completions(
+            7,
+            5,
+            3,
+            vec![
+                ("Steve".into(), None),
+                ("Stella".into(), Some("A friend".into())),
+            ],
+        );
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::CommandSuggestionsS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::CommandSuggestionsS2c(pkt) = pkt {
+            assert_eq!(pkt.id, VarInt(7));
+            assert_eq!(pkt.start, VarInt(5));
+            assert_eq!(pkt.length, VarInt(3));
+            assert_eq!(
+                pkt.matches,
+                [
+                    Match {
+                        suggested_match: "Steve",
+                        tooltip: None,
+                    },
+                    Match {
+                        suggested_match: "Stella",
+                        tooltip: Some(Cow::Owned("A friend".into())),
+                    },
+                ]
+            );
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use valence_client::cooldown::Cooldowns;
+use valence_core::item::ItemKind;
+use valence_core::packet::s2c::play::{CooldownUpdateS2c, S2cPlayPacket};
+use valence_core::packet::var_int::VarInt;
+
+use super::*;
+
+#[test]
+fn test_cooldowns() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.world
+        .entity_mut(client_ent)
+        .insert(Cooldowns::default());
+
+    app.update();
+    client_helper.clear_sent();
+
+    app.world
+        .get_mut::<Cooldowns>(client_ent)
+        .unwrap()
+        .set(ItemKind::EnderPearl, 20);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::CooldownUpdateS2c(CooldownUpdateS2c {
+            cooldown_ticks: VarInt(20),
+            ..
+        })
+    );
+
+    // The cooldown is decremented every tick.
+    let cooldowns = app.world.get::<Cooldowns>(client_ent).unwrap();
+    assert_eq!(cooldowns.remaining(ItemKind::EnderPearl), 19);
+    assert_eq!(cooldowns.remaining(ItemKind::Stone), 0);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::CooldownUpdateS2c(_));
+
+    // The cooldown is sent again when the client respawns in another
+    // instance.
+    let server = app.world.resource::<Server>();
+    let instance = Instance::new_unit_testing(ident!("overworld"), server);
+    let instance_ent = app.world.spawn(instance).id();
+
+    app.world.get_mut::<Location>(client_ent).unwrap().0 = instance_ent;
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlayerRespawnS2c(_),
+        S2cPlayPacket::CooldownUpdateS2c(CooldownUpdateS2c {
+            cooldown_ticks: VarInt(18),
+            ..
+        })
+    );
+
+    // The cooldown expires.
+    for _ in 0..17 {
+        app.update();
+    }
+
+    let cooldowns = app.world.get::<Cooldowns>(client_ent).unwrap();
+    assert!(!cooldowns.is_cooling_down(ItemKind::EnderPearl));
+    assert_eq!(cooldowns.iter().count(), 0);
+}
@@ -0,0 +1,175 @@
+use bevy_ecs::system::Command;
+use glam::DVec3;
+use valence_client::death::{KillClient, RespawnClient};
+use valence_client::DeathLocation;
+use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_entity::living::Health;
+use valence_entity::{entity, Pose, Position};
+use valence_instance::Chunk;
+
+use super::*;
+
+#[test]
+fn test_kill_and_respawn_in_same_instance() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    app.world.send_event(KillClient {
+        client: client_ent,
+        killer: None,
+        message: "Oops".into(),
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::DeathMessageS2c(_));
+
+    let client = app.world.entity(client_ent);
+
+    assert_eq!(client.get::<Health>().unwrap().0, 0.0);
+    assert_eq!(client.get::<entity::Pose>().unwrap().0, Pose::Dying);
+    assert!(client.get::<DeathLocation>().unwrap().0.is_some());
+
+    // Killing a dead client does nothing.
+    app.world.send_event(KillClient {
+        client: client_ent,
+        killer: None,
+        message: "Oops again".into(),
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::DeathMessageS2c(_));
+
+    // Respawning in the same instance.
+    let instance_ent = app.world.get::<Location>(client_ent).unwrap().0;
+
+    RespawnClient {
+        client: client_ent,
+        instance: instance_ent,
+        position: DVec3::new(1.0, 2.0, 3.0),
+    }
+    .write(&mut app.world);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    // The position and health both have to arrive after the respawn, but not in
+    // any particular order relative to each other.
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlayerRespawnS2c(_),
+        S2cPlayPacket::PlayerPositionLookS2c(_)
+    );
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlayerRespawnS2c(_),
+        S2cPlayPacket::HealthUpdateS2c(_)
+    );
+
+    let client = app.world.entity(client_ent);
+
+    assert_eq!(client.get::<Health>().unwrap().0, 20.0);
+    assert_eq!(client.get::<entity::Pose>().unwrap().0, Pose::Standing);
+    assert_eq!(
+        client.get::<Position>().unwrap().0,
+        DVec3::new(1.0, 2.0, 3.0)
+    );
+}
+
+#[test]
+fn test_zero_health_kills() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    app.world.get_mut::<Health>(client_ent).unwrap().0 = 0.0;
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::DeathMessageS2c(_));
+
+    assert_eq!(
+        app.world.get::<entity::Pose>(client_ent).unwrap().0,
+        Pose::Dying
+    );
+}
+
+#[test]
+fn test_respawn_in_other_dimension() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    app.world.get_mut::<Health>(client_ent).unwrap().0 = 0.0;
+
+    app.update();
+    client_helper.clear_sent();
+
+    let server = app.world.resource::<Server>();
+    let mut instance = Instance::new_unit_testing(ident!("the_nether"), server);
+
+    for z in -5..5 {
+        for x in -5..5 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    let instance_ent = app.world.spawn(instance).id();
+
+    RespawnClient {
+        client: client_ent,
+        instance: instance_ent,
+        position: DVec3::ZERO,
+    }
+    .write(&mut app.world);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    // Chunks sent before the respawn packet would be discarded by the client.
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlayerRespawnS2c(_),
+        S2cPlayPacket::ChunkRenderDistanceCenterS2c(_),
+        S2cPlayPacket::ChunkDataS2c(_)
+    );
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::PlayerRespawnS2c(pkt) = pkt {
+            assert_eq!(pkt.dimension_type_name.as_str(), "minecraft:the_nether");
+            assert!(!pkt.copy_metadata);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use valence_client::action::{Digging, DiggingState};
+use valence_core::block_pos::BlockPos;
+use valence_core::direction::Direction;
+use valence_core::packet::c2s::play::player_action::Action;
+use valence_core::packet::c2s::play::PlayerActionC2s;
+use valence_core::packet::s2c::play::{BlockBreakingProgressS2c, S2cPlayPacket};
+use valence_core::packet::var_int::VarInt;
+use valence_entity::EntityId;
+use valence_instance::Chunk;
+
+use super::*;
+
+#[test]
+fn test_digging_distance() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&PlayerActionC2s {
+        action: Action::StartDestroyBlock,
+        position: BlockPos::new(1, 1, 1),
+        direction: Direction::Up,
+        sequence: VarInt(3),
+    });
+
+    app.update();
+
+    let events = app.world.resource::<Events<Digging>>();
+    let events = events.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(
+        events,
+        [&Digging {
+            client: client_ent,
+            position: BlockPos::new(1, 1, 1),
+            direction: Direction::Up,
+            state: DiggingState::Start,
+        }]
+    );
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::PlayerActionResponseS2c(_));
+
+    // Blocks too far away are ignored, but the action is still acknowledged.
+    client_helper.send(&PlayerActionC2s {
+        action: Action::StopDestroyBlock,
+        position: BlockPos::new(10, 0, 0),
+        direction: Direction::Up,
+        sequence: VarInt(4),
+    });
+
+    app.update();
+
+    let events = app.world.resource::<Events<Digging>>();
+
+    assert_eq!(events.iter_current_update_events().count(), 0);
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::PlayerActionResponseS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::PlayerActionResponseS2c(pkt) = pkt {
+            assert_eq!(pkt.sequence.0, 4);
+        }
+    }
+}
+
+#[test]
+fn test_break_stage() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    for z in -5..5 {
+        for x in -5..5 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    app.update();
+    client_helper.clear_sent();
+
+    let breaker = *app.world.get::<EntityId>(client_ent).unwrap();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.set_break_stage(breaker, [1, 2, 3], Some(20));
+    instance.set_break_stage(breaker, [1, 2, 3], None);
+
+    app.update();
+
+    let stages = client_helper
+        .collect_sent()
+        .of::<BlockBreakingProgressS2c>()
+        .into_iter()
+        .map(|pkt| {
+            assert_eq!(pkt.entity_id.0, breaker.get());
+            assert_eq!(pkt.position, BlockPos::new(1, 2, 3));
+            pkt.destroy_stage
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(stages, [9, 10]);
+}
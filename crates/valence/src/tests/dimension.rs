@@ -0,0 +1,171 @@
+use valence_biome::Biome;
+use valence_block::BlockState;
+use valence_core::chunk_pos::ChunkPos;
+use valence_core::packet::var_int::VarInt;
+use valence_core::packet::Decode;
+use valence_dimension::DimensionType;
+use valence_entity::Position;
+use valence_instance::Chunk;
+use valence_nbt::{List, Value};
+
+use super::*;
+
+/// Reads the entry at `idx` of a paletted container like a client would,
+/// advancing `r` past the container. Containers with more than
+/// `max_indirect_bits` bits per entry have no palette.
+fn read_paletted_container(
+    r: &mut &[u8],
+    len: usize,
+    max_indirect_bits: u8,
+    idx: usize,
+) -> anyhow::Result<u64> {
+    let bits = u8::decode(r)?;
+
+    let palette = if bits <= max_indirect_bits {
+        let palette_len = if bits == 0 { 1 } else { VarInt::decode(r)?.0 };
+
+        (0..palette_len)
+            .map(|_| Ok(VarInt::decode(r)?.0 as u64))
+            .collect::<anyhow::Result<Vec<_>>>()?
+    } else {
+        vec![]
+    };
+
+    let longs = (0..VarInt::decode(r)?.0)
+        .map(|_| i64::decode(r).map(|l| l as u64))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    if bits == 0 {
+        return Ok(palette[0]);
+    }
+
+    assert!(idx < len);
+
+    let per_long = 64 / bits as usize;
+    let entry = longs[idx / per_long] >> (idx % per_long * bits as usize) & ((1 << bits) - 1);
+
+    Ok(if palette.is_empty() {
+        entry
+    } else {
+        palette[entry as usize]
+    })
+}
+
+/// Decodes the block state at the given offsets from the data of a chunk data
+/// packet.
+fn decode_block_state(mut data: &[u8], x: usize, y: usize, z: usize) -> BlockState {
+    let r = &mut data;
+
+    for sect_y in 0..=y / 16 {
+        let _non_air_count = i16::decode(r).unwrap();
+
+        let idx = if sect_y == y / 16 {
+            x + z * 16 + y % 16 * 256
+        } else {
+            0
+        };
+
+        let block = read_paletted_container(r, 4096, 8, idx).unwrap();
+        read_paletted_container(r, 64, 3, 0).unwrap();
+
+        if sect_y == y / 16 {
+            return BlockState::from_raw(block as u16).unwrap();
+        }
+    }
+
+    unreachable!()
+}
+
+#[test]
+fn tall_custom_dimension() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.world.spawn(DimensionType {
+        name: ident!("custom:tall").into(),
+        ambient_light: 0.5,
+        has_skylight: false,
+        min_y: 0,
+        height: 1024,
+        logical_height: 1024,
+        ..Default::default()
+    });
+
+    // The default biomes and dimension types are spawned during startup.
+    app.add_startup_system(
+        move |mut commands: Commands,
+              dimensions: Query<&DimensionType>,
+              biomes: Query<&Biome>,
+              server: Res<Server>,
+              mut clients: Query<(&mut Location, &mut Position)>| {
+            let mut instance = Instance::new(ident!("custom:tall"), &dimensions, &biomes, &server);
+
+            assert_eq!(instance.section_count(), 64);
+
+            instance.insert_chunk([0, 0], Chunk::default());
+            instance.set_block([8, 900, 8], BlockState::GOLD_BLOCK);
+
+            let (mut loc, mut pos) = clients.get_mut(client_ent).unwrap();
+
+            loc.0 = commands.spawn(instance).id();
+            pos.set([8.0, 901.0, 8.0]);
+        },
+    );
+
+    // Tick
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    // The dimension type is in the registry codec sent to the client.
+    let game_join = sent_packets
+        .iter()
+        .find_map(|pkt| match pkt {
+            S2cPlayPacket::GameJoinS2c(pkt) => Some(pkt),
+            _ => None,
+        })
+        .expect("missing game join packet");
+
+    assert_eq!(game_join.dimension_type_name.as_str(), "custom:tall");
+
+    let Some(Value::List(List::Compound(dimension_types))) = game_join
+        .registry_codec
+        .get("minecraft:dimension_type")
+        .and_then(Value::as_compound)
+        .and_then(|reg| reg.get("value"))
+    else {
+        panic!("missing dimension type registry");
+    };
+
+    let tall = dimension_types
+        .iter()
+        .find(|value| value.get("name") == Some(&Value::String("custom:tall".into())))
+        .and_then(|value| value.get("element"))
+        .and_then(Value::as_compound)
+        .expect("missing custom dimension type");
+
+    assert_eq!(tall.get("height"), Some(&Value::Int(1024)));
+    assert_eq!(tall.get("min_y"), Some(&Value::Int(0)));
+
+    let chunk_data = sent_packets
+        .iter()
+        .find_map(|pkt| match pkt {
+            S2cPlayPacket::ChunkDataS2c(pkt) if pkt.pos == ChunkPos::new(0, 0) => Some(pkt),
+            _ => None,
+        })
+        .expect("missing chunk data packet");
+
+    assert_eq!(
+        decode_block_state(chunk_data.blocks_and_biomes, 8, 900, 8),
+        BlockState::GOLD_BLOCK
+    );
+    assert_eq!(
+        decode_block_state(chunk_data.blocks_and_biomes, 8, 1023, 8),
+        BlockState::AIR
+    );
+}
@@ -31,8 +31,12 @@ fn example_test_server_tick_increment() {
 /// packet to the server.
 #[test]
 fn example_test_client_position() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     // Send a packet as the client to the server.
     let packet = PositionAndOnGround {
@@ -52,8 +56,12 @@ fn example_test_client_position() {
 /// A unit test where we want to test what packets are sent to the client.
 #[test]
 fn example_test_open_inventory() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     let inventory = Inventory::new(InventoryKind::Generic3x3);
     let inventory_ent = app.world.spawn(inventory).id();
@@ -76,7 +84,8 @@ fn example_test_open_inventory() {
     app.world
         .get::<Client>(client_ent)
         .expect("client not found");
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
 
     assert_packet_count!(sent_packets, 1, S2cPlayPacket::OpenScreenS2c(_));
     assert_packet_count!(sent_packets, 1, S2cPlayPacket::InventoryS2c(_));
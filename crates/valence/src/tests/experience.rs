@@ -0,0 +1,103 @@
+use bevy_app::App;
+use valence_client::experience::{
+    points_for_level, points_to_next_level, ExperienceLevel, ExperienceProgress, ExperienceQuery,
+    TotalExperience,
+};
+use valence_core::packet::s2c::play::S2cPlayPacket;
+
+use super::*;
+
+#[test]
+fn test_experience_level_formulas() {
+    assert_eq!(points_for_level(0), 0);
+    assert_eq!(points_for_level(1), 7);
+
+    // Around the first breakpoint.
+    assert_eq!(points_to_next_level(15), 37);
+    assert_eq!(points_for_level(16), 352);
+    assert_eq!(points_for_level(17), 394);
+
+    // Around the second breakpoint.
+    assert_eq!(points_to_next_level(30), 112);
+    assert_eq!(points_for_level(31), 1507);
+    assert_eq!(points_for_level(32), 1628);
+
+    for level in 0..100 {
+        assert_eq!(
+            points_for_level(level + 1) - points_for_level(level),
+            points_to_next_level(level),
+            "level {level}"
+        );
+    }
+}
+
+#[test]
+fn test_add_experience_points() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::ExperienceBarUpdateS2c(_));
+
+    let add_points = |app: &mut App, points: i32| {
+        let mut query = app.world.query::<ExperienceQuery>();
+        query
+            .get_mut(&mut app.world, client_ent)
+            .unwrap()
+            .add_points(points);
+
+        let level = app.world.get::<ExperienceLevel>(client_ent).unwrap().0;
+        let progress = app.world.get::<ExperienceProgress>(client_ent).unwrap().0;
+
+        (level, progress)
+    };
+
+    // Reach level 16 exactly.
+    assert_eq!(add_points(&mut app, 352), (16, 0.0));
+
+    // Cross level 17 with a few points to spare.
+    let (level, progress) = add_points(&mut app, 42 + 22);
+    assert_eq!(level, 17);
+    assert!((progress - 22.0 / 47.0).abs() < 1e-4);
+
+    // Go back down across the breakpoint.
+    let (level, progress) = add_points(&mut app, -22 - 21);
+    assert_eq!(level, 16);
+    assert!((progress - 0.5).abs() < 1e-4);
+
+    // Reach level 31 from level 0.
+    add_points(&mut app, -10_000);
+    assert_eq!(add_points(&mut app, 1507), (31, 0.0));
+    assert_eq!(
+        app.world.get::<TotalExperience>(client_ent).unwrap().0,
+        1507
+    );
+
+    // Removing more points than collected stops at level 0.
+    assert_eq!(add_points(&mut app, -5000), (0, 0.0));
+    assert_eq!(app.world.get::<TotalExperience>(client_ent).unwrap().0, 0);
+
+    add_points(&mut app, 10);
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::ExperienceBarUpdateS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::ExperienceBarUpdateS2c(pkt) = pkt {
+            assert_eq!(pkt.level.0, 1);
+            assert_eq!(pkt.total_xp.0, 10);
+            assert!((pkt.bar - 3.0 / 9.0).abs() < 1e-4);
+        }
+    }
+}
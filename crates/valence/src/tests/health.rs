@@ -0,0 +1,69 @@
+use valence_client::health::{Food, Saturation};
+use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_entity::living::Health;
+
+use super::*;
+
+#[test]
+fn test_health_update_combined() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // The health is sent when joining.
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::HealthUpdateS2c(_));
+
+    // Changing all three at once sends a single packet.
+    let mut client = app.world.entity_mut(client_ent);
+    client.get_mut::<Health>().unwrap().0 = 15.0;
+    client.get_mut::<Food>().unwrap().0 = 10;
+    client.get_mut::<Saturation>().unwrap().0 = 2.5;
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::HealthUpdateS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::HealthUpdateS2c(pkt) = pkt {
+            assert_eq!(pkt.health, 15.0);
+            assert_eq!(pkt.food.0, 10);
+            assert_eq!(pkt.food_saturation, 2.5);
+        }
+    }
+
+    // Nothing is sent while nothing changes.
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::HealthUpdateS2c(_));
+
+    // Changing one of them sends the others along.
+    app.world.get_mut::<Food>(client_ent).unwrap().0 = 4;
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::HealthUpdateS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::HealthUpdateS2c(pkt) = pkt {
+            assert_eq!(pkt.health, 15.0);
+            assert_eq!(pkt.food.0, 4);
+        }
+    }
+}
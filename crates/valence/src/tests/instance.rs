@@ -0,0 +1,342 @@
+use valence_biome::BiomeId;
+use valence_block::BlockState;
+use valence_core::chunk_pos::ChunkPos;
+use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_entity::cow::CowEntityBundle;
+use valence_entity::Position;
+use valence_instance::{Block, Chunk, ChunkRetention, ChunkUnloaded, FillBlocks, FillEvents};
+use valence_nbt::compound;
+
+use super::*;
+
+#[test]
+fn fill_blocks_resends_chunks() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    for z in -2..2 {
+        for x in -2..2 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    // Tick
+    app.update();
+    client_helper.clear_sent();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    // Corners are out of order and the box extends below the instance and into
+    // unloaded chunks.
+    let res = instance.fill_blocks(
+        [10, 0, 7],
+        [-40, -100, -8],
+        BlockState::STONE,
+        FillEvents::Region,
+    );
+
+    assert_eq!(
+        res,
+        FillBlocks {
+            filled: 43 * 65 * 16,
+            skipped: 51 * 101 * 16 - 43 * 65 * 16,
+        }
+    );
+
+    assert_eq!(
+        instance.block([-32, -64, -8]).unwrap().state(),
+        BlockState::STONE
+    );
+    assert_eq!(
+        instance.block([10, 0, 7]).unwrap().state(),
+        BlockState::STONE
+    );
+    assert_eq!(instance.block([11, 0, 7]).unwrap().state(), BlockState::AIR);
+    assert_eq!(instance.block([10, 1, 7]).unwrap().state(), BlockState::AIR);
+
+    // Tick
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::BlockUpdateS2c(_));
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::ChunkDeltaUpdateS2c(_));
+    assert_packet_count!(sent_packets, 6, S2cPlayPacket::ChunkDataS2c(_));
+}
+
+#[test]
+fn block_changes_aggregated_per_section() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.insert_chunk([0, 0], Chunk::default());
+
+    // Tick
+    app.update();
+    client_helper.clear_sent();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.set_block([0, 0, 0], BlockState::STONE);
+    instance.set_block([1, 0, 0], BlockState::STONE);
+    instance.set_block(
+        [2, 0, 0],
+        Block::with_nbt(BlockState::CHEST, compound! { "Lock" => "key" }),
+    );
+    instance.set_block([0, 16, 0], BlockState::STONE);
+
+    // Tick
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::ChunkDeltaUpdateS2c(_));
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::BlockUpdateS2c(_));
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::BlockEntityUpdateS2c(_));
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::ChunkDataS2c(_));
+
+    // Block entities must be sent after the blocks they belong to.
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::ChunkDeltaUpdateS2c(_),
+        S2cPlayPacket::BlockEntityUpdateS2c(_)
+    );
+}
+
+#[test]
+fn light_updates_sent_for_viewed_chunks() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.insert_chunk([0, 0], Chunk::default());
+
+    // Tick
+    app.update();
+    client_helper.clear_sent();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.set_block([8, 0, 8], BlockState::TORCH);
+
+    // Tick
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::LightUpdateS2c(_));
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::BlockUpdateS2c(_),
+        S2cPlayPacket::LightUpdateS2c(_)
+    );
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+    let chunk = instance.chunk([0, 0]).unwrap();
+
+    // Y = 0 is section 4 of the instance.
+    assert_eq!(chunk.block_light(8, 64, 8), 14);
+    assert_eq!(chunk.block_light(8, 66, 8), 12);
+    assert_eq!(chunk.sky_light(8, 64, 8), 15);
+}
+
+#[test]
+fn lighting_disabled() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.set_lighting(false);
+    instance.insert_chunk([0, 0], Chunk::default());
+    instance.set_block([8, 0, 8], BlockState::TORCH);
+
+    // Tick
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::ChunkDataS2c(_));
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::LightUpdateS2c(_));
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    assert_eq!(instance.chunk([0, 0]).unwrap().block_light(8, 64, 8), 0);
+}
+
+#[test]
+fn biome_changes_sent_to_viewers() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    for z in -1..1 {
+        for x in -1..1 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    // Tick
+    app.update();
+    client_helper.clear_sent();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    assert_eq!(instance.set_biome([5, 2, 3], BiomeId(1)), Some(BiomeId(0)));
+    assert_eq!(instance.set_biome([5, 2, 100], BiomeId(1)), None);
+
+    // The box covers the bottom section of chunk (-1, -1) and part of the one
+    // above it.
+    instance.fill_biomes([-16, -64, -16], [-1, -45, -1], BiomeId(2));
+
+    assert_eq!(instance.biome([4, 0, 0]), Some(BiomeId(1)));
+    assert_eq!(instance.biome([7, 3, 3]), Some(BiomeId(1)));
+    assert_eq!(instance.biome([8, 3, 3]), Some(BiomeId(0)));
+    assert_eq!(instance.biome([-8, -60, -8]), Some(BiomeId(2)));
+    assert_eq!(instance.biome([-8, -45, -8]), Some(BiomeId(2)));
+    assert_eq!(instance.biome([-8, -44, -8]), Some(BiomeId(0)));
+    assert_eq!(instance.biome([0, -60, -8]), Some(BiomeId(0)));
+
+    // Tick
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 2, S2cPlayPacket::ChunkBiomeDataS2c(_));
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::ChunkDataS2c(_));
+}
+
+#[test]
+fn unviewed_chunks_unloaded() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    instance.set_chunk_retention(ChunkRetention::UnloadAfter { ticks: 3 });
+    instance.insert_chunk([0, 0], Chunk::default());
+    instance.insert_chunk([100, 100], Chunk::default());
+    instance.insert_chunk([101, 100], Chunk::default());
+    assert!(instance.pin_chunk([101, 100]));
+    assert!(!instance.pin_chunk([101, 100]));
+
+    let cow = app
+        .world
+        .spawn(CowEntityBundle {
+            location: Location(instance_ent),
+            position: Position::new([1600.0, 0.0, 1600.0]),
+            ..Default::default()
+        })
+        .id();
+
+    for _ in 0..2 {
+        app.update();
+    }
+
+    let instance = app.world.get::<Instance>(instance_ent).unwrap();
+
+    assert!(instance.chunk([100, 100]).is_some());
+    assert_eq!(instance.chunk([100, 100]).unwrap().unviewed_ticks(), 2);
+    assert_eq!(instance.chunk([0, 0]).unwrap().unviewed_ticks(), 0);
+
+    client_helper.clear_sent();
+    app.update();
+
+    let instance = app.world.get::<Instance>(instance_ent).unwrap();
+
+    // Only the unviewed chunk that is not pinned is unloaded.
+    assert!(instance.chunk([100, 100]).is_none());
+    assert!(instance.chunk([101, 100]).is_some());
+    assert!(instance.chunk([0, 0]).is_some());
+
+    let events = app.world.resource::<Events<ChunkUnloaded>>();
+    let events = events.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].instance, instance_ent);
+    assert_eq!(events[0].pos, ChunkPos::new(100, 100));
+    assert_eq!(events[0].entities, vec![cow]);
+
+    // The entity in the unloaded chunk is left alone.
+    assert!(app.world.get_entity(cow).is_some());
+
+    // No client was in view of the chunk, so no unload packet is sent.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::UnloadChunkS2c(_));
+
+    // Unpinned chunks can be unloaded again.
+    let mut instance = app.world.get_mut::<Instance>(instance_ent).unwrap();
+
+    assert!(instance.unpin_chunk([101, 100]));
+    assert!(!instance.is_chunk_pinned([101, 100]));
+
+    app.update();
+
+    let instance = app.world.get::<Instance>(instance_ent).unwrap();
+
+    assert!(instance.chunk([101, 100]).is_none());
+    assert!(instance.chunk([0, 0]).is_some());
+}
@@ -1,20 +1,31 @@
 use bevy_app::App;
+use bevy_ecs::system::CommandQueue;
+use valence_client::experience::ExperienceLevel;
 use valence_core::game_mode::GameMode;
 use valence_core::item::{ItemKind, ItemStack};
 use valence_core::packet::c2s::play::click_slot::{ClickMode, Slot};
-use valence_core::packet::c2s::play::ClickSlotC2s;
+use valence_core::packet::c2s::play::{
+    ClickSlotC2s, CraftRequestC2s, RenameItemC2s, SelectMerchantTradeC2s,
+};
 use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_entity::{item, EntityKind, Look, Position, Velocity};
 use valence_inventory::{
-    convert_to_player_slot_id, ClientInventoryState, CursorItem, DropItemStack, Inventory,
-    InventoryKind, OpenInventory,
+    convert_to_player_slot_id, item_drop_velocity, spawn_item_entity, AnvilState, ClickSlot,
+    ClientInventoryState, CreativeItemSetEvent, CursorItem, DropItemEvent, HeldItem, Inventory,
+    InventoryKind, InventorySettings, OpenInventory, Recipe, RecipeRegistry, RenameItem,
+    SelectMerchantTrade, Trade, TradeList, UpdateSelectedSlot, ITEM_DROP_HEIGHT,
 };
 
 use super::*;
 
 #[test]
 fn test_should_open_inventory() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     let inventory = Inventory::new(InventoryKind::Generic3x3);
     let inventory_ent = app.world.spawn(inventory).id();
@@ -33,7 +44,8 @@ fn test_should_open_inventory() {
     app.update();
 
     // Make assertions
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
 
     assert_packet_count!(sent_packets, 1, S2cPlayPacket::OpenScreenS2c(_));
     assert_packet_count!(sent_packets, 1, S2cPlayPacket::InventoryS2c(_));
@@ -46,8 +58,12 @@ fn test_should_open_inventory() {
 
 #[test]
 fn test_should_close_inventory() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     let inventory = Inventory::new(InventoryKind::Generic3x3);
     let inventory_ent = app.world.spawn(inventory).id();
@@ -75,15 +91,20 @@ fn test_should_close_inventory() {
     app.update();
 
     // Make assertions
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
 
     assert_packet_count!(sent_packets, 1, S2cPlayPacket::CloseScreenS2c(_));
 }
 
 #[test]
 fn test_should_remove_invalid_open_inventory() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     let inventory = Inventory::new(InventoryKind::Generic3x3);
     let inventory_ent = app.world.spawn(inventory).id();
@@ -109,14 +130,19 @@ fn test_should_remove_invalid_open_inventory() {
 
     // Make assertions
     assert!(app.world.get::<OpenInventory>(client_ent).is_none());
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
     assert_packet_count!(sent_packets, 1, S2cPlayPacket::CloseScreenS2c(_));
 }
 
 #[test]
 fn test_should_modify_player_inventory_click_slot() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     // Process a tick to get past the "on join" logic.
     app.update();
@@ -150,7 +176,8 @@ fn test_should_modify_player_inventory_click_slot() {
     app.update();
 
     // Make assertions
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
 
     // because the inventory was changed as a result of the client's click, the
     // server should not send any packets to the client because the client
@@ -177,8 +204,12 @@ fn test_should_modify_player_inventory_click_slot() {
 
 #[test]
 fn test_should_modify_player_inventory_server_side() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     // Process a tick to get past the "on join" logic.
     app.update();
@@ -202,7 +233,8 @@ fn test_should_modify_player_inventory_server_side() {
     app.update();
 
     // Make assertions
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
     // because the inventory was modified server side, the client needs to be
     // updated with the change.
     assert_packet_count!(
@@ -214,8 +246,12 @@ fn test_should_modify_player_inventory_server_side() {
 
 #[test]
 fn test_should_sync_entire_player_inventory() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     // Process a tick to get past the "on join" logic.
     app.update();
@@ -230,7 +266,8 @@ fn test_should_sync_entire_player_inventory() {
     app.update();
 
     // Make assertions
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
     assert_packet_count!(sent_packets, 1, S2cPlayPacket::InventoryS2c(_));
 }
 
@@ -250,8 +287,12 @@ fn set_up_open_inventory(app: &mut App, client_ent: Entity) -> Entity {
 
 #[test]
 fn test_should_modify_open_inventory_click_slot() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
     let inventory_ent = set_up_open_inventory(&mut app, client_ent);
     let mut inventory = app
         .world
@@ -283,7 +324,8 @@ fn test_should_modify_open_inventory_click_slot() {
     app.update();
 
     // Make assertions
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
 
     // because the inventory was modified as a result of the client's click, the
     // server should not send any packets to the client because the client
@@ -310,8 +352,12 @@ fn test_should_modify_open_inventory_click_slot() {
 
 #[test]
 fn test_should_modify_open_inventory_server_side() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
     let inventory_ent = set_up_open_inventory(&mut app, client_ent);
 
     // Process a tick to get past the "on join" logic.
@@ -328,7 +374,8 @@ fn test_should_modify_open_inventory_server_side() {
     app.update();
 
     // Make assertions
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
 
     // because the inventory was modified server side, the client needs to be
     // updated with the change.
@@ -349,8 +396,12 @@ fn test_should_modify_open_inventory_server_side() {
 
 #[test]
 fn test_should_sync_entire_open_inventory() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
     let inventory_ent = set_up_open_inventory(&mut app, client_ent);
 
     // Process a tick to get past the "on join" logic.
@@ -366,14 +417,19 @@ fn test_should_sync_entire_open_inventory() {
     app.update();
 
     // Make assertions
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
     assert_packet_count!(sent_packets, 1, S2cPlayPacket::InventoryS2c(_));
 }
 
 #[test]
 fn test_set_creative_mode_slot_handling() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
     let mut game_mode = app
         .world
         .get_mut::<GameMode>(client_ent)
@@ -406,8 +462,12 @@ fn test_set_creative_mode_slot_handling() {
 
 #[test]
 fn test_ignore_set_creative_mode_slot_if_not_creative() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
     let mut game_mode = app
         .world
         .get_mut::<GameMode>(client_ent)
@@ -435,10 +495,214 @@ fn test_ignore_set_creative_mode_slot_if_not_creative() {
     assert_eq!(inventory.slot(36), None);
 }
 
+#[test]
+fn test_set_creative_mode_slot_event() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+    app.world.entity_mut(client_ent).insert(GameMode::Creative);
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    app.world
+        .get_mut::<Inventory>(client_ent)
+        .expect("could not find inventory for client")
+        .set_slot(36, ItemStack::new(ItemKind::Stone, 1, None));
+
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(
+        &valence_core::packet::c2s::play::CreativeInventoryActionC2s {
+            slot: 36,
+            clicked_item: Some(ItemStack::new(ItemKind::Diamond, 2, None)),
+        },
+    );
+
+    app.update();
+
+    let events = app
+        .world
+        .get_resource::<Events<CreativeItemSetEvent>>()
+        .expect("expected creative item set events")
+        .iter_current_update_events()
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].client, client_ent);
+    assert_eq!(events[0].slot, 36);
+    assert_eq!(
+        events[0].previous,
+        Some(ItemStack::new(ItemKind::Stone, 1, None))
+    );
+    assert_eq!(
+        events[0].item,
+        Some(ItemStack::new(ItemKind::Diamond, 2, None))
+    );
+}
+
+#[test]
+fn test_veto_set_creative_mode_slot() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+    app.world.entity_mut(client_ent).insert(GameMode::Creative);
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(
+        &valence_core::packet::c2s::play::CreativeInventoryActionC2s {
+            slot: 36,
+            clicked_item: Some(ItemStack::new(ItemKind::Bedrock, 1, None)),
+        },
+    );
+
+    app.update();
+    client_helper.clear_sent();
+
+    // Veto the change by restoring the previous item.
+    let events = app
+        .world
+        .get_resource::<Events<CreativeItemSetEvent>>()
+        .expect("expected creative item set events")
+        .iter_current_update_events()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+
+    let mut inventory = app
+        .world
+        .get_mut::<Inventory>(client_ent)
+        .expect("could not find inventory for client");
+    inventory.set_slot(events[0].slot, events[0].previous.clone());
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ScreenHandlerSlotUpdateS2c(_)
+    );
+
+    let inventory = app
+        .world
+        .get::<Inventory>(client_ent)
+        .expect("could not find inventory for client");
+    assert_eq!(inventory.slot(36), None);
+}
+
+#[test]
+fn test_reject_creative_mode_slot_over_max_stack() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+    app.world.entity_mut(client_ent).insert(GameMode::Creative);
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(
+        &valence_core::packet::c2s::play::CreativeInventoryActionC2s {
+            slot: 36,
+            clicked_item: Some(ItemStack::new(ItemKind::DiamondSword, 64, None)),
+        },
+    );
+
+    app.update();
+
+    // The slot is resent to the client.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ScreenHandlerSlotUpdateS2c(_)
+    );
+
+    let inventory = app
+        .world
+        .get::<Inventory>(client_ent)
+        .expect("could not find inventory for client");
+    assert_eq!(inventory.slot(36), None);
+
+    let events = app
+        .world
+        .get_resource::<Events<CreativeItemSetEvent>>()
+        .expect("expected creative item set events");
+    assert!(events.iter_current_update_events().next().is_none());
+}
+
+#[test]
+fn test_strip_creative_mode_slot_nbt_over_limit() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+    app.world.entity_mut(client_ent).insert(GameMode::Creative);
+    app.world
+        .resource_mut::<InventorySettings>()
+        .max_creative_nbt_size = Some(64);
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    let small = ItemStack::new(ItemKind::Diamond, 1, None).with_name("Gem");
+    let large = ItemStack::new(ItemKind::Diamond, 1, None).with_name("a".repeat(100));
+
+    client_helper.send(
+        &valence_core::packet::c2s::play::CreativeInventoryActionC2s {
+            slot: 36,
+            clicked_item: Some(small.clone()),
+        },
+    );
+    client_helper.send(
+        &valence_core::packet::c2s::play::CreativeInventoryActionC2s {
+            slot: 37,
+            clicked_item: Some(large),
+        },
+    );
+
+    app.update();
+
+    let inventory = app
+        .world
+        .get::<Inventory>(client_ent)
+        .expect("could not find inventory for client");
+    assert_eq!(inventory.slot(36), Some(&small));
+    assert_eq!(
+        inventory.slot(37),
+        Some(&ItemStack::new(ItemKind::Diamond, 1, None))
+    );
+}
+
 #[test]
 fn test_window_id_increments() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
     let inventory = Inventory::new(InventoryKind::Generic9x3);
     let inventory_ent = app.world.spawn(inventory).id();
 
@@ -472,133 +736,417 @@ fn test_window_id_increments() {
 }
 
 #[test]
-fn test_should_handle_set_held_item() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+fn test_should_replace_open_inventory() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+    let chest_ent = app
+        .world
+        .spawn(Inventory::new(InventoryKind::Generic9x3))
+        .id();
+    let furnace_ent = app.world.spawn(Inventory::new(InventoryKind::Furnace)).id();
 
     // Process a tick to get past the "on join" logic.
     app.update();
+
+    app.world
+        .entity_mut(client_ent)
+        .insert(OpenInventory::new(chest_ent));
+
+    app.update();
     client_helper.clear_sent();
 
-    client_helper.send(&valence_core::packet::c2s::play::UpdateSelectedSlotC2s { slot: 4 });
+    // Open another inventory without closing the first one.
+    app.world
+        .entity_mut(client_ent)
+        .insert(OpenInventory::new(furnace_ent));
 
     app.update();
 
-    // Make assertions
-    let inv_state = app
-        .world
-        .get::<ClientInventoryState>(client_ent)
-        .expect("could not find client");
-    assert_eq!(inv_state.held_item_slot(), 40);
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::OpenScreenS2c(_));
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::CloseScreenS2c(_));
+
+    let inv_state = app.world.get::<ClientInventoryState>(client_ent).unwrap();
+    assert_eq!(inv_state.window_id(), 2);
+
+    // Close the first inventory and open another one in the same tick.
+    let mut client = app.world.entity_mut(client_ent);
+    client.remove::<OpenInventory>();
+    client.insert(OpenInventory::new(chest_ent));
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::OpenScreenS2c(_));
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::CloseScreenS2c(_));
+
+    let inv_state = app.world.get::<ClientInventoryState>(client_ent).unwrap();
+    assert_eq!(inv_state.window_id(), 3);
 }
 
 #[test]
-fn should_not_increment_state_id_on_cursor_item_change() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+fn test_should_handle_client_closing_inventory() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+    let inventory_ent = app
+        .world
+        .spawn(Inventory::new(InventoryKind::Generic9x3))
+        .id();
 
     // Process a tick to get past the "on join" logic.
     app.update();
+
+    app.world
+        .entity_mut(client_ent)
+        .insert(OpenInventory::new(inventory_ent));
+
+    app.update();
     client_helper.clear_sent();
 
-    let inv_state = app
+    let window_id = app
         .world
         .get::<ClientInventoryState>(client_ent)
-        .expect("could not find client");
-    let expected_state_id = inv_state.state_id().0;
+        .unwrap()
+        .window_id();
 
-    let mut cursor_item = app.world.get_mut::<CursorItem>(client_ent).unwrap();
-    cursor_item.0 = Some(ItemStack::new(ItemKind::Diamond, 2, None));
+    // Closing a window which is not open anymore is ignored.
+    client_helper.send(&valence_core::packet::c2s::play::CloseHandledScreenC2s {
+        window_id: window_id as i8 - 1,
+    });
 
     app.update();
 
-    // Make assertions
-    let inv_state = app
-        .world
-        .get::<ClientInventoryState>(client_ent)
-        .expect("could not find client");
-    assert_eq!(
-        inv_state.state_id().0,
-        expected_state_id,
-        "state id should not have changed"
-    );
-}
+    assert!(app.world.get::<OpenInventory>(client_ent).is_some());
 
-mod dropping_items {
-    use valence_core::block_pos::BlockPos;
-    use valence_core::direction::Direction;
-    use valence_core::packet::c2s::play::click_slot::{ClickMode, Slot};
-    use valence_core::packet::c2s::play::player_action::Action;
-    use valence_inventory::convert_to_player_slot_id;
+    client_helper.send(&valence_core::packet::c2s::play::CloseHandledScreenC2s {
+        window_id: window_id as i8,
+    });
 
-    use super::*;
+    app.update();
+    app.update();
 
-    #[test]
-    fn should_drop_item_player_action() {
-        let mut app = App::new();
-        let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    assert!(app.world.get::<OpenInventory>(client_ent).is_none());
 
-        // Process a tick to get past the "on join" logic.
-        app.update();
-        client_helper.clear_sent();
+    // The client closed the window itself, so it isn't closed again.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::CloseScreenS2c(_));
+}
 
-        let mut inventory = app
-            .world
-            .get_mut::<Inventory>(client_ent)
-            .expect("could not find inventory");
-        inventory.set_slot(36, ItemStack::new(ItemKind::IronIngot, 3, None));
+#[test]
+fn test_should_send_window_properties() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+    let inventory_ent = app.world.spawn(Inventory::new(InventoryKind::Furnace)).id();
 
-        client_helper.send(&valence_core::packet::c2s::play::PlayerActionC2s {
-            action: Action::DropItem,
-            position: BlockPos::new(0, 0, 0),
-            direction: Direction::Down,
-            sequence: VarInt(0),
-        });
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
 
-        app.update();
+    let mut open_inventory = OpenInventory::new(inventory_ent);
+    open_inventory.set_property(0, 200);
+    open_inventory.set_property(1, 200);
+    app.world.entity_mut(client_ent).insert(open_inventory);
 
-        // Make assertions
-        let inventory = app
-            .world
-            .get::<Inventory>(client_ent)
-            .expect("could not find client");
-        assert_eq!(
-            inventory.slot(36),
-            Some(&ItemStack::new(ItemKind::IronIngot, 2, None))
-        );
-        let events = app
-            .world
-            .get_resource::<Events<DropItemStack>>()
-            .expect("expected drop item stack events");
-        let events = events.iter_current_update_events().collect::<Vec<_>>();
-        assert_eq!(events.len(), 1);
-        assert_eq!(events[0].client, client_ent);
-        assert_eq!(events[0].from_slot, Some(36));
-        assert_eq!(
-            events[0].stack,
-            ItemStack::new(ItemKind::IronIngot, 1, None)
-        );
+    app.update();
 
-        let sent_packets = client_helper.collect_sent();
-        assert_packet_count!(
-            sent_packets,
-            0,
-            S2cPlayPacket::ScreenHandlerSlotUpdateS2c(_)
-        );
-    }
+    // Properties set before opening are sent with the window.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(
+        sent_packets,
+        2,
+        S2cPlayPacket::ScreenHandlerPropertyUpdateS2c(_)
+    );
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::OpenScreenS2c(_),
+        S2cPlayPacket::ScreenHandlerPropertyUpdateS2c(_)
+    );
 
-    #[test]
-    fn should_drop_item_stack_player_action() {
-        let mut app = App::new();
-        let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let mut open_inventory = app.world.get_mut::<OpenInventory>(client_ent).unwrap();
+    open_inventory.set_property(0, 200);
+    open_inventory.set_property(2, 50);
+    assert_eq!(open_inventory.property(2), 50);
+    assert_eq!(open_inventory.property(3), 0);
 
-        // Process a tick to get past the "on join" logic.
-        app.update();
-        client_helper.clear_sent();
+    app.update();
 
-        let mut inventory = app
-            .world
-            .get_mut::<Inventory>(client_ent)
+    // Only the changed property is sent.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ScreenHandlerPropertyUpdateS2c(_)
+    );
+
+    let window_id = app
+        .world
+        .get::<ClientInventoryState>(client_ent)
+        .unwrap()
+        .window_id();
+
+    for pkt in sent_packets {
+        if let S2cPlayPacket::ScreenHandlerPropertyUpdateS2c(pkt) = pkt {
+            assert_eq!(pkt.window_id, window_id);
+            assert_eq!(pkt.property, 2);
+            assert_eq!(pkt.value, 50);
+        }
+    }
+}
+
+#[test]
+fn test_should_handle_set_held_item() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&valence_core::packet::c2s::play::UpdateSelectedSlotC2s { slot: 4 });
+
+    app.update();
+
+    // Make assertions
+    let held = app
+        .world
+        .get::<HeldItem>(client_ent)
+        .expect("could not find client");
+    assert_eq!(held.slot(), 40);
+    assert_eq!(selected_slot_events(&app), [4]);
+
+    // The slot is not sent back to the client.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::UpdateSelectedSlotS2c(_));
+}
+
+fn selected_slot_events(app: &App) -> Vec<i16> {
+    app.world
+        .resource::<Events<UpdateSelectedSlot>>()
+        .iter_current_update_events()
+        .map(|event| event.slot)
+        .collect()
+}
+
+#[test]
+fn test_should_select_held_item_on_client() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    app.world
+        .get_mut::<HeldItem>(client_ent)
+        .expect("could not find client")
+        .set_hotbar_idx(3);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::UpdateSelectedSlotS2c(
+            valence_core::packet::s2c::play::UpdateSelectedSlotS2c { slot: 3 }
+        )
+    );
+    assert!(selected_slot_events(&app).is_empty());
+
+    // The client confirming the slot is not an event.
+    client_helper.send(&valence_core::packet::c2s::play::UpdateSelectedSlotC2s { slot: 3 });
+
+    app.update();
+
+    assert!(selected_slot_events(&app).is_empty());
+    assert_eq!(app.world.get::<HeldItem>(client_ent).unwrap().slot(), 39);
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::UpdateSelectedSlotS2c(_));
+
+    // Setting the slot the client already holds sends nothing.
+    app.world
+        .get_mut::<HeldItem>(client_ent)
+        .unwrap()
+        .set_hotbar_idx(3);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::UpdateSelectedSlotS2c(_));
+}
+
+#[test]
+fn test_should_ignore_invalid_selected_slot() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&valence_core::packet::c2s::play::UpdateSelectedSlotC2s { slot: 9 });
+
+    app.update();
+
+    assert!(selected_slot_events(&app).is_empty());
+    assert_eq!(app.world.get::<HeldItem>(client_ent).unwrap().slot(), 36);
+}
+
+#[test]
+fn should_not_increment_state_id_on_cursor_item_change() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    let inv_state = app
+        .world
+        .get::<ClientInventoryState>(client_ent)
+        .expect("could not find client");
+    let expected_state_id = inv_state.state_id().0;
+
+    let mut cursor_item = app.world.get_mut::<CursorItem>(client_ent).unwrap();
+    cursor_item.0 = Some(ItemStack::new(ItemKind::Diamond, 2, None));
+
+    app.update();
+
+    // Make assertions
+    let inv_state = app
+        .world
+        .get::<ClientInventoryState>(client_ent)
+        .expect("could not find client");
+    assert_eq!(
+        inv_state.state_id().0,
+        expected_state_id,
+        "state id should not have changed"
+    );
+}
+
+mod dropping_items {
+    use valence_core::block_pos::BlockPos;
+    use valence_core::direction::Direction;
+    use valence_core::packet::c2s::play::click_slot::{ClickMode, Slot};
+    use valence_core::packet::c2s::play::player_action::Action;
+    use valence_inventory::convert_to_player_slot_id;
+
+    use super::*;
+
+    #[test]
+    fn should_drop_item_player_action() {
+        let Scenario {
+            mut app,
+            client: client_ent,
+            mock_client: mut client_helper,
+            ..
+        } = ScenarioBuilder::new().build();
+
+        // Process a tick to get past the "on join" logic.
+        app.update();
+        client_helper.clear_sent();
+
+        let mut inventory = app
+            .world
+            .get_mut::<Inventory>(client_ent)
+            .expect("could not find inventory");
+        inventory.set_slot(36, ItemStack::new(ItemKind::IronIngot, 3, None));
+
+        client_helper.send(&valence_core::packet::c2s::play::PlayerActionC2s {
+            action: Action::DropItem,
+            position: BlockPos::new(0, 0, 0),
+            direction: Direction::Down,
+            sequence: VarInt(0),
+        });
+
+        app.update();
+
+        // Make assertions
+        let inventory = app
+            .world
+            .get::<Inventory>(client_ent)
+            .expect("could not find client");
+        assert_eq!(
+            inventory.slot(36),
+            Some(&ItemStack::new(ItemKind::IronIngot, 2, None))
+        );
+        let events = app
+            .world
+            .get_resource::<Events<DropItemEvent>>()
+            .expect("expected drop item stack events");
+        let events = events.iter_current_update_events().collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].client, client_ent);
+        assert_eq!(events[0].from_slot, Some(36));
+        assert!(!events[0].full_stack);
+        assert_eq!(
+            events[0].stack,
+            ItemStack::new(ItemKind::IronIngot, 1, None)
+        );
+
+        let sent = client_helper.collect_sent();
+        let sent_packets = sent.all();
+        assert_packet_count!(
+            sent_packets,
+            0,
+            S2cPlayPacket::ScreenHandlerSlotUpdateS2c(_)
+        );
+    }
+
+    #[test]
+    fn should_drop_item_stack_player_action() {
+        let Scenario {
+            mut app,
+            client: client_ent,
+            mock_client: mut client_helper,
+            ..
+        } = ScenarioBuilder::new().build();
+
+        // Process a tick to get past the "on join" logic.
+        app.update();
+        client_helper.clear_sent();
+
+        let mut inventory = app
+            .world
+            .get_mut::<Inventory>(client_ent)
             .expect("could not find inventory");
         inventory.set_slot(36, ItemStack::new(ItemKind::IronIngot, 32, None));
 
@@ -612,11 +1160,11 @@ mod dropping_items {
         app.update();
 
         // Make assertions
-        let inv_state = app
+        let held = app
             .world
-            .get::<ClientInventoryState>(client_ent)
+            .get::<HeldItem>(client_ent)
             .expect("could not find client");
-        assert_eq!(inv_state.held_item_slot(), 36);
+        assert_eq!(held.slot(), 36);
         let inventory = app
             .world
             .get::<Inventory>(client_ent)
@@ -624,12 +1172,13 @@ mod dropping_items {
         assert_eq!(inventory.slot(36), None);
         let events = app
             .world
-            .get_resource::<Events<DropItemStack>>()
+            .get_resource::<Events<DropItemEvent>>()
             .expect("expected drop item stack events");
         let events = events.iter_current_update_events().collect::<Vec<_>>();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].client, client_ent);
         assert_eq!(events[0].from_slot, Some(36));
+        assert!(events[0].full_stack);
         assert_eq!(
             events[0].stack,
             ItemStack::new(ItemKind::IronIngot, 32, None)
@@ -638,8 +1187,12 @@ mod dropping_items {
 
     #[test]
     fn should_drop_item_stack_set_creative_mode_slot() {
-        let mut app = App::new();
-        let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+        let Scenario {
+            mut app,
+            client: client_ent,
+            mock_client: mut client_helper,
+            ..
+        } = ScenarioBuilder::new().build();
 
         // Process a tick to get past the "on join" logic.
         app.update();
@@ -659,7 +1212,7 @@ mod dropping_items {
         // Make assertions
         let events = app
             .world
-            .get_resource::<Events<DropItemStack>>()
+            .get_resource::<Events<DropItemEvent>>()
             .expect("expected drop item stack events")
             .iter_current_update_events()
             .collect::<Vec<_>>();
@@ -667,6 +1220,7 @@ mod dropping_items {
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].client, client_ent);
         assert_eq!(events[0].from_slot, None);
+        assert!(events[0].full_stack);
         assert_eq!(
             events[0].stack,
             ItemStack::new(ItemKind::IronIngot, 32, None)
@@ -675,8 +1229,12 @@ mod dropping_items {
 
     #[test]
     fn should_drop_item_stack_click_container_outside() {
-        let mut app = App::new();
-        let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+        let Scenario {
+            mut app,
+            client: client_ent,
+            mock_client: mut client_helper,
+            ..
+        } = ScenarioBuilder::new().build();
 
         // Process a tick to get past the "on join" logic.
         app.update();
@@ -713,22 +1271,86 @@ mod dropping_items {
         assert_eq!(cursor_item.0, None);
         let events = app
             .world
-            .get_resource::<Events<DropItemStack>>()
+            .get_resource::<Events<DropItemEvent>>()
             .expect("expected drop item stack events");
         let events = events.iter_current_update_events().collect::<Vec<_>>();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].client, client_ent);
         assert_eq!(events[0].from_slot, None);
+        assert!(events[0].full_stack);
         assert_eq!(
             events[0].stack,
             ItemStack::new(ItemKind::IronIngot, 32, None)
         );
     }
 
+    #[test]
+    fn should_drop_single_item_right_click_outside() {
+        let Scenario {
+            mut app,
+            client: client_ent,
+            mock_client: mut client_helper,
+            ..
+        } = ScenarioBuilder::new().build();
+
+        // Process a tick to get past the "on join" logic.
+        app.update();
+        client_helper.clear_sent();
+
+        let mut cursor_item = app
+            .world
+            .get_mut::<CursorItem>(client_ent)
+            .expect("could not find client");
+        cursor_item.0 = Some(ItemStack::new(ItemKind::IronIngot, 32, None));
+        let inv_state = app
+            .world
+            .get_mut::<ClientInventoryState>(client_ent)
+            .expect("could not find client");
+        let state_id = inv_state.state_id().0;
+
+        client_helper.send(&valence_core::packet::c2s::play::ClickSlotC2s {
+            window_id: 0,
+            slot_idx: -999,
+            button: 1,
+            mode: ClickMode::Click,
+            state_id: VarInt(state_id),
+            slot_changes: vec![],
+            carried_item: Some(ItemStack::new(ItemKind::IronIngot, 31, None)),
+        });
+
+        app.update();
+
+        // Make assertions
+        let cursor_item = app
+            .world
+            .get::<CursorItem>(client_ent)
+            .expect("could not find client");
+        assert_eq!(
+            cursor_item.0,
+            Some(ItemStack::new(ItemKind::IronIngot, 31, None))
+        );
+        let events = app
+            .world
+            .get_resource::<Events<DropItemEvent>>()
+            .expect("expected drop item events");
+        let events = events.iter_current_update_events().collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].from_slot, None);
+        assert!(!events[0].full_stack);
+        assert_eq!(
+            events[0].stack,
+            ItemStack::new(ItemKind::IronIngot, 1, None)
+        );
+    }
+
     #[test]
     fn should_drop_item_click_container_with_dropkey_single() {
-        let mut app = App::new();
-        let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+        let Scenario {
+            mut app,
+            client: client_ent,
+            mock_client: mut client_helper,
+            ..
+        } = ScenarioBuilder::new().build();
 
         // Process a tick to get past the "on join" logic.
         app.update();
@@ -763,12 +1385,13 @@ mod dropping_items {
         // Make assertions
         let events = app
             .world
-            .get_resource::<Events<DropItemStack>>()
+            .get_resource::<Events<DropItemEvent>>()
             .expect("expected drop item stack events");
         let events = events.iter_current_update_events().collect::<Vec<_>>();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].client, client_ent);
         assert_eq!(events[0].from_slot, Some(40));
+        assert!(!events[0].full_stack);
         assert_eq!(
             events[0].stack,
             ItemStack::new(ItemKind::IronIngot, 1, None)
@@ -777,8 +1400,12 @@ mod dropping_items {
 
     #[test]
     fn should_drop_item_stack_click_container_with_dropkey() {
-        let mut app = App::new();
-        let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+        let Scenario {
+            mut app,
+            client: client_ent,
+            mock_client: mut client_helper,
+            ..
+        } = ScenarioBuilder::new().build();
 
         // Process a tick to get past the "on join" logic.
         app.update();
@@ -813,12 +1440,13 @@ mod dropping_items {
         // Make assertions
         let events = app
             .world
-            .get_resource::<Events<DropItemStack>>()
+            .get_resource::<Events<DropItemEvent>>()
             .expect("expected drop item stack events");
         let events = events.iter_current_update_events().collect::<Vec<_>>();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].client, client_ent);
         assert_eq!(events[0].from_slot, Some(40));
+        assert!(events[0].full_stack);
         assert_eq!(
             events[0].stack,
             ItemStack::new(ItemKind::IronIngot, 32, None)
@@ -830,8 +1458,12 @@ mod dropping_items {
         // The item should be dropped successfully, if the player has an inventory open
         // and the slot id points to his inventory.
 
-        let mut app = App::new();
-        let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+        let Scenario {
+            mut app,
+            client: client_ent,
+            mock_client: mut client_helper,
+            ..
+        } = ScenarioBuilder::new().build();
 
         // Process a tick to get past the "on join" logic.
         app.update();
@@ -875,7 +1507,7 @@ mod dropping_items {
         // Make assertions
         let events = app
             .world
-            .get_resource::<Events<DropItemStack>>()
+            .get_resource::<Events<DropItemEvent>>()
             .expect("expected drop item stack events");
         let player_inventory = app
             .world
@@ -888,6 +1520,7 @@ mod dropping_items {
             events[0].from_slot,
             Some(convert_to_player_slot_id(InventoryKind::Generic9x3, 50))
         );
+        assert!(!events[0].full_stack);
         assert_eq!(
             events[0].stack,
             ItemStack::new(ItemKind::IronIngot, 1, None)
@@ -906,8 +1539,12 @@ fn should_drop_item_stack_player_open_inventory_with_dropkey() {
     // The item stack should be dropped successfully, if the player has an inventory
     // open and the slot id points to his inventory.
 
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     // Process a tick to get past the "on join" logic.
     app.update();
@@ -951,7 +1588,7 @@ fn should_drop_item_stack_player_open_inventory_with_dropkey() {
     // Make assertions
     let events = app
         .world
-        .get_resource::<Events<DropItemStack>>()
+        .get_resource::<Events<DropItemEvent>>()
         .expect("expected drop item stack events");
     let player_inventory = app
         .world
@@ -964,6 +1601,7 @@ fn should_drop_item_stack_player_open_inventory_with_dropkey() {
         events[0].from_slot,
         Some(convert_to_player_slot_id(InventoryKind::Generic9x3, 50))
     );
+    assert!(events[0].full_stack);
     assert_eq!(
         events[0].stack,
         ItemStack::new(ItemKind::IronIngot, 32, None)
@@ -973,10 +1611,53 @@ fn should_drop_item_stack_player_open_inventory_with_dropkey() {
     assert_eq!(player_inventory.slot(expected_player_slot_id), None);
 }
 
+#[test]
+fn should_spawn_item_entity() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: _client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    let instance_ent = app.world.get::<Location>(client_ent).unwrap().0;
+    let stack = ItemStack::new(ItemKind::Diamond, 3, None);
+
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, &app.world);
+    let item_ent = spawn_item_entity(
+        &mut commands,
+        instance_ent,
+        Position::new([0.0, ITEM_DROP_HEIGHT, 0.0]),
+        item_drop_velocity(Look::new(0.0, 0.0)),
+        stack.clone(),
+    );
+    queue.apply(&mut app.world);
+
+    app.update();
+
+    let item = app.world.entity(item_ent);
+    assert_eq!(item.get::<EntityKind>(), Some(&EntityKind::ITEM));
+    assert_eq!(item.get::<item::Stack>().map(|s| &s.0), Some(&stack));
+
+    // Looking south with a level pitch throws the item south and slightly up.
+    let velocity = item.get::<Velocity>().unwrap().0;
+    assert!(velocity.x.abs() < 1e-4);
+    assert!(velocity.y > 0.0);
+    assert!(velocity.z > 0.0);
+}
+
 #[test]
 fn dragging_items() {
-    let mut app = App::new();
-    let (client_ent, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     // Process a tick to get past the "on join" logic.
     app.update();
@@ -989,6 +1670,29 @@ fn dragging_items() {
     let window_id = inv_state.window_id();
     let state_id = inv_state.state_id().0;
 
+    // Dragging starts with one packet, then one packet for each slot.
+    client_helper.send(&ClickSlotC2s {
+        window_id,
+        state_id: VarInt(state_id),
+        slot_idx: -999,
+        button: 0,
+        mode: ClickMode::Drag,
+        slot_changes: vec![],
+        carried_item: Some(ItemStack::new(ItemKind::Diamond, 64, None)),
+    });
+
+    for slot_idx in 9..12 {
+        client_helper.send(&ClickSlotC2s {
+            window_id,
+            state_id: VarInt(state_id),
+            slot_idx,
+            button: 1,
+            mode: ClickMode::Drag,
+            slot_changes: vec![],
+            carried_item: Some(ItemStack::new(ItemKind::Diamond, 64, None)),
+        });
+    }
+
     let drag_packet = ClickSlotC2s {
         window_id,
         state_id: VarInt(state_id),
@@ -1014,7 +1718,8 @@ fn dragging_items() {
     client_helper.send(&drag_packet);
 
     app.update();
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
     assert_eq!(sent_packets.len(), 0);
 
     let cursor_item = app
@@ -1036,3 +1741,999 @@ fn dragging_items() {
         );
     }
 }
+
+#[test]
+fn swapping_with_number_key() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    let mut inventory = app
+        .world
+        .get_mut::<Inventory>(client_ent)
+        .expect("could not find inventory");
+    inventory.set_slot(20, ItemStack::new(ItemKind::Diamond, 5, None));
+    inventory.set_slot(38, ItemStack::new(ItemKind::IronIngot, 3, None));
+
+    app.update();
+    client_helper.clear_sent();
+
+    let state_id = app
+        .world
+        .get::<ClientInventoryState>(client_ent)
+        .unwrap()
+        .state_id()
+        .0;
+
+    // Pressing 3 while hovering over slot 20.
+    client_helper.send(&ClickSlotC2s {
+        window_id: 0,
+        state_id: VarInt(state_id),
+        slot_idx: 20,
+        button: 2,
+        mode: ClickMode::Hotbar,
+        slot_changes: vec![
+            Slot {
+                idx: 20,
+                item: Some(ItemStack::new(ItemKind::IronIngot, 3, None)),
+            },
+            Slot {
+                idx: 38,
+                item: Some(ItemStack::new(ItemKind::Diamond, 5, None)),
+            },
+        ],
+        carried_item: None,
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(
+        sent_packets,
+        0,
+        S2cPlayPacket::InventoryS2c(_) | S2cPlayPacket::ScreenHandlerSlotUpdateS2c(_)
+    );
+
+    let inventory = app
+        .world
+        .get::<Inventory>(client_ent)
+        .expect("could not find inventory");
+    assert_eq!(
+        inventory.slot(20),
+        Some(&ItemStack::new(ItemKind::IronIngot, 3, None))
+    );
+    assert_eq!(
+        inventory.slot(38),
+        Some(&ItemStack::new(ItemKind::Diamond, 5, None))
+    );
+}
+
+#[test]
+fn should_resync_on_mispredicted_click() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    let mut inventory = app
+        .world
+        .get_mut::<Inventory>(client_ent)
+        .expect("could not find inventory");
+    inventory.set_slot(20, ItemStack::new(ItemKind::Diamond, 10, None));
+
+    app.update();
+    client_helper.clear_sent();
+
+    let state_id = app
+        .world
+        .get::<ClientInventoryState>(client_ent)
+        .unwrap()
+        .state_id()
+        .0;
+
+    // A right click picks up half of the stack, not all of it.
+    client_helper.send(&ClickSlotC2s {
+        window_id: 0,
+        state_id: VarInt(state_id),
+        slot_idx: 20,
+        button: 1,
+        mode: ClickMode::Click,
+        slot_changes: vec![Slot {
+            idx: 20,
+            item: None,
+        }],
+        carried_item: Some(ItemStack::new(ItemKind::Diamond, 10, None)),
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::InventoryS2c(_));
+
+    let inventory = app
+        .world
+        .get::<Inventory>(client_ent)
+        .expect("could not find inventory");
+    assert_eq!(
+        inventory.slot(20),
+        Some(&ItemStack::new(ItemKind::Diamond, 10, None))
+    );
+    let cursor_item = app
+        .world
+        .get::<CursorItem>(client_ent)
+        .expect("could not find client");
+    assert_eq!(cursor_item.0, None);
+}
+
+/// Replaces the clicked slot like a plugin reacting to the click.
+fn replace_clicked_slot(
+    mut events: EventReader<ClickSlot>,
+    mut inventories: Query<&mut Inventory>,
+) {
+    for event in events.iter() {
+        if let Ok(mut inventory) = inventories.get_mut(event.client) {
+            inventory.set_slot(
+                event.slot_id as u16,
+                ItemStack::new(ItemKind::Stick, 1, None),
+            );
+        }
+    }
+}
+
+#[test]
+fn should_send_slot_changed_by_server_after_click() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.add_system(replace_clicked_slot);
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    app.world
+        .get_mut::<Inventory>(client_ent)
+        .expect("could not find inventory")
+        .set_slot(20, ItemStack::new(ItemKind::Diamond, 10, None));
+
+    app.update();
+    client_helper.clear_sent();
+
+    let state_id = app
+        .world
+        .get::<ClientInventoryState>(client_ent)
+        .unwrap()
+        .state_id()
+        .0;
+
+    client_helper.send(&ClickSlotC2s {
+        window_id: 0,
+        state_id: VarInt(state_id),
+        slot_idx: 20,
+        button: 0,
+        mode: ClickMode::Click,
+        slot_changes: vec![Slot {
+            idx: 20,
+            item: None,
+        }],
+        carried_item: Some(ItemStack::new(ItemKind::Diamond, 10, None)),
+    });
+
+    app.update();
+
+    // The click is valid, but the slot was changed again in the same tick, so the
+    // client needs to be told about it.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::InventoryS2c(_));
+
+    let mut slot_updates = sent_packets.iter().filter_map(|pkt| match pkt {
+        S2cPlayPacket::ScreenHandlerSlotUpdateS2c(pkt) if pkt.window_id == 0 => Some(pkt),
+        _ => None,
+    });
+
+    let update = slot_updates.next().expect("slot update should be sent");
+    assert_eq!(update.slot_idx, 20);
+    assert_eq!(
+        update.slot_data.as_ref(),
+        &Some(ItemStack::new(ItemKind::Stick, 1, None))
+    );
+    assert!(update.state_id.0 > state_id);
+    assert!(slot_updates.next().is_none());
+
+    let inventory = app
+        .world
+        .get::<Inventory>(client_ent)
+        .expect("could not find inventory");
+    assert_eq!(
+        inventory.slot(20),
+        Some(&ItemStack::new(ItemKind::Stick, 1, None))
+    );
+    assert_eq!(
+        app.world.get::<CursorItem>(client_ent).unwrap().0,
+        Some(ItemStack::new(ItemKind::Diamond, 10, None))
+    );
+}
+
+#[test]
+fn should_resync_on_stale_state_id() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    app.world
+        .get_mut::<Inventory>(client_ent)
+        .expect("could not find inventory")
+        .set_slot(20, ItemStack::new(ItemKind::Diamond, 10, None));
+
+    app.update();
+    client_helper.clear_sent();
+
+    // The client clicks before receiving the server's change to the slot.
+    let stale_state_id = app
+        .world
+        .get::<ClientInventoryState>(client_ent)
+        .unwrap()
+        .state_id()
+        .0;
+
+    app.world
+        .get_mut::<Inventory>(client_ent)
+        .expect("could not find inventory")
+        .set_slot(20, ItemStack::new(ItemKind::Emerald, 3, None));
+
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&ClickSlotC2s {
+        window_id: 0,
+        state_id: VarInt(stale_state_id),
+        slot_idx: 20,
+        button: 0,
+        mode: ClickMode::Click,
+        slot_changes: vec![Slot {
+            idx: 20,
+            item: None,
+        }],
+        carried_item: Some(ItemStack::new(ItemKind::Diamond, 10, None)),
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::InventoryS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::InventoryS2c(pkt) = pkt {
+            assert_eq!(
+                pkt.slots[20],
+                Some(ItemStack::new(ItemKind::Emerald, 3, None))
+            );
+            assert_eq!(pkt.carried_item.as_ref(), &None);
+        }
+    }
+
+    // The click is ignored.
+    let inventory = app
+        .world
+        .get::<Inventory>(client_ent)
+        .expect("could not find inventory");
+    assert_eq!(
+        inventory.slot(20),
+        Some(&ItemStack::new(ItemKind::Emerald, 3, None))
+    );
+    assert_eq!(app.world.get::<CursorItem>(client_ent).unwrap().0, None);
+}
+
+#[test]
+fn shift_clicking_furnace_result() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut furnace = Inventory::new(InventoryKind::Furnace);
+    furnace.set_slot(2, ItemStack::new(ItemKind::IronIngot, 5, None));
+    let furnace_ent = app.world.spawn(furnace).id();
+    app.world
+        .entity_mut(client_ent)
+        .insert(OpenInventory::new(furnace_ent));
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    let inv_state = app.world.get::<ClientInventoryState>(client_ent).unwrap();
+    let state_id = inv_state.state_id().0;
+    let window_id = inv_state.window_id();
+
+    client_helper.send(&ClickSlotC2s {
+        window_id,
+        state_id: VarInt(state_id),
+        slot_idx: 2,
+        button: 0,
+        mode: ClickMode::ShiftClick,
+        slot_changes: vec![
+            Slot { idx: 2, item: None },
+            Slot {
+                idx: 38,
+                item: Some(ItemStack::new(ItemKind::IronIngot, 5, None)),
+            },
+        ],
+        carried_item: None,
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(
+        sent_packets,
+        0,
+        S2cPlayPacket::InventoryS2c(_) | S2cPlayPacket::ScreenHandlerSlotUpdateS2c(_)
+    );
+
+    let furnace = app
+        .world
+        .get::<Inventory>(furnace_ent)
+        .expect("could not find furnace");
+    assert_eq!(furnace.slot(2), None);
+    let inventory = app
+        .world
+        .get::<Inventory>(client_ent)
+        .expect("could not find inventory");
+    assert_eq!(
+        inventory.slot(convert_to_player_slot_id(InventoryKind::Furnace, 38)),
+        Some(&ItemStack::new(ItemKind::IronIngot, 5, None))
+    );
+}
+
+fn set_up_merchant(app: &mut App, client_ent: Entity, merchant: Inventory) -> Entity {
+    let trade_list = TradeList {
+        trades: vec![Trade::new(
+            ItemStack::new(ItemKind::Emerald, 3, None),
+            ItemStack::new(ItemKind::Bread, 6, None),
+        )],
+        villager_level: 1,
+        is_regular_villager: true,
+        ..Default::default()
+    };
+
+    let merchant_ent = app.world.spawn((merchant, trade_list)).id();
+    app.world
+        .entity_mut(client_ent)
+        .insert(OpenInventory::new(merchant_ent));
+
+    merchant_ent
+}
+
+#[test]
+fn test_should_send_trade_offers() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    let merchant_ent = set_up_merchant(
+        &mut app,
+        client_ent,
+        Inventory::new(InventoryKind::Merchant),
+    );
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::SetTradeOffersS2c(_));
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::OpenScreenS2c(_),
+        S2cPlayPacket::SetTradeOffersS2c(_)
+    );
+
+    app.update();
+
+    // The trades are only sent again when they change.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::SetTradeOffersS2c(_));
+
+    app.world.get_mut::<TradeList>(merchant_ent).unwrap().trades[0].max_uses = 1;
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::SetTradeOffersS2c(_));
+}
+
+#[test]
+fn trading_with_merchant() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut merchant = Inventory::new(InventoryKind::Merchant);
+    merchant.set_slot(0, ItemStack::new(ItemKind::Emerald, 5, None));
+    merchant.set_slot(2, ItemStack::new(ItemKind::Bread, 6, None));
+    let merchant_ent = set_up_merchant(&mut app, client_ent, merchant);
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    let inv_state = app.world.get::<ClientInventoryState>(client_ent).unwrap();
+    let state_id = inv_state.state_id().0;
+    let window_id = inv_state.window_id();
+
+    // Taking the result pays for the trade, and two emeralds don't pay for
+    // another one.
+    client_helper.send(&ClickSlotC2s {
+        window_id,
+        state_id: VarInt(state_id),
+        slot_idx: 2,
+        button: 0,
+        mode: ClickMode::Click,
+        slot_changes: vec![
+            Slot {
+                idx: 0,
+                item: Some(ItemStack::new(ItemKind::Emerald, 2, None)),
+            },
+            Slot { idx: 2, item: None },
+        ],
+        carried_item: Some(ItemStack::new(ItemKind::Bread, 6, None)),
+    });
+
+    app.update();
+
+    // The client predicted the trade, so nothing is sent back.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(
+        sent_packets,
+        0,
+        S2cPlayPacket::InventoryS2c(_)
+            | S2cPlayPacket::ScreenHandlerSlotUpdateS2c(_)
+            | S2cPlayPacket::SetTradeOffersS2c(_)
+    );
+
+    let merchant = app.world.get::<Inventory>(merchant_ent).unwrap();
+    assert_eq!(
+        merchant.slot(0),
+        Some(&ItemStack::new(ItemKind::Emerald, 2, None))
+    );
+    assert_eq!(merchant.slot(2), None);
+
+    let trade_list = app.world.get::<TradeList>(merchant_ent).unwrap();
+    assert_eq!(trade_list.trades[0].uses, 1);
+}
+
+#[test]
+fn selecting_merchant_trade() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    app.world
+        .get_mut::<Inventory>(client_ent)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::Emerald, 10, None));
+    let merchant_ent = set_up_merchant(
+        &mut app,
+        client_ent,
+        Inventory::new(InventoryKind::Merchant),
+    );
+
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&SelectMerchantTradeC2s {
+        selected_slot: VarInt(0),
+    });
+
+    app.update();
+
+    let events = app
+        .world
+        .resource::<Events<SelectMerchantTrade>>()
+        .iter_current_update_events()
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].client, client_ent);
+    assert_eq!(events[0].trade, 0);
+
+    // The emeralds are moved into the first input slot.
+    let merchant = app.world.get::<Inventory>(merchant_ent).unwrap();
+    assert_eq!(
+        merchant.slot(0),
+        Some(&ItemStack::new(ItemKind::Emerald, 10, None))
+    );
+    assert_eq!(
+        merchant.slot(2),
+        Some(&ItemStack::new(ItemKind::Bread, 6, None))
+    );
+
+    let inventory = app.world.get::<Inventory>(client_ent).unwrap();
+    assert_eq!(inventory.slot(36), None);
+}
+
+#[test]
+fn renaming_item_in_anvil() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let anvil_ent = app.world.spawn(Inventory::new(InventoryKind::Anvil)).id();
+    app.world
+        .get_mut::<Inventory>(anvil_ent)
+        .unwrap()
+        .set_slot(0, ItemStack::new(ItemKind::DiamondSword, 1, None));
+    app.world
+        .entity_mut(client_ent)
+        .insert(OpenInventory::new(anvil_ent));
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&RenameItemC2s {
+        item_name: "§6Excalibur",
+    });
+
+    app.update();
+
+    let events = app
+        .world
+        .resource::<Events<RenameItem>>()
+        .iter_current_update_events()
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].name, "6Excalibur");
+
+    let renamed = ItemStack::new(ItemKind::DiamondSword, 1, None).with_name("6Excalibur");
+
+    let anvil = app.world.get::<Inventory>(anvil_ent).unwrap();
+    assert_eq!(anvil.slot(2), Some(&renamed));
+    assert_eq!(
+        app.world.get::<AnvilState>(anvil_ent).unwrap().level_cost,
+        1
+    );
+
+    // The result and its cost are sent to the client.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::ScreenHandlerSlotUpdateS2c(_),
+        S2cPlayPacket::ScreenHandlerPropertyUpdateS2c(_)
+    );
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::ScreenHandlerPropertyUpdateS2c(pkt) = pkt {
+            assert_eq!(pkt.property, 0);
+            assert_eq!(pkt.value, 1);
+        }
+    }
+
+    app.world.get_mut::<ExperienceLevel>(client_ent).unwrap().0 = 5;
+    app.update();
+    client_helper.clear_sent();
+
+    let inv_state = app.world.get::<ClientInventoryState>(client_ent).unwrap();
+    let state_id = inv_state.state_id().0;
+    let window_id = inv_state.window_id();
+
+    client_helper.send(&ClickSlotC2s {
+        window_id,
+        state_id: VarInt(state_id),
+        slot_idx: 2,
+        button: 0,
+        mode: ClickMode::Click,
+        slot_changes: vec![Slot { idx: 0, item: None }, Slot { idx: 2, item: None }],
+        carried_item: Some(renamed.clone()),
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::InventoryS2c(_));
+
+    let anvil = app.world.get::<Inventory>(anvil_ent).unwrap();
+    assert_eq!(anvil.slot(0), None);
+    assert_eq!(anvil.slot(2), None);
+    assert_eq!(app.world.get::<ExperienceLevel>(client_ent).unwrap().0, 4);
+    assert_eq!(
+        app.world.get::<CursorItem>(client_ent).unwrap().0,
+        Some(renamed)
+    );
+}
+
+#[test]
+fn anvil_result_needs_levels() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut anvil = Inventory::new(InventoryKind::Anvil);
+    anvil.set_slot(0, ItemStack::new(ItemKind::DiamondSword, 1, None));
+    let anvil_ent = app.world.spawn(anvil).id();
+    app.world
+        .entity_mut(client_ent)
+        .insert(OpenInventory::new(anvil_ent));
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    client_helper.send(&RenameItemC2s {
+        item_name: "Excalibur",
+    });
+
+    app.update();
+    client_helper.clear_sent();
+
+    let inv_state = app.world.get::<ClientInventoryState>(client_ent).unwrap();
+    let state_id = inv_state.state_id().0;
+    let window_id = inv_state.window_id();
+
+    client_helper.send(&ClickSlotC2s {
+        window_id,
+        state_id: VarInt(state_id),
+        slot_idx: 2,
+        button: 0,
+        mode: ClickMode::Click,
+        slot_changes: vec![Slot { idx: 0, item: None }, Slot { idx: 2, item: None }],
+        carried_item: Some(ItemStack::new(ItemKind::DiamondSword, 1, None).with_name("Excalibur")),
+    });
+
+    app.update();
+
+    // The client has no levels, so the click is rejected.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::InventoryS2c(_));
+
+    let anvil = app.world.get::<Inventory>(anvil_ent).unwrap();
+    assert_eq!(
+        anvil.slot(0),
+        Some(&ItemStack::new(ItemKind::DiamondSword, 1, None))
+    );
+    assert_eq!(app.world.get::<CursorItem>(client_ent).unwrap().0, None);
+}
+
+fn planks_recipe() -> Recipe {
+    Recipe::shapeless(
+        [ItemKind::OakLog.into()],
+        ItemStack::new(ItemKind::OakPlanks, 4, None),
+    )
+}
+
+#[test]
+fn test_should_send_recipes_on_join() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.world
+        .resource_mut::<RecipeRegistry>()
+        .insert(ident!("oak_planks"), planks_recipe());
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::SynchronizeRecipesS2c(_),
+        S2cPlayPacket::UnlockRecipesS2c(_)
+    );
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::UnlockRecipesS2c(pkt) = pkt {
+            assert_eq!(pkt.recipe_ids, [ident!("oak_planks")]);
+        }
+    }
+
+    app.update();
+
+    // The recipes are only sent again when they change.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::SynchronizeRecipesS2c(_));
+}
+
+#[test]
+fn shift_click_crafting_in_player_inventory() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.world
+        .resource_mut::<RecipeRegistry>()
+        .insert(ident!("oak_planks"), planks_recipe());
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    app.world
+        .get_mut::<Inventory>(client_ent)
+        .unwrap()
+        .set_slot(1, ItemStack::new(ItemKind::OakLog, 3, None));
+
+    app.update();
+    client_helper.clear_sent();
+
+    // The result of the grid is computed by the server.
+    let inventory = app.world.get::<Inventory>(client_ent).unwrap();
+    assert_eq!(
+        inventory.slot(0),
+        Some(&ItemStack::new(ItemKind::OakPlanks, 4, None))
+    );
+
+    let state_id = app
+        .world
+        .get::<ClientInventoryState>(client_ent)
+        .unwrap()
+        .state_id()
+        .0;
+
+    // The client only predicts the first craft.
+    client_helper.send(&ClickSlotC2s {
+        window_id: 0,
+        state_id: VarInt(state_id),
+        slot_idx: 0,
+        button: 0,
+        mode: ClickMode::ShiftClick,
+        slot_changes: vec![
+            Slot { idx: 0, item: None },
+            Slot {
+                idx: 1,
+                item: Some(ItemStack::new(ItemKind::OakLog, 2, None)),
+            },
+            Slot {
+                idx: 44,
+                item: Some(ItemStack::new(ItemKind::OakPlanks, 4, None)),
+            },
+        ],
+        carried_item: None,
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::InventoryS2c(_));
+
+    let inventory = app.world.get::<Inventory>(client_ent).unwrap();
+    assert_eq!(inventory.slot(0), None);
+    assert_eq!(inventory.slot(1), None);
+    assert_eq!(
+        inventory.slot(44),
+        Some(&ItemStack::new(ItemKind::OakPlanks, 12, None))
+    );
+}
+
+#[test]
+fn crafting_leaves_buckets_in_grid() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.world.resource_mut::<RecipeRegistry>().insert(
+        ident!("honey_block"),
+        Recipe::shaped(
+            ["HH", "HW"],
+            [
+                ('H', ItemKind::HoneyBottle.into()),
+                ('W', ItemKind::WaterBucket.into()),
+            ],
+            ItemStack::new(ItemKind::HoneyBlock, 1, None),
+        ),
+    );
+
+    let crafting_table = app
+        .world
+        .spawn(Inventory::new(InventoryKind::Crafting))
+        .id();
+    app.world
+        .entity_mut(client_ent)
+        .insert(OpenInventory::new(crafting_table));
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    let mut inventory = app.world.get_mut::<Inventory>(crafting_table).unwrap();
+    inventory.set_slot(1, ItemStack::new(ItemKind::HoneyBottle, 1, None));
+    inventory.set_slot(2, ItemStack::new(ItemKind::HoneyBottle, 1, None));
+    inventory.set_slot(4, ItemStack::new(ItemKind::HoneyBottle, 1, None));
+    inventory.set_slot(5, ItemStack::new(ItemKind::WaterBucket, 1, None));
+
+    app.update();
+    client_helper.clear_sent();
+
+    let inv_state = app.world.get::<ClientInventoryState>(client_ent).unwrap();
+    let state_id = inv_state.state_id().0;
+    let window_id = inv_state.window_id();
+
+    // The bottles stay in the grid because their slots are empty, and so does the
+    // bucket.
+    client_helper.send(&ClickSlotC2s {
+        window_id,
+        state_id: VarInt(state_id),
+        slot_idx: 0,
+        button: 0,
+        mode: ClickMode::Click,
+        slot_changes: vec![
+            Slot { idx: 0, item: None },
+            Slot {
+                idx: 1,
+                item: Some(ItemStack::new(ItemKind::GlassBottle, 1, None)),
+            },
+            Slot {
+                idx: 2,
+                item: Some(ItemStack::new(ItemKind::GlassBottle, 1, None)),
+            },
+            Slot {
+                idx: 4,
+                item: Some(ItemStack::new(ItemKind::GlassBottle, 1, None)),
+            },
+            Slot {
+                idx: 5,
+                item: Some(ItemStack::new(ItemKind::Bucket, 1, None)),
+            },
+        ],
+        carried_item: Some(ItemStack::new(ItemKind::HoneyBlock, 1, None)),
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::InventoryS2c(_));
+
+    let inventory = app.world.get::<Inventory>(crafting_table).unwrap();
+    assert_eq!(inventory.slot(0), None);
+    assert_eq!(
+        inventory.slot(5),
+        Some(&ItemStack::new(ItemKind::Bucket, 1, None))
+    );
+    assert_eq!(
+        app.world.get::<CursorItem>(client_ent).unwrap().0,
+        Some(ItemStack::new(ItemKind::HoneyBlock, 1, None))
+    );
+}
+
+#[test]
+fn clicking_recipe_in_recipe_book() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut recipes = app.world.resource_mut::<RecipeRegistry>();
+    recipes.insert(ident!("oak_planks"), planks_recipe());
+    recipes.insert(
+        ident!("stick"),
+        Recipe::shaped(
+            ["#", "#"],
+            [('#', ItemKind::OakPlanks.into())],
+            ItemStack::new(ItemKind::Stick, 4, None),
+        ),
+    );
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    app.world
+        .get_mut::<Inventory>(client_ent)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::OakLog, 10, None));
+
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&CraftRequestC2s {
+        window_id: 0,
+        recipe: ident!("oak_planks").into(),
+        make_all: true,
+    });
+
+    app.update();
+
+    let inventory = app.world.get::<Inventory>(client_ent).unwrap();
+    assert_eq!(
+        inventory.slot(1),
+        Some(&ItemStack::new(ItemKind::OakLog, 10, None))
+    );
+    assert_eq!(inventory.slot(36), None);
+    assert_eq!(
+        inventory.slot(0),
+        Some(&ItemStack::new(ItemKind::OakPlanks, 4, None))
+    );
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(
+        sent_packets,
+        3,
+        S2cPlayPacket::ScreenHandlerSlotUpdateS2c(_)
+    );
+
+    // The player has no planks, so the recipe is only shown in the grid.
+    client_helper.send(&CraftRequestC2s {
+        window_id: 0,
+        recipe: ident!("stick").into(),
+        make_all: false,
+    });
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::CraftFailedResponseS2c(_));
+
+    let inventory = app.world.get::<Inventory>(client_ent).unwrap();
+    assert_eq!(inventory.slot(1), None);
+    assert_eq!(
+        inventory.slot(36),
+        Some(&ItemStack::new(ItemKind::OakLog, 10, None))
+    );
+}
@@ -0,0 +1,256 @@
+use bevy_app::App;
+use glam::Vec3;
+use valence_client::health::Food;
+use valence_client::misc::{InteractBlock, InteractItem};
+use valence_core::block_pos::BlockPos;
+use valence_core::direction::Direction;
+use valence_core::hand::Hand;
+use valence_core::item::{ItemKind, ItemStack};
+use valence_core::packet::c2s::play::player_action::Action;
+use valence_core::packet::c2s::play::{
+    PlayerActionC2s, PlayerInteractBlockC2s, PlayerInteractItemC2s,
+};
+use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_core::packet::var_int::VarInt;
+use valence_entity::living::LivingFlags;
+use valence_inventory::{FinishUsingItem, Inventory, UsingItem};
+
+use super::*;
+
+fn finish_events(app: &App) -> Vec<FinishUsingItem> {
+    app.world
+        .resource::<Events<FinishUsingItem>>()
+        .iter_current_update_events()
+        .copied()
+        .collect()
+}
+
+#[test]
+fn test_interact_block_distance() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&PlayerInteractBlockC2s {
+        hand: Hand::Main,
+        position: BlockPos::new(1, 0, 1),
+        face: Direction::Up,
+        cursor_pos: Vec3::new(0.5, 1.0, 0.25),
+        head_inside_block: false,
+        sequence: VarInt(3),
+    });
+
+    app.update();
+
+    let events = app.world.resource::<Events<InteractBlock>>();
+    let events = events.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].client, client_ent);
+    assert_eq!(events[0].position, BlockPos::new(1, 0, 1));
+    assert_eq!(events[0].face, Direction::Up);
+    assert_eq!(events[0].cursor_pos, Vec3::new(0.5, 1.0, 0.25));
+    assert_eq!(events[0].sequence, 3);
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::PlayerActionResponseS2c(_));
+
+    // Blocks too far away are ignored, but the interaction is still
+    // acknowledged so that the client doesn't keep a block it placed.
+    client_helper.send(&PlayerInteractBlockC2s {
+        hand: Hand::Main,
+        position: BlockPos::new(20, 0, 0),
+        face: Direction::Up,
+        cursor_pos: Vec3::new(0.5, 1.0, 0.5),
+        head_inside_block: false,
+        sequence: VarInt(4),
+    });
+
+    app.update();
+
+    let events = app.world.resource::<Events<InteractBlock>>();
+
+    assert_eq!(events.iter_current_update_events().count(), 0);
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::PlayerActionResponseS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::PlayerActionResponseS2c(pkt) = pkt {
+            assert_eq!(pkt.sequence.0, 4);
+        }
+    }
+}
+
+#[test]
+fn test_eating_food() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    app.world.get_mut::<Food>(client_ent).unwrap().0 = 10;
+    app.world
+        .get_mut::<Inventory>(client_ent)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::Apple, 5, None));
+
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&PlayerInteractItemC2s {
+        hand: Hand::Main,
+        sequence: VarInt(1),
+    });
+
+    app.update();
+
+    let events = app.world.resource::<Events<InteractItem>>();
+    assert_eq!(events.iter_current_update_events().count(), 1);
+
+    assert_eq!(
+        app.world.get::<UsingItem>(client_ent),
+        Some(&UsingItem {
+            hand: Hand::Main,
+            item: ItemKind::Apple,
+            use_ticks: 0,
+        })
+    );
+    assert!(app
+        .world
+        .get::<LivingFlags>(client_ent)
+        .unwrap()
+        .using_item());
+
+    // Eating an apple takes 32 ticks.
+    for _ in 0..31 {
+        app.update();
+        assert!(finish_events(&app).is_empty());
+    }
+
+    app.update();
+
+    assert_eq!(
+        finish_events(&app),
+        [FinishUsingItem {
+            client: client_ent,
+            hand: Hand::Main,
+            item: ItemKind::Apple,
+            use_ticks: 32,
+            released: false,
+        }]
+    );
+
+    app.update();
+
+    assert!(app.world.get::<UsingItem>(client_ent).is_none());
+    assert!(!app
+        .world
+        .get::<LivingFlags>(client_ent)
+        .unwrap()
+        .using_item());
+}
+
+#[test]
+fn test_not_eating_when_full() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    app.world
+        .get_mut::<Inventory>(client_ent)
+        .unwrap()
+        .set_slot(45, ItemStack::new(ItemKind::Bread, 5, None));
+
+    app.update();
+
+    client_helper.send(&PlayerInteractItemC2s {
+        hand: Hand::Off,
+        sequence: VarInt(1),
+    });
+
+    app.update();
+
+    assert!(app.world.get::<UsingItem>(client_ent).is_none());
+}
+
+#[test]
+fn test_drawing_bow() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    app.world
+        .get_mut::<Inventory>(client_ent)
+        .unwrap()
+        .set_slot(45, ItemStack::new(ItemKind::Bow, 1, None));
+
+    app.update();
+
+    client_helper.send(&PlayerInteractItemC2s {
+        hand: Hand::Off,
+        sequence: VarInt(1),
+    });
+
+    app.update();
+
+    let flags = app.world.get::<LivingFlags>(client_ent).unwrap();
+    assert!(flags.using_item());
+    assert!(flags.off_hand_active());
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    assert!(finish_events(&app).is_empty());
+
+    client_helper.send(&PlayerActionC2s {
+        action: Action::ReleaseUseItem,
+        position: BlockPos::new(0, 0, 0),
+        direction: Direction::Down,
+        sequence: VarInt(0),
+    });
+
+    app.update();
+
+    let events = finish_events(&app);
+
+    assert_eq!(
+        events,
+        [FinishUsingItem {
+            client: client_ent,
+            hand: Hand::Off,
+            item: ItemKind::Bow,
+            use_ticks: 10,
+            released: true,
+        }]
+    );
+    assert_eq!(events[0].bow_pull_progress(), 1.25 / 3.0);
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use valence_client::keepalive::KeepaliveSettings;
+use valence_client::{Client, Ping};
+use valence_core::packet::c2s::play::KeepAliveC2s;
+use valence_core::packet::s2c::play::KeepAliveS2c;
+
+use super::*;
+
+fn last_keepalive_id(client_helper: &mut MockClient) -> Option<u64> {
+    client_helper
+        .collect_sent()
+        .first::<KeepAliveS2c>()
+        .map(|pkt| pkt.id)
+}
+
+#[test]
+fn test_keepalive_period() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // One tick at the default tick rate of 20.
+    app.insert_resource(KeepaliveSettings {
+        period: Duration::from_millis(50),
+        ..Default::default()
+    });
+
+    for _ in 0..3 {
+        app.update();
+
+        let id = last_keepalive_id(&mut client_helper).expect("no keepalive was sent");
+        client_helper.send(&KeepAliveC2s { id });
+    }
+}
+
+#[test]
+fn test_keepalive_timeout() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.insert_resource(KeepaliveSettings {
+        timeout: Duration::ZERO,
+        ..Default::default()
+    });
+
+    app.update();
+
+    assert!(last_keepalive_id(&mut client_helper).is_some());
+    assert!(app.world.get::<Client>(client_ent).is_some());
+
+    // The client does not respond.
+    app.update();
+
+    assert!(app.world.get::<Client>(client_ent).is_none());
+}
+
+#[test]
+fn test_keepalive_ping_smoothing() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.insert_resource(KeepaliveSettings {
+        period: Duration::from_millis(50),
+        ..Default::default()
+    });
+
+    app.update();
+
+    // Pretend the previous round trip took 400 ms.
+    app.world.get_mut::<Ping>(client_ent).unwrap().0 = 400;
+
+    let id = last_keepalive_id(&mut client_helper).unwrap();
+    client_helper.send(&KeepAliveC2s { id });
+
+    app.update();
+
+    // The new round trip is close to 0 ms, so the ping is reduced by about a
+    // quarter.
+    let ping = app.world.get::<Ping>(client_ent).unwrap().0;
+    assert!((300..400).contains(&ping), "unexpected ping {ping}");
+}
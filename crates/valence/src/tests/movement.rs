@@ -0,0 +1,148 @@
+use glam::DVec3;
+use valence_client::abilities::PlayerAbilities;
+use valence_client::movement::{
+    BasicMovementValidator, Movement, MovementCheck, MovementSettings, MovementValidator,
+};
+use valence_core::packet::c2s::play::{PositionAndOnGround, TeleportConfirmC2s};
+use valence_core::packet::s2c::play::PlayerPositionLookS2c;
+use valence_entity::{Look, Position};
+
+use super::*;
+
+/// Returns the position and ID of the teleports sent to the client.
+fn sent_teleports(client_helper: &mut MockClient) -> Vec<(DVec3, VarInt)> {
+    client_helper
+        .collect_sent()
+        .of::<PlayerPositionLookS2c>()
+        .into_iter()
+        .map(|pkt| (pkt.position, pkt.teleport_id))
+        .collect()
+}
+
+/// Confirms the teleports sent to the client.
+fn confirm_teleports(client_helper: &mut MockClient, teleports: &[(DVec3, VarInt)]) {
+    for &(_, teleport_id) in teleports {
+        client_helper.send(&TeleportConfirmC2s { teleport_id });
+    }
+}
+
+#[test]
+fn test_rejected_movement_rubber_bands() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.insert_resource(MovementSettings {
+        validator: Some(Box::new(|check: &MovementCheck| {
+            check.movement.position.y < 100.0
+        })),
+    });
+
+    app.update();
+
+    let teleports = sent_teleports(&mut client_helper);
+    confirm_teleports(&mut client_helper, &teleports);
+
+    let start = app.world.get::<Position>(client_ent).unwrap().0;
+
+    client_helper.send(&PositionAndOnGround {
+        position: DVec3::new(0.0, 200.0, 0.0),
+        on_ground: false,
+    });
+
+    // Stale movement sent before the client sees the teleport is ignored.
+    client_helper.send(&PositionAndOnGround {
+        position: DVec3::new(5.0, 64.0, 0.0),
+        on_ground: true,
+    });
+
+    app.update();
+
+    assert_eq!(app.world.get::<Position>(client_ent).unwrap().0, start);
+    assert_eq!(
+        app.world
+            .resource::<Events<Movement>>()
+            .iter_current_update_events()
+            .count(),
+        0
+    );
+
+    let teleports = sent_teleports(&mut client_helper);
+
+    assert_eq!(teleports.len(), 1);
+    assert_eq!(teleports[0].0, start);
+
+    // Movement is applied again once the client confirms the teleport.
+    confirm_teleports(&mut client_helper, &teleports);
+
+    client_helper.send(&PositionAndOnGround {
+        position: DVec3::new(5.0, 64.0, 0.0),
+        on_ground: true,
+    });
+
+    app.update();
+
+    assert_eq!(
+        app.world.get::<Position>(client_ent).unwrap().0,
+        DVec3::new(5.0, 64.0, 0.0)
+    );
+    assert!(sent_teleports(&mut client_helper).is_empty());
+}
+
+#[test]
+fn test_movement_accepted_by_default() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    client_helper.send(&PositionAndOnGround {
+        position: DVec3::new(1000.0, 1000.0, 1000.0),
+        on_ground: false,
+    });
+
+    app.update();
+
+    assert_eq!(
+        app.world.get::<Position>(client_ent).unwrap().0,
+        DVec3::new(1000.0, 1000.0, 1000.0)
+    );
+}
+
+#[test]
+fn test_basic_movement_validator() {
+    let validator = BasicMovementValidator::default();
+    let abilities = PlayerAbilities::default();
+
+    let check = |old_position: DVec3, position: DVec3, old_on_ground, elapsed_ticks| {
+        validator.validate(&MovementCheck {
+            movement: &Movement {
+                client: Entity::PLACEHOLDER,
+                position,
+                old_position,
+                look: Look::default(),
+                old_look: Look::default(),
+                on_ground: false,
+                old_on_ground,
+            },
+            elapsed_ticks,
+            abilities: &abilities,
+        })
+    };
+
+    // Walking.
+    assert!(check(DVec3::ZERO, DVec3::new(0.2, 0.0, 0.2), true, 1));
+    // Too fast.
+    assert!(!check(DVec3::ZERO, DVec3::new(5.0, 0.0, 0.0), true, 1));
+    // Fine when spread over enough ticks.
+    assert!(check(DVec3::ZERO, DVec3::new(5.0, 0.0, 0.0), true, 10));
+    // Jumping off the ground.
+    assert!(check(DVec3::ZERO, DVec3::new(0.0, 0.42, 0.0), true, 1));
+    // Rising in the air.
+    assert!(!check(DVec3::ZERO, DVec3::new(0.0, 1.0, 0.0), false, 1));
+}
@@ -0,0 +1,144 @@
+use valence_client::keepalive::KeepaliveSettings;
+use valence_client::Ping;
+use valence_core::packet::c2s::play::KeepAliveC2s;
+use valence_core::packet::s2c::play::{
+    KeepAliveS2c, PlayerListHeaderS2c, PlayerListS2c, S2cPlayPacket,
+};
+use valence_core::text::Text;
+use valence_player_list::{DisplayName, PlayerList};
+
+use super::*;
+
+#[test]
+fn test_player_list_header_footer() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.world.resource_mut::<PlayerList>().set_header("Header");
+
+    // The header is sent to the client when it joins.
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::PlayerListHeaderS2c(_));
+
+    // Changes are broadcast once.
+    app.world.resource_mut::<PlayerList>().set_footer("Footer");
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::PlayerListHeaderS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::PlayerListHeaderS2c(PlayerListHeaderS2c { header, footer }) = pkt {
+            assert_eq!(**header, Text::from("Header"));
+            assert_eq!(**footer, Text::from("Footer"));
+        }
+    }
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::PlayerListHeaderS2c(_));
+}
+
+#[test]
+fn test_player_list_entry_update() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    // Only the changed fields of the entry are sent.
+    app.world.get_mut::<DisplayName>(client_ent).unwrap().0 = Some("Steve".into());
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::PlayerListS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::PlayerListS2c(PlayerListS2c { actions, entries }) = pkt {
+            assert!(actions.update_display_name());
+            assert!(!actions.add_player());
+            assert!(!actions.update_latency());
+            assert_eq!(entries[0].display_name.as_deref(), Some(&"Steve".into()));
+        }
+    }
+}
+
+#[test]
+fn test_player_list_latency_from_keepalive() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // The first keepalive is sent on the first tick.
+    app.update();
+
+    let id = client_helper
+        .collect_sent()
+        .first::<KeepAliveS2c>()
+        .expect("no keepalive was sent")
+        .id;
+
+    client_helper.send(&KeepAliveC2s { id });
+
+    app.update();
+
+    assert!(app.world.get::<Ping>(client_ent).unwrap().0 >= 0);
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::PlayerListS2c(_));
+}
+
+#[test]
+fn test_player_list_latency_set_manually() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.insert_resource(KeepaliveSettings {
+        update_ping: false,
+        ..Default::default()
+    });
+
+    app.update();
+
+    let id = client_helper
+        .collect_sent()
+        .first::<KeepAliveS2c>()
+        .expect("no keepalive was sent")
+        .id;
+
+    client_helper.send(&KeepAliveC2s { id });
+
+    app.update();
+
+    assert_eq!(*app.world.get::<Ping>(client_ent).unwrap(), Ping::default());
+}
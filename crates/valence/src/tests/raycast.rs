@@ -0,0 +1,275 @@
+use bevy_app::App;
+use glam::DVec3;
+use valence_block::{BlockKind, BlockState, PropName, PropValue};
+use valence_core::block_pos::BlockPos;
+use valence_core::direction::Direction;
+use valence_instance::{Chunk, FluidHandling};
+
+use super::*;
+
+/// Loads the chunk at the origin and places a few rows of blocks along the X
+/// axis, each starting at `x = 0`.
+fn setup() -> App {
+    let Scenario { mut app, .. } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.insert_chunk([0, 0], Chunk::default());
+
+    // A stone block.
+    instance.set_block([3, 0, 0], BlockState::STONE);
+
+    // A bottom slab in front of a stone block.
+    instance.set_block([2, 0, 2], BlockState::OAK_SLAB);
+    instance.set_block([3, 0, 2], BlockState::STONE);
+
+    // A water source and glass in front of a stone block.
+    instance.set_block([2, 0, 4], BlockState::WATER);
+    instance.set_block([3, 0, 4], BlockState::GLASS);
+    instance.set_block([4, 0, 4], BlockState::STONE);
+
+    // A fence, which is one and a half blocks high.
+    instance.set_block([3, 0, 6], BlockState::OAK_FENCE);
+
+    // Flowing water.
+    instance.set_block(
+        [2, 0, 8],
+        BlockState::WATER.set(PropName::Level, PropValue::_3),
+    );
+
+    app
+}
+
+#[test]
+fn raycast_hits_full_block() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    let hit = instance
+        .raycast([0.5, 0.5, 0.5], [1.0, 0.0, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(3, 0, 0));
+    assert_eq!(hit.state, BlockState::STONE);
+    assert_eq!(hit.face, Direction::West);
+    assert_eq!(hit.point, DVec3::new(3.0, 0.5, 0.5));
+    assert_eq!(hit.distance, 2.5);
+
+    // The direction doesn't need to be normalized.
+    assert_eq!(
+        instance.raycast([0.5, 0.5, 0.5], [2.0, 0.0, 0.0], 10.0),
+        Some(hit)
+    );
+
+    // The block is too far away.
+    assert_eq!(
+        instance.raycast([0.5, 0.5, 0.5], [1.0, 0.0, 0.0], 2.4),
+        None
+    );
+
+    // Coming from the other side.
+    let hit = instance
+        .raycast([6.0, 1.5, 0.5], [-1.0, -0.5, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(3, 0, 0));
+    assert_eq!(hit.face, Direction::East);
+    assert!(hit.point.abs_diff_eq(DVec3::new(4.0, 0.5, 0.5), 1e-9));
+}
+
+#[test]
+fn raycast_collision_shapes() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    // Rays pass over bottom slabs.
+    let hit = instance
+        .raycast([0.5, 0.75, 2.5], [1.0, 0.0, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(3, 0, 2));
+
+    let hit = instance
+        .raycast([0.5, 0.25, 2.5], [1.0, 0.0, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(2, 0, 2));
+    assert_eq!(hit.face, Direction::West);
+
+    // Rays from above hit the top of slabs.
+    let hit = instance
+        .raycast([2.5, 3.0, 2.5], [0.0, -1.0, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(2, 0, 2));
+    assert_eq!(hit.face, Direction::Up);
+    assert_eq!(hit.point, DVec3::new(2.5, 0.5, 2.5));
+
+    // Fences are hit above the block they are in.
+    let hit = instance
+        .raycast([0.5, 1.25, 6.5], [1.0, 0.0, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(3, 0, 6));
+    assert_eq!(hit.state.to_kind(), BlockKind::OakFence);
+    assert_eq!(hit.face, Direction::West);
+
+    // The post of a fence doesn't fill the block.
+    assert_eq!(
+        instance.raycast([3.1, 0.5, 5.5], [0.0, 0.0, 1.0], 10.0),
+        None
+    );
+}
+
+#[test]
+fn raycast_starting_inside_block() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    let hit = instance
+        .raycast([3.5, 0.5, 0.5], [0.0, 0.0, -1.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(3, 0, 0));
+    assert_eq!(hit.distance, 0.0);
+    assert_eq!(hit.point, DVec3::new(3.5, 0.5, 0.5));
+    assert_eq!(hit.face, Direction::South);
+
+    // Starting on the top face and looking down.
+    let hit = instance
+        .raycast([3.5, 1.0, 0.5], [0.0, -1.0, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(3, 0, 0));
+    assert_eq!(hit.distance, 0.0);
+    assert_eq!(hit.face, Direction::Up);
+
+    // Starting on the top face and looking up.
+    assert_eq!(
+        instance.raycast([3.5, 1.0, 0.5], [0.0, 1.0, 0.0], 10.0),
+        None
+    );
+}
+
+#[test]
+fn raycast_grazing_block_boundaries() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    // Rays along the top of blocks pass over them.
+    assert_eq!(
+        instance.raycast([0.5, 1.0, 0.5], [1.0, 0.0, 0.0], 10.0),
+        None
+    );
+
+    // Rays along the bottom of blocks hit them.
+    let hit = instance
+        .raycast([0.5, 0.0, 0.5], [1.0, 0.0, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(3, 0, 0));
+    assert_eq!(hit.face, Direction::West);
+
+    // Rays along the boundary between two rows of blocks only hit the row
+    // in the positive direction.
+    assert_eq!(
+        instance.raycast([0.5, 0.5, 1.0], [1.0, 0.0, 0.0], 10.0),
+        None
+    );
+
+    let hit = instance
+        .raycast([0.5, 0.25, 2.0], [1.0, 0.0, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(2, 0, 2));
+
+    // The top of the slab is outside of it.
+    let hit = instance
+        .raycast([0.5, 0.5, 2.5], [1.0, 0.0, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(3, 0, 2));
+}
+
+#[test]
+fn raycast_fluids_and_passthrough() {
+    let mut app = setup();
+
+    let instance = app.world.query::<&Instance>().single(&app.world);
+
+    let hit = instance
+        .raycast([0.5, 0.5, 4.5], [1.0, 0.0, 0.0], 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(3, 0, 4));
+
+    let hit = instance
+        .raycast_filtered(
+            [0.5, 0.5, 4.5],
+            [1.0, 0.0, 0.0],
+            10.0,
+            FluidHandling::Ignore,
+            |_, state| state == BlockState::GLASS,
+        )
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(4, 0, 4));
+
+    let hit = instance
+        .raycast_filtered(
+            [0.5, 0.5, 4.5],
+            [1.0, 0.0, 0.0],
+            10.0,
+            FluidHandling::SourceOnly,
+            |_, _| false,
+        )
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(2, 0, 4));
+    assert_eq!(hit.state, BlockState::WATER);
+    assert_eq!(hit.face, Direction::West);
+
+    // Water sources are 8/9 of a block high.
+    let hit = instance
+        .raycast_filtered(
+            [2.5, 3.0, 4.5],
+            [0.0, -1.0, 0.0],
+            10.0,
+            FluidHandling::SourceOnly,
+            |_, _| false,
+        )
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(2, 0, 4));
+    assert!(hit.point.abs_diff_eq(DVec3::new(2.5, 8.0 / 9.0, 4.5), 1e-9));
+
+    // Flowing water is only hit with `FluidHandling::Any`.
+    let source_only = instance.raycast_filtered(
+        [0.5, 0.25, 8.5],
+        [1.0, 0.0, 0.0],
+        10.0,
+        FluidHandling::SourceOnly,
+        |_, _| false,
+    );
+
+    assert_eq!(source_only, None);
+
+    let hit = instance
+        .raycast_filtered(
+            [0.5, 0.25, 8.5],
+            [1.0, 0.0, 0.0],
+            10.0,
+            FluidHandling::Any,
+            |_, _| false,
+        )
+        .unwrap();
+
+    assert_eq!(hit.pos, BlockPos::new(2, 0, 8));
+}
@@ -0,0 +1,82 @@
+use bevy_app::App;
+use valence_client::resource_pack::{ForcedResourcePackPolicy, ResourcePackHash};
+use valence_client::Client;
+use valence_core::packet::c2s::play::ResourcePackStatusC2s;
+use valence_core::packet::s2c::play::{ResourcePackSendS2c, S2cPlayPacket};
+
+use super::*;
+
+const HASH: &str = "d7c6108849fb190ec2a49f2d38b7f1f897d9ce9f";
+
+fn send_pack_and_decline(forced: bool) -> (App, Entity, MockClient) {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.insert_resource(ForcedResourcePackPolicy::Kick(
+        "Resource pack required".into(),
+    ));
+
+    app.update();
+    client_helper.clear_sent();
+
+    let hash: ResourcePackHash = HASH.parse().unwrap();
+
+    app.world
+        .get_mut::<Client>(client_ent)
+        .unwrap()
+        .set_resource_pack("https://example.com/pack.zip", hash, forced, None);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ResourcePackSendS2c(ResourcePackSendS2c { hash: HASH, .. })
+    );
+
+    client_helper.send(&ResourcePackStatusC2s::Declined);
+    app.update();
+
+    (app, client_ent, client_helper)
+}
+
+#[test]
+fn test_forced_resource_pack_declined() {
+    let (app, client_ent, mut client_helper) = send_pack_and_decline(true);
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::DisconnectS2c(_));
+    assert!(app.world.get::<Client>(client_ent).is_none());
+}
+
+#[test]
+fn test_optional_resource_pack_declined() {
+    let (app, client_ent, mut client_helper) = send_pack_and_decline(false);
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::DisconnectS2c(_));
+    assert!(app.world.get::<Client>(client_ent).is_some());
+}
+
+#[test]
+fn test_resource_pack_hash() {
+    let hash: ResourcePackHash = HASH.to_uppercase().parse().unwrap();
+
+    assert_eq!(hash.to_string(), HASH);
+    assert_eq!(ResourcePackHash::try_from(&hash.0[..]).unwrap(), hash);
+
+    assert!(ResourcePackHash::try_from(&hash.0[1..]).is_err());
+    assert!(HASH[1..].parse::<ResourcePackHash>().is_err());
+    assert!(HASH.replace('d', "g").parse::<ResourcePackHash>().is_err());
+}
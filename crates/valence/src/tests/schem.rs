@@ -0,0 +1,115 @@
+use valence_block::{BlockState, PropName, PropValue};
+use valence_instance::{Block, Chunk};
+use valence_nbt::{compound, List};
+use valence_schem::{Mirror, PasteOptions, Rotation, Schematic};
+
+use super::*;
+
+/// A schematic two blocks wide and two blocks high, with stairs facing north
+/// and a chest at the bottom and air at the top.
+fn stairs_and_chest() -> Schematic {
+    let nbt = compound! {
+        "Version" => 2,
+        "DataVersion" => 3337,
+        "Width" => 2_i16,
+        "Height" => 2_i16,
+        "Length" => 1_i16,
+        "Offset" => vec![0, 0, 0],
+        "Palette" => compound! {
+            "minecraft:oak_stairs[facing=north,half=bottom,shape=straight,waterlogged=false]" => 0,
+            "minecraft:chest[facing=north,type=single,waterlogged=false]" => 1,
+            "minecraft:air" => 2,
+        },
+        "BlockData" => vec![0_i8, 1, 2, 2],
+        "BlockEntities" => List::Compound(vec![compound! {
+            "Pos" => vec![1, 0, 0],
+            "Id" => "minecraft:chest",
+            "CustomName" => r#"{"text":"Loot"}"#,
+        }]),
+    };
+
+    Schematic::from_nbt(&nbt, BlockState::STONE).unwrap()
+}
+
+#[test]
+fn paste_rotated_schematic() {
+    let Scenario { mut app, .. } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.insert_chunk([0, 0], Chunk::default());
+    instance.set_block([4, 11, 4], BlockState::STONE);
+    instance.set_block([4, 11, 5], BlockState::STONE);
+
+    stairs_and_chest().paste(
+        &mut instance,
+        [4, 10, 4],
+        PasteOptions {
+            rotation: Rotation::Clockwise90,
+            skip_air: true,
+            ..Default::default()
+        },
+    );
+
+    // The schematic extends along the X axis, which becomes the Z axis.
+    assert_eq!(
+        instance.block([4, 10, 4]).unwrap().state(),
+        BlockState::OAK_STAIRS.set(PropName::Facing, PropValue::East)
+    );
+
+    let chest = instance.block([4, 10, 5]).unwrap();
+    assert_eq!(
+        chest.state(),
+        BlockState::CHEST.set(PropName::Facing, PropValue::East)
+    );
+    assert_eq!(
+        chest.nbt(),
+        Some(&compound! { "CustomName" => r#"{"text":"Loot"}"# })
+    );
+
+    // Air is skipped.
+    assert_eq!(
+        instance.block([4, 11, 4]).unwrap().state(),
+        BlockState::STONE
+    );
+    assert_eq!(
+        instance.block([4, 11, 5]).unwrap().state(),
+        BlockState::STONE
+    );
+}
+
+#[test]
+fn paste_mirrored_schematic() {
+    let Scenario { mut app, .. } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.insert_chunk([0, 0], Chunk::default());
+    instance.set_block([3, 11, 4], Block::new(BlockState::STONE));
+
+    stairs_and_chest().paste(
+        &mut instance,
+        [4, 10, 4],
+        PasteOptions {
+            mirror: Mirror::FrontBack,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        instance.block([4, 10, 4]).unwrap().state(),
+        BlockState::OAK_STAIRS.set(PropName::Facing, PropValue::North)
+    );
+    assert_eq!(
+        instance.block([3, 10, 4]).unwrap().state(),
+        BlockState::CHEST.set(PropName::Facing, PropValue::North)
+    );
+    // Air replaces the blocks in the instance by default.
+    assert_eq!(instance.block([3, 11, 4]).unwrap().state(), BlockState::AIR);
+}
@@ -0,0 +1,182 @@
+use valence_core::despawn::Despawned;
+use valence_core::packet::s2c::play::scoreboard_objective_update::Mode;
+use valence_core::packet::s2c::play::scoreboard_player_update::Action;
+use valence_core::packet::s2c::play::{
+    S2cPlayPacket, ScoreboardDisplayS2c, ScoreboardObjectiveUpdateS2c, ScoreboardPlayerUpdateS2c,
+};
+use valence_core::packet::var_int::VarInt;
+use valence_scoreboard::{
+    DisplaySlot, ObjectiveBundle, ObjectiveDisplaySlot, ObjectiveName, ObjectiveScores,
+    ObjectiveViewers, OldObjectiveScores,
+};
+
+use super::*;
+
+#[test]
+fn test_objective_viewed_by_client() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let objective = app
+        .world
+        .spawn(ObjectiveBundle {
+            name: ObjectiveName("stats".into()),
+            display_slot: ObjectiveDisplaySlot(Some(DisplaySlot::Sidebar)),
+            scores: ObjectiveScores([("Kills".into(), 3), ("Deaths".into(), 1)].into()),
+            viewers: ObjectiveViewers {
+                clients: [client_ent].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .id();
+
+    // The objective is sent once the client has joined, before its scores
+    // and display slot.
+    app.update();
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ScoreboardObjectiveUpdateS2c(_)
+    );
+    assert_packet_count!(sent_packets, 2, S2cPlayPacket::ScoreboardPlayerUpdateS2c(_));
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::ScoreboardDisplayS2c(_));
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::ScoreboardObjectiveUpdateS2c(ScoreboardObjectiveUpdateS2c {
+            objective_name: "stats",
+            mode: Mode::Create { .. }
+        }),
+        S2cPlayPacket::ScoreboardPlayerUpdateS2c(ScoreboardPlayerUpdateS2c {
+            action: Action::Update { .. },
+            ..
+        }),
+        S2cPlayPacket::ScoreboardDisplayS2c(ScoreboardDisplayS2c {
+            position: DisplaySlot::Sidebar,
+            score_name: "stats"
+        })
+    );
+
+    // Only the changed scores are sent.
+    let mut obj = app.world.entity_mut(objective);
+    let mut scores = obj.get_mut::<ObjectiveScores>().unwrap();
+    scores.0.insert("Kills".into(), 4);
+    scores.0.remove("Deaths");
+    obj.insert(ObjectiveDisplaySlot(Some(DisplaySlot::List)));
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 2, S2cPlayPacket::ScoreboardPlayerUpdateS2c(_));
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ScoreboardPlayerUpdateS2c(ScoreboardPlayerUpdateS2c {
+            entity_name: "Kills",
+            action: Action::Update {
+                objective_score: VarInt(4),
+                ..
+            },
+        })
+    );
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ScoreboardPlayerUpdateS2c(ScoreboardPlayerUpdateS2c {
+            entity_name: "Deaths",
+            action: Action::Remove { .. },
+        })
+    );
+
+    // The sidebar is cleared and the objective is moved to the list.
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::ScoreboardDisplayS2c(ScoreboardDisplayS2c {
+            position: DisplaySlot::Sidebar,
+            score_name: ""
+        }),
+        S2cPlayPacket::ScoreboardDisplayS2c(ScoreboardDisplayS2c {
+            position: DisplaySlot::List,
+            score_name: "stats"
+        })
+    );
+
+    assert_eq!(
+        app.world
+            .get::<OldObjectiveScores>(objective)
+            .unwrap()
+            .get()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn test_objective_viewed_by_instance() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    let objective = app
+        .world
+        .spawn(ObjectiveBundle {
+            name: ObjectiveName("kills".into()),
+            viewers: ObjectiveViewers {
+                instance: Some(instance_ent),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ScoreboardObjectiveUpdateS2c(ScoreboardObjectiveUpdateS2c {
+            mode: Mode::Create { .. },
+            ..
+        })
+    );
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::ScoreboardDisplayS2c(_));
+
+    // The objective is removed from the client's scoreboard.
+    app.world.entity_mut(objective).insert(Despawned);
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ScoreboardObjectiveUpdateS2c(ScoreboardObjectiveUpdateS2c {
+            objective_name: "kills",
+            mode: Mode::Remove
+        })
+    );
+    assert!(app.world.get_entity(objective).is_none());
+}
@@ -0,0 +1,66 @@
+use valence_client::settings::{
+    Arm, ChatMode, ClientSettings, ClientSettingsChange, DisplayedSkinParts, MainArm,
+    PlayerModelParts,
+};
+use valence_client::ViewDistance;
+use valence_core::packet::c2s::play::ClientSettingsC2s;
+
+use super::*;
+
+#[test]
+fn test_client_settings() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    let skin_parts = DisplayedSkinParts::new().with_cape(true).with_hat(true);
+
+    let pkt = ClientSettingsC2s {
+        locale: "en_us",
+        view_distance: 12,
+        chat_mode: ChatMode::Enabled,
+        chat_colors: true,
+        displayed_skin_parts: skin_parts,
+        main_arm: Arm::Left,
+        enable_text_filtering: false,
+        allow_server_listings: true,
+    };
+
+    client_helper.send(&pkt);
+
+    app.update();
+
+    let settings = app.world.get::<ClientSettings>(client_ent).unwrap();
+
+    assert_eq!(&*settings.locale, "en_us");
+    assert_eq!(settings.view_distance, 12);
+    assert_eq!(settings.chat_mode, ChatMode::Enabled);
+    assert_eq!(settings.displayed_skin_parts, skin_parts);
+    assert_eq!(settings.main_arm, Arm::Left);
+
+    assert_eq!(app.world.get::<ViewDistance>(client_ent).unwrap().get(), 12);
+    assert_eq!(
+        app.world.get::<PlayerModelParts>(client_ent).unwrap().0,
+        u8::from(skin_parts) as i8
+    );
+    assert_eq!(app.world.get::<MainArm>(client_ent).unwrap().0, 0);
+
+    let events = app.world.resource::<Events<ClientSettingsChange>>();
+    let events = events.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(events, [&ClientSettingsChange { client: client_ent }]);
+
+    // Sending the same settings again is not a change.
+    client_helper.send(&pkt);
+
+    app.update();
+
+    let events = app.world.resource::<Events<ClientSettingsChange>>();
+
+    assert_eq!(events.iter_current_update_events().count(), 0);
+}
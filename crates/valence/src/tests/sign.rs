@@ -0,0 +1,59 @@
+use valence_client::sign::{UpdateSign, MAX_SIGN_LINE_LEN};
+use valence_core::block_pos::BlockPos;
+use valence_core::packet::c2s::play::UpdateSignC2s;
+use valence_core::text::Text;
+use valence_nbt::{compound, Value};
+
+use super::*;
+
+#[test]
+fn test_update_sign() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    let position = BlockPos::new(1, 2, 3);
+
+    client_helper.send(&UpdateSignC2s {
+        position,
+        lines: ["§cRed", "Tab\tbed", "", "Plain"],
+    });
+
+    // Lines longer than the limit are rejected.
+    let too_long = "a".repeat(MAX_SIGN_LINE_LEN + 1);
+
+    client_helper.send(&UpdateSignC2s {
+        position,
+        lines: ["", &too_long, "", ""],
+    });
+
+    app.update();
+
+    let events = app.world.resource::<Events<UpdateSign>>();
+    let events = events.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        *events[0],
+        UpdateSign {
+            client: client_ent,
+            position,
+            lines: ["Red".into(), "Tabbed".into(), "".into(), "Plain".into()],
+        }
+    );
+
+    let mut nbt = compound! {
+        "Color" => "red",
+    };
+
+    events[0].write_nbt(&mut nbt);
+
+    assert_eq!(nbt.get("Color"), Some(&Value::String("red".into())));
+    assert_eq!(nbt.get("Text1"), Some(&Value::from(Text::text("Red"))));
+    assert_eq!(nbt.get("Text4"), Some(&Value::from(Text::text("Plain"))));
+}
@@ -0,0 +1,126 @@
+use valence_client::Client;
+use valence_core::packet::s2c::play::play_sound::SoundId;
+use valence_core::packet::s2c::play::{PlaySoundS2c, S2cPlayPacket, StopSoundS2c};
+use valence_core::sound::{Sound, SoundCategory};
+use valence_instance::Chunk;
+
+use super::*;
+
+#[test]
+fn client_play_sound_with_seed() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    let mut client = app.world.get_mut::<Client>(client_ent).unwrap();
+
+    client.play_sound_with_seed(
+        ident!("my_pack:custom.sound"),
+        SoundCategory::Record,
+        [1.0, 2.0, 3.0],
+        0.5,
+        2.0,
+        42,
+    );
+    client.stop_sound(Some(SoundCategory::Record), None);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlaySoundS2c(_),
+        S2cPlayPacket::StopSoundS2c(_)
+    );
+
+    for pkt in sent_packets {
+        match pkt {
+            S2cPlayPacket::PlaySoundS2c(pkt) => {
+                assert_eq!(
+                    pkt.id,
+                    SoundId::Direct {
+                        id: ident!("my_pack:custom.sound").into(),
+                        range: None
+                    }
+                );
+                assert_eq!(pkt.category, SoundCategory::Record);
+                assert_eq!(pkt.position, [8, 16, 24].into());
+                assert_eq!(pkt.seed, 42);
+            }
+            S2cPlayPacket::StopSoundS2c(pkt) => {
+                assert_eq!(
+                    pkt,
+                    StopSoundS2c {
+                        source: Some(SoundCategory::Record),
+                        sound: None
+                    }
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn instance_play_sound() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.insert_chunk([0, 0], Chunk::default());
+
+    app.update();
+    client_helper.clear_sent();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.play_sound_with_seed(
+        Sound::BlockBellUse,
+        SoundCategory::Block,
+        [4.0, 5.0, 6.0],
+        1.0,
+        1.0,
+        7,
+    );
+
+    // Not audible because the chunk does not exist.
+    instance.play_sound(
+        Sound::BlockBellUse,
+        SoundCategory::Block,
+        [1000.0, 0.0, 1000.0],
+        1.0,
+        1.0,
+    );
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::PlaySoundS2c(_));
+
+    for pkt in sent_packets {
+        if let S2cPlayPacket::PlaySoundS2c(PlaySoundS2c { id, seed, .. }) = pkt {
+            assert_eq!(id, Sound::BlockBellUse.to_id());
+            assert_eq!(seed, 7);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use valence_block::BlockKind;
+use valence_client::misc::RequestStats;
+use valence_client::statistics::{Statistic, Statistics};
+use valence_core::item::ItemKind;
+use valence_core::packet::c2s::play::ClientStatusC2s;
+use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_core::statistic::CustomStatistic;
+
+use super::*;
+
+#[test]
+fn test_statistics_sent_on_request() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    let mut stats = app.world.get_mut::<Statistics>(client_ent).unwrap();
+    stats.increment(CustomStatistic::Jump, 3);
+    stats.increment(CustomStatistic::Jump, 2);
+    stats.set(Statistic::Mined(BlockKind::Stone), 7);
+    stats.set(Statistic::Crafted(ItemKind::Stick), 0);
+
+    app.update();
+
+    // Nothing is sent until the client asks.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::StatisticsS2c(_));
+
+    client_helper.send(&ClientStatusC2s::RequestStats);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::StatisticsS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::StatisticsS2c(pkt) = pkt {
+            let mut stats = pkt
+                .statistics
+                .iter()
+                .map(|s| (s.category_id.0, s.statistic_id.0, s.value.0))
+                .collect::<Vec<_>>();
+
+            stats.sort();
+
+            assert_eq!(
+                stats,
+                [
+                    (0, BlockKind::Stone.to_raw() as i32, 7),
+                    (8, CustomStatistic::Jump.to_raw() as i32, 5),
+                ]
+            );
+        }
+    }
+}
+
+#[test]
+fn test_statistics_populated_on_request() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    fn load_stats(mut events: EventReader<RequestStats>, mut clients: Query<&mut Statistics>) {
+        for event in events.iter() {
+            let mut stats = clients.get_mut(event.client).unwrap();
+            stats.set(CustomStatistic::Deaths, 42);
+        }
+    }
+
+    app.add_system(load_stats);
+
+    app.update();
+    client_helper.clear_sent();
+
+    client_helper.send(&ClientStatusC2s::RequestStats);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::StatisticsS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::StatisticsS2c(pkt) = pkt {
+            assert_eq!(pkt.statistics.len(), 1);
+            assert_eq!(pkt.statistics[0].value.0, 42);
+        }
+    }
+}
+
+#[test]
+fn test_custom_statistic_names() {
+    for stat in CustomStatistic::ALL {
+        assert_eq!(CustomStatistic::from_str(stat.to_str()), Some(stat));
+        assert_eq!(CustomStatistic::from_raw(stat.to_raw()), Some(stat));
+    }
+
+    assert_eq!(CustomStatistic::Jump.to_str(), "jump");
+}
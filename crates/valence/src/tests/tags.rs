@@ -0,0 +1,60 @@
+use valence_block::BlockKind;
+use valence_core::packet::s2c::play::{S2cPlayPacket, SynchronizeTagsS2c};
+use valence_registry::TagsRegistry;
+
+use super::*;
+
+#[test]
+fn tags_sent_on_join_and_change() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::GameJoinS2c(_),
+        S2cPlayPacket::SynchronizeTagsS2c(_)
+    );
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::SynchronizeTagsS2c(_));
+
+    // Tags are not sent again while they are unchanged.
+    app.update();
+
+    client_helper
+        .collect_sent()
+        .assert_not_contains::<SynchronizeTagsS2c>();
+
+    app.world.resource_mut::<TagsRegistry>().insert(
+        TagsRegistry::BLOCK,
+        ident!("custom:ground"),
+        [BlockKind::Dirt.to_raw() as i32],
+    );
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let tags = sent.of::<SynchronizeTagsS2c>();
+
+    assert_eq!(tags.len(), 1);
+
+    let custom = tags[0]
+        .tags
+        .iter()
+        .find(|group| group.kind.as_str() == "minecraft:block")
+        .and_then(|group| {
+            group
+                .tags
+                .iter()
+                .find(|tag| tag.name.as_str() == "custom:ground")
+        })
+        .expect("missing custom tag");
+
+    assert_eq!(custom.entries, [VarInt(BlockKind::Dirt.to_raw() as i32)]);
+}
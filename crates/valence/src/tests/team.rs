@@ -0,0 +1,119 @@
+use valence_core::despawn::Despawned;
+use valence_core::packet::s2c::play::team::{Mode, TeamS2c};
+use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_core::uuid::UniqueId;
+use valence_entity::cow::CowEntityBundle;
+use valence_scoreboard::team::{TeamBundle, TeamColor, TeamDisplay, TeamMembers, TeamName};
+
+use super::*;
+
+#[test]
+fn test_team_members() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    let cow_ent = app
+        .world
+        .spawn(CowEntityBundle {
+            location: Location(instance_ent),
+            ..Default::default()
+        })
+        .id();
+
+    let cow_uuid = app.world.get::<UniqueId>(cow_ent).unwrap().0.to_string();
+
+    let team = app
+        .world
+        .spawn(TeamBundle {
+            name: TeamName("red".into()),
+            members: TeamMembers([client_ent, cow_ent].into()),
+            ..Default::default()
+        })
+        .id();
+
+    // The team is sent once the client has joined, along with all of its
+    // members.
+    app.update();
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::TeamS2c(_));
+
+    let mut created = sent_packets
+        .iter()
+        .find_map(|p| match p {
+            S2cPlayPacket::TeamS2c(TeamS2c {
+                team_name: "red",
+                mode: Mode::CreateTeam { entities, .. },
+            }) => Some(entities.clone()),
+            _ => None,
+        })
+        .unwrap();
+
+    created.sort_unstable();
+
+    assert_eq!(created, [cow_uuid.as_str(), "test"]);
+
+    // Despawned members are removed from the team.
+    app.world.entity_mut(cow_ent).insert(Despawned);
+    app.world.get_mut::<TeamDisplay>(team).unwrap().color = TeamColor::Red;
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 2, S2cPlayPacket::TeamS2c(_));
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::TeamS2c(TeamS2c {
+            mode: Mode::UpdateTeamInfo {
+                team_color: TeamColor::Red,
+                ..
+            },
+            ..
+        })
+    );
+
+    let removed = sent_packets.iter().find_map(|p| match p {
+        S2cPlayPacket::TeamS2c(TeamS2c {
+            mode: Mode::RemoveEntities { entities },
+            ..
+        }) => Some(entities.clone()),
+        _ => None,
+    });
+
+    assert_eq!(removed, Some(vec![cow_uuid.as_str()]));
+    assert_eq!(
+        app.world.get::<TeamMembers>(team).unwrap().0,
+        [client_ent].into()
+    );
+
+    // The team is removed from the client's screen.
+    app.world.entity_mut(team).insert(Despawned);
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::TeamS2c(TeamS2c {
+            team_name: "red",
+            mode: Mode::RemoveTeam
+        })
+    );
+}
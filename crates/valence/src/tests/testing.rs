@@ -0,0 +1,105 @@
+use std::collections::BTreeSet;
+
+use glam::DVec3;
+use valence_core::chunk_pos::ChunkPos;
+use valence_core::packet::c2s::play::{PositionAndOnGround, TeleportConfirmC2s};
+use valence_core::packet::s2c::play::{
+    ChunkDataS2c, GameJoinS2c, PlayerPositionLookS2c, PlayerSpawnS2c, S2cPlayPacket, UnloadChunkS2c,
+};
+use valence_entity::Position;
+
+use crate::testing::ScenarioBuilder;
+
+#[test]
+fn scenario_client_joins_and_receives_chunks() {
+    let mut scenario = ScenarioBuilder::new().with_chunk_radius(2).build();
+
+    scenario.tick();
+
+    let sent = scenario.mock_client.collect_sent();
+
+    sent.assert_count::<GameJoinS2c>(1);
+    sent.assert_count::<ChunkDataS2c>(16);
+    sent.assert_not_contains::<UnloadChunkS2c>();
+    assert!(sent.position::<GameJoinS2c>() < sent.position::<ChunkDataS2c>());
+
+    let chunks: BTreeSet<_> = sent
+        .of::<ChunkDataS2c>()
+        .into_iter()
+        .map(|pkt| pkt.pos)
+        .collect();
+
+    let expected: BTreeSet<_> = (-2..2)
+        .flat_map(|z| (-2..2).map(move |x| ChunkPos::new(x, z)))
+        .collect();
+
+    assert_eq!(chunks, expected);
+
+    // Nothing else is sent until the next tick.
+    assert!(scenario.mock_client.collect_sent().is_empty());
+}
+
+#[test]
+fn scenario_injects_packets() {
+    let mut scenario = ScenarioBuilder::new().build();
+
+    scenario.tick();
+
+    let teleport = scenario
+        .mock_client
+        .collect_sent()
+        .first::<PlayerPositionLookS2c>()
+        .expect("no teleport was sent on join");
+
+    scenario.mock_client.send(&TeleportConfirmC2s {
+        teleport_id: teleport.teleport_id,
+    });
+
+    scenario.mock_client.send(&PositionAndOnGround {
+        position: DVec3::new(12.0, 64.0, 0.0),
+        on_ground: true,
+    });
+
+    scenario.tick();
+
+    let pos = scenario.app.world.get::<Position>(scenario.client).unwrap();
+    assert_eq!(pos.0, DVec3::new(12.0, 64.0, 0.0));
+}
+
+#[test]
+fn scenario_clients_see_each_other() {
+    let mut scenario = ScenarioBuilder::new()
+        .with_position([1.0, 0.0, 1.0])
+        .build();
+
+    let (_, mut other) = scenario.add_client("other", [2.0, 0.0, 2.0]);
+
+    scenario.tick_n(2);
+
+    let sent = other.collect_sent();
+
+    let spawn = sent
+        .first::<PlayerSpawnS2c>()
+        .expect("the other client was not spawned");
+
+    assert_eq!(spawn.position, DVec3::new(1.0, 0.0, 1.0));
+
+    // Untyped packets can still be matched on.
+    assert!(sent
+        .all()
+        .iter()
+        .any(|pkt| matches!(pkt, S2cPlayPacket::GameJoinS2c(_))));
+}
+
+#[test]
+#[should_panic(expected = "expected a")]
+fn scenario_assert_contains_fails() {
+    let mut scenario = ScenarioBuilder::new().build();
+
+    scenario.tick();
+
+    scenario
+        .mock_client
+        .collect_sent()
+        .assert_contains::<PlayerSpawnS2c>();
+}
@@ -0,0 +1,89 @@
+use valence_client::Client;
+use valence_core::packet::s2c::play::{
+    ClearTitleS2c, S2cPlayPacket, SubtitleS2c, TitleFadeS2c, TitleS2c,
+};
+
+use super::*;
+
+fn assert_title_packets(sent_packets: Vec<S2cPlayPacket>) {
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::TitleS2c(_));
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::SubtitleS2c(_));
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::TitleFadeS2c(_));
+
+    // The title is sent last, otherwise the subtitle and times would only apply
+    // to the next title.
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::TitleFadeS2c(TitleFadeS2c {
+            fade_in: 5,
+            stay: 40,
+            fade_out: 5
+        }),
+        S2cPlayPacket::SubtitleS2c(SubtitleS2c { .. }),
+        S2cPlayPacket::TitleS2c(TitleS2c { .. })
+    );
+}
+
+#[test]
+fn test_client_title() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    let mut client = app.world.get_mut::<Client>(client_ent).unwrap();
+    client.set_title_times(5, 40, 5);
+    client.set_title("Title");
+    client.set_subtitle("Subtitle");
+
+    app.update();
+
+    assert_title_packets(client_helper.collect_sent().all());
+
+    // Clearing the title discards the title which was not sent yet.
+    let mut client = app.world.get_mut::<Client>(client_ent).unwrap();
+    client.set_title("Title");
+    client.clear_title();
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::TitleS2c(_));
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::ClearTitleS2c(ClearTitleS2c { reset: false })
+    );
+}
+
+#[test]
+fn test_instance_title() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    let mut instance = app
+        .world
+        .query::<&mut Instance>()
+        .single_mut(&mut app.world);
+
+    instance.set_title_times(5, 40, 5);
+    instance.set_title("Title");
+    instance.set_subtitle("Subtitle");
+
+    app.update();
+
+    assert_title_packets(client_helper.collect_sent().all());
+}
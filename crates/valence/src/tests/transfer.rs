@@ -0,0 +1,172 @@
+use bevy_app::App;
+use glam::DVec3;
+use valence_client::Client;
+use valence_core::ident::Ident;
+use valence_core::packet::s2c::play::{PlayerRespawnS2c, S2cPlayPacket};
+use valence_entity::{Look, Position};
+use valence_instance::Chunk;
+
+use super::*;
+
+fn respawn_dimension_names(sent_packets: &[S2cPlayPacket]) -> Vec<String> {
+    sent_packets
+        .iter()
+        .filter_map(|pkt| match pkt {
+            S2cPlayPacket::PlayerRespawnS2c(PlayerRespawnS2c { dimension_name, .. }) => {
+                Some(dimension_name.to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Spawns an instance with a few chunks around the origin.
+fn spawn_instance(app: &mut App, dimension_type_name: Ident<&'static str>) -> Entity {
+    let server = app.world.resource::<Server>();
+    let mut instance = Instance::new_unit_testing(dimension_type_name, server);
+
+    for z in -2..2 {
+        for x in -2..2 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    app.world.spawn(instance).id()
+}
+
+#[test]
+fn transfer_to_other_dimension_type() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    let nether = spawn_instance(&mut app, ident!("the_nether"));
+
+    let look = Look::new(90.0, 10.0);
+
+    app.world
+        .get_mut::<Client>(client_ent)
+        .unwrap()
+        .transfer_to(nether, [8.0, 64.0, 8.0], look);
+
+    app.update();
+
+    let client = app.world.entity(client_ent);
+
+    assert_eq!(client.get::<Location>().unwrap().0, nether);
+    assert_eq!(
+        client.get::<Position>().unwrap().get(),
+        DVec3::new(8.0, 64.0, 8.0)
+    );
+    assert_eq!(*client.get::<Look>().unwrap(), look);
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_eq!(
+        respawn_dimension_names(&sent_packets),
+        ["minecraft:the_nether"]
+    );
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlayerRespawnS2c(_),
+        S2cPlayPacket::ChunkRenderDistanceCenterS2c(_),
+        S2cPlayPacket::ChunkDataS2c(_),
+        S2cPlayPacket::PlayerPositionLookS2c(_)
+    );
+}
+
+#[test]
+fn transfer_to_same_dimension_type() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    let other = spawn_instance(&mut app, ident!("overworld"));
+
+    app.world
+        .get_mut::<Client>(client_ent)
+        .unwrap()
+        .transfer_to(other, [0.0, 64.0, 0.0], Look::default());
+
+    app.update();
+
+    assert_eq!(app.world.get::<Location>(client_ent).unwrap().0, other);
+
+    // The client is respawned in another dimension first so that it forgets
+    // the old instance.
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_eq!(
+        respawn_dimension_names(&sent_packets),
+        ["valence:transfer", "minecraft:overworld"]
+    );
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlayerRespawnS2c(_),
+        S2cPlayPacket::ChunkDataS2c(_),
+        S2cPlayPacket::PlayerPositionLookS2c(_)
+    );
+
+    // Respawning in the same instance doesn't need another dimension.
+    app.world
+        .get_mut::<Client>(client_ent)
+        .unwrap()
+        .transfer_to(other, [1.0, 64.0, 1.0], Look::default());
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_eq!(
+        respawn_dimension_names(&sent_packets),
+        ["minecraft:overworld"]
+    );
+}
+
+#[test]
+fn transfer_to_nonexistent_instance() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+    client_helper.clear_sent();
+
+    let instance_ent = app.world.get::<Location>(client_ent).unwrap().0;
+    let nonexistent = app.world.spawn_empty().id();
+
+    app.world
+        .get_mut::<Client>(client_ent)
+        .unwrap()
+        .transfer_to(nonexistent, [0.0, 64.0, 0.0], Look::default());
+
+    app.update();
+
+    assert_eq!(
+        app.world.get::<Location>(client_ent).unwrap().0,
+        instance_ent
+    );
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::PlayerRespawnS2c(_));
+}
@@ -0,0 +1,105 @@
+use glam::DVec3;
+use valence_client::vehicle::{ClientControlledVehicle, VehicleInput, VehicleMove};
+use valence_core::packet::c2s::play::player_input::Flags;
+use valence_core::packet::c2s::play::{PlayerInputC2s, VehicleMoveC2s};
+use valence_entity::horse::HorseEntityBundle;
+use valence_entity::{Look, Position};
+
+use super::*;
+
+#[test]
+fn test_vehicle_input() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    app.update();
+
+    client_helper.send(&PlayerInputC2s {
+        sideways: 0.5,
+        forward: -1.0,
+        flags: Flags::new().with_jump(true),
+    });
+
+    app.update();
+
+    let events = app.world.resource::<Events<VehicleInput>>();
+    let events = events.iter_current_update_events().collect::<Vec<_>>();
+
+    assert_eq!(
+        events,
+        [&VehicleInput {
+            client: client_ent,
+            sideways: 0.5,
+            forward: -1.0,
+            jumping: true,
+            dismounting: false,
+        }]
+    );
+}
+
+#[test]
+fn test_vehicle_move() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app.world.get::<Location>(client_ent).unwrap().0;
+
+    let horse_ent = app
+        .world
+        .spawn(HorseEntityBundle {
+            location: Location(instance_ent),
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+
+    let move_pkt = VehicleMoveC2s {
+        position: DVec3::new(1.0, 2.0, 3.0),
+        yaw: 90.0,
+        pitch: 0.0,
+    };
+
+    // Without the component, only the event is sent.
+    client_helper.send(&move_pkt);
+
+    app.update();
+
+    let events = app.world.resource::<Events<VehicleMove>>();
+
+    assert_eq!(events.iter_current_update_events().count(), 1);
+    assert_eq!(app.world.get::<Position>(horse_ent).unwrap().0, DVec3::ZERO);
+    assert_eq!(
+        app.world.get::<Position>(client_ent).unwrap().0,
+        DVec3::ZERO
+    );
+
+    // With the component, the vehicle follows the client.
+    app.world
+        .entity_mut(client_ent)
+        .insert(ClientControlledVehicle(horse_ent));
+
+    client_helper.send(&move_pkt);
+
+    app.update();
+
+    let horse = app.world.entity(horse_ent);
+
+    assert_eq!(
+        horse.get::<Position>().unwrap().0,
+        DVec3::new(1.0, 2.0, 3.0)
+    );
+    assert_eq!(horse.get::<Look>().unwrap().yaw, 90.0);
+    assert_eq!(
+        app.world.get::<Position>(client_ent).unwrap().0,
+        DVec3::ZERO
+    );
+}
@@ -1,4 +1,3 @@
-use bevy_app::App;
 use valence_client::weather::{Rain, Thunder};
 use valence_client::Client;
 use valence_core::packet::s2c::play::game_state_change::GameEventKind;
@@ -48,8 +47,11 @@ fn assert_weather_packets(sent_packets: Vec<S2cPlayPacket>) {
 
 #[test]
 fn test_weather_instance() {
-    let mut app = App::new();
-    let (_, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     // Process a tick to get past the "on join" logic.
     app.update();
@@ -88,15 +90,19 @@ fn test_weather_instance() {
     }
 
     // Make assertions.
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
 
     assert_weather_packets(sent_packets);
 }
 
 #[test]
 fn test_weather_client() {
-    let mut app = App::new();
-    let (_, mut client_helper) = scenario_single_client(&mut app);
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
 
     // Process a tick to get past the "on join" logic.
     app.update();
@@ -135,7 +141,96 @@ fn test_weather_client() {
     }
 
     // Make assertions.
-    let sent_packets = client_helper.collect_sent();
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
 
     assert_weather_packets(sent_packets);
 }
+
+#[test]
+fn test_weather_resent_on_respawn() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    let server = app.world.resource::<Server>();
+    let instance = Instance::new_unit_testing(ident!("overworld"), server);
+    let new_instance_ent = app.world.spawn((instance, Rain(0.25), Thunder(0.75))).id();
+
+    // Move the client to the rainy instance, which respawns the client.
+    app.world.get_mut::<Location>(client_ent).unwrap().0 = new_instance_ent;
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlayerRespawnS2c(_),
+        S2cPlayPacket::GameStateChangeS2c(GameStateChangeS2c {
+            kind: GameEventKind::BeginRaining,
+            value: _
+        }),
+        S2cPlayPacket::GameStateChangeS2c(GameStateChangeS2c {
+            kind: GameEventKind::RainLevelChange,
+            value: _
+        }),
+        S2cPlayPacket::GameStateChangeS2c(GameStateChangeS2c {
+            kind: GameEventKind::ThunderLevelChange,
+            value: _
+        })
+    );
+
+    let levels: Vec<_> = sent_packets
+        .iter()
+        .filter_map(|p| match p {
+            S2cPlayPacket::GameStateChangeS2c(pkt) => Some((pkt.kind, pkt.value)),
+            _ => None,
+        })
+        .collect();
+
+    assert!(levels.contains(&(GameEventKind::RainLevelChange, 0.25)));
+    assert!(levels.contains(&(GameEventKind::ThunderLevelChange, 0.75)));
+
+    // The client's own weather takes priority over the instance's.
+    app.world.entity_mut(client_ent).insert(Rain(1.0));
+    app.update();
+    client_helper.clear_sent();
+
+    let first_instance_ent = app
+        .world
+        .iter_entities()
+        .find(|e| e.contains::<Instance>() && e.id() != new_instance_ent)
+        .expect("could not find instance")
+        .id();
+
+    app.world.get_mut::<Location>(client_ent).unwrap().0 = first_instance_ent;
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        1,
+        S2cPlayPacket::GameStateChangeS2c(GameStateChangeS2c {
+            kind: GameEventKind::RainLevelChange,
+            value: _
+        })
+    );
+    assert_packet_count!(
+        sent_packets,
+        0,
+        S2cPlayPacket::GameStateChangeS2c(GameStateChangeS2c {
+            kind: GameEventKind::ThunderLevelChange,
+            value: _
+        })
+    );
+}
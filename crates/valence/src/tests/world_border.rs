@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use valence_client::world_border::{WorldBorder, DEFAULT_DIAMETER};
+use valence_core::packet::s2c::play::S2cPlayPacket;
+
+use super::*;
+
+#[test]
+fn world_border_initialized_on_join() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    let mut border = WorldBorder::new([10.0, -5.0], 100.0);
+    border.set_diameter_over(50.0, Duration::from_secs(60));
+    app.world.entity_mut(instance_ent).insert(border);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::WorldBorderInitializeS2c(_));
+    assert_packet_count!(
+        sent_packets,
+        0,
+        S2cPlayPacket::WorldBorderInterpolateSizeS2c(_)
+    );
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::GameJoinS2c(_),
+        S2cPlayPacket::WorldBorderInitializeS2c(_)
+    );
+
+    for pkt in sent_packets {
+        if let S2cPlayPacket::WorldBorderInitializeS2c(pkt) = pkt {
+            assert_eq!((pkt.x, pkt.z), (10.0, -5.0));
+            assert!(pkt.old_diameter <= 100.0 && pkt.old_diameter > 50.0);
+            assert_eq!(pkt.new_diameter, 50.0);
+            // The speed is the remaining time in milliseconds.
+            assert!(pkt.speed.0 <= 60_000 && pkt.speed.0 > 50_000);
+        }
+    }
+}
+
+#[test]
+fn world_border_sent_when_inserted_after_join() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    app.world
+        .entity_mut(instance_ent)
+        .insert(WorldBorder::new([4.0, 4.0], 32.0));
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::WorldBorderInitializeS2c(_));
+
+    for pkt in sent_packets {
+        if let S2cPlayPacket::WorldBorderInitializeS2c(pkt) = pkt {
+            assert_eq!((pkt.x, pkt.z), (4.0, 4.0));
+            assert_eq!(pkt.new_diameter, 32.0);
+        }
+    }
+
+    // The border is not sent again on the next tick.
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::WorldBorderInitializeS2c(_));
+}
+
+#[test]
+fn world_border_changes() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    app.world
+        .entity_mut(instance_ent)
+        .insert(WorldBorder::new([0.0, 0.0], 200.0));
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    let mut border = app.world.get_mut::<WorldBorder>(instance_ent).unwrap();
+    border.set_center([8.0, 8.0]);
+    border.set_diameter_over(100.0, Duration::from_secs(10));
+    border.set_warning_blocks(10);
+    border.set_warning_time(30);
+
+    assert_eq!(border.target_diameter(), 100.0);
+    assert!(border.diameter() > 100.0);
+    assert!(border.remaining_time() <= Duration::from_secs(10));
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::WorldBorderCenterChangedS2c(_),
+        S2cPlayPacket::WorldBorderInterpolateSizeS2c(_),
+        S2cPlayPacket::WorldBorderWarningBlocksChangedS2c(_),
+        S2cPlayPacket::WorldBorderWarningTimeChangedS2c(_)
+    );
+    assert_packet_count!(sent_packets, 0, S2cPlayPacket::WorldBorderInitializeS2c(_));
+
+    for pkt in &sent_packets {
+        if let S2cPlayPacket::WorldBorderInterpolateSizeS2c(pkt) = pkt {
+            assert!(pkt.old_diameter <= 200.0 && pkt.old_diameter > 100.0);
+            assert_eq!(pkt.new_diameter, 100.0);
+            assert!(pkt.speed.0 <= 10_000 && pkt.speed.0 > 0);
+        }
+    }
+
+    // Nothing is sent when the border is unchanged.
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(
+        sent_packets,
+        0,
+        S2cPlayPacket::WorldBorderCenterChangedS2c(_)
+    );
+    assert_packet_count!(
+        sent_packets,
+        0,
+        S2cPlayPacket::WorldBorderInterpolateSizeS2c(_)
+    );
+
+    // Setting the diameter immediately ends the size change.
+    let mut border = app.world.get_mut::<WorldBorder>(instance_ent).unwrap();
+    border.set_diameter(20.0);
+
+    assert_eq!(border.diameter(), 20.0);
+    assert_eq!(border.remaining_time(), Duration::ZERO);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::WorldBorderSizeChangedS2c(_));
+
+    // Removing the border resets it for clients.
+    app.world.entity_mut(instance_ent).remove::<WorldBorder>();
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_count!(sent_packets, 1, S2cPlayPacket::WorldBorderInitializeS2c(_));
+
+    for pkt in sent_packets {
+        if let S2cPlayPacket::WorldBorderInitializeS2c(pkt) = pkt {
+            assert_eq!(pkt.old_diameter, DEFAULT_DIAMETER);
+            assert_eq!(pkt.new_diameter, DEFAULT_DIAMETER);
+        }
+    }
+}
+
+#[test]
+fn world_border_lerp_finishes() {
+    let mut border = WorldBorder::new([0.0, 0.0], 100.0);
+    border.set_diameter_over(50.0, Duration::from_millis(20));
+
+    let diameter = border.diameter();
+    assert!((50.0..=100.0).contains(&diameter));
+
+    std::thread::sleep(Duration::from_millis(30));
+
+    assert_eq!(border.diameter(), 50.0);
+    assert_eq!(border.remaining_time(), Duration::ZERO);
+}
+
+#[test]
+fn world_border_reset_on_respawn() {
+    let Scenario {
+        mut app,
+        client: client_ent,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let first_instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    app.world
+        .entity_mut(first_instance_ent)
+        .insert(WorldBorder::new([0.0, 0.0], 64.0));
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    client_helper.clear_sent();
+
+    // Move the client to an instance without a border.
+    let server = app.world.resource::<Server>();
+    let instance = Instance::new_unit_testing(ident!("overworld"), server);
+    let second_instance_ent = app.world.spawn(instance).id();
+
+    app.world.get_mut::<Location>(client_ent).unwrap().0 = second_instance_ent;
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::PlayerRespawnS2c(_),
+        S2cPlayPacket::WorldBorderInitializeS2c(_)
+    );
+
+    for pkt in sent_packets {
+        if let S2cPlayPacket::WorldBorderInitializeS2c(pkt) = pkt {
+            assert_eq!(pkt.new_diameter, DEFAULT_DIAMETER);
+        }
+    }
+}
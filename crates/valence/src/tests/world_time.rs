@@ -0,0 +1,131 @@
+use valence_client::world_time::{WorldTime, DAY_LENGTH};
+use valence_core::packet::s2c::play::{S2cPlayPacket, WorldTimeUpdateS2c};
+
+use super::*;
+
+fn sent_times(client_helper: &mut MockClient) -> Vec<WorldTimeUpdateS2c> {
+    client_helper.collect_sent().of::<WorldTimeUpdateS2c>()
+}
+
+#[test]
+fn world_time_sent_on_join_and_interval() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    let mut time = WorldTime::new(WorldTime::NOON);
+    time.set_sync_interval(5);
+    app.world.entity_mut(instance_ent).insert(time);
+
+    app.update();
+
+    let sent = client_helper.collect_sent();
+    let sent_packets = sent.all();
+
+    assert_packet_order!(
+        sent_packets,
+        S2cPlayPacket::GameJoinS2c(_),
+        S2cPlayPacket::WorldTimeUpdateS2c(_)
+    );
+
+    for _ in 0..9 {
+        app.update();
+    }
+
+    // The time is sent every five ticks.
+    let times = sent_times(&mut client_helper);
+
+    assert_eq!(times.len(), 2);
+    assert_eq!(times[0].world_age, 5);
+    assert_eq!(times[0].time_of_day, WorldTime::NOON + 5);
+    assert_eq!(times[1].world_age, 10);
+    assert_eq!(times[1].time_of_day, WorldTime::NOON + 10);
+
+    // Changes are sent immediately.
+    let mut time = app.world.get_mut::<WorldTime>(instance_ent).unwrap();
+    time.set_midnight();
+
+    app.update();
+
+    let times = sent_times(&mut client_helper);
+
+    assert_eq!(times.len(), 1);
+    assert_eq!(times[0].time_of_day, WorldTime::MIDNIGHT + 1);
+}
+
+#[test]
+fn world_time_frozen() {
+    let Scenario {
+        mut app,
+        mock_client: mut client_helper,
+        ..
+    } = ScenarioBuilder::new().build();
+
+    let instance_ent = app
+        .world
+        .query_filtered::<Entity, With<Instance>>()
+        .single(&app.world);
+
+    let mut time = WorldTime::default();
+    time.set_frozen(true);
+    app.world.entity_mut(instance_ent).insert(time);
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let times = sent_times(&mut client_helper);
+
+    // Zero is sent as -1 so the client still sees the time as frozen.
+    assert_eq!(times[0].time_of_day, -1);
+
+    let mut time = app.world.get_mut::<WorldTime>(instance_ent).unwrap();
+
+    assert_eq!(time.world_age(), 3);
+    assert_eq!(time.time_of_day(), 0);
+
+    time.set_night();
+    app.update();
+
+    let times = sent_times(&mut client_helper);
+
+    assert_eq!(times.len(), 1);
+    assert_eq!(times[0].world_age, 4);
+    assert_eq!(times[0].time_of_day, -WorldTime::NIGHT);
+}
+
+#[test]
+fn world_time_wraparound() {
+    let mut time = WorldTime::new(DAY_LENGTH - 100);
+
+    assert_eq!(time.day(), 0);
+
+    time.add_time(150);
+
+    assert_eq!(time.time_of_day(), 50);
+    assert_eq!(time.day(), 1);
+    assert_eq!(time.day_time(), DAY_LENGTH + 50);
+
+    time.set_day();
+
+    assert_eq!(time.time_of_day(), WorldTime::DAY);
+    assert_eq!(time.day(), 1);
+
+    time.set_time_of_day(-1000);
+
+    assert_eq!(time.time_of_day(), DAY_LENGTH - 1000);
+    assert_eq!(time.day(), 1);
+
+    time.add_time(-3 * DAY_LENGTH);
+
+    assert_eq!(time.day_time(), 0);
+
+    assert_eq!(WorldTime::new(DAY_LENGTH + 5).time_of_day(), 5);
+}
@@ -0,0 +1,217 @@
+//! Counts the heap allocations made by a server tick in the steady state.
+//!
+//! The global allocator is shared by the whole process, so this test lives in
+//! its own test binary with no other tests running next to it.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bytes::BytesMut;
+use valence::client::{ClientBundle, ClientBundleArgs, ClientConnection, ReceivedPacket};
+use valence::entity::cow::CowEntityBundle;
+use valence::entity::entity::Flags;
+use valence::packet::encode::PacketEncoder;
+use valence::prelude::*;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// The number of clients in the smaller of the two measured scenes. The larger
+/// one has twice as many.
+const CLIENT_COUNT: usize = 16;
+const COW_COUNT: usize = 64;
+const WARMUP_TICKS: usize = 20;
+const MEASURED_TICKS: usize = 20;
+
+/// The maximum average number of allocations per tick which don't depend on
+/// the number of clients. These are made by the scheduler when running systems
+/// on the task pool.
+const MAX_ALLOCATIONS_PER_TICK: usize = 500;
+
+/// The maximum average number of allocations per tick made for each client.
+/// Writing, compressing and sending packets to a client in the steady state
+/// must reuse the buffers of earlier ticks, so this is less than one.
+const MAX_ALLOCATIONS_PER_CLIENT_PER_TICK: f64 = 0.1;
+
+#[test]
+fn steady_state_tick_allocations() {
+    let small = measure_allocations(CLIENT_COUNT);
+    let large = measure_allocations(CLIENT_COUNT * 2);
+
+    let per_tick = small / MEASURED_TICKS;
+
+    assert!(
+        per_tick <= MAX_ALLOCATIONS_PER_TICK,
+        "a tick made {per_tick} allocations on average, but at most {MAX_ALLOCATIONS_PER_TICK} \
+         are allowed"
+    );
+
+    let per_client_per_tick =
+        large.saturating_sub(small) as f64 / (CLIENT_COUNT * MEASURED_TICKS) as f64;
+
+    assert!(
+        per_client_per_tick <= MAX_ALLOCATIONS_PER_CLIENT_PER_TICK,
+        "a tick made {per_client_per_tick} allocations per client on average, but at most \
+         {MAX_ALLOCATIONS_PER_CLIENT_PER_TICK} are allowed"
+    );
+}
+
+/// The number of clients spawned by [`setup`].
+#[derive(Resource)]
+struct ClientCount(usize);
+
+/// Returns the number of allocations made by [`MEASURED_TICKS`] ticks of a
+/// scene with `client_count` clients, after it has warmed up.
+fn measure_allocations(client_count: usize) -> usize {
+    let mut app = App::new();
+
+    app.insert_resource(CoreSettings {
+        compression_threshold: Some(256),
+        ..Default::default()
+    })
+    .insert_resource(ClientCount(client_count));
+
+    #[cfg(feature = "network")]
+    app.add_plugins(
+        DefaultPlugins
+            .build()
+            .disable::<valence::network::NetworkPlugin>(),
+    );
+    #[cfg(not(feature = "network"))]
+    app.add_plugins(DefaultPlugins);
+
+    app.add_startup_system(setup);
+    app.add_system(update_scene);
+
+    // Send the chunks and entities to the clients and let all the buffers grow
+    // to their final size.
+    for _ in 0..WARMUP_TICKS {
+        app.update();
+    }
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+
+    for _ in 0..MEASURED_TICKS {
+        app.update();
+    }
+
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn setup(
+    mut commands: Commands,
+    dimensions: Query<&DimensionType>,
+    biomes: Query<&Biome>,
+    server: Res<Server>,
+    client_count: Res<ClientCount>,
+) {
+    let mut instance = Instance::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+    for z in -5..5 {
+        for x in -5..5 {
+            instance.insert_chunk([x, z], Chunk::default());
+        }
+    }
+
+    for z in -80..80 {
+        for x in -80..80 {
+            instance.set_block([x, 64, z], BlockState::GRASS_BLOCK);
+        }
+    }
+
+    let instance = commands.spawn(instance).id();
+
+    for i in 0..client_count.0 {
+        let mut enc = PacketEncoder::new();
+        enc.set_compression(Some(256));
+
+        let mut client = ClientBundle::new(ClientBundleArgs {
+            username: format!("bot_{i}"),
+            uuid: Uuid::from_u128(i as u128),
+            ip: "127.0.0.1".parse().unwrap(),
+            properties: vec![],
+            conn: Box::new(DiscardConnection),
+            enc,
+            send_queue_limit: SendQueueLimit::default(),
+        });
+
+        client.player.location.0 = instance;
+        client.player.position = Position::new([(i % 4) as f64 * 4.0, 65.0, (i / 4) as f64 * 4.0]);
+
+        commands.spawn(client);
+    }
+
+    for i in 0..COW_COUNT {
+        commands.spawn(CowEntityBundle {
+            location: Location(instance),
+            position: Position::new([(i % 8) as f64 * 2.0, 65.0, (i / 8) as f64 * 2.0]),
+            uuid: UniqueId(Uuid::from_u128((client_count.0 + i) as u128)),
+            ..Default::default()
+        });
+    }
+}
+
+/// Changes the look, head yaw, and tracked data of every cow and sends a large
+/// packet to every client, which is compressed.
+fn update_scene(
+    mut cows: Query<(&mut Look, &mut HeadYaw, &mut Flags), Without<Client>>,
+    mut clients: Query<&mut Client>,
+    server: Res<Server>,
+) {
+    let tick = server.current_tick();
+
+    for (mut look, mut head_yaw, mut flags) in &mut cows {
+        look.yaw = (tick % 360) as f32;
+        head_yaw.0 = look.yaw;
+        flags.set_sneaking(tick % 2 == 0);
+    }
+
+    let data = [tick as u8; 1024];
+
+    for mut client in &mut clients {
+        client.send_custom_payload(ident!("valence:test"), &data);
+    }
+}
+
+/// A synthetic client connection which discards all packets sent to it and
+/// never sends anything.
+struct DiscardConnection;
+
+impl ClientConnection for DiscardConnection {
+    fn try_send(&mut self, _bytes: BytesMut) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn try_recv(&mut self) -> anyhow::Result<Option<ReceivedPacket>> {
+        Ok(None)
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+}
@@ -7,15 +7,17 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::Context;
 use bevy_app::{CoreSet, Plugin};
-use bevy_ecs::prelude::{Bundle, Component, Entity};
-use bevy_ecs::query::{Added, Changed, Or, With};
+use bevy_ecs::prelude::{Bundle, Component, DetectChanges, Entity};
+use bevy_ecs::query::{Added, Changed, Or, With, Without};
 use bevy_ecs::schedule::{IntoSystemConfig, IntoSystemSetConfig, SystemSet};
 use bevy_ecs::system::{Commands, Query, SystemParam};
+use bevy_ecs::world::Ref;
 pub use bevy_hierarchy;
 use bevy_hierarchy::{Children, Parent};
 use event::{handle_advancement_tab_change, AdvancementTabChange};
 use rustc_hash::FxHashMap;
 use valence_client::{Client, FlushPacketsSet, SpawnClientsSet};
+use valence_core::despawn::Despawned;
 use valence_core::ident::Ident;
 use valence_core::item::ItemStack;
 use valence_core::packet::encode::WritePacket;
@@ -54,6 +56,11 @@ impl Plugin for AdvancementPlugin {
         )
         .add_system(handle_advancement_tab_change.in_base_set(CoreSet::PreUpdate))
         .add_system(update_advancement_cached_bytes.in_set(WriteAdvancementToCacheSet))
+        .add_system(
+            sync_advancements_with_clients
+                .in_base_set(CoreSet::PostUpdate)
+                .before(WriteAdvancementPacketToClientsSet),
+        )
         .add_system(send_advancement_update_packet.in_set(WriteAdvancementPacketToClientsSet));
     }
 }
@@ -62,6 +69,10 @@ impl Plugin for AdvancementPlugin {
 /// Optional components:
 /// [AdvancementDisplay]
 /// [Parent] - parent advancement
+///
+/// Advancements are sent to every client when they join, and to the clients
+/// already in the game when they are spawned. Advancements with the
+/// [`Despawned`] component are removed from the clients.
 #[derive(Bundle)]
 pub struct AdvancementBundle {
     pub advancement: Advancement,
@@ -69,6 +80,23 @@ pub struct AdvancementBundle {
     pub cached_bytes: AdvancementCachedBytes,
 }
 
+impl AdvancementBundle {
+    /// Returns the components of an advancement with the given identifier and
+    /// no requirements.
+    pub fn new(ident: impl Into<Ident<Cow<'static, str>>>) -> Self {
+        Self {
+            advancement: Advancement::new(ident.into()),
+            requirements: AdvancementRequirements::default(),
+            cached_bytes: AdvancementCachedBytes::default(),
+        }
+    }
+
+    pub fn with_requirements(mut self, requirements: AdvancementRequirements) -> Self {
+        self.requirements = requirements;
+        self
+    }
+}
+
 fn add_advancement_update_component_to_new_clients(
     mut commands: Commands,
     query: Query<Entity, Added<Client>>,
@@ -287,6 +315,39 @@ impl<'w, 's, 'a, 'b> Packet<'b> for AdvancementUpdateEncodeS2c<'w, 's, 'a> {
     }
 }
 
+/// Schedules the advancements to be sent to new clients, the new advancements
+/// to be sent to the other clients, and the despawned advancements to be
+/// removed from them.
+#[allow(clippy::type_complexity)]
+fn sync_advancements_with_clients(
+    mut clients: Query<&mut AdvancementClientUpdate>,
+    roots: Query<Entity, (With<Advancement>, Without<Parent>, Without<Despawned>)>,
+    added: Query<Entity, (Added<Advancement>, Without<Despawned>)>,
+    despawned: Query<(Entity, Ref<Advancement>), With<Despawned>>,
+    children_query: Query<&Children>,
+    advancement_check_query: Query<(), With<Advancement>>,
+) {
+    for mut client_update in &mut clients {
+        if client_update.is_added() {
+            // Send whole trees, so parents are sent before their children.
+            for root in &roots {
+                client_update.send_advancements(root, &children_query, &advancement_check_query);
+            }
+
+            continue;
+        }
+
+        client_update.new_advancements.extend(added.iter());
+
+        for (entity, advancement) in &despawned {
+            // Advancements spawned this tick were never sent.
+            if !advancement.is_added() {
+                client_update.remove_advancements.push(entity);
+            }
+        }
+    }
+}
+
 #[allow(clippy::type_complexity)]
 fn send_advancement_update_packet(
     mut client: Query<(&mut AdvancementClientUpdate, &mut Client)>,
@@ -348,7 +409,7 @@ impl Advancement {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum AdvancementFrameType {
     Task,
     Challenge,
@@ -370,6 +431,57 @@ pub struct AdvancementDisplay {
 }
 
 impl AdvancementDisplay {
+    /// Returns a display with the given title, description, icon and frame,
+    /// which shows a toast when the advancement is completed.
+    pub fn new(
+        title: impl Into<Text>,
+        description: impl Into<Text>,
+        icon: ItemStack,
+        frame_type: AdvancementFrameType,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            description: description.into(),
+            icon: Some(icon),
+            frame_type,
+            show_toast: true,
+            hidden: false,
+            background_texture: None,
+            x_coord: 0.0,
+            y_coord: 0.0,
+        }
+    }
+
+    /// Sets the background of the tab. Only used by root advancements, which
+    /// need one to be shown as a tab.
+    pub fn with_background_texture(mut self, texture: impl Into<Ident<Cow<'static, str>>>) -> Self {
+        self.background_texture = Some(texture.into());
+        self
+    }
+
+    /// Sets the position of the advancement in its tab.
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.x_coord = x;
+        self.y_coord = y;
+        self
+    }
+
+    /// Sets whether a toast is shown when the advancement is completed. The
+    /// toast is not shown for advancements completed in the first update sent
+    /// to a client, or any update with
+    /// [`reset`](AdvancementClientUpdate::reset).
+    pub fn with_show_toast(mut self, show_toast: bool) -> Self {
+        self.show_toast = show_toast;
+        self
+    }
+
+    /// Sets whether the advancement is hidden in its tab until it is
+    /// completed.
+    pub fn with_hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
     pub(crate) fn flags(&self) -> i32 {
         let mut flags = 0;
         flags |= self.background_texture.is_some() as i32;
@@ -399,6 +511,18 @@ impl AdvancementCriteria {
 #[derive(Component, Default)]
 pub struct AdvancementRequirements(pub Vec<Vec<Entity>>);
 
+impl AdvancementRequirements {
+    /// Requirements which are met once all of the criteria are completed.
+    pub fn all(criteria: impl IntoIterator<Item = Entity>) -> Self {
+        Self(criteria.into_iter().map(|c| vec![c]).collect())
+    }
+
+    /// Requirements which are met once any of the criteria is completed.
+    pub fn any(criteria: impl IntoIterator<Item = Entity>) -> Self {
+        Self(vec![criteria.into_iter().collect()])
+    }
+}
+
 #[derive(Component, Default)]
 pub struct AdvancementCachedBytes(pub(crate) Vec<u8>);
 
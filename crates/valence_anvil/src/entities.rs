@@ -0,0 +1,226 @@
+use glam::{DVec3, Vec3};
+use thiserror::Error;
+use tracing::warn;
+use uuid::Uuid;
+use valence_block::BlockEntityKind;
+use valence_core::block_pos::BlockPos;
+use valence_core::ident::Ident;
+use valence_core::text::Text;
+use valence_core::DEFAULT_TPS;
+use valence_entity::EntityKind;
+use valence_instance::BlockEntity;
+use valence_nbt::{Compound, List, Value};
+
+use crate::ToValenceError;
+
+/// An entity read from an Anvil world.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParsedEntity {
+    pub kind: EntityKind,
+    /// The UUID of the entity, or `None` if it was not saved.
+    pub uuid: Option<Uuid>,
+    pub position: DVec3,
+    /// The velocity of the entity in m/s.
+    pub velocity: Vec3,
+    /// The yaw angle in degrees.
+    pub yaw: f32,
+    /// The pitch angle in degrees.
+    pub pitch: f32,
+    pub on_ground: bool,
+    pub custom_name: Option<Text>,
+    pub custom_name_visible: bool,
+    pub no_gravity: bool,
+    pub silent: bool,
+    /// The entities riding this entity.
+    pub passengers: Vec<ParsedEntity>,
+    /// The complete NBT data of the entity, including the fields which were
+    /// parsed.
+    pub nbt: Compound,
+}
+
+/// A block entity read from an Anvil world.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParsedBlockEntity {
+    /// The absolute position of the block entity.
+    pub pos: BlockPos,
+    pub block_entity: BlockEntity,
+}
+
+#[derive(Clone, Debug, Error)]
+enum ParseEntityError {
+    #[error("missing entity ident")]
+    MissingIdent,
+    #[error("unknown entity ident of \"{0}\"")]
+    UnknownIdent(String),
+    #[error("invalid entity position")]
+    InvalidPosition,
+}
+
+/// Parses the entities of a chunk from the data returned by
+/// [`AnvilWorld::read_entities`].
+///
+/// Entities which are malformed or of an unknown kind are skipped with a
+/// warning. Passengers are stored in the entity they are riding instead of the
+/// returned list.
+///
+/// [`AnvilWorld::read_entities`]: crate::AnvilWorld::read_entities
+pub fn parse_entities(nbt: &Compound) -> Vec<ParsedEntity> {
+    match nbt.get("Entities") {
+        Some(Value::List(List::Compound(entities))) => parse_entity_list(entities),
+        Some(Value::List(List::End)) | None => vec![],
+        Some(_) => {
+            warn!("entity list is not a list of compounds");
+            vec![]
+        }
+    }
+}
+
+fn parse_entity_list(entities: &[Compound]) -> Vec<ParsedEntity> {
+    entities
+        .iter()
+        .filter_map(|comp| match parse_entity(comp) {
+            Ok(entity) => Some(entity),
+            Err(e) => {
+                warn!("skipping entity: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+fn parse_entity(comp: &Compound) -> Result<ParsedEntity, ParseEntityError> {
+    let Some(Value::String(id)) = comp.get("id") else {
+        return Err(ParseEntityError::MissingIdent);
+    };
+
+    let Some(kind) = Ident::new(&id[..])
+        .ok()
+        .and_then(|ident| EntityKind::from_ident(ident.as_str_ident()))
+    else {
+        return Err(ParseEntityError::UnknownIdent(id.clone()));
+    };
+
+    let Some(Value::List(List::Double(pos))) = comp.get("Pos") else {
+        return Err(ParseEntityError::InvalidPosition);
+    };
+
+    let &[x, y, z] = pos.as_slice() else {
+        return Err(ParseEntityError::InvalidPosition);
+    };
+
+    let uuid = match comp.get("UUID") {
+        Some(Value::IntArray(uuid)) => match uuid.as_slice() {
+            &[a, b, c, d] => Some(Uuid::from_u64_pair(
+                (a as u32 as u64) << 32 | b as u32 as u64,
+                (c as u32 as u64) << 32 | d as u32 as u64,
+            )),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    // Motion is saved in blocks per tick.
+    let velocity = match comp.get("Motion") {
+        Some(Value::List(List::Double(motion))) => match motion.as_slice() {
+            &[x, y, z] => Vec3::new(x as f32, y as f32, z as f32) * DEFAULT_TPS.get() as f32,
+            _ => Vec3::ZERO,
+        },
+        _ => Vec3::ZERO,
+    };
+
+    let (yaw, pitch) = match comp.get("Rotation") {
+        Some(Value::List(List::Float(rotation))) => match rotation.as_slice() {
+            &[yaw, pitch] => (yaw, pitch),
+            _ => (0.0, 0.0),
+        },
+        _ => (0.0, 0.0),
+    };
+
+    let custom_name = match comp.get("CustomName") {
+        Some(Value::String(json)) => match serde_json::from_str(json) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                warn!("invalid custom name of {kind:?} entity: {e}");
+                None
+            }
+        },
+        _ => None,
+    };
+
+    let passengers = match comp.get("Passengers") {
+        Some(Value::List(List::Compound(passengers))) => parse_entity_list(passengers),
+        _ => vec![],
+    };
+
+    Ok(ParsedEntity {
+        kind,
+        uuid,
+        position: DVec3::new(x, y, z),
+        velocity,
+        yaw,
+        pitch,
+        on_ground: get_bool(comp, "OnGround"),
+        custom_name,
+        custom_name_visible: get_bool(comp, "CustomNameVisible"),
+        no_gravity: get_bool(comp, "NoGravity"),
+        silent: get_bool(comp, "Silent"),
+        passengers,
+        nbt: comp.clone(),
+    })
+}
+
+/// Parses the `block_entities` list of a chunk from the data returned by
+/// [`AnvilWorld::read_chunk`].
+///
+/// Block entities which are malformed or of an unknown kind are skipped with a
+/// warning.
+///
+/// [`AnvilWorld::read_chunk`]: crate::AnvilWorld::read_chunk
+pub fn parse_block_entities(nbt: &Compound) -> Vec<ParsedBlockEntity> {
+    let Some(Value::List(List::Compound(block_entities))) = nbt.get("block_entities") else {
+        return vec![];
+    };
+
+    block_entities
+        .iter()
+        .filter_map(|comp| match parse_block_entity(comp) {
+            Ok(block_entity) => Some(block_entity),
+            Err(e) => {
+                warn!("skipping block entity: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+fn parse_block_entity(comp: &Compound) -> Result<ParsedBlockEntity, ToValenceError> {
+    let Some(Value::String(ident)) = comp.get("id") else {
+        return Err(ToValenceError::MissingBlockEntityIdent);
+    };
+    let Ok(ident) = Ident::new(&ident[..]) else {
+        return Err(ToValenceError::UnknownBlockEntityIdent(ident.clone()));
+    };
+    let Some(kind) = BlockEntityKind::from_ident(ident.as_str_ident()) else {
+        return Err(ToValenceError::UnknownBlockEntityIdent(
+            ident.as_str().to_string(),
+        ));
+    };
+
+    let (Some(&Value::Int(x)), Some(&Value::Int(y)), Some(&Value::Int(z))) =
+        (comp.get("x"), comp.get("y"), comp.get("z"))
+    else {
+        return Err(ToValenceError::InvalidBlockEntityPosition);
+    };
+
+    Ok(ParsedBlockEntity {
+        pos: BlockPos::new(x, y, z),
+        block_entity: BlockEntity {
+            kind,
+            nbt: comp.clone(),
+        },
+    })
+}
+
+fn get_bool(comp: &Compound, key: &str) -> bool {
+    matches!(comp.get(key), Some(&Value::Byte(b)) if b != 0)
+}
@@ -0,0 +1,334 @@
+use valence_biome::BiomeId;
+use valence_block::BlockState;
+use valence_core::chunk_pos::ChunkPos;
+use valence_core::ident::Ident;
+use valence_instance::{Chunk, HeightmapKind};
+use valence_nbt::{compound, Compound, List, Value};
+
+use crate::to_valence::{bit_width, parse_block_state, BIOMES_PER_SECTION, BLOCKS_PER_SECTION};
+use crate::{upgrade_chunk, ToValenceError};
+
+/// The data version of the chunks written by [`from_valence`], which is the
+/// data version of Minecraft 1.19.4.
+pub const DATA_VERSION: i32 = 3337;
+
+/// Takes a Valence [`Chunk`] and writes its data to an Anvil chunk in NBT form.
+/// This is the inverse of [`to_valence`].
+///
+/// The NBT data is updated in place so that the parts of the Anvil chunk that
+/// Valence does not know about are kept. To create a new Anvil chunk, pass an
+/// empty compound. Sections of the Anvil chunk outside of the Valence chunk
+/// are left as they are, as are sections whose blocks and biomes have not
+/// changed. If any section changed, the light of the Anvil chunk is removed so
+/// that the game computes it again. The heightmaps are replaced with the ones
+/// of the Valence chunk if it covers every section of the Anvil chunk, and are
+/// removed for the game to compute otherwise. Anvil chunks saved
+/// by older versions of Minecraft are converted with [`upgrade_chunk`] first.
+///
+/// # Errors
+///
+/// Returns an error if the Anvil chunk cannot be upgraded, in which case `nbt`
+/// is left unchanged.
+///
+/// # Arguments
+///
+/// - `chunk`: The Valence chunk to read from.
+/// - `pos`: The position of the chunk. Block entities are stored with absolute
+///   positions.
+/// - `nbt`: The Anvil chunk to write to. This is usually the value returned by
+///   [`AnvilWorld::read_chunk`] or an empty compound, and is written with
+///   [`AnvilWorld::write_chunk`] afterwards.
+/// - `sect_offset`: The same offset as passed to [`to_valence`]. Section `y` of
+///   the Valence chunk is written to the Anvil section with Y position `y -
+///   sect_offset`.
+/// - `map_biome`: A function to map Valence [`BiomeId`]s to biome resource
+///   identifiers.
+///
+/// [`to_valence`]: crate::to_valence
+/// [`AnvilWorld::read_chunk`]: crate::AnvilWorld::read_chunk
+/// [`AnvilWorld::write_chunk`]: crate::AnvilWorld::write_chunk
+pub fn from_valence<F, const LOADED: bool>(
+    chunk: &Chunk<LOADED>,
+    pos: ChunkPos,
+    nbt: &mut Compound,
+    sect_offset: i32,
+    mut map_biome: F,
+) -> Result<(), ToValenceError>
+where
+    F: FnMut(BiomeId) -> Ident<String>,
+{
+    // Chunks saved by older versions of Minecraft are converted to the current
+    // layout first.
+    upgrade_chunk(nbt)?;
+
+    let mut sections = match nbt.remove("sections") {
+        Some(Value::List(List::Compound(sections))) => sections,
+        _ => vec![],
+    };
+
+    let mut block_entities = match nbt.remove("block_entities") {
+        Some(Value::List(List::Compound(block_entities))) => block_entities,
+        _ => vec![],
+    };
+
+    let min_y = -sect_offset * 16;
+    let max_y = min_y + chunk.section_count() as i32 * 16;
+
+    // Block entities inside the Valence chunk are replaced with its own.
+    block_entities
+        .retain(|be| !matches!(be.get("y"), Some(&Value::Int(y)) if (min_y..max_y).contains(&y)));
+
+    let mut biome_names: Vec<(BiomeId, String)> = vec![];
+    let mut changed = false;
+
+    for sect_y in 0..chunk.section_count() {
+        let anvil_y = sect_y as i32 - sect_offset;
+
+        let idx = match sections
+            .iter()
+            .position(|s| matches!(s.get("Y"), Some(&Value::Byte(y)) if y as i32 == anvil_y))
+        {
+            Some(idx) => idx,
+            None => {
+                sections.push(compound! { "Y" => anvil_y as i8 });
+                sections.len() - 1
+            }
+        };
+
+        let section = &mut sections[idx];
+
+        let mut states = Vec::with_capacity(BLOCKS_PER_SECTION);
+
+        for i in 0..BLOCKS_PER_SECTION {
+            let x = i % 16;
+            let z = i / 16 % 16;
+            let y = sect_y * 16 + i / (16 * 16);
+
+            let state = chunk.block_state(x, y, z);
+
+            states.push(state);
+
+            if state.block_entity_kind().is_some() {
+                if let Some(be) = chunk.block_entity(x, y, z) {
+                    let mut be_nbt = be.nbt.clone();
+
+                    be_nbt.insert("id", be.kind.ident().to_string());
+                    be_nbt.insert("x", pos.x * 16 + x as i32);
+                    be_nbt.insert("y", y as i32 + min_y);
+                    be_nbt.insert("z", pos.z * 16 + z as i32);
+                    be_nbt.insert("keepPacked", false);
+
+                    block_entities.push(be_nbt);
+                }
+            }
+        }
+
+        let mut biomes = Vec::with_capacity(BIOMES_PER_SECTION);
+
+        for i in 0..BIOMES_PER_SECTION {
+            let biome = chunk.biome(i % 4, sect_y * 4 + i / (4 * 4), i / 4 % 4);
+
+            let name = match biome_names.iter().find(|(id, _)| *id == biome) {
+                Some((_, name)) => name,
+                None => {
+                    biome_names.push((biome, map_biome(biome).to_string()));
+                    &biome_names.last().unwrap().1
+                }
+            };
+
+            biomes.push(name.clone());
+        }
+
+        // Seeding the palettes with the existing ones keeps the encoding of
+        // unchanged sections the same.
+        let old_block_palette = match section.get("block_states") {
+            Some(Value::Compound(block_states)) => match block_states.get("palette") {
+                Some(Value::List(List::Compound(palette))) => palette
+                    .iter()
+                    .map_while(|block| parse_block_state(block).ok())
+                    .collect(),
+                _ => vec![],
+            },
+            _ => vec![],
+        };
+
+        let old_biome_palette = match section.get("biomes") {
+            Some(Value::Compound(biomes)) => match biomes.get("palette") {
+                Some(Value::List(List::String(palette))) => palette.clone(),
+                _ => vec![],
+            },
+            _ => vec![],
+        };
+
+        let block_states = encode_block_states(&states, old_block_palette);
+        let biomes_nbt = encode_biomes(&biomes, old_biome_palette);
+
+        if section.get("block_states") != Some(&block_states)
+            || section.get("biomes") != Some(&biomes_nbt)
+        {
+            // Leave out palette entries that are no longer used.
+            section.insert("block_states", encode_block_states(&states, vec![]));
+            section.insert("biomes", encode_biomes(&biomes, vec![]));
+
+            // The light of the old blocks is no longer valid.
+            section.remove("BlockLight");
+            section.remove("SkyLight");
+
+            changed = true;
+        }
+    }
+
+    sections.sort_by_key(|s| match s.get("Y") {
+        Some(&Value::Byte(y)) => y,
+        _ => i8::MIN,
+    });
+
+    // Vanilla chunks have extra sections above and below that only contain
+    // light, which are not counted here.
+    let min_sect_y = sections
+        .iter()
+        .filter(|s| s.contains_key("block_states"))
+        .find_map(|s| match s.get("Y") {
+            Some(&Value::Byte(y)) => Some(y as i32),
+            _ => None,
+        })
+        .unwrap_or(-sect_offset);
+
+    // The heightmaps of the Valence chunk are relative to its bottom, so they
+    // are only valid if there are no blocks outside of it.
+    let covers_anvil_chunk = sections
+        .iter()
+        .filter(|s| s.contains_key("block_states"))
+        .all(|s| match s.get("Y") {
+            Some(&Value::Byte(y)) => {
+                (0..chunk.section_count() as i32).contains(&(y as i32 + sect_offset))
+            }
+            _ => true,
+        });
+
+    if changed {
+        // The game computes the light and missing heightmaps again.
+        nbt.insert("isLightOn", false);
+
+        if covers_anvil_chunk {
+            let heightmaps = HeightmapKind::ALL
+                .into_iter()
+                .map(|kind| (kind.name().to_owned(), chunk.packed_heightmap(kind).into()))
+                .collect::<Compound>();
+
+            nbt.insert("Heightmaps", heightmaps);
+        } else {
+            nbt.remove("Heightmaps");
+        }
+    }
+
+    nbt.insert("DataVersion", DATA_VERSION);
+    nbt.insert("xPos", pos.x);
+    nbt.insert("zPos", pos.z);
+    nbt.insert("yPos", min_sect_y);
+
+    if !nbt.contains_key("Status") {
+        nbt.insert("Status", "full");
+    }
+
+    nbt.insert("sections", List::Compound(sections));
+    nbt.insert("block_entities", List::Compound(block_entities));
+
+    Ok(())
+}
+
+/// Encodes the block states of a section. The entries of `palette` are kept
+/// at the start of the palette, even if they are unused.
+fn encode_block_states(states: &[BlockState], mut palette: Vec<BlockState>) -> Value {
+    let idxs: Vec<_> = states
+        .iter()
+        .map(|&state| palette_index(&mut palette, state))
+        .collect();
+
+    let mut c = compound! {
+        "palette" => List::Compound(palette.iter().map(|&s| block_state_nbt(s)).collect()),
+    };
+
+    if palette.len() > 1 {
+        c.insert(
+            "data",
+            pack_indices(&idxs, bit_width(palette.len() - 1).max(4)),
+        );
+    }
+
+    Value::Compound(c)
+}
+
+/// Encodes the biome names of a section. The entries of `palette` are kept at
+/// the start of the palette, even if they are unused.
+fn encode_biomes(biomes: &[String], mut palette: Vec<String>) -> Value {
+    let idxs: Vec<_> = biomes
+        .iter()
+        .map(|name| palette_index(&mut palette, name.clone()))
+        .collect();
+
+    let bits_per_idx = bit_width(palette.len() - 1);
+
+    let mut c = compound! {
+        "palette" => List::String(palette),
+    };
+
+    if bits_per_idx > 0 {
+        c.insert("data", pack_indices(&idxs, bits_per_idx));
+    }
+
+    Value::Compound(c)
+}
+
+/// Returns the index of a value in a palette, adding it to the palette if
+/// needed.
+pub(crate) fn palette_index<T: PartialEq>(palette: &mut Vec<T>, value: T) -> usize {
+    match palette.iter().position(|v| *v == value) {
+        Some(idx) => idx,
+        None => {
+            palette.push(value);
+            palette.len() - 1
+        }
+    }
+}
+
+/// Converts a block state into a block palette entry.
+fn block_state_nbt(state: BlockState) -> Compound {
+    let kind = state.to_kind();
+
+    let mut c = compound! {
+        "Name" => format!("minecraft:{}", kind.to_str()),
+    };
+
+    if !kind.props().is_empty() {
+        let mut props = Compound::new();
+
+        for &name in kind.props() {
+            if let Some(value) = state.get(name) {
+                props.insert(name.to_str(), value.to_str());
+            }
+        }
+
+        c.insert("Properties", props);
+    }
+
+    c
+}
+
+/// Packs palette indices into longs without letting indices span across two
+/// longs, like the game does.
+pub(crate) fn pack_indices(idxs: &[usize], bits_per_idx: usize) -> Vec<i64> {
+    let idxs_per_long = 64 / bits_per_idx;
+
+    idxs.chunks(idxs_per_long)
+        .map(|chunk| {
+            let mut long = 0_u64;
+
+            for (j, &idx) in chunk.iter().enumerate() {
+                long |= (idx as u64) << (bits_per_idx * j);
+            }
+
+            long as i64
+        })
+        .collect()
+}
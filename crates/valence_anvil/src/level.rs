@@ -0,0 +1,453 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::thread;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use flume::{Receiver, Sender};
+use thiserror::Error;
+use valence_biome::{Biome, BiomeId, BiomeRegistry};
+use valence_client::{Client, OldView, View};
+use valence_core::chunk_pos::ChunkPos;
+use valence_core::ident::Ident;
+use valence_entity::{ClearEntityChangesSet, Location, OldLocation};
+use valence_instance::{Chunk, Instance};
+
+use crate::{
+    parse_block_entities, parse_entities, to_valence, upgrade_chunk, AnvilChunk, AnvilWorld,
+    ParsedBlockEntity, ParsedEntity, ReadChunkError, ToValenceError,
+};
+
+pub struct AnvilPlugin;
+
+/// When the chunks requested from [`AnvilLevel`]s are sent to be loaded.
+/// Systems which request chunks should run _before_ this set.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RequestAnvilChunksSet;
+
+/// When the chunks loaded from [`AnvilLevel`]s are inserted into their
+/// instances and [`ChunkLoadedEvent`]s and [`ChunkLoadFailedEvent`]s are sent.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct InsertAnvilChunksSet;
+
+impl Plugin for AnvilPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets((
+            RequestAnvilChunksSet
+                .in_base_set(CoreSet::PostUpdate)
+                .before(ClearEntityChangesSet),
+            InsertAnvilChunksSet.in_base_set(CoreSet::PreUpdate),
+        ))
+        .add_systems(
+            (request_viewed_chunks, send_chunk_requests)
+                .chain()
+                .in_set(RequestAnvilChunksSet),
+        )
+        .add_system(insert_loaded_chunks.in_set(InsertAnvilChunksSet))
+        .add_event::<ChunkLoadedEvent>()
+        .add_event::<ChunkLoadFailedEvent>();
+    }
+}
+
+/// Loads the chunks of an instance from an Anvil world in the background.
+///
+/// Insert this component on an instance entity to load chunks from the Anvil
+/// world at the given path. Chunks are read and converted on a separate thread
+/// so the tick is not blocked, and are inserted into the instance once they
+/// are ready. A [`ChunkLoadedEvent`] or a [`ChunkLoadFailedEvent`] is sent for
+/// every requested chunk.
+///
+/// By default, chunks entering the view of clients in the instance are
+/// requested automatically, and are cancelled if they leave the view of every
+/// client before they are loaded. Chunks are not unloaded by this component.
+/// Use [`Instance::set_chunk_retention`] to unload chunks which are no longer
+/// viewed.
+#[derive(Component, Debug)]
+pub struct AnvilLevel {
+    world_root: PathBuf,
+    max_in_flight: usize,
+    load_viewed_chunks: bool,
+    /// The chunks which have been requested and have not been delivered or
+    /// cancelled yet.
+    requested: HashSet<ChunkPos>,
+    /// The requested chunks which have not been sent to the worker yet, with
+    /// their priority. Lower values are sent first.
+    queued: HashMap<ChunkPos, u64>,
+    /// The chunks sent to the worker which have not been received back yet.
+    /// These may no longer be requested.
+    in_flight: HashSet<ChunkPos>,
+    worker: Option<WorkerChannels>,
+}
+
+#[derive(Debug)]
+struct WorkerChannels {
+    sender: Sender<ChunkPos>,
+    receiver: Receiver<(ChunkPos, WorkerResult)>,
+}
+
+type WorkerResult = Result<LoadedChunk, LoadChunkError>;
+
+#[derive(Debug)]
+struct LoadedChunk {
+    chunk: Chunk,
+    entities: Vec<ParsedEntity>,
+    block_entities: Vec<ParsedBlockEntity>,
+}
+
+impl AnvilLevel {
+    /// Creates a new level which loads chunks from the world at `world_root`,
+    /// the directory containing the `region` subdirectory.
+    pub fn new(world_root: impl Into<PathBuf>) -> Self {
+        Self {
+            world_root: world_root.into(),
+            max_in_flight: 32,
+            load_viewed_chunks: true,
+            requested: HashSet::new(),
+            queued: HashMap::new(),
+            in_flight: HashSet::new(),
+            worker: None,
+        }
+    }
+
+    /// Requests the chunk at the given position to be loaded. Once loaded, it
+    /// replaces the chunk at the position in the instance, if any. Requests
+    /// made with this method are sent to be loaded before the chunks requested
+    /// automatically.
+    ///
+    /// Returns `false` if the chunk was already requested.
+    pub fn request_chunk(&mut self, pos: impl Into<ChunkPos>) -> bool {
+        self.request_chunk_with_priority(pos.into(), 0)
+    }
+
+    fn request_chunk_with_priority(&mut self, pos: ChunkPos, priority: u64) -> bool {
+        if !self.requested.insert(pos) {
+            if let Some(old_priority) = self.queued.get_mut(&pos) {
+                *old_priority = (*old_priority).min(priority);
+            }
+
+            return false;
+        }
+
+        // A chunk which was cancelled and requested again while it was being
+        // loaded is delivered when the load finishes.
+        if !self.in_flight.contains(&pos) {
+            self.queued.insert(pos, priority);
+        }
+
+        true
+    }
+
+    /// Cancels the request for the chunk at the given position. The chunk is
+    /// not inserted into the instance and no event is sent for it, even if
+    /// it is being loaded already.
+    ///
+    /// Returns `false` if the chunk was not requested.
+    pub fn cancel_chunk(&mut self, pos: impl Into<ChunkPos>) -> bool {
+        let pos = pos.into();
+
+        self.queued.remove(&pos);
+        self.requested.remove(&pos)
+    }
+
+    /// Returns whether the chunk at the given position has been requested and
+    /// has not been delivered or cancelled yet.
+    pub fn is_chunk_requested(&self, pos: impl Into<ChunkPos>) -> bool {
+        self.requested.contains(&pos.into())
+    }
+
+    /// Returns an iterator over the chunks which have been requested and have
+    /// not been delivered or cancelled yet.
+    pub fn requested_chunks(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.requested.iter().copied()
+    }
+
+    /// Returns the maximum number of chunks being loaded at the same time. See
+    /// [`Self::set_max_in_flight`].
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// Sets the maximum number of chunks being loaded at the same time. The
+    /// other requested chunks wait in a queue ordered by their distance to the
+    /// clients which requested them, and can be cancelled without being loaded
+    /// at all. The default is 32.
+    pub fn set_max_in_flight(&mut self, max: usize) {
+        self.max_in_flight = max.max(1);
+    }
+
+    /// Returns whether chunks entering the view of clients are requested
+    /// automatically. See [`Self::set_load_viewed_chunks`].
+    pub fn load_viewed_chunks(&self) -> bool {
+        self.load_viewed_chunks
+    }
+
+    /// Sets whether chunks entering the view of clients in the instance are
+    /// requested automatically, and cancelled when they are no longer viewed
+    /// by any client. This is enabled by default.
+    pub fn set_load_viewed_chunks(&mut self, load: bool) {
+        self.load_viewed_chunks = load;
+    }
+}
+
+/// Sent when a chunk requested from an [`AnvilLevel`] has been loaded and
+/// inserted into its instance.
+///
+/// Valence does not spawn the entities of the chunk by itself, since the
+/// components an entity needs depend on its kind. Systems reading this event
+/// can spawn the entities they are interested in.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChunkLoadedEvent {
+    /// The instance the chunk was inserted into.
+    pub instance: Entity,
+    /// The position of the loaded chunk.
+    pub pos: ChunkPos,
+    /// The entities in the chunk, from [`parse_entities`].
+    pub parsed_entities: Vec<ParsedEntity>,
+    /// The block entities in the chunk, from [`parse_block_entities`]. These
+    /// are also inserted into the chunk.
+    pub parsed_block_entities: Vec<ParsedBlockEntity>,
+}
+
+/// Sent when a chunk requested from an [`AnvilLevel`] could not be loaded,
+/// including when the chunk does not exist in the Anvil world. Nothing is
+/// inserted into the instance.
+#[derive(Debug)]
+pub struct ChunkLoadFailedEvent {
+    /// The instance the chunk was requested for.
+    pub instance: Entity,
+    /// The position of the chunk.
+    pub pos: ChunkPos,
+    pub error: LoadChunkError,
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum LoadChunkError {
+    /// The chunk has not been generated in the Anvil world. Systems can
+    /// generate the chunk themselves in response.
+    #[error("chunk does not exist")]
+    Missing,
+    #[error(transparent)]
+    Read(#[from] ReadChunkError),
+    #[error(transparent)]
+    ToValence(#[from] ToValenceError),
+}
+
+/// The information the worker thread needs to convert chunks.
+struct WorkerInfo {
+    section_count: usize,
+    sect_offset: i32,
+    biomes: HashMap<Ident<String>, BiomeId>,
+}
+
+fn anvil_worker(
+    mut world: AnvilWorld,
+    info: WorkerInfo,
+    receiver: Receiver<ChunkPos>,
+    sender: Sender<(ChunkPos, WorkerResult)>,
+) {
+    while let Ok(pos) = receiver.recv() {
+        let result = load_chunk(&mut world, &info, pos);
+
+        if sender.send((pos, result)).is_err() {
+            // The level was removed.
+            break;
+        }
+    }
+}
+
+fn load_chunk(world: &mut AnvilWorld, info: &WorkerInfo, pos: ChunkPos) -> WorkerResult {
+    let Some(AnvilChunk { mut data, .. }) = world.read_chunk(pos.x, pos.z)? else {
+        return Err(LoadChunkError::Missing);
+    };
+
+    upgrade_chunk(&mut data)?;
+
+    let mut chunk = Chunk::new(info.section_count);
+
+    to_valence(&data, &mut chunk, info.sect_offset, |name| {
+        info.biomes.get(name.as_str()).copied().unwrap_or_default()
+    })?;
+
+    let entities = match world.read_entities(pos.x, pos.z)? {
+        Some(AnvilChunk { data, .. }) => parse_entities(&data),
+        // Entities were saved in the chunk before Minecraft 1.17.
+        None => parse_entities(&data),
+    };
+
+    Ok(LoadedChunk {
+        chunk,
+        entities,
+        block_entities: parse_block_entities(&data),
+    })
+}
+
+/// Requests the chunks entering the view of clients, and cancels the requests
+/// for chunks leaving the view of every client.
+fn request_viewed_chunks(
+    mut levels: Query<(&Instance, &mut AnvilLevel)>,
+    clients: Query<(Ref<Client>, &Location, &OldLocation, View, OldView)>,
+) {
+    let mut leaving = vec![];
+
+    for (client, loc, old_loc, view, old_view) in &clients {
+        let view = view.get();
+        let old_view = old_view.get();
+
+        let moved = client.is_added() || loc.0 != old_loc.get();
+
+        if !moved && view == old_view {
+            continue;
+        }
+
+        if let Ok((instance, mut level)) = levels.get_mut(loc.0) {
+            if level.load_viewed_chunks {
+                for pos in view.iter() {
+                    if (moved || !old_view.contains(pos)) && instance.chunk(pos).is_none() {
+                        level.request_chunk_with_priority(pos, view.pos.distance_squared(pos));
+                    }
+                }
+            }
+        }
+
+        if !client.is_added() && levels.contains(old_loc.get()) {
+            leaving.extend(
+                old_view
+                    .iter()
+                    .filter(|&pos| loc.0 != old_loc.get() || !view.contains(pos))
+                    .map(|pos| (old_loc.get(), pos)),
+            );
+        }
+    }
+
+    for (instance, pos) in leaving {
+        let Ok((_, mut level)) = levels.get_mut(instance) else {
+            continue;
+        };
+
+        if !level.load_viewed_chunks || !level.requested.contains(&pos) {
+            continue;
+        }
+
+        let still_viewed = clients
+            .iter()
+            .any(|(_, loc, _, view, _)| loc.0 == instance && view.get().contains(pos));
+
+        if !still_viewed {
+            level.cancel_chunk(pos);
+        }
+    }
+}
+
+/// Sends the queued chunks to the worker threads, starting the threads if
+/// needed.
+fn send_chunk_requests(
+    mut levels: Query<(&Instance, &mut AnvilLevel)>,
+    biomes: Query<&Biome>,
+    biome_reg: Res<BiomeRegistry>,
+) {
+    for (instance, mut level) in &mut levels {
+        if level.queued.is_empty() || level.in_flight.len() >= level.max_in_flight {
+            continue;
+        }
+
+        let level = level.as_mut();
+
+        let worker = level.worker.get_or_insert_with(|| {
+            let info = WorkerInfo {
+                section_count: instance.section_count(),
+                sect_offset: -instance.min_y().div_euclid(16),
+                biomes: biome_reg
+                    .iter()
+                    .filter_map(|(id, biome)| Some((biomes.get(biome).ok()?.name.clone(), id)))
+                    .collect(),
+            };
+
+            let world = AnvilWorld::new(&level.world_root);
+
+            let (pos_sender, pos_receiver) = flume::unbounded();
+            let (result_sender, result_receiver) = flume::unbounded();
+
+            thread::spawn(move || anvil_worker(world, info, pos_receiver, result_sender));
+
+            WorkerChannels {
+                sender: pos_sender,
+                receiver: result_receiver,
+            }
+        });
+
+        let mut to_send: Vec<_> = level.queued.iter().map(|(&pos, &pri)| (pri, pos)).collect();
+
+        let count = (level.max_in_flight - level.in_flight.len()).min(to_send.len());
+
+        if count < to_send.len() {
+            to_send.select_nth_unstable(count);
+            to_send.truncate(count);
+        }
+
+        // Sort chunks by ascending priority.
+        to_send.sort_unstable();
+
+        for (_, pos) in to_send {
+            if worker.sender.send(pos).is_err() {
+                // The worker thread stopped, so the chunk stays queued.
+                break;
+            }
+
+            level.queued.remove(&pos);
+            level.in_flight.insert(pos);
+        }
+    }
+}
+
+/// Inserts the chunks loaded by the worker threads into their instances.
+fn insert_loaded_chunks(
+    mut levels: Query<(Entity, &mut Instance, &mut AnvilLevel)>,
+    mut loaded_events: EventWriter<ChunkLoadedEvent>,
+    mut failed_events: EventWriter<ChunkLoadFailedEvent>,
+) {
+    for (instance_ent, mut instance, mut level) in &mut levels {
+        let level = level.as_mut();
+
+        let Some(worker) = &level.worker else {
+            continue;
+        };
+
+        for (pos, result) in worker.receiver.try_iter() {
+            level.in_flight.remove(&pos);
+
+            if !level.requested.remove(&pos) {
+                // The chunk was cancelled while it was being loaded.
+                continue;
+            }
+
+            match result {
+                Ok(loaded) => {
+                    instance.insert_chunk(pos, loaded.chunk);
+
+                    loaded_events.send(ChunkLoadedEvent {
+                        instance: instance_ent,
+                        pos,
+                        parsed_entities: loaded.entities,
+                        parsed_block_entities: loaded.block_entities,
+                    });
+                }
+                Err(error) => failed_events.send(ChunkLoadFailedEvent {
+                    instance: instance_ent,
+                    pos,
+                    error,
+                }),
+            }
+        }
+
+        // The chunk may have been requested again after the worker stopped.
+        for pos in &level.requested {
+            if !level.in_flight.contains(pos) {
+                if let Entry::Vacant(ve) = level.queued.entry(*pos) {
+                    ve.insert(0);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use thiserror::Error;
+use valence_core::block_pos::BlockPos;
+use valence_core::difficulty::Difficulty;
+use valence_nbt::{compound, Compound, List, Value};
+
+/// The metadata of a world, read from its `level.dat` file with
+/// [`AnvilWorld::read_level_dat`].
+///
+/// Only the most useful fields are parsed. The others are kept in
+/// [`Self::nbt`], so that they are written back unchanged by
+/// [`AnvilWorld::write_level_dat`]. Fields missing from the file are given the
+/// default values of the game.
+///
+/// [`AnvilWorld::read_level_dat`]: crate::AnvilWorld::read_level_dat
+/// [`AnvilWorld::write_level_dat`]: crate::AnvilWorld::write_level_dat
+#[derive(Clone, PartialEq, Debug)]
+pub struct LevelDat {
+    /// The data version of the game which last saved the world, or `None` for
+    /// worlds last saved before Minecraft 1.9.
+    pub data_version: Option<i32>,
+    /// The name of the world shown in the world list.
+    pub level_name: String,
+    /// The world spawn point.
+    pub spawn_pos: BlockPos,
+    /// The yaw of players spawning at the world spawn point, in degrees.
+    pub spawn_angle: f32,
+    /// The number of ticks the world has been running for.
+    pub time: i64,
+    /// The time of day in ticks. This keeps increasing past the length of a
+    /// day.
+    pub day_time: i64,
+    pub difficulty: Difficulty,
+    pub difficulty_locked: bool,
+    /// The game rules of the world, by name. Values are stored as strings,
+    /// like `"true"` or `"3"`.
+    pub game_rules: BTreeMap<String, String>,
+    pub border: LevelDatBorder,
+    /// The names of the enabled data packs, like `"vanilla"` or
+    /// `"file/example.zip"`.
+    pub enabled_data_packs: Vec<String>,
+    /// The names of the data packs which are available but disabled.
+    pub disabled_data_packs: Vec<String>,
+    /// The complete `Data` compound of the file, including the fields which
+    /// were not parsed. The parsed fields above take precedence over the
+    /// values here when writing.
+    pub nbt: Compound,
+}
+
+/// The world border settings of a [`LevelDat`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LevelDatBorder {
+    pub center_x: f64,
+    pub center_z: f64,
+    /// The side length of the border in blocks.
+    pub size: f64,
+    /// The distance outside of the border in blocks at which players start
+    /// taking damage.
+    pub safe_zone: f64,
+    /// The damage per second players take for every block they are past the
+    /// safe zone.
+    pub damage_per_block: f64,
+    /// The distance from the border in blocks at which players get a warning.
+    pub warning_blocks: f64,
+    /// The number of seconds before a moving border reaches players at which
+    /// they get a warning.
+    pub warning_time: f64,
+    /// The size the border is moving to.
+    pub size_lerp_target: f64,
+    /// The number of milliseconds left until the border reaches
+    /// [`Self::size_lerp_target`].
+    pub size_lerp_time: i64,
+}
+
+impl Default for LevelDatBorder {
+    fn default() -> Self {
+        Self {
+            center_x: 0.0,
+            center_z: 0.0,
+            size: DEFAULT_BORDER_SIZE,
+            safe_zone: 5.0,
+            damage_per_block: 0.2,
+            warning_blocks: 5.0,
+            warning_time: 15.0,
+            size_lerp_target: DEFAULT_BORDER_SIZE,
+            size_lerp_time: 0,
+        }
+    }
+}
+
+const DEFAULT_BORDER_SIZE: f64 = 59_999_968.0;
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ReadLevelDatError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Nbt(#[from] valence_nbt::Error),
+    #[error("missing `Data` compound in level.dat")]
+    MissingData,
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum WriteLevelDatError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Nbt(#[from] valence_nbt::Error),
+}
+
+impl LevelDat {
+    /// Parses the root compound of a `level.dat` file.
+    pub fn from_nbt(root: &Compound) -> Result<Self, ReadLevelDatError> {
+        let Some(Value::Compound(data)) = root.get("Data") else {
+            return Err(ReadLevelDatError::MissingData);
+        };
+
+        let game_rules = match data.get("GameRules") {
+            Some(Value::Compound(rules)) => rules
+                .iter()
+                .filter_map(|(name, value)| match value {
+                    Value::String(value) => Some((name.clone(), value.clone())),
+                    _ => None,
+                })
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+
+        let (enabled_data_packs, disabled_data_packs) = match data.get("DataPacks") {
+            Some(Value::Compound(packs)) => (
+                get_string_list(packs, "Enabled"),
+                get_string_list(packs, "Disabled"),
+            ),
+            _ => (vec!["vanilla".into()], vec![]),
+        };
+
+        let default_border = LevelDatBorder::default();
+
+        let border = LevelDatBorder {
+            center_x: get_double(data, "BorderCenterX").unwrap_or(default_border.center_x),
+            center_z: get_double(data, "BorderCenterZ").unwrap_or(default_border.center_z),
+            size: get_double(data, "BorderSize").unwrap_or(default_border.size),
+            safe_zone: get_double(data, "BorderSafeZone").unwrap_or(default_border.safe_zone),
+            damage_per_block: get_double(data, "BorderDamagePerBlock")
+                .unwrap_or(default_border.damage_per_block),
+            warning_blocks: get_double(data, "BorderWarningBlocks")
+                .unwrap_or(default_border.warning_blocks),
+            warning_time: get_double(data, "BorderWarningTime")
+                .unwrap_or(default_border.warning_time),
+            size_lerp_target: get_double(data, "BorderSizeLerpTarget")
+                .unwrap_or(default_border.size_lerp_target),
+            size_lerp_time: get_long(data, "BorderSizeLerpTime")
+                .unwrap_or(default_border.size_lerp_time),
+        };
+
+        let difficulty = match data.get("Difficulty") {
+            Some(Value::Byte(0)) => Difficulty::Peaceful,
+            Some(Value::Byte(1)) => Difficulty::Easy,
+            Some(Value::Byte(3)) => Difficulty::Hard,
+            _ => Difficulty::Normal,
+        };
+
+        Ok(Self {
+            data_version: match data.get("DataVersion") {
+                Some(&Value::Int(version)) => Some(version),
+                _ => None,
+            },
+            level_name: match data.get("LevelName") {
+                Some(Value::String(name)) => name.clone(),
+                _ => String::new(),
+            },
+            spawn_pos: BlockPos::new(
+                get_int(data, "SpawnX").unwrap_or(0),
+                get_int(data, "SpawnY").unwrap_or(64),
+                get_int(data, "SpawnZ").unwrap_or(0),
+            ),
+            spawn_angle: match data.get("SpawnAngle") {
+                Some(&Value::Float(angle)) => angle,
+                _ => 0.0,
+            },
+            time: get_long(data, "Time").unwrap_or(0),
+            day_time: get_long(data, "DayTime").unwrap_or(0),
+            difficulty,
+            difficulty_locked: matches!(data.get("DifficultyLocked"), Some(&Value::Byte(b)) if b != 0),
+            game_rules,
+            border,
+            enabled_data_packs,
+            disabled_data_packs,
+            nbt: data.clone(),
+        })
+    }
+
+    /// Creates the root compound of a `level.dat` file from this metadata.
+    pub fn to_nbt(&self) -> Compound {
+        let mut data = self.nbt.clone();
+
+        if let Some(version) = self.data_version {
+            data.insert("DataVersion", version);
+        }
+
+        data.insert("LevelName", self.level_name.clone());
+        data.insert("SpawnX", self.spawn_pos.x);
+        data.insert("SpawnY", self.spawn_pos.y);
+        data.insert("SpawnZ", self.spawn_pos.z);
+        data.insert("SpawnAngle", self.spawn_angle);
+        data.insert("Time", self.time);
+        data.insert("DayTime", self.day_time);
+        data.insert(
+            "Difficulty",
+            match self.difficulty {
+                Difficulty::Peaceful => 0_i8,
+                Difficulty::Easy => 1,
+                Difficulty::Normal => 2,
+                Difficulty::Hard => 3,
+            },
+        );
+        data.insert("DifficultyLocked", self.difficulty_locked);
+        data.insert(
+            "GameRules",
+            self.game_rules
+                .iter()
+                .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+                .collect::<Compound>(),
+        );
+
+        let border = &self.border;
+
+        data.insert("BorderCenterX", border.center_x);
+        data.insert("BorderCenterZ", border.center_z);
+        data.insert("BorderSize", border.size);
+        data.insert("BorderSafeZone", border.safe_zone);
+        data.insert("BorderDamagePerBlock", border.damage_per_block);
+        data.insert("BorderWarningBlocks", border.warning_blocks);
+        data.insert("BorderWarningTime", border.warning_time);
+        data.insert("BorderSizeLerpTarget", border.size_lerp_target);
+        data.insert("BorderSizeLerpTime", border.size_lerp_time);
+
+        data.insert(
+            "DataPacks",
+            compound! {
+                "Enabled" => List::String(self.enabled_data_packs.clone()),
+                "Disabled" => List::String(self.disabled_data_packs.clone()),
+            },
+        );
+
+        compound! {
+            "Data" => data,
+        }
+    }
+}
+
+fn get_int(comp: &Compound, key: &str) -> Option<i32> {
+    match comp.get(key) {
+        Some(&Value::Int(i)) => Some(i),
+        _ => None,
+    }
+}
+
+fn get_long(comp: &Compound, key: &str) -> Option<i64> {
+    match comp.get(key) {
+        Some(&Value::Long(l)) => Some(l),
+        _ => None,
+    }
+}
+
+fn get_double(comp: &Compound, key: &str) -> Option<f64> {
+    match comp.get(key) {
+        Some(&Value::Double(d)) => Some(d),
+        _ => None,
+    }
+}
+
+fn get_string_list(comp: &Compound, key: &str) -> Vec<String> {
+    match comp.get(key) {
+        Some(Value::List(List::String(list))) => list.clone(),
+        _ => vec![],
+    }
+}
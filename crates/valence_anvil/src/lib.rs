@@ -17,28 +17,75 @@
     clippy::dbg_macro
 )]
 
-use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io;
-use std::io::{ErrorKind, Read, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+pub use entities::*;
 use flate2::bufread::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+pub use from_valence::*;
+pub use level::*;
+pub use level_dat::*;
+use num_integer::div_ceil;
+pub use player_data::*;
 use thiserror::Error;
 pub use to_valence::*;
+pub use upgrade::*;
+use uuid::Uuid;
 use valence_nbt::Compound;
 
+mod entities;
+mod from_valence;
+mod level;
+mod level_dat;
+mod player_data;
 mod to_valence;
+mod upgrade;
 
 #[derive(Debug)]
 pub struct AnvilWorld {
-    /// Path to the "region" subdirectory in the world root.
-    region_root: PathBuf,
-    // TODO: LRU cache for region file handles.
-    /// Maps region (x, z) positions to region files.
+    /// Path to the world directory.
+    root: PathBuf,
+    /// The region files in the "region" subdirectory, containing the terrain
+    /// and block entities.
+    chunks: RegionFolder,
+    /// The region files in the "entities" subdirectory.
+    entities: RegionFolder,
+    /// The maximum number of region files kept open in both subdirectories.
+    region_cache_capacity: usize,
+}
+
+/// A directory of region files.
+#[derive(Debug)]
+struct RegionFolder {
+    /// Path to the directory.
+    root: PathBuf,
+    /// Maps region (x, z) positions to open region files.
     regions: BTreeMap<(i32, i32), Region>,
+    /// The number of region accesses which found the file open.
+    hits: u64,
+    /// The number of region accesses which had to open the file.
+    misses: u64,
+}
+
+/// Statistics about the region files kept open by an [`AnvilWorld`]. See
+/// [`AnvilWorld::region_cache_stats`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct RegionCacheStats {
+    /// The number of chunk reads and writes which found their region file
+    /// already open.
+    pub hits: u64,
+    /// The number of chunk reads and writes which had to open their region
+    /// file, including those for which the file does not exist.
+    pub misses: u64,
+    /// The number of region files currently open.
+    pub open_handles: usize,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -67,23 +114,109 @@ pub enum ReadChunkError {
     IncompleteNbtRead,
 }
 
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum WriteChunkError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Nbt(#[from] valence_nbt::Error),
+    #[error("region file is too large")]
+    RegionTooLarge,
+}
+
 #[derive(Debug)]
 struct Region {
     file: File,
     /// The first 8 KiB in the file.
     header: [u8; SECTOR_SIZE * 2],
+    /// Which sectors of the file are in use, including the header.
+    used_sectors: Vec<bool>,
+    /// When the region was last accessed, used to close the least recently
+    /// used region files first.
+    last_used: Instant,
+    /// The modification time and length of the file after it was last read
+    /// or written here. If these change, the file was modified by another
+    /// program and the header must be read again.
+    modified: Option<SystemTime>,
+    len: u64,
 }
 
 const SECTOR_SIZE: usize = 4096;
 
+/// The largest number of sectors a chunk can occupy in a region file. Larger
+/// chunks are stored in a separate `.mcc` file.
+const MAX_SECTOR_COUNT: usize = 255;
+
+/// Set in the compression scheme byte when the chunk data is stored in a
+/// separate `.mcc` file.
+const EXTERNAL_FLAG: u8 = 0x80;
+
+const ZLIB_COMPRESSION: u8 = 2;
+
+/// The default maximum number of region files an [`AnvilWorld`] keeps open.
+pub const DEFAULT_REGION_CACHE_CAPACITY: usize = 256;
+
 impl AnvilWorld {
     pub fn new(world_root: impl Into<PathBuf>) -> Self {
-        let mut region_root = world_root.into();
-        region_root.push("region");
+        let world_root = world_root.into();
 
         Self {
-            region_root,
-            regions: BTreeMap::new(),
+            chunks: RegionFolder::new(world_root.join("region")),
+            entities: RegionFolder::new(world_root.join("entities")),
+            root: world_root,
+            region_cache_capacity: DEFAULT_REGION_CACHE_CAPACITY,
+        }
+    }
+
+    /// Returns the maximum number of region files kept open. See
+    /// [`Self::set_region_cache_capacity`].
+    pub fn region_cache_capacity(&self) -> usize {
+        self.region_cache_capacity
+    }
+
+    /// Sets the maximum number of region files kept open, in the region and
+    /// entities subdirectories combined. Once the limit is reached, the least
+    /// recently used files are closed. A capacity of zero closes every file
+    /// after each access.
+    ///
+    /// The default is [`DEFAULT_REGION_CACHE_CAPACITY`].
+    pub fn set_region_cache_capacity(&mut self, capacity: usize) {
+        self.region_cache_capacity = capacity;
+        self.evict_regions();
+    }
+
+    /// Returns statistics about the region files kept open, which can be used
+    /// to tune [`Self::set_region_cache_capacity`].
+    pub fn region_cache_stats(&self) -> RegionCacheStats {
+        RegionCacheStats {
+            hits: self.chunks.hits + self.entities.hits,
+            misses: self.chunks.misses + self.entities.misses,
+            open_handles: self.chunks.regions.len() + self.entities.regions.len(),
+        }
+    }
+
+    /// Closes the least recently used region files until no more than the
+    /// capacity are open.
+    fn evict_regions(&mut self) {
+        while self.chunks.regions.len() + self.entities.regions.len() > self.region_cache_capacity {
+            match (
+                self.chunks.least_recently_used(),
+                self.entities.least_recently_used(),
+            ) {
+                (Some((pos, chunks_used)), Some((_, entities_used)))
+                    if chunks_used <= entities_used =>
+                {
+                    self.chunks.regions.remove(&pos);
+                }
+                (_, Some((pos, _))) => {
+                    self.entities.regions.remove(&pos);
+                }
+                (Some((pos, _)), None) => {
+                    self.chunks.regions.remove(&pos);
+                }
+                (None, None) => break,
+            }
         }
     }
 
@@ -94,35 +227,274 @@ impl AnvilWorld {
         chunk_x: i32,
         chunk_z: i32,
     ) -> Result<Option<AnvilChunk>, ReadChunkError> {
+        let res = self.chunks.read(chunk_x, chunk_z);
+        self.evict_regions();
+        res
+    }
+
+    /// Writes a chunk to the file system at the given chunk coordinates,
+    /// replacing the chunk that was there before. The region file is created if
+    /// it does not exist, and the timestamp of the chunk is set to the current
+    /// time.
+    ///
+    /// The chunk data is compressed with zlib. Data that does not fit in the
+    /// region file is stored in a separate `.mcc` file, like in vanilla.
+    ///
+    /// The new data is written to unused sectors before the region header is
+    /// updated, so the old chunk is left intact if writing fails.
+    pub fn write_chunk(
+        &mut self,
+        chunk_x: i32,
+        chunk_z: i32,
+        data: &Compound,
+    ) -> Result<(), WriteChunkError> {
+        let res = self.chunks.write(chunk_x, chunk_z, data);
+        self.evict_regions();
+        res
+    }
+
+    /// Reads the entities in the chunk at the given chunk coordinates. Since
+    /// Minecraft 1.17, entities are stored separately from the terrain in the
+    /// "entities" subdirectory. If the chunk has no saved entities, then
+    /// `None` is returned.
+    ///
+    /// Use [`parse_entities`] to read the entities from the returned data.
+    pub fn read_entities(
+        &mut self,
+        chunk_x: i32,
+        chunk_z: i32,
+    ) -> Result<Option<AnvilChunk>, ReadChunkError> {
+        let res = self.entities.read(chunk_x, chunk_z);
+        self.evict_regions();
+        res
+    }
+
+    /// Writes the entities of the chunk at the given chunk coordinates to the
+    /// "entities" subdirectory. See [`Self::write_chunk`].
+    pub fn write_entities(
+        &mut self,
+        chunk_x: i32,
+        chunk_z: i32,
+        data: &Compound,
+    ) -> Result<(), WriteChunkError> {
+        let res = self.entities.write(chunk_x, chunk_z, data);
+        self.evict_regions();
+        res
+    }
+
+    /// Reads the world metadata from the gzip-compressed `level.dat` file in
+    /// the world directory.
+    pub fn read_level_dat(&self) -> Result<LevelDat, ReadLevelDatError> {
+        let compressed = fs::read(self.root.join("level.dat"))?;
+
+        LevelDat::from_nbt(&decode_gzip_nbt::<ReadLevelDatError>(&compressed)?)
+    }
+
+    /// Writes the world metadata to the `level.dat` file in the world
+    /// directory. Like the game, the previous file is kept as
+    /// `level.dat_old`.
+    pub fn write_level_dat(&self, level_dat: &LevelDat) -> Result<(), WriteLevelDatError> {
+        write_gzip_nbt(&self.root, "level.dat", &level_dat.to_nbt())
+    }
+
+    /// Reads the data of the player with the given UUID from the
+    /// `playerdata` subdirectory. If the player has no saved data, then
+    /// `None` is returned.
+    pub fn read_player_data(&self, uuid: Uuid) -> Result<Option<PlayerData>, ReadPlayerDataError> {
+        let path = self.root.join("playerdata").join(format!("{uuid}.dat"));
+
+        let compressed = match fs::read(path) {
+            Ok(compressed) => compressed,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let nbt = decode_gzip_nbt::<ReadPlayerDataError>(&compressed)?;
+
+        Ok(Some(PlayerData::from_nbt(&nbt)))
+    }
+
+    /// Writes the data of the player with the given UUID to the `playerdata`
+    /// subdirectory. Like the game, the previous file is kept with the `.dat_old`
+    /// extension.
+    pub fn write_player_data(
+        &self,
+        uuid: Uuid,
+        player_data: &PlayerData,
+    ) -> Result<(), WritePlayerDataError> {
+        write_gzip_nbt(
+            &self.root.join("playerdata"),
+            &format!("{uuid}.dat"),
+            &player_data.to_nbt(),
+        )
+    }
+}
+
+/// Decodes the contents of a gzip-compressed NBT file.
+fn decode_gzip_nbt<E>(compressed: &[u8]) -> Result<Compound, E>
+where
+    E: From<io::Error> + From<valence_nbt::Error>,
+{
+    let mut buf = vec![];
+    GzDecoder::new(compressed).read_to_end(&mut buf)?;
+
+    let (nbt, _) = valence_nbt::from_binary_slice(&mut buf.as_slice())?;
+
+    Ok(nbt)
+}
+
+/// Writes a gzip-compressed NBT file to the directory, creating the directory
+/// if needed. The previous file is kept with an `_old` suffix.
+fn write_gzip_nbt<E>(dir: &Path, file_name: &str, nbt: &Compound) -> Result<(), E>
+where
+    E: From<io::Error> + From<valence_nbt::Error>,
+{
+    let mut z = GzEncoder::new(vec![], Compression::default());
+    valence_nbt::to_binary_writer(&mut z, nbt, "")?;
+    let compressed = z.finish()?;
+
+    fs::create_dir_all(dir)?;
+
+    let path = dir.join(file_name);
+    let new_path = dir.join(format!("{file_name}_new"));
+
+    // Write to a separate file first so that the old file is left intact if
+    // writing fails.
+    fs::write(&new_path, compressed)?;
+
+    if path.exists() {
+        fs::rename(&path, dir.join(format!("{file_name}_old")))?;
+    }
+
+    fs::rename(new_path, path)?;
+
+    Ok(())
+}
+
+impl RegionFolder {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            regions: BTreeMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the region file containing the given chunk. If the file does
+    /// not exist, it is created if `create` is true and `None` is returned
+    /// otherwise.
+    fn region(
+        &mut self,
+        chunk_x: i32,
+        chunk_z: i32,
+        create: bool,
+    ) -> io::Result<Option<&mut Region>> {
         let region_x = chunk_x.div_euclid(32);
         let region_z = chunk_z.div_euclid(32);
 
-        let region = match self.regions.entry((region_x, region_z)) {
-            Entry::Vacant(ve) => {
-                // Load the region file if it exists. Otherwise, the chunk is considered absent.
+        let path = self.root.join(format!("r.{region_x}.{region_z}.mca"));
 
-                // TODO: Add tombstone for missing region file in `regions`.
+        if let Some(region) = self.regions.get(&(region_x, region_z)) {
+            // The file may have been modified by another program since the header was
+            // read, in which case it is opened again.
+            let unchanged = match fs::metadata(&path) {
+                Ok(metadata) => region.matches_metadata(&metadata),
+                Err(e) if e.kind() == ErrorKind::NotFound => false,
+                Err(e) => return Err(e),
+            };
 
-                let path = self
-                    .region_root
-                    .join(format!("r.{region_x}.{region_z}.mca"));
+            if unchanged {
+                self.hits += 1;
 
-                let mut file = match File::options().read(true).write(true).open(path) {
-                    Ok(file) => file,
-                    Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
-                    Err(e) => return Err(e.into()),
-                };
+                let region = self.regions.get_mut(&(region_x, region_z)).unwrap();
+                region.last_used = Instant::now();
+                return Ok(Some(region));
+            }
 
-                let mut header = [0; SECTOR_SIZE * 2];
+            self.regions.remove(&(region_x, region_z));
+        }
+
+        self.misses += 1;
+
+        // TODO: Add tombstone for missing region file in `regions`.
 
-                file.read_exact(&mut header)?;
+        let mut file = match File::options().read(true).write(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound && create => {
+                fs::create_dir_all(&self.root)?;
 
-                ve.insert(Region { file, header })
+                let mut file = File::options()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(&path)?;
+
+                file.write_all(&[0; SECTOR_SIZE * 2])?;
+                file
             }
-            Entry::Occupied(oe) => oe.into_mut(),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
         };
 
-        let chunk_idx = (chunk_x.rem_euclid(32) + chunk_z.rem_euclid(32) * 32) as usize;
+        let mut header = [0; SECTOR_SIZE * 2];
+
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+
+        let mut used_sectors = vec![true; 2];
+
+        for location in header[..SECTOR_SIZE].chunks_exact(4) {
+            let (offset, count) = sector_location(location);
+
+            if offset >= 2 {
+                if used_sectors.len() < offset + count {
+                    used_sectors.resize(offset + count, false);
+                }
+
+                used_sectors[offset..offset + count].fill(true);
+            }
+        }
+
+        let mut region = Region {
+            file,
+            header,
+            used_sectors,
+            last_used: Instant::now(),
+            modified: None,
+            len: 0,
+        };
+
+        region.update_metadata()?;
+
+        Ok(Some(
+            self.regions.entry((region_x, region_z)).or_insert(region),
+        ))
+    }
+
+    /// Returns the path of the file storing the data of a chunk that is too
+    /// large to fit in its region file.
+    fn external_chunk_path(&self, chunk_x: i32, chunk_z: i32) -> PathBuf {
+        self.root.join(format!("c.{chunk_x}.{chunk_z}.mcc"))
+    }
+
+    /// Returns the position and last access time of the least recently used
+    /// open region file.
+    fn least_recently_used(&self) -> Option<((i32, i32), Instant)> {
+        self.regions
+            .iter()
+            .map(|(&pos, region)| (pos, region.last_used))
+            .min_by_key(|&(_, last_used)| last_used)
+    }
+
+    fn read(&mut self, chunk_x: i32, chunk_z: i32) -> Result<Option<AnvilChunk>, ReadChunkError> {
+        let Some(region) = self.region(chunk_x, chunk_z, false)? else {
+            // The region file does not exist, so the chunk is considered absent.
+            return Ok(None);
+        };
+
+        let chunk_idx = chunk_idx(chunk_x, chunk_z);
 
         let location_bytes = (&region.header[chunk_idx * 4..]).read_u32::<BigEndian>()?;
         let timestamp = (&region.header[chunk_idx * 4 + SECTOR_SIZE..]).read_u32::<BigEndian>()?;
@@ -148,7 +520,7 @@ impl AnvilWorld {
 
         let exact_chunk_size = region.file.read_u32::<BigEndian>()? as usize;
 
-        if exact_chunk_size > sector_count * SECTOR_SIZE {
+        if exact_chunk_size == 0 || exact_chunk_size > sector_count * SECTOR_SIZE {
             // Sector size of this chunk must always be >= the exact size.
             return Err(ReadChunkError::BadChunkSize);
         }
@@ -158,10 +530,21 @@ impl AnvilWorld {
 
         let mut r = data_buf.as_ref();
 
+        let mut compression = r.read_u8()?;
+
+        let external_buf;
+
+        if compression & EXTERNAL_FLAG != 0 {
+            // The chunk data is stored in a separate file.
+            compression &= !EXTERNAL_FLAG;
+            external_buf = fs::read(self.external_chunk_path(chunk_x, chunk_z))?;
+            r = &external_buf;
+        }
+
         let mut decompress_buf = vec![];
 
         // What compression does the chunk use?
-        let mut nbt_slice = match r.read_u8()? {
+        let mut nbt_slice = match compression {
             // GZip
             1 => {
                 let mut z = GzDecoder::new(r);
@@ -188,4 +571,1046 @@ impl AnvilWorld {
 
         Ok(Some(AnvilChunk { data, timestamp }))
     }
+
+    fn write(
+        &mut self,
+        chunk_x: i32,
+        chunk_z: i32,
+        data: &Compound,
+    ) -> Result<(), WriteChunkError> {
+        let mut z = ZlibEncoder::new(vec![], Compression::default());
+        valence_nbt::to_binary_writer(&mut z, data, "")?;
+        let compressed = z.finish()?;
+
+        let external = 5 + compressed.len() > MAX_SECTOR_COUNT * SECTOR_SIZE;
+        let external_path = self.external_chunk_path(chunk_x, chunk_z);
+
+        let mut payload = vec![];
+
+        if external {
+            fs::create_dir_all(&self.root)?;
+            fs::write(&external_path, &compressed)?;
+
+            payload.write_u32::<BigEndian>(1)?;
+            payload.write_u8(ZLIB_COMPRESSION | EXTERNAL_FLAG)?;
+        } else {
+            payload.write_u32::<BigEndian>(compressed.len() as u32 + 1)?;
+            payload.write_u8(ZLIB_COMPRESSION)?;
+            payload.extend_from_slice(&compressed);
+        }
+
+        // Pad the data to a whole number of sectors.
+        let sector_count = div_ceil(payload.len(), SECTOR_SIZE);
+        payload.resize(sector_count * SECTOR_SIZE, 0);
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as u32);
+
+        let region = self
+            .region(chunk_x, chunk_z, true)?
+            .expect("region file should have been created");
+
+        let sector_offset = region.allocate_sectors(sector_count);
+
+        if sector_offset >= 1 << 24 {
+            region.free_sectors(sector_offset, sector_count);
+            return Err(WriteChunkError::RegionTooLarge);
+        }
+
+        region
+            .file
+            .seek(SeekFrom::Start((sector_offset * SECTOR_SIZE) as u64))?;
+        region.file.write_all(&payload)?;
+
+        let chunk_idx = chunk_idx(chunk_x, chunk_z);
+        let (old_offset, old_count) =
+            sector_location(&region.header[chunk_idx * 4..chunk_idx * 4 + 4]);
+
+        let location = (sector_offset as u32) << 8 | sector_count as u32;
+
+        (&mut region.header[chunk_idx * 4..]).write_u32::<BigEndian>(location)?;
+        (&mut region.header[chunk_idx * 4 + SECTOR_SIZE..]).write_u32::<BigEndian>(timestamp)?;
+
+        region.file.seek(SeekFrom::Start((chunk_idx * 4) as u64))?;
+        region.file.write_u32::<BigEndian>(location)?;
+
+        region
+            .file
+            .seek(SeekFrom::Start((chunk_idx * 4 + SECTOR_SIZE) as u64))?;
+        region.file.write_u32::<BigEndian>(timestamp)?;
+
+        // Only free the old sectors once the header points to the new data.
+        if old_offset >= 2 {
+            region.free_sectors(old_offset, old_count);
+        }
+
+        region.update_metadata()?;
+
+        if !external {
+            // Remove the data of the chunk from when it was too large, if any.
+            match fs::remove_file(&external_path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Region {
+    /// Returns whether the file still has the modification time and length it
+    /// had after it was last read or written here.
+    fn matches_metadata(&self, metadata: &fs::Metadata) -> bool {
+        metadata.modified().ok() == self.modified && metadata.len() == self.len
+    }
+
+    /// Records the current modification time and length of the file.
+    fn update_metadata(&mut self) -> io::Result<()> {
+        let metadata = self.file.metadata()?;
+
+        self.modified = metadata.modified().ok();
+        self.len = metadata.len();
+
+        Ok(())
+    }
+
+    /// Finds a run of unused sectors and marks them as used. Returns the offset
+    /// of the first sector.
+    fn allocate_sectors(&mut self, count: usize) -> usize {
+        let mut run_start = 0;
+        let mut run_len = 0;
+
+        for (i, &used) in self.used_sectors.iter().enumerate() {
+            if used {
+                run_len = 0;
+            } else {
+                if run_len == 0 {
+                    run_start = i;
+                }
+
+                run_len += 1;
+
+                if run_len == count {
+                    self.used_sectors[run_start..run_start + count].fill(true);
+                    return run_start;
+                }
+            }
+        }
+
+        // Append the sectors to the end of the file, reusing the unused sectors
+        // at the end if there are any.
+        let offset = if run_len > 0 {
+            run_start
+        } else {
+            self.used_sectors.len()
+        };
+
+        self.used_sectors.resize(offset + count, true);
+        self.used_sectors[offset..].fill(true);
+
+        offset
+    }
+
+    fn free_sectors(&mut self, offset: usize, count: usize) {
+        let end = (offset + count).min(self.used_sectors.len());
+        self.used_sectors[offset..end].fill(false);
+    }
+}
+
+/// Returns the index of a chunk in the header of its region file.
+fn chunk_idx(chunk_x: i32, chunk_z: i32) -> usize {
+    (chunk_x.rem_euclid(32) + chunk_z.rem_euclid(32) * 32) as usize
+}
+
+/// Decodes the sector offset and sector count of a chunk from its location in
+/// the region header.
+fn sector_location(bytes: &[u8]) -> (usize, usize) {
+    let location = u32::from_be_bytes(bytes.try_into().unwrap());
+
+    ((location >> 8) as usize, (location & 0xff) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use glam::{DVec3, Vec3};
+    use uuid::Uuid;
+    use valence_biome::BiomeId;
+    use valence_block::{BlockEntityKind, BlockKind, BlockState, PropName, PropValue};
+    use valence_core::block_pos::BlockPos;
+    use valence_core::chunk_pos::ChunkPos;
+    use valence_core::difficulty::Difficulty;
+    use valence_core::game_mode::GameMode;
+    use valence_core::ident;
+    use valence_core::ident::Ident;
+    use valence_core::item::{ItemKind, ItemStack};
+    use valence_entity::EntityKind;
+    use valence_instance::{BlockEntity, Chunk, HeightmapKind};
+    use valence_nbt::{compound, List, Value};
+
+    use super::*;
+
+    /// Returns the data of a chunk laid out like the chunks saved by vanilla
+    /// 1.19.4, with light-only sections above and below the chunk.
+    fn vanilla_chunk(x: i32, z: i32) -> Compound {
+        let mut sections = vec![compound! {
+            "Y" => -5_i8,
+            "SkyLight" => vec![0_i8; 2048],
+        }];
+
+        for y in -4..20_i8 {
+            let mut section = if y == -4 {
+                // Bedrock in the bottom layer and stone above it, with an
+                // enchanting table at (5, 4, 3).
+                let mut data = vec![0_i64; 256];
+                data[16..].fill(0x1111_1111_1111_1111);
+                data[67] = 0x1111_1111_1121_1111;
+
+                compound! {
+                    "Y" => y,
+                    "block_states" => compound! {
+                        "palette" => List::Compound(vec![
+                            compound! { "Name" => "minecraft:bedrock" },
+                            compound! { "Name" => "minecraft:stone" },
+                            compound! { "Name" => "minecraft:enchanting_table" },
+                        ]),
+                        "data" => data,
+                    },
+                    "biomes" => compound! {
+                        "palette" => List::String(vec![
+                            "minecraft:plains".into(),
+                            "minecraft:desert".into(),
+                        ]),
+                        "data" => vec![0x5555_5555_5555_5555_i64],
+                    },
+                }
+            } else {
+                compound! {
+                    "Y" => y,
+                    "block_states" => compound! {
+                        "palette" => List::Compound(vec![
+                            compound! { "Name" => "minecraft:air" },
+                        ]),
+                    },
+                    "biomes" => compound! {
+                        "palette" => List::String(vec!["minecraft:plains".into()]),
+                    },
+                }
+            };
+
+            section.insert("BlockLight", vec![0_i8; 2048]);
+            section.insert("SkyLight", vec![-1_i8; 2048]);
+
+            sections.push(section);
+        }
+
+        sections.push(compound! {
+            "Y" => 20_i8,
+            "SkyLight" => vec![-1_i8; 2048],
+        });
+
+        compound! {
+            "DataVersion" => DATA_VERSION,
+            "xPos" => x,
+            "zPos" => z,
+            "yPos" => -4,
+            "Status" => "full",
+            "LastUpdate" => 1234_i64,
+            "InhabitedTime" => 42_i64,
+            "isLightOn" => true,
+            "Heightmaps" => compound! {
+                "MOTION_BLOCKING" => vec![0_i64; 37],
+            },
+            "structures" => compound! {
+                "References" => Compound::new(),
+                "starts" => Compound::new(),
+            },
+            "sections" => List::Compound(sections),
+            "block_entities" => List::Compound(vec![compound! {
+                "id" => "minecraft:enchanting_table",
+                "x" => x * 16 + 5,
+                "y" => -60,
+                "z" => z * 16 + 3,
+                "keepPacked" => false,
+                "CustomName" => r#"{"text":"Table"}"#,
+            }]),
+        }
+    }
+
+    fn biome_id(name: Ident<&str>) -> BiomeId {
+        match name.as_str() {
+            "minecraft:plains" => BiomeId(0),
+            "minecraft:desert" => BiomeId(1),
+            _ => panic!("unexpected biome {name}"),
+        }
+    }
+
+    fn biome_name(id: BiomeId) -> Ident<String> {
+        let name = match id.0 {
+            0 => "minecraft:plains",
+            1 => "minecraft:desert",
+            _ => panic!("unexpected biome {id:?}"),
+        };
+
+        name.try_into().unwrap()
+    }
+
+    /// Generates data that does not compress well.
+    fn noise(len: usize) -> Vec<i8> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as i8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn write_and_read_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut world = AnvilWorld::new(dir.path());
+
+        assert_eq!(world.read_chunk(0, 0).unwrap(), None);
+
+        let a = vanilla_chunk(0, 0);
+        let b = vanilla_chunk(-1, 31);
+
+        world.write_chunk(0, 0, &a).unwrap();
+        world.write_chunk(-1, 31, &b).unwrap();
+
+        let chunk = world.read_chunk(0, 0).unwrap().unwrap();
+
+        assert_eq!(chunk.data, a);
+        assert!(chunk.timestamp > 0);
+
+        // Read the chunks back with new file handles.
+        let mut world = AnvilWorld::new(dir.path());
+
+        assert_eq!(world.read_chunk(0, 0).unwrap().unwrap().data, a);
+        assert_eq!(world.read_chunk(-1, 31).unwrap().unwrap().data, b);
+        assert_eq!(world.read_chunk(1, 0).unwrap(), None);
+
+        assert!(dir.path().join("region/r.0.0.mca").exists());
+        assert!(dir.path().join("region/r.-1.0.mca").exists());
+    }
+
+    #[test]
+    fn sectors_are_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut world = AnvilWorld::new(dir.path());
+
+        let small = compound! { "data" => vec![0_i8; 10] };
+        // Takes up three sectors after compression.
+        let large = compound! { "data" => noise(3 * SECTOR_SIZE - 100) };
+
+        world.write_chunk(0, 0, &small).unwrap();
+        world.write_chunk(1, 0, &small).unwrap();
+
+        let region_path = dir.path().join("region/r.0.0.mca");
+        let len = |path: &PathBuf| fs::metadata(path).unwrap().len() as usize;
+
+        assert_eq!(len(&region_path), 4 * SECTOR_SIZE);
+
+        // The larger chunk does not fit in its old sector, so it is moved to
+        // the end of the file.
+        world.write_chunk(0, 0, &large).unwrap();
+
+        assert_eq!(len(&region_path), 7 * SECTOR_SIZE);
+
+        // The sector freed by the first chunk is reused.
+        world.write_chunk(2, 0, &small).unwrap();
+
+        assert_eq!(len(&region_path), 7 * SECTOR_SIZE);
+
+        // Shrinking the first chunk frees its sectors for the next chunk.
+        world.write_chunk(0, 0, &small).unwrap();
+        world.write_chunk(3, 0, &large).unwrap();
+
+        assert_eq!(len(&region_path), 8 * SECTOR_SIZE);
+
+        let mut world = AnvilWorld::new(dir.path());
+
+        assert_eq!(world.read_chunk(0, 0).unwrap().unwrap().data, small);
+        assert_eq!(world.read_chunk(1, 0).unwrap().unwrap().data, small);
+        assert_eq!(world.read_chunk(2, 0).unwrap().unwrap().data, small);
+        assert_eq!(world.read_chunk(3, 0).unwrap().unwrap().data, large);
+    }
+
+    #[test]
+    fn oversized_chunk_stored_externally() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut world = AnvilWorld::new(dir.path());
+
+        let large = compound! { "data" => noise(MAX_SECTOR_COUNT * SECTOR_SIZE + 1) };
+        let small = vanilla_chunk(5, -3);
+
+        world.write_chunk(5, -3, &large).unwrap();
+
+        let mcc_path = dir.path().join("region/c.5.-3.mcc");
+
+        assert!(mcc_path.exists());
+        assert_eq!(
+            fs::metadata(dir.path().join("region/r.0.-1.mca"))
+                .unwrap()
+                .len(),
+            3 * SECTOR_SIZE as u64
+        );
+
+        let mut world = AnvilWorld::new(dir.path());
+
+        assert_eq!(world.read_chunk(5, -3).unwrap().unwrap().data, large);
+
+        world.write_chunk(5, -3, &small).unwrap();
+
+        assert!(!mcc_path.exists());
+        assert_eq!(world.read_chunk(5, -3).unwrap().unwrap().data, small);
+    }
+
+    #[test]
+    fn region_cache_closes_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut world = AnvilWorld::new(dir.path());
+        world.set_region_cache_capacity(2);
+
+        let chunk = compound! { "data" => 1 };
+
+        // One chunk in each of three regions.
+        world.write_chunk(0, 0, &chunk).unwrap();
+        world.write_chunk(32, 0, &chunk).unwrap();
+        world.write_entities(64, 0, &chunk).unwrap();
+
+        assert_eq!(
+            world.region_cache_stats(),
+            RegionCacheStats {
+                hits: 0,
+                misses: 3,
+                open_handles: 2,
+            }
+        );
+
+        // The region written last is still open.
+        world.read_entities(64, 0).unwrap().unwrap();
+        assert_eq!(world.region_cache_stats().hits, 1);
+
+        // The region written first was closed.
+        world.read_chunk(0, 0).unwrap().unwrap();
+        assert_eq!(world.region_cache_stats().misses, 4);
+
+        // Chunks in the same region share the open file.
+        world.read_chunk(31, 31).unwrap();
+        assert_eq!(world.region_cache_stats().hits, 2);
+
+        world.set_region_cache_capacity(0);
+        assert_eq!(world.region_cache_stats().open_handles, 0);
+    }
+
+    #[test]
+    fn region_cache_detects_external_modification() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut world = AnvilWorld::new(dir.path());
+        let mut other_world = AnvilWorld::new(dir.path());
+
+        let a = compound! { "data" => 1 };
+        let b = compound! { "data" => 2 };
+
+        world.write_chunk(0, 0, &a).unwrap();
+        assert_eq!(world.read_chunk(1, 0).unwrap(), None);
+
+        // The region header read by `world` is now out of date.
+        other_world.write_chunk(1, 0, &b).unwrap();
+        other_world.write_chunk(0, 0, &b).unwrap();
+
+        assert_eq!(world.read_chunk(1, 0).unwrap().unwrap().data, b);
+        assert_eq!(world.read_chunk(0, 0).unwrap().unwrap().data, b);
+
+        // Deleted region files are not read from the open handle.
+        fs::remove_file(dir.path().join("region/r.0.0.mca")).unwrap();
+
+        assert_eq!(world.read_chunk(0, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn unchanged_chunk_round_trip() {
+        let original = vanilla_chunk(1, 2);
+
+        let mut chunk = Chunk::new(24);
+        to_valence(&original, &mut chunk, 4, biome_id).unwrap();
+
+        let mut nbt = original.clone();
+        from_valence(&chunk, ChunkPos::new(1, 2), &mut nbt, 4, biome_name).unwrap();
+
+        assert_eq!(nbt, original);
+    }
+
+    #[test]
+    fn modified_chunk_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut world = AnvilWorld::new(dir.path());
+
+        let original = vanilla_chunk(1, 2);
+        world.write_chunk(1, 2, &original).unwrap();
+
+        // Load the chunk and change a block in the section at Y = 0.
+        let mut nbt = world.read_chunk(1, 2).unwrap().unwrap().data;
+
+        let mut chunk = Chunk::new(24);
+        to_valence(&nbt, &mut chunk, 4, biome_id).unwrap();
+
+        assert_eq!(chunk.block_state(0, 0, 0), BlockState::BEDROCK);
+        assert_eq!(chunk.block_state(0, 1, 0), BlockState::STONE);
+        assert_eq!(chunk.block_state(5, 4, 3), BlockState::ENCHANTING_TABLE);
+        assert_eq!(chunk.biome(0, 0, 0), BiomeId(1));
+        assert_eq!(chunk.biome(1, 0, 0), BiomeId(0));
+        assert!(chunk.block_entity(5, 4, 3).is_some());
+
+        chunk.set_block_state(8, 64, 8, BlockState::OAK_STAIRS);
+        chunk.set_block(
+            1,
+            65,
+            1,
+            valence_instance::Block::with_nbt(BlockState::CHEST, Compound::new()),
+        );
+        chunk.set_block_entity(
+            1,
+            65,
+            1,
+            BlockEntity::new(BlockEntityKind::Chest, compound! { "Lock" => "key" }),
+        );
+
+        from_valence(&chunk, ChunkPos::new(1, 2), &mut nbt, 4, biome_name).unwrap();
+        world.write_chunk(1, 2, &nbt).unwrap();
+
+        let mut world = AnvilWorld::new(dir.path());
+        let saved = world.read_chunk(1, 2).unwrap().unwrap().data;
+
+        let Some(Value::List(List::Compound(old_sections))) = original.get("sections") else {
+            panic!("missing sections");
+        };
+        let Some(Value::List(List::Compound(new_sections))) = saved.get("sections") else {
+            panic!("missing sections");
+        };
+
+        assert_eq!(old_sections.len(), new_sections.len());
+
+        for (old, new) in old_sections.iter().zip(new_sections) {
+            if new.get("Y") == Some(&Value::Byte(0)) {
+                assert_ne!(old, new);
+                assert!(!new.contains_key("BlockLight"));
+                assert!(!new.contains_key("SkyLight"));
+            } else {
+                // Sections that were not changed are left exactly as they were.
+                assert_eq!(old, new);
+            }
+        }
+
+        assert_eq!(saved.get("isLightOn"), Some(&Value::Byte(0)));
+
+        // The heightmaps are replaced with the ones of the Valence chunk.
+        let Some(Value::Compound(heightmaps)) = saved.get("Heightmaps") else {
+            panic!("missing heightmaps");
+        };
+
+        for kind in HeightmapKind::ALL {
+            assert_eq!(
+                heightmaps.get(kind.name()),
+                Some(&Value::LongArray(chunk.packed_heightmap(kind)))
+            );
+        }
+
+        assert_eq!(chunk.height_at(8, 8, HeightmapKind::WorldSurface), 65);
+        assert_eq!(chunk.height_at(8, 8, HeightmapKind::MotionBlocking), 65);
+
+        for key in [
+            "InhabitedTime",
+            "LastUpdate",
+            "structures",
+            "yPos",
+            "Status",
+        ] {
+            assert_eq!(saved.get(key), original.get(key), "{key}");
+        }
+
+        // Load the saved chunk again and check that nothing was lost.
+        let mut reloaded = Chunk::new(24);
+        to_valence(&saved, &mut reloaded, 4, biome_id).unwrap();
+
+        for y in 0..reloaded.section_count() * 16 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    assert_eq!(
+                        reloaded.block_state(x, y, z),
+                        chunk.block_state(x, y, z),
+                        "{x} {y} {z}"
+                    );
+                }
+            }
+        }
+
+        for y in 0..reloaded.section_count() * 4 {
+            for z in 0..4 {
+                for x in 0..4 {
+                    assert_eq!(reloaded.biome(x, y, z), chunk.biome(x, y, z));
+                }
+            }
+        }
+
+        let table = reloaded.block_entity(5, 4, 3).unwrap();
+
+        assert_eq!(table.kind, BlockEntityKind::EnchantingTable);
+        assert_eq!(table.nbt.get("x"), Some(&Value::Int(21)));
+        assert!(table.nbt.contains_key("CustomName"));
+
+        let new_chest = reloaded.block_entity(1, 65, 1).unwrap();
+
+        assert_eq!(
+            new_chest.nbt.get("Lock"),
+            Some(&Value::String("key".into()))
+        );
+        assert_eq!(new_chest.nbt.get("x"), Some(&Value::Int(17)));
+        assert_eq!(new_chest.nbt.get("y"), Some(&Value::Int(1)));
+        assert_eq!(new_chest.nbt.get("z"), Some(&Value::Int(33)));
+    }
+
+    #[test]
+    fn parse_vanilla_entities() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut world = AnvilWorld::new(dir.path());
+
+        let zombie = compound! {
+            "id" => "minecraft:zombie",
+            "UUID" => vec![1, 2, 3, -4],
+            "Pos" => List::Double(vec![10.5, 64.0, -3.5]),
+            "Motion" => List::Double(vec![0.0, -0.05, 0.0]),
+            "Rotation" => List::Float(vec![90.0, 10.0]),
+            "OnGround" => true,
+            "CustomName" => r#"{"text":"Bob"}"#,
+            "CustomNameVisible" => true,
+            "Passengers" => List::Compound(vec![
+                compound! {
+                    "id" => "minecraft:chicken",
+                    "Pos" => List::Double(vec![10.5, 65.0, -3.5]),
+                },
+                compound! { "id" => "minecraft:not_an_entity" },
+            ]),
+        };
+
+        let entities = compound! {
+            "DataVersion" => DATA_VERSION,
+            "Position" => vec![0, -1],
+            "Entities" => List::Compound(vec![
+                zombie.clone(),
+                compound! { "id" => "minecraft:not_an_entity", "Pos" => List::Double(vec![0.0; 3]) },
+                compound! { "id" => "minecraft:cow", "Pos" => List::Double(vec![0.0; 2]) },
+                compound! { "Pos" => List::Double(vec![0.0; 3]) },
+                compound! { "id" => "minecraft:item_frame", "Pos" => List::Double(vec![1.0, 2.0, 3.0]) },
+            ]),
+        };
+
+        assert!(world.read_entities(0, -1).unwrap().is_none());
+
+        world.write_entities(0, -1, &entities).unwrap();
+
+        // Entities are stored separately from the terrain.
+        assert!(world.read_chunk(0, -1).unwrap().is_none());
+        assert!(dir.path().join("entities/r.0.-1.mca").exists());
+
+        let data = world.read_entities(0, -1).unwrap().unwrap().data;
+        let parsed = parse_entities(&data);
+
+        assert_eq!(parsed.len(), 2);
+
+        let zombie_entity = &parsed[0];
+
+        assert_eq!(zombie_entity.kind, EntityKind::ZOMBIE);
+        assert_eq!(
+            zombie_entity.uuid,
+            Some(Uuid::from_u128(0x00000001_00000002_00000003_fffffffc))
+        );
+        assert_eq!(zombie_entity.position, DVec3::new(10.5, 64.0, -3.5));
+        assert_eq!(zombie_entity.velocity, Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!((zombie_entity.yaw, zombie_entity.pitch), (90.0, 10.0));
+        assert!(zombie_entity.on_ground);
+        assert_eq!(zombie_entity.custom_name, Some("Bob".into()));
+        assert!(zombie_entity.custom_name_visible);
+        assert!(!zombie_entity.no_gravity);
+        assert_eq!(zombie_entity.nbt, zombie);
+
+        assert_eq!(zombie_entity.passengers.len(), 1);
+        assert_eq!(zombie_entity.passengers[0].kind, EntityKind::CHICKEN);
+        assert_eq!(zombie_entity.passengers[0].uuid, None);
+
+        assert_eq!(parsed[1].kind, EntityKind::ITEM_FRAME);
+    }
+
+    #[test]
+    fn bad_block_entities_skipped() {
+        let mut nbt = vanilla_chunk(0, 0);
+
+        let Some(Value::List(List::Compound(block_entities))) = nbt.get_mut("block_entities")
+        else {
+            panic!("missing block entities");
+        };
+
+        block_entities.extend([
+            compound! { "id" => "minecraft:not_a_block_entity", "x" => 0, "y" => 0, "z" => 0 },
+            compound! { "id" => "minecraft:chest", "x" => 0, "z" => 0 },
+            compound! { "id" => "minecraft:chest", "x" => 0, "y" => 1000, "z" => 0 },
+            compound! { "id" => "minecraft:sign", "x" => 1, "y" => 70, "z" => -1 },
+        ]);
+
+        let parsed = parse_block_entities(&nbt);
+
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0].pos, BlockPos::new(5, -60, 3));
+        assert_eq!(parsed[2].block_entity.kind, BlockEntityKind::Sign);
+
+        // The chunk is still loaded, without the block entity outside of it.
+        let mut chunk = Chunk::new(24);
+        to_valence(&nbt, &mut chunk, 4, biome_id).unwrap();
+
+        assert!(chunk.block_entity(5, 4, 3).is_some());
+        assert!(chunk.block_entity(1, 134, 15).is_some());
+    }
+
+    /// Reads the chunk at (0, 0) of a fixture world, which is laid out like
+    /// the chunks saved by the given version of Minecraft.
+    fn read_fixture(version: &str) -> (Chunk, Vec<ParsedEntity>) {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(version);
+
+        let mut world = AnvilWorld::new(root);
+
+        let AnvilChunk { mut data, .. } = world.read_chunk(0, 0).unwrap().unwrap();
+
+        let mut chunk = Chunk::new(24);
+        to_valence(&data, &mut chunk, 4, |name| match name.as_str() {
+            "minecraft:plains" => BiomeId(0),
+            "minecraft:desert" => BiomeId(1),
+            "minecraft:windswept_hills" => BiomeId(2),
+            _ => panic!("unexpected biome {name} in {version} fixture"),
+        })
+        .unwrap();
+
+        upgrade_chunk(&mut data).unwrap();
+
+        let entities = match world.read_entities(0, 0).unwrap() {
+            Some(AnvilChunk { data, .. }) => parse_entities(&data),
+            None => parse_entities(&data),
+        };
+
+        (chunk, entities)
+    }
+
+    #[test]
+    fn read_chunks_from_supported_versions() {
+        for version in ["1.16.5", "1.17.1", "1.18.2", "1.19.4"] {
+            let (chunk, entities) = read_fixture(version);
+
+            assert_eq!(chunk.block_state(0, 64, 0), BlockState::STONE, "{version}");
+            // Renamed in 1.17.
+            assert_eq!(
+                chunk.block_state(1, 64, 0),
+                BlockState::DIRT_PATH,
+                "{version}"
+            );
+            assert_eq!(
+                chunk.block_state(2, 64, 0),
+                BlockState::WATER_CAULDRON.set(PropName::Level, PropValue::_2),
+                "{version}"
+            );
+            assert_eq!(
+                chunk.block_state(5, 134, 5),
+                BlockState::OAK_LOG.set(PropName::Axis, PropValue::X),
+                "{version}"
+            );
+            assert_eq!(chunk.block_state(5, 133, 5), BlockState::AIR, "{version}");
+
+            assert_eq!(chunk.block_state(3, 64, 0).to_kind(), BlockKind::Chest);
+            let chest = chunk.block_entity(3, 64, 0).expect(version);
+            assert_eq!(chest.kind, BlockEntityKind::Chest, "{version}");
+
+            assert_eq!(chunk.biome(0, 16, 0), BiomeId(2), "{version}");
+            assert_eq!(chunk.biome(1, 16, 0), BiomeId(0), "{version}");
+            assert_eq!(chunk.biome(0, 32, 0), BiomeId(1), "{version}");
+            assert_eq!(chunk.biome(0, 33, 0), BiomeId(0), "{version}");
+
+            assert_eq!(entities.len(), 1, "{version}");
+            assert_eq!(entities[0].kind, EntityKind::PIG, "{version}");
+            assert_eq!(entities[0].position, DVec3::new(8.5, 1.0, 8.5), "{version}");
+        }
+    }
+
+    #[test]
+    fn upgraded_chunk_written_in_current_layout() {
+        let (chunk, _) = read_fixture("1.16.5");
+
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/1.16.5");
+        let AnvilChunk { mut data, .. } = AnvilWorld::new(root).read_chunk(0, 0).unwrap().unwrap();
+
+        from_valence(&chunk, ChunkPos::new(0, 0), &mut data, 4, |id| {
+            match id.0 {
+                0 => ident!("plains"),
+                1 => ident!("desert"),
+                _ => ident!("windswept_hills"),
+            }
+            .into()
+        })
+        .unwrap();
+
+        assert!(!data.contains_key("Level"));
+        assert_eq!(data.get("DataVersion"), Some(&Value::Int(DATA_VERSION)));
+
+        let mut read_back = Chunk::new(24);
+        to_valence(&data, &mut read_back, 4, |name| match name.as_str() {
+            "minecraft:plains" => BiomeId(0),
+            "minecraft:desert" => BiomeId(1),
+            _ => BiomeId(2),
+        })
+        .unwrap();
+
+        assert_eq!(read_back.block_state(1, 64, 0), BlockState::DIRT_PATH);
+        assert_eq!(read_back.biome(0, 16, 0), BiomeId(2));
+        assert!(read_back.block_entity(3, 64, 0).is_some());
+    }
+
+    #[test]
+    fn unsupported_data_version() {
+        let mut nbt = compound! {
+            "DataVersion" => 2230,
+            "Level" => compound! {
+                "xPos" => 0,
+                "zPos" => 0,
+            },
+        };
+
+        let err = to_valence(&nbt, &mut Chunk::new(24), 4, biome_id).unwrap_err();
+
+        assert!(matches!(err, ToValenceError::UnsupportedDataVersion(2230)));
+        assert!(err.to_string().contains("2230"));
+
+        nbt.remove("DataVersion");
+
+        assert!(matches!(
+            upgrade_chunk(&mut nbt),
+            Err(ToValenceError::MissingDataVersion)
+        ));
+    }
+
+    #[test]
+    fn failed_upgrade_keeps_chunk() {
+        // Too old to be upgraded.
+        let old = compound! {
+            "DataVersion" => 2230,
+            "Level" => compound! { "xPos" => 0, "zPos" => 0 },
+        };
+
+        // Fails halfway through the upgrade.
+        let broken = compound! {
+            "DataVersion" => 2730,
+            "Level" => compound! { "Sections" => 5 },
+        };
+
+        for original in [old, broken] {
+            let mut nbt = original.clone();
+
+            assert!(from_valence(
+                &Chunk::new(24),
+                ChunkPos::new(0, 0),
+                &mut nbt,
+                4,
+                biome_name
+            )
+            .is_err());
+            assert_eq!(nbt, original);
+        }
+    }
+
+    #[test]
+    fn read_and_write_level_dat() {
+        let dir = tempfile::tempdir().unwrap();
+        let world = AnvilWorld::new(dir.path());
+
+        assert!(matches!(
+            world.read_level_dat(),
+            Err(ReadLevelDatError::Io(e)) if e.kind() == ErrorKind::NotFound
+        ));
+
+        // Laid out like the level.dat files saved by vanilla 1.19.4.
+        let root = compound! {
+            "Data" => compound! {
+                "DataVersion" => 3337,
+                "LevelName" => "New World",
+                "SpawnX" => 16,
+                "SpawnY" => 72,
+                "SpawnZ" => -32,
+                "SpawnAngle" => 0.0_f32,
+                "Time" => 24_000_i64,
+                "DayTime" => 30_000_i64,
+                "Difficulty" => 3_i8,
+                "DifficultyLocked" => false,
+                "GameRules" => compound! {
+                    "doDaylightCycle" => "true",
+                    "randomTickSpeed" => "3",
+                },
+                "BorderCenterX" => 0.0,
+                "BorderCenterZ" => 0.0,
+                "BorderSize" => 1000.0,
+                "BorderSafeZone" => 5.0,
+                "BorderDamagePerBlock" => 0.2,
+                "BorderWarningBlocks" => 5.0,
+                "BorderWarningTime" => 15.0,
+                "BorderSizeLerpTarget" => 1000.0,
+                "BorderSizeLerpTime" => 0_i64,
+                "DataPacks" => compound! {
+                    "Enabled" => List::String(vec!["vanilla".into()]),
+                    "Disabled" => List::String(vec!["bundle".into()]),
+                },
+                "WorldGenSettings" => compound! {
+                    "seed" => 1234_i64,
+                },
+            },
+        };
+
+        let mut z = GzEncoder::new(vec![], Compression::default());
+        valence_nbt::to_binary_writer(&mut z, &root, "").unwrap();
+        fs::write(dir.path().join("level.dat"), z.finish().unwrap()).unwrap();
+
+        let mut level_dat = world.read_level_dat().unwrap();
+
+        assert_eq!(level_dat.data_version, Some(3337));
+        assert_eq!(level_dat.level_name, "New World");
+        assert_eq!(level_dat.spawn_pos, BlockPos::new(16, 72, -32));
+        assert_eq!(level_dat.time, 24_000);
+        assert_eq!(level_dat.day_time, 30_000);
+        assert_eq!(level_dat.difficulty, Difficulty::Hard);
+        assert_eq!(level_dat.game_rules["randomTickSpeed"], "3");
+        assert_eq!(level_dat.border.size, 1000.0);
+        assert_eq!(level_dat.enabled_data_packs, ["vanilla"]);
+        assert_eq!(level_dat.disabled_data_packs, ["bundle"]);
+
+        level_dat.time = 48_000;
+        level_dat.spawn_pos = BlockPos::new(0, 100, 0);
+
+        world.write_level_dat(&level_dat).unwrap();
+
+        assert!(dir.path().join("level.dat_old").exists());
+
+        let read_back = world.read_level_dat().unwrap();
+
+        assert_eq!(read_back.time, 48_000);
+        assert_eq!(read_back.spawn_pos, BlockPos::new(0, 100, 0));
+        assert_eq!(read_back.game_rules, level_dat.game_rules);
+        // Fields which are not parsed are kept.
+        assert_eq!(
+            read_back.nbt.get("WorldGenSettings"),
+            Some(&Value::Compound(compound! { "seed" => 1234_i64 }))
+        );
+    }
+
+    #[test]
+    fn read_and_write_player_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let world = AnvilWorld::new(dir.path());
+        let uuid = Uuid::from_u128(0x1234);
+
+        assert_eq!(world.read_player_data(uuid).unwrap(), None);
+
+        // Laid out like the player data saved by vanilla 1.19.4.
+        let nbt = compound! {
+            "DataVersion" => 3337,
+            "Pos" => List::Double(vec![10.5, 64.0, -3.5]),
+            "Rotation" => List::Float(vec![90.0, 10.0]),
+            "Dimension" => "minecraft:the_nether",
+            "playerGameType" => 2,
+            "Inventory" => List::Compound(vec![
+                compound! {
+                    "Slot" => 0_i8,
+                    "id" => "minecraft:diamond_sword",
+                    "Count" => 1_i8,
+                    "tag" => compound! { "Damage" => 5 },
+                },
+                compound! { "Slot" => 9_i8, "id" => "minecraft:stone", "Count" => 64_i8 },
+                compound! { "Slot" => 103_i8, "id" => "minecraft:iron_helmet", "Count" => 1_i8 },
+                compound! { "Slot" => -106_i8, "id" => "minecraft:shield", "Count" => 1_i8 },
+                compound! { "Slot" => 1_i8, "id" => "othermod:gadget", "Count" => 1_i8 },
+            ]),
+            "EnderItems" => List::Compound(vec![
+                compound! { "Slot" => 26_i8, "id" => "minecraft:ender_pearl", "Count" => 16_i8 },
+            ]),
+            "SelectedItemSlot" => 3,
+            "Health" => 15.5_f32,
+            "foodLevel" => 18,
+            "foodSaturationLevel" => 2.5_f32,
+            "XpLevel" => 7,
+            "XpP" => 0.25_f32,
+            "XpTotal" => 120,
+            "abilities" => compound! { "flying" => false },
+        };
+
+        let mut z = GzEncoder::new(vec![], Compression::default());
+        valence_nbt::to_binary_writer(&mut z, &nbt, "").unwrap();
+        fs::create_dir_all(dir.path().join("playerdata")).unwrap();
+        fs::write(
+            dir.path().join(format!("playerdata/{uuid}.dat")),
+            z.finish().unwrap(),
+        )
+        .unwrap();
+
+        let mut data = world.read_player_data(uuid).unwrap().unwrap();
+
+        assert_eq!(data.position, DVec3::new(10.5, 64.0, -3.5));
+        assert_eq!((data.yaw, data.pitch), (90.0, 10.0));
+        assert_eq!(data.dimension.as_str(), "minecraft:the_nether");
+        assert_eq!(data.game_mode, GameMode::Adventure);
+        assert_eq!(data.selected_slot, 3);
+        assert_eq!(data.health, 15.5);
+        assert_eq!((data.food_level, data.food_saturation), (18, 2.5));
+        assert_eq!(
+            (data.xp_level, data.xp_progress, data.xp_total),
+            (7, 0.25, 120)
+        );
+
+        // The item of an unknown kind is skipped.
+        assert_eq!(
+            data.inventory,
+            [
+                (
+                    36,
+                    ItemStack::new(ItemKind::DiamondSword, 1, Some(compound! { "Damage" => 5 }))
+                ),
+                (9, ItemStack::new(ItemKind::Stone, 64, None)),
+                (5, ItemStack::new(ItemKind::IronHelmet, 1, None)),
+                (45, ItemStack::new(ItemKind::Shield, 1, None)),
+            ]
+        );
+        assert_eq!(
+            data.ender_items,
+            [(26, ItemStack::new(ItemKind::EnderPearl, 16, None))]
+        );
+
+        data.position = DVec3::new(0.0, 100.0, 0.0);
+        data.inventory
+            .push((44, ItemStack::new(ItemKind::Bread, 3, None)));
+
+        world.write_player_data(uuid, &data).unwrap();
+
+        let read_back = world.read_player_data(uuid).unwrap().unwrap();
+
+        assert_eq!(read_back.position, DVec3::new(0.0, 100.0, 0.0));
+        assert_eq!(read_back.inventory, data.inventory);
+        assert_eq!(read_back.ender_items, data.ender_items);
+        // Fields which are not parsed are kept.
+        assert_eq!(
+            read_back.nbt.get("abilities"),
+            Some(&Value::Compound(compound! { "flying" => false }))
+        );
+    }
 }
@@ -0,0 +1,318 @@
+use std::io;
+
+use glam::DVec3;
+use thiserror::Error;
+use tracing::warn;
+use valence_core::game_mode::GameMode;
+use valence_core::ident;
+use valence_core::ident::Ident;
+use valence_core::item::{ItemKind, ItemStack};
+use valence_nbt::{compound, Compound, List, Value};
+
+/// The saved state of a player, read from the `playerdata` subdirectory with
+/// [`AnvilWorld::read_player_data`].
+///
+/// Only the most useful fields are parsed. The others are kept in
+/// [`Self::nbt`], so that they are written back unchanged by
+/// [`AnvilWorld::write_player_data`]. Loading and saving is left to the
+/// application, which typically reads the data when a client joins and writes
+/// it when the client leaves.
+///
+/// [`AnvilWorld::read_player_data`]: crate::AnvilWorld::read_player_data
+/// [`AnvilWorld::write_player_data`]: crate::AnvilWorld::write_player_data
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerData {
+    pub position: DVec3,
+    /// The yaw angle in degrees.
+    pub yaw: f32,
+    /// The pitch angle in degrees.
+    pub pitch: f32,
+    /// The name of the dimension the player is in, like
+    /// `minecraft:overworld`.
+    pub dimension: Ident<String>,
+    pub game_mode: GameMode,
+    /// The items in the player's inventory, by slot index of the player
+    /// `Inventory` from `valence_inventory`. See [`player_inventory_slot`].
+    pub inventory: Vec<(u16, ItemStack)>,
+    /// The items in the player's ender chest, by slot index from 0 to 26.
+    pub ender_items: Vec<(u16, ItemStack)>,
+    /// The selected hotbar slot from 0 to 8.
+    pub selected_slot: u8,
+    pub health: f32,
+    pub food_level: i32,
+    pub food_saturation: f32,
+    pub xp_level: i32,
+    /// The progress towards the next level from 0 to 1.
+    pub xp_progress: f32,
+    pub xp_total: i32,
+    /// The complete NBT data of the player, including the fields which were
+    /// not parsed. The parsed fields above take precedence over the values
+    /// here when writing.
+    pub nbt: Compound,
+}
+
+impl Default for PlayerData {
+    fn default() -> Self {
+        Self {
+            position: DVec3::ZERO,
+            yaw: 0.0,
+            pitch: 0.0,
+            dimension: ident!("overworld").into(),
+            game_mode: GameMode::default(),
+            inventory: vec![],
+            ender_items: vec![],
+            selected_slot: 0,
+            health: 20.0,
+            food_level: 20,
+            food_saturation: 5.0,
+            xp_level: 0,
+            xp_progress: 0.0,
+            xp_total: 0,
+            nbt: Compound::new(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ReadPlayerDataError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Nbt(#[from] valence_nbt::Error),
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum WritePlayerDataError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Nbt(#[from] valence_nbt::Error),
+}
+
+/// Converts a slot number of the `Inventory` list in player data to the index
+/// of the slot in the player `Inventory` from `valence_inventory`, or `None`
+/// if the slot number is invalid.
+pub fn player_inventory_slot(saved_slot: i8) -> Option<u16> {
+    match saved_slot {
+        // Hotbar.
+        0..=8 => Some(saved_slot as u16 + 36),
+        // Main inventory.
+        9..=35 => Some(saved_slot as u16),
+        // Armor from feet to head.
+        100..=103 => Some(108 - saved_slot as u16),
+        // Offhand.
+        -106 => Some(45),
+        _ => None,
+    }
+}
+
+/// The inverse of [`player_inventory_slot`].
+fn saved_player_inventory_slot(slot: u16) -> Option<i8> {
+    match slot {
+        36..=44 => Some((slot - 36) as i8),
+        9..=35 => Some(slot as i8),
+        5..=8 => Some((108 - slot) as i8),
+        45 => Some(-106),
+        _ => None,
+    }
+}
+
+impl PlayerData {
+    /// Parses the NBT data of a player. Fields which are missing or invalid
+    /// are given their default values, and items which are invalid or of an
+    /// unknown kind are skipped with a warning.
+    pub fn from_nbt(nbt: &Compound) -> Self {
+        let default = Self::default();
+
+        let position = match nbt.get("Pos") {
+            Some(Value::List(List::Double(pos))) => match pos.as_slice() {
+                &[x, y, z] => DVec3::new(x, y, z),
+                _ => default.position,
+            },
+            _ => default.position,
+        };
+
+        let (yaw, pitch) = match nbt.get("Rotation") {
+            Some(Value::List(List::Float(rotation))) => match rotation.as_slice() {
+                &[yaw, pitch] => (yaw, pitch),
+                _ => (default.yaw, default.pitch),
+            },
+            _ => (default.yaw, default.pitch),
+        };
+
+        let dimension = match nbt.get("Dimension") {
+            Some(Value::String(dim)) => match Ident::<String>::try_from(dim.clone()) {
+                Ok(dim) => dim,
+                Err(e) => {
+                    warn!("invalid player dimension: {e}");
+                    default.dimension
+                }
+            },
+            _ => default.dimension,
+        };
+
+        let game_mode = match nbt.get("playerGameType") {
+            Some(Value::Int(1)) => GameMode::Creative,
+            Some(Value::Int(2)) => GameMode::Adventure,
+            Some(Value::Int(3)) => GameMode::Spectator,
+            _ => GameMode::Survival,
+        };
+
+        Self {
+            position,
+            yaw,
+            pitch,
+            dimension,
+            game_mode,
+            inventory: parse_items(nbt, "Inventory", player_inventory_slot),
+            ender_items: parse_items(nbt, "EnderItems", |slot| {
+                (0..27).contains(&slot).then_some(slot as u16)
+            }),
+            selected_slot: match nbt.get("SelectedItemSlot") {
+                Some(&Value::Int(slot @ 0..=8)) => slot as u8,
+                _ => default.selected_slot,
+            },
+            health: get_float(nbt, "Health").unwrap_or(default.health),
+            food_level: get_int(nbt, "foodLevel").unwrap_or(default.food_level),
+            food_saturation: get_float(nbt, "foodSaturationLevel")
+                .unwrap_or(default.food_saturation),
+            xp_level: get_int(nbt, "XpLevel").unwrap_or(default.xp_level),
+            xp_progress: get_float(nbt, "XpP").unwrap_or(default.xp_progress),
+            xp_total: get_int(nbt, "XpTotal").unwrap_or(default.xp_total),
+            nbt: nbt.clone(),
+        }
+    }
+
+    /// Creates the NBT data of the player.
+    pub fn to_nbt(&self) -> Compound {
+        let mut nbt = self.nbt.clone();
+
+        nbt.insert(
+            "Pos",
+            List::Double(vec![self.position.x, self.position.y, self.position.z]),
+        );
+        nbt.insert("Rotation", List::Float(vec![self.yaw, self.pitch]));
+        nbt.insert("Dimension", self.dimension.as_str());
+        nbt.insert(
+            "playerGameType",
+            match self.game_mode {
+                GameMode::Survival => 0,
+                GameMode::Creative => 1,
+                GameMode::Adventure => 2,
+                GameMode::Spectator => 3,
+            },
+        );
+        nbt.insert(
+            "Inventory",
+            encode_items(&self.inventory, saved_player_inventory_slot),
+        );
+        nbt.insert(
+            "EnderItems",
+            encode_items(&self.ender_items, |slot| (slot < 27).then_some(slot as i8)),
+        );
+        nbt.insert("SelectedItemSlot", self.selected_slot as i32);
+        nbt.insert("Health", self.health);
+        nbt.insert("foodLevel", self.food_level);
+        nbt.insert("foodSaturationLevel", self.food_saturation);
+        nbt.insert("XpLevel", self.xp_level);
+        nbt.insert("XpP", self.xp_progress);
+        nbt.insert("XpTotal", self.xp_total);
+
+        nbt
+    }
+}
+
+fn parse_items(
+    nbt: &Compound,
+    key: &str,
+    map_slot: impl Fn(i8) -> Option<u16>,
+) -> Vec<(u16, ItemStack)> {
+    let items = match nbt.get(key) {
+        Some(Value::List(List::Compound(items))) => items,
+        _ => return vec![],
+    };
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let Some(&Value::Byte(saved_slot)) = item.get("Slot") else {
+                warn!("skipping item without a slot in {key}");
+                return None;
+            };
+
+            let Some(slot) = map_slot(saved_slot) else {
+                warn!("skipping item in invalid slot {saved_slot} of {key}");
+                return None;
+            };
+
+            let Some(Value::String(id)) = item.get("id") else {
+                warn!("skipping item without an ident in slot {saved_slot} of {key}");
+                return None;
+            };
+
+            let Some(kind) = Ident::new(&id[..])
+                .ok()
+                .filter(|ident| ident.namespace() == "minecraft")
+                .and_then(|ident| ItemKind::from_str(ident.path()))
+            else {
+                warn!("skipping item of unknown kind \"{id}\" in slot {saved_slot} of {key}");
+                return None;
+            };
+
+            let count = match item.get("Count") {
+                Some(&Value::Byte(count)) => count.max(1) as u8,
+                _ => 1,
+            };
+
+            let tag = match item.get("tag") {
+                Some(Value::Compound(tag)) => Some(tag.clone()),
+                _ => None,
+            };
+
+            Some((slot, ItemStack::new(kind, count, tag)))
+        })
+        .collect()
+}
+
+fn encode_items(items: &[(u16, ItemStack)], map_slot: impl Fn(u16) -> Option<i8>) -> List {
+    List::Compound(
+        items
+            .iter()
+            .filter(|(_, stack)| stack.item != ItemKind::Air)
+            .filter_map(|(slot, stack)| {
+                let Some(saved_slot) = map_slot(*slot) else {
+                    warn!("skipping item in slot {slot} which cannot be saved");
+                    return None;
+                };
+
+                let mut item = compound! {
+                    "Slot" => saved_slot,
+                    "id" => format!("minecraft:{}", stack.item.to_str()),
+                    "Count" => stack.count() as i8,
+                };
+
+                if let Some(tag) = &stack.nbt {
+                    item.insert("tag", tag.clone());
+                }
+
+                Some(item)
+            })
+            .collect(),
+    )
+}
+
+fn get_int(comp: &Compound, key: &str) -> Option<i32> {
+    match comp.get(key) {
+        Some(&Value::Int(i)) => Some(i),
+        _ => None,
+    }
+}
+
+fn get_float(comp: &Compound, key: &str) -> Option<f32> {
+    match comp.get(key) {
+        Some(&Value::Float(f)) => Some(f),
+        _ => None,
+    }
+}
@@ -1,16 +1,29 @@
 use std::borrow::Cow;
 
-use num_integer::{div_ceil, Integer};
+use num_integer::div_ceil;
 use thiserror::Error;
+use tracing::warn;
 use valence_biome::BiomeId;
-use valence_block::{BlockEntityKind, BlockKind, PropName, PropValue};
+use valence_block::{BlockKind, BlockState, PropName, PropValue};
 use valence_core::ident::Ident;
-use valence_instance::{BlockEntity, Chunk};
+use valence_instance::Chunk;
 use valence_nbt::{Compound, List, Value};
 
+use crate::upgrade::has_current_layout;
+use crate::{parse_block_entities, upgrade_chunk, ParsedBlockEntity, MIN_DATA_VERSION};
+
 #[derive(Clone, Debug, Error)]
 #[non_exhaustive]
 pub enum ToValenceError {
+    #[error(
+        "unsupported chunk data version {0}, the oldest supported data version is {} (Minecraft 1.16)",
+        MIN_DATA_VERSION
+    )]
+    UnsupportedDataVersion(i32),
+    #[error("missing data version of chunk saved before Minecraft 1.9")]
+    MissingDataVersion,
+    #[error("missing level data of chunk saved before Minecraft 1.18")]
+    MissingLevel,
     #[error("missing chunk sections")]
     MissingSections,
     #[error("missing chunk section Y")]
@@ -65,7 +78,12 @@ pub enum ToValenceError {
 
 /// Takes an Anvil chunk in NBT form and writes its data to a Valence [`Chunk`].
 /// An error is returned if the NBT data does not match the expected structure
-/// for an Anvil chunk.
+/// for an Anvil chunk. Block entities which are malformed or outside of the
+/// Valence chunk are skipped with a warning instead.
+///
+/// Chunks saved by older versions of Minecraft are converted with
+/// [`upgrade_chunk`] first, which fails for chunks older than
+/// [`MIN_DATA_VERSION`].
 ///
 /// # Arguments
 ///
@@ -88,6 +106,16 @@ pub fn to_valence<F, const LOADED: bool>(
 where
     F: FnMut(Ident<&str>) -> BiomeId,
 {
+    let upgraded;
+    let nbt = if has_current_layout(nbt) {
+        nbt
+    } else {
+        let mut nbt = nbt.clone();
+        upgrade_chunk(&mut nbt)?;
+        upgraded = nbt;
+        &upgraded
+    };
+
     let Some(Value::List(List::Compound(sections))) = nbt.get("sections") else {
         return Err(ToValenceError::MissingSections)
     };
@@ -122,35 +150,7 @@ where
         converted_block_palette.clear();
 
         for block in palette {
-            let Some(Value::String(name)) = block.get("Name") else {
-                return Err(ToValenceError::MissingBlockName)
-            };
-
-            let Some(block_kind) = BlockKind::from_str(ident_path(name)) else {
-                return Err(ToValenceError::UnknownBlockName(name.into()))
-            };
-
-            let mut state = block_kind.to_state();
-
-            if let Some(Value::Compound(properties)) = block.get("Properties") {
-                for (key, value) in properties {
-                    let Value::String(value) = value else {
-                        return Err(ToValenceError::BadPropValueType)
-                    };
-
-                    let Some(prop_name) = PropName::from_str(key) else {
-                        return Err(ToValenceError::UnknownPropName(key.into()))
-                    };
-
-                    let Some(prop_value) = PropValue::from_str(value) else {
-                        return Err(ToValenceError::UnknownPropValue(value.into()))
-                    };
-
-                    state = state.set(prop_name, prop_value);
-                }
-            }
-
-            converted_block_palette.push(state);
+            converted_block_palette.push(parse_block_state(block)?);
         }
 
         if converted_block_palette.len() == 1 {
@@ -264,53 +264,63 @@ where
         }
     }
 
-    let Some(Value::List(block_entities)) = nbt.get("block_entities") else {
+    let Some(Value::List(_)) = nbt.get("block_entities") else {
         return Err(ToValenceError::MissingBlockEntity);
     };
 
-    if let List::Compound(block_entities) = block_entities {
-        for comp in block_entities {
-            let Some(Value::String(ident)) = comp.get("id") else {
-                return Err(ToValenceError::MissingBlockEntityIdent);
-            };
-            let Ok(ident) = Ident::new(&ident[..]) else {
-                return Err(ToValenceError::UnknownBlockEntityIdent(ident.clone()));
-            };
-            let Some(kind) = BlockEntityKind::from_ident(ident.as_str_ident()) else {
-                return Err(ToValenceError::UnknownBlockEntityIdent(ident.as_str().to_string()));
-            };
-            let block_entity = BlockEntity {
-                kind,
-                nbt: comp.clone(),
-            };
-            let Some(Value::Int(x)) = comp.get("x") else {
-                return Err(ToValenceError::InvalidBlockEntityPosition);
-            };
-            let Ok(x) = usize::try_from(x.mod_floor(&16)) else {
-                return Err(ToValenceError::InvalidBlockEntityPosition);
-            };
-            let Some(Value::Int(y)) = comp.get("y") else {
-                return Err(ToValenceError::InvalidBlockEntityPosition);
-            };
-            let Ok(y) = usize::try_from(y + sect_offset * 16) else {
-                return Err(ToValenceError::InvalidBlockEntityPosition);
+    for ParsedBlockEntity { pos, block_entity } in parse_block_entities(nbt) {
+        let x = pos.x.rem_euclid(16) as usize;
+        let z = pos.z.rem_euclid(16) as usize;
+
+        let Some(y) = usize::try_from(pos.y + sect_offset * 16)
+            .ok()
+            .filter(|&y| y < chunk.section_count() * 16)
+        else {
+            warn!("skipping block entity at {pos:?} outside of the chunk");
+            continue;
+        };
+
+        chunk.set_block_entity(x, y, z, block_entity);
+    }
+
+    Ok(())
+}
+
+/// Converts an entry of a block palette into a block state.
+pub(crate) fn parse_block_state(block: &Compound) -> Result<BlockState, ToValenceError> {
+    let Some(Value::String(name)) = block.get("Name") else {
+        return Err(ToValenceError::MissingBlockName)
+    };
+
+    let Some(block_kind) = BlockKind::from_str(ident_path(name)) else {
+        return Err(ToValenceError::UnknownBlockName(name.into()))
+    };
+
+    let mut state = block_kind.to_state();
+
+    if let Some(Value::Compound(properties)) = block.get("Properties") {
+        for (key, value) in properties {
+            let Value::String(value) = value else {
+                return Err(ToValenceError::BadPropValueType)
             };
-            let Some(Value::Int(z)) = comp.get("z") else {
-                return Err(ToValenceError::InvalidBlockEntityPosition);
+
+            let Some(prop_name) = PropName::from_str(key) else {
+                return Err(ToValenceError::UnknownPropName(key.into()))
             };
-            let Ok(z) = usize::try_from(z.mod_floor(&16)) else {
-                return Err(ToValenceError::InvalidBlockEntityPosition);
+
+            let Some(prop_value) = PropValue::from_str(value) else {
+                return Err(ToValenceError::UnknownPropValue(value.into()))
             };
 
-            chunk.set_block_entity(x, y, z, block_entity);
+            state = state.set(prop_name, prop_value);
         }
     }
 
-    Ok(())
+    Ok(state)
 }
 
-const BLOCKS_PER_SECTION: usize = 16 * 16 * 16;
-const BIOMES_PER_SECTION: usize = 4 * 4 * 4;
+pub(crate) const BLOCKS_PER_SECTION: usize = 16 * 16 * 16;
+pub(crate) const BIOMES_PER_SECTION: usize = 4 * 4 * 4;
 
 /// Gets the path part of a resource identifier.
 fn ident_path(ident: &str) -> &str {
@@ -321,6 +331,6 @@ fn ident_path(ident: &str) -> &str {
 }
 
 /// Returns the minimum number of bits needed to represent the integer `n`.
-const fn bit_width(n: usize) -> usize {
+pub(crate) const fn bit_width(n: usize) -> usize {
     (usize::BITS - n.leading_zeros()) as _
 }
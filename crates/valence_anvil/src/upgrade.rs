@@ -0,0 +1,312 @@
+use std::collections::BTreeMap;
+
+use tracing::warn;
+use valence_nbt::{compound, Compound, List, Value};
+
+use crate::from_valence::{pack_indices, palette_index};
+use crate::to_valence::{bit_width, BIOMES_PER_SECTION};
+use crate::{ToValenceError, DATA_VERSION};
+
+/// The oldest data version of chunks which can be read, which is the data
+/// version of Minecraft 1.16.
+pub const MIN_DATA_VERSION: i32 = 2566;
+
+/// The first data version with the current chunk layout, from a Minecraft 1.18
+/// snapshot. Older chunks have their data in a `Level` compound.
+const FLAT_LAYOUT_DATA_VERSION: i32 = 2844;
+
+/// Converts an Anvil chunk saved by an older version of Minecraft to the
+/// layout written by the current version. Chunks which already have the
+/// current layout are left as they are.
+///
+/// Chunks from Minecraft 1.16 and 1.17 have their data moved out of the
+/// `Level` compound, their numeric biome IDs replaced with biome names, and
+/// the blocks renamed in Minecraft 1.17 replaced. The data version of
+/// upgraded chunks is set to [`DATA_VERSION`]. Chunks older than
+/// [`MIN_DATA_VERSION`] cannot be upgraded. If the chunk cannot be upgraded,
+/// `nbt` is left unchanged.
+///
+/// This is done by [`to_valence`] and [`from_valence`] already, so there is
+/// usually no need to call it directly.
+///
+/// [`to_valence`]: crate::to_valence
+/// [`from_valence`]: crate::from_valence
+pub fn upgrade_chunk(nbt: &mut Compound) -> Result<(), ToValenceError> {
+    match nbt.get("DataVersion") {
+        Some(&Value::Int(version)) if version < MIN_DATA_VERSION => {
+            Err(ToValenceError::UnsupportedDataVersion(version))
+        }
+        Some(&Value::Int(version)) if version < FLAT_LAYOUT_DATA_VERSION => {
+            // Upgrade a copy so that the chunk isn't left half converted on errors.
+            let mut upgraded = nbt.clone();
+            upgrade_level(&mut upgraded)?;
+            *nbt = upgraded;
+            Ok(())
+        }
+        Some(Value::Int(_)) => Ok(()),
+        // Chunks from before Minecraft 1.9 have no data version.
+        _ if nbt.contains_key("Level") => Err(ToValenceError::MissingDataVersion),
+        _ => Ok(()),
+    }
+}
+
+/// Returns whether the chunk has the layout written by the current version.
+pub(crate) fn has_current_layout(nbt: &Compound) -> bool {
+    match nbt.get("DataVersion") {
+        Some(&Value::Int(version)) => version >= FLAT_LAYOUT_DATA_VERSION,
+        _ => !nbt.contains_key("Level"),
+    }
+}
+
+/// Converts a chunk with the layout of Minecraft 1.16 and 1.17.
+fn upgrade_level(nbt: &mut Compound) -> Result<(), ToValenceError> {
+    let Some(Value::Compound(mut level)) = nbt.remove("Level") else {
+        return Err(ToValenceError::MissingLevel);
+    };
+
+    let old_sections = match level.remove("Sections") {
+        Some(Value::List(List::Compound(sections))) => sections,
+        Some(Value::List(List::End)) | None => vec![],
+        Some(_) => return Err(ToValenceError::MissingSections),
+    };
+
+    let old_biomes = match level.remove("Biomes") {
+        Some(Value::IntArray(biomes)) => biomes,
+        _ => vec![],
+    };
+
+    let mut sections = BTreeMap::new();
+
+    for mut old_section in old_sections {
+        let Some(&Value::Byte(sect_y)) = old_section.get("Y") else {
+            return Err(ToValenceError::MissingSectionY);
+        };
+
+        let mut section = compound! {
+            "Y" => sect_y,
+        };
+
+        for key in ["BlockLight", "SkyLight"] {
+            if let Some(light) = old_section.remove(key) {
+                section.insert(key, light);
+            }
+        }
+
+        // Sections without a palette only contain light.
+        if let Some(Value::List(List::Compound(mut palette))) = old_section.remove("Palette") {
+            palette.iter_mut().for_each(upgrade_block_state);
+
+            let mut block_states = compound! {
+                "palette" => List::Compound(palette),
+            };
+
+            if let Some(data) = old_section.remove("BlockStates") {
+                block_states.insert("data", data);
+            }
+
+            section.insert("block_states", block_states);
+        }
+
+        sections.insert(sect_y, section);
+    }
+
+    // Biomes were stored for the whole chunk, starting at section zero.
+    let biome_sections: Vec<_> = old_biomes
+        .chunks_exact(BIOMES_PER_SECTION)
+        .map(encode_legacy_biomes)
+        .collect();
+
+    for sect_y in 0..biome_sections.len() {
+        sections.entry(sect_y as i8).or_insert_with(|| {
+            compound! {
+                "Y" => sect_y as i8,
+            }
+        });
+    }
+
+    let (min_sect_y, max_sect_y) = match (sections.keys().next(), sections.keys().last()) {
+        (Some(&min), Some(&max)) => (min, max),
+        _ => (0, -1),
+    };
+
+    // Every section has blocks and biomes in the current layout. Sections
+    // outside of the stored biomes use the biomes of the nearest section.
+    for (&sect_y, section) in &mut sections {
+        if !section.contains_key("block_states") {
+            section.insert(
+                "block_states",
+                compound! {
+                    "palette" => List::Compound(vec![compound! {
+                        "Name" => "minecraft:air",
+                    }]),
+                },
+            );
+        }
+
+        let biomes = match biome_sections.len() {
+            0 => compound! {
+                "palette" => List::String(vec!["minecraft:plains".into()]),
+            },
+            len => biome_sections[(sect_y.max(0) as usize).min(len - 1)].clone(),
+        };
+
+        section.insert("biomes", biomes);
+    }
+
+    // The heightmaps were computed for a different world height, and the game
+    // computes them again when they are missing.
+    level.remove("Heightmaps");
+
+    for (old_key, new_key) in [
+        ("TileEntities", "block_entities"),
+        ("TileTicks", "block_ticks"),
+        ("LiquidTicks", "fluid_ticks"),
+        ("Structures", "structures"),
+    ] {
+        if let Some(value) = level.remove(old_key) {
+            level.insert(new_key, value);
+        }
+    }
+
+    if !level.contains_key("block_entities") {
+        level.insert("block_entities", List::End);
+    }
+
+    level.insert("sections", List::Compound(sections.into_values().collect()));
+
+    if min_sect_y <= max_sect_y {
+        level.insert("yPos", min_sect_y as i32);
+    }
+
+    // The remaining entries of `Level`, including the entities saved in the
+    // chunk before Minecraft 1.17, are kept with the same names.
+    nbt.merge(level);
+    nbt.insert("DataVersion", DATA_VERSION);
+
+    Ok(())
+}
+
+/// Replaces the blocks which were renamed or split in Minecraft 1.17.
+fn upgrade_block_state(block: &mut Compound) {
+    let Some(Value::String(name)) = block.get_mut("Name") else {
+        return;
+    };
+
+    match name.as_str() {
+        "minecraft:grass_path" => *name = "minecraft:dirt_path".into(),
+        "minecraft:cauldron" => {
+            let level = match block.get("Properties") {
+                Some(Value::Compound(props)) => match props.get("level") {
+                    Some(Value::String(level)) => level.clone(),
+                    _ => "0".into(),
+                },
+                _ => "0".into(),
+            };
+
+            if level == "0" {
+                block.remove("Properties");
+            } else {
+                *block = compound! {
+                    "Name" => "minecraft:water_cauldron",
+                    "Properties" => compound! {
+                        "level" => level,
+                    },
+                };
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Encodes the numeric biome IDs of a section in the current layout.
+fn encode_legacy_biomes(ids: &[i32]) -> Compound {
+    let mut palette = vec![];
+
+    let idxs: Vec<_> = ids
+        .iter()
+        .map(|&id| palette_index(&mut palette, id))
+        .collect();
+
+    let bits_per_idx = bit_width(palette.len() - 1);
+
+    let mut biomes = compound! {
+        "palette" => List::String(
+            palette
+                .iter()
+                .map(|&id| format!("minecraft:{}", legacy_biome_name(id)))
+                .collect(),
+        ),
+    };
+
+    if bits_per_idx > 0 {
+        biomes.insert("data", pack_indices(&idxs, bits_per_idx));
+    }
+
+    biomes
+}
+
+/// Maps the numeric biome IDs used before Minecraft 1.18 to the names of the
+/// biomes which replaced them.
+fn legacy_biome_name(id: i32) -> &'static str {
+    match id {
+        0 => "ocean",
+        1 => "plains",
+        2 | 17 | 130 => "desert",
+        3 | 20 => "windswept_hills",
+        4 | 18 => "forest",
+        5 | 19 | 133 => "taiga",
+        6 | 134 => "swamp",
+        7 => "river",
+        8 => "nether_wastes",
+        9 => "the_end",
+        10 => "frozen_ocean",
+        11 => "frozen_river",
+        12 | 13 => "snowy_plains",
+        14 | 15 => "mushroom_fields",
+        16 => "beach",
+        21 | 22 | 149 => "jungle",
+        23 | 151 => "sparse_jungle",
+        24 => "deep_ocean",
+        25 => "stony_shore",
+        26 => "snowy_beach",
+        27 | 28 => "birch_forest",
+        29 | 157 => "dark_forest",
+        30 | 31 | 158 => "snowy_taiga",
+        32 | 33 => "old_growth_pine_taiga",
+        34 => "windswept_forest",
+        35 => "savanna",
+        36 => "savanna_plateau",
+        37 | 39 | 167 => "badlands",
+        38 | 166 => "wooded_badlands",
+        40 => "small_end_islands",
+        41 => "end_midlands",
+        42 => "end_highlands",
+        43 => "end_barrens",
+        44 | 47 => "warm_ocean",
+        45 => "lukewarm_ocean",
+        46 => "cold_ocean",
+        48 => "deep_lukewarm_ocean",
+        49 => "deep_cold_ocean",
+        50 => "deep_frozen_ocean",
+        127 => "the_void",
+        129 => "sunflower_plains",
+        131 | 162 => "windswept_gravelly_hills",
+        132 => "flower_forest",
+        140 => "ice_spikes",
+        155 | 156 => "old_growth_birch_forest",
+        160 | 161 => "old_growth_spruce_taiga",
+        163 | 164 => "windswept_savanna",
+        165 => "eroded_badlands",
+        168 | 169 => "bamboo_jungle",
+        170 => "soul_sand_valley",
+        171 => "crimson_forest",
+        172 => "warped_forest",
+        173 => "basalt_deltas",
+        174 => "dripstone_caves",
+        175 => "lush_caves",
+        _ => {
+            warn!("unknown legacy biome ID of {id}");
+            "plains"
+        }
+    }
+}
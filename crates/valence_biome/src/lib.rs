@@ -18,14 +18,15 @@
 )]
 
 use std::ops::Index;
+use std::str::FromStr;
 
-use anyhow::{bail, Context};
+use anyhow::{bail, ensure, Context};
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use tracing::error;
 use valence_core::ident;
 use valence_core::ident::Ident;
-use valence_nbt::{compound, Value};
+use valence_nbt::{compound, Compound, Value};
 use valence_registry::{RegistryCodec, RegistryCodecSet, RegistryValue};
 
 pub struct BiomePlugin;
@@ -58,75 +59,19 @@ fn load_default_biomes(
     codec: Res<RegistryCodec>,
     mut commands: Commands,
 ) {
-    let mut helper = move || {
-        for value in codec.registry(BiomeRegistry::KEY) {
-            let downfall = *value
-                .element
-                .get("downfall")
-                .and_then(|v| v.as_float())
-                .context("invalid downfall")?;
-
-            let Some(Value::Compound(effects)) = value.element.get("effects") else {
-                bail!("missing biome effects")
-            };
-
-            let fog_color = *effects
-                .get("fog_color")
-                .and_then(|v| v.as_int())
-                .context("invalid fog color")?;
-
-            let sky_color = *effects
-                .get("sky_color")
-                .and_then(|v| v.as_int())
-                .context("invalid sky color")?;
-
-            let water_color = *effects
-                .get("water_color")
-                .and_then(|v| v.as_int())
-                .context("invalid water color")?;
-
-            let water_fog_color = *effects
-                .get("water_fog_color")
-                .and_then(|v| v.as_int())
-                .context("invalid water fog color")?;
-
-            let grass_color = effects.get("grass_color").and_then(|v| v.as_int()).copied();
-
-            let has_precipitation = *value
-                .element
-                .get("has_precipitation")
-                .and_then(|v| v.as_byte())
-                .context("invalid has_precipitation")?
-                != 0;
-
-            let temperature = *value
-                .element
-                .get("temperature")
-                .and_then(|v| v.as_float())
-                .context("invalid temperature")?;
-
-            let entity = commands
-                .spawn(Biome {
-                    name: value.name.clone(),
-                    downfall,
-                    fog_color,
-                    sky_color,
-                    water_color,
-                    water_fog_color,
-                    grass_color,
-                    has_precipitation,
-                    temperature,
-                })
-                .id();
-
-            reg.id_to_biome.push(entity);
+    for value in codec.registry(BiomeRegistry::KEY) {
+        match Biome::from_nbt(value.name.clone(), &value.element) {
+            Ok(biome) => {
+                let entity = commands.spawn(biome).id();
+                reg.id_to_biome.push(entity);
+            }
+            Err(e) => {
+                error!(
+                    "failed to load default biome \"{}\" from registry codec: {e:#}",
+                    value.name
+                );
+            }
         }
-
-        Ok(())
-    };
-
-    if let Err(e) = helper() {
-        error!("failed to load default biomes from registry codec: {e:#}");
     }
 }
 
@@ -137,29 +82,24 @@ fn update_biome_registry(
     biomes: Query<(Entity, &Biome), Changed<Biome>>,
 ) {
     for (entity, biome) in &biomes {
-        let biome_registry = codec.registry_mut(BiomeRegistry::KEY);
-
-        let mut effects = compound! {
-            "fog_color" => biome.fog_color,
-            "sky_color" => biome.sky_color,
-            "water_color" => biome.water_color,
-            "water_fog_color" => biome.water_fog_color,
-        };
-
-        if let Some(grass_color) = biome.grass_color {
-            effects.insert("grass_color", grass_color);
+        if let Err(e) = biome.validate() {
+            error!("biome \"{}\" is invalid: {e:#}", biome.name);
+            continue;
         }
 
-        let biome_compound = compound! {
-            "downfall" => biome.downfall,
-            "effects" => effects,
-            "has_precipitation" => biome.has_precipitation,
-            "temperature" => biome.temperature,
-        };
+        let biome_registry = codec.registry_mut(BiomeRegistry::KEY);
 
-        if let Some(value) = biome_registry.iter_mut().find(|v| v.name == biome.name) {
+        let biome_compound = biome.to_nbt();
+
+        if let Some(idx) = reg.id_to_biome.iter().position(|&e| e == entity) {
+            // In case the name was changed.
+            let value = &mut biome_registry[idx];
             value.name = biome.name.clone();
-            value.element.merge(biome_compound);
+            value.element = biome_compound;
+        } else if let Some(idx) = biome_registry.iter().position(|v| v.name == biome.name) {
+            // A new biome with the name of an existing one replaces it.
+            biome_registry[idx].element = biome_compound;
+            reg.id_to_biome[idx] = entity;
         } else {
             biome_registry.push(RegistryValue {
                 name: biome.name.clone(),
@@ -224,7 +164,15 @@ impl Index<BiomeId> for BiomeRegistry {
 #[derive(Component, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
 pub struct BiomeId(pub u16);
 
-#[derive(Component, Clone, Debug)]
+/// A biome in the biome registry. Every field of the vanilla biome format is
+/// covered, and biomes are sent to clients in the registry codec while
+/// joining.
+///
+/// Clients only receive the registry codec once, so biomes which are spawned,
+/// changed or despawned while clients are connected don't reach them until
+/// they rejoin. Biomes which fail [`Biome::validate`] are not added to the
+/// registry.
+#[derive(Component, Clone, PartialEq, Debug)]
 pub struct Biome {
     pub name: Ident<String>,
     pub downfall: f32,
@@ -232,10 +180,25 @@ pub struct Biome {
     pub sky_color: i32,
     pub water_color: i32,
     pub water_fog_color: i32,
+    /// Overrides the foliage color computed from the temperature and downfall.
+    pub foliage_color: Option<i32>,
+    /// Overrides the grass color computed from the temperature and downfall.
     pub grass_color: Option<i32>,
+    pub grass_color_modifier: GrassColorModifier,
+    /// Particles which randomly appear in the air.
+    pub particle: Option<BiomeParticle>,
+    /// The sound which loops while the player is in the biome.
+    pub ambient_sound: Option<BiomeSound>,
+    /// The sound which plays in dark places, like the cave sounds of the
+    /// overworld.
+    pub mood_sound: Option<BiomeMoodSound>,
+    /// Sounds which randomly play while the player is in the biome.
+    pub additions_sound: Option<BiomeAdditionsSound>,
+    pub music: Option<BiomeMusic>,
     pub has_precipitation: bool,
     pub temperature: f32,
-    // TODO: more stuff.
+    /// Changes the temperature used to decide whether it rains or snows.
+    pub temperature_modifier: TemperatureModifier,
 }
 
 impl Default for Biome {
@@ -247,9 +210,524 @@ impl Default for Biome {
             sky_color: 7907327,
             water_color: 4159204,
             water_fog_color: 329011,
+            foliage_color: None,
             grass_color: None,
+            grass_color_modifier: GrassColorModifier::None,
+            particle: None,
+            ambient_sound: None,
+            mood_sound: Some(BiomeMoodSound {
+                sound: BiomeSound::new(ident!("ambient.cave")),
+                tick_delay: 6000,
+                block_search_extent: 8,
+                offset: 2.0,
+            }),
+            additions_sound: None,
+            music: None,
             has_precipitation: true,
             temperature: 0.8,
+            temperature_modifier: TemperatureModifier::None,
+        }
+    }
+}
+
+/// Changes the color of grass in a biome.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum GrassColorModifier {
+    #[default]
+    None,
+    /// Darkens the grass color, like in dark forests.
+    DarkForest,
+    /// Replaces the grass color with the mottled colors of swamps.
+    Swamp,
+}
+
+impl GrassColorModifier {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::DarkForest => "dark_forest",
+            Self::Swamp => "swamp",
+        }
+    }
+}
+
+impl FromStr for GrassColorModifier {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "dark_forest" => Ok(Self::DarkForest),
+            "swamp" => Ok(Self::Swamp),
+            other => bail!("unknown grass color modifier \"{other}\""),
+        }
+    }
+}
+
+/// Changes the temperature of a biome at some positions.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum TemperatureModifier {
+    #[default]
+    None,
+    /// Makes some positions warm enough for rain instead of snow, like the
+    /// unfrozen patches of frozen oceans.
+    Frozen,
+}
+
+impl TemperatureModifier {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Frozen => "frozen",
+        }
+    }
+}
+
+impl FromStr for TemperatureModifier {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "frozen" => Ok(Self::Frozen),
+            other => bail!("unknown temperature modifier \"{other}\""),
+        }
+    }
+}
+
+/// Particles which randomly appear in the air of a biome.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BiomeParticle {
+    /// The name of the particle type, like `minecraft:white_ash`.
+    pub kind: Ident<String>,
+    /// Additional options of the particle type, like the color of dust
+    /// particles.
+    pub options: Compound,
+    /// The chance of a particle appearing at a position each tick, between 0
+    /// and 1.
+    pub probability: f32,
+}
+
+/// A sound played by a biome.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BiomeSound {
+    /// The name of the sound event, like `minecraft:ambient.cave`.
+    pub id: Ident<String>,
+    /// The distance at which the sound can be heard. The default range of the
+    /// sound is used if `None`.
+    pub range: Option<f32>,
+}
+
+impl BiomeSound {
+    /// Creates a sound with the default range.
+    pub fn new(id: impl Into<Ident<String>>) -> Self {
+        Self {
+            id: id.into(),
+            range: None,
+        }
+    }
+}
+
+/// The sound which plays in dark places of a biome.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BiomeMoodSound {
+    pub sound: BiomeSound,
+    /// The number of ticks the player must spend in darkness before the sound
+    /// plays.
+    pub tick_delay: i32,
+    /// The radius of the cube around the player in which dark blocks are
+    /// searched for.
+    pub block_search_extent: i32,
+    /// The distance from the player at which the sound plays.
+    pub offset: f64,
+}
+
+/// Sounds which randomly play in a biome.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BiomeAdditionsSound {
+    pub sound: BiomeSound,
+    /// The chance of the sound playing each tick, between 0 and 1.
+    pub tick_chance: f64,
+}
+
+/// The music of a biome.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BiomeMusic {
+    pub sound: BiomeSound,
+    /// The minimum number of ticks between songs.
+    pub min_delay: i32,
+    /// The maximum number of ticks between songs.
+    pub max_delay: i32,
+    /// Whether the music stops the music which is already playing.
+    pub replace_current_music: bool,
+}
+
+impl Biome {
+    /// Checks that the values of this biome are within the bounds vanilla
+    /// clients accept.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(self.downfall.is_finite(), "downfall is not finite");
+        ensure!(self.temperature.is_finite(), "temperature is not finite");
+
+        if let Some(particle) = &self.particle {
+            ensure!(
+                (0.0..=1.0).contains(&particle.probability),
+                "particle probability of {} is not between 0 and 1",
+                particle.probability
+            );
+        }
+
+        let sounds = [
+            self.ambient_sound.as_ref(),
+            self.mood_sound.as_ref().map(|mood| &mood.sound),
+            self.additions_sound
+                .as_ref()
+                .map(|additions| &additions.sound),
+            self.music.as_ref().map(|music| &music.sound),
+        ];
+
+        for sound in sounds.into_iter().flatten() {
+            if let Some(range) = sound.range {
+                ensure!(
+                    range.is_finite() && range > 0.0,
+                    "range of sound \"{}\" is not positive",
+                    sound.id
+                );
+            }
+        }
+
+        if let Some(mood) = &self.mood_sound {
+            ensure!(mood.tick_delay > 0, "mood sound tick delay is not positive");
+            ensure!(
+                mood.block_search_extent >= 0,
+                "mood sound block search extent is negative"
+            );
+        }
+
+        if let Some(additions) = &self.additions_sound {
+            ensure!(
+                (0.0..=1.0).contains(&additions.tick_chance),
+                "additions sound tick chance of {} is not between 0 and 1",
+                additions.tick_chance
+            );
+        }
+
+        if let Some(music) = &self.music {
+            ensure!(
+                0 <= music.min_delay && music.min_delay <= music.max_delay,
+                "music delay range of {}..={} is invalid",
+                music.min_delay,
+                music.max_delay
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns this biome in the format of the registry codec.
+    pub fn to_nbt(&self) -> Compound {
+        let mut effects = compound! {
+            "fog_color" => self.fog_color,
+            "sky_color" => self.sky_color,
+            "water_color" => self.water_color,
+            "water_fog_color" => self.water_fog_color,
+        };
+
+        if let Some(foliage_color) = self.foliage_color {
+            effects.insert("foliage_color", foliage_color);
+        }
+
+        if let Some(grass_color) = self.grass_color {
+            effects.insert("grass_color", grass_color);
+        }
+
+        if self.grass_color_modifier != GrassColorModifier::None {
+            effects.insert("grass_color_modifier", self.grass_color_modifier.as_str());
+        }
+
+        if let Some(particle) = &self.particle {
+            let mut options = particle.options.clone();
+            options.insert("type", particle.kind.as_str());
+
+            effects.insert(
+                "particle",
+                compound! {
+                    "options" => options,
+                    "probability" => particle.probability,
+                },
+            );
+        }
+
+        if let Some(sound) = &self.ambient_sound {
+            effects.insert("ambient_sound", sound.to_nbt());
+        }
+
+        if let Some(mood) = &self.mood_sound {
+            effects.insert(
+                "mood_sound",
+                compound! {
+                    "sound" => mood.sound.to_nbt(),
+                    "tick_delay" => mood.tick_delay,
+                    "block_search_extent" => mood.block_search_extent,
+                    "offset" => mood.offset,
+                },
+            );
+        }
+
+        if let Some(additions) = &self.additions_sound {
+            effects.insert(
+                "additions_sound",
+                compound! {
+                    "sound" => additions.sound.to_nbt(),
+                    "tick_chance" => additions.tick_chance,
+                },
+            );
+        }
+
+        if let Some(music) = &self.music {
+            effects.insert(
+                "music",
+                compound! {
+                    "sound" => music.sound.to_nbt(),
+                    "min_delay" => music.min_delay,
+                    "max_delay" => music.max_delay,
+                    "replace_current_music" => music.replace_current_music,
+                },
+            );
+        }
+
+        let mut element = compound! {
+            "downfall" => self.downfall,
+            "effects" => effects,
+            "has_precipitation" => self.has_precipitation,
+            "temperature" => self.temperature,
+        };
+
+        if self.temperature_modifier != TemperatureModifier::None {
+            element.insert("temperature_modifier", self.temperature_modifier.as_str());
+        }
+
+        element
+    }
+
+    /// Parses a biome from the format of the registry codec. The error
+    /// describes the first missing or invalid field.
+    pub fn from_nbt(name: Ident<String>, element: &Compound) -> anyhow::Result<Self> {
+        let mut biome = Self {
+            name,
+            downfall: *get(element, "downfall", Value::as_float)?,
+            has_precipitation: *get(element, "has_precipitation", Value::as_byte)? != 0,
+            temperature: *get(element, "temperature", Value::as_float)?,
+            temperature_modifier: get_optional(element, "temperature_modifier", Value::as_string)?
+                .map_or(Ok(TemperatureModifier::None), |s| s.parse())?,
+            ..Default::default()
+        };
+
+        biome
+            .read_effects(get(element, "effects", Value::as_compound)?)
+            .context("invalid \"effects\"")?;
+
+        Ok(biome)
+    }
+
+    /// Sets every field of this biome found in the `effects` compound.
+    fn read_effects(&mut self, effects: &Compound) -> anyhow::Result<()> {
+        self.fog_color = *get(effects, "fog_color", Value::as_int)?;
+        self.sky_color = *get(effects, "sky_color", Value::as_int)?;
+        self.water_color = *get(effects, "water_color", Value::as_int)?;
+        self.water_fog_color = *get(effects, "water_fog_color", Value::as_int)?;
+        self.foliage_color = get_optional(effects, "foliage_color", Value::as_int)?.copied();
+        self.grass_color = get_optional(effects, "grass_color", Value::as_int)?.copied();
+        self.grass_color_modifier =
+            get_optional(effects, "grass_color_modifier", Value::as_string)?
+                .map_or(Ok(GrassColorModifier::None), |s| s.parse())?;
+
+        self.particle = get_optional(effects, "particle", Value::as_compound)?
+            .map(|particle| -> anyhow::Result<_> {
+                let mut options = get(particle, "options", Value::as_compound)?.clone();
+
+                let Some(Value::String(kind)) = options.remove("type") else {
+                    bail!("missing particle type");
+                };
+
+                Ok(BiomeParticle {
+                    kind: Ident::new(kind)?.into(),
+                    options,
+                    probability: *get(particle, "probability", Value::as_float)?,
+                })
+            })
+            .transpose()
+            .context("invalid \"particle\"")?;
+
+        self.ambient_sound = effects
+            .get("ambient_sound")
+            .map(BiomeSound::from_nbt)
+            .transpose()
+            .context("invalid \"ambient_sound\"")?;
+
+        self.mood_sound = get_optional(effects, "mood_sound", Value::as_compound)?
+            .map(|mood| -> anyhow::Result<_> {
+                Ok(BiomeMoodSound {
+                    sound: BiomeSound::from_nbt(get(mood, "sound", Some)?)?,
+                    tick_delay: *get(mood, "tick_delay", Value::as_int)?,
+                    block_search_extent: *get(mood, "block_search_extent", Value::as_int)?,
+                    offset: *get(mood, "offset", Value::as_double)?,
+                })
+            })
+            .transpose()
+            .context("invalid \"mood_sound\"")?;
+
+        self.additions_sound = get_optional(effects, "additions_sound", Value::as_compound)?
+            .map(|additions| -> anyhow::Result<_> {
+                Ok(BiomeAdditionsSound {
+                    sound: BiomeSound::from_nbt(get(additions, "sound", Some)?)?,
+                    tick_chance: *get(additions, "tick_chance", Value::as_double)?,
+                })
+            })
+            .transpose()
+            .context("invalid \"additions_sound\"")?;
+
+        self.music = get_optional(effects, "music", Value::as_compound)?
+            .map(|music| -> anyhow::Result<_> {
+                Ok(BiomeMusic {
+                    sound: BiomeSound::from_nbt(get(music, "sound", Some)?)?,
+                    min_delay: *get(music, "min_delay", Value::as_int)?,
+                    max_delay: *get(music, "max_delay", Value::as_int)?,
+                    replace_current_music: *get(music, "replace_current_music", Value::as_byte)?
+                        != 0,
+                })
+            })
+            .transpose()
+            .context("invalid \"music\"")?;
+
+        Ok(())
+    }
+}
+
+impl BiomeSound {
+    /// Sounds without a range are written as just the name of the sound
+    /// event, like vanilla does.
+    fn to_nbt(&self) -> Value {
+        match self.range {
+            Some(range) => compound! {
+                "sound_id" => self.id.as_str(),
+                "range" => range,
+            }
+            .into(),
+            None => self.id.as_str().into(),
+        }
+    }
+
+    fn from_nbt(value: &Value) -> anyhow::Result<Self> {
+        match value {
+            Value::String(id) => Ok(Self::new(Ident::new(id.as_str())?)),
+            Value::Compound(sound) => Ok(Self {
+                id: Ident::new(get(sound, "sound_id", Value::as_string)?.as_str())?.into(),
+                range: get_optional(sound, "range", Value::as_float)?.copied(),
+            }),
+            _ => bail!("sound is not a string or compound"),
         }
     }
 }
+
+/// Gets a required field of a compound, or returns an error naming the field.
+fn get<'a, T: ?Sized>(
+    compound: &'a Compound,
+    key: &str,
+    f: impl FnOnce(&'a Value) -> Option<&'a T>,
+) -> anyhow::Result<&'a T> {
+    get_optional(compound, key, f)?.with_context(|| format!("missing \"{key}\""))
+}
+
+/// Gets an optional field of a compound, or returns an error naming the field
+/// if it has the wrong type.
+fn get_optional<'a, T: ?Sized>(
+    compound: &'a Compound,
+    key: &str,
+    f: impl FnOnce(&'a Value) -> Option<&'a T>,
+) -> anyhow::Result<Option<&'a T>> {
+    compound
+        .get(key)
+        .map(|value| f(value).with_context(|| format!("\"{key}\" has the wrong type")))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vanilla_biomes_round_trip() {
+        let codec = RegistryCodec::default();
+
+        for value in codec.registry(BiomeRegistry::KEY) {
+            let biome = Biome::from_nbt(value.name.clone(), &value.element).unwrap();
+
+            biome.validate().unwrap();
+            assert_eq!(biome.to_nbt(), value.element, "biome {}", value.name);
+        }
+    }
+
+    #[test]
+    fn custom_biome_round_trip() {
+        let biome = Biome {
+            name: ident!("custom:ashes").into(),
+            foliage_color: Some(0x123456),
+            grass_color_modifier: GrassColorModifier::Swamp,
+            particle: Some(BiomeParticle {
+                kind: ident!("dust").into(),
+                options: compound! { "scale" => 1.0_f32 },
+                probability: 0.5,
+            }),
+            ambient_sound: Some(BiomeSound {
+                id: ident!("custom:wind").into(),
+                range: Some(32.0),
+            }),
+            additions_sound: Some(BiomeAdditionsSound {
+                sound: BiomeSound::new(ident!("ambient.crimson_forest.additions")),
+                tick_chance: 0.01,
+            }),
+            music: Some(BiomeMusic {
+                sound: BiomeSound::new(ident!("music.nether.basalt_deltas")),
+                min_delay: 100,
+                max_delay: 200,
+                replace_current_music: true,
+            }),
+            temperature_modifier: TemperatureModifier::Frozen,
+            ..Default::default()
+        };
+
+        biome.validate().unwrap();
+
+        let parsed = Biome::from_nbt(biome.name.clone(), &biome.to_nbt()).unwrap();
+        assert_eq!(parsed, biome);
+    }
+
+    #[test]
+    fn invalid_biomes() {
+        let mut element = Biome::default().to_nbt();
+        element
+            .get_mut("effects")
+            .and_then(Value::as_compound_mut)
+            .unwrap()
+            .remove("sky_color");
+
+        let err = Biome::from_nbt(ident!("plains").into(), &element).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "invalid \"effects\": missing \"sky_color\""
+        );
+
+        let biome = Biome {
+            music: Some(BiomeMusic {
+                sound: BiomeSound::new(ident!("music.game")),
+                min_delay: 200,
+                max_delay: 100,
+                replace_current_music: false,
+            }),
+            ..Default::default()
+        };
+
+        assert!(biome.validate().is_err());
+    }
+}
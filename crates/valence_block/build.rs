@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use heck::{ToPascalCase, ToShoutySnakeCase};
 use proc_macro2::TokenStream;
@@ -53,6 +53,8 @@ struct State {
     id: u16,
     luminance: u8,
     opaque: bool,
+    /// Not in the output of older versions of the extractor.
+    light_opacity: Option<u8>,
     replaceable: bool,
     collision_shapes: Vec<u16>,
     block_entity_type: Option<u32>,
@@ -68,10 +70,129 @@ struct Shape {
     max_z: f64,
 }
 
+#[derive(Deserialize, Clone, Debug)]
+struct Tags {
+    /// The names of the blocks in each block tag.
+    block: BTreeMap<String, Vec<String>>,
+}
+
 pub fn main() -> anyhow::Result<()> {
-    rerun_if_changed(["../../extracted/blocks.json"]);
+    rerun_if_changed(["../../extracted/blocks.json", "../../extracted/tags.json"]);
 
-    write_generated_file(build()?, "block.rs")
+    write_generated_file(build()?, "block.rs")?;
+    write_generated_file(build_tags()?, "block_tag.rs")
+}
+
+fn build_tags() -> anyhow::Result<TokenStream> {
+    let Tags { block: tags } = serde_json::from_str(include_str!("../../extracted/tags.json"))?;
+
+    let tag_variant = |name: &str| ident(name.replace('/', "_").to_pascal_case());
+    let kind_variant = |name: &str| ident(name.replace('.', "_").to_pascal_case());
+
+    let tag_variants = tags
+        .keys()
+        .map(|name| tag_variant(name))
+        .collect::<Vec<_>>();
+
+    let tag_count = tags.len();
+
+    let tag_from_ident_arms = tags
+        .keys()
+        .map(|name| {
+            let variant = tag_variant(name);
+            let ident = format!("minecraft:{name}");
+
+            quote! {
+                #ident => Some(Self::#variant),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let tag_to_ident_arms = tags
+        .keys()
+        .map(|name| {
+            let variant = tag_variant(name);
+            let ident = format!("minecraft:{name}");
+
+            quote! {
+                Self::#variant => ident!(#ident),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let tag_blocks_arms = tags
+        .iter()
+        .map(|(name, blocks)| {
+            let variant = tag_variant(name);
+            let kinds = blocks.iter().map(|b| kind_variant(b));
+
+            quote! {
+                Self::#variant => &[#(BlockKind::#kinds,)*],
+            }
+        })
+        .collect::<TokenStream>();
+
+    let kind_is_in_arms = tags
+        .iter()
+        .map(|(name, blocks)| {
+            let variant = tag_variant(name);
+            let kinds = blocks.iter().map(|b| kind_variant(b));
+
+            quote! {
+                BlockTag::#variant => matches!(self, #(Self::#kinds)|*),
+            }
+        })
+        .collect::<TokenStream>();
+
+    Ok(quote! {
+        /// A vanilla block tag, like `#minecraft:wool`. Tags referenced by a
+        /// tag are flattened into its blocks.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub enum BlockTag {
+            #(#tag_variants,)*
+        }
+
+        impl BlockTag {
+            /// Constructs a block tag from its resource identifier, without
+            /// the leading `#`.
+            ///
+            /// Returns `None` if the tag is not a vanilla block tag.
+            pub fn from_ident(ident: Ident<&str>) -> Option<Self> {
+                match ident.as_str() {
+                    #tag_from_ident_arms
+                    _ => None
+                }
+            }
+
+            /// Returns the resource identifier of this tag, without the
+            /// leading `#`.
+            pub fn ident(self) -> Ident<&'static str> {
+                match self {
+                    #tag_to_ident_arms
+                }
+            }
+
+            /// Returns the kinds of blocks in this tag.
+            pub const fn blocks(self) -> &'static [BlockKind] {
+                match self {
+                    #tag_blocks_arms
+                }
+            }
+
+            /// An array of all block tags.
+            pub const ALL: [Self; #tag_count] = [#(Self::#tag_variants,)*];
+        }
+
+        impl BlockKind {
+            /// Returns whether this kind of block is in the given vanilla
+            /// tag.
+            pub const fn is_in(self, tag: BlockTag) -> bool {
+                match tag {
+                    #kind_is_in_arms
+                }
+            }
+        }
+    })
 }
 
 fn build() -> anyhow::Result<TokenStream> {
@@ -141,6 +262,30 @@ fn build() -> anyhow::Result<TokenStream> {
         })
         .collect::<TokenStream>();
 
+    let state_to_light_opacity_arms = blocks
+        .iter()
+        .flat_map(|b| {
+            b.states.iter().filter_map(|s| {
+                // Estimate the opacity the way the lighting engine used to if the
+                // data doesn't have it.
+                let opacity = s.light_opacity.unwrap_or(if s.opaque {
+                    15
+                } else if b.name == "water" || b.name == "lava" {
+                    1
+                } else {
+                    0
+                });
+
+                let id = s.id;
+                (opacity != 0).then(|| {
+                    quote! {
+                        #id => #opacity,
+                    }
+                })
+            })
+        })
+        .collect::<TokenStream>();
+
     let state_to_replaceable_arms = blocks
         .iter()
         .flat_map(|b| {
@@ -286,6 +431,10 @@ fn build() -> anyhow::Result<TokenStream> {
         })
         .collect::<TokenStream>();
 
+    let rotate_clockwise_90 = transform_states(&blocks, max_state_id, Transform::RotateClockwise90);
+    let mirror_left_right = transform_states(&blocks, max_state_id, Transform::MirrorLeftRight);
+    let state_count = max_state_id as usize + 1;
+
     let default_block_states = blocks
         .iter()
         .map(|b| {
@@ -576,6 +725,12 @@ fn build() -> anyhow::Result<TokenStream> {
         use valence_core::aabb::Aabb;
         use glam::dvec3;
 
+        /// The ID of every block state rotated 90 degrees clockwise around the Y axis.
+        static ROTATE_CLOCKWISE_90: [u16; #state_count] = [#(#rotate_clockwise_90,)*];
+
+        /// The ID of every block state mirrored so that north and south are swapped.
+        static MIRROR_LEFT_RIGHT: [u16; #state_count] = [#(#mirror_left_right,)*];
+
         /// Represents the state of a block. This does not include block entity data such as
         /// the text on a sign, the design on a banner, or the content of a spawner.
         #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
@@ -674,6 +829,16 @@ fn build() -> anyhow::Result<TokenStream> {
                 }
             }
 
+            /// Returns how much light is lost when entering a block with this
+            /// state, in addition to the one level lost for every block
+            /// travelled. Opaque full blocks like stone block all light.
+            pub const fn light_opacity(self) -> u8 {
+                match self.0 {
+                    #state_to_light_opacity_arms
+                    _ => 0,
+                }
+            }
+
             pub const fn is_replaceable(self) -> bool {
                 match self.0 {
                     #state_to_replaceable_arms
@@ -685,6 +850,11 @@ fn build() -> anyhow::Result<TokenStream> {
                 #(#shapes,)*
             ];
 
+            /// Returns the boxes making up the collision shape of this block state,
+            /// relative to the minimum corner of the block. Blocks without
+            /// collision, like air and flowers, have no boxes, and some boxes
+            /// extend outside of the block, like the 1.5 block high boxes of
+            /// fences.
             pub fn collision_shapes(self) -> impl ExactSizeIterator<Item = Aabb> + FusedIterator + Clone {
                 let shape_idxs: &'static [u16] = match self.0 {
                     #state_to_collision_shapes_arms
@@ -969,3 +1139,170 @@ fn build() -> anyhow::Result<TokenStream> {
         }
     })
 }
+
+/// A transformation of the world which changes the properties of block states,
+/// like the direction stairs are facing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Transform {
+    RotateClockwise90,
+    MirrorLeftRight,
+}
+
+impl Transform {
+    /// Transforms a horizontal direction. Returns `None` for other values.
+    fn direction(self, dir: &str) -> Option<&'static str> {
+        Some(match (self, dir) {
+            (Self::RotateClockwise90, "north") => "east",
+            (Self::RotateClockwise90, "east") => "south",
+            (Self::RotateClockwise90, "south") => "west",
+            (Self::RotateClockwise90, "west") => "north",
+            (Self::MirrorLeftRight, "north") => "south",
+            (Self::MirrorLeftRight, "east") => "east",
+            (Self::MirrorLeftRight, "south") => "north",
+            (Self::MirrorLeftRight, "west") => "west",
+            _ => return None,
+        })
+    }
+
+    /// Swaps `left` and `right` at the end of a value when mirroring.
+    fn swap_left_right(self, value: &str) -> Option<String> {
+        if self != Self::MirrorLeftRight {
+            return None;
+        }
+
+        if let Some(prefix) = value.strip_suffix("left") {
+            Some(format!("{prefix}right"))
+        } else {
+            value
+                .strip_suffix("right")
+                .map(|prefix| format!("{prefix}left"))
+        }
+    }
+
+    /// Returns the name and value a property is changed to, or `None` if the
+    /// property doesn't depend on the orientation of the block.
+    fn prop(self, name: &str, value: &str) -> Option<(String, String)> {
+        // Properties named after directions, like the sides fences connect to,
+        // are moved to the transformed direction.
+        if let Some(dir) = self.direction(name) {
+            return Some((dir.into(), value.into()));
+        }
+
+        let value = match name {
+            "facing" => self.direction(value)?.into(),
+            "orientation" => {
+                let (a, b) = value.split_once('_')?;
+                let a = self.direction(a).unwrap_or(a);
+                let b = self.direction(b).unwrap_or(b);
+                format!("{a}_{b}")
+            }
+            "axis" if self == Self::RotateClockwise90 => match value {
+                "x" => "z".into(),
+                "z" => "x".into(),
+                _ => return None,
+            },
+            // Rotation 0 faces south, and each step is a sixteenth of a turn
+            // clockwise.
+            "rotation" => {
+                let rotation: u32 = value.parse().ok()?;
+
+                match self {
+                    Self::RotateClockwise90 => (rotation + 4) % 16,
+                    Self::MirrorLeftRight => (24 - rotation) % 16,
+                }
+                .to_string()
+            }
+            "shape" => {
+                if let Some(dir) = value.strip_prefix("ascending_") {
+                    format!("ascending_{}", self.direction(dir)?)
+                } else if let Some((a, b)) = value.split_once('_') {
+                    match (self.direction(a), self.direction(b)) {
+                        // The shapes of rails.
+                        (Some(a), Some(b)) => format!("{a}_{b}"),
+                        // The shapes of stairs.
+                        _ => self.swap_left_right(value)?,
+                    }
+                } else {
+                    return None;
+                }
+            }
+            "hinge" | "type" => self.swap_left_right(value)?,
+            _ => return None,
+        };
+
+        Some((name.into(), value))
+    }
+}
+
+/// Returns the ID every block state is changed to by `transform`, indexed by
+/// block state ID.
+fn transform_states(blocks: &[Block], max_state_id: u16, transform: Transform) -> Vec<u16> {
+    let mut res: Vec<u16> = (0..=max_state_id).collect();
+
+    for b in blocks {
+        // The number of states each value of a property is apart.
+        let products: Vec<u16> = (0..b.properties.len())
+            .map(|i| {
+                b.properties[i + 1..]
+                    .iter()
+                    .map(|p| p.values.len() as u16)
+                    .product()
+            })
+            .collect();
+
+        for state in &b.states {
+            let offset = state.id - b.min_state_id();
+
+            let value_idxs: Vec<u16> = b
+                .properties
+                .iter()
+                .zip(&products)
+                .map(|(p, &product)| offset / product % p.values.len() as u16)
+                .collect();
+
+            let mut new_value_idxs = value_idxs.clone();
+
+            for (p, &value_idx) in b.properties.iter().zip(&value_idxs) {
+                let Some((name, value)) = transform.prop(&p.name, &p.values[value_idx as usize])
+                else {
+                    continue;
+                };
+
+                let Some(new_prop_idx) = b.properties.iter().position(|p| p.name == name) else {
+                    continue;
+                };
+
+                let new_prop = &b.properties[new_prop_idx];
+
+                // Values made of two directions, like the shapes of rails, may be
+                // listed in the opposite order.
+                let reversed = value.split_once('_').map(|(a, b)| format!("{b}_{a}"));
+
+                let new_value_idx =
+                    new_prop
+                        .values
+                        .iter()
+                        .position(|v| *v == value)
+                        .or_else(|| {
+                            new_prop
+                                .values
+                                .iter()
+                                .position(|v| Some(v) == reversed.as_ref())
+                        });
+
+                if let Some(new_value_idx) = new_value_idx {
+                    new_value_idxs[new_prop_idx] = new_value_idx as u16;
+                }
+            }
+
+            res[state.id as usize] = b.min_state_id()
+                + new_value_idxs
+                    .iter()
+                    .zip(&products)
+                    .map(|(idx, product)| idx * product)
+                    .sum::<u16>();
+        }
+    }
+
+    res
+}
@@ -22,8 +22,11 @@ use std::fmt;
 use std::fmt::Display;
 use std::io::Write;
 use std::iter::FusedIterator;
+use std::str::FromStr;
 
 use anyhow::Context;
+use glam::IVec3;
+use thiserror::Error;
 use valence_core::ident;
 use valence_core::ident::Ident;
 use valence_core::item::ItemKind;
@@ -31,6 +34,132 @@ use valence_core::packet::var_int::VarInt;
 use valence_core::packet::{Decode, Encode};
 
 include!(concat!(env!("OUT_DIR"), "/block.rs"));
+include!(concat!(env!("OUT_DIR"), "/block_tag.rs"));
+
+impl BlockState {
+    /// Returns this block state rotated around the Y axis. Properties which
+    /// depend on the orientation of the block, like the direction stairs are
+    /// facing or the sides a fence connects to, are changed accordingly.
+    #[must_use]
+    pub fn rotate(self, rotation: Rotation) -> Self {
+        let mut id = self.0;
+
+        for _ in 0..rotation.quarter_turns() {
+            id = ROTATE_CLOCKWISE_90[id as usize];
+        }
+
+        Self(id)
+    }
+
+    /// Returns this block state mirrored along a vertical plane. Properties
+    /// which depend on the orientation of the block are changed accordingly,
+    /// like the corner of stairs moving to the other side.
+    #[must_use]
+    pub fn mirror(self, mirror: Mirror) -> Self {
+        match mirror {
+            Mirror::None => self,
+            Mirror::LeftRight => Self(MIRROR_LEFT_RIGHT[self.0 as usize]),
+            // Swapping east and west is the same as swapping north and south
+            // and turning around.
+            Mirror::FrontBack => {
+                Self(MIRROR_LEFT_RIGHT[self.0 as usize]).rotate(Rotation::Clockwise180)
+            }
+        }
+    }
+
+    /// Gets the value of the property with the given name from this block as
+    /// a string, like `"north"` for the `"facing"` property of stairs.
+    ///
+    /// If this block does not have the property, then `None` is returned.
+    pub fn get_str(self, name: &str) -> Option<&'static str> {
+        self.get(PropName::from_str(name)?).map(PropValue::to_str)
+    }
+
+    /// Sets the value of a property on this block from strings, returning the
+    /// modified block.
+    ///
+    /// Unlike [`BlockState::set`], `None` is returned if this block does not
+    /// have the property or the value is invalid for it.
+    pub fn set_str(self, name: &str, value: &str) -> Option<Self> {
+        let name = PropName::from_str(name)?;
+        let value = PropValue::from_str(value)?;
+
+        self.try_set(name, value)
+    }
+
+    /// Like [`BlockState::set`], but returns `None` instead of the unchanged
+    /// block if the property or value is invalid.
+    fn try_set(self, name: PropName, value: PropValue) -> Option<Self> {
+        let new = self.set(name, value);
+
+        (new.get(name) == Some(value)).then_some(new)
+    }
+
+    /// Returns the names and values of the properties of this block as
+    /// strings, in the same order as [`BlockKind::props`].
+    pub fn props(
+        self,
+    ) -> impl ExactSizeIterator<Item = (&'static str, &'static str)> + FusedIterator + Clone {
+        self.to_kind()
+            .props()
+            .iter()
+            .map(move |&name| (name.to_str(), self.get(name).unwrap().to_str()))
+    }
+}
+
+/// A rotation around the Y axis, as seen from above.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Hash, Debug)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    Counterclockwise90,
+}
+
+impl Rotation {
+    /// Returns the number of quarter turns clockwise this rotation is made of.
+    pub const fn quarter_turns(self) -> u8 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Clockwise90 => 1,
+            Rotation::Clockwise180 => 2,
+            Rotation::Counterclockwise90 => 3,
+        }
+    }
+
+    /// Rotates a position around the Y axis through the origin.
+    pub fn rotate_pos(self, pos: IVec3) -> IVec3 {
+        match self {
+            Rotation::None => pos,
+            Rotation::Clockwise90 => IVec3::new(-pos.z, pos.y, pos.x),
+            Rotation::Clockwise180 => IVec3::new(-pos.x, pos.y, -pos.z),
+            Rotation::Counterclockwise90 => IVec3::new(pos.z, pos.y, -pos.x),
+        }
+    }
+}
+
+/// A mirror along a vertical plane through the origin.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Hash, Debug)]
+pub enum Mirror {
+    #[default]
+    None,
+    /// Mirrors along the Z axis, swapping north and south.
+    LeftRight,
+    /// Mirrors along the X axis, swapping east and west.
+    FrontBack,
+}
+
+impl Mirror {
+    /// Mirrors a position.
+    pub fn mirror_pos(self, pos: IVec3) -> IVec3 {
+        match self {
+            Mirror::None => pos,
+            Mirror::LeftRight => IVec3::new(pos.x, pos.y, -pos.z),
+            Mirror::FrontBack => IVec3::new(-pos.x, pos.y, pos.z),
+        }
+    }
+}
 
 impl fmt::Debug for BlockState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -38,12 +167,100 @@ impl fmt::Debug for BlockState {
     }
 }
 
+/// Formats the block state like vanilla does in commands and data files, such
+/// as `minecraft:oak_stairs[facing=east,half=bottom]`. This is the format
+/// parsed by the [`FromStr`] implementation.
 impl Display for BlockState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt_block_state(*self, f)
+        write!(f, "minecraft:{}", self.to_kind().to_str())?;
+
+        let mut props = self.props();
+
+        if let Some((name, value)) = props.next() {
+            write!(f, "[{name}={value}")?;
+
+            for (name, value) in props {
+                write!(f, ",{name}={value}")?;
+            }
+
+            write!(f, "]")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a block state like `minecraft:oak_stairs[facing=east,half=bottom]`.
+/// The namespace is optional, and properties which are not given keep their
+/// default values.
+impl FromStr for BlockState {
+    type Err = ParseBlockStateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (ident, props) = match s.split_once('[') {
+            Some((ident, props)) => (
+                ident,
+                props
+                    .strip_suffix(']')
+                    .ok_or(ParseBlockStateError::InvalidSyntax)?,
+            ),
+            None => (s, ""),
+        };
+
+        let path = match ident.split_once(':') {
+            Some(("minecraft", path)) => path,
+            Some(_) => return Err(ParseBlockStateError::UnknownBlock(ident.into())),
+            None => ident,
+        };
+
+        let kind = BlockKind::from_str(path)
+            .ok_or_else(|| ParseBlockStateError::UnknownBlock(ident.into()))?;
+
+        let mut state = kind.to_state();
+        let mut seen = vec![];
+
+        for prop in props.split(',').filter(|prop| !prop.is_empty()) {
+            let (name, value) = prop
+                .split_once('=')
+                .ok_or(ParseBlockStateError::InvalidSyntax)?;
+
+            let prop_name = PropName::from_str(name)
+                .filter(|name| kind.props().contains(name))
+                .ok_or_else(|| ParseBlockStateError::UnknownProperty(name.into()))?;
+
+            if seen.contains(&prop_name) {
+                return Err(ParseBlockStateError::DuplicateProperty(name.into()));
+            }
+
+            seen.push(prop_name);
+
+            state = PropValue::from_str(value)
+                .and_then(|value| state.try_set(prop_name, value))
+                .ok_or_else(|| ParseBlockStateError::InvalidValue {
+                    name: name.into(),
+                    value: value.into(),
+                })?;
+        }
+
+        Ok(state)
     }
 }
 
+/// An error returned when parsing a [`BlockState`] from a string fails.
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+pub enum ParseBlockStateError {
+    #[error("invalid block state syntax")]
+    InvalidSyntax,
+    #[error("unknown block \"{0}\"")]
+    UnknownBlock(String),
+    #[error("unknown property \"{0}\"")]
+    UnknownProperty(String),
+    #[error("property \"{0}\" is given more than once")]
+    DuplicateProperty(String),
+    #[error("invalid value \"{value}\" for property \"{name}\"")]
+    InvalidValue { name: String, value: String },
+}
+
 fn fmt_block_state(bs: BlockState, f: &mut fmt::Formatter) -> fmt::Result {
     let kind = bs.to_kind();
 
@@ -116,6 +333,207 @@ mod tests {
         }
     }
 
+    #[test]
+    fn props_by_name() {
+        let stairs = BlockState::OAK_STAIRS;
+
+        assert_eq!(stairs.get_str("facing"), Some("north"));
+        assert_eq!(stairs.get_str("axis"), None);
+        assert_eq!(stairs.get_str("not_a_property"), None);
+
+        assert_eq!(
+            stairs.set_str("facing", "east"),
+            Some(stairs.set(PropName::Facing, PropValue::East))
+        );
+        assert_eq!(stairs.set_str("facing", "up"), None);
+        assert_eq!(stairs.set_str("facing", "not_a_value"), None);
+        assert_eq!(stairs.set_str("axis", "x"), None);
+
+        assert_eq!(
+            BlockState::SNOW.set_str("layers", "3"),
+            Some(BlockState::SNOW.set(PropName::Layers, PropValue::_3))
+        );
+
+        assert_eq!(
+            stairs.props().collect::<Vec<_>>(),
+            [
+                ("facing", "north"),
+                ("half", "bottom"),
+                ("shape", "straight"),
+                ("waterlogged", "false"),
+            ]
+        );
+        assert_eq!(BlockState::STONE.props().len(), 0);
+    }
+
+    #[test]
+    fn parse_display_round_trip() {
+        for id in 0..=BlockState::max_raw() {
+            let block = BlockState::from_raw(id).unwrap();
+
+            assert_eq!(block.to_string().parse(), Ok(block));
+        }
+
+        assert_eq!(
+            BlockState::OAK_STAIRS
+                .set(PropName::Facing, PropValue::East)
+                .to_string(),
+            "minecraft:oak_stairs[facing=east,half=bottom,shape=straight,waterlogged=false]"
+        );
+        assert_eq!(BlockState::STONE.to_string(), "minecraft:stone");
+    }
+
+    #[test]
+    fn parse_block_states() {
+        assert_eq!(
+            "oak_stairs[half=top,facing=west]".parse(),
+            Ok(BlockState::OAK_STAIRS
+                .set(PropName::Facing, PropValue::West)
+                .set(PropName::Half, PropValue::Top))
+        );
+        assert_eq!("minecraft:stone[]".parse(), Ok(BlockState::STONE));
+
+        assert_eq!(
+            "examplemod:stone".parse::<BlockState>(),
+            Err(ParseBlockStateError::UnknownBlock(
+                "examplemod:stone".into()
+            ))
+        );
+        assert_eq!(
+            "minecraft:oak_stairs[facing=north".parse::<BlockState>(),
+            Err(ParseBlockStateError::InvalidSyntax)
+        );
+        assert_eq!(
+            "minecraft:oak_stairs[facing]".parse::<BlockState>(),
+            Err(ParseBlockStateError::InvalidSyntax)
+        );
+        assert_eq!(
+            "minecraft:stone[facing=north]".parse::<BlockState>(),
+            Err(ParseBlockStateError::UnknownProperty("facing".into()))
+        );
+        assert_eq!(
+            "minecraft:oak_stairs[facing=up]".parse::<BlockState>(),
+            Err(ParseBlockStateError::InvalidValue {
+                name: "facing".into(),
+                value: "up".into(),
+            })
+        );
+        assert_eq!(
+            "minecraft:oak_stairs[facing=east,facing=west]".parse::<BlockState>(),
+            Err(ParseBlockStateError::DuplicateProperty("facing".into()))
+        );
+    }
+
+    #[test]
+    fn rotate_mirror_inverse() {
+        for id in 0..=BlockState::max_raw() {
+            let block = BlockState::from_raw(id).unwrap();
+
+            let mut rotated = block;
+            for _ in 0..4 {
+                rotated = rotated.rotate(Rotation::Clockwise90);
+            }
+            assert_eq!(rotated, block);
+
+            assert_eq!(
+                block
+                    .rotate(Rotation::Clockwise90)
+                    .rotate(Rotation::Counterclockwise90),
+                block
+            );
+
+            for mirror in [Mirror::LeftRight, Mirror::FrontBack] {
+                assert_eq!(block.mirror(mirror).mirror(mirror), block);
+            }
+        }
+    }
+
+    #[test]
+    fn rotate_mirror_props() {
+        let stairs = BlockState::OAK_STAIRS
+            .set(PropName::Facing, PropValue::North)
+            .set(PropName::Shape, PropValue::InnerLeft);
+
+        assert_eq!(
+            stairs.rotate(Rotation::Clockwise90),
+            stairs.set(PropName::Facing, PropValue::East)
+        );
+        assert_eq!(
+            stairs.mirror(Mirror::LeftRight),
+            stairs
+                .set(PropName::Facing, PropValue::South)
+                .set(PropName::Shape, PropValue::InnerRight)
+        );
+        assert_eq!(
+            stairs.mirror(Mirror::FrontBack),
+            stairs.set(PropName::Shape, PropValue::InnerRight)
+        );
+
+        let log = BlockState::OAK_LOG.set(PropName::Axis, PropValue::X);
+        assert_eq!(
+            log.rotate(Rotation::Clockwise90),
+            log.set(PropName::Axis, PropValue::Z)
+        );
+        assert_eq!(log.mirror(Mirror::LeftRight), log);
+
+        let fence = BlockState::OAK_FENCE
+            .set(PropName::North, PropValue::True)
+            .set(PropName::West, PropValue::True);
+        assert_eq!(
+            fence.rotate(Rotation::Clockwise90),
+            BlockState::OAK_FENCE
+                .set(PropName::North, PropValue::True)
+                .set(PropName::East, PropValue::True)
+        );
+
+        let rail = BlockState::RAIL.set(PropName::Shape, PropValue::NorthWest);
+        assert_eq!(
+            rail.rotate(Rotation::Clockwise90),
+            rail.set(PropName::Shape, PropValue::NorthEast)
+        );
+        assert_eq!(
+            rail.mirror(Mirror::FrontBack),
+            rail.set(PropName::Shape, PropValue::NorthEast)
+        );
+
+        let sign = BlockState::OAK_SIGN.set(PropName::Rotation, PropValue::_14);
+        assert_eq!(
+            sign.rotate(Rotation::Clockwise90),
+            sign.set(PropName::Rotation, PropValue::_2)
+        );
+
+        let jigsaw = BlockState::JIGSAW.set(PropName::Orientation, PropValue::NorthUp);
+        assert_eq!(
+            jigsaw.rotate(Rotation::Clockwise90),
+            jigsaw.set(PropName::Orientation, PropValue::EastUp)
+        );
+
+        let chest = BlockState::CHEST.set(PropName::Type, PropValue::Left);
+        assert_eq!(
+            chest.mirror(Mirror::LeftRight),
+            chest
+                .set(PropName::Facing, PropValue::South)
+                .set(PropName::Type, PropValue::Right)
+        );
+    }
+
+    #[test]
+    fn rotate_mirror_pos() {
+        let pos = IVec3::new(1, 2, 3);
+
+        assert_eq!(Rotation::Clockwise90.rotate_pos(pos), IVec3::new(-3, 2, 1));
+        assert_eq!(
+            Rotation::Clockwise180.rotate_pos(pos),
+            IVec3::new(-1, 2, -3)
+        );
+        assert_eq!(
+            Rotation::Counterclockwise90.rotate_pos(pos),
+            IVec3::new(3, 2, -1)
+        );
+        assert_eq!(Mirror::LeftRight.mirror_pos(pos), IVec3::new(1, 2, -3));
+        assert_eq!(Mirror::FrontBack.mirror_pos(pos), IVec3::new(-1, 2, 3));
+    }
+
     #[test]
     fn blockstate_to_wall() {
         assert_eq!(BlockState::STONE.wall_block_id(), None);
@@ -132,4 +550,20 @@ mod tests {
             Some(BlockState::GREEN_BANNER)
         );
     }
+
+    #[test]
+    fn block_tags() {
+        for tag in BlockTag::ALL {
+            assert_eq!(BlockTag::from_ident(tag.ident()), Some(tag));
+
+            for &kind in tag.blocks() {
+                assert!(kind.is_in(tag));
+            }
+        }
+
+        assert!(BlockKind::WhiteWool.is_in(BlockTag::Wool));
+        assert!(BlockKind::BlueIce.is_in(BlockTag::Ice));
+        assert!(!BlockKind::Stone.is_in(BlockTag::Wool));
+        assert_eq!(BlockTag::from_ident(ident!("minecraft:nonexistent")), None);
+    }
 }
@@ -0,0 +1,256 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+use std::collections::BTreeSet;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::WorldQuery;
+use uuid::Uuid;
+use valence_client::{Client, FlushPacketsSet};
+use valence_core::despawn::Despawned;
+use valence_core::packet::encode::WritePacket;
+use valence_core::packet::s2c::play::boss_bar::{Action, BossBarS2c, Flags};
+pub use valence_core::packet::s2c::play::boss_bar::{
+    Color as BossBarColor, Division as BossBarDivision,
+};
+use valence_core::text::Text;
+use valence_entity::Location;
+
+pub struct BossBarPlugin;
+
+/// The [`SystemSet`] in [`CoreSet::PostUpdate`] where the boss bars are sent
+/// to their viewers. Changes made to boss bars after this set are sent the
+/// next tick.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UpdateBossBarsSet;
+
+impl Plugin for BossBarPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_set(
+            UpdateBossBarsSet
+                .in_base_set(CoreSet::PostUpdate)
+                .before(FlushPacketsSet),
+        )
+        .add_system(update_boss_bars.in_set(UpdateBossBarsSet));
+    }
+}
+
+/// The bundle of components for a boss bar. All components are required.
+///
+/// The bundle can be spawned as its own entity, or inserted into an existing
+/// entity such as a Minecraft entity. Either way, the boss bar is removed from
+/// the screen of its viewers once the entity has the [`Despawned`] component.
+#[derive(Bundle, Default, Debug)]
+pub struct BossBarBundle {
+    pub id: BossBarId,
+    pub title: BossBarTitle,
+    pub health: BossBarHealth,
+    pub style: BossBarStyle,
+    pub flags: BossBarFlags,
+    pub viewers: BossBarViewers,
+    pub old_viewers: OldBossBarViewers,
+}
+
+/// The UUID identifying a boss bar on the client. Careful not to modify this!
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BossBarId(pub Uuid);
+
+/// Generates a new random UUID.
+impl Default for BossBarId {
+    fn default() -> Self {
+        Self(Uuid::from_bytes(rand::random()))
+    }
+}
+
+/// The text displayed above the boss bar.
+#[derive(Component, Clone, PartialEq, Default, Debug)]
+pub struct BossBarTitle(pub Text);
+
+/// The fraction of the boss bar which is filled. Valid values are within
+/// `0.0..=1.0`. Defaults to `1.0`.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct BossBarHealth(pub f32);
+
+impl Default for BossBarHealth {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// The appearance of the boss bar.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BossBarStyle {
+    pub color: BossBarColor,
+    pub division: BossBarDivision,
+}
+
+impl Default for BossBarStyle {
+    fn default() -> Self {
+        Self {
+            color: BossBarColor::Pink,
+            division: BossBarDivision::NoDivision,
+        }
+    }
+}
+
+/// The effects applied to the screen of the clients viewing the boss bar.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct BossBarFlags {
+    pub darken_sky: bool,
+    /// Plays the music of the ender dragon fight.
+    pub dragon_bar: bool,
+    pub create_fog: bool,
+}
+
+impl BossBarFlags {
+    fn to_packet_flags(self) -> Flags {
+        Flags::new()
+            .with_darken_sky(self.darken_sky)
+            .with_dragon_bar(self.dragon_bar)
+            .with_create_fog(self.create_fog)
+    }
+}
+
+/// The clients viewing a boss bar.
+///
+/// Clients which reconnect are new entities, so they need to be added to
+/// [`Self::clients`] again. Viewers from [`Self::instance`] are tracked
+/// automatically.
+#[derive(Component, Clone, Default, Debug)]
+pub struct BossBarViewers {
+    /// The client entities viewing the boss bar.
+    pub clients: BTreeSet<Entity>,
+    /// If set, all the clients located in this instance also view the boss
+    /// bar.
+    pub instance: Option<Entity>,
+}
+
+/// The clients which have the boss bar on their screen, as of the last
+/// [`UpdateBossBarsSet`].
+#[derive(Component, Clone, Default, Debug)]
+pub struct OldBossBarViewers(BTreeSet<Entity>);
+
+impl OldBossBarViewers {
+    pub fn get(&self) -> &BTreeSet<Entity> {
+        &self.0
+    }
+}
+
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+struct BossBarQuery {
+    id: &'static BossBarId,
+    title: Ref<'static, BossBarTitle>,
+    health: Ref<'static, BossBarHealth>,
+    style: Ref<'static, BossBarStyle>,
+    flags: Ref<'static, BossBarFlags>,
+    viewers: &'static BossBarViewers,
+    old_viewers: &'static mut OldBossBarViewers,
+    despawned: Option<&'static Despawned>,
+}
+
+fn update_boss_bars(
+    mut boss_bars: Query<BossBarQuery>,
+    mut clients: Query<(Entity, &mut Client, &Location)>,
+    mut new_viewers: Local<BTreeSet<Entity>>,
+) {
+    for mut bar in &mut boss_bars {
+        new_viewers.clear();
+
+        if bar.despawned.is_none() {
+            for (entity, client, loc) in &mut clients {
+                // Clients which were just added have not joined the game yet.
+                // The boss bar is sent to them the next tick.
+                if client.is_added() {
+                    continue;
+                }
+
+                if bar.viewers.clients.contains(&entity) || bar.viewers.instance == Some(loc.0) {
+                    new_viewers.insert(entity);
+                }
+            }
+        }
+
+        let old_viewers = &bar.old_viewers.0;
+
+        for &entity in old_viewers.difference(&new_viewers) {
+            // Disconnected clients no longer have the component.
+            if let Ok((_, mut client, _)) = clients.get_mut(entity) {
+                client.write_packet(&BossBarS2c {
+                    id: bar.id.0,
+                    action: Action::Remove,
+                });
+            }
+        }
+
+        for &entity in new_viewers.iter() {
+            let Ok((_, mut client, _)) = clients.get_mut(entity) else {
+                continue;
+            };
+
+            if !old_viewers.contains(&entity) {
+                client.write_packet(&BossBarS2c {
+                    id: bar.id.0,
+                    action: Action::Add {
+                        title: bar.title.0.clone(),
+                        health: bar.health.0,
+                        color: bar.style.color,
+                        division: bar.style.division,
+                        flags: bar.flags.to_packet_flags(),
+                    },
+                });
+
+                continue;
+            }
+
+            if bar.title.is_changed() {
+                client.write_packet(&BossBarS2c {
+                    id: bar.id.0,
+                    action: Action::UpdateTitle(bar.title.0.clone()),
+                });
+            }
+
+            if bar.health.is_changed() {
+                client.write_packet(&BossBarS2c {
+                    id: bar.id.0,
+                    action: Action::UpdateHealth(bar.health.0),
+                });
+            }
+
+            if bar.style.is_changed() {
+                client.write_packet(&BossBarS2c {
+                    id: bar.id.0,
+                    action: Action::UpdateStyle(bar.style.color, bar.style.division),
+                });
+            }
+
+            if bar.flags.is_changed() {
+                client.write_packet(&BossBarS2c {
+                    id: bar.id.0,
+                    action: Action::UpdateFlags(bar.flags.to_packet_flags()),
+                });
+            }
+        }
+
+        if bar.old_viewers.0 != *new_viewers {
+            bar.old_viewers.0.clone_from(&new_viewers);
+        }
+    }
+}
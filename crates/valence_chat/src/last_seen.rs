@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+
+use crate::ChatVerificationError;
+
+/// The number of signed messages clients acknowledge with each chat message.
+const LAST_SEEN_COUNT: usize = 20;
+
+/// Tracks the signed messages sent to a client, and which of them the client
+/// has seen. Clients reference the messages they have seen in the signature of
+/// their own messages.
+#[derive(Clone, Debug)]
+pub(crate) struct LastSeenTracker {
+    /// The messages which can be acknowledged by the client. The first
+    /// [`LAST_SEEN_COUNT`] entries are the window of the last update from the
+    /// client, and `None` for messages the client ignored.
+    entries: VecDeque<Option<TrackedMessage>>,
+    last_pending: Option<Box<[u8; 256]>>,
+}
+
+#[derive(Clone, Debug)]
+struct TrackedMessage {
+    signature: Box<[u8; 256]>,
+    /// If the message was sent to the client, but not acknowledged yet.
+    pending: bool,
+}
+
+impl Default for LastSeenTracker {
+    fn default() -> Self {
+        Self {
+            entries: vec![None; LAST_SEEN_COUNT].into(),
+            last_pending: None,
+        }
+    }
+}
+
+impl LastSeenTracker {
+    /// Tracks a message sent to the client.
+    pub(crate) fn add_pending(&mut self, signature: &[u8; 256]) {
+        if self.last_pending.as_deref() != Some(signature) {
+            self.entries.push_back(Some(TrackedMessage {
+                signature: Box::new(*signature),
+                pending: true,
+            }));
+            self.last_pending = Some(Box::new(*signature));
+        }
+    }
+
+    /// Moves the window of the client `offset` messages forward.
+    pub(crate) fn apply_offset(&mut self, offset: i32) -> Result<(), ChatVerificationError> {
+        let max_offset = self.entries.len() - LAST_SEEN_COUNT;
+
+        match usize::try_from(offset) {
+            Ok(offset) if offset <= max_offset => {
+                self.entries.drain(..offset);
+                Ok(())
+            }
+            _ => Err(ChatVerificationError::InvalidAcknowledgement),
+        }
+    }
+
+    /// Applies an update of the window of the client, where `acknowledged` is
+    /// a bitset of the messages in the window the client has seen. Returns
+    /// the signatures of those messages.
+    pub(crate) fn apply_update(
+        &mut self,
+        offset: i32,
+        acknowledged: [u8; 3],
+    ) -> Result<Vec<[u8; 256]>, ChatVerificationError> {
+        self.apply_offset(offset)?;
+
+        let [a, b, c] = acknowledged;
+        let bits = u32::from_le_bytes([a, b, c, 0]);
+
+        if bits >> LAST_SEEN_COUNT != 0 {
+            return Err(ChatVerificationError::InvalidAcknowledgement);
+        }
+
+        let mut last_seen = vec![];
+
+        for (i, entry) in self.entries.iter_mut().take(LAST_SEEN_COUNT).enumerate() {
+            if bits & (1 << i) != 0 {
+                // The client can't acknowledge messages it ignored before.
+                let Some(msg) = entry else {
+                    return Err(ChatVerificationError::InvalidAcknowledgement);
+                };
+
+                msg.pending = false;
+                last_seen.push(*msg.signature);
+            } else {
+                // The client can't ignore messages it acknowledged before.
+                if matches!(entry, Some(msg) if !msg.pending) {
+                    return Err(ChatVerificationError::InvalidAcknowledgement);
+                }
+
+                *entry = None;
+            }
+        }
+
+        Ok(last_seen)
+    }
+}
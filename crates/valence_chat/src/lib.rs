@@ -0,0 +1,453 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod last_seen;
+mod session;
+
+use std::borrow::Cow;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use last_seen::LastSeenTracker;
+pub use rsa::RsaPublicKey;
+pub use session::ChatSession;
+use thiserror::Error;
+use tracing::warn;
+use uuid::Uuid;
+use valence_client::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
+use valence_client::{broadcast_packet_cached, Client, FlushPacketsSet, SpawnClientsSet};
+// Also export the unsigned chat API, since this crate replaces the module in `valence`.
+pub use valence_core::chat::*;
+use valence_core::despawn::Despawned;
+use valence_core::packet::c2s::play::{
+    ChatMessageC2s, CommandExecutionC2s, MessageAcknowledgmentC2s, PlayerSessionC2s,
+};
+use valence_core::packet::encode::WritePacket;
+use valence_core::packet::message_signature::MessageSignature;
+use valence_core::packet::s2c::play::chat_message::MessageFilterType;
+use valence_core::packet::s2c::play::player_list::{Actions, Entry};
+use valence_core::packet::s2c::play::{ChatMessageS2c, PlayerListS2c};
+use valence_core::packet::var_int::VarInt;
+use valence_core::text::Text;
+use valence_core::translation_key;
+use valence_core::uuid::UniqueId;
+use valence_player_list::PlayerListSet;
+
+pub struct ChatPlugin;
+
+/// The [`SystemSet`] in [`CoreSet::PostUpdate`] where the chat sessions of
+/// clients are sent to the other clients.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UpdateChatSessionsSet;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatSettings>()
+            .add_event::<SignedChatMessage>()
+            .add_event::<ChatVerificationFailure>()
+            .configure_set(
+                UpdateChatSessionsSet
+                    .in_base_set(CoreSet::PostUpdate)
+                    // The entries of the player list need to exist before sessions are added to
+                    // them.
+                    .after(PlayerListSet)
+                    .before(FlushPacketsSet),
+            )
+            .add_system(
+                check_chat_settings
+                    .run_if(resource_changed::<ChatSettings>())
+                    .in_base_set(CoreSet::PreUpdate),
+            )
+            .add_system(
+                init_chat_states
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(SpawnClientsSet),
+            )
+            .add_system(
+                handle_chat_packets
+                    .in_schedule(EventLoopSchedule)
+                    .in_base_set(EventLoopSet::PreUpdate),
+            )
+            .add_systems(
+                (init_chat_sessions_for_clients, update_chat_sessions)
+                    .chain()
+                    .in_set(UpdateChatSessionsSet),
+            );
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct ChatSettings {
+    /// If chat messages are signed. When enabled, the signatures of chat
+    /// messages are verified, and messages are sent as [`SignedChatMessage`]
+    /// events which can be relayed to other clients with
+    /// [`ChatState::write_chat_message`].
+    ///
+    /// When disabled, the signatures are ignored and clients mark chat
+    /// messages as not secure. Chat messages are only sent as
+    /// `valence_client::misc::ChatMessage` events, which are sent in both
+    /// modes. Disabled by default.
+    pub secure: bool,
+    /// The key Mojang signs the public keys of players with, which is found in
+    /// `yggdrasil_session_pubkey.der` in the authlib library. It must be set
+    /// when [`secure`](Self::secure) is enabled, or the app panics.
+    pub session_public_key: Option<RsaPublicKey>,
+}
+
+/// Makes sure the public keys of players can be checked when secure chat is
+/// enabled, since accepting any key would let clients sign messages as other
+/// players.
+fn check_chat_settings(settings: Res<ChatSettings>) {
+    assert!(
+        !settings.secure || settings.session_public_key.is_some(),
+        "secure chat is enabled, but `ChatSettings::session_public_key` is not set"
+    );
+}
+
+/// The chat state of a client, which is added to clients by the
+/// [`ChatPlugin`].
+#[derive(Component, Default, Debug)]
+pub struct ChatState {
+    session: Option<ChatSession>,
+    /// If the session was changed since it was last sent to the other
+    /// clients.
+    session_changed: bool,
+    /// The index of the next message of the client, or `None` if the chain of
+    /// messages is broken.
+    next_index: Option<i32>,
+    last_timestamp: u64,
+    last_seen: LastSeenTracker,
+}
+
+impl ChatState {
+    /// Returns the current chat session of the client, if it has sent a valid
+    /// one.
+    pub fn session(&self) -> Option<&ChatSession> {
+        self.session.as_ref()
+    }
+
+    /// Writes a signed chat message to the client, which is tracked so the
+    /// client can reference it in its own messages.
+    ///
+    /// The UUID of `sender` must be the UUID of the client which sent the
+    /// message, or the signature won't be valid. The session of the sender
+    /// is sent to all clients in the player list.
+    pub fn write_chat_message(
+        &mut self,
+        mut writer: impl WritePacket,
+        msg: &SignedChatMessage,
+        sender: &ChatSender,
+        chat_type: ChatType,
+    ) {
+        writer.write_packet(&ChatMessageS2c {
+            sender: sender.uuid,
+            index: VarInt(msg.index),
+            message_signature: Some(&msg.signature),
+            message: &msg.message,
+            time_stamp: msg.timestamp,
+            salt: msg.salt,
+            previous_messages: msg
+                .last_seen
+                .iter()
+                .map(|sig| MessageSignature {
+                    message_id: -1,
+                    signature: Some(sig),
+                })
+                .collect(),
+            unsigned_content: None,
+            filter_type: MessageFilterType::PassThrough,
+            filter_type_bits: None,
+            chat_type: VarInt(chat_type.0),
+            network_name: Cow::Borrowed(&sender.name),
+            network_target_name: sender.target_name.as_ref().map(Cow::Borrowed),
+        });
+
+        self.last_seen.add_pending(&msg.signature);
+    }
+
+    fn set_session(&mut self, session: ChatSession) {
+        self.session = Some(session);
+        self.session_changed = true;
+        // A new session starts a new chain of messages.
+        self.next_index = Some(0);
+        self.last_timestamp = 0;
+    }
+
+    /// Checks the signature and index of a chat message, and returns the
+    /// index if they are valid.
+    fn verify_message(
+        &mut self,
+        uuid: Uuid,
+        pkt: &ChatMessageC2s,
+        last_seen: &[[u8; 256]],
+    ) -> Result<i32, ChatVerificationError> {
+        let Some(session) = &self.session else {
+            return Err(ChatVerificationError::MissingSession);
+        };
+
+        let Some(signature) = pkt.signature else {
+            return Err(ChatVerificationError::MissingSignature);
+        };
+
+        let Some(index) = self.next_index else {
+            return Err(ChatVerificationError::BrokenChain);
+        };
+
+        if session.is_expired() {
+            return Err(ChatVerificationError::ExpiredSessionKey);
+        }
+
+        if pkt.timestamp < self.last_timestamp {
+            return Err(ChatVerificationError::OutOfOrder);
+        }
+
+        if !session.verify_message(
+            uuid,
+            index,
+            pkt.salt,
+            pkt.timestamp,
+            pkt.message,
+            last_seen,
+            signature,
+        ) {
+            // All following messages are rejected until the client sends a new session.
+            self.next_index = None;
+            return Err(ChatVerificationError::InvalidSignature);
+        }
+
+        self.next_index = Some(index + 1);
+        self.last_timestamp = pkt.timestamp;
+
+        Ok(index)
+    }
+}
+
+/// Sent when a client sends a chat message with a valid signature. Only sent
+/// if [`ChatSettings::secure`] is enabled.
+#[derive(Clone, Debug)]
+pub struct SignedChatMessage {
+    pub client: Entity,
+    pub message: Box<str>,
+    /// The time at which the message was sent, in milliseconds since the Unix
+    /// epoch.
+    pub timestamp: u64,
+    pub salt: u64,
+    pub signature: Box<[u8; 256]>,
+    /// The index of the message in the chain of messages of the client.
+    pub index: i32,
+    /// The signatures of the messages the client had seen when it sent the
+    /// message.
+    pub last_seen: Vec<[u8; 256]>,
+}
+
+/// Sent when the chat session or a chat message of a client is rejected. Only
+/// sent if [`ChatSettings::secure`] is enabled.
+///
+/// The client is not disconnected, but messages can't be verified again until
+/// the problem is fixed. Use [`ChatVerificationError::disconnect_reason`] to
+/// disconnect the client like vanilla servers do.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ChatVerificationFailure {
+    pub client: Entity,
+    pub error: ChatVerificationError,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Error)]
+pub enum ChatVerificationError {
+    #[error("invalid public key in chat session")]
+    InvalidSessionKey,
+    #[error("invalid signature of the public key in chat session")]
+    InvalidSessionSignature,
+    #[error("public key of chat session has expired")]
+    ExpiredSessionKey,
+    #[error("chat message sent without chat session")]
+    MissingSession,
+    #[error("chat message is not signed")]
+    MissingSignature,
+    #[error("chat message sent after the chain of messages was broken")]
+    BrokenChain,
+    #[error("chat message is older than the previous message")]
+    OutOfOrder,
+    #[error("invalid signature of chat message")]
+    InvalidSignature,
+    #[error("invalid acknowledgement of seen messages")]
+    InvalidAcknowledgement,
+}
+
+impl ChatVerificationError {
+    /// Returns the reason vanilla servers disconnect clients with for this
+    /// error.
+    pub fn disconnect_reason(self) -> Text {
+        let key = match self {
+            Self::InvalidSessionKey | Self::InvalidSessionSignature => {
+                translation_key::MULTIPLAYER_DISCONNECT_INVALID_PUBLIC_KEY_SIGNATURE
+            }
+            Self::ExpiredSessionKey => translation_key::MULTIPLAYER_DISCONNECT_EXPIRED_PUBLIC_KEY,
+            Self::MissingSession | Self::MissingSignature => {
+                translation_key::MULTIPLAYER_DISCONNECT_UNSIGNED_CHAT
+            }
+            Self::OutOfOrder => translation_key::MULTIPLAYER_DISCONNECT_OUT_OF_ORDER_CHAT,
+            Self::BrokenChain | Self::InvalidSignature | Self::InvalidAcknowledgement => {
+                translation_key::MULTIPLAYER_DISCONNECT_CHAT_VALIDATION_FAILED
+            }
+        };
+
+        Text::translate(key, [])
+    }
+}
+
+fn init_chat_states(clients: Query<Entity, Added<Client>>, mut commands: Commands) {
+    for entity in &clients {
+        commands.entity(entity).insert(ChatState::default());
+    }
+}
+
+fn handle_chat_packets(
+    mut packets: EventReader<PacketEvent>,
+    mut clients: Query<(&UniqueId, &mut ChatState)>,
+    settings: Res<ChatSettings>,
+    mut message_events: EventWriter<SignedChatMessage>,
+    mut failure_events: EventWriter<ChatVerificationFailure>,
+) {
+    let session_public_key = settings
+        .session_public_key
+        .as_ref()
+        .filter(|_| settings.secure);
+
+    for packet in packets.iter() {
+        let Some(session_public_key) = session_public_key else {
+            continue;
+        };
+
+        let Ok((uuid, mut state)) = clients.get_mut(packet.client) else {
+            continue;
+        };
+
+        let result = if let Some(pkt) = packet.decode::<PlayerSessionC2s>() {
+            ChatSession::new(uuid.0, &pkt, session_public_key)
+                .map(|session| state.set_session(session))
+        } else if let Some(pkt) = packet.decode::<ChatMessageC2s>() {
+            state
+                .last_seen
+                .apply_update(pkt.message_count.0, pkt.acknowledgement)
+                .and_then(|last_seen| {
+                    let index = state.verify_message(uuid.0, &pkt, &last_seen)?;
+
+                    message_events.send(SignedChatMessage {
+                        client: packet.client,
+                        message: pkt.message.into(),
+                        timestamp: pkt.timestamp,
+                        salt: pkt.salt,
+                        signature: Box::new(*pkt.signature.unwrap()),
+                        index,
+                        last_seen,
+                    });
+
+                    Ok(())
+                })
+        } else if let Some(pkt) = packet.decode::<CommandExecutionC2s>() {
+            state
+                .last_seen
+                .apply_update(pkt.message_count.0, pkt.acknowledgement)
+                .map(|_| ())
+        } else if let Some(pkt) = packet.decode::<MessageAcknowledgmentC2s>() {
+            state.last_seen.apply_offset(pkt.message_count.0)
+        } else {
+            Ok(())
+        };
+
+        if let Err(error) = result {
+            warn!(
+                "failed to verify chat of client {:?}: {error}",
+                packet.client
+            );
+
+            failure_events.send(ChatVerificationFailure {
+                client: packet.client,
+                error,
+            });
+        }
+    }
+}
+
+/// Sends the sessions of all clients to new clients.
+fn init_chat_sessions_for_clients(
+    mut clients: Query<&mut Client, (Added<Client>, Without<Despawned>)>,
+    states: Query<(&UniqueId, &ChatState)>,
+) {
+    if clients.is_empty() {
+        return;
+    }
+
+    let entries: Vec<_> = states
+        .iter()
+        .filter_map(|(uuid, state)| {
+            Some(Entry {
+                player_uuid: uuid.0,
+                chat_data: Some(state.session.as_ref()?.chat_data()),
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return;
+    }
+
+    broadcast_packet_cached(
+        &PlayerListS2c {
+            actions: Actions::new().with_initialize_chat(true),
+            entries: Cow::Borrowed(&entries),
+        },
+        &mut clients,
+    );
+}
+
+/// Sends changed sessions to all clients.
+fn update_chat_sessions(
+    mut states: Query<(&UniqueId, &mut ChatState)>,
+    mut clients: Query<&mut Client, Without<Despawned>>,
+) {
+    for (uuid, mut state) in &mut states {
+        if !state.session_changed {
+            continue;
+        }
+
+        state.session_changed = false;
+
+        let Some(session) = &state.session else {
+            continue;
+        };
+
+        let entry = Entry {
+            player_uuid: uuid.0,
+            chat_data: Some(session.chat_data()),
+            ..Default::default()
+        };
+
+        // New clients already got all sessions.
+        broadcast_packet_cached(
+            &PlayerListS2c {
+                actions: Actions::new().with_initialize_chat(true),
+                entries: Cow::Borrowed(std::slice::from_ref(&entry)),
+            },
+            clients.iter_mut().filter(|client| !client.is_added()),
+        );
+    }
+}
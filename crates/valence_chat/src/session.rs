@@ -0,0 +1,136 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{PaddingScheme, PublicKey, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use valence_core::packet::c2s::play::PlayerSessionC2s;
+use valence_core::packet::s2c::play::player_list::ChatData;
+
+use crate::ChatVerificationError;
+
+/// The chat session of a client, which holds the public key its chat messages
+/// are signed with. Clients send a new session each time they join, and when
+/// their key expires.
+#[derive(Clone, Debug)]
+pub struct ChatSession {
+    session_id: Uuid,
+    expires_at: i64,
+    public_key: RsaPublicKey,
+    public_key_der: Box<[u8]>,
+    key_signature: Box<[u8]>,
+}
+
+impl ChatSession {
+    /// Validates the session sent by the client with the given UUID, whose key
+    /// has to be signed with `session_public_key`.
+    pub(crate) fn new(
+        uuid: Uuid,
+        pkt: &PlayerSessionC2s,
+        session_public_key: &RsaPublicKey,
+    ) -> Result<Self, ChatVerificationError> {
+        // The key is signed by Mojang together with the UUID of the player and the
+        // expiry time.
+        let hash = Sha1::new()
+            .chain_update(uuid.as_bytes())
+            .chain_update(pkt.expires_at.to_be_bytes())
+            .chain_update(pkt.public_key_data)
+            .finalize();
+
+        session_public_key
+            .verify(
+                PaddingScheme::new_pkcs1v15_sign::<Sha1>(),
+                &hash,
+                pkt.key_signature,
+            )
+            .map_err(|_| ChatVerificationError::InvalidSessionSignature)?;
+
+        if pkt.expires_at < now_millis() {
+            return Err(ChatVerificationError::ExpiredSessionKey);
+        }
+
+        let public_key = RsaPublicKey::from_public_key_der(pkt.public_key_data)
+            .map_err(|_| ChatVerificationError::InvalidSessionKey)?;
+
+        Ok(Self {
+            session_id: pkt.session_id,
+            expires_at: pkt.expires_at,
+            public_key,
+            public_key_der: pkt.public_key_data.into(),
+            key_signature: pkt.key_signature.into(),
+        })
+    }
+
+    pub fn session_id(&self) -> Uuid {
+        self.session_id
+    }
+
+    /// The time at which the key expires, in milliseconds since the Unix
+    /// epoch.
+    pub fn expires_at(&self) -> i64 {
+        self.expires_at
+    }
+
+    pub fn public_key(&self) -> &RsaPublicKey {
+        &self.public_key
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < now_millis()
+    }
+
+    /// Returns the session as sent to other clients in the player list.
+    pub(crate) fn chat_data(&self) -> ChatData {
+        ChatData {
+            session_id: self.session_id,
+            key_expiry_time: self.expires_at,
+            public_key: &self.public_key_der,
+            public_key_signature: &self.key_signature,
+        }
+    }
+
+    /// Checks the signature of a chat message sent by `sender`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn verify_message(
+        &self,
+        sender: Uuid,
+        index: i32,
+        salt: u64,
+        timestamp: u64,
+        message: &str,
+        last_seen: &[[u8; 256]],
+        signature: &[u8; 256],
+    ) -> bool {
+        let mut hasher = Sha256::new()
+            // The version of the signature format.
+            .chain_update(1_i32.to_be_bytes())
+            .chain_update(sender.as_bytes())
+            .chain_update(self.session_id.as_bytes())
+            .chain_update(index.to_be_bytes())
+            .chain_update(salt.to_be_bytes())
+            // The timestamp is signed in seconds.
+            .chain_update((timestamp / 1000).to_be_bytes())
+            .chain_update((message.len() as i32).to_be_bytes())
+            .chain_update(message.as_bytes())
+            .chain_update((last_seen.len() as i32).to_be_bytes());
+
+        for sig in last_seen {
+            hasher.update(&sig[..]);
+        }
+
+        self.public_key
+            .verify(
+                PaddingScheme::new_pkcs1v15_sign::<Sha256>(),
+                &hasher.finalize(),
+                signature,
+            )
+            .is_ok()
+    }
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
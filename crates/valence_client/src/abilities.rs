@@ -0,0 +1,191 @@
+//! Player abilities.
+//!
+//! The [`PlayerAbilities`] of a client control whether it can fly, how fast it
+//! flies, and whether it takes damage. They are reset to the defaults of the
+//! [`GameMode`] when it changes. Clients starting or stopping to fly are sent
+//! as [`Flying`] events.
+
+use valence_core::packet::c2s::play::UpdatePlayerAbilitiesC2s;
+use valence_core::packet::s2c::play::player_abilities::PlayerAbilitiesFlags;
+use valence_core::packet::s2c::play::PlayerAbilitiesS2c;
+
+use super::*;
+use crate::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<Flying>()
+        .add_system(
+            handle_update_player_abilities
+                .in_schedule(EventLoopSchedule)
+                .in_base_set(EventLoopSet::PreUpdate),
+        )
+        .add_systems(
+            (
+                reset_abilities_on_game_mode_change.after(update_game_mode),
+                update_abilities
+                    .after(reset_abilities_on_game_mode_change)
+                    .after(respawn),
+            )
+                .in_set(UpdateClientsSet),
+        );
+}
+
+/// The abilities of a client. Changes are sent to the client, and the
+/// abilities are sent again after it respawns.
+///
+/// When the [`GameMode`] of the client changes, the abilities are reset to the
+/// defaults of the new game mode, unless they were also modified during the
+/// same tick.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct PlayerAbilities {
+    /// Whether the client is shown as invulnerable. Damage is still up to the
+    /// server.
+    pub invulnerable: bool,
+    /// Whether the client is flying. Kept in sync with the client.
+    pub flying: bool,
+    /// Whether the client can start flying by double jumping.
+    pub allow_flying: bool,
+    /// Whether the client breaks blocks instantly.
+    pub instant_break: bool,
+    /// The flying speed of the client. Defaults to `0.05`.
+    pub fly_speed: f32,
+    /// The walking speed used by the client to compute its field of view.
+    /// Defaults to `0.1`. This does not change the actual movement speed.
+    pub walk_speed: f32,
+}
+
+impl PlayerAbilities {
+    /// Returns the default abilities of clients in the given game mode.
+    pub fn from_game_mode(game_mode: GameMode) -> Self {
+        let mut abilities = Self::default();
+        abilities.set_game_mode_defaults(game_mode);
+        abilities
+    }
+
+    /// Sets the abilities determined by the game mode to their defaults, like
+    /// the vanilla server does. The speeds are left unchanged.
+    pub fn set_game_mode_defaults(&mut self, game_mode: GameMode) {
+        match game_mode {
+            GameMode::Survival | GameMode::Adventure => {
+                self.invulnerable = false;
+                self.flying = false;
+                self.allow_flying = false;
+                self.instant_break = false;
+            }
+            GameMode::Creative => {
+                self.invulnerable = true;
+                self.allow_flying = true;
+                self.instant_break = true;
+            }
+            GameMode::Spectator => {
+                self.invulnerable = true;
+                self.flying = true;
+                self.allow_flying = true;
+                self.instant_break = false;
+            }
+        }
+    }
+}
+
+impl Default for PlayerAbilities {
+    fn default() -> Self {
+        Self {
+            invulnerable: false,
+            flying: false,
+            allow_flying: false,
+            instant_break: false,
+            fly_speed: 0.05,
+            walk_speed: 0.1,
+        }
+    }
+}
+
+/// Sent when a client starts or stops flying. The [`PlayerAbilities`] of the
+/// client are updated before the event is sent, unless the client is not
+/// allowed to fly.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Flying {
+    pub client: Entity,
+    pub state: FlyState,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FlyState {
+    Start,
+    Stop,
+}
+
+fn handle_update_player_abilities(
+    mut packets: EventReader<PacketEvent>,
+    mut clients: Query<&mut PlayerAbilities>,
+    mut events: EventWriter<Flying>,
+) {
+    for packet in packets.iter() {
+        if let Some(pkt) = packet.decode::<UpdatePlayerAbilitiesC2s>() {
+            let Ok(mut abilities) = clients.get_mut(packet.client) else {
+                continue
+            };
+
+            let flying = matches!(pkt, UpdatePlayerAbilitiesC2s::StartFlying);
+
+            if flying && !abilities.allow_flying {
+                // Make the client stop flying by sending the abilities again.
+                abilities.set_changed();
+            } else {
+                // The client already knows it is flying, so there is no need to
+                // send the abilities back.
+                abilities.bypass_change_detection().flying = flying;
+            }
+
+            events.send(Flying {
+                client: packet.client,
+                state: if flying {
+                    FlyState::Start
+                } else {
+                    FlyState::Stop
+                },
+            });
+        }
+    }
+}
+
+fn reset_abilities_on_game_mode_change(
+    mut clients: Query<(&GameMode, &mut PlayerAbilities), Changed<GameMode>>,
+) {
+    for (game_mode, mut abilities) in &mut clients {
+        // Abilities modified since the last tick override the defaults. The
+        // abilities of new clients are always reset, since they are only
+        // "modified" by being added.
+        if abilities.is_changed() && !abilities.is_added() {
+            continue;
+        }
+
+        abilities.set_game_mode_defaults(*game_mode);
+    }
+}
+
+/// Sends the abilities to clients when they change, or when the clients join,
+/// respawn or change game mode, since the client resets its abilities then.
+#[allow(clippy::type_complexity)]
+fn update_abilities(
+    mut clients: Query<
+        (&mut Client, &PlayerAbilities),
+        Or<(
+            Changed<PlayerAbilities>,
+            Changed<GameMode>,
+            Changed<Location>,
+        )>,
+    >,
+) {
+    for (mut client, abilities) in &mut clients {
+        client.write_packet(&PlayerAbilitiesS2c {
+            flags: PlayerAbilitiesFlags::new()
+                .with_invulnerable(abilities.invulnerable)
+                .with_flying(abilities.flying)
+                .with_allow_flying(abilities.allow_flying)
+                .with_instant_break(abilities.instant_break),
+            flying_speed: abilities.fly_speed,
+            fov_modifier: abilities.walk_speed,
+        });
+    }
+}
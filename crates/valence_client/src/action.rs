@@ -8,15 +8,33 @@ use crate::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
 
 pub(super) fn build(app: &mut App) {
     app.add_event::<Digging>()
+        .add_event::<ReleaseUseItem>()
         .add_system(
             handle_player_action
                 .in_schedule(EventLoopSchedule)
                 .in_base_set(EventLoopSet::PreUpdate),
         )
-        .add_system(acknowledge_player_actions.in_set(UpdateClientsSet));
+        .add_system(
+            acknowledge_player_actions
+                .after(read_data_in_old_view)
+                .in_set(UpdateClientsSet),
+        );
 }
 
-#[derive(Copy, Clone, Debug)]
+/// The maximum distance from the eyes of a client to the center of a block it
+/// can dig, like in vanilla.
+pub const MAX_DIGGING_DISTANCE: f64 = 6.0;
+
+/// The height of the eyes of a standing player.
+pub(crate) const EYE_HEIGHT: f64 = 1.62;
+
+/// Sent when a client starts, aborts or finishes digging a block. Digging
+/// blocks further than [`MAX_DIGGING_DISTANCE`] away is ignored.
+///
+/// The client predicts the result of digging. All actions are acknowledged at
+/// the end of the tick, which makes the client show the blocks as they are on
+/// the server, so blocks which were not broken reappear.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Digging {
     pub client: Entity,
     pub position: BlockPos,
@@ -31,6 +49,16 @@ pub enum DiggingState {
     Stop,
 }
 
+/// Sent when a client stops using the item in its hand, such as when it
+/// releases a bow. See [`InteractItem`] for when the client starts using an
+/// item.
+///
+/// [`InteractItem`]: crate::misc::InteractItem
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ReleaseUseItem {
+    pub client: Entity,
+}
+
 #[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
 pub struct ActionSequence(i32);
 
@@ -45,17 +73,38 @@ impl ActionSequence {
 }
 
 fn handle_player_action(
-    mut clients: Query<&mut ActionSequence>,
+    mut clients: Query<(&mut ActionSequence, &Position)>,
     mut packets: EventReader<PacketEvent>,
     mut digging_events: EventWriter<Digging>,
+    mut release_use_item_events: EventWriter<ReleaseUseItem>,
 ) {
     for packet in packets.iter() {
         if let Some(pkt) = packet.decode::<PlayerActionC2s>() {
-            if let Ok(mut seq) = clients.get_mut(packet.client) {
-                seq.update(pkt.sequence.0);
+            let Ok((mut seq, pos)) = clients.get_mut(packet.client) else {
+                continue
+            };
+
+            seq.update(pkt.sequence.0);
+
+            let is_digging = matches!(
+                pkt.action,
+                Action::StartDestroyBlock | Action::AbortDestroyBlock | Action::StopDestroyBlock
+            );
+
+            if is_digging {
+                let eye_pos = pos.0 + DVec3::new(0.0, EYE_HEIGHT, 0.0);
+                let block_center = DVec3::new(
+                    pkt.position.x as f64 + 0.5,
+                    pkt.position.y as f64 + 0.5,
+                    pkt.position.z as f64 + 0.5,
+                );
+
+                if eye_pos.distance_squared(block_center) > MAX_DIGGING_DISTANCE.powi(2) {
+                    // Too far away. The acknowledgement restores the block on the client.
+                    continue;
+                }
             }
 
-            // TODO: check that digging is happening within configurable distance to client.
             // TODO: check that blocks are being broken at the appropriate speeds.
 
             match pkt.action {
@@ -79,7 +128,9 @@ fn handle_player_action(
                 }),
                 Action::DropAllItems => {}
                 Action::DropItem => {}
-                Action::ReleaseUseItem => todo!(), // TODO: release use item.
+                Action::ReleaseUseItem => release_use_item_events.send(ReleaseUseItem {
+                    client: packet.client,
+                }),
                 Action::SwapItemWithOffhand => {}
             }
         }
@@ -0,0 +1,159 @@
+//! Blocks only seen by a single client.
+//!
+//! The [`BlockOverrides`] of a client replace blocks of the instance the client
+//! is in, but only for that client. This is useful for things like walls only
+//! visible to some players. Overrides are sent again whenever the chunk they
+//! are in is sent to the client or the blocks of the chunk change, so they are
+//! not reverted by the real blocks.
+//!
+//! Overrides are purely visual. The client can still walk through blocks which
+//! are not really there, but the server won't know about it.
+
+use std::collections::{HashMap, HashSet};
+
+use valence_block::BlockState;
+use valence_core::packet::s2c::play::BlockUpdateS2c;
+
+use super::*;
+
+pub(super) fn build(app: &mut App) {
+    app.add_system(
+        send_block_overrides
+            .after(update_view)
+            .after(read_data_in_old_view)
+            .in_set(UpdateClientsSet),
+    );
+}
+
+/// The blocks of the instance which look different for a client. Overrides in
+/// chunks the client can't see are sent when the chunk enters its view.
+#[derive(Component, Clone, Default, Debug)]
+pub struct BlockOverrides {
+    blocks: HashMap<BlockPos, BlockState>,
+    /// Positions which were overridden or restored since the overrides were
+    /// last sent.
+    modified: HashSet<BlockPos>,
+}
+
+impl BlockOverrides {
+    /// Returns the block shown to the client at the position instead of the
+    /// real block, if there is one.
+    pub fn get(&self, pos: impl Into<BlockPos>) -> Option<BlockState> {
+        self.blocks.get(&pos.into()).copied()
+    }
+
+    /// Shows the block to the client at the position instead of the real
+    /// block. Returns the previous override at the position.
+    pub fn set(&mut self, pos: impl Into<BlockPos>, block: BlockState) -> Option<BlockState> {
+        let pos = pos.into();
+
+        self.modified.insert(pos);
+        self.blocks.insert(pos, block)
+    }
+
+    /// Shows the real block to the client at the position again. Returns the
+    /// removed override.
+    pub fn remove(&mut self, pos: impl Into<BlockPos>) -> Option<BlockState> {
+        let pos = pos.into();
+        let old = self.blocks.remove(&pos);
+
+        if old.is_some() {
+            self.modified.insert(pos);
+        }
+
+        old
+    }
+
+    /// Shows all the real blocks to the client again.
+    pub fn clear(&mut self) {
+        self.modified.extend(self.blocks.keys().copied());
+        self.blocks.clear();
+    }
+
+    /// Returns an iterator over the overridden positions and the blocks shown
+    /// there.
+    pub fn iter(&self) -> impl Iterator<Item = (BlockPos, BlockState)> + '_ {
+        self.blocks.iter().map(|(&pos, &block)| (pos, block))
+    }
+}
+
+/// Sends the overrides which were modified, which entered the view of the
+/// client, or which are in chunks with block changes. This has to run after the
+/// chunks and block changes are sent.
+#[allow(clippy::type_complexity)]
+fn send_block_overrides(
+    mut clients: Query<(
+        &mut Client,
+        &mut BlockOverrides,
+        &Location,
+        &OldLocation,
+        &Position,
+        &OldPosition,
+        &ViewDistance,
+        &OldViewDistance,
+    )>,
+    instances: Query<&Instance>,
+) {
+    for (mut client, mut overrides, loc, old_loc, pos, old_pos, view_dist, old_view_dist) in
+        &mut clients
+    {
+        if overrides.blocks.is_empty() && overrides.modified.is_empty() {
+            continue;
+        }
+
+        let Ok(instance) = instances.get(loc.0) else {
+            continue;
+        };
+
+        let view = ChunkView::new(pos.chunk_pos(), view_dist.0);
+        let old_view = ChunkView::new(old_pos.chunk_pos(), old_view_dist.0);
+        let changed_instance = loc.0 != old_loc.get();
+
+        let modified = if overrides.modified.is_empty() {
+            HashSet::new()
+        } else {
+            std::mem::take(&mut overrides.modified)
+        };
+
+        // Restore the real blocks which are no longer overridden.
+        for &block_pos in &modified {
+            if overrides.blocks.contains_key(&block_pos) {
+                continue;
+            }
+
+            if !view.contains(ChunkPos::from_block_pos(block_pos)) {
+                continue;
+            }
+
+            if let Some(block) = instance.block(block_pos) {
+                client.write_packet(&BlockUpdateS2c {
+                    position: block_pos,
+                    block_id: VarInt(block.state().to_raw() as i32),
+                });
+            }
+        }
+
+        for (&block_pos, &block) in &overrides.blocks {
+            let chunk_pos = ChunkPos::from_block_pos(block_pos);
+
+            if !view.contains(chunk_pos) {
+                continue;
+            }
+
+            let Some(chunk) = instance.chunk(chunk_pos) else {
+                continue;
+            };
+
+            if modified.contains(&block_pos)
+                || changed_instance
+                || !old_view.contains(chunk_pos)
+                || chunk.is_block_states_changed()
+            {
+                client.write_packet(&BlockUpdateS2c {
+                    position: block_pos,
+                    block_id: VarInt(block.to_raw() as i32),
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Written books and book editing.
+//!
+//! Use [`WrittenBook`] to create a written book item, and
+//! [`Client::open_book`] to show the book held by a client. The pages written
+//! by clients in writable books are sent as [`EditBook`] events.
+
+use valence_core::item::ItemStack;
+use valence_core::packet::c2s::play::BookUpdateC2s;
+use valence_nbt::{compound, Compound, List};
+
+use super::*;
+use crate::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<EditBook>().add_system(
+        handle_book_update
+            .in_schedule(EventLoopSchedule)
+            .in_base_set(EventLoopSet::PreUpdate),
+    );
+}
+
+/// The maximum number of pages in a book edited by a client. Edits with more
+/// pages are ignored.
+pub const MAX_BOOK_PAGES: usize = 100;
+
+/// The maximum number of characters in a page of a book edited by a client.
+/// Edits with longer pages are ignored.
+pub const MAX_BOOK_PAGE_LEN: usize = 1024;
+
+/// The maximum number of characters in the title of a book signed by a client.
+/// Edits with longer titles are ignored.
+pub const MAX_BOOK_TITLE_LEN: usize = 32;
+
+/// The hotbar slot used by clients to refer to the book in their off hand.
+const OFF_HAND_SLOT: i32 = 40;
+
+/// Sent when a client edits or signs a writable book.
+///
+/// Nothing is changed in the inventory until the pages are written to the
+/// book by the server.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EditBook {
+    pub client: Entity,
+    /// The hotbar slot of the book, from 0 to 8, or 40 for the off hand.
+    pub slot: i32,
+    pub pages: Vec<String>,
+    /// The title of the book if the client signed it.
+    pub title: Option<String>,
+}
+
+fn handle_book_update(mut packets: EventReader<PacketEvent>, mut events: EventWriter<EditBook>) {
+    for packet in packets.iter() {
+        if let Some(pkt) = packet.decode::<BookUpdateC2s>() {
+            let slot = pkt.slot.0;
+
+            if !(0..=8).contains(&slot) && slot != OFF_HAND_SLOT {
+                warn!(
+                    "client {:?} edited a book in invalid slot {slot}",
+                    packet.client
+                );
+                continue;
+            }
+
+            if pkt.entries.len() > MAX_BOOK_PAGES {
+                warn!(
+                    "client {:?} sent a book with more than {MAX_BOOK_PAGES} pages",
+                    packet.client
+                );
+                continue;
+            }
+
+            if pkt
+                .entries
+                .iter()
+                .any(|page| page.chars().count() > MAX_BOOK_PAGE_LEN)
+            {
+                warn!(
+                    "client {:?} sent a book page longer than {MAX_BOOK_PAGE_LEN} characters",
+                    packet.client
+                );
+                continue;
+            }
+
+            if matches!(pkt.title, Some(title) if title.chars().count() > MAX_BOOK_TITLE_LEN) {
+                warn!(
+                    "client {:?} sent a book title longer than {MAX_BOOK_TITLE_LEN} characters",
+                    packet.client
+                );
+                continue;
+            }
+
+            events.send(EditBook {
+                client: packet.client,
+                slot,
+                pages: pkt.entries.into_iter().map(String::from).collect(),
+                title: pkt.title.map(String::from),
+            });
+        }
+    }
+}
+
+/// The contents of a written book, which can be turned into an item.
+///
+/// ```
+/// # use valence_client::book::WrittenBook;
+/// # use valence_core::item::ItemKind;
+/// let book = WrittenBook {
+///     title: "Rules".into(),
+///     author: "Server".into(),
+///     pages: vec!["Be nice.".into()],
+/// };
+///
+/// assert_eq!(book.to_item_stack().item, ItemKind::WrittenBook);
+/// ```
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct WrittenBook {
+    pub title: String,
+    pub author: String,
+    pub pages: Vec<Text>,
+}
+
+impl WrittenBook {
+    /// Returns the NBT of a written book item with the contents of the book.
+    pub fn to_nbt(&self) -> Compound {
+        compound! {
+            "title" => self.title.clone(),
+            "author" => self.author.clone(),
+            "pages" => List::String(
+                self.pages
+                    .iter()
+                    .map(|page| serde_json::to_string(page).expect("failed to jsonify text"))
+                    .collect()
+            ),
+            // Prevents the client from resolving selectors in the pages.
+            "resolved" => true,
+        }
+    }
+
+    /// Returns a written book item with the contents of the book.
+    pub fn to_item_stack(&self) -> ItemStack {
+        ItemStack::new(ItemKind::WrittenBook, 1, Some(self.to_nbt()))
+    }
+}
+
+impl From<WrittenBook> for ItemStack {
+    fn from(book: WrittenBook) -> Self {
+        book.to_item_stack()
+    }
+}
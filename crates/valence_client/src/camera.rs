@@ -0,0 +1,154 @@
+//! Spectating other entities.
+//!
+//! Set the target of the [`Camera`] of a client to show the world from the
+//! viewpoint of another entity. Spectators can also teleport to other players
+//! from the spectator menu, which is sent as a [`SpectatorTeleport`] event.
+
+use valence_core::packet::c2s::play::SpectatorTeleportC2s;
+use valence_core::packet::s2c::play::SetCameraEntityS2c;
+
+use super::*;
+use crate::command::{SneakState, Sneaking};
+use crate::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<SpectatorTeleport>()
+        .add_system(
+            handle_spectator_teleport
+                .in_schedule(EventLoopSchedule)
+                .in_base_set(EventLoopSet::PreUpdate),
+        )
+        .add_system(
+            update_camera
+                .after(update_view)
+                .after(respawn)
+                .before(update_game_mode)
+                .in_set(UpdateClientsSet),
+        );
+}
+
+/// The entity whose viewpoint a client sees the world from.
+///
+/// The camera is reset to the client when the target is despawned, leaves the
+/// view of the client, or when the client starts sneaking.
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
+pub struct Camera {
+    /// The entity being spectated, or `None` if the client sees the world
+    /// from its own viewpoint. The target needs an [`EntityId`].
+    pub target: Option<Entity>,
+    /// Whether the client is switched to [`GameMode::Spectator`] while it has
+    /// a target. The previous game mode is restored once the camera is reset
+    /// to the client.
+    pub force_spectator: bool,
+    /// The game mode to restore when the camera is reset.
+    prev_game_mode: Option<GameMode>,
+}
+
+impl Camera {
+    /// Returns a camera with the given target.
+    pub fn new(target: Option<Entity>) -> Self {
+        Self {
+            target,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the camera with [`Self::force_spectator`] set.
+    #[must_use]
+    pub fn with_force_spectator(mut self, force_spectator: bool) -> Self {
+        self.force_spectator = force_spectator;
+        self
+    }
+}
+
+/// Sent when a spectator selects a player to teleport to in the spectator
+/// menu. Nothing happens unless the server moves the client.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SpectatorTeleport {
+    pub client: Entity,
+    /// The [`UniqueId`] of the selected entity.
+    pub target: Uuid,
+}
+
+fn handle_spectator_teleport(
+    mut packets: EventReader<PacketEvent>,
+    mut events: EventWriter<SpectatorTeleport>,
+) {
+    for packet in packets.iter() {
+        if let Some(pkt) = packet.decode::<SpectatorTeleportC2s>() {
+            events.send(SpectatorTeleport {
+                client: packet.client,
+                target: pkt.target,
+            });
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_camera(
+    mut clients: Query<(
+        Entity,
+        &mut Client,
+        &mut Camera,
+        Ref<Location>,
+        View,
+        &mut GameMode,
+    )>,
+    targets: Query<(&EntityId, &Location, &Position), Without<Despawned>>,
+    mut sneaking: EventReader<Sneaking>,
+) {
+    // Sneaking leaves the viewpoint of the target, like in vanilla.
+    for event in sneaking.iter() {
+        if event.state == SneakState::Start {
+            if let Ok((_, _, mut camera, _, _, _)) = clients.get_mut(event.client) {
+                if camera.target.is_some() {
+                    camera.target = None;
+                }
+            }
+        }
+    }
+
+    for (entity, mut client, mut camera, loc, view, mut game_mode) in &mut clients {
+        let target_id = match camera.target {
+            Some(target) => match targets.get(target) {
+                Ok((id, target_loc, target_pos))
+                    if target != entity
+                        && target_loc.0 == loc.0
+                        && view.get().contains(target_pos.chunk_pos()) =>
+                {
+                    Some(id.get())
+                }
+                _ => {
+                    camera.target = None;
+                    None
+                }
+            },
+            None => None,
+        };
+
+        // The client resets its camera when it respawns.
+        let send = if loc.is_changed() {
+            target_id.is_some()
+        } else {
+            camera.is_changed()
+        };
+
+        if send {
+            client.write_packet(&SetCameraEntityS2c {
+                // The client refers to itself with an entity ID of 0.
+                entity_id: VarInt(target_id.unwrap_or(0)),
+            });
+        }
+
+        if target_id.is_some() {
+            if camera.force_spectator && *game_mode != GameMode::Spectator {
+                camera.prev_game_mode = Some(*game_mode);
+                *game_mode = GameMode::Spectator;
+            }
+        } else if let Some(prev) = camera.prev_game_mode {
+            // Avoids marking the camera as changed when there is nothing to restore.
+            camera.prev_game_mode = None;
+            *game_mode = prev;
+        }
+    }
+}
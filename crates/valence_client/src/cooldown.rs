@@ -0,0 +1,91 @@
+//! Item cooldowns.
+//!
+//! Attach a [`Cooldowns`] to a client to keep track of the cooldowns of its
+//! items on the server. The cooldowns are shown on the client as an overlay
+//! over the items, and are sent again when the client respawns, since the
+//! client forgets them.
+
+use std::collections::BTreeMap;
+
+use super::*;
+
+pub(super) fn build(app: &mut App) {
+    app.add_system(update_cooldowns.after(respawn).in_set(UpdateClientsSet));
+}
+
+/// The remaining cooldowns of the items of a client, in ticks. Cooldowns
+/// prevent the client from using the items until they expire.
+///
+/// The cooldowns are decremented every tick. Cooldowns set with
+/// [`Client::set_item_cooldown`] are not tracked by this component.
+#[derive(Component, Clone, Default, Debug)]
+pub struct Cooldowns {
+    remaining: BTreeMap<ItemKind, i32>,
+    /// The items whose cooldown was set since the last tick.
+    changed: Vec<ItemKind>,
+}
+
+impl Cooldowns {
+    /// Returns the number of ticks before the cooldown of the item expires,
+    /// or `0` if the item has no cooldown.
+    pub fn remaining(&self, item: ItemKind) -> i32 {
+        self.remaining.get(&item).copied().unwrap_or(0)
+    }
+
+    /// Returns `true` if the item has a cooldown which has not expired.
+    pub fn is_cooling_down(&self, item: ItemKind) -> bool {
+        self.remaining(item) > 0
+    }
+
+    /// Sets the cooldown of the item to the given number of ticks. A
+    /// nonpositive number of ticks removes the cooldown.
+    pub fn set(&mut self, item: ItemKind, ticks: i32) {
+        if ticks > 0 {
+            self.remaining.insert(item, ticks);
+        } else {
+            self.remaining.remove(&item);
+        }
+
+        self.changed.push(item);
+    }
+
+    /// Removes the cooldowns of all items.
+    pub fn clear(&mut self) {
+        self.changed.extend(self.remaining.keys());
+        self.remaining.clear();
+    }
+
+    /// Returns an iterator over the items which have a cooldown and the
+    /// number of ticks remaining.
+    pub fn iter(&self) -> impl Iterator<Item = (ItemKind, i32)> + '_ {
+        self.remaining.iter().map(|(&item, &ticks)| (item, ticks))
+    }
+}
+
+fn update_cooldowns(mut clients: Query<(&mut Client, &mut Cooldowns, Ref<Location>)>) {
+    for (mut client, mut cooldowns, loc) in &mut clients {
+        if cooldowns.remaining.is_empty() && cooldowns.changed.is_empty() {
+            continue;
+        }
+
+        let cooldowns = cooldowns.as_mut();
+
+        // The client forgets its cooldowns when it joins or respawns.
+        if loc.is_changed() {
+            for (&item, &ticks) in &cooldowns.remaining {
+                client.set_item_cooldown(item, ticks);
+            }
+        } else {
+            for &item in &cooldowns.changed {
+                client.set_item_cooldown(item, cooldowns.remaining(item));
+            }
+        }
+
+        cooldowns.changed.clear();
+
+        cooldowns.remaining.retain(|_, ticks| {
+            *ticks -= 1;
+            *ticks > 0
+        });
+    }
+}
@@ -0,0 +1,126 @@
+//! Deaths and respawns.
+//!
+//! A client dies when a [`KillClient`] event is sent for it, or when the
+//! [`Health`] of its player entity drops to `0.0`. The client is shown the
+//! death screen, and the player entity plays the death animation for the
+//! other clients.
+//!
+//! Clients pressing the respawn button are sent as
+//! [`Respawn`](crate::misc::Respawn) events. Use [`RespawnClient`] to respawn a
+//! client, which also restores its health.
+
+use valence_entity::living::Health;
+use valence_entity::EntityStatuses;
+
+use super::*;
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<KillClient>().add_system(
+        kill_clients
+            .before(respawn)
+            .before(UpdateTrackedDataSet)
+            .in_set(UpdateClientsSet),
+    );
+}
+
+/// An event which kills a client and shows `message` on its death screen.
+/// Clients which are already dead are left as they are.
+#[derive(Clone, PartialEq, Debug)]
+pub struct KillClient {
+    pub client: Entity,
+    /// The entity which killed the client, if any. It needs an [`EntityId`].
+    pub killer: Option<Entity>,
+    pub message: Text,
+}
+
+/// A [`Command`] to respawn a client at a position in an instance, which can
+/// be the instance the client is already in.
+///
+/// The health of the client is restored if it was dead.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RespawnClient {
+    pub client: Entity,
+    /// The instance to respawn in.
+    pub instance: Entity,
+    pub position: DVec3,
+}
+
+impl Command for RespawnClient {
+    fn write(self, world: &mut World) {
+        let Some(mut entity) = world.get_entity_mut(self.client) else {
+            return
+        };
+
+        // Setting the location marks it as changed even if the instance is the
+        // same, which sends the respawn packet.
+        if let Some(mut loc) = entity.get_mut::<Location>() {
+            loc.0 = self.instance;
+        }
+
+        if let Some(mut pos) = entity.get_mut::<Position>() {
+            pos.set(self.position);
+        }
+    }
+}
+
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+struct KillQuery {
+    client: &'static mut Client,
+    health: &'static mut Health,
+    pose: &'static mut entity::Pose,
+    statuses: &'static mut EntityStatuses,
+    death_loc: &'static mut DeathLocation,
+    loc: &'static Location,
+    pos: &'static Position,
+}
+
+fn kill_clients(
+    mut events: EventReader<KillClient>,
+    mut clients: Query<KillQuery>,
+    entity_ids: Query<&EntityId>,
+    instances: Query<&Instance>,
+) {
+    for event in events.iter() {
+        if let Ok(mut q) = clients.get_mut(event.client) {
+            let killer = event.killer.and_then(|e| entity_ids.get(e).ok()).copied();
+            kill(&mut q, killer, event.message.clone(), &instances);
+        }
+    }
+
+    // Clients whose health dropped to zero without an event die too.
+    for mut q in &mut clients {
+        if q.health.is_changed() && q.health.0 <= 0.0 {
+            kill(&mut q, None, Text::default(), &instances);
+        }
+    }
+}
+
+fn kill(
+    q: &mut KillQueryItem<'_>,
+    killer: Option<EntityId>,
+    message: Text,
+    instances: &Query<&Instance>,
+) {
+    if q.pose.0 == Pose::Dying {
+        // Already dead.
+        return;
+    }
+
+    if q.health.0 > 0.0 {
+        q.health.0 = 0.0;
+    }
+
+    q.pose.0 = Pose::Dying;
+    q.statuses
+        .trigger(EntityStatus::PlayDeathSoundOrAddProjectileHitParticles);
+
+    if let Ok(instance) = instances.get(q.loc.0) {
+        q.death_loc.0 = Some((
+            instance.dimension_type_name().to_string_ident(),
+            BlockPos::at(q.pos.0),
+        ));
+    }
+
+    q.client.kill(killer, message);
+}
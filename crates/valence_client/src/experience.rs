@@ -0,0 +1,151 @@
+//! Experience of clients.
+//!
+//! The experience bar of a client shows its [`ExperienceLevel`] and
+//! [`ExperienceProgress`], which are sent whenever they change. Use
+//! [`ExperienceQuery`] to add experience points the way vanilla does.
+
+use valence_core::packet::s2c::play::ExperienceBarUpdateS2c;
+
+use super::*;
+
+pub(super) fn build(app: &mut App) {
+    app.add_system(
+        update_experience_bar
+            .after(respawn)
+            .in_set(UpdateClientsSet),
+    );
+}
+
+/// The experience level of a client, shown above the experience bar.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ExperienceLevel(pub i32);
+
+/// The fill of the experience bar of a client, from `0.0` to `1.0`.
+#[derive(Component, Copy, Clone, PartialEq, Default, Debug)]
+pub struct ExperienceProgress(pub f32);
+
+/// The total number of experience points collected by a client. The client
+/// shows it on the death screen.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct TotalExperience(pub i32);
+
+/// Returns the number of experience points needed to go from the given level
+/// to the next one.
+pub fn points_to_next_level(level: i32) -> i32 {
+    if level >= 30 {
+        112 + (level - 30) * 9
+    } else if level >= 15 {
+        37 + (level - 15) * 5
+    } else {
+        7 + level * 2
+    }
+}
+
+/// Returns the number of experience points needed to reach the given level
+/// from level 0.
+pub fn points_for_level(level: i32) -> i32 {
+    let level = level.max(0);
+
+    // The formulas of the vanilla wiki, multiplied by 2 to stay in integers.
+    let double = if level <= 16 {
+        2 * level * level + 12 * level
+    } else if level <= 31 {
+        5 * level * level - 81 * level + 720
+    } else {
+        9 * level * level - 325 * level + 4440
+    };
+
+    double / 2
+}
+
+/// The experience of a client, for adding experience points.
+///
+/// ```
+/// # use bevy_ecs::prelude::*;
+/// # use valence_client::experience::ExperienceQuery;
+/// fn reward_clients(mut clients: Query<ExperienceQuery>) {
+///     for mut exp in &mut clients {
+///         exp.add_points(10);
+///     }
+/// }
+/// ```
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct ExperienceQuery {
+    pub level: &'static mut ExperienceLevel,
+    pub progress: &'static mut ExperienceProgress,
+    pub total: &'static mut TotalExperience,
+}
+
+impl ExperienceQueryItem<'_> {
+    /// Adds the given number of experience points, moving to other levels as
+    /// needed. Negative numbers remove points, down to level 0.
+    pub fn add_points(&mut self, points: i32) {
+        let mut level = self.level.0.max(0);
+        // Counting in points instead of progress keeps whole levels exact.
+        let mut points_in_level =
+            f64::from(self.progress.0) * f64::from(points_to_next_level(level)) + f64::from(points);
+
+        self.total.0 = self.total.0.saturating_add(points).max(0);
+
+        while points_in_level < 0.0 {
+            if level == 0 {
+                points_in_level = 0.0;
+                self.total.0 = 0;
+                break;
+            }
+
+            level -= 1;
+            points_in_level += f64::from(points_to_next_level(level));
+        }
+
+        while points_in_level >= f64::from(points_to_next_level(level)) {
+            points_in_level -= f64::from(points_to_next_level(level));
+            level += 1;
+        }
+
+        self.level.0 = level;
+        self.progress.0 = (points_in_level / f64::from(points_to_next_level(level))) as f32;
+    }
+
+    /// Adds the given number of levels, keeping the progress in the current
+    /// level. Like in vanilla, removing more levels than the client has resets
+    /// its experience, and the total is left alone otherwise.
+    pub fn add_levels(&mut self, levels: i32) {
+        self.level.0 = self.level.0.saturating_add(levels);
+
+        if self.level.0 < 0 {
+            self.level.0 = 0;
+            self.progress.0 = 0.0;
+            self.total.0 = 0;
+        }
+    }
+}
+
+/// Sends the experience bar to clients when it changes, or when the clients
+/// join or respawn.
+#[allow(clippy::type_complexity)]
+fn update_experience_bar(
+    mut clients: Query<
+        (
+            &mut Client,
+            &ExperienceLevel,
+            &ExperienceProgress,
+            &TotalExperience,
+        ),
+        Or<(
+            Changed<ExperienceLevel>,
+            Changed<ExperienceProgress>,
+            Changed<TotalExperience>,
+            Changed<Location>,
+        )>,
+    >,
+) {
+    for (mut client, level, progress, total) in &mut clients {
+        client.write_packet(&ExperienceBarUpdateS2c {
+            bar: progress.0.clamp(0.0, 1.0),
+            level: VarInt(level.0),
+            total_xp: VarInt(total.0),
+        });
+    }
+}
@@ -0,0 +1,61 @@
+//! Health and hunger of clients.
+//!
+//! The health bar of a client shows the [`Health`] of its player entity, and
+//! the hunger bar shows its [`Food`] and [`Saturation`]. The three are sent
+//! together whenever any of them changes.
+
+use valence_core::packet::s2c::play::HealthUpdateS2c;
+use valence_entity::living::Health;
+
+use super::*;
+
+pub(super) fn build(app: &mut App) {
+    app.add_system(update_health.after(respawn).in_set(UpdateClientsSet));
+}
+
+/// The food level of a client, from `0` to `20`. Defaults to `20`.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Food(pub i32);
+
+impl Default for Food {
+    fn default() -> Self {
+        Self(20)
+    }
+}
+
+/// The food saturation of a client, from `0.0` to the [`Food`] level. Defaults
+/// to `5.0`.
+///
+/// The saturation is not shown to the client, but keeps the hunger bar from
+/// shaking when it is above `0.0`.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct Saturation(pub f32);
+
+impl Default for Saturation {
+    fn default() -> Self {
+        Self(5.0)
+    }
+}
+
+/// Sends the health, food and saturation to clients in a single packet when
+/// any of them changes, or when the clients join or respawn.
+#[allow(clippy::type_complexity)]
+fn update_health(
+    mut clients: Query<
+        (&mut Client, &Health, &Food, &Saturation),
+        Or<(
+            Changed<Health>,
+            Changed<Food>,
+            Changed<Saturation>,
+            Changed<Location>,
+        )>,
+    >,
+) {
+    for (mut client, health, food, saturation) in &mut clients {
+        client.write_packet(&HealthUpdateS2c {
+            health: health.0,
+            food: VarInt(food.0),
+            food_saturation: saturation.0,
+        });
+    }
+}
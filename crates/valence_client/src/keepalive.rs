@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use valence_core::packet::c2s::play::KeepAliveC2s;
 use valence_core::CoreSettings;
 
@@ -5,7 +7,8 @@ use super::*;
 use crate::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
 
 pub(super) fn build(app: &mut App) {
-    app.add_system(send_keepalive.in_set(UpdateClientsSet))
+    app.init_resource::<KeepaliveSettings>()
+        .add_system(send_keepalive.in_set(UpdateClientsSet))
         .add_system(
             handle_keepalive_response
                 .in_base_set(EventLoopSet::PreUpdate)
@@ -13,6 +16,32 @@ pub(super) fn build(app: &mut App) {
         );
 }
 
+#[derive(Resource, Clone, Debug)]
+pub struct KeepaliveSettings {
+    /// The time between keepalive packets. Rounded to a whole number of ticks.
+    /// Defaults to 10 seconds.
+    pub period: Duration,
+    /// Clients which have not responded to a keepalive packet after this
+    /// amount of time are disconnected. No new keepalive packet is sent to a
+    /// client while it has not responded to the previous one. Defaults to 10
+    /// seconds.
+    pub timeout: Duration,
+    /// If the [`Ping`] of clients is set to the round trip time of keepalive
+    /// packets. Disable this to control the latency displayed in the player
+    /// list manually. Enabled by default.
+    pub update_ping: bool,
+}
+
+impl Default for KeepaliveSettings {
+    fn default() -> Self {
+        Self {
+            period: Duration::from_secs(10),
+            timeout: Duration::from_secs(10),
+            update_ping: true,
+        }
+    }
+}
+
 #[derive(Component, Debug)]
 pub struct KeepaliveState {
     got_keepalive: bool,
@@ -33,14 +62,27 @@ impl KeepaliveState {
 fn send_keepalive(
     mut clients: Query<(Entity, &mut Client, &mut KeepaliveState)>,
     server: Res<Server>,
-    settings: Res<CoreSettings>,
+    core_settings: Res<CoreSettings>,
+    settings: Res<KeepaliveSettings>,
     mut commands: Commands,
 ) {
-    if server.current_tick() % (settings.tick_rate.get() * 10) as i64 == 0 {
+    let now = Instant::now();
+
+    for (entity, _, state) in &clients {
+        if !state.got_keepalive && now.duration_since(state.keepalive_sent_time) >= settings.timeout
+        {
+            warn!("Client {entity:?} timed out (no keepalive response)");
+            commands.entity(entity).remove::<Client>();
+        }
+    }
+
+    let period_ticks =
+        (settings.period.as_secs_f64() * core_settings.tick_rate.get() as f64).round() as i64;
+
+    if server.current_tick() % period_ticks.max(1) == 0 {
         let mut rng = rand::thread_rng();
-        let now = Instant::now();
 
-        for (entity, mut client, mut state) in &mut clients {
+        for (_, mut client, mut state) in &mut clients {
             if state.got_keepalive {
                 let id = rng.gen();
                 client.write_packet(&KeepAliveS2c { id });
@@ -48,9 +90,6 @@ fn send_keepalive(
                 state.got_keepalive = false;
                 state.last_keepalive_id = id;
                 state.keepalive_sent_time = now;
-            } else {
-                warn!("Client {entity:?} timed out (no keepalive response)");
-                commands.entity(entity).remove::<Client>();
             }
         }
     }
@@ -59,6 +98,7 @@ fn send_keepalive(
 fn handle_keepalive_response(
     mut packets: EventReader<PacketEvent>,
     mut clients: Query<(Entity, &mut KeepaliveState, &mut Ping)>,
+    settings: Res<KeepaliveSettings>,
     mut commands: Commands,
 ) {
     for packet in packets.iter() {
@@ -75,7 +115,17 @@ fn handle_keepalive_response(
                     commands.entity(client).remove::<Client>();
                 } else {
                     state.got_keepalive = true;
-                    ping.0 = state.keepalive_sent_time.elapsed().as_millis() as i32;
+
+                    if settings.update_ping {
+                        let rtt = state.keepalive_sent_time.elapsed().as_millis() as i32;
+
+                        // Smooth out the latency like the vanilla server.
+                        ping.0 = if ping.0 < 0 {
+                            rtt
+                        } else {
+                            (ping.0 * 3 + rtt) / 4
+                        };
+                    }
                 }
             }
         }
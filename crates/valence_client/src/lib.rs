@@ -20,7 +20,7 @@
 use std::borrow::Cow;
 use std::fmt;
 use std::net::IpAddr;
-use std::ops::Deref;
+use std::ops::{Deref, DerefMut};
 use std::time::Instant;
 
 use bevy_app::prelude::*;
@@ -30,54 +30,78 @@ use bevy_ecs::system::Command;
 use bytes::{Bytes, BytesMut};
 use glam::{DVec3, Vec3};
 use rand::Rng;
-use tracing::warn;
+use tracing::{debug, error, warn};
 use uuid::Uuid;
 use valence_biome::BiomeRegistry;
 use valence_core::block_pos::BlockPos;
+use valence_core::chat::{write_player_chat, ChatSender, ChatType};
 use valence_core::chunk_pos::{ChunkPos, ChunkView};
 use valence_core::despawn::Despawned;
 use valence_core::game_mode::GameMode;
+use valence_core::hand::Hand;
 use valence_core::ident::Ident;
+use valence_core::item::ItemKind;
 use valence_core::packet::byte_angle::ByteAngle;
-use valence_core::packet::encode::{PacketEncoder, WritePacket};
+use valence_core::packet::encode::{CachedPacket, PacketEncoder, WritePacket};
 use valence_core::packet::global_pos::GlobalPos;
-use valence_core::packet::s2c::play::game_state_change::GameEventKind;
+use valence_core::packet::s2c::play::command_suggestions::Match;
+use valence_core::packet::s2c::play::game_state_change::{DemoEvent, GameEventKind};
 use valence_core::packet::s2c::play::particle::Particle;
+use valence_core::packet::s2c::play::play_sound::SoundId;
 use valence_core::packet::s2c::play::player_position_look::Flags as PlayerPositionLookFlags;
 use valence_core::packet::s2c::play::{
-    ChunkLoadDistanceS2c, ChunkRenderDistanceCenterS2c, CustomPayloadS2c, DeathMessageS2c,
-    DisconnectS2c, EntitiesDestroyS2c, EntitySetHeadYawS2c, EntitySpawnS2c, EntityStatusS2c,
+    ChunkLoadDistanceS2c, ChunkRenderDistanceCenterS2c, ClearTitleS2c, CommandSuggestionsS2c,
+    CooldownUpdateS2c, CustomPayloadS2c, DeathMessageS2c, DisconnectS2c, EntitiesDestroyS2c,
+    EntityPositionS2c, EntitySetHeadYawS2c, EntitySpawnS2c, EntityStatusS2c,
     EntityTrackerUpdateS2c, EntityVelocityUpdateS2c, ExperienceOrbSpawnS2c, GameJoinS2c,
-    GameMessageS2c, GameStateChangeS2c, KeepAliveS2c, OverlayMessageS2c, ParticleS2c, PlaySoundS2c,
-    PlayerActionResponseS2c, PlayerPositionLookS2c, PlayerRespawnS2c, PlayerSpawnPositionS2c,
-    PlayerSpawnS2c, ResourcePackSendS2c, SubtitleS2c, TitleFadeS2c, TitleS2c, UnloadChunkS2c,
+    GameMessageS2c, GameStateChangeS2c, KeepAliveS2c, OpenWrittenBookS2c, OverlayMessageS2c,
+    ParticleS2c, PlaySoundS2c, PlayerActionResponseS2c, PlayerPositionLookS2c, PlayerRespawnS2c,
+    PlayerSpawnPositionS2c, PlayerSpawnS2c, ResourcePackSendS2c, SignEditorOpenS2c, StopSoundS2c,
+    SubtitleS2c, TitleFadeS2c, TitleS2c, UnloadChunkS2c,
 };
 use valence_core::packet::var_int::VarInt;
 use valence_core::packet::Packet;
 use valence_core::property::Property;
 use valence_core::scratch::ScratchBuf;
-use valence_core::sound::{Sound, SoundCategory};
+use valence_core::sound::SoundCategory;
 use valence_core::text::Text;
 use valence_core::uuid::UniqueId;
 use valence_core::Server;
 use valence_entity::player::PlayerEntityBundle;
 use valence_entity::{
-    ClearEntityChangesSet, EntityId, EntityKind, EntityStatus, HeadYaw, Location, Look, ObjectData,
-    OldLocation, OldPosition, OnGround, PacketByteRange, Position, TrackedData, Velocity,
+    entity, living, ClearEntityChangesSet, EntityId, EntityKind, EntityStatus, HeadYaw, Location,
+    Look, ObjectData, OldLocation, OldPosition, OnGround, PacketByteRange, Pose, Position,
+    TrackedData, UpdateTrackedDataSet, Velocity,
 };
 use valence_instance::{ClearInstanceChangesSet, Instance, WriteUpdatePacketsToInstancesSet};
 use valence_registry::{RegistryCodec, RegistryCodecSet};
 
+pub mod abilities;
 pub mod action;
+pub mod block_override;
+pub mod book;
+pub mod camera;
 pub mod command;
+pub mod cooldown;
+pub mod death;
 pub mod event_loop;
+pub mod experience;
+pub mod health;
 pub mod interact_entity;
 pub mod keepalive;
 pub mod misc;
 pub mod movement;
+pub mod resource_pack;
 pub mod settings;
+pub mod sign;
+pub mod statistics;
+pub mod tags;
 pub mod teleport;
+pub mod transfer;
+pub mod vehicle;
 pub mod weather;
+pub mod world_border;
+pub mod world_time;
 
 pub struct ClientPlugin;
 
@@ -95,27 +119,36 @@ pub struct FlushPacketsSet;
 
 pub struct SpawnClientsSet;
 
+/// The [`SystemSet`] in [`CoreSet::PostUpdate`] where clients are joined,
+/// respawned and sent the changes to their components. Systems sending packets
+/// which need to arrive after a respawn should run _after_ this set.
 #[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
-struct UpdateClientsSet;
+pub struct UpdateClientsSet;
 
 impl Plugin for ClientPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             (
                 initial_join.after(RegistryCodecSet),
+                warn_stale_registry_codec.after(RegistryCodecSet),
+                update_view_dist
+                    .before(initial_join)
+                    .before(update_chunk_load_dist),
                 update_chunk_load_dist,
                 read_data_in_old_view
                     .after(WriteUpdatePacketsToInstancesSet)
                     .after(update_chunk_load_dist),
-                update_view.after(initial_join).after(read_data_in_old_view),
-                respawn.after(update_view),
+                // Respawning clears the world of the client when the dimension changes, so
+                // the new view has to be sent after.
+                respawn.after(initial_join).before(UpdateTrackedDataSet),
+                update_view.after(respawn).after(read_data_in_old_view),
                 remove_entities.after(update_view),
                 update_spawn_position.after(update_view),
                 update_old_view_dist.after(update_view),
                 update_game_mode,
                 update_tracked_data.after(WriteUpdatePacketsToInstancesSet),
                 init_tracked_data.after(WriteUpdatePacketsToInstancesSet),
-                update_op_level,
+                update_op_level.after(respawn),
             )
                 .in_set(UpdateClientsSet),
         )
@@ -128,18 +161,41 @@ impl Plugin for ClientPlugin {
             FlushPacketsSet.in_base_set(CoreSet::PostUpdate),
             ClearInstanceChangesSet.after(FlushPacketsSet),
         ))
-        .add_system(flush_packets.in_set(FlushPacketsSet));
+        .add_systems(
+            (
+                flush_packets,
+                update_network_stats.after(flush_packets),
+                update_degraded_clients.after(update_network_stats),
+            )
+                .in_set(FlushPacketsSet),
+        );
 
         event_loop::build(app);
         movement::build(app);
         command::build(app);
+        cooldown::build(app);
         keepalive::build(app);
         interact_entity::build(app);
         settings::build(app);
+        sign::build(app);
         misc::build(app);
+        resource_pack::build(app);
         action::build(app);
+        block_override::build(app);
+        book::build(app);
+        camera::build(app);
+        abilities::build(app);
+        death::build(app);
+        experience::build(app);
+        health::build(app);
+        statistics::build(app);
+        tags::build(app);
         teleport::build(app);
+        transfer::build(app);
+        vehicle::build(app);
         weather::build(app);
+        world_border::build(app);
+        world_time::build(app);
     }
 }
 
@@ -155,11 +211,21 @@ pub struct ClientBundle {
     pub ip: Ip,
     pub properties: Properties,
     pub compass_pos: CompassPos,
+    pub camera: camera::Camera,
     pub game_mode: GameMode,
+    pub abilities: abilities::PlayerAbilities,
+    pub experience_level: experience::ExperienceLevel,
+    pub experience_progress: experience::ExperienceProgress,
+    pub total_experience: experience::TotalExperience,
+    pub food: health::Food,
+    pub saturation: health::Saturation,
+    pub statistics: statistics::Statistics,
+    pub block_overrides: block_override::BlockOverrides,
     pub op_level: OpLevel,
     pub action_sequence: action::ActionSequence,
     pub view_distance: ViewDistance,
     pub old_view_distance: OldViewDistance,
+    pub max_view_distance: MaxViewDistance,
     pub death_location: DeathLocation,
     pub keepalive_state: keepalive::KeepaliveState,
     pub ping: Ping,
@@ -171,6 +237,8 @@ pub struct ClientBundle {
     pub is_debug: IsDebug,
     pub is_flat: IsFlat,
     pub teleport_state: teleport::TeleportState,
+    pub network_stats: NetworkStats,
+    pub send_queue_limit: SendQueueLimit,
     pub player: PlayerEntityBundle,
 }
 
@@ -180,6 +248,9 @@ impl ClientBundle {
             client: Client {
                 conn: args.conn,
                 enc: args.enc,
+                pending_title: None,
+                forced_resource_pack: false,
+                pending_transfer: None,
             },
             settings: settings::ClientSettings::default(),
             scratch: ScratchBuf::default(),
@@ -188,15 +259,27 @@ impl ClientBundle {
             ip: Ip(args.ip),
             properties: Properties(args.properties),
             compass_pos: CompassPos::default(),
+            camera: camera::Camera::default(),
             game_mode: GameMode::default(),
+            abilities: abilities::PlayerAbilities::default(),
+            experience_level: experience::ExperienceLevel::default(),
+            experience_progress: experience::ExperienceProgress::default(),
+            total_experience: experience::TotalExperience::default(),
+            food: health::Food::default(),
+            saturation: health::Saturation::default(),
+            statistics: statistics::Statistics::default(),
+            block_overrides: block_override::BlockOverrides::default(),
             op_level: OpLevel::default(),
             action_sequence: action::ActionSequence::default(),
             view_distance: ViewDistance::default(),
             old_view_distance: OldViewDistance(2),
+            max_view_distance: MaxViewDistance::default(),
             death_location: DeathLocation::default(),
             keepalive_state: keepalive::KeepaliveState::new(),
             ping: Ping::default(),
             teleport_state: teleport::TeleportState::new(),
+            network_stats: NetworkStats::default(),
+            send_queue_limit: args.send_queue_limit,
             is_hardcore: IsHardcore::default(),
             is_flat: IsFlat::default(),
             has_respawn_screen: HasRespawnScreen::default(),
@@ -222,6 +305,7 @@ pub struct ClientBundleArgs {
     pub conn: Box<dyn ClientConnection>,
     /// The packet encoder to use. This should be in sync with [`Self::conn`].
     pub enc: PacketEncoder,
+    pub send_queue_limit: SendQueueLimit,
 }
 
 /// The main client component. Contains the underlying network connection and
@@ -233,6 +317,15 @@ pub struct ClientBundleArgs {
 pub struct Client {
     conn: Box<dyn ClientConnection>,
     enc: PacketEncoder,
+    /// The title written after the other packets of the tick. See
+    /// [`Self::set_title`].
+    pending_title: Option<Text>,
+    /// If the last resource pack sent to the client is required. See
+    /// [`Self::set_resource_pack`].
+    forced_resource_pack: bool,
+    /// The transfer applied at the end of the update. See
+    /// [`Self::transfer_to`].
+    pending_transfer: Option<transfer::PendingTransfer>,
 }
 
 /// Represents the bidirectional packet channel between the server and a client
@@ -251,8 +344,117 @@ pub trait ClientConnection: Send + Sync + 'static {
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Returns counters of the data received from the client so far. See
+    /// [`NetworkStats`].
+    fn received_stats(&self) -> ReceivedStats {
+        ReceivedStats::default()
+    }
+
+    /// The number of bytes passed to [`Self::try_send`] which were not sent
+    /// yet.
+    fn send_queue_len(&self) -> usize {
+        0
+    }
+}
+
+/// Counters of the data received by a [`ClientConnection`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ReceivedStats {
+    /// The number of bytes received, before decryption and decompression.
+    pub bytes: u64,
+    /// The total size of the received packets after decompression, excluding
+    /// the packet length prefixes.
+    pub uncompressed_bytes: u64,
+    /// The number of packets received.
+    pub packets: u64,
+}
+
+/// Counters of the data sent to and received from a client, updated at the
+/// end of every tick in [`FlushPacketsSet`].
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct NetworkStats {
+    /// The number of bytes sent to the client, after compression and
+    /// encryption.
+    pub bytes_sent: u64,
+    /// The number of bytes received from the client, before decryption and
+    /// decompression.
+    pub bytes_received: u64,
+    /// The total size of the packets sent to the client before compression.
+    pub uncompressed_bytes_sent: u64,
+    /// The total size of the packets received from the client after
+    /// decompression.
+    pub uncompressed_bytes_received: u64,
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    /// The number of bytes waiting to be sent to the client at the end of the
+    /// tick. A queue which keeps growing means the connection of the client
+    /// cannot keep up.
+    pub send_queue_len: usize,
+}
+
+impl NetworkStats {
+    /// Returns the size of the sent packets before compression divided by the
+    /// number of bytes sent, or `1.0` if nothing was sent.
+    pub fn compression_ratio(&self) -> f64 {
+        if self.bytes_sent == 0 {
+            1.0
+        } else {
+            self.uncompressed_bytes_sent as f64 / self.bytes_sent as f64
+        }
+    }
+}
+
+/// Limits the number of bytes waiting to be sent to a client. The send queue
+/// grows when the connection of the client cannot keep up with the packets
+/// sent to it, or when the client stopped reading them.
+///
+/// The limit cannot be raised above the limit of the [`ClientConnection`]
+/// itself, which disconnects the client without a message.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SendQueueLimit {
+    /// The maximum number of bytes in the send queue. A client whose queue
+    /// would grow past this limit is disconnected with a "Connection
+    /// overloaded" message, and the pending packets are dropped.
+    pub max_bytes: usize,
+    /// How to handle a client before it reaches the limit.
+    pub policy: OverloadPolicy,
 }
 
+impl Default for SendQueueLimit {
+    fn default() -> Self {
+        Self {
+            max_bytes: 8388608, // 8 MiB
+            policy: OverloadPolicy::default(),
+        }
+    }
+}
+
+/// See [`SendQueueLimit::policy`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum OverloadPolicy {
+    /// The client is left alone until its send queue reaches
+    /// [`SendQueueLimit::max_bytes`].
+    #[default]
+    Disconnect,
+    /// The client enters degraded mode once its send queue grows past
+    /// `threshold` bytes, and stays in it until the queue is drained. Clients
+    /// in degraded mode have the [`Degraded`] component and do not receive
+    /// non-essential packets broadcast by their instance: particles, sounds
+    /// and the movement of entities further than `entity_distance` chunks
+    /// away. The position of these entities is synchronized again when the
+    /// client leaves degraded mode.
+    Degrade {
+        threshold: usize,
+        entity_distance: u8,
+    },
+}
+
+/// Marker component for clients in degraded mode. See
+/// [`OverloadPolicy::Degrade`].
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct Degraded;
+
 #[derive(Clone, Debug)]
 pub struct ReceivedPacket {
     /// The moment in time this packet arrived. This is _not_ the instant this
@@ -282,6 +484,38 @@ impl WritePacket for Client {
     }
 }
 
+/// Writes a packet which is identical for all the given clients. Unlike calling
+/// [`WritePacket::write_packet`] on every client, the packet is only encoded
+/// once. See [`CachedPacket`].
+///
+/// Nothing is encoded if there are no clients.
+pub fn broadcast_packet_cached<'a, C>(
+    packet: &impl Packet<'a>,
+    clients: impl IntoIterator<Item = C>,
+) where
+    C: DerefMut<Target = Client>,
+{
+    let mut clients = clients.into_iter().peekable();
+
+    if clients.peek().is_none() {
+        return;
+    }
+
+    let mut cached = match CachedPacket::new(packet) {
+        Ok(cached) => cached,
+        Err(e) => {
+            warn!("failed to write packet: {e:#}");
+            return;
+        }
+    };
+
+    for mut client in clients {
+        if let Err(e) = client.enc.append_cached_packet(&mut cached) {
+            warn!("failed to write packet: {e:#}");
+        }
+    }
+}
+
 impl Client {
     pub fn connection(&self) -> &dyn ClientConnection {
         self.conn.as_ref()
@@ -291,6 +525,13 @@ impl Client {
         self.conn.as_mut()
     }
 
+    /// Returns the number of bytes waiting to be sent to the client, including
+    /// the packets written this tick which were not flushed yet. See
+    /// [`SendQueueLimit`].
+    pub fn send_queue_len(&self) -> usize {
+        self.conn.send_queue_len() + self.enc.len()
+    }
+
     /// Flushes the packet queue to the underlying connection.
     ///
     /// This is called automatically at the end of the tick and when the client
@@ -299,6 +540,12 @@ impl Client {
     ///
     /// Returns an error if flushing was unsuccessful.
     pub fn flush_packets(&mut self) -> anyhow::Result<()> {
+        if let Some(title) = self.pending_title.take() {
+            self.enc.write_packet(&TitleS2c {
+                title_text: title.into(),
+            });
+        }
+
         let bytes = self.enc.take();
         if !bytes.is_empty() {
             self.conn.try_send(bytes)
@@ -308,14 +555,46 @@ impl Client {
     }
 
     /// Sends a system message to the player which is visible in the chat. The
-    /// message is only visible to this client.
+    /// message is only visible to this client. Same as
+    /// [`Self::send_system_message`].
     pub fn send_message(&mut self, msg: impl Into<Text>) {
+        self.send_system_message(msg);
+    }
+
+    /// Sends a system message to the player which is visible in the chat.
+    /// System messages are not attributed to a player, so they are never
+    /// hidden by the chat settings of the client.
+    pub fn send_system_message(&mut self, msg: impl Into<Text>) {
         self.write_packet(&GameMessageS2c {
             chat: msg.into().into(),
             overlay: false,
         });
     }
 
+    /// Sends a system message to the player which is shown above the hotbar
+    /// instead of in the chat.
+    pub fn send_overlay_message(&mut self, msg: impl Into<Text>) {
+        self.write_packet(&GameMessageS2c {
+            chat: msg.into().into(),
+            overlay: true,
+        });
+    }
+
+    /// Sends a chat message from a player to this client. Unlike system
+    /// messages, the client decorates the message according to the
+    /// [`ChatType`], and can hide it depending on its chat settings or when the
+    /// sender is blocked.
+    ///
+    /// The message is not signed, so the client marks it as not secure.
+    pub fn send_player_chat(
+        &mut self,
+        sender: &ChatSender,
+        msg: impl Into<Text>,
+        chat_type: ChatType,
+    ) {
+        write_player_chat(self, sender, &msg.into(), chat_type);
+    }
+
     pub fn send_custom_payload(&mut self, channel: Ident<&str>, data: &[u8]) {
         self.write_packet(&CustomPayloadS2c {
             channel: channel.into(),
@@ -341,57 +620,125 @@ impl Client {
         });
     }
 
+    /// Starts rain for this client only. Consider using the
+    /// [`Rain`](weather::Rain) component instead, which is kept in sync when
+    /// the client respawns.
+    pub fn begin_raining(&mut self) {
+        self.write_packet(&GameStateChangeS2c {
+            kind: GameEventKind::BeginRaining,
+            value: 0.0,
+        });
+    }
+
+    /// Stops rain for this client only.
+    pub fn end_raining(&mut self) {
+        self.write_packet(&GameStateChangeS2c {
+            kind: GameEventKind::EndRaining,
+            value: 0.0,
+        });
+    }
+
+    /// Sets the rain level for this client only. Valid values are within
+    /// `0.0..=1.0`. Has no visible effect unless it is raining.
+    pub fn set_rain_level(&mut self, level: f32) {
+        self.write_packet(&GameStateChangeS2c {
+            kind: GameEventKind::RainLevelChange,
+            value: level,
+        });
+    }
+
+    /// Sets the thunder level for this client only. Valid values are within
+    /// `0.0..=1.0`. Has no visible effect unless it is raining.
+    pub fn set_thunder_level(&mut self, level: f32) {
+        self.write_packet(&GameStateChangeS2c {
+            kind: GameEventKind::ThunderLevelChange,
+            value: level,
+        });
+    }
+
+    /// Shows a demo mode screen or message to the client.
+    pub fn demo_event(&mut self, event: DemoEvent) {
+        self.write_packet(&GameStateChangeS2c {
+            kind: GameEventKind::DemoEvent,
+            value: event.value(),
+        });
+    }
+
     /// Requests that the client download and enable a resource pack.
     ///
+    /// The response of the client is sent as a
+    /// [`ResourcePackStatusChange`](misc::ResourcePackStatusChange) event.
+    ///
     /// # Arguments
     /// * `url` - The URL of the resource pack file.
-    /// * `hash` - The SHA-1 hash of the resource pack file. Any value other
-    ///   than a 40-character hexadecimal string is ignored by the client.
+    /// * `hash` - The SHA-1 hash of the resource pack file. See
+    ///   [`ResourcePackHash`](resource_pack::ResourcePackHash).
     /// * `forced` - Whether a client should be kicked from the server upon
-    ///   declining the pack (this is enforced client-side)
+    ///   declining the pack. This is enforced client-side, and server-side
+    ///   according to the
+    ///   [`ForcedResourcePackPolicy`](resource_pack::ForcedResourcePackPolicy).
     /// * `prompt_message` - A message to be displayed with the resource pack
     ///   dialog.
     pub fn set_resource_pack(
         &mut self,
         url: &str,
-        hash: &str,
+        hash: impl Into<resource_pack::ResourcePackHash>,
         forced: bool,
         prompt_message: Option<Text>,
     ) {
+        self.forced_resource_pack = forced;
+
         self.write_packet(&ResourcePackSendS2c {
             url,
-            hash,
+            hash: &hash.into().to_string(),
             forced,
             prompt_message: prompt_message.map(|t| t.into()),
         });
     }
 
-    /// Sets the title this client sees.
+    /// Displays a title in the center of the screen of the client.
     ///
-    /// A title is a large piece of text displayed in the center of the screen
-    /// which may also include a subtitle underneath it. The title can be
-    /// configured to fade in and out using the [`TitleFadeS2c`]
-    /// struct.
-    pub fn set_title(
-        &mut self,
-        title: impl Into<Text>,
-        subtitle: impl Into<Text>,
-        animation: impl Into<Option<TitleFadeS2c>>,
-    ) {
-        let title = title.into().into();
-        let subtitle = subtitle.into();
+    /// The title packet is written at the end of the tick, after the
+    /// subtitle and times set with [`Self::set_subtitle`] and
+    /// [`Self::set_title_times`], because the client only shows them along
+    /// with the next title. Calling this again in the same tick replaces the
+    /// title.
+    pub fn set_title(&mut self, text: impl Into<Text>) {
+        self.pending_title = Some(text.into());
+    }
 
-        self.write_packet(&TitleS2c { title_text: title });
+    /// Sets the subtitle displayed under the title. The subtitle only appears
+    /// once a title is displayed with [`Self::set_title`].
+    pub fn set_subtitle(&mut self, text: impl Into<Text>) {
+        self.write_packet(&SubtitleS2c {
+            subtitle_text: text.into().into(),
+        });
+    }
 
-        if !subtitle.is_empty() {
-            self.write_packet(&SubtitleS2c {
-                subtitle_text: subtitle.into(),
-            });
-        }
+    /// Sets the number of ticks the title and subtitle spend fading in, staying
+    /// on the screen, and fading out.
+    ///
+    /// Unless this is called, the client uses the times of the previous title,
+    /// which are 10, 70 and 20 ticks by default.
+    pub fn set_title_times(&mut self, fade_in: i32, stay: i32, fade_out: i32) {
+        self.write_packet(&TitleFadeS2c {
+            fade_in,
+            stay,
+            fade_out,
+        });
+    }
 
-        if let Some(anim) = animation.into() {
-            self.write_packet(&anim);
-        }
+    /// Removes the title and subtitle from the screen of the client.
+    pub fn clear_title(&mut self) {
+        self.pending_title = None;
+        self.write_packet(&ClearTitleS2c { reset: false });
+    }
+
+    /// Removes the title and subtitle from the screen of the client, and
+    /// resets the subtitle and the title times to their default values.
+    pub fn reset_title(&mut self) {
+        self.pending_title = None;
+        self.write_packet(&ClearTitleS2c { reset: true });
     }
 
     /// Sets the action bar for this client.
@@ -431,27 +778,59 @@ impl Client {
 
     /// Plays a sound effect at the given position, only for this client.
     ///
+    /// `sound` is either a [`Sound`] or the identifier of a custom sound, such
+    /// as one from a resource pack. The seed is random. Use
+    /// [`Self::play_sound_with_seed`] to choose it.
+    ///
     /// If you want to play a sound effect to all players, use
     /// [`Instance::play_sound`]
     ///
+    /// [`Sound`]: valence_core::sound::Sound
     /// [`Instance::play_sound`]: Instance::play_sound
-    pub fn play_sound(
+    pub fn play_sound<'a>(
         &mut self,
-        sound: Sound,
+        sound: impl Into<SoundId<'a>>,
         category: SoundCategory,
         position: impl Into<DVec3>,
         volume: f32,
         pitch: f32,
+    ) {
+        self.play_sound_with_seed(sound, category, position, volume, pitch, rand::random())
+    }
+
+    /// Like [`Self::play_sound`], but with a fixed seed.
+    pub fn play_sound_with_seed<'a>(
+        &mut self,
+        sound: impl Into<SoundId<'a>>,
+        category: SoundCategory,
+        position: impl Into<DVec3>,
+        volume: f32,
+        pitch: f32,
+        seed: i64,
     ) {
         let position = position.into();
 
         self.write_packet(&PlaySoundS2c {
-            id: sound.to_id(),
+            id: sound.into(),
             category,
             position: (position * 8.0).as_ivec3(),
             volume,
             pitch,
-            seed: rand::random(),
+            seed,
+        });
+    }
+
+    /// Stops sounds for this client. If `category` is `None`, sounds in every
+    /// category are stopped. If `sound` is `None`, all sounds in the category
+    /// are stopped.
+    pub fn stop_sound<'a>(
+        &mut self,
+        category: Option<SoundCategory>,
+        sound: Option<Ident<Cow<'a, str>>>,
+    ) {
+        self.write_packet(&StopSoundS2c {
+            source: category,
+            sound,
         });
     }
 
@@ -463,6 +842,67 @@ impl Client {
         });
     }
 
+    /// Opens the sign editor for the sign at the given position. The text
+    /// submitted by the client is sent as an [`UpdateSign`](sign::UpdateSign)
+    /// event.
+    ///
+    /// The client only keeps the edited text if there is a sign at the
+    /// position.
+    pub fn open_sign_editor(&mut self, pos: impl Into<BlockPos>) {
+        self.write_packet(&SignEditorOpenS2c {
+            location: pos.into(),
+        });
+    }
+
+    /// Shows the content of the written book held by the client in the given
+    /// hand. Does nothing if the client is not holding a written book.
+    ///
+    /// See [`WrittenBook`](book::WrittenBook) to create written books.
+    pub fn open_book(&mut self, hand: Hand) {
+        self.write_packet(&OpenWrittenBookS2c { hand });
+    }
+
+    /// Sends the completions of the command a client is typing, in reply to a
+    /// completion request with the given transaction ID.
+    ///
+    /// The completions replace the `length` UTF-16 code units of the typed
+    /// text starting at `start`, where the leading slash is at position 0.
+    /// Each completion can have a tooltip shown when it is selected.
+    pub fn send_completions(
+        &mut self,
+        transaction_id: i32,
+        start: usize,
+        length: usize,
+        completions: Vec<(String, Option<Text>)>,
+    ) {
+        self.write_packet(&CommandSuggestionsS2c {
+            id: VarInt(transaction_id),
+            start: VarInt(start as i32),
+            length: VarInt(length as i32),
+            matches: completions
+                .iter()
+                .map(|(completion, tooltip)| Match {
+                    suggested_match: completion,
+                    tooltip: tooltip.as_ref().map(Cow::Borrowed),
+                })
+                .collect(),
+        });
+    }
+
+    /// Displays a cooldown over all the items of the given kind in the
+    /// inventory of the client, during which the client cannot use them. A
+    /// nonpositive number of ticks removes the cooldown.
+    ///
+    /// The client forgets the cooldown when it respawns. See
+    /// [`Cooldowns`](cooldown::Cooldowns) to keep track of cooldowns on the
+    /// server.
+    pub fn set_item_cooldown(&mut self, item: ItemKind, ticks: i32) {
+        self.write_packet(&CooldownUpdateS2c {
+            item_id: VarInt(item.to_raw().into()),
+            cooldown_ticks: VarInt(ticks.max(0)),
+        });
+    }
+
     /// Triggers an [`EntityStatus`].
     ///
     /// The status is only visible to this client.
@@ -593,6 +1033,12 @@ pub struct Ip(pub IpAddr);
 #[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
 pub struct CompassPos(pub BlockPos);
 
+/// The permission level of a client, from `0` to `4`. The client uses it to
+/// decide which actions it offers, such as switching game modes with F3 + F4
+/// from level `2` upwards.
+///
+/// The level is sent to the client whenever it changes, and again after the
+/// client respawns, since the client forgets it then.
 #[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
 pub struct OpLevel(u8);
 
@@ -601,12 +1047,20 @@ impl OpLevel {
         self.0
     }
 
-    /// Sets the op level. Value is clamped to `0..=3`.
+    /// Sets the op level. Value is clamped to `0..=4`.
     pub fn set(&mut self, lvl: u8) {
-        self.0 = lvl.min(3);
+        self.0 = lvl.min(4);
     }
 }
 
+/// The distance in chunks around the client in which chunks and entities are
+/// sent to it.
+///
+/// Whenever the [`MaxViewDistance`] of the client or the view distance in its
+/// [`ClientSettings`](settings::ClientSettings) change after the client
+/// spawned, this is set to the smaller of the two. Changes made during a tick
+/// are only applied at the end of it, so only the final value is sent to the
+/// client.
 #[derive(Component, Clone, PartialEq, Eq, Debug)]
 pub struct ViewDistance(u8);
 
@@ -633,6 +1087,37 @@ impl Default for ViewDistance {
     }
 }
 
+/// The largest [`ViewDistance`] the server allows for a client, regardless of
+/// the view distance the client asks for. Defaults to `32`.
+///
+/// Lowering this unloads the chunks which are no longer in view, and raising
+/// it sends the chunks which came into view.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct MaxViewDistance(u8);
+
+impl MaxViewDistance {
+    pub fn new(dist: u8) -> Self {
+        let mut new = Self(0);
+        new.set(dist);
+        new
+    }
+
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+
+    /// `dist` is clamped to `2..=32`.
+    pub fn set(&mut self, dist: u8) {
+        self.0 = dist.clamp(2, 32);
+    }
+}
+
+impl Default for MaxViewDistance {
+    fn default() -> Self {
+        Self(32)
+    }
+}
+
 /// The [`ViewDistance`] at the end of the previous tick. Automatically updated
 /// as [`ViewDistance`] is changed.
 #[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
@@ -679,6 +1164,11 @@ impl OldViewItem<'_> {
 pub struct DeathLocation(pub Option<(Ident<String>, BlockPos)>);
 
 /// Delay measured in milliseconds. Negative values indicate absence.
+///
+/// For clients, this is the smoothed round trip time of keepalive packets
+/// unless
+/// [`KeepaliveSettings::update_ping`](keepalive::KeepaliveSettings::update_ping)
+/// is disabled. It is displayed as latency bars in the player list.
 #[derive(Component, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct Ping(pub i32);
 
@@ -807,25 +1297,59 @@ fn initial_join(
     }
 }
 
+/// Clients only receive the registry codec while joining, so changes to it
+/// don't reach the clients which are already connected until they rejoin.
+fn warn_stale_registry_codec(
+    codec: Res<RegistryCodec>,
+    clients: Query<(), With<Client>>,
+    new_clients: Query<(), Added<Client>>,
+) {
+    if codec.is_changed() && !codec.is_added() {
+        let stale_count = clients.iter().count() - new_clients.iter().count();
+
+        if stale_count > 0 {
+            error!(
+                "the registry codec was changed while {stale_count} client(s) were connected. \
+                 Connected clients keep using the old registries until they rejoin, which can \
+                 cause them to be disconnected or display the wrong biomes and dimensions"
+            );
+        }
+    }
+}
+
 #[allow(clippy::type_complexity)]
 fn respawn(
     mut clients: Query<
         (
             &mut Client,
             &Location,
+            &OldLocation,
             &DeathLocation,
             &HashedSeed,
             &GameMode,
             &PrevGameMode,
             &IsDebug,
             &IsFlat,
+            &mut living::Health,
+            &mut entity::Pose,
         ),
         Changed<Location>,
     >,
     instances: Query<&Instance>,
 ) {
-    for (mut client, loc, death_loc, hashed_seed, game_mode, prev_game_mode, is_debug, is_flat) in
-        &mut clients
+    for (
+        mut client,
+        loc,
+        old_loc,
+        death_loc,
+        hashed_seed,
+        game_mode,
+        prev_game_mode,
+        is_debug,
+        is_flat,
+        mut health,
+        mut pose,
+    ) in &mut clients
     {
         if client.is_added() {
             // No need to respawn since we are sending the game join packet this tick.
@@ -839,11 +1363,42 @@ fn respawn(
 
         let dimension_name = instance.dimension_type_name();
 
+        // Dead clients come back to life with full health. The client only keeps
+        // its data when it was alive, like when changing dimensions.
+        let is_dead = pose.0 == Pose::Dying;
+
+        if is_dead {
+            health.0 = living::Health::default().0;
+            pose.0 = Pose::Standing;
+        }
+
         let last_death_location = death_loc.0.as_ref().map(|(id, pos)| GlobalPos {
             dimension_name: id.as_str_ident().into(),
             position: *pos,
         });
 
+        // Clients keep the chunks and entities of their world when respawning in
+        // the same dimension, so clients moving between instances with the same
+        // dimension type are respawned in another dimension first.
+        let same_dimension = old_loc.get() != loc.0
+            && instances
+                .get(old_loc.get())
+                .map_or(false, |old| old.dimension_type_name() == dimension_name);
+
+        if same_dimension {
+            client.write_packet(&PlayerRespawnS2c {
+                dimension_type_name: dimension_name.into(),
+                dimension_name: transfer::TRANSFER_DIMENSION_NAME.into(),
+                hashed_seed: hashed_seed.0,
+                game_mode: *game_mode,
+                previous_game_mode: prev_game_mode.0.map(|g| g as i8).unwrap_or(-1),
+                is_debug: is_debug.0,
+                is_flat: is_flat.0,
+                copy_metadata: !is_dead,
+                last_death_location: last_death_location.clone(),
+            });
+        }
+
         client.write_packet(&PlayerRespawnS2c {
             dimension_type_name: dimension_name.into(),
             dimension_name: dimension_name.into(),
@@ -852,12 +1407,35 @@ fn respawn(
             previous_game_mode: prev_game_mode.0.map(|g| g as i8).unwrap_or(-1),
             is_debug: is_debug.0,
             is_flat: is_flat.0,
-            copy_metadata: true,
+            copy_metadata: !is_dead,
             last_death_location,
         });
     }
 }
 
+/// Sets the view distance of clients to the smaller of the requested and the
+/// maximum view distance.
+#[allow(clippy::type_complexity)]
+fn update_view_dist(
+    mut clients: Query<
+        (
+            &mut ViewDistance,
+            Ref<MaxViewDistance>,
+            Ref<settings::ClientSettings>,
+        ),
+        Or<(Changed<MaxViewDistance>, Changed<settings::ClientSettings>)>,
+    >,
+) {
+    for (mut dist, max_dist, settings) in &mut clients {
+        // Keep the view distance the client spawned with until either changes.
+        if max_dist.is_added() && settings.is_added() {
+            continue;
+        }
+
+        dist.set_if_neq(ViewDistance::new(settings.view_distance.min(max_dist.0)));
+    }
+}
+
 fn update_chunk_load_dist(
     mut clients: Query<(&mut Client, &ViewDistance, &OldViewDistance), Changed<ViewDistance>>,
 ) {
@@ -938,6 +1516,12 @@ impl EntityInitQueryItem<'_> {
     }
 }
 
+/// Sends the packet data of the instance and of every cell in the client's
+/// view from the previous tick to the client.
+///
+/// The packet data of the cells is encoded once per tick when the instances
+/// are updated, so clients only copy the byte ranges they should receive into
+/// their own encoder.
 #[allow(clippy::type_complexity)]
 fn read_data_in_old_view(
     mut clients: Query<(
@@ -949,13 +1533,26 @@ fn read_data_in_old_view(
         &OldPosition,
         &OldViewDistance,
         Option<&PacketByteRange>,
+        &SendQueueLimit,
+        Option<&Degraded>,
     )>,
     instances: Query<&Instance>,
     entities: Query<(EntityInitQuery, &OldPosition)>,
     entity_ids: Query<&EntityId>,
 ) {
     clients.par_iter_mut().for_each_mut(
-        |(mut client, mut remove_buf, loc, old_loc, pos, old_pos, old_view_dist, byte_range)| {
+        |(
+            mut client,
+            mut remove_buf,
+            loc,
+            old_loc,
+            pos,
+            old_pos,
+            old_view_dist,
+            byte_range,
+            limit,
+            degraded,
+        )| {
             let Ok(instance) = instances.get(old_loc.get()) else {
                 return;
             };
@@ -969,6 +1566,15 @@ fn read_data_in_old_view(
 
             let view = ChunkView::new(old_chunk_pos, old_view_dist.0);
 
+            // Clients in degraded mode skip the movement of entities further than
+            // this distance.
+            let entity_distance = match limit.policy {
+                OverloadPolicy::Degrade {
+                    entity_distance, ..
+                } if degraded.is_some() => Some(entity_distance as u32),
+                _ => None,
+            };
+
             // Iterate over all visible chunks from the previous tick.
             view.for_each(|pos| {
                 if let Some(cell) = instance.partition.get(&pos) {
@@ -1007,6 +1613,11 @@ fn read_data_in_old_view(
                         }
                     }
 
+                    let skip_movement = matches!(
+                        entity_distance,
+                        Some(dist) if pos.chebyshev_distance(old_chunk_pos) > dist
+                    );
+
                     // Send all data in the chunk's packet buffer to this client. This will update
                     // entities in the cell, spawn or update the chunk in the cell, or send any
                     // other packet data that was added here by users.
@@ -1016,10 +1627,25 @@ fn read_data_in_old_view(
                             client.write_packet_bytes(&cell.packet_buf[..byte_range.0.start]);
                             client.write_packet_bytes(&cell.packet_buf[byte_range.0.end..]);
                         }
+                        _ if skip_movement => {
+                            // Skip the movement of the entities in distant cells.
+                            let mut start = 0;
+
+                            for range in &cell.movement_ranges {
+                                client.write_packet_bytes(&cell.packet_buf[start..range.start]);
+                                start = range.end;
+                            }
+
+                            client.write_packet_bytes(&cell.packet_buf[start..]);
+                        }
                         _ => {
                             client.write_packet_bytes(&cell.packet_buf);
                         }
                     }
+
+                    if degraded.is_none() {
+                        client.write_packet_bytes(&cell.non_essential_buf);
+                    }
                 }
             });
         },
@@ -1070,8 +1696,9 @@ fn update_view(
             let old_view = ChunkView::new(ChunkPos::from_dvec3(old_pos.get()), old_view_dist.0);
 
             // Make sure the center chunk is set before loading chunks! Otherwise the client
-            // may ignore the chunk.
-            if old_view.pos != view.pos {
+            // may ignore the chunk. Clients respawning in another dimension forget the
+            // center.
+            if old_view.pos != view.pos || loc.0 != old_loc.get() {
                 client.write_packet(&ChunkRenderDistanceCenterS2c {
                     chunk_x: VarInt(view.pos.x),
                     chunk_z: VarInt(view.pos.z),
@@ -1190,15 +1817,17 @@ fn update_view(
 fn remove_entities(
     mut clients: Query<(&mut Client, &mut EntityRemoveBuf), Changed<EntityRemoveBuf>>,
 ) {
-    for (mut client, mut buf) in &mut clients {
-        if !buf.0.is_empty() {
-            client.write_packet(&EntitiesDestroyS2c {
-                entity_ids: Cow::Borrowed(&buf.0),
-            });
+    clients
+        .par_iter_mut()
+        .for_each_mut(|(mut client, mut buf)| {
+            if !buf.0.is_empty() {
+                client.write_packet(&EntitiesDestroyS2c {
+                    entity_ids: Cow::Borrowed(&buf.0),
+                });
 
-            buf.0.clear();
-        }
-    }
+                buf.0.clear();
+            }
+        });
 }
 
 fn update_game_mode(mut clients: Query<(&mut Client, &GameMode), Changed<GameMode>>) {
@@ -1236,18 +1865,129 @@ fn update_spawn_position(mut clients: Query<(&mut Client, &CompassPos), Changed<
     }
 }
 
+/// Sends the packets written to every client during the tick.
+///
+/// Clients are flushed in parallel, since encrypting the packets of a client
+/// and handing them to its connection doesn't depend on any other client.
 fn flush_packets(
-    mut clients: Query<(Entity, &mut Client), Changed<Client>>,
+    mut clients: Query<(Entity, &mut Client, &SendQueueLimit), Changed<Client>>,
+    par_commands: ParallelCommands,
+) {
+    clients
+        .par_iter_mut()
+        .for_each_mut(|(entity, mut client, limit)| {
+            if client.send_queue_len() > limit.max_bytes {
+                warn!(
+                    "Send queue of client {entity:?} exceeded the limit of {} bytes.",
+                    limit.max_bytes
+                );
+
+                // The pending packets are not encrypted yet, so they can be dropped
+                // to make room for the disconnect message.
+                client.enc.clear();
+                client.pending_title = None;
+                client.write_packet(&DisconnectS2c {
+                    reason: Text::from("Connection overloaded").into(),
+                });
+                _ = client.flush_packets();
+
+                par_commands.command_scope(|mut commands| {
+                    commands.entity(entity).remove::<Client>();
+                });
+                return;
+            }
+
+            if let Err(e) = client.flush_packets() {
+                warn!("Failed to flush packet queue for client {entity:?}: {e:#}.");
+                par_commands.command_scope(|mut commands| {
+                    commands.entity(entity).remove::<Client>();
+                });
+            }
+        });
+}
+
+/// Moves the clients in and out of degraded mode according to their
+/// [`OverloadPolicy`].
+#[allow(clippy::type_complexity)]
+fn update_degraded_clients(
+    mut clients: Query<(
+        Entity,
+        &mut Client,
+        &SendQueueLimit,
+        Option<&Degraded>,
+        &Location,
+        &Position,
+        &ViewDistance,
+    )>,
+    instances: Query<&Instance>,
+    entities: Query<(&EntityId, &Position, &Look, &OnGround)>,
     mut commands: Commands,
 ) {
-    for (entity, mut client) in &mut clients {
-        if let Err(e) = client.flush_packets() {
-            warn!("Failed to flush packet queue for client {entity:?}: {e:#}.");
-            commands.entity(entity).remove::<Client>();
+    for (entity, mut client, limit, degraded, loc, pos, view_dist) in &mut clients {
+        let queue_len = client.conn.send_queue_len();
+
+        match (limit.policy, degraded.is_some()) {
+            (OverloadPolicy::Degrade { threshold, .. }, false) if queue_len > threshold => {
+                debug!("Client {entity:?} entered degraded mode.");
+                commands.entity(entity).insert(Degraded);
+            }
+            (OverloadPolicy::Degrade { .. }, true) if queue_len == 0 => {
+                debug!("Client {entity:?} left degraded mode.");
+                commands.entity(entity).remove::<Degraded>();
+
+                // Movement packets were skipped in degraded mode, so the
+                // positions of the entities in view are sent again.
+                let Ok(instance) = instances.get(loc.0) else {
+                    continue;
+                };
+
+                for cell_pos in ChunkView::new(pos.chunk_pos(), view_dist.0).iter() {
+                    let Some(cell) = instance.partition.get(&cell_pos) else {
+                        continue;
+                    };
+
+                    for &e in &cell.entities {
+                        if e == entity {
+                            continue;
+                        }
+
+                        if let Ok((id, pos, look, on_ground)) = entities.get(e) {
+                            client.write_packet(&EntityPositionS2c {
+                                entity_id: VarInt(id.get()),
+                                position: pos.0,
+                                yaw: ByteAngle::from_degrees(look.yaw),
+                                pitch: ByteAngle::from_degrees(look.pitch),
+                                on_ground: on_ground.0,
+                            });
+                        }
+                    }
+                }
+            }
+            (OverloadPolicy::Disconnect, true) => {
+                commands.entity(entity).remove::<Degraded>();
+            }
+            _ => {}
         }
     }
 }
 
+fn update_network_stats(mut clients: Query<(&Client, &mut NetworkStats)>) {
+    for (client, mut stats) in &mut clients {
+        let sent = client.enc.stats();
+        let received = client.conn.received_stats();
+
+        *stats = NetworkStats {
+            bytes_sent: sent.bytes,
+            bytes_received: received.bytes,
+            uncompressed_bytes_sent: sent.uncompressed_bytes,
+            uncompressed_bytes_received: received.uncompressed_bytes,
+            packets_sent: sent.packets,
+            packets_received: received.packets,
+            send_queue_len: client.conn.send_queue_len(),
+        };
+    }
+}
+
 fn init_tracked_data(mut clients: Query<(&mut Client, &TrackedData), Added<TrackedData>>) {
     for (mut client, tracked_data) in &mut clients {
         if let Some(init_data) = tracked_data.init_data() {
@@ -1270,7 +2010,12 @@ fn update_tracked_data(mut clients: Query<(&mut Client, &TrackedData)>) {
     }
 }
 
-fn update_op_level(mut clients: Query<(&mut Client, &OpLevel), Changed<OpLevel>>) {
+/// Sends the op level to clients when it changes, or when the clients join or
+/// respawn.
+#[allow(clippy::type_complexity)]
+fn update_op_level(
+    mut clients: Query<(&mut Client, &OpLevel), Or<(Changed<OpLevel>, Changed<Location>)>>,
+) {
     for (mut client, lvl) in &mut clients {
         client.write_packet(&EntityStatusS2c {
             entity_id: 0,
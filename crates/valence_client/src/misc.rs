@@ -1,21 +1,27 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
-use glam::Vec3;
+use glam::{DVec3, Vec3};
+use tracing::warn;
 use valence_core::block_pos::BlockPos;
+use valence_core::chat::{is_valid_chat_char, MAX_CHAT_MESSAGE_LENGTH};
 use valence_core::direction::Direction;
 use valence_core::hand::Hand;
 use valence_core::packet::c2s::play::{
     ChatMessageC2s, ClientStatusC2s, HandSwingC2s, PlayerInteractBlockC2s, PlayerInteractItemC2s,
     ResourcePackStatusC2s,
 };
-use valence_entity::{EntityAnimation, EntityAnimations};
+use valence_core::text::Text;
+use valence_core::translation_key;
+use valence_entity::{EntityAnimation, EntityAnimations, Position};
 
-use super::action::ActionSequence;
+use super::action::{ActionSequence, EYE_HEIGHT};
+use super::DisconnectClient;
 use crate::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
 
 pub(super) fn build(app: &mut App) {
     app.add_event::<HandSwing>()
         .add_event::<InteractBlock>()
+        .add_event::<InteractItem>()
         .add_event::<ChatMessage>()
         .add_event::<Respawn>()
         .add_event::<RequestStats>()
@@ -33,6 +39,14 @@ pub struct HandSwing {
     pub hand: Hand,
 }
 
+/// Sent when a client uses the item in its hand on a block, such as to place a
+/// block or open a chest. Interactions with blocks further than
+/// [`MAX_INTERACT_BLOCK_DISTANCE`] away are ignored.
+///
+/// The client predicts the result of the interaction. The interaction is
+/// acknowledged at the end of the tick with the sequence number, which makes
+/// the client show the blocks as they are on the server, so blocks which were
+/// not placed disappear again.
 #[derive(Copy, Clone, Debug)]
 pub struct InteractBlock {
     pub client: Entity,
@@ -50,6 +64,26 @@ pub struct InteractBlock {
     pub sequence: i32,
 }
 
+/// Sent when a client uses the item in its hand without targeting a block or
+/// an entity, such as to start eating or drawing a bow. The interaction is
+/// acknowledged like [`InteractBlock`].
+#[derive(Copy, Clone, Debug)]
+pub struct InteractItem {
+    pub client: Entity,
+    /// The hand that was used
+    pub hand: Hand,
+    /// Sequence number for synchronization
+    pub sequence: i32,
+}
+
+/// The maximum distance from the eyes of a client to the center of a block it
+/// can interact with, like in vanilla.
+pub const MAX_INTERACT_BLOCK_DISTANCE: f64 = 8.0;
+
+/// Sent when a client sends a chat message, whether it is signed or not.
+///
+/// Messages longer than [`MAX_CHAT_MESSAGE_LENGTH`] or with characters which
+/// are not allowed in chat are rejected, and the client is disconnected.
 #[derive(Clone, Debug)]
 pub struct ChatMessage {
     pub client: Entity,
@@ -62,6 +96,10 @@ pub struct Respawn {
     pub client: Entity,
 }
 
+/// Sent when a client opens the statistics screen. The
+/// [`Statistics`](crate::statistics::Statistics) of the client are sent back
+/// in [`CoreSet::PostUpdate`], so they can still be modified when handling
+/// this event.
 #[derive(Copy, Clone, Debug)]
 pub struct RequestStats {
     pub client: Entity,
@@ -88,17 +126,19 @@ pub struct ResourcePackStatusChange {
 #[allow(clippy::too_many_arguments)]
 fn handle_misc_packets(
     mut packets: EventReader<PacketEvent>,
-    mut clients: Query<(&mut ActionSequence, &mut EntityAnimations)>,
+    mut clients: Query<(&mut ActionSequence, &mut EntityAnimations, &Position)>,
     mut hand_swing_events: EventWriter<HandSwing>,
     mut interact_block_events: EventWriter<InteractBlock>,
+    mut interact_item_events: EventWriter<InteractItem>,
     mut chat_message_events: EventWriter<ChatMessage>,
     mut respawn_events: EventWriter<Respawn>,
     mut request_stats_events: EventWriter<RequestStats>,
     mut resource_pack_status_change_events: EventWriter<ResourcePackStatusChange>,
+    mut commands: Commands,
 ) {
     for packet in packets.iter() {
         if let Some(pkt) = packet.decode::<HandSwingC2s>() {
-            if let Ok((_, mut animations)) = clients.get_mut(packet.client) {
+            if let Ok((_, mut animations, _)) = clients.get_mut(packet.client) {
                 animations.trigger(match pkt.hand {
                     Hand::Main => EntityAnimation::SwingMainHand,
                     Hand::Off => EntityAnimation::SwingOffHand,
@@ -110,8 +150,22 @@ fn handle_misc_packets(
                 hand: pkt.hand,
             });
         } else if let Some(pkt) = packet.decode::<PlayerInteractBlockC2s>() {
-            if let Ok((mut action_seq, _)) = clients.get_mut(packet.client) {
-                action_seq.update(pkt.sequence.0);
+            let Ok((mut action_seq, _, pos)) = clients.get_mut(packet.client) else {
+                continue
+            };
+
+            action_seq.update(pkt.sequence.0);
+
+            let eye_pos = pos.0 + DVec3::new(0.0, EYE_HEIGHT, 0.0);
+            let block_center = DVec3::new(
+                pkt.position.x as f64 + 0.5,
+                pkt.position.y as f64 + 0.5,
+                pkt.position.z as f64 + 0.5,
+            );
+
+            if eye_pos.distance_squared(block_center) > MAX_INTERACT_BLOCK_DISTANCE.powi(2) {
+                // Too far away. The acknowledgement undoes the prediction of the client.
+                continue;
             }
 
             interact_block_events.send(InteractBlock {
@@ -124,12 +178,42 @@ fn handle_misc_packets(
                 sequence: pkt.sequence.0,
             });
         } else if let Some(pkt) = packet.decode::<PlayerInteractItemC2s>() {
-            if let Ok((mut action_seq, _)) = clients.get_mut(packet.client) {
+            if let Ok((mut action_seq, _, _)) = clients.get_mut(packet.client) {
                 action_seq.update(pkt.sequence.0);
             }
 
-            // TODO
+            interact_item_events.send(InteractItem {
+                client: packet.client,
+                hand: pkt.hand,
+                sequence: pkt.sequence.0,
+            });
         } else if let Some(pkt) = packet.decode::<ChatMessageC2s>() {
+            if pkt.message.chars().count() > MAX_CHAT_MESSAGE_LENGTH {
+                warn!("chat message from client {:?} is too long", packet.client);
+
+                commands.add(DisconnectClient {
+                    client: packet.client,
+                    reason: "Chat message is too long".into(),
+                });
+                continue;
+            }
+
+            if !pkt.message.chars().all(is_valid_chat_char) {
+                warn!(
+                    "chat message from client {:?} has illegal characters",
+                    packet.client
+                );
+
+                commands.add(DisconnectClient {
+                    client: packet.client,
+                    reason: Text::translate(
+                        translation_key::MULTIPLAYER_DISCONNECT_ILLEGAL_CHARACTERS,
+                        [],
+                    ),
+                });
+                continue;
+            }
+
             chat_message_events.send(ChatMessage {
                 client: packet.client,
                 message: pkt.message.into(),
@@ -1,12 +1,16 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use glam::DVec3;
-use valence_core::packet::c2s::play::{
-    Full, LookAndOnGround, OnGroundOnly, PositionAndOnGround, VehicleMoveC2s,
-};
+use valence_core::packet::c2s::play::{Full, LookAndOnGround, OnGroundOnly, PositionAndOnGround};
+use valence_core::packet::encode::WritePacket;
+use valence_core::packet::s2c::play::player_position_look::Flags as PlayerPositionLookFlags;
+use valence_core::packet::s2c::play::PlayerPositionLookS2c;
+use valence_core::Server;
 use valence_entity::{HeadYaw, Look, OnGround, Position};
 
+use super::abilities::PlayerAbilities;
 use super::teleport::TeleportState;
+use super::Client;
 use crate::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
 
 pub(super) fn build(app: &mut App) {
@@ -22,7 +26,93 @@ pub(super) fn build(app: &mut App) {
 /// Configuration resource for client movement checks.
 #[derive(Resource, Default)]
 pub struct MovementSettings {
-    // TODO
+    /// Checks the movement of clients before it is applied to their
+    /// [`Position`]. Rejected movement is not applied, and the client is
+    /// teleported back to its last accepted position. All movement is accepted
+    /// if this is `None`, which is the default.
+    pub validator: Option<Box<dyn MovementValidator>>,
+}
+
+/// Decides whether the movement of a client is accepted by the server.
+///
+/// This is implemented for closures taking a [`MovementCheck`] and returning
+/// `true` if the movement is accepted.
+pub trait MovementValidator: Send + Sync + 'static {
+    /// Returns `true` if the movement is accepted.
+    fn validate(&self, check: &MovementCheck) -> bool;
+}
+
+impl<F> MovementValidator for F
+where
+    F: Fn(&MovementCheck) -> bool + Send + Sync + 'static,
+{
+    fn validate(&self, check: &MovementCheck) -> bool {
+        self(check)
+    }
+}
+
+/// The movement of a client checked by a [`MovementValidator`]. Only movement
+/// which changes the position of the client is checked.
+#[derive(Clone, Debug)]
+pub struct MovementCheck<'a> {
+    /// The movement of the client. The old position is the last accepted
+    /// position of the client.
+    pub movement: &'a Movement,
+    /// The number of ticks since the last accepted movement of the client, or
+    /// since the server last set its position.
+    pub elapsed_ticks: i64,
+    /// The abilities of the client, like whether it is allowed to fly.
+    pub abilities: &'a PlayerAbilities,
+}
+
+/// A simple [`MovementValidator`] which limits the horizontal speed of clients
+/// and rejects clients rising through the air when they are not allowed to
+/// fly.
+///
+/// This is only meant as an example and is easily fooled. Knockback, elytras,
+/// riptide tridents and similar need a higher speed than the vanilla walking
+/// speed.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BasicMovementValidator {
+    /// The maximum horizontal distance a client can move per tick.
+    pub max_horizontal_speed: f64,
+    /// The maximum height a client can rise per tick while in the air. Clients
+    /// allowed to fly are not limited.
+    pub max_air_rise: f64,
+}
+
+impl Default for BasicMovementValidator {
+    fn default() -> Self {
+        Self {
+            // Sprint-jumping is about 0.6 blocks per tick.
+            max_horizontal_speed: 1.0,
+            // The initial velocity of a jump.
+            max_air_rise: 0.42,
+        }
+    }
+}
+
+impl MovementValidator for BasicMovementValidator {
+    fn validate(&self, check: &MovementCheck) -> bool {
+        let mov = check.movement;
+        let elapsed = check.elapsed_ticks.max(1) as f64;
+        let delta = mov.position - mov.old_position;
+
+        if delta.x.hypot(delta.z) > self.max_horizontal_speed * elapsed {
+            return false;
+        }
+
+        // Clients only rise that fast in the air after jumping off the ground.
+        if !check.abilities.allow_flying
+            && !mov.old_on_ground
+            && !mov.on_ground
+            && delta.y > self.max_air_rise
+        {
+            return false;
+        }
+
+        true
+    }
 }
 
 /// Event sent when a client successfully moves.
@@ -40,17 +130,21 @@ pub struct Movement {
 fn handle_client_movement(
     mut packets: EventReader<PacketEvent>,
     mut clients: Query<(
+        &mut Client,
         &mut Position,
         &mut Look,
         &mut HeadYaw,
         &mut OnGround,
         &mut TeleportState,
+        &PlayerAbilities,
     )>,
+    settings: Res<MovementSettings>,
+    server: Res<Server>,
     mut movement_events: EventWriter<Movement>,
 ) {
     for packet in packets.iter() {
         if let Some(pkt) = packet.decode::<PositionAndOnGround>() {
-            if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
+            if let Ok((client, pos, look, head_yaw, on_ground, teleport_state, abilities)) =
                 clients.get_mut(packet.client)
             {
                 let mov = Movement {
@@ -65,16 +159,20 @@ fn handle_client_movement(
 
                 handle(
                     mov,
+                    client,
                     pos,
                     look,
                     head_yaw,
                     on_ground,
                     teleport_state,
+                    abilities,
+                    &settings,
+                    server.current_tick(),
                     &mut movement_events,
                 );
             }
         } else if let Some(pkt) = packet.decode::<Full>() {
-            if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
+            if let Ok((client, pos, look, head_yaw, on_ground, teleport_state, abilities)) =
                 clients.get_mut(packet.client)
             {
                 let mov = Movement {
@@ -92,16 +190,20 @@ fn handle_client_movement(
 
                 handle(
                     mov,
+                    client,
                     pos,
                     look,
                     head_yaw,
                     on_ground,
                     teleport_state,
+                    abilities,
+                    &settings,
+                    server.current_tick(),
                     &mut movement_events,
                 );
             }
         } else if let Some(pkt) = packet.decode::<LookAndOnGround>() {
-            if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
+            if let Ok((client, pos, look, head_yaw, on_ground, teleport_state, abilities)) =
                 clients.get_mut(packet.client)
             {
                 let mov = Movement {
@@ -119,16 +221,20 @@ fn handle_client_movement(
 
                 handle(
                     mov,
+                    client,
                     pos,
                     look,
                     head_yaw,
                     on_ground,
                     teleport_state,
+                    abilities,
+                    &settings,
+                    server.current_tick(),
                     &mut movement_events,
                 );
             }
         } else if let Some(pkt) = packet.decode::<OnGroundOnly>() {
-            if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
+            if let Ok((client, pos, look, head_yaw, on_ground, teleport_state, abilities)) =
                 clients.get_mut(packet.client)
             {
                 let mov = Movement {
@@ -143,38 +249,15 @@ fn handle_client_movement(
 
                 handle(
                     mov,
+                    client,
                     pos,
                     look,
                     head_yaw,
                     on_ground,
                     teleport_state,
-                    &mut movement_events,
-                );
-            }
-        } else if let Some(pkt) = packet.decode::<VehicleMoveC2s>() {
-            if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
-                clients.get_mut(packet.client)
-            {
-                let mov = Movement {
-                    client: packet.client,
-                    position: pkt.position,
-                    old_position: pos.0,
-                    look: Look {
-                        yaw: pkt.yaw,
-                        pitch: pkt.pitch,
-                    },
-                    old_look: *look,
-                    on_ground: on_ground.0,
-                    old_on_ground: on_ground.0,
-                };
-
-                handle(
-                    mov,
-                    pos,
-                    look,
-                    head_yaw,
-                    on_ground,
-                    teleport_state,
+                    abilities,
+                    &settings,
+                    server.current_tick(),
                     &mut movement_events,
                 );
             }
@@ -182,24 +265,61 @@ fn handle_client_movement(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle(
     mov: Movement,
+    mut client: Mut<Client>,
     mut pos: Mut<Position>,
     mut look: Mut<Look>,
     mut head_yaw: Mut<HeadYaw>,
     mut on_ground: Mut<OnGround>,
     mut teleport_state: Mut<TeleportState>,
+    abilities: &PlayerAbilities,
+    settings: &MovementSettings,
+    current_tick: i64,
     movement_events: &mut EventWriter<Movement>,
 ) {
+    // Movement sent before the client confirmed the last teleport is stale.
     if teleport_state.pending_teleports() != 0 {
         return;
     }
 
-    // TODO: check that the client isn't moving too fast / flying.
     // TODO: check that the client isn't clipping through blocks.
 
+    if let Some(validator) = &settings.validator {
+        if mov.position != mov.old_position {
+            let check = MovementCheck {
+                movement: &mov,
+                elapsed_ticks: current_tick - teleport_state.synced_tick,
+                abilities,
+            };
+
+            if !validator.validate(&check) {
+                // Teleport the client back to its last accepted position, but
+                // keep its look. Movement sent before the client confirms the
+                // teleport is ignored.
+                client.write_packet(&PlayerPositionLookS2c {
+                    position: pos.0,
+                    yaw: 0.0,
+                    pitch: 0.0,
+                    flags: PlayerPositionLookFlags::new()
+                        .with_y_rot(true)
+                        .with_x_rot(true),
+                    teleport_id: teleport_state.next_teleport_id(),
+                });
+
+                look.set_if_neq(mov.look);
+                teleport_state.synced_look = mov.look;
+                head_yaw.set_if_neq(HeadYaw(mov.look.yaw));
+
+                return;
+            }
+        }
+    }
+
     pos.set_if_neq(Position(mov.position));
     teleport_state.synced_pos = mov.position;
+    teleport_state.synced_tick = current_tick;
     look.set_if_neq(mov.look);
     teleport_state.synced_look = mov.look;
     head_yaw.set_if_neq(HeadYaw(mov.look.yaw));
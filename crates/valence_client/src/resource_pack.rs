@@ -0,0 +1,132 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, ensure};
+use valence_core::text::Text;
+
+use super::*;
+use crate::misc::{ResourcePackStatus, ResourcePackStatusChange};
+
+pub(super) fn build(app: &mut App) {
+    app.init_resource::<ForcedResourcePackPolicy>()
+        .add_system(enforce_forced_resource_pack);
+}
+
+/// The SHA-1 hash of a resource pack file, used by the client to check the
+/// integrity of the download and to cache the pack.
+///
+/// A hash can be created from its 20 raw bytes, or parsed from a 40-character
+/// hexadecimal string.
+///
+/// ```
+/// # use valence_client::resource_pack::ResourcePackHash;
+/// let hash: ResourcePackHash = "d7c6108849fb190ec2a49f2d38b7f1f897d9ce9f".parse().unwrap();
+///
+/// assert_eq!(hash.to_string(), "d7c6108849fb190ec2a49f2d38b7f1f897d9ce9f");
+/// assert!("d7c6108849fb".parse::<ResourcePackHash>().is_err());
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub struct ResourcePackHash(pub [u8; 20]);
+
+impl From<[u8; 20]> for ResourcePackHash {
+    fn from(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for ResourcePackHash {
+    type Error = anyhow::Error;
+
+    fn try_from(bytes: &[u8]) -> anyhow::Result<Self> {
+        ensure!(
+            bytes.len() == 20,
+            "a SHA-1 hash is 20 bytes long, got {} bytes",
+            bytes.len()
+        );
+
+        let mut res = [0; 20];
+        res.copy_from_slice(bytes);
+        Ok(Self(res))
+    }
+}
+
+impl FromStr for ResourcePackHash {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        ensure!(
+            s.len() == 40,
+            "a SHA-1 hash is 40 hexadecimal characters long, got {} characters",
+            s.len()
+        );
+
+        fn digit(c: u8) -> anyhow::Result<u8> {
+            match c {
+                b'0'..=b'9' => Ok(c - b'0'),
+                b'a'..=b'f' => Ok(c - b'a' + 10),
+                b'A'..=b'F' => Ok(c - b'A' + 10),
+                _ => bail!("invalid hexadecimal character {:?}", c as char),
+            }
+        }
+
+        let mut res = [0; 20];
+
+        for (b, pair) in res.iter_mut().zip(s.as_bytes().chunks_exact(2)) {
+            *b = digit(pair[0])? << 4 | digit(pair[1])?;
+        }
+
+        Ok(Self(res))
+    }
+}
+
+/// Formats the hash as a lowercase hexadecimal string, which is the format
+/// expected by the client.
+impl fmt::Display for ResourcePackHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in self.0 {
+            write!(f, "{b:02x}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What happens to clients which decline or fail to download a resource pack
+/// sent with `forced` set. See [`Client::set_resource_pack`].
+#[derive(Resource, Clone, PartialEq, Default, Debug)]
+pub enum ForcedResourcePackPolicy {
+    /// Nothing is done by the server. The vanilla client disconnects on its
+    /// own when it declines a forced resource pack.
+    #[default]
+    Ignore,
+    /// The client is disconnected with this reason.
+    Kick(Text),
+}
+
+fn enforce_forced_resource_pack(
+    mut events: EventReader<ResourcePackStatusChange>,
+    clients: Query<&Client>,
+    policy: Res<ForcedResourcePackPolicy>,
+    mut commands: Commands,
+) {
+    let ForcedResourcePackPolicy::Kick(reason) = &*policy else {
+        // Drain the events so they are not read again once the policy changes.
+        events.clear();
+        return;
+    };
+
+    for event in events.iter() {
+        let failed = matches!(
+            event.status,
+            ResourcePackStatus::Declined | ResourcePackStatus::FailedDownload
+        );
+
+        if failed && matches!(clients.get(event.client), Ok(client) if client.forced_resource_pack)
+        {
+            commands.add(DisconnectClient {
+                client: event.client,
+                reason: reason.clone(),
+            });
+        }
+    }
+}
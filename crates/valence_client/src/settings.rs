@@ -1,4 +1,6 @@
-pub use valence_core::packet::c2s::play::client_settings::ChatMode;
+pub use valence_core::packet::c2s::play::client_settings::{
+    ChatMode, DisplayedSkinParts, MainArm as Arm,
+};
 use valence_core::packet::c2s::play::ClientSettingsC2s;
 pub use valence_entity::player::{MainArm, PlayerModelParts};
 
@@ -6,46 +8,69 @@ use super::*;
 use crate::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
 
 pub(super) fn build(app: &mut App) {
-    app.add_system(
+    app.add_event::<ClientSettingsChange>().add_system(
         handle_client_settings
             .in_schedule(EventLoopSchedule)
             .in_base_set(EventLoopSet::PreUpdate),
     );
 }
 
-#[derive(Component, Default, Debug)]
+/// The settings sent by the client when it joins and whenever they are
+/// changed in the options menu.
+///
+/// The view distance limits the [`ViewDistance`] of the client, and the skin
+/// parts and main arm are written to the [`PlayerModelParts`] and [`MainArm`]
+/// of the player entity, so that other players see them.
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
 pub struct ClientSettings {
     pub locale: Box<str>,
+    /// The view distance requested by the client, in chunks.
+    pub view_distance: u8,
     pub chat_mode: ChatMode,
     pub chat_colors: bool,
+    pub displayed_skin_parts: DisplayedSkinParts,
+    pub main_arm: Arm,
     pub enable_text_filtering: bool,
     pub allow_server_listings: bool,
 }
 
+/// Sent when the [`ClientSettings`] of a client change.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ClientSettingsChange {
+    pub client: Entity,
+}
+
 fn handle_client_settings(
     mut packets: EventReader<PacketEvent>,
-    mut clients: Query<(
-        &mut ViewDistance,
-        &mut ClientSettings,
-        &mut PlayerModelParts,
-        &mut MainArm,
-    )>,
+    mut clients: Query<(&mut ClientSettings, &mut PlayerModelParts, &mut MainArm)>,
+    mut events: EventWriter<ClientSettingsChange>,
 ) {
     for packet in packets.iter() {
         if let Some(pkt) = packet.decode::<ClientSettingsC2s>() {
-            if let Ok((mut view_dist, mut settings, mut model_parts, mut main_arm)) =
+            if let Ok((mut settings, mut model_parts, mut main_arm)) =
                 clients.get_mut(packet.client)
             {
-                view_dist.set_if_neq(ViewDistance::new(pkt.view_distance));
-
-                settings.locale = pkt.locale.into();
-                settings.chat_mode = pkt.chat_mode;
-                settings.chat_colors = pkt.chat_colors;
-                settings.enable_text_filtering = pkt.enable_text_filtering;
-                settings.allow_server_listings = pkt.allow_server_listings;
+                let new_settings = ClientSettings {
+                    locale: pkt.locale.into(),
+                    view_distance: pkt.view_distance,
+                    chat_mode: pkt.chat_mode,
+                    chat_colors: pkt.chat_colors,
+                    displayed_skin_parts: pkt.displayed_skin_parts,
+                    main_arm: pkt.main_arm,
+                    enable_text_filtering: pkt.enable_text_filtering,
+                    allow_server_listings: pkt.allow_server_listings,
+                };
 
                 model_parts.set_if_neq(PlayerModelParts(u8::from(pkt.displayed_skin_parts) as i8));
                 main_arm.set_if_neq(MainArm(pkt.main_arm as i8));
+
+                if *settings != new_settings {
+                    *settings = new_settings;
+
+                    events.send(ClientSettingsChange {
+                        client: packet.client,
+                    });
+                }
             }
         }
     }
@@ -0,0 +1,88 @@
+//! Sign editing.
+//!
+//! Use [`Client::open_sign_editor`] to let a client edit the text of a sign.
+//! The submitted text is sent as an [`UpdateSign`] event, which can be written
+//! to the sign with [`UpdateSign::write_nbt`].
+
+use valence_core::block_pos::BlockPos;
+use valence_core::packet::c2s::play::UpdateSignC2s;
+use valence_nbt::Compound;
+
+use super::*;
+use crate::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<UpdateSign>().add_system(
+        handle_update_sign
+            .in_schedule(EventLoopSchedule)
+            .in_base_set(EventLoopSet::PreUpdate),
+    );
+}
+
+/// The maximum number of characters in a line of text submitted by a client.
+/// Updates with longer lines are ignored.
+pub const MAX_SIGN_LINE_LEN: usize = 384;
+
+/// Sent when a client submits the text of a sign after editing it.
+///
+/// Formatting codes and control characters are removed from the lines.
+/// Nothing is changed in the instance until the lines are written to the sign
+/// by the server.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UpdateSign {
+    pub client: Entity,
+    /// The position of the edited sign.
+    pub position: BlockPos,
+    pub lines: [String; 4],
+}
+
+impl UpdateSign {
+    /// Writes the lines as plain text into the NBT of a sign block entity,
+    /// keeping the color and glow of the text.
+    pub fn write_nbt(&self, nbt: &mut Compound) {
+        for (i, line) in self.lines.iter().enumerate() {
+            nbt.insert(format!("Text{}", i + 1), Text::text(line.clone()));
+        }
+    }
+}
+
+fn handle_update_sign(mut packets: EventReader<PacketEvent>, mut events: EventWriter<UpdateSign>) {
+    for packet in packets.iter() {
+        if let Some(pkt) = packet.decode::<UpdateSignC2s>() {
+            if pkt
+                .lines
+                .iter()
+                .any(|line| line.chars().count() > MAX_SIGN_LINE_LEN)
+            {
+                warn!(
+                    "client {:?} sent a sign line longer than {MAX_SIGN_LINE_LEN} characters",
+                    packet.client
+                );
+                continue;
+            }
+
+            events.send(UpdateSign {
+                client: packet.client,
+                position: pkt.position,
+                lines: pkt.lines.map(sanitize_line),
+            });
+        }
+    }
+}
+
+/// Removes the formatting codes and control characters from a line of text.
+fn sanitize_line(line: &str) -> String {
+    let mut res = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            // Skip the formatting code.
+            chars.next();
+        } else if !c.is_control() {
+            res.push(c);
+        }
+    }
+
+    res
+}
@@ -0,0 +1,149 @@
+//! Statistics of clients.
+//!
+//! The [`Statistics`] of a client are sent to it when it opens the statistics
+//! screen, which sends a [`RequestStats`] event first. Statistics can be filled
+//! in lazily by handling the event before [`CoreSet::PostUpdate`].
+
+use std::collections::BTreeMap;
+
+use valence_block::BlockKind;
+use valence_core::packet::s2c::play::statistics::Statistic as StatisticEntry;
+use valence_core::packet::s2c::play::StatisticsS2c;
+use valence_core::statistic::CustomStatistic;
+
+use super::*;
+use crate::misc::RequestStats;
+
+pub(super) fn build(app: &mut App) {
+    app.add_system(send_statistics.in_set(UpdateClientsSet));
+}
+
+/// A statistic shown on the statistics screen of the client.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Statistic {
+    /// The number of times a block was mined.
+    Mined(BlockKind),
+    /// The number of times an item was crafted.
+    Crafted(ItemKind),
+    /// The number of times an item was used.
+    Used(ItemKind),
+    /// The number of times an item broke from running out of durability.
+    Broken(ItemKind),
+    /// The number of times an item was picked up.
+    PickedUp(ItemKind),
+    /// The number of times an item was dropped.
+    Dropped(ItemKind),
+    /// The number of times an entity was killed.
+    Killed(EntityKind),
+    /// The number of times the client was killed by an entity.
+    KilledBy(EntityKind),
+    /// A statistic which is not about a block, item or entity.
+    Custom(CustomStatistic),
+}
+
+impl Statistic {
+    /// Returns the raw ID of the statistic type, like `minecraft:mined`.
+    pub const fn category_id(self) -> i32 {
+        match self {
+            Statistic::Mined(_) => 0,
+            Statistic::Crafted(_) => 1,
+            Statistic::Used(_) => 2,
+            Statistic::Broken(_) => 3,
+            Statistic::PickedUp(_) => 4,
+            Statistic::Dropped(_) => 5,
+            Statistic::Killed(_) => 6,
+            Statistic::KilledBy(_) => 7,
+            Statistic::Custom(_) => 8,
+        }
+    }
+
+    /// Returns the raw ID of the block, item, entity or custom statistic in
+    /// the registry of the statistic type.
+    pub const fn statistic_id(self) -> i32 {
+        match self {
+            Statistic::Mined(block) => block.to_raw() as i32,
+            Statistic::Crafted(item)
+            | Statistic::Used(item)
+            | Statistic::Broken(item)
+            | Statistic::PickedUp(item)
+            | Statistic::Dropped(item) => item.to_raw() as i32,
+            Statistic::Killed(kind) | Statistic::KilledBy(kind) => kind.get(),
+            Statistic::Custom(stat) => stat.to_raw() as i32,
+        }
+    }
+}
+
+impl From<CustomStatistic> for Statistic {
+    fn from(stat: CustomStatistic) -> Self {
+        Self::Custom(stat)
+    }
+}
+
+/// The values of the statistics of a client. Statistics which were never set
+/// are `0`.
+///
+/// The statistics are only sent when the client requests them.
+#[derive(Component, Clone, Default, Debug)]
+pub struct Statistics {
+    values: BTreeMap<Statistic, i32>,
+}
+
+impl Statistics {
+    /// Returns the value of the statistic.
+    pub fn get(&self, stat: impl Into<Statistic>) -> i32 {
+        self.values.get(&stat.into()).copied().unwrap_or(0)
+    }
+
+    /// Sets the value of the statistic.
+    pub fn set(&mut self, stat: impl Into<Statistic>, value: i32) {
+        let stat = stat.into();
+
+        if value == 0 {
+            self.values.remove(&stat);
+        } else {
+            self.values.insert(stat, value);
+        }
+    }
+
+    /// Adds `amount` to the value of the statistic, without going past
+    /// [`i32::MAX`]. This is what the vanilla server does.
+    pub fn increment(&mut self, stat: impl Into<Statistic>, amount: i32) {
+        let stat = stat.into();
+        let value = self.get(stat).saturating_add(amount);
+
+        self.set(stat, value);
+    }
+
+    /// Sets the values of all statistics to `0`.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Returns an iterator over the statistics which are not `0` and their
+    /// values.
+    pub fn iter(&self) -> impl Iterator<Item = (Statistic, i32)> + '_ {
+        self.values.iter().map(|(&stat, &value)| (stat, value))
+    }
+}
+
+fn send_statistics(
+    mut events: EventReader<RequestStats>,
+    mut clients: Query<(&mut Client, &Statistics)>,
+) {
+    for event in events.iter() {
+        let Ok((mut client, stats)) = clients.get_mut(event.client) else {
+            continue
+        };
+
+        client.write_packet(&StatisticsS2c {
+            statistics: stats
+                .iter()
+                .map(|(stat, value)| StatisticEntry {
+                    category_id: VarInt(stat.category_id()),
+                    statistic_id: VarInt(stat.statistic_id()),
+                    value: VarInt(value),
+                })
+                .collect(),
+        });
+    }
+}
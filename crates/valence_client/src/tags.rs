@@ -0,0 +1,26 @@
+//! Sends the tags in the [`TagsRegistry`] to clients.
+//!
+//! Clients receive every tag when they join, and again whenever the registry
+//! is changed.
+
+use valence_registry::TagsRegistry;
+
+use super::*;
+
+pub(super) fn build(app: &mut App) {
+    app.add_system(send_tags.after(initial_join).in_set(UpdateClientsSet));
+}
+
+fn send_tags(tags: Res<TagsRegistry>, mut clients: Query<&mut Client>) {
+    let changed = tags.is_changed();
+
+    let mut clients = clients
+        .iter_mut()
+        .filter(|client| changed || client.is_added())
+        .peekable();
+
+    // Avoid building the packet when no client needs it.
+    if clients.peek().is_some() {
+        broadcast_packet_cached(&tags.sync_packet(), clients);
+    }
+}
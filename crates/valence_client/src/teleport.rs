@@ -22,6 +22,9 @@ pub struct TeleportState {
     pending_teleports: u32,
     pub(super) synced_pos: DVec3,
     pub(super) synced_look: Look,
+    /// The tick the position of the client was last accepted or set by the
+    /// server.
+    pub(super) synced_tick: i64,
 }
 
 impl TeleportState {
@@ -35,6 +38,7 @@ impl TeleportState {
                 yaw: 180.0,
                 pitch: 0.0,
             },
+            synced_tick: 0,
         }
     }
 
@@ -45,28 +49,53 @@ impl TeleportState {
     pub fn pending_teleports(&self) -> u32 {
         self.pending_teleports
     }
+
+    /// Returns the ID of a new teleport sent to the client. Position packets
+    /// from the client are ignored until it confirms the teleport.
+    pub(super) fn next_teleport_id(&mut self) -> VarInt {
+        let id = VarInt(self.teleport_id_counter as i32);
+
+        self.pending_teleports = self.pending_teleports.wrapping_add(1);
+        self.teleport_id_counter = self.teleport_id_counter.wrapping_add(1);
+
+        id
+    }
 }
 
 /// Syncs the client's position and look with the server.
 ///
 /// This should happen after chunks are loaded so the client doesn't fall though
-/// the floor.
+/// the floor. Clients which respawned are always sent their full position and
+/// look, since they are reset by the client.
 #[allow(clippy::type_complexity)]
 fn teleport(
     mut clients: Query<
-        (&mut Client, &mut TeleportState, &Position, &Look),
-        Or<(Changed<Position>, Changed<Look>)>,
+        (
+            &mut Client,
+            &mut TeleportState,
+            &Position,
+            &Look,
+            Ref<Location>,
+        ),
+        Or<(Changed<Position>, Changed<Look>, Changed<Location>)>,
     >,
+    server: Res<Server>,
 ) {
-    for (mut client, mut state, pos, look) in &mut clients {
-        let changed_pos = pos.0 != state.synced_pos;
-        let changed_yaw = look.yaw != state.synced_look.yaw;
-        let changed_pitch = look.pitch != state.synced_look.pitch;
+    for (mut client, mut state, pos, look, loc) in &mut clients {
+        let respawned = loc.is_changed() && !client.is_added();
+
+        let changed_pos = respawned || pos.0 != state.synced_pos;
+        let changed_yaw = respawned || look.yaw != state.synced_look.yaw;
+        let changed_pitch = respawned || look.pitch != state.synced_look.pitch;
 
         if changed_pos || changed_yaw || changed_pitch {
             state.synced_pos = pos.0;
             state.synced_look = *look;
 
+            if changed_pos {
+                state.synced_tick = server.current_tick();
+            }
+
             let flags = PlayerPositionLookFlags::new()
                 .with_x(!changed_pos)
                 .with_y(!changed_pos)
@@ -79,11 +108,8 @@ fn teleport(
                 yaw: if changed_yaw { look.yaw } else { 0.0 },
                 pitch: if changed_pitch { look.pitch } else { 0.0 },
                 flags,
-                teleport_id: VarInt(state.teleport_id_counter as i32),
+                teleport_id: state.next_teleport_id(),
             });
-
-            state.pending_teleports = state.pending_teleports.wrapping_add(1);
-            state.teleport_id_counter = state.teleport_id_counter.wrapping_add(1);
         }
     }
 }
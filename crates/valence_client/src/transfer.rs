@@ -0,0 +1,94 @@
+//! Moving clients between instances.
+//!
+//! [`Client::transfer_to`] moves a client to a position in another instance
+//! (or the same one) and respawns it there. Respawning makes the client forget
+//! most of its state, so the modules managing that state send it again when
+//! the [`Location`] of a client changes. This includes the inventory, held
+//! item, abilities, health, experience, cooldowns, camera, op level, time,
+//! weather and world border.
+//!
+//! Clients only forget the chunks and entities of the world they were in when
+//! they respawn into a different dimension. Clients moving between instances
+//! with the same dimension type are therefore first respawned into a
+//! placeholder dimension, so that they don't keep anything from the old
+//! instance.
+
+use valence_core::ident;
+
+use super::*;
+
+/// The name of the dimension clients are briefly respawned into when moving
+/// between instances with the same dimension type.
+pub(super) const TRANSFER_DIMENSION_NAME: Ident<&str> = ident!("valence:transfer");
+
+/// The [`SystemSet`] in [`CoreSet::UpdateFlush`] where the transfers requested
+/// with [`Client::transfer_to`] are applied. Transfers requested after this
+/// set are applied on the next tick.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TransferClientsSet;
+
+pub(super) fn build(app: &mut App) {
+    app.configure_set(TransferClientsSet.in_base_set(CoreSet::UpdateFlush))
+        .add_system(transfer_clients.in_set(TransferClientsSet));
+}
+
+/// A transfer requested with [`Client::transfer_to`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(super) struct PendingTransfer {
+    instance: Entity,
+    position: DVec3,
+    look: Look,
+}
+
+impl Client {
+    /// Moves the client to `position` in `instance` and respawns it there,
+    /// facing in the direction of `look`.
+    ///
+    /// The transfer is applied at the end of [`CoreSet::Update`], after which
+    /// the [`Location`], [`Position`] and [`Look`] of the client are updated.
+    /// The instance may have a different dimension type than the current one,
+    /// or be the current instance. Requesting another transfer before it is
+    /// applied replaces it.
+    pub fn transfer_to(&mut self, instance: Entity, position: impl Into<DVec3>, look: Look) {
+        self.pending_transfer = Some(PendingTransfer {
+            instance,
+            position: position.into(),
+            look,
+        });
+    }
+}
+
+fn transfer_clients(
+    mut clients: Query<(
+        Entity,
+        &mut Client,
+        &mut Location,
+        &mut Position,
+        &mut Look,
+        &mut HeadYaw,
+    )>,
+    instances: Query<(), With<Instance>>,
+) {
+    for (entity, mut client, mut loc, mut pos, mut look, mut head_yaw) in &mut clients {
+        let Some(transfer) = client.pending_transfer else {
+            continue;
+        };
+
+        client.pending_transfer = None;
+
+        if instances.get(transfer.instance).is_err() {
+            warn!(
+                "client {entity:?} cannot be transferred to nonexistent instance {:?}",
+                transfer.instance
+            );
+            continue;
+        }
+
+        // Setting the location marks it as changed even if the instance is the
+        // same, which sends the respawn packet.
+        loc.0 = transfer.instance;
+        pos.set(transfer.position);
+        *look = transfer.look;
+        head_yaw.0 = transfer.look.yaw;
+    }
+}
@@ -0,0 +1,89 @@
+//! Steering of vehicles.
+//!
+//! Clients riding an entity send their steering input as [`VehicleInput`]
+//! events. Clients controlling the entity they ride, like a boat or a saddled
+//! horse, also send its movement as [`VehicleMove`] events. The charge of horse
+//! jumps is sent as [`JumpWithHorse`](crate::command::JumpWithHorse) events.
+
+use valence_core::packet::c2s::play::{PlayerInputC2s, VehicleMoveC2s};
+
+use super::*;
+use crate::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent};
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<VehicleInput>()
+        .add_event::<VehicleMove>()
+        .add_system(
+            handle_vehicle_packets
+                .in_schedule(EventLoopSchedule)
+                .in_base_set(EventLoopSet::PreUpdate),
+        );
+}
+
+/// The entity controlled by a client while riding it. The movement of the
+/// vehicle sent by the client is applied to the [`Position`] and [`Look`] of
+/// the entity.
+///
+/// Without this component, the movement is only sent as [`VehicleMove`]
+/// events.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ClientControlledVehicle(pub Entity);
+
+/// Sent every tick by clients riding an entity.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct VehicleInput {
+    pub client: Entity,
+    /// Positive to the left of the client, negative to the right.
+    pub sideways: f32,
+    /// Positive forward, negative backward.
+    pub forward: f32,
+    pub jumping: bool,
+    /// Whether the client is sneaking to get off the vehicle.
+    pub dismounting: bool,
+}
+
+/// Sent when a client moves the vehicle it controls.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct VehicleMove {
+    pub client: Entity,
+    pub position: DVec3,
+    pub look: Look,
+}
+
+fn handle_vehicle_packets(
+    mut packets: EventReader<PacketEvent>,
+    clients: Query<&ClientControlledVehicle>,
+    mut vehicles: Query<(&mut Position, &mut Look)>,
+    mut vehicle_input_events: EventWriter<VehicleInput>,
+    mut vehicle_move_events: EventWriter<VehicleMove>,
+) {
+    for packet in packets.iter() {
+        if let Some(pkt) = packet.decode::<PlayerInputC2s>() {
+            vehicle_input_events.send(VehicleInput {
+                client: packet.client,
+                sideways: pkt.sideways,
+                forward: pkt.forward,
+                jumping: pkt.flags.jump(),
+                dismounting: pkt.flags.unmount(),
+            });
+        } else if let Some(pkt) = packet.decode::<VehicleMoveC2s>() {
+            let look = Look {
+                yaw: pkt.yaw,
+                pitch: pkt.pitch,
+            };
+
+            if let Ok(vehicle) = clients.get(packet.client) {
+                if let Ok((mut pos, mut vehicle_look)) = vehicles.get_mut(vehicle.0) {
+                    pos.set_if_neq(Position(pkt.position));
+                    vehicle_look.set_if_neq(look);
+                }
+            }
+
+            vehicle_move_events.send(VehicleMove {
+                client: packet.client,
+                position: pkt.position,
+                look,
+            });
+        }
+    }
+}
@@ -12,8 +12,9 @@
 //! - [`Thunder`]: When attached, thunder level set event is emitted. When
 //!   removed, the thunder level set to zero event is emitted.
 //!
-//! New joined players are handled, so that they are get weather events from
-//! the instance.
+//! The weather is sent again whenever a client joins or respawns, which
+//! happens when its [`Location`] changes. The weather of the client takes
+//! priority over the weather of its instance.
 
 use valence_core::packet::s2c::play::game_state_change::GameEventKind;
 use valence_core::packet::s2c::play::GameStateChangeS2c;
@@ -61,9 +62,10 @@ pub(super) fn build(app: &mut App) {
             .in_set(UpdateWeatherPerClientSet),
     )
     .add_system(
-        handle_weather_for_joined_player
-            .before(UpdateWeatherPerClientSet)
-            .in_base_set(CoreSet::PostUpdate),
+        handle_weather_on_location_change
+            .after(respawn)
+            .after(UpdateWeatherPerClientSet)
+            .in_set(UpdateClientsSet),
     );
 }
 
@@ -79,30 +81,23 @@ pub struct Rain(pub f32);
 #[derive(Component)]
 pub struct Thunder(pub f32);
 
-fn handle_weather_for_joined_player(
-    mut clients: Query<(&mut Client, &Location), Added<Client>>,
+/// Sends the weather to clients which have joined or respawned, since the
+/// client resets the weather when it respawns.
+#[allow(clippy::type_complexity)]
+fn handle_weather_on_location_change(
+    mut clients: Query<(&mut Client, &Location, Option<&Rain>, Option<&Thunder>), Changed<Location>>,
     weathers: Query<(Option<&Rain>, Option<&Thunder>), With<Instance>>,
 ) {
-    for (mut client, loc) in &mut clients {
-        if let Ok((rain, thunder)) = weathers.get(loc.0) {
-            if let Some(level) = rain {
-                client.write_packet(&GameStateChangeS2c {
-                    kind: GameEventKind::BeginRaining,
-                    value: 0.0,
-                });
-
-                client.write_packet(&GameStateChangeS2c {
-                    kind: GameEventKind::RainLevelChange,
-                    value: level.0,
-                });
-            }
-
-            if let Some(level) = thunder {
-                client.write_packet(&GameStateChangeS2c {
-                    kind: GameEventKind::ThunderLevelChange,
-                    value: level.0,
-                });
-            }
+    for (mut client, loc, client_rain, client_thunder) in &mut clients {
+        let (instance_rain, instance_thunder) = weathers.get(loc.0).unwrap_or_default();
+
+        if let Some(rain) = client_rain.or(instance_rain) {
+            client.begin_raining();
+            client.set_rain_level(rain.0);
+        }
+
+        if let Some(thunder) = client_thunder.or(instance_thunder) {
+            client.set_thunder_level(thunder.0);
         }
     }
 }
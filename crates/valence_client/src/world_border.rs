@@ -0,0 +1,360 @@
+//! The world border.
+//!
+//! Attach a [`WorldBorder`] to an instance to give it a world border. Changes
+//! made through the methods of [`WorldBorder`] are sent to every client in the
+//! instance, and the whole border is sent to clients when they join or respawn
+//! in the instance, or when the component is inserted. When the component is
+//! removed, the border of the instance is reset to the vanilla default.
+//!
+//! The diameter of the border can be changed gradually with
+//! [`WorldBorder::set_diameter_over`]. Like in vanilla, the change is timed in
+//! real time rather than in ticks, so the size of the border on the server
+//! agrees with the animation seen by clients.
+
+use std::time::{Duration, Instant};
+
+use valence_core::packet::s2c::play::{
+    WorldBorderCenterChangedS2c, WorldBorderInitializeS2c, WorldBorderInterpolateSizeS2c,
+    WorldBorderSizeChangedS2c, WorldBorderWarningBlocksChangedS2c,
+    WorldBorderWarningTimeChangedS2c,
+};
+use valence_core::packet::var_long::VarLong;
+
+use super::*;
+
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+struct UpdateWorldBorderPerInstanceSet;
+
+pub(super) fn build(app: &mut App) {
+    app.configure_set(
+        UpdateWorldBorderPerInstanceSet
+            .in_base_set(CoreSet::PostUpdate)
+            .before(WriteUpdatePacketsToInstancesSet),
+    )
+    .add_systems(
+        (update_world_border_per_instance, reset_removed_world_border)
+            .chain()
+            .in_set(UpdateWorldBorderPerInstanceSet),
+    )
+    .add_systems(
+        (
+            init_world_border_on_location_change,
+            init_added_world_border,
+        )
+            .after(respawn)
+            .in_set(UpdateClientsSet),
+    );
+}
+
+/// The vanilla default diameter of the world border.
+pub const DEFAULT_DIAMETER: f64 = 59_999_968.0;
+
+/// The vanilla default distance from the center at which clients are
+/// teleported when using a nether portal.
+pub const DEFAULT_PORTAL_TELEPORT_BOUNDARY: i32 = 29_999_984;
+
+/// The world border of an instance.
+///
+/// The default value is the vanilla default world border, which is centered
+/// at the origin and effectively infinite.
+#[derive(Component, Clone, Debug)]
+pub struct WorldBorder {
+    center: [f64; 2],
+    /// The diameter at the start of the current size change.
+    old_diameter: f64,
+    /// The diameter at the end of the current size change.
+    new_diameter: f64,
+    /// When the current size change started.
+    lerp_start: Instant,
+    /// How long the current size change lasts.
+    lerp_duration: Duration,
+    portal_teleport_boundary: i32,
+    warning_blocks: i32,
+    warning_time: i32,
+    changes: WorldBorderChanges,
+}
+
+/// The parts of a [`WorldBorder`] that have changed since the last tick.
+#[derive(Clone, Copy, Default, Debug)]
+struct WorldBorderChanges {
+    center: bool,
+    diameter: bool,
+    warning_blocks: bool,
+    warning_time: bool,
+    /// Set when a change cannot be sent with the incremental packets.
+    all: bool,
+}
+
+impl WorldBorder {
+    /// Creates a stationary world border with the given center and diameter.
+    /// The other properties have their default values.
+    pub fn new(center: [f64; 2], diameter: f64) -> Self {
+        Self {
+            center,
+            old_diameter: diameter,
+            new_diameter: diameter,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the `[x, z]` position of the center of the border.
+    pub fn center(&self) -> [f64; 2] {
+        self.center
+    }
+
+    /// Sets the `[x, z]` position of the center of the border.
+    pub fn set_center(&mut self, center: [f64; 2]) {
+        if self.center != center {
+            self.center = center;
+            self.changes.center = true;
+        }
+    }
+
+    /// Returns the current diameter of the border, taking into account any size
+    /// change in progress.
+    pub fn diameter(&self) -> f64 {
+        self.diameter_at(Instant::now())
+    }
+
+    /// Returns the diameter the border will have once the current size change
+    /// is finished. This is the same as [`Self::diameter`] when the border is
+    /// not changing size.
+    pub fn target_diameter(&self) -> f64 {
+        self.new_diameter
+    }
+
+    /// Returns the time left until the current size change is finished, or
+    /// [`Duration::ZERO`] if the border is not changing size.
+    pub fn remaining_time(&self) -> Duration {
+        self.remaining_time_at(Instant::now())
+    }
+
+    /// Sets the diameter of the border immediately, cancelling any size change
+    /// in progress.
+    pub fn set_diameter(&mut self, diameter: f64) {
+        self.set_diameter_over(diameter, Duration::ZERO);
+    }
+
+    /// Gradually changes the diameter of the border from its current value to
+    /// `diameter` over the given real-time duration.
+    ///
+    /// A duration of zero sets the diameter immediately.
+    pub fn set_diameter_over(&mut self, diameter: f64, duration: Duration) {
+        let now = Instant::now();
+
+        self.old_diameter = self.diameter_at(now);
+        self.new_diameter = diameter;
+        self.lerp_start = now;
+        self.lerp_duration = duration;
+        self.changes.diameter = true;
+    }
+
+    /// Returns the distance from the center at which clients using a nether
+    /// portal are teleported.
+    pub fn portal_teleport_boundary(&self) -> i32 {
+        self.portal_teleport_boundary
+    }
+
+    /// Sets the distance from the center at which clients using a nether
+    /// portal are teleported.
+    ///
+    /// There is no packet to change this on its own, so the whole border is
+    /// sent to clients again.
+    pub fn set_portal_teleport_boundary(&mut self, boundary: i32) {
+        if self.portal_teleport_boundary != boundary {
+            self.portal_teleport_boundary = boundary;
+            self.changes.all = true;
+        }
+    }
+
+    /// Returns the distance in blocks from the border at which clients see the
+    /// warning effect.
+    pub fn warning_blocks(&self) -> i32 {
+        self.warning_blocks
+    }
+
+    /// Sets the distance in blocks from the border at which clients see the
+    /// warning effect.
+    pub fn set_warning_blocks(&mut self, blocks: i32) {
+        if self.warning_blocks != blocks {
+            self.warning_blocks = blocks;
+            self.changes.warning_blocks = true;
+        }
+    }
+
+    /// Returns the time in seconds before a shrinking border reaches clients
+    /// at which they see the warning effect.
+    pub fn warning_time(&self) -> i32 {
+        self.warning_time
+    }
+
+    /// Sets the time in seconds before a shrinking border reaches clients at
+    /// which they see the warning effect.
+    pub fn set_warning_time(&mut self, seconds: i32) {
+        if self.warning_time != seconds {
+            self.warning_time = seconds;
+            self.changes.warning_time = true;
+        }
+    }
+
+    fn diameter_at(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.lerp_start);
+
+        if elapsed >= self.lerp_duration {
+            return self.new_diameter;
+        }
+
+        let t = elapsed.as_secs_f64() / self.lerp_duration.as_secs_f64();
+
+        self.old_diameter + (self.new_diameter - self.old_diameter) * t
+    }
+
+    fn remaining_time_at(&self, now: Instant) -> Duration {
+        self.lerp_duration
+            .saturating_sub(now.saturating_duration_since(self.lerp_start))
+    }
+
+    /// Returns the packet describing the entire border at this moment.
+    fn initialize_packet(&self) -> WorldBorderInitializeS2c {
+        let now = Instant::now();
+
+        WorldBorderInitializeS2c {
+            x: self.center[0],
+            z: self.center[1],
+            old_diameter: self.diameter_at(now),
+            new_diameter: self.new_diameter,
+            speed: VarLong(self.remaining_time_at(now).as_millis() as i64),
+            portal_teleport_boundary: VarInt(self.portal_teleport_boundary),
+            warning_blocks: VarInt(self.warning_blocks),
+            warning_time: VarInt(self.warning_time),
+        }
+    }
+
+    /// Writes the packets for the changes made since the last call.
+    fn write_changes(&mut self, instance: &mut Instance) {
+        let changes = std::mem::take(&mut self.changes);
+
+        if changes.all {
+            instance.write_packet(&self.initialize_packet());
+            return;
+        }
+
+        if changes.center {
+            instance.write_packet(&WorldBorderCenterChangedS2c {
+                x_pos: self.center[0],
+                z_pos: self.center[1],
+            });
+        }
+
+        if changes.diameter {
+            if self.lerp_duration.is_zero() {
+                instance.write_packet(&WorldBorderSizeChangedS2c {
+                    diameter: self.new_diameter,
+                });
+            } else {
+                // Time may have passed since the change was made, so send what is
+                // left of it.
+                let now = Instant::now();
+
+                instance.write_packet(&WorldBorderInterpolateSizeS2c {
+                    old_diameter: self.diameter_at(now),
+                    new_diameter: self.new_diameter,
+                    speed: VarLong(self.remaining_time_at(now).as_millis() as i64),
+                });
+            }
+        }
+
+        if changes.warning_blocks {
+            instance.write_packet(&WorldBorderWarningBlocksChangedS2c {
+                warning_blocks: VarInt(self.warning_blocks),
+            });
+        }
+
+        if changes.warning_time {
+            instance.write_packet(&WorldBorderWarningTimeChangedS2c {
+                warning_time: VarInt(self.warning_time),
+            });
+        }
+    }
+}
+
+impl Default for WorldBorder {
+    fn default() -> Self {
+        Self {
+            center: [0.0, 0.0],
+            old_diameter: DEFAULT_DIAMETER,
+            new_diameter: DEFAULT_DIAMETER,
+            lerp_start: Instant::now(),
+            lerp_duration: Duration::ZERO,
+            portal_teleport_boundary: DEFAULT_PORTAL_TELEPORT_BOUNDARY,
+            warning_blocks: 5,
+            warning_time: 15,
+            changes: WorldBorderChanges::default(),
+        }
+    }
+}
+
+fn update_world_border_per_instance(
+    mut instances: Query<(&mut Instance, &mut WorldBorder), Changed<WorldBorder>>,
+) {
+    for (mut instance, mut border) in &mut instances {
+        // Clients in the instance are sent the whole border when they join, so
+        // there is nothing to send for a border that was just added.
+        if border.is_added() {
+            border.bypass_change_detection().changes = WorldBorderChanges::default();
+        }
+
+        border
+            .bypass_change_detection()
+            .write_changes(&mut instance);
+    }
+}
+
+fn reset_removed_world_border(
+    mut instances: Query<&mut Instance, Without<WorldBorder>>,
+    mut removed: RemovedComponents<WorldBorder>,
+) {
+    for entity in &mut removed {
+        if let Ok(mut instance) = instances.get_mut(entity) {
+            instance.write_packet(&WorldBorder::default().initialize_packet());
+        }
+    }
+}
+
+/// Sends the world border to clients which have joined or respawned. Clients
+/// keep their old border when respawning in the same dimension, so clients
+/// moving to an instance without a border are sent the default one.
+fn init_world_border_on_location_change(
+    mut clients: Query<(&mut Client, &Location), Changed<Location>>,
+    borders: Query<&WorldBorder, With<Instance>>,
+) {
+    for (mut client, loc) in &mut clients {
+        if let Ok(border) = borders.get(loc.0) {
+            client.write_packet(&border.initialize_packet());
+        } else if !client.is_added() {
+            client.write_packet(&WorldBorder::default().initialize_packet());
+        }
+    }
+}
+
+/// Sends the world border to the clients already in an instance when the
+/// border is inserted. Clients which joined or respawned this tick are sent
+/// the border by [`init_world_border_on_location_change`].
+fn init_added_world_border(
+    mut clients: Query<(&mut Client, Ref<Location>)>,
+    borders: Query<&WorldBorder, (With<Instance>, Added<WorldBorder>)>,
+) {
+    if borders.is_empty() {
+        return;
+    }
+
+    for (mut client, loc) in &mut clients {
+        if loc.is_changed() {
+            continue;
+        }
+
+        if let Ok(border) = borders.get(loc.0) {
+            client.write_packet(&border.initialize_packet());
+        }
+    }
+}
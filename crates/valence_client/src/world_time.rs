@@ -0,0 +1,230 @@
+//! The time of day.
+//!
+//! Attach a [`WorldTime`] to an instance to give it a day/night cycle. The time
+//! is advanced every tick and sent to the clients in the instance at a regular
+//! interval, since clients advance the time on their own in between. Changes
+//! made through the methods of [`WorldTime`] are sent immediately, and the time
+//! is sent to clients when they join or respawn in the instance.
+
+use valence_core::packet::s2c::play::WorldTimeUpdateS2c;
+
+use super::*;
+
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+struct UpdateWorldTimePerInstanceSet;
+
+pub(super) fn build(app: &mut App) {
+    app.configure_set(
+        UpdateWorldTimePerInstanceSet
+            .in_base_set(CoreSet::PostUpdate)
+            .before(WriteUpdatePacketsToInstancesSet),
+    )
+    .add_system(update_world_time_per_instance.in_set(UpdateWorldTimePerInstanceSet))
+    .add_system(
+        init_world_time_on_location_change
+            .after(respawn)
+            .in_set(UpdateClientsSet),
+    );
+}
+
+/// The number of ticks in a Minecraft day.
+pub const DAY_LENGTH: i64 = 24000;
+
+/// The age of an instance and its time of day, both in ticks.
+///
+/// The time of day is stored as the total number of ticks since the first
+/// day, so the day count and the phase of the moon are kept when the time
+/// wraps around to the next day.
+#[derive(Component, Clone, Debug)]
+pub struct WorldTime {
+    world_age: i64,
+    day_time: i64,
+    frozen: bool,
+    sync_interval: u32,
+    /// Ticks since the time was last sent to clients.
+    ticks_since_sync: u32,
+    /// If the time was changed and should be sent to clients immediately.
+    changed: bool,
+}
+
+impl WorldTime {
+    /// The time of day at which `set_day` sets the time.
+    pub const DAY: i64 = 1000;
+    /// The time of day at which `set_noon` sets the time.
+    pub const NOON: i64 = 6000;
+    /// The time of day at which `set_night` sets the time.
+    pub const NIGHT: i64 = 13000;
+    /// The time of day at which `set_midnight` sets the time.
+    pub const MIDNIGHT: i64 = 18000;
+
+    /// Creates a new world time starting at the given time of day on the first
+    /// day.
+    pub fn new(time_of_day: i64) -> Self {
+        Self {
+            day_time: time_of_day.rem_euclid(DAY_LENGTH),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the number of ticks the instance has existed for. This is
+    /// advanced every tick, even when the time of day is frozen.
+    pub fn world_age(&self) -> i64 {
+        self.world_age
+    }
+
+    /// Sets the number of ticks the instance has existed for.
+    pub fn set_world_age(&mut self, world_age: i64) {
+        self.world_age = world_age;
+        self.changed = true;
+    }
+
+    /// Returns the time of day in the range `0..DAY_LENGTH`. 0 is sunrise,
+    /// 6000 is noon, 12000 is sunset, and 18000 is midnight.
+    pub fn time_of_day(&self) -> i64 {
+        self.day_time.rem_euclid(DAY_LENGTH)
+    }
+
+    /// Sets the time of day without changing the current day. The time wraps
+    /// around if it is outside of `0..DAY_LENGTH`.
+    pub fn set_time_of_day(&mut self, time_of_day: i64) {
+        self.day_time = self.day() * DAY_LENGTH + time_of_day.rem_euclid(DAY_LENGTH);
+        self.changed = true;
+    }
+
+    /// Returns the number of days that have passed, starting at zero.
+    pub fn day(&self) -> i64 {
+        self.day_time.div_euclid(DAY_LENGTH)
+    }
+
+    /// Returns the total number of ticks of daytime that have passed. Unlike
+    /// [`Self::time_of_day`], this does not wrap around at the end of the day.
+    pub fn day_time(&self) -> i64 {
+        self.day_time
+    }
+
+    /// Sets the total number of ticks of daytime that have passed. Negative
+    /// values are treated as zero.
+    pub fn set_day_time(&mut self, day_time: i64) {
+        self.day_time = day_time.max(0);
+        self.changed = true;
+    }
+
+    /// Advances the time of day by the given number of ticks, moving to the
+    /// next day if needed. Negative values turn the time back, but never before
+    /// the first day.
+    pub fn add_time(&mut self, ticks: i64) {
+        self.set_day_time(self.day_time.saturating_add(ticks));
+    }
+
+    /// Sets the time of day to [`Self::DAY`].
+    pub fn set_day(&mut self) {
+        self.set_time_of_day(Self::DAY);
+    }
+
+    /// Sets the time of day to [`Self::NOON`].
+    pub fn set_noon(&mut self) {
+        self.set_time_of_day(Self::NOON);
+    }
+
+    /// Sets the time of day to [`Self::NIGHT`].
+    pub fn set_night(&mut self) {
+        self.set_time_of_day(Self::NIGHT);
+    }
+
+    /// Sets the time of day to [`Self::MIDNIGHT`].
+    pub fn set_midnight(&mut self) {
+        self.set_time_of_day(Self::MIDNIGHT);
+    }
+
+    /// Returns whether the time of day is frozen. See [`Self::set_frozen`].
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Sets whether the time of day is frozen. When frozen, the time of day is
+    /// no longer advanced on the server or on clients, but the world age is.
+    pub fn set_frozen(&mut self, frozen: bool) {
+        if self.frozen != frozen {
+            self.frozen = frozen;
+            self.changed = true;
+        }
+    }
+
+    /// Returns the number of ticks between each time the time is sent to
+    /// clients.
+    pub fn sync_interval(&self) -> u32 {
+        self.sync_interval
+    }
+
+    /// Sets the number of ticks between each time the time is sent to clients.
+    /// Clients advance the time on their own in between, so this only corrects
+    /// drift. The default is 20, like in vanilla.
+    pub fn set_sync_interval(&mut self, ticks: u32) {
+        self.sync_interval = ticks.max(1);
+    }
+
+    fn tick(&mut self) {
+        self.world_age = self.world_age.wrapping_add(1);
+
+        if !self.frozen {
+            self.day_time = self.day_time.saturating_add(1);
+        }
+    }
+
+    fn packet(&self) -> WorldTimeUpdateS2c {
+        // A negative time of day tells the client that the time is frozen. The
+        // client takes the absolute value, so zero must be sent as -1.
+        let time_of_day = if self.frozen {
+            (-self.day_time).min(-1)
+        } else {
+            self.day_time
+        };
+
+        WorldTimeUpdateS2c {
+            world_age: self.world_age,
+            time_of_day,
+        }
+    }
+}
+
+impl Default for WorldTime {
+    fn default() -> Self {
+        Self {
+            world_age: 0,
+            day_time: 0,
+            frozen: false,
+            sync_interval: 20,
+            ticks_since_sync: 0,
+            changed: false,
+        }
+    }
+}
+
+fn update_world_time_per_instance(mut instances: Query<(&mut Instance, &mut WorldTime)>) {
+    for (mut instance, mut time) in &mut instances {
+        // Avoid triggering change detection every tick.
+        let time = time.bypass_change_detection();
+
+        time.tick();
+        time.ticks_since_sync += 1;
+
+        if time.changed || time.ticks_since_sync >= time.sync_interval {
+            instance.write_packet(&time.packet());
+
+            time.changed = false;
+            time.ticks_since_sync = 0;
+        }
+    }
+}
+
+/// Sends the time to clients which have joined or respawned.
+fn init_world_time_on_location_change(
+    mut clients: Query<(&mut Client, &Location), Changed<Location>>,
+    times: Query<&WorldTime, With<Instance>>,
+) {
+    for (mut client, loc) in &mut clients {
+        if let Ok(time) = times.get(loc.0) {
+            client.write_packet(&time.packet());
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use bevy_ecs::prelude::{Entity, EventReader, EventWriter};
+use valence_client::event_loop::PacketEvent;
+use valence_core::packet::c2s::play::{CommandExecutionC2s, RequestCommandCompletionsC2s};
+
+/// Sent when a client runs a command from its chat box.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CommandExecution {
+    pub client: Entity,
+    /// The command without the leading slash.
+    pub command: String,
+    /// The time at which the command was sent, in milliseconds since the Unix
+    /// epoch.
+    pub timestamp: u64,
+}
+
+/// Sent when a client asks for the completions of a command it is typing. This
+/// only happens for arguments whose suggestions are provided by
+/// [`Suggestion::AskServer`](crate::Suggestion::AskServer).
+///
+/// Reply with `Client::send_completions`, passing the transaction ID of the
+/// request along.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CompletionRequest {
+    pub client: Entity,
+    pub transaction_id: i32,
+    /// The command typed so far, up to the cursor of the client. Unlike
+    /// [`CommandExecution::command`], this includes the leading slash.
+    pub text: String,
+}
+
+impl CompletionRequest {
+    /// Returns the argument under the cursor of the client, which is the part
+    /// of the text after the last space. The argument is empty if the text
+    /// ends with a space.
+    pub fn argument(&self) -> &str {
+        &self.text[self.argument_byte_start()..]
+    }
+
+    /// Returns the position of [`Self::argument`] in the text, which can be
+    /// passed to `Client::send_completions` to replace the argument with the
+    /// completions.
+    ///
+    /// The position is counted in UTF-16 code units, as the client expects.
+    pub fn argument_start(&self) -> usize {
+        self.text[..self.argument_byte_start()]
+            .encode_utf16()
+            .count()
+    }
+
+    /// Returns the length of [`Self::argument`] in UTF-16 code units.
+    pub fn argument_len(&self) -> usize {
+        self.argument().encode_utf16().count()
+    }
+
+    fn argument_byte_start(&self) -> usize {
+        match self.text.rfind(' ') {
+            Some(idx) => idx + 1,
+            // The first argument is the name of the command, after the slash.
+            None => usize::from(self.text.starts_with('/')),
+        }
+    }
+}
+
+pub(crate) fn handle_command_packets(
+    mut packets: EventReader<PacketEvent>,
+    mut execution_events: EventWriter<CommandExecution>,
+    mut completion_events: EventWriter<CompletionRequest>,
+) {
+    for packet in packets.iter() {
+        if let Some(pkt) = packet.decode::<CommandExecutionC2s>() {
+            execution_events.send(CommandExecution {
+                client: packet.client,
+                command: pkt.command.into(),
+                timestamp: pkt.timestamp,
+            });
+        } else if let Some(pkt) = packet.decode::<RequestCommandCompletionsC2s>() {
+            completion_events.send(CompletionRequest {
+                client: packet.client,
+                transaction_id: pkt.transaction_id.0,
+                text: pkt.text.into(),
+            });
+        }
+    }
+}
@@ -0,0 +1,376 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+pub mod event;
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Context;
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use event::{handle_command_packets, CommandExecution, CompletionRequest};
+use tracing::warn;
+use valence_client::event_loop::{EventLoopSchedule, EventLoopSet};
+use valence_client::{Client, FlushPacketsSet, OpLevel};
+use valence_core::packet::encode::WritePacket;
+use valence_core::packet::s2c::play::command_tree::{Node, NodeData};
+pub use valence_core::packet::s2c::play::command_tree::{Parser, StringArg, Suggestion};
+use valence_core::packet::s2c::play::CommandTreeS2c;
+use valence_core::packet::var_int::VarInt;
+
+pub struct CommandPlugin;
+
+/// The [`SystemSet`] in [`CoreSet::PostUpdate`] where the command trees are
+/// sent to clients. Changes made to the trees after this set are sent the
+/// next tick.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UpdateCommandTreesSet;
+
+impl Plugin for CommandPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_set(
+            UpdateCommandTreesSet
+                .in_base_set(CoreSet::PostUpdate)
+                .before(FlushPacketsSet),
+        )
+        .add_system(update_command_trees.in_set(UpdateCommandTreesSet))
+        .add_event::<CommandExecution>()
+        .add_event::<CompletionRequest>()
+        .add_system(
+            handle_command_packets
+                .in_schedule(EventLoopSchedule)
+                .in_base_set(EventLoopSet::PreUpdate),
+        );
+    }
+}
+
+/// The commands known to a client. Insert this component into a client entity
+/// to enable tab completion and highlighting of the commands in its chat box.
+///
+/// The tree is sent again whenever it changes or the [`OpLevel`] of the client
+/// changes. Commands requiring a higher op level than the one of the client
+/// are left out, so clients with different permissions can share the same
+/// tree.
+///
+/// ```
+/// # use valence_command::{CommandNode, CommandTree, Parser};
+/// let tree = CommandTree::new()
+///     .with_command(
+///         CommandNode::literal("teleport").requires(2).then(
+///             CommandNode::argument(
+///                 "target",
+///                 Parser::Entity {
+///                     single: true,
+///                     only_players: false,
+///                 },
+///             )
+///             .executes(),
+///         ),
+///     )
+///     .with_command(
+///         CommandNode::literal("tp")
+///             .requires(2)
+///             .redirect(["teleport"]),
+///     );
+///
+/// let pkt = tree.to_packet(0).unwrap();
+///
+/// // Only the root node is visible to clients which are not op.
+/// assert_eq!(pkt.commands.len(), 1);
+/// ```
+#[derive(Component, Clone, Default, Debug)]
+pub struct CommandTree {
+    commands: Vec<CommandNode>,
+}
+
+impl CommandTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the tree with the command added. See [`Self::add_command`].
+    #[must_use]
+    pub fn with_command(mut self, command: CommandNode) -> Self {
+        self.add_command(command);
+        self
+    }
+
+    /// Adds a command to the tree, replacing any command with the same name.
+    pub fn add_command(&mut self, command: CommandNode) {
+        self.commands.retain(|c| c.name != command.name);
+        self.commands.push(command);
+    }
+
+    /// Removes the command with the given name from the tree. Returns the
+    /// removed command, if any.
+    pub fn remove_command(&mut self, name: &str) -> Option<CommandNode> {
+        let idx = self.commands.iter().position(|c| c.name == name)?;
+        Some(self.commands.remove(idx))
+    }
+
+    /// Returns the top-level nodes of the tree.
+    pub fn commands(&self) -> &[CommandNode] {
+        &self.commands
+    }
+
+    /// Flattens the tree into a packet, leaving out the nodes requiring an
+    /// op level higher than `op_level`.
+    ///
+    /// Returns an error if a redirect points to a node which does not exist
+    /// or is left out.
+    pub fn to_packet(&self, op_level: u8) -> anyhow::Result<CommandTreeS2c<'_>> {
+        let mut flattener = Flattener {
+            op_level,
+            nodes: vec![Node {
+                children: vec![],
+                data: NodeData::Root,
+                executable: false,
+                redirect_node: None,
+            }],
+            path: vec![],
+            indices: HashMap::from([(vec![], 0)]),
+            redirects: vec![],
+        };
+
+        let children = flattener.flatten_children(&self.commands);
+        flattener.nodes[0].children = children;
+
+        for (idx, path) in flattener.redirects {
+            let key = path.iter().map(String::as_str).collect::<Vec<_>>();
+
+            let target = flattener.indices.get(&key).with_context(|| {
+                format!("redirect target \"{}\" does not exist", path.join(" "))
+            })?;
+
+            flattener.nodes[idx].redirect_node = Some(VarInt(*target as i32));
+        }
+
+        Ok(CommandTreeS2c {
+            commands: flattener.nodes,
+            root_index: VarInt(0),
+        })
+    }
+}
+
+struct Flattener<'a> {
+    op_level: u8,
+    nodes: Vec<Node<'a>>,
+    /// The names of the nodes from the root to the current node.
+    path: Vec<&'a str>,
+    /// The index of every node by path.
+    indices: HashMap<Vec<&'a str>, usize>,
+    /// The nodes with a redirect and the path to their target.
+    redirects: Vec<(usize, &'a [String])>,
+}
+
+impl<'a> Flattener<'a> {
+    fn flatten_children(&mut self, children: &'a [CommandNode]) -> Vec<VarInt> {
+        let op_level = self.op_level;
+
+        children
+            .iter()
+            .filter(|child| child.op_level <= op_level)
+            .map(|child| self.flatten(child))
+            .collect()
+    }
+
+    fn flatten(&mut self, node: &'a CommandNode) -> VarInt {
+        let idx = self.nodes.len();
+
+        self.nodes.push(Node {
+            children: vec![],
+            data: match &node.kind {
+                NodeKind::Literal => NodeData::Literal { name: &node.name },
+                NodeKind::Argument { parser, suggestion } => NodeData::Argument {
+                    name: &node.name,
+                    parser: parser.clone(),
+                    suggestion: *suggestion,
+                },
+            },
+            executable: node.executable,
+            redirect_node: None,
+        });
+
+        self.path.push(&node.name);
+        self.indices.insert(self.path.clone(), idx);
+
+        if let Some(redirect) = &node.redirect {
+            self.redirects.push((idx, redirect));
+        }
+
+        let children = self.flatten_children(&node.children);
+        self.nodes[idx].children = children;
+
+        self.path.pop();
+
+        VarInt(idx as i32)
+    }
+}
+
+/// A literal or an argument in a [`CommandTree`], along with the nodes which
+/// can follow it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CommandNode {
+    name: String,
+    kind: NodeKind,
+    children: Vec<CommandNode>,
+    executable: bool,
+    redirect: Option<Vec<String>>,
+    op_level: u8,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum NodeKind {
+    Literal,
+    Argument {
+        parser: Parser<'static>,
+        suggestion: Option<Suggestion>,
+    },
+}
+
+impl CommandNode {
+    /// Returns a node matching the given word exactly.
+    pub fn literal(name: impl Into<String>) -> Self {
+        Self::new(name.into(), NodeKind::Literal)
+    }
+
+    /// Returns a node matching a value parsed by `parser`. The name is shown
+    /// to the client as a hint.
+    pub fn argument(name: impl Into<String>, parser: Parser<'static>) -> Self {
+        Self::new(
+            name.into(),
+            NodeKind::Argument {
+                parser,
+                suggestion: None,
+            },
+        )
+    }
+
+    fn new(name: String, kind: NodeKind) -> Self {
+        Self {
+            name,
+            kind,
+            children: vec![],
+            executable: false,
+            redirect: None,
+            op_level: 0,
+        }
+    }
+
+    /// Returns the node with the child added, replacing any child with the
+    /// same name.
+    #[must_use]
+    pub fn then(mut self, child: CommandNode) -> Self {
+        self.children.retain(|c| c.name != child.name);
+        self.children.push(child);
+        self
+    }
+
+    /// Returns the node marked as a valid end of a command. Commands ending on
+    /// other nodes are shown as incomplete by the client.
+    #[must_use]
+    pub fn executes(mut self) -> Self {
+        self.executable = true;
+        self
+    }
+
+    /// Returns the node with a redirect to the node at the given path of
+    /// names, starting from the root. The client continues parsing the
+    /// command from the children of the target. An empty path redirects to
+    /// the root, like `execute run`.
+    #[must_use]
+    pub fn redirect<I>(mut self, path: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.redirect = Some(path.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Returns the node with the completions of the argument provided by the
+    /// given source. Has no effect on literals.
+    #[must_use]
+    pub fn suggests(mut self, suggestion: Suggestion) -> Self {
+        if let NodeKind::Argument { suggestion: s, .. } = &mut self.kind {
+            *s = Some(suggestion);
+        }
+
+        self
+    }
+
+    /// Returns the node hidden from clients with an [`OpLevel`] lower than
+    /// the given level, along with its children.
+    #[must_use]
+    pub fn requires(mut self, op_level: u8) -> Self {
+        self.op_level = op_level;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn children(&self) -> &[CommandNode] {
+        &self.children
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.executable
+    }
+
+    /// Returns the parser of the node if it is an argument.
+    pub fn parser(&self) -> Option<&Parser<'static>> {
+        match &self.kind {
+            NodeKind::Literal => None,
+            NodeKind::Argument { parser, .. } => Some(parser),
+        }
+    }
+
+    /// Returns the minimum op level needed to see the node.
+    pub fn op_level(&self) -> u8 {
+        self.op_level
+    }
+}
+
+fn update_command_trees(
+    mut clients: Query<(Entity, &mut Client, Ref<CommandTree>, Ref<OpLevel>)>,
+    mut new_clients: Local<BTreeSet<Entity>>,
+) {
+    // Forget the clients which disconnected before the tree was sent.
+    new_clients.retain(|&e| clients.contains(e));
+
+    for (entity, mut client, tree, op_level) in &mut clients {
+        if client.is_added() {
+            // Clients which were just added have not joined the game yet. The
+            // tree is sent to them the next tick.
+            new_clients.insert(entity);
+            continue;
+        }
+
+        if !new_clients.remove(&entity) && !tree.is_changed() && !op_level.is_changed() {
+            continue;
+        }
+
+        match tree.to_packet(op_level.get()) {
+            Ok(pkt) => client.write_packet(&pkt),
+            Err(e) => warn!("failed to send the command tree of client {entity:?}: {e:#}"),
+        }
+    }
+}
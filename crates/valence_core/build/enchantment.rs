@@ -0,0 +1,325 @@
+use anyhow::bail;
+use heck::ToPascalCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::Deserialize;
+use valence_build_utils::ident;
+
+#[derive(Deserialize, Debug)]
+pub struct Enchantment {
+    id: u16,
+    name: String,
+    translation_key: String,
+    min_level: u8,
+    max_level: u8,
+    rarity_weight: u8,
+    cursed: bool,
+    target: String,
+    acceptable_items: Vec<String>,
+    incompatible: Vec<String>,
+}
+
+pub fn build() -> anyhow::Result<TokenStream> {
+    let enchantments =
+        serde_json::from_str::<Vec<Enchantment>>(include_str!("../../../extracted/enchants.json"))?;
+
+    let enchantment_count = enchantments.len();
+
+    let enchantment_variants = enchantments
+        .iter()
+        .map(|enchantment| ident(enchantment.name.to_pascal_case()))
+        .collect::<Vec<_>>();
+
+    let enchantment_from_raw_id_arms = enchantments
+        .iter()
+        .map(|enchantment| {
+            let id = &enchantment.id;
+            let name = ident(enchantment.name.to_pascal_case());
+
+            quote! {
+                #id => Some(Self::#name),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_to_raw_id_arms = enchantments
+        .iter()
+        .map(|enchantment| {
+            let id = &enchantment.id;
+            let name = ident(enchantment.name.to_pascal_case());
+
+            quote! {
+                Self::#name => #id,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_from_str_arms = enchantments
+        .iter()
+        .map(|enchantment| {
+            let str_name = &enchantment.name;
+            let name = ident(str_name.to_pascal_case());
+
+            quote! {
+                #str_name => Some(Self::#name),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_to_str_arms = enchantments
+        .iter()
+        .map(|enchantment| {
+            let str_name = &enchantment.name;
+            let name = ident(str_name.to_pascal_case());
+
+            quote! {
+                Self::#name => #str_name,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_to_ident_arms = enchantments
+        .iter()
+        .map(|enchantment| {
+            let str_name = &enchantment.name;
+            let name = ident(str_name.to_pascal_case());
+
+            quote! {
+                Self::#name => ident!(#str_name),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_translation_key_arms = enchantments
+        .iter()
+        .map(|enchantment| {
+            let name = ident(enchantment.name.to_pascal_case());
+            let translation_key = &enchantment.translation_key;
+
+            quote! {
+                Self::#name => #translation_key,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_min_level_arms = enchantments
+        .iter()
+        .map(|enchantment| {
+            let name = ident(enchantment.name.to_pascal_case());
+            let min_level = &enchantment.min_level;
+
+            quote! {
+                Self::#name => #min_level,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_max_level_arms = enchantments
+        .iter()
+        .map(|enchantment| {
+            let name = ident(enchantment.name.to_pascal_case());
+            let max_level = &enchantment.max_level;
+
+            quote! {
+                Self::#name => #max_level,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_is_cursed_arms = enchantments
+        .iter()
+        .filter(|enchantment| enchantment.cursed)
+        .map(|enchantment| {
+            let name = ident(enchantment.name.to_pascal_case());
+
+            quote! {
+                Self::#name => true,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_rarity_arms = enchantments
+        .iter()
+        .map(|enchantment| {
+            let name = ident(enchantment.name.to_pascal_case());
+            let rarity = match enchantment.rarity_weight {
+                10 => ident("Common"),
+                5 => ident("Uncommon"),
+                2 => ident("Rare"),
+                1 => ident("VeryRare"),
+                weight => bail!("unknown rarity weight {weight} of {}", enchantment.name),
+            };
+
+            Ok(quote! {
+                Self::#name => EnchantmentRarity::#rarity,
+            })
+        })
+        .collect::<anyhow::Result<TokenStream>>()?;
+
+    let enchantment_target_arms = enchantments
+        .iter()
+        .map(|enchantment| {
+            let name = ident(enchantment.name.to_pascal_case());
+            let target = ident(enchantment.target.to_pascal_case());
+
+            quote! {
+                Self::#name => EnchantmentTarget::#target,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_can_enchant_arms = enchantments
+        .iter()
+        .filter(|enchantment| !enchantment.acceptable_items.is_empty())
+        .map(|enchantment| {
+            let name = ident(enchantment.name.to_pascal_case());
+            let items = enchantment
+                .acceptable_items
+                .iter()
+                .map(|item| ident(item.replace('.', "_").to_pascal_case()));
+
+            quote! {
+                Self::#name => matches!(item, #(ItemKind::#items)|*),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let enchantment_incompatible_arms = enchantments
+        .iter()
+        .filter(|enchantment| !enchantment.incompatible.is_empty())
+        .map(|enchantment| {
+            let name = ident(enchantment.name.to_pascal_case());
+            let incompatible = enchantment
+                .incompatible
+                .iter()
+                .map(|other| ident(other.to_pascal_case()));
+
+            quote! {
+                Self::#name => &[#(Self::#incompatible,)*],
+            }
+        })
+        .collect::<TokenStream>();
+
+    Ok(quote! {
+        /// Represents an enchantment from the game.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        #[repr(u16)]
+        pub enum Enchantment {
+            #(#enchantment_variants,)*
+        }
+
+        impl Enchantment {
+            /// Constructs an enchantment from a raw enchantment ID.
+            ///
+            /// If the given ID is invalid, `None` is returned.
+            pub const fn from_raw(id: u16) -> Option<Self> {
+                match id {
+                    #enchantment_from_raw_id_arms
+                    _ => None
+                }
+            }
+
+            /// Gets the raw enchantment ID from the enchantment.
+            pub const fn to_raw(self) -> u16 {
+                match self {
+                    #enchantment_to_raw_id_arms
+                }
+            }
+
+            /// Construct an enchantment from its snake_case name.
+            ///
+            /// Returns `None` if the name is invalid.
+            #[allow(clippy::should_implement_trait)]
+            pub fn from_str(name: &str) -> Option<Self> {
+                match name {
+                    #enchantment_from_str_arms
+                    _ => None
+                }
+            }
+
+            /// Gets the snake_case name of this enchantment.
+            pub const fn to_str(self) -> &'static str {
+                match self {
+                    #enchantment_to_str_arms
+                }
+            }
+
+            /// Gets the identifier of this enchantment.
+            pub const fn to_ident(self) -> Ident<&'static str> {
+                match self {
+                    #enchantment_to_ident_arms
+                }
+            }
+
+            /// Gets the translation key of the name of this enchantment.
+            pub const fn translation_key(self) -> &'static str {
+                match self {
+                    #enchantment_translation_key_arms
+                }
+            }
+
+            /// Gets the lowest level of this enchantment obtainable in
+            /// survival.
+            pub const fn min_level(self) -> u8 {
+                match self {
+                    #enchantment_min_level_arms
+                }
+            }
+
+            /// Gets the highest level of this enchantment obtainable in
+            /// survival.
+            pub const fn max_level(self) -> u8 {
+                match self {
+                    #enchantment_max_level_arms
+                }
+            }
+
+            /// Returns `true` if this enchantment is a curse.
+            pub const fn is_cursed(self) -> bool {
+                match self {
+                    #enchantment_is_cursed_arms
+                    _ => false
+                }
+            }
+
+            /// Gets how common this enchantment is in the enchanting table and in
+            /// loot.
+            pub const fn rarity(self) -> EnchantmentRarity {
+                match self {
+                    #enchantment_rarity_arms
+                }
+            }
+
+            /// Gets the category of items this enchantment is meant for.
+            ///
+            /// Some enchantments accept items outside of their target. Use
+            /// [`Enchantment::can_enchant`] to check a specific item.
+            pub const fn target(self) -> EnchantmentTarget {
+                match self {
+                    #enchantment_target_arms
+                }
+            }
+
+            /// Returns `true` if this enchantment can be applied to the given
+            /// item with the `/enchant` command.
+            pub const fn can_enchant(self, item: ItemKind) -> bool {
+                match self {
+                    #enchantment_can_enchant_arms
+                    _ => false
+                }
+            }
+
+            /// Gets the other enchantments which can't be on the same item as
+            /// this one, such as the different kinds of protection.
+            pub const fn incompatible(self) -> &'static [Self] {
+                match self {
+                    #enchantment_incompatible_arms
+                    _ => &[]
+                }
+            }
+
+            /// An array of all enchantments.
+            pub const ALL: [Self; #enchantment_count] = [#(Self::#enchantment_variants,)*];
+        }
+    })
+}
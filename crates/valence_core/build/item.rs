@@ -14,6 +14,8 @@ struct Item {
     max_durability: u16,
     enchantability: u8,
     fireproof: bool,
+    fuel_time: u16,
+    recipe_remainder: Option<String>,
     food: Option<FoodComponent>,
 }
 
@@ -170,6 +172,32 @@ pub fn build() -> anyhow::Result<TokenStream> {
         })
         .collect::<TokenStream>();
 
+    let item_kind_to_fuel_time_arms = items
+        .iter()
+        .filter(|item| item.fuel_time != 0)
+        .map(|item| {
+            let name = ident(item.name.replace('.', "_").to_pascal_case());
+            let fuel_time = item.fuel_time;
+
+            quote! {
+                Self::#name => #fuel_time,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let item_kind_to_recipe_remainder_arms = items
+        .iter()
+        .filter_map(|item| {
+            let remainder = item.recipe_remainder.as_ref()?;
+            let name = ident(item.name.replace('.', "_").to_pascal_case());
+            let remainder = ident(remainder.replace('.', "_").to_pascal_case());
+
+            Some(quote! {
+                Self::#name => Some(Self::#remainder),
+            })
+        })
+        .collect::<TokenStream>();
+
     Ok(quote! {
         /// Represents an item from the game
         #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
@@ -250,6 +278,12 @@ pub fn build() -> anyhow::Result<TokenStream> {
                 }
             }
 
+            /// Returns `true` if the item can be eaten. See
+            /// [`Self::food_component`] for the nutrition of the item.
+            pub const fn is_food(self) -> bool {
+                self.food_component().is_some()
+            }
+
             /// Returns the maximum durability before the item will break.
             ///
             /// If the item doesn't have durability, `0` is returned.
@@ -279,6 +313,24 @@ pub fn build() -> anyhow::Result<TokenStream> {
                 }
             }
 
+            /// Gets the number of ticks this item burns for when used as fuel
+            /// in a furnace, or 0 if it isn't fuel.
+            pub const fn fuel_time(self) -> u16 {
+                match self {
+                    #item_kind_to_fuel_time_arms
+                    _ => 0
+                }
+            }
+
+            /// Gets the item left behind in the crafting grid when this item
+            /// is used in a recipe, such as the bucket of a milk bucket.
+            pub const fn recipe_remainder(self) -> Option<Self> {
+                match self {
+                    #item_kind_to_recipe_remainder_arms
+                    _ => None
+                }
+            }
+
             /*
             /// Constructs an item kind from a block kind.
             ///
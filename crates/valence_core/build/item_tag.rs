@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+use heck::ToPascalCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::Deserialize;
+use valence_build_utils::ident;
+
+#[derive(Deserialize, Clone, Debug)]
+struct Tags {
+    /// The names of the items in each item tag.
+    item: BTreeMap<String, Vec<String>>,
+}
+
+pub fn build() -> anyhow::Result<TokenStream> {
+    let Tags { item: tags } =
+        serde_json::from_str::<Tags>(include_str!("../../../extracted/tags.json"))?;
+
+    let tag_variant = |name: &str| ident(name.replace('/', "_").to_pascal_case());
+    let kind_variant = |name: &str| ident(name.replace('.', "_").to_pascal_case());
+
+    let tag_variants = tags
+        .keys()
+        .map(|name| tag_variant(name))
+        .collect::<Vec<_>>();
+
+    let tag_count = tags.len();
+
+    let tag_from_ident_arms = tags
+        .keys()
+        .map(|name| {
+            let variant = tag_variant(name);
+            let ident = format!("minecraft:{name}");
+
+            quote! {
+                #ident => Some(Self::#variant),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let tag_to_ident_arms = tags
+        .keys()
+        .map(|name| {
+            let variant = tag_variant(name);
+            let ident = format!("minecraft:{name}");
+
+            quote! {
+                Self::#variant => ident!(#ident),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let tag_items_arms = tags
+        .iter()
+        .map(|(name, items)| {
+            let variant = tag_variant(name);
+            let kinds = items.iter().map(|i| kind_variant(i));
+
+            quote! {
+                Self::#variant => &[#(ItemKind::#kinds,)*],
+            }
+        })
+        .collect::<TokenStream>();
+
+    let kind_is_in_arms = tags
+        .iter()
+        .map(|(name, items)| {
+            let variant = tag_variant(name);
+            let kinds = items.iter().map(|i| kind_variant(i));
+
+            quote! {
+                ItemTag::#variant => matches!(self, #(Self::#kinds)|*),
+            }
+        })
+        .collect::<TokenStream>();
+
+    Ok(quote! {
+        /// A vanilla item tag, like `#minecraft:wool`. Tags referenced by a
+        /// tag are flattened into its items.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub enum ItemTag {
+            #(#tag_variants,)*
+        }
+
+        impl ItemTag {
+            /// Constructs an item tag from its resource identifier, without
+            /// the leading `#`.
+            ///
+            /// Returns `None` if the tag is not a vanilla item tag.
+            pub fn from_ident(ident: Ident<&str>) -> Option<Self> {
+                match ident.as_str() {
+                    #tag_from_ident_arms
+                    _ => None
+                }
+            }
+
+            /// Returns the resource identifier of this tag, without the
+            /// leading `#`.
+            pub fn ident(self) -> Ident<&'static str> {
+                match self {
+                    #tag_to_ident_arms
+                }
+            }
+
+            /// Returns the kinds of items in this tag.
+            pub const fn items(self) -> &'static [ItemKind] {
+                match self {
+                    #tag_items_arms
+                }
+            }
+
+            /// An array of all item tags.
+            pub const ALL: [Self; #tag_count] = [#(Self::#tag_variants,)*];
+        }
+
+        impl ItemKind {
+            /// Returns whether this kind of item is in the given vanilla tag.
+            pub const fn is_in(self, tag: ItemTag) -> bool {
+                match tag {
+                    #kind_is_in_arms
+                }
+            }
+        }
+    })
+}
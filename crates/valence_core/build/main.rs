@@ -1,20 +1,29 @@
 use valence_build_utils::{rerun_if_changed, write_generated_file};
 
+mod enchantment;
 mod item;
+mod item_tag;
 mod packet_id;
 mod sound;
+mod statistic;
 mod translation_key;
 
 pub fn main() -> anyhow::Result<()> {
     rerun_if_changed([
+        "../../extracted/enchants.json",
         "../../extracted/items.json",
+        "../../extracted/misc.json",
         "../../extracted/packets.json",
         "../../extracted/sounds.json",
+        "../../extracted/tags.json",
         "../../extracted/translation_keys.json",
     ]);
 
+    write_generated_file(enchantment::build()?, "enchantment.rs")?;
     write_generated_file(item::build()?, "item.rs")?;
+    write_generated_file(item_tag::build()?, "item_tag.rs")?;
     write_generated_file(sound::build()?, "sound.rs")?;
+    write_generated_file(statistic::build()?, "statistic.rs")?;
     write_generated_file(translation_key::build()?, "translation_key.rs")?;
     write_generated_file(packet_id::build()?, "packet_id.rs")?;
 
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use heck::ToPascalCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::Deserialize;
+use valence_build_utils::ident;
+
+#[derive(Deserialize, Debug)]
+struct Misc {
+    custom_stat: BTreeMap<String, u16>,
+}
+
+pub fn build() -> anyhow::Result<TokenStream> {
+    let misc = serde_json::from_str::<Misc>(include_str!("../../../extracted/misc.json"))?;
+
+    let mut stats = misc.custom_stat.into_iter().collect::<Vec<_>>();
+    stats.sort_by_key(|(_, id)| *id);
+
+    let stat_count = stats.len();
+
+    let stat_from_raw_id_arms = stats
+        .iter()
+        .map(|(name, id)| {
+            let name = ident(name.to_pascal_case());
+
+            quote! {
+                #id => Some(Self::#name),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let stat_to_raw_id_arms = stats
+        .iter()
+        .map(|(name, id)| {
+            let name = ident(name.to_pascal_case());
+
+            quote! {
+                Self::#name => #id,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let stat_from_str_arms = stats
+        .iter()
+        .map(|(str_name, _)| {
+            let name = ident(str_name.to_pascal_case());
+
+            quote! {
+                #str_name => Some(Self::#name),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let stat_to_str_arms = stats
+        .iter()
+        .map(|(str_name, _)| {
+            let name = ident(str_name.to_pascal_case());
+
+            quote! {
+                Self::#name => #str_name,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let stat_variants = stats
+        .iter()
+        .map(|(name, _)| ident(name.to_pascal_case()))
+        .collect::<Vec<_>>();
+
+    Ok(quote! {
+        /// A statistic which is not about a block, item or entity, like the
+        /// number of jumps.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        #[repr(u16)]
+        pub enum CustomStatistic {
+            #(#stat_variants,)*
+        }
+
+        impl CustomStatistic {
+            /// Constructs a custom statistic from a raw custom statistic ID.
+            ///
+            /// If the given ID is invalid, `None` is returned.
+            pub const fn from_raw(id: u16) -> Option<Self> {
+                match id {
+                    #stat_from_raw_id_arms
+                    _ => None
+                }
+            }
+
+            /// Gets the raw custom statistic ID from the custom statistic.
+            pub const fn to_raw(self) -> u16 {
+                match self {
+                    #stat_to_raw_id_arms
+                }
+            }
+
+            /// Construct a custom statistic from its snake_case name.
+            ///
+            /// Returns `None` if the name is invalid.
+            #[allow(clippy::should_implement_trait)]
+            pub fn from_str(name: &str) -> Option<Self> {
+                match name {
+                    #stat_from_str_arms
+                    _ => None
+                }
+            }
+
+            /// Gets the snake_case name of this custom statistic.
+            pub const fn to_str(self) -> &'static str {
+                match self {
+                    #stat_to_str_arms
+                }
+            }
+
+            /// An array of all custom statistics.
+            pub const ALL: [Self; #stat_count] = [#(Self::#stat_variants,)*];
+        }
+    })
+}
@@ -0,0 +1,54 @@
+//! Prints the contents of a packet recording created with
+//! `valence_core::packet::recorder::PacketRecorder` in a human-readable form.
+//!
+//! Usage: `cargo run --example dump_recording -- <path>`
+
+use std::fs::File;
+use std::io::BufReader;
+
+use valence_core::packet::c2s::play::C2sPlayPacket;
+use valence_core::packet::decode::decode_packet;
+use valence_core::packet::recorder::{PacketDirection, PacketRecordingReader};
+use valence_core::packet::s2c::play::S2cPlayPacket;
+use valence_core::packet::Packet;
+
+fn main() -> anyhow::Result<()> {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("Usage: dump_recording <path>");
+        std::process::exit(1);
+    };
+
+    let reader = PacketRecordingReader::new(BufReader::new(File::open(path)?))?;
+
+    for entry in reader {
+        let entry = entry?;
+
+        let name = match entry.direction {
+            PacketDirection::C2s => decode_packet::<C2sPlayPacket>(&entry.data)
+                .map(|pkt| pkt.packet_name().to_owned()),
+            PacketDirection::S2c => decode_packet::<S2cPlayPacket>(&entry.data)
+                .map(|pkt| pkt.packet_name().to_owned()),
+        };
+
+        // Packets outside of the play state (or malformed packets) will fail to
+        // decode, so fall back to printing the ID.
+        let name = name.unwrap_or_else(|_| match entry.packet_id() {
+            Ok(id) => format!("<unknown packet 0x{id:02x}>"),
+            Err(_) => "<malformed packet>".into(),
+        });
+
+        let arrow = match entry.direction {
+            PacketDirection::C2s => "C->S",
+            PacketDirection::S2c => "S->C",
+        };
+
+        println!(
+            "[tick {:>6}] [{:>10.3}ms] {arrow} {name} ({} bytes)",
+            entry.tick,
+            entry.timestamp.as_secs_f64() * 1000.0,
+            entry.data.len()
+        );
+    }
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use std::io::Write;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
 
 use anyhow::bail;
+use glam::{DVec3, IVec3};
 
 use crate::direction::Direction;
 use crate::packet::{Decode, Encode};
@@ -45,6 +47,148 @@ impl BlockPos {
             Direction::East => BlockPos::new(self.x + 1, self.y, self.z),
         }
     }
+
+    /// Returns this position offset by the given amounts on each axis.
+    ///
+    /// ```
+    /// use valence_core::block_pos::BlockPos;
+    ///
+    /// let pos = BlockPos::new(1, 2, 3);
+    /// assert_eq!(pos.offset(-1, 0, 5), BlockPos::new(0, 2, 8));
+    /// ```
+    #[must_use]
+    pub const fn offset(self, dx: i32, dy: i32, dz: i32) -> Self {
+        Self::new(self.x + dx, self.y + dy, self.z + dz)
+    }
+
+    /// Returns the position at the center of this block in world space.
+    ///
+    /// ```
+    /// use glam::DVec3;
+    /// use valence_core::block_pos::BlockPos;
+    ///
+    /// let pos = BlockPos::new(-1, 0, 2);
+    /// assert_eq!(pos.center(), DVec3::new(-0.5, 0.5, 2.5));
+    /// assert_eq!(BlockPos::at(pos.center()), pos);
+    /// ```
+    pub fn center(self) -> DVec3 {
+        DVec3::new(self.x as f64, self.y as f64, self.z as f64) + 0.5
+    }
+
+    /// Returns the [Manhattan distance] between two block positions.
+    ///
+    /// [Manhattan distance]: https://en.wikipedia.org/wiki/Taxicab_geometry
+    pub fn manhattan_distance(self, other: Self) -> u64 {
+        let diff_x = (other.x as i64 - self.x as i64).unsigned_abs();
+        let diff_y = (other.y as i64 - self.y as i64).unsigned_abs();
+        let diff_z = (other.z as i64 - self.z as i64).unsigned_abs();
+
+        diff_x + diff_y + diff_z
+    }
+
+    /// Returns the squared Euclidean distance between two block positions.
+    pub fn distance_squared(self, other: Self) -> u64 {
+        let diff_x = other.x as i64 - self.x as i64;
+        let diff_y = other.y as i64 - self.y as i64;
+        let diff_z = other.z as i64 - self.z as i64;
+
+        (diff_x * diff_x + diff_y * diff_y + diff_z * diff_z) as u64
+    }
+
+    /// Returns an iterator over all block positions in the box between `min`
+    /// and `max` (inclusive). Nothing is yielded if any component of `min` is
+    /// greater than the corresponding component of `max`.
+    ///
+    /// Positions are yielded in YZX order (X changes fastest), which matches
+    /// the order blocks are stored in chunk sections.
+    ///
+    /// ```
+    /// use valence_core::block_pos::BlockPos;
+    ///
+    /// let positions: Vec<_> =
+    ///     BlockPos::iter_box(BlockPos::new(0, 0, 0), BlockPos::new(1, 0, 1)).collect();
+    ///
+    /// assert_eq!(
+    ///     positions,
+    ///     [
+    ///         BlockPos::new(0, 0, 0),
+    ///         BlockPos::new(1, 0, 0),
+    ///         BlockPos::new(0, 0, 1),
+    ///         BlockPos::new(1, 0, 1),
+    ///     ]
+    /// );
+    /// ```
+    pub fn iter_box(min: BlockPos, max: BlockPos) -> impl Iterator<Item = BlockPos> + Clone {
+        (min.y..=max.y).flat_map(move |y| {
+            (min.z..=max.z)
+                .flat_map(move |z| (min.x..=max.x).map(move |x| BlockPos::new(x, y, z)))
+        })
+    }
+}
+
+impl Add<IVec3> for BlockPos {
+    type Output = BlockPos;
+
+    fn add(self, rhs: IVec3) -> Self::Output {
+        self.offset(rhs.x, rhs.y, rhs.z)
+    }
+}
+
+impl Add<[i32; 3]> for BlockPos {
+    type Output = BlockPos;
+
+    fn add(self, [x, y, z]: [i32; 3]) -> Self::Output {
+        self.offset(x, y, z)
+    }
+}
+
+impl Sub<IVec3> for BlockPos {
+    type Output = BlockPos;
+
+    fn sub(self, rhs: IVec3) -> Self::Output {
+        self.offset(-rhs.x, -rhs.y, -rhs.z)
+    }
+}
+
+impl Sub<[i32; 3]> for BlockPos {
+    type Output = BlockPos;
+
+    fn sub(self, [x, y, z]: [i32; 3]) -> Self::Output {
+        self.offset(-x, -y, -z)
+    }
+}
+
+/// Returns the offset from `rhs` to `self`.
+impl Sub for BlockPos {
+    type Output = IVec3;
+
+    fn sub(self, rhs: BlockPos) -> Self::Output {
+        IVec3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl AddAssign<IVec3> for BlockPos {
+    fn add_assign(&mut self, rhs: IVec3) {
+        *self = *self + rhs;
+    }
+}
+
+impl AddAssign<[i32; 3]> for BlockPos {
+    fn add_assign(&mut self, rhs: [i32; 3]) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign<IVec3> for BlockPos {
+    fn sub_assign(&mut self, rhs: IVec3) {
+        *self = *self - rhs;
+    }
+}
+
+impl SubAssign<[i32; 3]> for BlockPos {
+    fn sub_assign(&mut self, rhs: [i32; 3]) {
+        *self = *self - rhs;
+    }
 }
 
 impl Encode for BlockPos {
@@ -98,6 +242,26 @@ impl From<BlockPos> for [i32; 3] {
     }
 }
 
+impl From<IVec3> for BlockPos {
+    fn from(v: IVec3) -> Self {
+        BlockPos::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<BlockPos> for IVec3 {
+    fn from(pos: BlockPos) -> Self {
+        IVec3::new(pos.x, pos.y, pos.z)
+    }
+}
+
+/// Returns the block position a point is contained within. Equivalent to
+/// [`BlockPos::at`].
+impl From<DVec3> for BlockPos {
+    fn from(pos: DVec3) -> Self {
+        BlockPos::at(pos)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,4 +303,76 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn block_pos_at_floors_negative_coords() {
+        assert_eq!(BlockPos::at([-0.5, -1.0, -1.001]), BlockPos::new(-1, -1, -2));
+        assert_eq!(BlockPos::at([0.999, 1.0, -0.0]), BlockPos::new(0, 1, 0));
+        assert_eq!(
+            BlockPos::from(DVec3::new(-16.5, 3.2, 15.9)),
+            BlockPos::new(-17, 3, 15)
+        );
+    }
+
+    #[test]
+    fn block_pos_center_round_trip() {
+        for pos in BlockPos::iter_box(BlockPos::new(-2, -2, -2), BlockPos::new(2, 2, 2)) {
+            assert_eq!(BlockPos::at(pos.center()), pos);
+        }
+    }
+
+    #[test]
+    fn block_pos_arithmetic() {
+        let pos = BlockPos::new(-3, 10, 7);
+
+        assert_eq!(pos + [1, -1, 0], BlockPos::new(-2, 9, 7));
+        assert_eq!(pos - IVec3::new(-3, 10, 7), BlockPos::new(0, 0, 0));
+        assert_eq!(BlockPos::new(1, 1, 1) - pos, IVec3::new(4, -9, -6));
+
+        let mut p = pos;
+        p += IVec3::ONE;
+        p -= [1, 1, 1];
+        assert_eq!(p, pos);
+
+        for dir in [
+            Direction::Down,
+            Direction::Up,
+            Direction::North,
+            Direction::South,
+            Direction::West,
+            Direction::East,
+        ] {
+            let adj = pos.get_in_direction(dir);
+            assert_eq!(pos.manhattan_distance(adj), 1);
+            assert_eq!(pos.distance_squared(adj), 1);
+        }
+
+        assert_eq!(
+            BlockPos::new(-1, -1, -1).manhattan_distance(BlockPos::new(1, 1, 1)),
+            6
+        );
+        assert_eq!(
+            BlockPos::new(-1, -1, -1).distance_squared(BlockPos::new(1, 1, 1)),
+            12
+        );
+    }
+
+    #[test]
+    fn block_pos_iter_box() {
+        let min = BlockPos::new(-2, -1, -3);
+        let max = BlockPos::new(1, 2, 0);
+
+        let positions: Vec<_> = BlockPos::iter_box(min, max).collect();
+        assert_eq!(positions.len(), 4 * 4 * 4);
+        assert_eq!(positions.first(), Some(&min));
+        assert_eq!(positions.last(), Some(&max));
+
+        // X changes fastest, then Z, then Y.
+        assert_eq!(positions[1], BlockPos::new(-1, -1, -3));
+        assert_eq!(positions[4], BlockPos::new(-2, -1, -2));
+        assert_eq!(positions[16], BlockPos::new(-2, 0, -3));
+
+        assert_eq!(BlockPos::iter_box(max, min).count(), 0);
+        assert_eq!(BlockPos::iter_box(min, min).count(), 1);
+    }
 }
@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use uuid::Uuid;
+
+use crate::packet::encode::WritePacket;
+use crate::packet::s2c::play::chat_message::MessageFilterType;
+use crate::packet::s2c::play::ChatMessageS2c;
+use crate::packet::var_int::VarInt;
+use crate::text::Text;
+
+/// The maximum length of a chat message sent by a client, in characters.
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 256;
+
+/// Returns `true` if the character is allowed in chat messages sent by
+/// clients. Control characters and the section sign used for legacy formatting
+/// are not allowed, like on the vanilla server.
+pub fn is_valid_chat_char(c: char) -> bool {
+    c != '§' && c >= ' ' && c != '\u{7f}'
+}
+
+/// An entry of the `minecraft:chat_type` registry, which decides how the client
+/// decorates, displays and narrates a player chat message.
+///
+/// The constants are the vanilla chat types, with their IDs in the default
+/// registry codec. Custom chat types are added with
+/// `RegistryCodec::add_chat_type` in `valence_registry`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ChatType(pub i32);
+
+impl ChatType {
+    /// `<name> message`
+    pub const CHAT: Self = Self(0);
+    /// `* name message`
+    pub const EMOTE_COMMAND: Self = Self(1);
+    /// `name whispers to you: message`
+    pub const MSG_COMMAND_INCOMING: Self = Self(2);
+    /// `You whisper to target: message`
+    pub const MSG_COMMAND_OUTGOING: Self = Self(3);
+    /// `[name] message`
+    pub const SAY_COMMAND: Self = Self(4);
+    /// `target <name> message`
+    pub const TEAM_MSG_COMMAND_INCOMING: Self = Self(5);
+    /// `-> target <name> message`
+    pub const TEAM_MSG_COMMAND_OUTGOING: Self = Self(6);
+}
+
+/// The sender of a player chat message.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChatSender {
+    /// The UUID of the sender. The sender has to be in the player list of the
+    /// receiving clients, or they disconnect.
+    pub uuid: Uuid,
+    /// The name of the sender shown in the message.
+    pub name: Text,
+    /// The name of the receiver of the message, which is shown by chat types
+    /// like [`ChatType::MSG_COMMAND_OUTGOING`].
+    pub target_name: Option<Text>,
+}
+
+impl ChatSender {
+    pub fn new(uuid: Uuid, name: impl Into<Text>) -> Self {
+        Self {
+            uuid,
+            name: name.into(),
+            target_name: None,
+        }
+    }
+
+    pub fn with_target_name(mut self, target_name: impl Into<Text>) -> Self {
+        self.target_name = Some(target_name.into());
+        self
+    }
+}
+
+/// Writes an unsigned player chat message. Used by `Client::send_player_chat`
+/// in `valence_client` and `Instance::send_player_chat` in `valence_instance`.
+pub fn write_player_chat(
+    mut writer: impl WritePacket,
+    sender: &ChatSender,
+    msg: &Text,
+    chat_type: ChatType,
+) {
+    let body = msg.to_string();
+
+    // Formatted messages are sent as unsigned content, which the client
+    // displays instead of the plain body.
+    let unsigned_content = if *msg == Text::text(body.clone()) {
+        None
+    } else {
+        Some(Cow::Borrowed(msg))
+    };
+
+    let time_stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+
+    writer.write_packet(&ChatMessageS2c {
+        sender: sender.uuid,
+        index: VarInt(0),
+        message_signature: None,
+        message: &body,
+        time_stamp,
+        salt: 0,
+        previous_messages: vec![],
+        unsigned_content,
+        filter_type: MessageFilterType::PassThrough,
+        filter_type_bits: None,
+        chat_type: VarInt(chat_type.0),
+        network_name: Cow::Borrowed(&sender.name),
+        network_target_name: sender.target_name.as_ref().map(Cow::Borrowed),
+    });
+}
@@ -42,6 +42,57 @@ impl ChunkPos {
 
         (diff_x * diff_x + diff_z * diff_z) as u64
     }
+
+    /// Returns the [Chebyshev distance] between two chunk positions. This is
+    /// the number of square "rings" `other` is away from `self`.
+    ///
+    /// [Chebyshev distance]: https://en.wikipedia.org/wiki/Chebyshev_distance
+    pub fn chebyshev_distance(self, other: Self) -> u32 {
+        let diff_x = (other.x as i64 - self.x as i64).unsigned_abs();
+        let diff_z = (other.z as i64 - self.z as i64).unsigned_abs();
+
+        diff_x.max(diff_z) as u32
+    }
+
+    /// Returns an iterator over all chunk positions within the square of
+    /// `radius` chunks around `center`, in order of increasing [Chebyshev
+    /// distance] from `center`. Every position in the square is yielded exactly
+    /// once.
+    ///
+    /// Each square ring of positions is walked clockwise starting from its
+    /// corner with the smallest X and Z.
+    ///
+    /// ```
+    /// use valence_core::chunk_pos::ChunkPos;
+    ///
+    /// let center = ChunkPos::new(5, -5);
+    /// let positions: Vec<_> = ChunkPos::spiral_iter(center, 1).collect();
+    ///
+    /// assert_eq!(positions.len(), 9);
+    /// assert_eq!(positions[0], center);
+    /// ```
+    ///
+    /// [Chebyshev distance]: ChunkPos::chebyshev_distance
+    pub fn spiral_iter(center: ChunkPos, radius: u32) -> impl Iterator<Item = ChunkPos> + Clone {
+        let radius = radius.min(i32::MAX as u32) as i32;
+
+        std::iter::once(center).chain((1..=radius).flat_map(move |r| {
+            // Each ring has four sides of length `2r`.
+            (0..8 * r).map(move |i| {
+                let side = i / (2 * r);
+                let j = i % (2 * r) - r;
+
+                let (dx, dz) = match side {
+                    0 => (j, -r),
+                    1 => (r, j),
+                    2 => (-j, r),
+                    _ => (-r, -j),
+                };
+
+                ChunkPos::new(center.x + dx, center.z + dz)
+            })
+        }))
+    }
 }
 
 impl From<(i32, i32)> for ChunkPos {
@@ -114,8 +165,40 @@ impl ChunkView {
             .filter(move |&p| self.contains(p))
     }
 
-    pub fn diff(self, other: Self) -> impl Iterator<Item = ChunkPos> {
-        self.iter().filter(move |&p| !other.contains(p))
+    /// Returns the chunk positions that change visibility when moving from the
+    /// `old` view to the `new` view. The first iterator contains the
+    /// positions entering the view (in `new` but not `old`) and the second
+    /// contains the positions leaving the view (in `old` but not `new`).
+    ///
+    /// No allocations are made.
+    ///
+    /// ```
+    /// use valence_core::chunk_pos::{ChunkPos, ChunkView};
+    ///
+    /// let old = ChunkView::new([0, 0], 4);
+    /// let new = ChunkView::new([1, 0], 4);
+    ///
+    /// let (entering, leaving) = ChunkView::diff(old, new);
+    ///
+    /// for pos in entering {
+    ///     assert!(new.contains(pos) && !old.contains(pos));
+    /// }
+    ///
+    /// for pos in leaving {
+    ///     assert!(old.contains(pos) && !new.contains(pos));
+    /// }
+    /// ```
+    pub fn diff(
+        old: Self,
+        new: Self,
+    ) -> (
+        impl Iterator<Item = ChunkPos>,
+        impl Iterator<Item = ChunkPos>,
+    ) {
+        (
+            new.iter().filter(move |&p| !old.contains(p)),
+            old.iter().filter(move |&p| !new.contains(p)),
+        )
     }
 
     // The foreach-based methods are optimizing better than the iterator ones.
@@ -184,6 +267,78 @@ mod tests {
         }
     }
 
+    #[test]
+    fn spiral_iter_matches_brute_force() {
+        for _ in 0..50 {
+            let center = ChunkPos::new(
+                rand::random::<i16>() as i32,
+                rand::random::<i16>() as i32,
+            );
+            let radius = rand::random::<u32>() % 20;
+
+            let spiral: Vec<_> = ChunkPos::spiral_iter(center, radius).collect();
+            let spiral_set = BTreeSet::from_iter(spiral.iter().copied());
+
+            // No duplicates.
+            assert_eq!(spiral.len(), spiral_set.len());
+
+            let r = radius as i32;
+            let mut expected = BTreeSet::new();
+            for z in center.z - r..=center.z + r {
+                for x in center.x - r..=center.x + r {
+                    expected.insert(ChunkPos::new(x, z));
+                }
+            }
+
+            // No misses.
+            assert_eq!(spiral_set, expected);
+
+            // Distance never decreases.
+            for w in spiral.windows(2) {
+                assert!(center.chebyshev_distance(w[0]) <= center.chebyshev_distance(w[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn chunk_view_diff_matches_brute_force() {
+        for _ in 0..50 {
+            let old = ChunkView::new(
+                [
+                    rand::random::<i8>() as i32 / 8,
+                    rand::random::<i8>() as i32 / 8,
+                ],
+                rand::random::<u8>() % 12,
+            );
+            let new = ChunkView::new(
+                [
+                    rand::random::<i8>() as i32 / 8,
+                    rand::random::<i8>() as i32 / 8,
+                ],
+                rand::random::<u8>() % 12,
+            );
+
+            let old_set = BTreeSet::from_iter(old.iter());
+            let new_set = BTreeSet::from_iter(new.iter());
+
+            let (entering, leaving) = ChunkView::diff(old, new);
+
+            let entering: Vec<_> = entering.collect();
+            let leaving: Vec<_> = leaving.collect();
+
+            assert_eq!(
+                BTreeSet::from_iter(entering.iter().copied()),
+                &new_set - &old_set
+            );
+            assert_eq!(
+                BTreeSet::from_iter(leaving.iter().copied()),
+                &old_set - &new_set
+            );
+            assert_eq!(entering.len(), (&new_set - &old_set).len());
+            assert_eq!(leaving.len(), (&old_set - &new_set).len());
+        }
+    }
+
     #[test]
     fn chunk_pos_round_trip_conv() {
         let p = ChunkPos::new(rand::random(), rand::random());
@@ -0,0 +1,101 @@
+use crate::ident;
+use crate::ident::Ident;
+use crate::item::ItemKind;
+
+include!(concat!(env!("OUT_DIR"), "/enchantment.rs"));
+
+impl Enchantment {
+    /// Returns `true` if this enchantment can be on the same item as `other`.
+    /// An enchantment is not compatible with itself.
+    pub fn is_compatible_with(self, other: Enchantment) -> bool {
+        self != other && !self.incompatible().contains(&other)
+    }
+}
+
+/// How common an enchantment is. Rarer enchantments are less likely to be
+/// picked by the enchanting table, and cost more in the anvil.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum EnchantmentRarity {
+    Common,
+    Uncommon,
+    Rare,
+    VeryRare,
+}
+
+impl EnchantmentRarity {
+    /// Gets the weight of enchantments with this rarity when picking random
+    /// enchantments.
+    pub const fn weight(self) -> u8 {
+        match self {
+            Self::Common => 10,
+            Self::Uncommon => 5,
+            Self::Rare => 2,
+            Self::VeryRare => 1,
+        }
+    }
+}
+
+/// The category of items an enchantment is meant for.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EnchantmentTarget {
+    /// Any armor piece.
+    Armor,
+    ArmorFeet,
+    ArmorLegs,
+    ArmorChest,
+    ArmorHead,
+    /// Swords.
+    Weapon,
+    /// Pickaxes, shovels, axes and hoes.
+    Digger,
+    FishingRod,
+    Trident,
+    /// Items with durability.
+    Breakable,
+    Bow,
+    /// Items which can be worn, such as armor, elytra and mob heads.
+    Wearable,
+    Crossbow,
+    /// Items which can have the curse of vanishing.
+    Vanishable,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enchantment_compatibility() {
+        assert!(!Enchantment::Protection.is_compatible_with(Enchantment::FireProtection));
+        assert!(!Enchantment::BlastProtection.is_compatible_with(Enchantment::Protection));
+        assert!(Enchantment::Protection.is_compatible_with(Enchantment::FeatherFalling));
+        assert!(!Enchantment::Sharpness.is_compatible_with(Enchantment::Smite));
+        assert!(!Enchantment::Riptide.is_compatible_with(Enchantment::Loyalty));
+        assert!(!Enchantment::Loyalty.is_compatible_with(Enchantment::Riptide));
+        assert!(Enchantment::Loyalty.is_compatible_with(Enchantment::Channeling));
+        assert!(!Enchantment::Mending.is_compatible_with(Enchantment::Mending));
+
+        for a in Enchantment::ALL {
+            for b in Enchantment::ALL {
+                assert_eq!(a.is_compatible_with(b), b.is_compatible_with(a));
+            }
+        }
+    }
+
+    #[test]
+    fn enchantment_items() {
+        assert!(Enchantment::Sharpness.can_enchant(ItemKind::DiamondSword));
+        assert!(Enchantment::Sharpness.can_enchant(ItemKind::DiamondAxe));
+        assert!(!Enchantment::Looting.can_enchant(ItemKind::DiamondAxe));
+        assert!(Enchantment::Efficiency.can_enchant(ItemKind::Shears));
+        assert!(Enchantment::Thorns.can_enchant(ItemKind::IronBoots));
+        assert!(!Enchantment::FeatherFalling.can_enchant(ItemKind::IronHelmet));
+        assert!(Enchantment::Unbreaking.can_enchant(ItemKind::Elytra));
+        assert!(!Enchantment::Unbreaking.can_enchant(ItemKind::Stick));
+
+        assert_eq!(Enchantment::Thorns.target(), EnchantmentTarget::ArmorChest);
+        assert_eq!(Enchantment::Mending.rarity(), EnchantmentRarity::Rare);
+        assert_eq!(Enchantment::Sharpness.max_level(), 5);
+        assert!(Enchantment::BindingCurse.is_cursed());
+    }
+}
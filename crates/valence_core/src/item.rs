@@ -1,12 +1,19 @@
 use std::io::Write;
 
 use anyhow::{ensure, Context};
-use valence_nbt::Compound;
+use bitfield_struct::bitfield;
+use thiserror::Error;
+use valence_nbt::{Compound, List, Value};
 
+use crate::enchantment::Enchantment;
+use crate::ident;
+use crate::ident::Ident;
 use crate::packet::var_int::VarInt;
 use crate::packet::{Decode, Encode};
+use crate::text::Text;
 
 include!(concat!(env!("OUT_DIR"), "/item.rs"));
+include!(concat!(env!("OUT_DIR"), "/item_tag.rs"));
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct ItemStack {
@@ -56,6 +63,256 @@ impl ItemStack {
     pub fn set_count(&mut self, count: u8) {
         self.count = count.clamp(Self::STACK_MIN, Self::STACK_MAX);
     }
+
+    /// Sets the custom name of the item, which replaces the name of the item
+    /// kind. Custom names are italic unless the text sets `italic` to `false`.
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<Text>) -> Self {
+        let name = name.into();
+        self.display_mut().insert("Name", text_to_json(&name));
+        self
+    }
+
+    /// Sets the lines shown below the name of the item in its tooltip. Lore is
+    /// italic and purple unless the text sets otherwise.
+    #[must_use]
+    pub fn with_lore(mut self, lore: impl IntoIterator<Item = impl Into<Text>>) -> Self {
+        let lore: Vec<_> = lore
+            .into_iter()
+            .map(|line| text_to_json(&line.into()))
+            .collect();
+
+        self.display_mut().insert("Lore", List::String(lore));
+        self
+    }
+
+    /// Adds an enchantment to the item, or changes its level if the item
+    /// already has it. Enchanted books store their enchantments differently,
+    /// so this doesn't apply to them.
+    ///
+    /// The enchantment is not validated. See [`ItemStack::add_enchantment`].
+    #[must_use]
+    pub fn with_enchantment(mut self, enchantment: Enchantment, level: u8) -> Self {
+        self.set_enchantment(enchantment, level);
+        self
+    }
+
+    /// Adds an enchantment to the item, or changes its level if the item
+    /// already has it.
+    ///
+    /// Like the `/enchant` command, this fails if the enchantment doesn't
+    /// apply to the item, the level is outside the levels of the enchantment,
+    /// or the item has an incompatible enchantment. If `force` is `true`, the
+    /// enchantment is added anyway.
+    pub fn add_enchantment(
+        &mut self,
+        enchantment: Enchantment,
+        level: u8,
+        force: bool,
+    ) -> Result<(), EnchantmentError> {
+        if !force {
+            if !enchantment.can_enchant(self.item) {
+                return Err(EnchantmentError::NotApplicable);
+            }
+
+            if !(enchantment.min_level()..=enchantment.max_level()).contains(&level) {
+                return Err(EnchantmentError::InvalidLevel);
+            }
+
+            if let Some((other, _)) = self
+                .enchantments()
+                .into_iter()
+                .find(|&(other, _)| other != enchantment && !enchantment.is_compatible_with(other))
+            {
+                return Err(EnchantmentError::Incompatible(other));
+            }
+        }
+
+        self.set_enchantment(enchantment, level);
+        Ok(())
+    }
+
+    /// Sets if the item has the enchantment glint, without showing any
+    /// enchantments in its tooltip. This adds an empty enchantment, which the
+    /// client ignores except for the glint.
+    ///
+    /// Items with real enchantments always have the glint.
+    #[must_use]
+    pub fn with_glint(mut self, glint: bool) -> Self {
+        if glint {
+            let enchantments = self.enchantments_mut();
+
+            if enchantments.is_empty() {
+                enchantments.push(Compound::new());
+            }
+        } else if let Some(nbt) = &mut self.nbt {
+            if let Some(Value::List(List::Compound(enchantments))) = nbt.get_mut("Enchantments") {
+                enchantments.retain(|ench| !ench.is_empty());
+
+                if enchantments.is_empty() {
+                    nbt.remove("Enchantments");
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Sets if the item loses durability when used.
+    #[must_use]
+    pub fn with_unbreakable(mut self, unbreakable: bool) -> Self {
+        if unbreakable {
+            self.nbt_mut().insert("Unbreakable", true);
+        } else if let Some(nbt) = &mut self.nbt {
+            nbt.remove("Unbreakable");
+        }
+
+        self
+    }
+
+    /// Sets the parts of the tooltip of the item which are hidden.
+    #[must_use]
+    pub fn with_hide_flags(mut self, flags: HideFlags) -> Self {
+        self.nbt_mut().insert("HideFlags", i32::from(flags.0));
+        self
+    }
+
+    /// Sets the custom model data of the item, which resource packs can use
+    /// to pick the model of the item.
+    #[must_use]
+    pub fn with_custom_model_data(mut self, custom_model_data: i32) -> Self {
+        self.nbt_mut().insert("CustomModelData", custom_model_data);
+        self
+    }
+
+    /// Gets the custom name of the item, if it has a valid one.
+    pub fn name(&self) -> Option<Text> {
+        let name = self.display()?.get("Name")?.as_string()?;
+
+        serde_json::from_str(name).ok()
+    }
+
+    /// Gets the lore of the item. Lines which are not valid text are skipped.
+    pub fn lore(&self) -> Vec<Text> {
+        match self.display().and_then(|display| display.get("Lore")) {
+            Some(Value::List(List::String(lore))) => lore
+                .iter()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Gets the enchantments of the item and their levels. Unknown
+    /// enchantments are skipped.
+    pub fn enchantments(&self) -> Vec<(Enchantment, u8)> {
+        let Some(Value::List(List::Compound(enchantments))) =
+            self.nbt.as_ref().and_then(|nbt| nbt.get("Enchantments"))
+        else {
+            return vec![];
+        };
+
+        enchantments
+            .iter()
+            .filter_map(|ench| {
+                let id = ench.get("id")?.as_string()?;
+                let enchantment =
+                    Enchantment::from_str(id.strip_prefix("minecraft:").unwrap_or(id))?;
+
+                // Levels are clamped the same way as in the game.
+                let level = match ench.get("lvl")? {
+                    Value::Byte(lvl) => (*lvl).max(0) as u8,
+                    Value::Short(lvl) => (*lvl).clamp(0, 255) as u8,
+                    Value::Int(lvl) => (*lvl).clamp(0, 255) as u8,
+                    _ => return None,
+                };
+
+                Some((enchantment, level))
+            })
+            .collect()
+    }
+
+    fn set_enchantment(&mut self, enchantment: Enchantment, level: u8) {
+        let id = enchantment.to_ident().to_string();
+
+        let enchantments = self.enchantments_mut();
+
+        enchantments.retain(|ench| ench.get("id").and_then(Value::as_string) != Some(&id));
+        enchantments.push(valence_nbt::compound! {
+            "id" => id,
+            "lvl" => i16::from(level),
+        });
+    }
+
+    fn nbt_mut(&mut self) -> &mut Compound {
+        self.nbt.get_or_insert_with(Compound::new)
+    }
+
+    fn display(&self) -> Option<&Compound> {
+        self.nbt.as_ref()?.get("display")?.as_compound()
+    }
+
+    fn display_mut(&mut self) -> &mut Compound {
+        let display = self
+            .nbt_mut()
+            .entry("display")
+            .or_insert_with(Compound::new);
+
+        if !display.is_compound() {
+            *display = Value::Compound(Compound::new());
+        }
+
+        display.as_compound_mut().unwrap()
+    }
+
+    fn enchantments_mut(&mut self) -> &mut Vec<Compound> {
+        let enchantments = self
+            .nbt_mut()
+            .entry("Enchantments")
+            .or_insert_with(|| List::Compound(vec![]));
+
+        if !matches!(enchantments, Value::List(List::Compound(_))) {
+            *enchantments = Value::List(List::Compound(vec![]));
+        }
+
+        match enchantments {
+            Value::List(List::Compound(enchantments)) => enchantments,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The reason an enchantment can't be added to an item with
+/// [`ItemStack::add_enchantment`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Error)]
+pub enum EnchantmentError {
+    #[error("enchantment does not apply to the item")]
+    NotApplicable,
+    #[error("enchantment level is outside of its minimum and maximum level")]
+    InvalidLevel,
+    #[error("enchantment is incompatible with {0:?} on the item")]
+    Incompatible(Enchantment),
+}
+
+/// The parts of the tooltip of an item which are hidden.
+#[bitfield(u8)]
+#[derive(PartialEq, Eq)]
+pub struct HideFlags {
+    pub enchantments: bool,
+    pub attribute_modifiers: bool,
+    pub unbreakable: bool,
+    pub can_destroy: bool,
+    pub can_place_on: bool,
+    /// Other information, such as the effects of potions and the contents of
+    /// written books.
+    pub additional: bool,
+    /// The color of dyed leather armor.
+    pub dye: bool,
+    _pad: bool,
+}
+
+fn text_to_json(text: &Text) -> String {
+    serde_json::to_string(text).expect("failed to jsonify text")
 }
 
 impl Default for ItemStack {
@@ -130,6 +387,201 @@ impl Decode<'_> for ItemKind {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use valence_nbt::snbt::from_snbt_str;
+
+    use super::*;
+    use crate::text::{Color, TextFormat};
+
+    /// Parses the NBT of an item as printed by `/data get`.
+    fn vanilla_nbt(snbt: &str) -> Compound {
+        from_snbt_str(snbt).unwrap().into_compound().unwrap()
+    }
+
+    #[test]
+    fn item_stack_name_and_lore() {
+        // Renamed in an anvil.
+        let stack = ItemStack::new(ItemKind::DiamondSword, 1, None).with_name("Excalibur");
+
+        assert_eq!(
+            stack.nbt,
+            Some(vanilla_nbt(r#"{display:{Name:'{"text":"Excalibur"}'}}"#))
+        );
+        assert_eq!(stack.name(), Some("Excalibur".into()));
+
+        let stack = stack.with_lore(["Line 1", "Line 2"]);
+
+        assert_eq!(
+            stack.nbt,
+            Some(vanilla_nbt(
+                r#"{display:{Name:'{"text":"Excalibur"}',Lore:['{"text":"Line 1"}','{"text":"Line 2"}']}}"#
+            ))
+        );
+        assert_eq!(stack.lore(), [Text::from("Line 1"), Text::from("Line 2")]);
+
+        let stack = ItemStack::new(ItemKind::DiamondSword, 1, None).with_nbt(vanilla_nbt(
+            r#"{display:{Name:'{"italic":false,"color":"gold","text":"Excalibur"}'}}"#,
+        ));
+
+        assert_eq!(
+            stack.name(),
+            Some("Excalibur".not_italic().color(Color::GOLD))
+        );
+        assert!(stack.lore().is_empty());
+    }
+
+    #[test]
+    fn item_stack_enchantments() {
+        // Enchanted with `/enchant`.
+        let stack = ItemStack::new(ItemKind::DiamondSword, 1, None)
+            .with_enchantment(Enchantment::Sharpness, 4)
+            .with_enchantment(Enchantment::Sharpness, 5);
+
+        assert_eq!(
+            stack.nbt,
+            Some(vanilla_nbt(
+                r#"{Enchantments:[{id:"minecraft:sharpness",lvl:5s}]}"#
+            ))
+        );
+        assert_eq!(stack.enchantments(), [(Enchantment::Sharpness, 5)]);
+
+        let stack = stack.with_enchantment(Enchantment::FireAspect, 2);
+
+        assert_eq!(
+            stack.enchantments(),
+            [(Enchantment::Sharpness, 5), (Enchantment::FireAspect, 2)]
+        );
+    }
+
+    #[test]
+    fn item_stack_glint() {
+        let stack = ItemStack::new(ItemKind::Stick, 1, None).with_glint(true);
+
+        assert_eq!(stack.nbt, Some(vanilla_nbt("{Enchantments:[{}]}")));
+        assert!(stack.enchantments().is_empty());
+
+        let stack = stack.with_glint(false);
+
+        assert_eq!(stack.nbt, Some(Compound::new()));
+
+        // Real enchantments are kept.
+        let stack = ItemStack::new(ItemKind::Stick, 1, None)
+            .with_enchantment(Enchantment::Knockback, 2)
+            .with_glint(true)
+            .with_glint(false);
+
+        assert_eq!(stack.enchantments(), [(Enchantment::Knockback, 2)]);
+    }
+
+    #[test]
+    fn item_tags() {
+        for tag in ItemTag::ALL {
+            assert_eq!(ItemTag::from_ident(tag.ident()), Some(tag));
+
+            for &kind in tag.items() {
+                assert!(kind.is_in(tag));
+            }
+        }
+
+        assert!(ItemKind::RedBed.is_in(ItemTag::Beds));
+        assert!(!ItemKind::Stick.is_in(ItemTag::Beds));
+    }
+
+    #[test]
+    fn item_stack_add_enchantment() {
+        let mut stack = ItemStack::new(ItemKind::DiamondSword, 1, None);
+
+        stack
+            .add_enchantment(Enchantment::Sharpness, 5, false)
+            .unwrap();
+        stack
+            .add_enchantment(Enchantment::Looting, 3, false)
+            .unwrap();
+
+        assert_eq!(
+            stack.add_enchantment(Enchantment::Smite, 1, false),
+            Err(EnchantmentError::Incompatible(Enchantment::Sharpness))
+        );
+        assert_eq!(
+            stack.add_enchantment(Enchantment::FireAspect, 3, false),
+            Err(EnchantmentError::InvalidLevel)
+        );
+        assert_eq!(
+            stack.add_enchantment(Enchantment::Efficiency, 1, false),
+            Err(EnchantmentError::NotApplicable)
+        );
+
+        // Changing the level of an existing enchantment is fine.
+        stack
+            .add_enchantment(Enchantment::Sharpness, 4, false)
+            .unwrap();
+
+        assert_eq!(
+            stack.enchantments(),
+            [(Enchantment::Looting, 3), (Enchantment::Sharpness, 4)]
+        );
+
+        stack.add_enchantment(Enchantment::Smite, 10, true).unwrap();
+
+        assert_eq!(
+            stack.enchantments(),
+            [
+                (Enchantment::Looting, 3),
+                (Enchantment::Sharpness, 4),
+                (Enchantment::Smite, 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn item_stack_flags() {
+        // Given with `/give`.
+        let stack = ItemStack::new(ItemKind::DiamondPickaxe, 1, None)
+            .with_unbreakable(true)
+            .with_hide_flags(
+                HideFlags::new()
+                    .with_enchantments(true)
+                    .with_unbreakable(true),
+            )
+            .with_custom_model_data(7);
+
+        assert_eq!(
+            stack.nbt,
+            Some(vanilla_nbt(
+                "{Unbreakable:1b,HideFlags:5,CustomModelData:7}"
+            ))
+        );
+
+        let stack = stack.with_unbreakable(false);
+
+        assert_eq!(
+            stack.nbt,
+            Some(vanilla_nbt("{HideFlags:5,CustomModelData:7}"))
+        );
+    }
+
+    #[test]
+    fn item_kind_properties() {
+        assert_eq!(ItemKind::Stone.max_stack(), 64);
+        assert_eq!(ItemKind::EnderPearl.max_stack(), 16);
+        assert_eq!(ItemKind::Snowball.max_stack(), 16);
+        assert_eq!(ItemKind::DiamondPickaxe.max_stack(), 1);
+
+        assert_eq!(ItemKind::DiamondPickaxe.max_durability(), 1561);
+        assert_eq!(ItemKind::Stone.max_durability(), 0);
+
+        assert!(ItemKind::Apple.is_food());
+        assert!(!ItemKind::Stone.is_food());
+        let food = ItemKind::GoldenApple.food_component().unwrap();
+        assert_eq!(food.hunger, 4);
+        assert!(food.always_edible);
+
+        assert_eq!(ItemKind::Coal.fuel_time(), 1600);
+        assert_eq!(ItemKind::Stone.fuel_time(), 0);
+    }
+}
+
 /*
 #[cfg(test)]
 mod tests {
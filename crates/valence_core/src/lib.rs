@@ -20,10 +20,12 @@
 
 pub mod aabb;
 pub mod block_pos;
+pub mod chat;
 pub mod chunk_pos;
 pub mod despawn;
 pub mod difficulty;
 pub mod direction;
+pub mod enchantment;
 pub mod game_mode;
 pub mod hand;
 pub mod ident;
@@ -33,6 +35,7 @@ pub mod player_textures;
 pub mod property;
 pub mod scratch;
 pub mod sound;
+pub mod statistic;
 pub mod text;
 pub mod translation_key;
 pub mod uuid;
@@ -12,6 +12,7 @@ pub mod global_pos;
 pub mod impls;
 pub mod message_signature;
 pub mod raw;
+pub mod recorder;
 pub mod var_int;
 pub mod var_long;
 
@@ -459,7 +460,7 @@ mod tests {
     use super::*;
     use crate::packet::c2s::play::{C2sPlayPacket, HandSwingC2s};
     use crate::packet::decode::{decode_packet, PacketDecoder};
-    use crate::packet::encode::PacketEncoder;
+    use crate::packet::encode::{CachedPacket, PacketEncoder};
 
     #[derive(Encode, Decode, Packet, Debug)]
     #[packet_id = 1]
@@ -651,4 +652,57 @@ mod tests {
         check_test_packet(&mut dec, "fourth");
         check_test_packet(&mut dec, "third");
     }
+    #[test]
+    fn encoder_stats() {
+        let mut enc = PacketEncoder::new();
+        #[cfg(feature = "compression")]
+        enc.set_compression(Some(64));
+
+        let packet = TestPacket::new("first");
+        let mut encoded = vec![];
+        packet.encode_packet(&mut encoded).unwrap();
+
+        enc.append_packet(&packet).unwrap();
+        enc.append_packet(&UnitStruct).unwrap();
+
+        // Raw bytes from another encoder with the same threshold.
+        let mut other = PacketEncoder::new();
+        #[cfg(feature = "compression")]
+        other.set_compression(Some(64));
+        other.append_packet(&packet).unwrap();
+        other.append_packet(&UnitStruct).unwrap();
+        enc.append_bytes(&other.take());
+
+        let bytes = enc.take().len() as u64;
+
+        let stats = enc.stats();
+        assert_eq!(stats.packets, 4);
+        assert_eq!(stats.uncompressed_bytes, 2 * (encoded.len() + 1) as u64);
+        assert_eq!(stats.bytes, bytes);
+    }
+
+    #[test]
+    fn cached_packets_match_appended_packets() {
+        let packet = TestPacket::new("cached");
+        let mut cached = CachedPacket::new(&packet).unwrap();
+
+        #[cfg(feature = "compression")]
+        let thresholds = [None, Some(0), Some(10_000), Some(0)];
+        #[cfg(not(feature = "compression"))]
+        let thresholds = [None::<u32>, None];
+
+        for threshold in thresholds {
+            let mut enc = PacketEncoder::new();
+            #[cfg(feature = "compression")]
+            enc.set_compression(threshold);
+            #[cfg(not(feature = "compression"))]
+            let _ = threshold;
+
+            enc.append_packet(&packet).unwrap();
+            let expected = enc.take();
+
+            enc.append_cached_packet(&mut cached).unwrap();
+            assert_eq!(enc.take(), expected, "threshold: {threshold:?}");
+        }
+    }
 }
@@ -23,7 +23,7 @@ pub enum ChatMode {
 }
 
 #[bitfield(u8)]
-#[derive(PartialEq, Eq, Encode, Decode)]
+#[derive(PartialEq, Eq, Default, Encode, Decode)]
 pub struct DisplayedSkinParts {
     pub cape: bool,
     pub jacket: bool,
@@ -4,7 +4,9 @@ use aes::cipher::generic_array::GenericArray;
 use aes::cipher::{BlockDecryptMut, BlockSizeUser, KeyIvInit};
 use anyhow::{bail, ensure};
 use bytes::{Buf, BytesMut};
+use tracing::warn;
 
+use crate::packet::recorder::{PacketDirection, PacketRecorder};
 use crate::packet::var_int::{VarInt, VarIntDecodeError};
 use crate::packet::{Packet, MAX_PACKET_SIZE};
 
@@ -22,6 +24,7 @@ pub struct PacketDecoder {
     compression_threshold: Option<u32>,
     #[cfg(feature = "encryption")]
     cipher: Option<Cipher>,
+    recorder: Option<PacketRecorder>,
 }
 
 impl PacketDecoder {
@@ -30,6 +33,18 @@ impl PacketDecoder {
     }
 
     pub fn try_next_packet(&mut self) -> anyhow::Result<Option<BytesMut>> {
+        let frame = self.try_next_frame()?;
+
+        if let (Some(recorder), Some(frame)) = (&self.recorder, &frame) {
+            if let Err(e) = recorder.record(PacketDirection::C2s, frame) {
+                warn!("failed to record packet: {e:#}");
+            }
+        }
+
+        Ok(frame)
+    }
+
+    fn try_next_frame(&mut self) -> anyhow::Result<Option<BytesMut>> {
         let mut r = &self.buf[..];
 
         let packet_len = match VarInt::decode_partial(&mut r) {
@@ -147,6 +162,12 @@ impl PacketDecoder {
         }
     }
 
+    /// Sets the [`PacketRecorder`] that all subsequently decoded packets are
+    /// written to. `None` disables recording.
+    pub fn set_recorder(&mut self, recorder: Option<PacketRecorder>) {
+        self.recorder = recorder;
+    }
+
     pub fn queue_bytes(&mut self, mut bytes: BytesMut) {
         #![allow(unused_mut)]
 
@@ -5,9 +5,10 @@ use aes::cipher::generic_array::GenericArray;
 #[cfg(feature = "encryption")]
 use aes::cipher::{BlockEncryptMut, BlockSizeUser, KeyIvInit};
 use anyhow::ensure;
-use bytes::{BufMut, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 use tracing::warn;
 
+use crate::packet::recorder::{PacketDirection, PacketRecorder};
 use crate::packet::var_int::VarInt;
 use crate::packet::{Encode, Packet, MAX_PACKET_SIZE};
 
@@ -25,6 +26,24 @@ pub struct PacketEncoder {
     compression_threshold: Option<u32>,
     #[cfg(feature = "encryption")]
     cipher: Option<Cipher>,
+    recorder: Option<PacketRecorder>,
+    stats: EncoderStats,
+}
+
+/// Counters of the packets written to a [`PacketEncoder`]. See
+/// [`PacketEncoder::stats`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct EncoderStats {
+    /// The number of packets appended, including the packets in raw bytes.
+    pub packets: u64,
+    /// The total size of the appended packets before compression, excluding
+    /// the packet length prefixes.
+    pub uncompressed_bytes: u64,
+    /// The number of bytes [taken] from the encoder, after compression and
+    /// encryption.
+    ///
+    /// [taken]: PacketEncoder::take
+    pub bytes: u64,
 }
 
 impl PacketEncoder {
@@ -34,9 +53,85 @@ impl PacketEncoder {
 
     #[inline]
     pub fn append_bytes(&mut self, bytes: &[u8]) {
+        self.scan_packets(bytes, true);
         self.buf.extend_from_slice(bytes)
     }
 
+    /// Counts the packets in already encoded bytes, which are compressed with
+    /// the same threshold as this encoder. If `record` is true, the packets are
+    /// also written to the recorder.
+    fn scan_packets(&mut self, mut bytes: &[u8], record: bool) {
+        while let Ok(packet_len) = VarInt::decode_partial(&mut bytes) {
+            let Some(packet) = bytes.get(..packet_len as usize) else {
+                return;
+            };
+
+            bytes = &bytes[packet.len()..];
+
+            self.stats.packets += 1;
+            self.stats.uncompressed_bytes += self.uncompressed_len(packet) as u64;
+
+            if record {
+                if let Err(e) = self.record_encoded(packet) {
+                    warn!("failed to record packet: {e:#}");
+                }
+            }
+        }
+    }
+
+    /// Writes an encoded packet without its length prefix to the recorder,
+    /// decompressing it first if needed.
+    fn record_encoded(&self, packet: &[u8]) -> anyhow::Result<()> {
+        let Some(recorder) = &self.recorder else {
+            return Ok(());
+        };
+
+        #[cfg(feature = "compression")]
+        if self.compression_threshold.is_some() {
+            use std::io::Read;
+
+            use flate2::read::ZlibDecoder;
+
+            let mut r = packet;
+            let data_len = VarInt::decode_partial(&mut r)?;
+
+            // The data length is zero when the packet is not compressed.
+            if data_len == 0 {
+                return recorder.record(PacketDirection::S2c, r);
+            }
+
+            ensure!(
+                (0..=MAX_PACKET_SIZE).contains(&data_len),
+                "decompressed packet length of {data_len} is out of bounds"
+            );
+
+            let mut data = Vec::with_capacity(data_len as usize);
+            ZlibDecoder::new(r)
+                .take(data_len as u64)
+                .read_to_end(&mut data)?;
+
+            return recorder.record(PacketDirection::S2c, &data);
+        }
+
+        recorder.record(PacketDirection::S2c, packet)
+    }
+
+    /// Returns the size of an encoded packet before compression.
+    fn uncompressed_len(&self, packet: &[u8]) -> usize {
+        #[cfg(feature = "compression")]
+        if self.compression_threshold.is_some() {
+            let mut r = packet;
+
+            // The data length is zero when the packet is not compressed.
+            return match VarInt::decode_partial(&mut r) {
+                Ok(0) | Err(_) => r.len(),
+                Ok(data_len) => data_len as usize,
+            };
+        }
+
+        packet.len()
+    }
+
     pub fn prepend_packet<'a, P>(&mut self, pkt: &P) -> anyhow::Result<()>
     where
         P: Packet<'a>,
@@ -68,29 +163,29 @@ impl PacketEncoder {
 
         let data_len = self.buf.len() - start_len;
 
-        #[cfg(feature = "compression")]
-        if let Some(threshold) = self.compression_threshold {
-            use std::io::Read;
+        self.stats.packets += 1;
+        self.stats.uncompressed_bytes += data_len as u64;
 
-            use flate2::bufread::ZlibEncoder;
-            use flate2::Compression;
+        if let Some(recorder) = &self.recorder {
+            if let Err(e) = recorder.record(PacketDirection::S2c, &self.buf[start_len..]) {
+                warn!("failed to record packet: {e:#}");
+            }
+        }
 
+        #[cfg(feature = "compression")]
+        if let Some(threshold) = self.compression_threshold {
             if data_len > threshold as usize {
-                let mut z = ZlibEncoder::new(&self.buf[start_len..], Compression::new(4));
-
-                self.compress_buf.clear();
+                compress_zlib(&self.buf[start_len..], &mut self.compress_buf)?;
 
                 let data_len_size = VarInt(data_len as i32).written_size();
 
-                let packet_len = data_len_size + z.read_to_end(&mut self.compress_buf)?;
+                let packet_len = data_len_size + self.compress_buf.len();
 
                 ensure!(
                     packet_len <= MAX_PACKET_SIZE as usize,
                     "packet exceeds maximum length"
                 );
 
-                drop(z);
-
                 self.buf.truncate(start_len);
 
                 let mut writer = (&mut self.buf).writer();
@@ -144,6 +239,29 @@ impl PacketEncoder {
         Ok(())
     }
 
+    /// Appends a [`CachedPacket`], framed for the compression threshold of this
+    /// encoder. The framed bytes are kept in the cache, so appending the same
+    /// packet to other encoders with the same threshold only copies them.
+    pub fn append_cached_packet(&mut self, pkt: &mut CachedPacket) -> anyhow::Result<()> {
+        #[cfg(feature = "compression")]
+        let bytes = pkt.framed(self.compression_threshold)?;
+
+        #[cfg(not(feature = "compression"))]
+        let bytes = pkt.framed()?;
+
+        if let Some(recorder) = &self.recorder {
+            if let Err(e) = recorder.record(PacketDirection::S2c, &pkt.data) {
+                warn!("failed to record packet: {e:#}");
+            }
+        }
+
+        // The packet was recorded above without decompressing it.
+        self.scan_packets(&bytes, false);
+        self.buf.extend_from_slice(&bytes);
+
+        Ok(())
+    }
+
     /// Takes all the packets written so far and encrypts them if encryption is
     /// enabled.
     pub fn take(&mut self) -> BytesMut {
@@ -155,9 +273,22 @@ impl PacketEncoder {
             }
         }
 
+        self.stats.bytes += self.buf.len() as u64;
+
         self.buf.split()
     }
 
+    /// Returns the number of bytes written so far which were not [taken] yet.
+    ///
+    /// [taken]: Self::take
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
     pub fn clear(&mut self) {
         self.buf.clear();
     }
@@ -176,6 +307,144 @@ impl PacketEncoder {
         assert!(self.cipher.is_none(), "encryption is already enabled");
         self.cipher = Some(Cipher::new_from_slices(key, key).expect("invalid key"));
     }
+
+    /// Returns counters of the packets written to this encoder.
+    pub fn stats(&self) -> EncoderStats {
+        self.stats
+    }
+
+    /// Sets the [`PacketRecorder`] that all subsequently appended packets are
+    /// written to. `None` disables recording.
+    pub fn set_recorder(&mut self, recorder: Option<PacketRecorder>) {
+        self.recorder = recorder;
+    }
+}
+
+/// A packet which is encoded once and then appended to many
+/// [`PacketEncoder`]s, such as a packet which is identical for every client.
+///
+/// The packet is framed lazily for every compression threshold it is appended
+/// with. Since the framing only depends on whether the packet is compressed,
+/// at most three framed variants are kept.
+#[derive(Clone, Debug)]
+pub struct CachedPacket {
+    /// The packet ID and the packet data, without any framing.
+    data: Bytes,
+    /// Framed for encoders without compression.
+    uncompressed: Option<Bytes>,
+    /// Framed for encoders with compression enabled, without compressing the
+    /// packet because it is under the threshold.
+    #[cfg(feature = "compression")]
+    below_threshold: Option<Bytes>,
+    /// Framed for encoders with compression enabled, compressed because it is
+    /// over the threshold.
+    #[cfg(feature = "compression")]
+    compressed: Option<Bytes>,
+}
+
+impl CachedPacket {
+    pub fn new<'a>(pkt: &impl Packet<'a>) -> anyhow::Result<Self> {
+        let mut data = vec![];
+        pkt.encode_packet(&mut data)?;
+
+        Ok(Self {
+            data: data.into(),
+            uncompressed: None,
+            #[cfg(feature = "compression")]
+            below_threshold: None,
+            #[cfg(feature = "compression")]
+            compressed: None,
+        })
+    }
+
+    /// Returns the packet ID and the packet data, without any framing.
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Returns the packet framed for the given compression threshold,
+    /// encoding the framing if it is not cached yet.
+    #[cfg(feature = "compression")]
+    pub fn framed(&mut self, threshold: Option<u32>) -> anyhow::Result<Bytes> {
+        let Some(threshold) = threshold else {
+            return self.framed_uncompressed();
+        };
+
+        let data_len = self.data.len();
+
+        if data_len > threshold as usize {
+            if let Some(bytes) = &self.compressed {
+                return Ok(bytes.clone());
+            }
+
+            let mut compressed = vec![];
+            compress_zlib(&self.data, &mut compressed)?;
+
+            let data_len_size = VarInt(data_len as i32).written_size();
+            let packet_len = data_len_size + compressed.len();
+
+            ensure!(
+                packet_len <= MAX_PACKET_SIZE as usize,
+                "packet exceeds maximum length"
+            );
+
+            let mut buf = Vec::with_capacity(5 + packet_len);
+
+            VarInt(packet_len as i32).encode(&mut buf)?;
+            VarInt(data_len as i32).encode(&mut buf)?;
+            buf.extend_from_slice(&compressed);
+
+            Ok(self.compressed.insert(buf.into()).clone())
+        } else {
+            if let Some(bytes) = &self.below_threshold {
+                return Ok(bytes.clone());
+            }
+
+            // One byte for the data length of zero.
+            let packet_len = 1 + data_len;
+
+            ensure!(
+                packet_len <= MAX_PACKET_SIZE as usize,
+                "packet exceeds maximum length"
+            );
+
+            let mut buf = Vec::with_capacity(5 + packet_len);
+
+            VarInt(packet_len as i32).encode(&mut buf)?;
+            // Zero for no compression on this packet.
+            VarInt(0).encode(&mut buf)?;
+            buf.extend_from_slice(&self.data);
+
+            Ok(self.below_threshold.insert(buf.into()).clone())
+        }
+    }
+
+    /// Returns the packet framed without compression, encoding the framing if
+    /// it is not cached yet.
+    #[cfg(not(feature = "compression"))]
+    pub fn framed(&mut self) -> anyhow::Result<Bytes> {
+        self.framed_uncompressed()
+    }
+
+    fn framed_uncompressed(&mut self) -> anyhow::Result<Bytes> {
+        if let Some(bytes) = &self.uncompressed {
+            return Ok(bytes.clone());
+        }
+
+        let packet_len = self.data.len();
+
+        ensure!(
+            packet_len <= MAX_PACKET_SIZE as usize,
+            "packet exceeds maximum length"
+        );
+
+        let mut buf = Vec::with_capacity(5 + packet_len);
+
+        VarInt(packet_len as i32).encode(&mut buf)?;
+        buf.extend_from_slice(&self.data);
+
+        Ok(self.uncompressed.insert(buf.into()).clone())
+    }
 }
 
 /// Types that can have packets written to them.
@@ -290,11 +559,6 @@ pub fn encode_packet_compressed<'a, P>(
 where
     P: Packet<'a>,
 {
-    use std::io::Read;
-
-    use flate2::bufread::ZlibEncoder;
-    use flate2::Compression;
-
     let start_len = buf.len();
 
     pkt.encode_packet(&mut *buf)?;
@@ -302,21 +566,17 @@ where
     let data_len = buf.len() - start_len;
 
     if data_len > threshold as usize {
-        let mut z = ZlibEncoder::new(&buf[start_len..], Compression::new(4));
-
-        scratch.clear();
+        compress_zlib(&buf[start_len..], scratch)?;
 
         let data_len_size = VarInt(data_len as i32).written_size();
 
-        let packet_len = data_len_size + z.read_to_end(scratch)?;
+        let packet_len = data_len_size + scratch.len();
 
         ensure!(
             packet_len <= MAX_PACKET_SIZE as usize,
             "packet exceeds maximum length"
         );
 
-        drop(z);
-
         buf.truncate(start_len);
 
         VarInt(packet_len as i32).encode(&mut *buf)?;
@@ -347,3 +607,37 @@ where
 
     Ok(())
 }
+
+/// Compresses `data` with zlib, replacing the contents of `out`.
+///
+/// Creating a compressor allocates a lot of memory for its state, so every
+/// thread keeps one around and resets it for each packet.
+#[cfg(feature = "compression")]
+fn compress_zlib(data: &[u8], out: &mut Vec<u8>) -> anyhow::Result<()> {
+    use std::cell::RefCell;
+
+    use flate2::{Compress, Compression, FlushCompress, Status};
+
+    thread_local! {
+        static COMPRESSOR: RefCell<Compress> =
+            RefCell::new(Compress::new(Compression::new(4), true));
+    }
+
+    COMPRESSOR.with(|compressor| {
+        let mut compressor = compressor.borrow_mut();
+        compressor.reset();
+
+        out.clear();
+        out.reserve(data.len() / 2 + 64);
+
+        loop {
+            let consumed = compressor.total_in() as usize;
+
+            match compressor.compress_vec(&data[consumed..], out, FlushCompress::Finish)? {
+                Status::StreamEnd => return Ok(()),
+                // The output buffer is full.
+                Status::Ok | Status::BufError => out.reserve(out.capacity()),
+            }
+        }
+    })
+}
@@ -13,7 +13,7 @@ use anyhow::{ensure, Result};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use glam::*;
 use uuid::Uuid;
-use valence_nbt::Compound;
+use valence_nbt::{Compound, DecodeLimits};
 
 use super::var_int::VarInt;
 use super::{Decode, Encode, MAX_PACKET_SIZE};
@@ -769,8 +769,16 @@ impl Encode for Compound {
     }
 }
 
+/// Limits on NBT received in packets. The size limit matches the one used by
+/// the vanilla client and server.
+const NBT_DECODE_LIMITS: DecodeLimits = DecodeLimits {
+    max_depth: 512,
+    max_elements: 2097152,
+    max_size: 2097152,
+};
+
 impl Decode<'_> for Compound {
     fn decode(r: &mut &[u8]) -> Result<Self> {
-        Ok(valence_nbt::from_binary_slice(r)?.0)
+        Ok(valence_nbt::from_binary_slice_with_limits(r, NBT_DECODE_LIMITS)?.0)
     }
 }
@@ -0,0 +1,376 @@
+//! Recording of packet streams to a compact binary file for debugging and
+//! replay.
+//!
+//! A [`PacketRecorder`] can be attached to a [`PacketEncoder`] and a
+//! [`PacketDecoder`] with [`PacketEncoder::set_recorder`] and
+//! [`PacketDecoder::set_recorder`]. Every packet that passes through them is
+//! then written to the recording, including packets appended to the encoder
+//! as already encoded bytes. Recordings are read back with
+//! [`PacketRecordingReader`].
+//!
+//! # Format
+//!
+//! A recording begins with a header consisting of the four magic bytes
+//! [`MAGIC`] followed by a single byte containing the format version
+//! ([`FORMAT_VERSION`]).
+//!
+//! The header is followed by zero or more entries. Each entry is prefixed with
+//! a [`VarInt`] containing the length of the remainder of the entry in bytes.
+//! The remainder contains, in order:
+//!
+//! - The packet direction as a byte (`0` for serverbound, `1` for
+//!   clientbound).
+//! - The server tick the packet was recorded on as a [`VarLong`].
+//! - The number of microseconds elapsed since the recording started as a
+//!   [`VarLong`].
+//! - The packet ID and body. This is the data after decryption and
+//!   decompression, so it can be passed directly to [`decode_packet`].
+//!
+//! [`PacketEncoder`]: crate::packet::encode::PacketEncoder
+//! [`PacketEncoder::set_recorder`]: crate::packet::encode::PacketEncoder::set_recorder
+//! [`PacketDecoder`]: crate::packet::decode::PacketDecoder
+//! [`PacketDecoder::set_recorder`]: crate::packet::decode::PacketDecoder::set_recorder
+//! [`decode_packet`]: crate::packet::decode::decode_packet
+
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, ensure, Context};
+use byteorder::ReadBytesExt;
+
+use crate::packet::var_int::VarInt;
+use crate::packet::var_long::VarLong;
+use crate::packet::{Decode, Encode, MAX_PACKET_SIZE};
+
+/// The magic bytes at the start of every recording.
+pub const MAGIC: [u8; 4] = *b"VPKR";
+
+/// The version of the recording format written by [`PacketRecorder`].
+pub const FORMAT_VERSION: u8 = 1;
+
+/// The maximum length of an entry. This is the maximum length of a packet
+/// plus the direction, tick and timestamp.
+const MAX_ENTRY_LEN: i32 = MAX_PACKET_SIZE + 1 + 10 + 10;
+
+/// The direction a recorded packet was traveling in.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PacketDirection {
+    /// Client to server.
+    C2s,
+    /// Server to client.
+    S2c,
+}
+
+/// Writes packets to a recording. See the [module level documentation] for
+/// details about the format.
+///
+/// The recorder is a cheaply cloneable handle, so the same recording can be
+/// shared between the encoder and decoder of a connection.
+///
+/// [module level documentation]: self
+#[derive(Clone)]
+pub struct PacketRecorder {
+    inner: Arc<Mutex<RecorderInner>>,
+}
+
+struct RecorderInner {
+    writer: Box<dyn Write + Send>,
+    start: Instant,
+    tick: i64,
+    entry_buf: Vec<u8>,
+}
+
+impl PacketRecorder {
+    /// Creates a new recorder and writes the recording header to `writer`.
+    pub fn new(mut writer: impl Write + Send + 'static) -> anyhow::Result<Self> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(RecorderInner {
+                writer: Box::new(writer),
+                start: Instant::now(),
+                tick: 0,
+                entry_buf: vec![],
+            })),
+        })
+    }
+
+    /// Sets the tick which subsequently recorded packets are associated with.
+    pub fn set_tick(&self, tick: i64) {
+        self.inner.lock().unwrap().tick = tick;
+    }
+
+    /// Records a single packet. `data` is the packet ID followed by the
+    /// packet body.
+    pub fn record(&self, direction: PacketDirection, data: &[u8]) -> anyhow::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+
+        let micros = inner.start.elapsed().as_micros() as i64;
+
+        inner.entry_buf.clear();
+
+        let dir_byte = match direction {
+            PacketDirection::C2s => 0_u8,
+            PacketDirection::S2c => 1_u8,
+        };
+
+        dir_byte.encode(&mut inner.entry_buf)?;
+        VarLong(inner.tick).encode(&mut inner.entry_buf)?;
+        VarLong(micros).encode(&mut inner.entry_buf)?;
+        inner.entry_buf.extend_from_slice(data);
+
+        VarInt(inner.entry_buf.len() as i32).encode(&mut inner.writer)?;
+        inner.writer.write_all(&inner.entry_buf)?;
+
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&self) -> anyhow::Result<()> {
+        Ok(self.inner.lock().unwrap().writer.flush()?)
+    }
+}
+
+/// A single packet read from a recording.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RecordedPacket {
+    pub direction: PacketDirection,
+    /// The server tick this packet was recorded on.
+    pub tick: i64,
+    /// The time elapsed between the start of the recording and this packet.
+    pub timestamp: Duration,
+    /// The packet ID followed by the packet body.
+    pub data: Vec<u8>,
+}
+
+impl RecordedPacket {
+    /// Returns the ID of this packet.
+    pub fn packet_id(&self) -> anyhow::Result<i32> {
+        Ok(VarInt::decode(&mut self.data.as_slice())?.0)
+    }
+}
+
+/// Reads packets from a recording created by [`PacketRecorder`]. Entries are
+/// returned through the [`Iterator`] implementation.
+pub struct PacketRecordingReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> PacketRecordingReader<R> {
+    /// Reads the recording header from `reader` and returns a new reader
+    /// positioned at the first entry.
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let mut magic = [0; 4];
+        reader
+            .read_exact(&mut magic)
+            .context("failed to read recording header")?;
+
+        ensure!(magic == MAGIC, "not a packet recording (bad magic bytes)");
+
+        let version = reader.read_u8()?;
+
+        ensure!(
+            version == FORMAT_VERSION,
+            "unsupported recording format version {version} (expected {FORMAT_VERSION})"
+        );
+
+        Ok(Self {
+            reader,
+            buf: vec![],
+        })
+    }
+
+    fn read_entry(&mut self) -> anyhow::Result<Option<RecordedPacket>> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        let len = VarInt::decode_partial(&mut self.reader)?;
+
+        ensure!(
+            (0..=MAX_ENTRY_LEN).contains(&len),
+            "entry length of {len} is out of bounds"
+        );
+
+        self.buf.resize(len as usize, 0);
+        self.reader
+            .read_exact(&mut self.buf)
+            .context("recording entry is truncated")?;
+
+        let mut r = self.buf.as_slice();
+
+        let direction = match u8::decode(&mut r)? {
+            0 => PacketDirection::C2s,
+            1 => PacketDirection::S2c,
+            n => bail!("invalid packet direction of {n}"),
+        };
+
+        let tick = VarLong::decode(&mut r)?.0;
+        let micros = VarLong::decode(&mut r)?.0;
+
+        ensure!(micros >= 0, "negative timestamp of {micros}");
+
+        Ok(Some(RecordedPacket {
+            direction,
+            tick,
+            timestamp: Duration::from_micros(micros as u64),
+            data: r.to_vec(),
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for PacketRecordingReader<R> {
+    type Item = anyhow::Result<RecordedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::packet::c2s::play::{C2sPlayPacket, HandSwingC2s};
+    use crate::packet::decode::{decode_packet, PacketDecoder};
+    use crate::packet::encode::{PacketEncoder, PacketWriter, WritePacket};
+    use crate::packet::s2c::play::{KeepAliveS2c, S2cPlayPacket};
+
+    /// A `Write` implementation that can be inspected after being given to the
+    /// recorder.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn recording_round_trip() {
+        let file = SharedBuf::default();
+        let recorder = PacketRecorder::new(file.clone()).unwrap();
+
+        let mut enc = PacketEncoder::new();
+        enc.set_recorder(Some(recorder.clone()));
+        #[cfg(feature = "compression")]
+        enc.set_compression(Some(0));
+
+        recorder.set_tick(5);
+        enc.append_packet(&KeepAliveS2c { id: 1234 }).unwrap();
+
+        // Replay the encoded bytes into a decoder recording the serverbound
+        // side.
+        let mut client_enc = PacketEncoder::new();
+        client_enc
+            .append_packet(&HandSwingC2s {
+                hand: Default::default(),
+            })
+            .unwrap();
+
+        let mut dec = PacketDecoder::new();
+        dec.set_recorder(Some(recorder.clone()));
+        dec.queue_bytes(client_enc.take());
+
+        recorder.set_tick(6);
+        assert!(dec.try_next_packet().unwrap().is_some());
+
+        let bytes = file.0.lock().unwrap().clone();
+        let mut reader = PacketRecordingReader::new(Cursor::new(bytes)).unwrap();
+
+        let first = reader.next().unwrap().unwrap();
+        assert_eq!(first.direction, PacketDirection::S2c);
+        assert_eq!(first.tick, 5);
+        assert_eq!(
+            first.packet_id().unwrap(),
+            <KeepAliveS2c as crate::packet::Packet>::PACKET_ID
+        );
+
+        let S2cPlayPacket::KeepAliveS2c(pkt) = decode_packet(&first.data).unwrap() else {
+            panic!("unexpected packet");
+        };
+        assert_eq!(pkt.id, 1234);
+
+        let second = reader.next().unwrap().unwrap();
+        assert_eq!(second.direction, PacketDirection::C2s);
+        assert_eq!(second.tick, 6);
+        assert!(second.timestamp >= first.timestamp);
+        assert!(matches!(
+            decode_packet(&second.data).unwrap(),
+            C2sPlayPacket::HandSwingC2s(_)
+        ));
+
+        assert!(reader.next().is_none());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn record_packet_bytes() {
+        let file = SharedBuf::default();
+        let recorder = PacketRecorder::new(file.clone()).unwrap();
+
+        // Bytes encoded elsewhere, such as the packets broadcast to every client
+        // in view of a chunk.
+        let mut buf = vec![];
+        let mut scratch = vec![];
+        let mut writer = PacketWriter::new(&mut buf, Some(0), &mut scratch);
+        writer.write_packet(&KeepAliveS2c { id: 1 });
+        writer.write_packet(&KeepAliveS2c { id: 2 });
+
+        let mut enc = PacketEncoder::new();
+        enc.set_recorder(Some(recorder));
+        enc.set_compression(Some(0));
+        enc.append_bytes(&buf);
+
+        let bytes = file.0.lock().unwrap().clone();
+        let ids: Vec<_> = PacketRecordingReader::new(Cursor::new(bytes))
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let S2cPlayPacket::KeepAliveS2c(pkt) = decode_packet(&entry.data).unwrap() else {
+                    panic!("unexpected packet");
+                };
+                pkt.id
+            })
+            .collect();
+
+        assert_eq!(ids, [1, 2]);
+    }
+
+    #[test]
+    fn reject_bad_header() {
+        assert!(PacketRecordingReader::new(Cursor::new(b"nope!".to_vec())).is_err());
+        assert!(PacketRecordingReader::new(Cursor::new(vec![])).is_err());
+    }
+
+    #[test]
+    fn truncated_entry_is_an_error() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(FORMAT_VERSION);
+        // Entry claims to be 10 bytes long but only has 2.
+        bytes.extend_from_slice(&[10, 1, 0]);
+
+        let mut reader = PacketRecordingReader::new(Cursor::new(bytes)).unwrap();
+        assert!(reader.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn oversized_entry_is_an_error() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(FORMAT_VERSION);
+        VarInt(i32::MAX).encode(&mut bytes).unwrap();
+
+        let mut reader = PacketRecordingReader::new(Cursor::new(bytes)).unwrap();
+        assert!(reader.next().unwrap().is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::io::Write;
 
 use crate::chunk_pos::ChunkPos;
 use crate::packet::{Decode, Encode};
@@ -8,9 +9,31 @@ pub struct ChunkBiomeDataS2c<'a> {
     pub chunks: Cow<'a, [ChunkBiome<'a>]>,
 }
 
-#[derive(Clone, Debug, Encode, Decode)]
+#[derive(Clone, Debug)]
 pub struct ChunkBiome<'a> {
     pub pos: ChunkPos,
     /// Chunk data structure, with sections containing only the `Biomes` field.
     pub data: &'a [u8],
 }
+
+// Unlike most packets, the position is packed into a long with `z` in the
+// upper half.
+impl Encode for ChunkBiome<'_> {
+    fn encode(&self, mut w: impl Write) -> anyhow::Result<()> {
+        self.pos.z.encode(&mut w)?;
+        self.pos.x.encode(&mut w)?;
+        self.data.encode(w)
+    }
+}
+
+impl<'a> Decode<'a> for ChunkBiome<'a> {
+    fn decode(r: &mut &'a [u8]) -> anyhow::Result<Self> {
+        let z = i32::decode(r)?;
+        let x = i32::decode(r)?;
+
+        Ok(Self {
+            pos: ChunkPos::new(x, z),
+            data: <&[u8]>::decode(r)?,
+        })
+    }
+}
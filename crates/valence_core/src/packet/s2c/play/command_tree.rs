@@ -8,13 +8,13 @@ use crate::ident::Ident;
 use crate::packet::var_int::VarInt;
 use crate::packet::{Decode, Encode};
 
-#[derive(Clone, Debug, Encode, Decode)]
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
 pub struct CommandTreeS2c<'a> {
     pub commands: Vec<Node<'a>>,
     pub root_index: VarInt,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Node<'a> {
     pub children: Vec<VarInt>,
     pub data: NodeData<'a>,
@@ -22,7 +22,7 @@ pub struct Node<'a> {
     pub redirect_node: Option<VarInt>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum NodeData<'a> {
     Root,
     Literal {
@@ -44,7 +44,7 @@ pub enum Suggestion {
     SummonableEntities,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum Parser<'a> {
     Bool,
     Float { min: Option<f32>, max: Option<f32> },
@@ -93,6 +93,7 @@ pub enum Parser<'a> {
     ResourceKey { registry: Ident<Cow<'a, str>> },
     TemplateMirror,
     TemplateRotation,
+    Heightmap,
     Uuid,
 }
 
@@ -329,7 +330,8 @@ impl Encode for Parser<'_> {
             }
             Parser::TemplateMirror => 45u8.encode(&mut w)?,
             Parser::TemplateRotation => 46u8.encode(&mut w)?,
-            Parser::Uuid => 47u8.encode(&mut w)?,
+            Parser::Heightmap => 47u8.encode(&mut w)?,
+            Parser::Uuid => 48u8.encode(&mut w)?,
         }
 
         Ok(())
@@ -434,7 +436,8 @@ impl<'a> Decode<'a> for Parser<'a> {
             },
             45 => Self::TemplateMirror,
             46 => Self::TemplateRotation,
-            47 => Self::Uuid,
+            47 => Self::Heightmap,
+            48 => Self::Uuid,
             n => bail!("unknown command parser ID of {n}"),
         })
     }
@@ -21,3 +21,28 @@ pub enum GameEventKind {
     PlayElderGuardianMobAppearance,
     EnableRespawnScreen,
 }
+
+/// The events that can be shown to a client in demo mode using
+/// [`GameEventKind::DemoEvent`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DemoEvent {
+    ShowWelcomeScreen,
+    ShowMovementControls,
+    ShowJumpControl,
+    ShowInventoryControl,
+    /// Tells the client that the demo is over and a screenshot can be taken.
+    DemoOver,
+}
+
+impl DemoEvent {
+    /// Returns the value of the [`GameStateChangeS2c`] packet for this event.
+    pub const fn value(self) -> f32 {
+        match self {
+            DemoEvent::ShowWelcomeScreen => 0.0,
+            DemoEvent::ShowMovementControls => 101.0,
+            DemoEvent::ShowJumpControl => 102.0,
+            DemoEvent::ShowInventoryControl => 103.0,
+            DemoEvent::DemoOver => 104.0,
+        }
+    }
+}
@@ -26,6 +26,7 @@ pub enum WindowType {
     Loom,
     Merchant,
     ShulkerBox,
+    LegacySmithing,
     Smithing,
     Smoker,
     Cartography,
@@ -12,6 +12,8 @@ use crate::packet::{Decode, Encode};
 #[derive(Clone, Debug)]
 pub struct ParticleS2c<'a> {
     pub particle: Cow<'a, Particle>,
+    /// If `true`, the client shows the particle from up to 512 blocks away
+    /// instead of 32 and ignores its particle settings.
     pub long_distance: bool,
     pub position: DVec3,
     pub offset: Vec3,
@@ -299,11 +301,11 @@ impl Particle {
             41 => Particle::InstantEffect,
             42 => Particle::Item(Decode::decode(r)?),
             43 => match <&str>::decode(r)? {
-                "block" => Particle::VibrationBlock {
+                "minecraft:block" | "block" => Particle::VibrationBlock {
                     block_pos: BlockPos::decode(r)?,
                     ticks: VarInt::decode(r)?.0,
                 },
-                "entity" => Particle::VibrationEntity {
+                "minecraft:entity" | "entity" => Particle::VibrationEntity {
                     entity_id: VarInt::decode(r)?.0,
                     entity_eye_height: f32::decode(r)?,
                     ticks: VarInt::decode(r)?.0,
@@ -425,7 +427,7 @@ impl Encode for Particle {
             Particle::SculkCharge { roll } => roll.encode(w),
             Particle::Item(stack) => stack.encode(w),
             Particle::VibrationBlock { block_pos, ticks } => {
-                "block".encode(&mut w)?;
+                "minecraft:block".encode(&mut w)?;
                 block_pos.encode(&mut w)?;
                 VarInt(*ticks).encode(w)
             }
@@ -434,7 +436,7 @@ impl Encode for Particle {
                 entity_eye_height,
                 ticks,
             } => {
-                "entity".encode(&mut w)?;
+                "minecraft:entity".encode(&mut w)?;
                 VarInt(*entity_id).encode(&mut w)?;
                 entity_eye_height.encode(&mut w)?;
                 VarInt(*ticks).encode(w)
@@ -444,3 +446,151 @@ impl Encode for Particle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::ItemKind;
+
+    /// Returns the bytes of a particle packet as written by the vanilla server
+    /// up to the particle data.
+    fn vanilla_header(id: u8, long_distance: bool) -> Vec<u8> {
+        let mut bytes = vec![id, long_distance as u8];
+
+        for v in [1.5_f64, 64.0, -3.25] {
+            bytes.extend(v.to_be_bytes());
+        }
+
+        for v in [0.5_f32, 0.0, 0.5, 0.1] {
+            bytes.extend(v.to_be_bytes());
+        }
+
+        bytes.extend(10_i32.to_be_bytes());
+        bytes
+    }
+
+    fn check(particle: Particle, long_distance: bool, data: &[u8]) {
+        let pkt = ParticleS2c {
+            particle: Cow::Owned(particle.clone()),
+            long_distance,
+            position: DVec3::new(1.5, 64.0, -3.25),
+            offset: Vec3::new(0.5, 0.0, 0.5),
+            max_speed: 0.1,
+            count: 10,
+        };
+
+        let mut expected = vanilla_header(particle.id() as u8, long_distance);
+        expected.extend_from_slice(data);
+
+        let mut buf = vec![];
+        pkt.encode(&mut buf).unwrap();
+        assert_eq!(buf, expected, "{particle:?}");
+
+        let mut r = expected.as_slice();
+        let decoded = ParticleS2c::decode(&mut r).unwrap();
+        assert!(r.is_empty(), "{particle:?}");
+        assert_eq!(decoded.particle.as_ref(), &particle);
+        assert_eq!(decoded.long_distance, long_distance);
+    }
+
+    #[test]
+    fn particle_without_data() {
+        check(Particle::Flame, false, &[]);
+        check(Particle::Flame, true, &[]);
+        check(Particle::SonicBoom, true, &[]);
+    }
+
+    #[test]
+    fn particle_block_state() {
+        // Block state 20000 as a VarInt.
+        check(Particle::Block(20000), false, &[0xa0, 0x9c, 0x01]);
+        check(Particle::BlockMarker(1), false, &[0x01]);
+        check(Particle::FallingDust(9), true, &[0x09]);
+    }
+
+    #[test]
+    fn particle_dust() {
+        let mut data = vec![];
+        for v in [1.0_f32, 0.5, 0.0, 2.0] {
+            data.extend(v.to_be_bytes());
+        }
+
+        check(
+            Particle::Dust {
+                rgb: Vec3::new(1.0, 0.5, 0.0),
+                scale: 2.0,
+            },
+            false,
+            &data,
+        );
+
+        let mut data = vec![];
+        for v in [1.0_f32, 0.0, 0.0, 1.5, 0.0, 0.0, 1.0] {
+            data.extend(v.to_be_bytes());
+        }
+
+        check(
+            Particle::DustColorTransition {
+                from_rgb: Vec3::new(1.0, 0.0, 0.0),
+                scale: 1.5,
+                to_rgb: Vec3::new(0.0, 0.0, 1.0),
+            },
+            false,
+            &data,
+        );
+    }
+
+    #[test]
+    fn particle_item() {
+        // Present, item ID 1 (stone), count 3, no NBT.
+        check(
+            Particle::Item(Some(ItemStack::new(ItemKind::Stone, 3, None))),
+            false,
+            &[0x01, 0x01, 0x03, 0x00],
+        );
+        check(Particle::Item(None), false, &[0x00]);
+    }
+
+    #[test]
+    fn particle_vibration() {
+        let mut data = vec![15];
+        data.extend(b"minecraft:block");
+        data.extend((1_u64 << 38 | 3 << 12 | 2).to_be_bytes());
+        data.push(40);
+
+        check(
+            Particle::VibrationBlock {
+                block_pos: BlockPos::new(1, 2, 3),
+                ticks: 40,
+            },
+            false,
+            &data,
+        );
+
+        let mut data = vec![16];
+        data.extend(b"minecraft:entity");
+        data.push(123);
+        data.extend(1.62_f32.to_be_bytes());
+        data.push(20);
+
+        check(
+            Particle::VibrationEntity {
+                entity_id: 123,
+                entity_eye_height: 1.62,
+                ticks: 20,
+            },
+            false,
+            &data,
+        );
+    }
+
+    #[test]
+    fn particle_sculk_and_shriek() {
+        check(
+            Particle::SculkCharge { roll: 0.25 },
+            false,
+            &0.25_f32.to_be_bytes(),
+        );
+        check(Particle::Shriek { delay: 300 }, false, &[0xac, 0x02]);
+    }
+}
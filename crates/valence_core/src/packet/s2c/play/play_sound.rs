@@ -29,6 +29,30 @@ pub enum SoundId<'a> {
     },
 }
 
+impl<'a> From<Ident<Cow<'a, str>>> for SoundId<'a> {
+    fn from(id: Ident<Cow<'a, str>>) -> Self {
+        Self::Direct { id, range: None }
+    }
+}
+
+impl<'a> From<Ident<&'a str>> for SoundId<'a> {
+    fn from(id: Ident<&'a str>) -> Self {
+        Self::Direct {
+            id: id.into(),
+            range: None,
+        }
+    }
+}
+
+impl From<Ident<String>> for SoundId<'_> {
+    fn from(id: Ident<String>) -> Self {
+        Self::Direct {
+            id: id.into(),
+            range: None,
+        }
+    }
+}
+
 impl Encode for SoundId<'_> {
     fn encode(&self, mut w: impl Write) -> anyhow::Result<()> {
         match self {
@@ -1,3 +1,5 @@
+use std::cell::Cell;
+
 use bevy_ecs::prelude::*;
 
 /// General-purpose reusable byte buffer.
@@ -6,3 +8,24 @@ use bevy_ecs::prelude::*;
 /// Therefore, the inner `Vec` should be cleared before use.
 #[derive(Component, Default, Debug)]
 pub struct ScratchBuf(pub Vec<u8>);
+
+thread_local! {
+    static THREAD_SCRATCH: Cell<Vec<u8>> = const { Cell::new(Vec::new()) };
+}
+
+/// Calls `f` with an empty byte buffer which is reused by later calls on the
+/// same thread. Useful where no [`ScratchBuf`] is at hand, like in code running
+/// in parallel.
+///
+/// Nested calls get a buffer of their own.
+pub fn with_thread_scratch<R>(f: impl FnOnce(&mut Vec<u8>) -> R) -> R {
+    THREAD_SCRATCH.with(|cell| {
+        let mut buf = cell.take();
+        buf.clear();
+
+        let res = f(&mut buf);
+
+        cell.set(buf);
+        res
+    })
+}
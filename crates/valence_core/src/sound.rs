@@ -14,6 +14,12 @@ impl Sound {
     }
 }
 
+impl From<Sound> for SoundId<'static> {
+    fn from(sound: Sound) -> Self {
+        sound.to_id()
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Encode, Decode)]
 pub enum SoundCategory {
     Master,
@@ -42,4 +48,16 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn custom_soundid() {
+        assert_eq!(
+            SoundId::from(ident!("my_pack:boom")),
+            SoundId::Direct {
+                id: ident!("my_pack:boom").into(),
+                range: None
+            },
+        );
+        assert_eq!(SoundId::from(Sound::BlockBellUse), Sound::BlockBellUse.to_id());
+    }
 }
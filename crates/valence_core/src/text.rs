@@ -1,5 +1,12 @@
 //! Formatted text.
 
+mod ansi;
+mod legacy;
+#[cfg(feature = "minimessage")]
+mod minimessage;
+#[cfg(feature = "translate")]
+mod translate;
+
 use std::borrow::Cow;
 use std::io::Write;
 use std::{fmt, ops};
@@ -10,7 +17,12 @@ use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use uuid::Uuid;
 use valence_nbt::Value;
 
+#[cfg(feature = "minimessage")]
+pub use self::minimessage::{MiniMessageError, MiniMessageErrorKind};
+#[cfg(feature = "translate")]
+pub use self::translate::LanguageMap;
 use crate::ident::Ident;
+use crate::item::ItemStack;
 use crate::packet::{Decode, Encode};
 
 /// Represents formatted text in Minecraft's JSON text format.
@@ -265,14 +277,18 @@ enum HoverEvent {
     ShowText(Text),
     ShowItem {
         id: Ident<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         count: Option<i32>,
-        // TODO: tag
+        /// The NBT of the item in SNBT format.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tag: Option<Cow<'static, str>>,
     },
     ShowEntity {
-        name: Text,
         #[serde(rename = "type")]
         kind: Ident<String>,
         id: Uuid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<Text>,
     },
 }
 
@@ -736,6 +752,34 @@ pub trait TextFormat: Into<Text> {
         t
     }
 
+    /// Shows the tooltip of `item` when the text is hovered over.
+    fn on_hover_show_item(self, item: &ItemStack) -> Text {
+        let mut t = self.into();
+        t.0.hover_event = Some(HoverEvent::ShowItem {
+            id: Ident::new_unchecked(format!("minecraft:{}", item.item.to_str())),
+            count: (item.count() != 1).then_some(item.count() as i32),
+            tag: item.nbt.as_ref().map(|nbt| nbt.to_snbt().into()),
+        });
+        t
+    }
+
+    /// Shows information about an entity when the text is hovered over.
+    /// `kind` is the entity type, such as `minecraft:pig`.
+    fn on_hover_show_entity(
+        self,
+        kind: impl Into<Ident<String>>,
+        id: Uuid,
+        name: Option<Text>,
+    ) -> Text {
+        let mut t = self.into();
+        t.0.hover_event = Some(HoverEvent::ShowEntity {
+            kind: kind.into(),
+            id,
+            name,
+        });
+        t
+    }
+
     fn clear_hover_event(self) -> Text {
         let mut t = self.into();
         t.0.hover_event = None;
@@ -869,6 +913,63 @@ impl Decode<'_> for Text {
     }
 }
 
+/// The effective formatting of a [`Text`] component after inheriting any
+/// unset properties from its parents.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+struct ResolvedStyle {
+    color: Option<Color>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strikethrough: bool,
+    obfuscated: bool,
+}
+
+impl ResolvedStyle {
+    /// Returns the style of `text` when it is a child of a component with this
+    /// style.
+    fn inherit(self, text: &TextInner) -> Self {
+        Self {
+            color: text.color.or(self.color),
+            bold: text.bold.unwrap_or(self.bold),
+            italic: text.italic.unwrap_or(self.italic),
+            underlined: text.underlined.unwrap_or(self.underlined),
+            strikethrough: text.strikethrough.unwrap_or(self.strikethrough),
+            obfuscated: text.obfuscated.unwrap_or(self.obfuscated),
+        }
+    }
+}
+
+impl Text {
+    /// Calls `f` with the content of every component in the text tree in
+    /// order, along with the resolved style of the component. Content other
+    /// than plain text is converted to a string in the same way as the
+    /// [`Display`](fmt::Display) impl.
+    fn for_each_styled(&self, mut f: impl FnMut(&str, ResolvedStyle)) {
+        fn inner(this: &Text, parent: ResolvedStyle, f: &mut impl FnMut(&str, ResolvedStyle)) {
+            let style = parent.inherit(&this.0);
+
+            match &this.0.content {
+                TextContent::Text { text } => f(text, style),
+                content => {
+                    let without_children = Text(Box::new(TextInner {
+                        content: content.clone(),
+                        ..Default::default()
+                    }));
+
+                    f(&without_children.to_string(), style);
+                }
+            }
+
+            for child in &this.0.extra {
+                inner(child, style, f);
+            }
+        }
+
+        inner(self, ResolvedStyle::default(), &mut f)
+    }
+}
+
 impl Default for TextContent {
     fn default() -> Self {
         Self::Text { text: "".into() }
@@ -987,6 +1088,90 @@ mod tests {
         assert_eq!(before.to_string(), after.to_string());
     }
 
+    #[test]
+    fn click_events() {
+        let txt = "[Accept]"
+            .color(Color::GREEN)
+            .on_click_run_command("/accept");
+
+        assert_eq!(
+            serde_json::to_string(&txt).unwrap(),
+            r##"{"text":"[Accept]","color":"#55ff55","clickEvent":{"action":"run_command","value":"/accept"}}"##
+        );
+
+        let samples = [
+            r#"{"text":"a","clickEvent":{"action":"open_url","value":"https://example.com"}}"#,
+            r#"{"text":"b","clickEvent":{"action":"suggest_command","value":"/msg "}}"#,
+            r#"{"text":"c","clickEvent":{"action":"copy_to_clipboard","value":"copied"}}"#,
+            r#"{"text":"d","clickEvent":{"action":"change_page","value":2}}"#,
+        ];
+
+        let built = [
+            "a".on_click_open_url("https://example.com"),
+            "b".on_click_suggest_command("/msg "),
+            "c".on_click_copy_to_clipboard("copied"),
+            "d".on_click_change_page(2),
+        ];
+
+        for (sample, built) in samples.into_iter().zip(built) {
+            let parsed: Text = serde_json::from_str(sample).unwrap();
+            assert_eq!(parsed, built);
+            assert_eq!(serde_json::to_string(&built).unwrap(), sample);
+        }
+    }
+
+    #[test]
+    fn hover_show_text() {
+        let sample = r#"{"text":"hover me","hoverEvent":{"action":"show_text","contents":{"text":"hi","bold":true}}}"#;
+
+        let txt = "hover me".on_hover_show_text("hi".bold());
+
+        assert_eq!(serde_json::to_string(&txt).unwrap(), sample);
+        assert_eq!(serde_json::from_str::<Text>(sample).unwrap(), txt);
+    }
+
+    #[test]
+    fn hover_show_item() {
+        use valence_nbt::compound;
+
+        use crate::item::ItemKind;
+
+        // Samples produced by the vanilla server.
+        let with_tag = r#"{"text":"[Diamond Sword]","hoverEvent":{"action":"show_item","contents":{"id":"minecraft:diamond_sword","tag":"{Damage:5}"}}}"#;
+        let with_count = r#"{"text":"[Stone]","hoverEvent":{"action":"show_item","contents":{"id":"minecraft:stone","count":64}}}"#;
+
+        let sword = ItemStack::new(
+            ItemKind::DiamondSword,
+            1,
+            Some(compound! { "Damage" => 5 }),
+        );
+        let stone = ItemStack::new(ItemKind::Stone, 64, None);
+
+        let txt = "[Diamond Sword]".on_hover_show_item(&sword);
+        assert_eq!(serde_json::to_string(&txt).unwrap(), with_tag);
+        assert_eq!(serde_json::from_str::<Text>(with_tag).unwrap(), txt);
+
+        let txt = "[Stone]".on_hover_show_item(&stone);
+        assert_eq!(serde_json::to_string(&txt).unwrap(), with_count);
+        assert_eq!(serde_json::from_str::<Text>(with_count).unwrap(), txt);
+    }
+
+    #[test]
+    fn hover_show_entity() {
+        let sample = r#"{"text":"Bob","hoverEvent":{"action":"show_entity","contents":{"type":"minecraft:pig","id":"1f5ad4a6-4d8e-4c9b-a0f8-2a7bb8b6c8b1","name":{"text":"Bob"}}}}"#;
+        let no_name = r#"{"text":"?","hoverEvent":{"action":"show_entity","contents":{"type":"minecraft:pig","id":"1f5ad4a6-4d8e-4c9b-a0f8-2a7bb8b6c8b1"}}}"#;
+
+        let id = Uuid::parse_str("1f5ad4a6-4d8e-4c9b-a0f8-2a7bb8b6c8b1").unwrap();
+
+        let txt = "Bob".on_hover_show_entity(ident!("pig"), id, Some("Bob".into()));
+        assert_eq!(serde_json::to_string(&txt).unwrap(), sample);
+        assert_eq!(serde_json::from_str::<Text>(sample).unwrap(), txt);
+
+        let txt = "?".on_hover_show_entity(ident!("pig"), id, None);
+        assert_eq!(serde_json::to_string(&txt).unwrap(), no_name);
+        assert_eq!(serde_json::from_str::<Text>(no_name).unwrap(), txt);
+    }
+
     #[test]
     fn text_color() {
         assert_eq!(
@@ -0,0 +1,149 @@
+//! Rendering of [`Text`] for terminals using ANSI escape sequences.
+
+use std::fmt;
+
+use super::{ResolvedStyle, Text};
+
+impl Text {
+    /// Converts this text into a string containing [ANSI escape sequences]
+    /// so that it can be displayed with colors and formatting in a terminal.
+    ///
+    /// Colors are written as 24-bit RGB colors. Bold, italic, underlined,
+    /// and strikethrough text is written with the corresponding SGR codes.
+    /// Obfuscated text and non-text content are written in the same way as
+    /// the [`Display`](fmt::Display) impl. The string always ends with the
+    /// default style.
+    ///
+    /// See [`Text::write_ansi`] for terminals that do not support colors.
+    ///
+    /// [ANSI escape sequences]: https://en.wikipedia.org/wiki/ANSI_escape_code
+    ///
+    /// ```
+    /// use valence_core::text::{Color, TextFormat};
+    ///
+    /// let txt = "Hello".color(Color::RED).bold();
+    ///
+    /// assert_eq!(txt.to_ansi(), "\x1b[0;1;38;2;255;85;85mHello\x1b[0m");
+    /// ```
+    pub fn to_ansi(&self) -> String {
+        let mut res = String::new();
+        let _ = self.write_ansi(&mut res, true);
+        res
+    }
+
+    /// Writes this text to the provided writer with [ANSI escape sequences].
+    /// If `color` is `false`, then no escape sequences are written and the
+    /// output is identical to the [`Display`](fmt::Display) impl. This is
+    /// intended for dumb terminals, or when the `NO_COLOR` environment
+    /// variable is set.
+    ///
+    /// [ANSI escape sequences]: https://en.wikipedia.org/wiki/ANSI_escape_code
+    pub fn write_ansi(&self, mut w: impl fmt::Write, color: bool) -> fmt::Result {
+        if !color {
+            return self.write_string(w);
+        }
+
+        let mut prev = ResolvedStyle::default();
+        let mut res = Ok(());
+
+        self.for_each_styled(|text, style| {
+            if res.is_err() || text.is_empty() {
+                return;
+            }
+
+            if style != prev {
+                res = write_sgr(&mut w, style);
+                prev = style;
+            }
+
+            if res.is_ok() {
+                res = w.write_str(text);
+            }
+        });
+
+        res?;
+
+        if prev != ResolvedStyle::default() {
+            w.write_str("\x1b[0m")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the SGR sequence which resets the terminal's style and then enables
+/// the given style.
+fn write_sgr(w: &mut impl fmt::Write, style: ResolvedStyle) -> fmt::Result {
+    w.write_str("\x1b[0")?;
+
+    for (enabled, code) in [
+        (style.bold, "1"),
+        (style.italic, "3"),
+        (style.underlined, "4"),
+        (style.strikethrough, "9"),
+    ] {
+        if enabled {
+            write!(w, ";{code}")?;
+        }
+    }
+
+    if let Some(color) = style.color {
+        write!(w, ";38;2;{};{};{}", color.r, color.g, color.b)?;
+    }
+
+    w.write_char('m')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::{Color, TextFormat};
+
+    #[test]
+    fn ansi_plain() {
+        assert_eq!("plain".into_text().to_ansi(), "plain");
+        assert_eq!(Text::default().to_ansi(), "");
+    }
+
+    #[test]
+    fn ansi_nested_inheritance() {
+        let txt = "a".color(Color::RED).italic()
+            + ("b".bold() + "c".not_italic().color(Color::new(1, 2, 3)))
+            + "d".clear_color();
+
+        assert_eq!(
+            txt.to_ansi(),
+            concat!(
+                "\x1b[0;3;38;2;255;85;85ma",
+                "\x1b[0;1;3;38;2;255;85;85mb",
+                "\x1b[0;1;38;2;1;2;3mc",
+                "\x1b[0;3;38;2;255;85;85md",
+                "\x1b[0m"
+            )
+        );
+    }
+
+    #[test]
+    fn ansi_siblings_reset() {
+        let txt = Text::default() + "x".underlined() + "y" + "z".strikethrough();
+
+        assert_eq!(txt.to_ansi(), "\x1b[0;4mx\x1b[0my\x1b[0;9mz\x1b[0m");
+    }
+
+    #[test]
+    fn ansi_same_style_not_repeated() {
+        let txt = "a".bold() + "b" + "c";
+
+        assert_eq!(txt.to_ansi(), "\x1b[0;1mabc\x1b[0m");
+    }
+
+    #[test]
+    fn ansi_no_color() {
+        let txt = "a".color(Color::RED).bold() + "b".underlined();
+
+        let mut s = String::new();
+        txt.write_ansi(&mut s, false).unwrap();
+
+        assert_eq!(s, txt.to_string());
+    }
+}
@@ -0,0 +1,422 @@
+//! Conversion between [`Text`] and strings using legacy `§` formatting codes.
+
+use std::fmt::Write;
+
+use super::{Color, ResolvedStyle, Text, TextFormat};
+
+/// The character used to begin a legacy formatting code.
+const SECTION_SIGN: char = '§';
+
+/// The named colors in the order of their legacy code digits `0-9a-f`.
+const LEGACY_COLORS: [Color; 16] = [
+    Color::BLACK,
+    Color::DARK_BLUE,
+    Color::DARK_GREEN,
+    Color::DARK_AQUA,
+    Color::DARK_RED,
+    Color::DARK_PURPLE,
+    Color::GOLD,
+    Color::GRAY,
+    Color::DARK_GRAY,
+    Color::BLUE,
+    Color::GREEN,
+    Color::AQUA,
+    Color::RED,
+    Color::LIGHT_PURPLE,
+    Color::YELLOW,
+    Color::WHITE,
+];
+
+impl Text {
+    /// Parses a string containing legacy `§` formatting codes into a [`Text`]
+    /// object.
+    ///
+    /// The color codes `0-9a-f`, the formatting codes `k-o`, the reset code
+    /// `r`, and hex colors of the form `§x§R§R§G§G§B§B` are supported. Codes
+    /// are case insensitive. As with the vanilla client, a color code resets
+    /// any formatting set before it. Unknown codes and a trailing `§` are
+    /// ignored.
+    ///
+    /// The text after a formatting code is a child of the text before it, so
+    /// it inherits the earlier styles until the next color or reset code.
+    ///
+    /// ```
+    /// use valence_core::text::{Color, Text, TextFormat};
+    ///
+    /// let txt = Text::from_legacy("§6Hello §lworld§r!");
+    ///
+    /// assert_eq!(
+    ///     txt,
+    ///     Text::default() + ("Hello ".color(Color::GOLD) + "world".bold()) + "!"
+    /// );
+    /// ```
+    pub fn from_legacy(legacy: &str) -> Text {
+        // The components which are still being built. The first one is the root
+        // and each one after it is the last child of the one before it.
+        let mut stack = vec![LegacyNode::default()];
+        let mut buf = String::new();
+
+        let mut chars = legacy.chars();
+
+        while let Some(c) = chars.next() {
+            if c != SECTION_SIGN {
+                buf.push(c);
+                continue;
+            }
+
+            let Some(code) = chars.next() else {
+                // Trailing section sign.
+                break;
+            };
+
+            let code = code.to_ascii_lowercase();
+
+            let color = match code {
+                d @ ('0'..='9' | 'a'..='f') => {
+                    Some(LEGACY_COLORS[d.to_digit(16).unwrap() as usize])
+                }
+                'x' => match parse_hex_color(chars.as_str()) {
+                    Some(color) => {
+                        // Skip the six `§` + digit pairs.
+                        for _ in 0..12 {
+                            chars.next();
+                        }
+
+                        Some(color)
+                    }
+                    None => continue,
+                },
+                'k' | 'l' | 'm' | 'n' | 'o' | 'r' => None,
+                _ => continue,
+            };
+
+            stack.last_mut().unwrap().push_text(&mut buf);
+
+            if let Some(color) = color {
+                pop_legacy_nodes(&mut stack);
+                stack.push(LegacyNode {
+                    style: ResolvedStyle {
+                        color: Some(color),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+                continue;
+            }
+
+            if code == 'r' {
+                pop_legacy_nodes(&mut stack);
+                continue;
+            }
+
+            // Formatting codes directly after another code apply to the same
+            // component.
+            if stack.len() == 1 || !stack.last().unwrap().is_empty() {
+                stack.push(LegacyNode::default());
+            }
+
+            let node = stack.last_mut().unwrap();
+
+            match code {
+                'k' => node.style.obfuscated = true,
+                'l' => node.style.bold = true,
+                'm' => node.style.strikethrough = true,
+                'n' => node.style.underlined = true,
+                'o' => node.style.italic = true,
+                _ => unreachable!(),
+            }
+        }
+
+        stack.last_mut().unwrap().push_text(&mut buf);
+        pop_legacy_nodes(&mut stack);
+
+        let mut root = stack.pop().unwrap();
+
+        if root.text.is_empty() && root.extra.len() == 1 {
+            root.extra.pop().unwrap()
+        } else {
+            root.into_text()
+        }
+    }
+
+    /// Converts this text into a string using legacy `§` formatting codes.
+    ///
+    /// This conversion is lossy. Click events, hover events, fonts, and
+    /// insertions are discarded, and content other than plain text is
+    /// converted in the same way as the [`Display`](std::fmt::Display) impl.
+    /// Colors which are not one of the sixteen named colors are written in
+    /// the `§x§R§R§G§G§B§B` form.
+    ///
+    /// ```
+    /// use valence_core::text::{Color, Text, TextFormat};
+    ///
+    /// let txt = Text::default()
+    ///     + "Hello ".color(Color::GOLD).bold()
+    ///     + "world".color(Color::new(1, 2, 3));
+    ///
+    /// assert_eq!(txt.to_legacy_lossy(), "§6§lHello §x§0§1§0§2§0§3world");
+    /// ```
+    pub fn to_legacy_lossy(&self) -> String {
+        let mut res = String::new();
+        let mut prev = ResolvedStyle::default();
+
+        self.for_each_styled(|text, style| {
+            if text.is_empty() {
+                return;
+            }
+
+            if style != prev {
+                let only_adds_formatting = style.color == prev.color
+                    && (style.bold || !prev.bold)
+                    && (style.italic || !prev.italic)
+                    && (style.underlined || !prev.underlined)
+                    && (style.strikethrough || !prev.strikethrough)
+                    && (style.obfuscated || !prev.obfuscated);
+
+                // Formatting codes can only be turned off by a color or reset
+                // code.
+                let base = if only_adds_formatting {
+                    prev
+                } else {
+                    match style.color {
+                        Some(color) => write_legacy_color(&mut res, color),
+                        None => {
+                            res.push(SECTION_SIGN);
+                            res.push('r');
+                        }
+                    }
+
+                    ResolvedStyle {
+                        color: style.color,
+                        ..Default::default()
+                    }
+                };
+
+                for (enabled, was_enabled, code) in [
+                    (style.obfuscated, base.obfuscated, 'k'),
+                    (style.bold, base.bold, 'l'),
+                    (style.strikethrough, base.strikethrough, 'm'),
+                    (style.underlined, base.underlined, 'n'),
+                    (style.italic, base.italic, 'o'),
+                ] {
+                    if enabled && !was_enabled {
+                        res.push(SECTION_SIGN);
+                        res.push(code);
+                    }
+                }
+
+                prev = style;
+            }
+
+            res.push_str(text);
+        });
+
+        res
+    }
+}
+
+/// A component being built by [`Text::from_legacy`].
+#[derive(Default)]
+struct LegacyNode {
+    /// The styles set by this component. Other styles are inherited.
+    style: ResolvedStyle,
+    text: String,
+    extra: Vec<Text>,
+}
+
+impl LegacyNode {
+    fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.extra.is_empty()
+    }
+
+    /// Moves the text in `buf` to the end of this component.
+    fn push_text(&mut self, buf: &mut String) {
+        if buf.is_empty() {
+            return;
+        }
+
+        if self.extra.is_empty() {
+            self.text.push_str(buf);
+            buf.clear();
+        } else {
+            self.extra.push(Text::text(std::mem::take(buf)));
+        }
+    }
+
+    fn into_text(self) -> Text {
+        let mut txt = styled(self.text, self.style);
+        txt.0.extra = self.extra;
+        txt
+    }
+}
+
+/// Finishes every component on the stack except the root, adding them to their
+/// parents. Components without any text are dropped.
+fn pop_legacy_nodes(stack: &mut Vec<LegacyNode>) {
+    while stack.len() > 1 {
+        let node = stack.pop().unwrap();
+
+        if !node.is_empty() {
+            stack.last_mut().unwrap().extra.push(node.into_text());
+        }
+    }
+}
+
+/// Creates a plain text component with the given style applied explicitly.
+fn styled(text: String, style: ResolvedStyle) -> Text {
+    let mut txt = Text::text(text);
+
+    if let Some(color) = style.color {
+        txt = txt.color(color);
+    }
+
+    if style.bold {
+        txt = txt.bold();
+    }
+
+    if style.italic {
+        txt = txt.italic();
+    }
+
+    if style.underlined {
+        txt = txt.underlined();
+    }
+
+    if style.strikethrough {
+        txt = txt.strikethrough();
+    }
+
+    if style.obfuscated {
+        txt = txt.obfuscated();
+    }
+
+    txt
+}
+
+/// Parses the `§R§R§G§G§B§B` part of a legacy hex color (after the `§x`).
+fn parse_hex_color(s: &str) -> Option<Color> {
+    let mut chars = s.chars();
+    let mut rgb = 0_u32;
+
+    for _ in 0..6 {
+        if chars.next()? != SECTION_SIGN {
+            return None;
+        }
+
+        rgb = rgb << 4 | chars.next()?.to_digit(16)?;
+    }
+
+    Some(Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+fn write_legacy_color(res: &mut String, color: Color) {
+    if let Some(idx) = LEGACY_COLORS.iter().position(|&c| c == color) {
+        res.push(SECTION_SIGN);
+        res.push(char::from_digit(idx as u32, 16).unwrap());
+    } else {
+        res.push(SECTION_SIGN);
+        res.push('x');
+
+        for digit in format!("{:02x}{:02x}{:02x}", color.r, color.g, color.b).chars() {
+            let _ = write!(res, "{SECTION_SIGN}{digit}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_round_trip() {
+        // Strings in the form the vanilla server and common plugins produce.
+        let strings = [
+            "",
+            "plain text",
+            "§6§lHello",
+            "§eWelcome to the server!",
+            "§cError: §7you do not have permission",
+            "§a§lGREEN §7gray §kmagic",
+            "§4§l§m§n§oAll the formatting",
+            "§6Hello §lWorld",
+            "§x§1§2§3§4§5§6Hex §x§a§b§c§d§e§fcolors",
+            "Line one\n§9Line two",
+            "Unicode ❤ §dhearts §r✔",
+        ];
+
+        for s in strings {
+            let txt = Text::from_legacy(s);
+            assert_eq!(txt.to_legacy_lossy(), s, "round trip of {s:?}");
+            assert_eq!(Text::from_legacy(&txt.to_legacy_lossy()), txt);
+        }
+    }
+
+    #[test]
+    fn legacy_structure() {
+        assert_eq!(Text::from_legacy(""), Text::default());
+        assert_eq!(Text::from_legacy("foo"), "foo".into_text());
+        assert_eq!(
+            Text::from_legacy("§6§lHello"),
+            "Hello".color(Color::GOLD).bold()
+        );
+        assert_eq!(
+            Text::from_legacy("§lbold§cred"),
+            Text::default() + "bold".bold() + "red".color(Color::RED)
+        );
+        assert_eq!(
+            Text::from_legacy("§x§F§f§0§0§8§0pink"),
+            "pink".color(Color::new(0xff, 0x00, 0x80))
+        );
+    }
+
+    #[test]
+    fn legacy_nesting() {
+        assert_eq!(
+            Text::from_legacy("§6Hello §lWorld"),
+            "Hello ".color(Color::GOLD) + "World".bold()
+        );
+        assert_eq!(
+            Text::from_legacy("§cError: §lbad §oinput§r done"),
+            Text::default()
+                + ("Error: ".color(Color::RED) + ("bad ".bold() + "input".italic()))
+                + " done"
+        );
+        assert_eq!(
+            Text::from_legacy("plain §lbold§r plain"),
+            "plain ".into_text() + "bold".bold() + " plain"
+        );
+        // A color code ends the formatting before it.
+        assert_eq!(
+            Text::from_legacy("§lbold §agreen"),
+            Text::default() + "bold ".bold() + "green".color(Color::GREEN)
+        );
+    }
+
+    #[test]
+    fn legacy_uppercase_codes() {
+        assert_eq!(Text::from_legacy("§6§LHi"), Text::from_legacy("§6§lHi"));
+        assert_eq!(Text::from_legacy("§AHi"), "Hi".color(Color::GREEN));
+    }
+
+    #[test]
+    fn legacy_malformed() {
+        assert_eq!(Text::from_legacy("§"), Text::default());
+        assert_eq!(Text::from_legacy("abc§"), "abc".into_text());
+        assert_eq!(Text::from_legacy("§6abc§"), "abc".color(Color::GOLD));
+        // Unknown codes are dropped.
+        assert_eq!(Text::from_legacy("a§zb"), Text::from_legacy("ab"));
+        // Incomplete hex colors are ignored.
+        assert_eq!(Text::from_legacy("§x§1§2hi"), "hi".color(Color::DARK_GREEN));
+        assert_eq!(Text::from_legacy("§x§1§2§3§4§5").to_string(), "");
+    }
+
+    #[test]
+    fn legacy_lossy_resets() {
+        let txt = "bold".bold() + "plain".not_bold();
+        assert_eq!(txt.to_legacy_lossy(), "§lbold§rplain");
+
+        let txt = "red".color(Color::RED) + "default".clear_color();
+        // Children inherit the color of the parent.
+        assert_eq!(txt.to_legacy_lossy(), "§creddefault");
+    }
+}
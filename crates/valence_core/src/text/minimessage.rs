@@ -0,0 +1,688 @@
+//! Parsing of [`Text`] from [MiniMessage]-style markup.
+//!
+//! [MiniMessage]: https://docs.advntr.dev/minimessage/format.html
+
+use thiserror::Error;
+
+use super::{color_from_str, Color, Text, TextContent, TextFormat};
+
+/// An error produced by [`Text::from_minimessage`].
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+#[error("{kind} at byte {pos}")]
+pub struct MiniMessageError {
+    /// The byte offset in the input where the error occurred.
+    pub pos: usize,
+    pub kind: MiniMessageErrorKind,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+pub enum MiniMessageErrorKind {
+    #[error("unknown tag <{0}>")]
+    UnknownTag(String),
+    #[error("unclosed tag <{0}>")]
+    UnclosedTag(String),
+    #[error("unexpected closing tag </{0}>")]
+    UnexpectedClosingTag(String),
+    #[error("missing argument for tag <{0}>")]
+    MissingArgument(String),
+    #[error("invalid argument \"{arg}\" for tag <{tag}>")]
+    InvalidArgument { tag: String, arg: String },
+    #[error("unterminated tag")]
+    UnterminatedTag,
+}
+
+impl Text {
+    /// Parses a string of [MiniMessage]-style markup into a [`Text`] object.
+    ///
+    /// The following tags are supported:
+    /// - Colors: `<red>`, `<#ff00ff>`, `<color:red>` (or `colour`, `c`).
+    /// - Decorations: `<bold>` (`b`), `<italic>` (`i`, `em`), `<underlined>`
+    ///   (`u`), `<strikethrough>` (`st`), and `<obfuscated>` (`obf`). A
+    ///   decoration is disabled with `<!bold>` or `<bold:false>`.
+    /// - `<click:action:value>` with the actions `run_command`,
+    ///   `suggest_command`, `open_url`, `copy_to_clipboard`, and `change_page`.
+    /// - `<hover:show_text:value>`, where the value is itself markup.
+    /// - `<insert:text>` and `<font:name>`.
+    /// - `<gradient:color1:color2:...>`, which colors each character of the
+    ///   enclosed text by interpolating between the colors.
+    /// - `<newline>` (or `<br>`) and `<reset>`, which closes all open tags.
+    ///
+    /// Arguments may be quoted with `'` or `"` to include `:` or `>`, and
+    /// `\<` is a literal `<`. A `<` followed by whitespace is also literal.
+    /// Every opened tag must be closed in order, e.g. `<red>...</red>`.
+    ///
+    /// [MiniMessage]: https://docs.advntr.dev/minimessage/format.html
+    ///
+    /// ```
+    /// use valence_core::text::{Color, Text, TextFormat};
+    ///
+    /// let txt = Text::from_minimessage("<red>Hello <bold>world</bold></red>").unwrap();
+    ///
+    /// assert_eq!(
+    ///     txt,
+    ///     (Text::default() + "Hello " + "world".bold()).color(Color::RED)
+    /// );
+    /// ```
+    pub fn from_minimessage(markup: &str) -> Result<Text, MiniMessageError> {
+        Parser::new().parse(markup, 0)
+    }
+}
+
+struct Parser {
+    stack: Vec<Frame>,
+    buf: String,
+}
+
+/// An open tag and the text inside it so far.
+struct Frame {
+    /// Name of the tag, which closing tags must match.
+    name: String,
+    pos: usize,
+    kind: FrameKind,
+    children: Vec<Text>,
+}
+
+enum FrameKind {
+    Root,
+    Style(Box<dyn FnOnce(Text) -> Text>),
+    Gradient(Vec<Color>),
+}
+
+impl Parser {
+    fn new() -> Self {
+        Self {
+            stack: vec![Frame {
+                name: String::new(),
+                pos: 0,
+                kind: FrameKind::Root,
+                children: vec![],
+            }],
+            buf: String::new(),
+        }
+    }
+
+    /// Parses `input`, which begins at byte `offset` of the original string.
+    fn parse(mut self, input: &str, offset: usize) -> Result<Text, MiniMessageError> {
+        let mut rest = input;
+
+        while let Some(c) = rest.chars().next() {
+            let pos = offset + input.len() - rest.len();
+
+            match c {
+                '\\' if rest[1..].starts_with(['<', '\\']) => {
+                    self.buf.push_str(&rest[1..2]);
+                    rest = &rest[2..];
+                }
+                '<' if rest[1..].starts_with(|c: char| !c.is_whitespace() && c != '<') => {
+                    let len = tag_len(rest).ok_or(MiniMessageError {
+                        pos,
+                        kind: MiniMessageErrorKind::UnterminatedTag,
+                    })?;
+
+                    self.handle_tag(&rest[1..len - 1], pos)?;
+                    rest = &rest[len..];
+                }
+                _ => {
+                    self.buf.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+
+        self.flush();
+
+        if self.stack.len() > 1 {
+            let frame = self.stack.pop().unwrap();
+
+            return Err(MiniMessageError {
+                pos: frame.pos,
+                kind: MiniMessageErrorKind::UnclosedTag(frame.name),
+            });
+        }
+
+        let mut children = self.stack.pop().unwrap().children;
+
+        if children.len() == 1 {
+            Ok(children.pop().unwrap())
+        } else {
+            Ok(build(children))
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.buf.is_empty() {
+            let text = Text::text(std::mem::take(&mut self.buf));
+            self.stack.last_mut().unwrap().children.push(text);
+        }
+    }
+
+    fn close_top(&mut self) {
+        let frame = self.stack.pop().unwrap();
+        let txt = close(frame);
+        self.stack.last_mut().unwrap().children.push(txt);
+    }
+
+    /// Handles the contents of a tag between the `<` and `>`.
+    fn handle_tag(&mut self, tag: &str, pos: usize) -> Result<(), MiniMessageError> {
+        let err = |kind| MiniMessageError { pos, kind };
+
+        if let Some(closing) = tag.strip_prefix('/') {
+            let name = closing
+                .split(':')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+
+            if self.stack.last().unwrap().name == name && self.stack.len() > 1 {
+                self.flush();
+                self.close_top();
+                return Ok(());
+            }
+
+            // Report the innermost tag if the closing tag matches an outer one.
+            if self.stack[1..].iter().any(|f| f.name == name) {
+                let top = self.stack.last().unwrap();
+
+                return Err(MiniMessageError {
+                    pos: top.pos,
+                    kind: MiniMessageErrorKind::UnclosedTag(top.name.clone()),
+                });
+            }
+
+            return Err(err(MiniMessageErrorKind::UnexpectedClosingTag(name)));
+        }
+
+        let args = split_args(tag, pos + 1);
+        let name = args[0].1.to_ascii_lowercase();
+
+        let arg = |i: usize| {
+            args.get(i)
+                .map(|(_, a)| a.as_str())
+                .ok_or_else(|| err(MiniMessageErrorKind::MissingArgument(name.clone())))
+        };
+
+        // Joins the remaining arguments, which is needed for values such as URLs
+        // that were not quoted.
+        let rest_from = |i: usize| -> Result<String, MiniMessageError> {
+            arg(i)?;
+            Ok(args[i..]
+                .iter()
+                .map(|(_, a)| a.as_str())
+                .collect::<Vec<_>>()
+                .join(":"))
+        };
+
+        let invalid = |arg: &str| {
+            err(MiniMessageErrorKind::InvalidArgument {
+                tag: name.clone(),
+                arg: arg.to_owned(),
+            })
+        };
+
+        let kind = match name.as_str() {
+            "reset" => {
+                self.flush();
+
+                while self.stack.len() > 1 {
+                    self.close_top();
+                }
+
+                return Ok(());
+            }
+            "newline" | "br" => {
+                self.buf.push('\n');
+                return Ok(());
+            }
+            "color" | "colour" | "c" => {
+                let color = parse_color(arg(1)?).ok_or_else(|| invalid(&args[1].1))?;
+                style(move |t| t.color(color))
+            }
+            "font" => {
+                let font = rest_from(1)?;
+                style(move |t| t.font(font))
+            }
+            "insert" => {
+                let insertion = rest_from(1)?;
+                style(move |t| t.insertion(insertion))
+            }
+            "click" => {
+                let value = rest_from(2)?;
+
+                match arg(1)? {
+                    "run_command" => style(move |t| t.on_click_run_command(value)),
+                    "suggest_command" => style(move |t| t.on_click_suggest_command(value)),
+                    "open_url" => style(move |t| t.on_click_open_url(value)),
+                    "copy_to_clipboard" => style(move |t| t.on_click_copy_to_clipboard(value)),
+                    "change_page" => {
+                        let page: i32 = value.parse().map_err(|_| invalid(&value))?;
+                        style(move |t| t.on_click_change_page(page))
+                    }
+                    action => return Err(invalid(action)),
+                }
+            }
+            "hover" => {
+                match arg(1)? {
+                    "show_text" => {}
+                    action => return Err(invalid(action)),
+                }
+
+                // Parse the markup in the argument, keeping error positions
+                // relative to the whole input.
+                let value = rest_from(2)?;
+                let hover = Parser::new().parse(&value, args[2].0)?;
+
+                style(move |t| t.on_hover_show_text(hover))
+            }
+            "gradient" => {
+                let colors = args[1..]
+                    .iter()
+                    .map(|(_, a)| parse_color(a).ok_or_else(|| invalid(a)))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if colors.len() < 2 {
+                    return Err(err(MiniMessageErrorKind::MissingArgument(name)));
+                }
+
+                FrameKind::Gradient(colors)
+            }
+            _ => {
+                let (decoration, enabled) = match name.strip_prefix('!') {
+                    Some(n) => (n, false),
+                    None => (name.as_str(), true),
+                };
+
+                let enabled = match args.get(1).map(|(_, a)| a.as_str()) {
+                    None => enabled,
+                    Some("true") if enabled => true,
+                    Some("false") if enabled => false,
+                    Some(a) => return Err(invalid(a)),
+                };
+
+                match (decoration, enabled) {
+                    ("bold" | "b", true) => style(|t| t.bold()),
+                    ("bold" | "b", false) => style(|t| t.not_bold()),
+                    ("italic" | "i" | "em", true) => style(|t| t.italic()),
+                    ("italic" | "i" | "em", false) => style(|t| t.not_italic()),
+                    ("underlined" | "u", true) => style(|t| t.underlined()),
+                    ("underlined" | "u", false) => style(|t| t.not_underlined()),
+                    ("strikethrough" | "st", true) => style(|t| t.strikethrough()),
+                    ("strikethrough" | "st", false) => style(|t| t.not_strikethrough()),
+                    ("obfuscated" | "obf", true) => style(|t| t.obfuscated()),
+                    ("obfuscated" | "obf", false) => style(|t| t.not_obfuscated()),
+                    _ => match parse_color(&name) {
+                        Some(color) if args.len() == 1 => style(move |t| t.color(color)),
+                        _ => return Err(err(MiniMessageErrorKind::UnknownTag(name))),
+                    },
+                }
+            }
+        };
+
+        self.flush();
+        self.stack.push(Frame {
+            name,
+            pos,
+            kind,
+            children: vec![],
+        });
+
+        Ok(())
+    }
+}
+
+fn style(f: impl FnOnce(Text) -> Text + 'static) -> FrameKind {
+    FrameKind::Style(Box::new(f))
+}
+
+/// Returns the length of the tag at the start of `s` including the `<` and
+/// `>`, or `None` if the tag is not terminated.
+fn tag_len(s: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut chars = s.char_indices().skip(1);
+
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '>') => return Some(i + 1),
+            (None, _) => {}
+        }
+    }
+
+    None
+}
+
+/// Splits the contents of a tag into `:` separated arguments, removing quotes.
+/// Each argument is returned with its byte offset in the input, given that
+/// `tag` begins at `offset`.
+fn split_args(tag: &str, offset: usize) -> Vec<(usize, String)> {
+    let mut args = vec![];
+    let mut arg = String::new();
+    let mut arg_pos = offset;
+    let mut quote = None;
+    let mut chars = tag.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => match chars.next() {
+                Some((_, e)) if Some(e) == quote || e == '\\' => arg.push(e),
+                Some((_, e)) => {
+                    arg.push('\\');
+                    arg.push(e);
+                }
+                None => arg.push('\\'),
+            },
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => arg.push(c),
+            (None, '\'' | '"') => {
+                if arg.is_empty() {
+                    // Point at the contents of the quoted argument.
+                    arg_pos = offset + i + 1;
+                }
+
+                quote = Some(c);
+            }
+            (None, ':') => {
+                args.push((arg_pos, std::mem::take(&mut arg)));
+                arg_pos = offset + i + 1;
+            }
+            (None, c) => arg.push(c),
+        }
+    }
+
+    args.push((arg_pos, arg));
+    args
+}
+
+fn parse_color(s: &str) -> Option<Color> {
+    match s.to_ascii_lowercase().as_str() {
+        "grey" => Some(Color::GRAY),
+        "dark_grey" => Some(Color::DARK_GRAY),
+        s => color_from_str(s),
+    }
+}
+
+/// Creates a text component containing `children`.
+fn build(mut children: Vec<Text>) -> Text {
+    if children.len() == 1 && is_plain(&children[0]) {
+        children.pop().unwrap()
+    } else {
+        let mut txt = Text::default();
+        txt.0.extra = children;
+        txt
+    }
+}
+
+/// Returns whether the text is a plain string without any style or children,
+/// so that a style can be applied to it directly.
+fn is_plain(txt: &Text) -> bool {
+    match &txt.0.content {
+        TextContent::Text { text } => *txt == Text::text(text.clone()),
+        _ => false,
+    }
+}
+
+fn close(frame: Frame) -> Text {
+    let txt = build(frame.children);
+
+    match frame.kind {
+        FrameKind::Root => txt,
+        FrameKind::Style(f) => f(txt),
+        FrameKind::Gradient(colors) => {
+            let mut txt = txt;
+            let total = count_chars(&txt);
+            apply_gradient(&mut txt, &colors, total, &mut 0);
+            txt
+        }
+    }
+}
+
+fn count_chars(txt: &Text) -> usize {
+    let own = match &txt.0.content {
+        TextContent::Text { text } => text.chars().count(),
+        _ => 0,
+    };
+
+    own + txt.0.extra.iter().map(count_chars).sum::<usize>()
+}
+
+/// Colors each character of plain text without a color of its own. `idx` is
+/// the index of the next character in the gradient.
+fn apply_gradient(txt: &mut Text, colors: &[Color], total: usize, idx: &mut usize) {
+    let mut skip = 0;
+
+    if let TextContent::Text { text } = &txt.0.content {
+        let len = text.chars().count();
+
+        if txt.0.color.is_some() {
+            // Neither this text nor its children are part of the gradient, but
+            // they still take up space in it.
+            *idx += count_chars(txt);
+            return;
+        }
+
+        if len > 0 {
+            let chars: Vec<Text> = text
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    Text::text(String::from(c)).color(gradient_color(colors, *idx + i, total))
+                })
+                .collect();
+
+            *idx += len;
+            skip = chars.len();
+            txt.0.content = TextContent::Text { text: "".into() };
+            txt.0.extra.splice(0..0, chars);
+        }
+    } else if txt.0.color.is_some() {
+        *idx += count_chars(txt);
+        return;
+    }
+
+    for child in &mut txt.0.extra[skip..] {
+        apply_gradient(child, colors, total, idx);
+    }
+}
+
+/// Interpolates the color at position `i` of a gradient of `total`
+/// characters.
+fn gradient_color(colors: &[Color], i: usize, total: usize) -> Color {
+    let t = if total <= 1 {
+        0.0
+    } else {
+        i as f32 / (total - 1) as f32
+    };
+
+    let segment = t * (colors.len() - 1) as f32;
+    let j = (segment as usize).min(colors.len() - 2);
+    let local = segment - j as f32;
+
+    let (a, b) = (colors[j], colors[j + 1]);
+    let lerp = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * local).round() as u8;
+
+    Color::new(lerp(a.r, b.r), lerp(a.g, b.g), lerp(a.b, b.b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_json(markup: &str) -> String {
+        let txt = Text::from_minimessage(markup).unwrap();
+        serde_json::to_string(&txt).unwrap()
+    }
+
+    #[test]
+    fn minimessage_corpus() {
+        let corpus = [
+            ("", r#"{"text":""}"#),
+            ("plain text", r#"{"text":"plain text"}"#),
+            (
+                "<red>Hello</red>",
+                r##"{"text":"Hello","color":"#ff5555"}"##,
+            ),
+            (
+                "<red>Hello <bold>world</bold></red>",
+                r##"{"text":"","color":"#ff5555","extra":[{"text":"Hello "},{"text":"world","bold":true}]}"##,
+            ),
+            (
+                "<#ff00ff>pink</#ff00ff> and <color:dark_grey>grey</color>",
+                r##"{"text":"","extra":[{"text":"pink","color":"#ff00ff"},{"text":" and "},{"text":"grey","color":"#555555"}]}"##,
+            ),
+            (
+                "<b><i>both</i></b>",
+                r#"{"text":"","bold":true,"extra":[{"text":"both","italic":true}]}"#,
+            ),
+            (
+                "<italic>a<!italic>b</!italic></italic>",
+                r#"{"text":"","italic":true,"extra":[{"text":"a"},{"text":"b","italic":false}]}"#,
+            ),
+            (
+                "<u>x</u><st>y</st><obf>z</obf>",
+                r#"{"text":"","extra":[{"text":"x","underlined":true},{"text":"y","strikethrough":true},{"text":"z","obfuscated":true}]}"#,
+            ),
+            (
+                "<click:run_command:'/spawn'>[Spawn]</click>",
+                r#"{"text":"[Spawn]","clickEvent":{"action":"run_command","value":"/spawn"}}"#,
+            ),
+            (
+                "<click:open_url:https://example.com>link</click>",
+                r#"{"text":"link","clickEvent":{"action":"open_url","value":"https://example.com"}}"#,
+            ),
+            (
+                "<click:change_page:3>next</click>",
+                r#"{"text":"next","clickEvent":{"action":"change_page","value":3}}"#,
+            ),
+            (
+                "<hover:show_text:'<green>Click me!</green>'>hi</hover>",
+                r##"{"text":"hi","hoverEvent":{"action":"show_text","contents":{"text":"Click me!","color":"#55ff55"}}}"##,
+            ),
+            (
+                "<insert:'some: text'>shift-click</insert>",
+                r#"{"text":"shift-click","insertion":"some: text"}"#,
+            ),
+            (
+                "<font:minecraft:uniform>font</font>",
+                r#"{"text":"font","font":"minecraft:uniform"}"#,
+            ),
+            (
+                "<gradient:#000000:#ffffff>abc</gradient>",
+                r##"{"text":"","extra":[{"text":"a","color":"#000000"},{"text":"b","color":"#808080"},{"text":"c","color":"#ffffff"}]}"##,
+            ),
+            (
+                "<gradient:red:blue:green>ab<bold>c</bold>d<yellow>e</yellow></gradient>",
+                concat!(
+                    r##"{"text":"","extra":["##,
+                    r##"{"text":"","extra":[{"text":"a","color":"#ff5555"},{"text":"b","color":"#aa55aa"}]},"##,
+                    r##"{"text":"","bold":true,"extra":[{"text":"c","color":"#5555ff"}]},"##,
+                    r##"{"text":"","extra":[{"text":"d","color":"#55aaaa"}]},"##,
+                    r##"{"text":"e","color":"#ffff55"}]}"##,
+                ),
+            ),
+            (
+                "<red>a<bold>b<reset>c",
+                r##"{"text":"","extra":[{"text":"","color":"#ff5555","extra":[{"text":"a"},{"text":"b","bold":true}]},{"text":"c"}]}"##,
+            ),
+            (
+                "line<newline>two<br>three",
+                r#"{"text":"line\ntwo\nthree"}"#,
+            ),
+            (
+                r"1 < 2 and \<red> is literal",
+                r#"{"text":"1 < 2 and <red> is literal"}"#,
+            ),
+        ];
+
+        for (markup, expected) in corpus {
+            assert_eq!(to_json(markup), expected, "markup: {markup:?}");
+        }
+    }
+
+    #[test]
+    fn minimessage_errors() {
+        let cases = [
+            (
+                "<red>unclosed",
+                0,
+                MiniMessageErrorKind::UnclosedTag("red".into()),
+            ),
+            (
+                "<red><bold>x</red>",
+                5,
+                MiniMessageErrorKind::UnclosedTag("bold".into()),
+            ),
+            (
+                "text</red>",
+                4,
+                MiniMessageErrorKind::UnexpectedClosingTag("red".into()),
+            ),
+            (
+                "a <nope>b</nope>",
+                2,
+                MiniMessageErrorKind::UnknownTag("nope".into()),
+            ),
+            ("abc <red", 4, MiniMessageErrorKind::UnterminatedTag),
+            (
+                "<color>x</color>",
+                0,
+                MiniMessageErrorKind::MissingArgument("color".into()),
+            ),
+            (
+                "<color:nope>x</color>",
+                0,
+                MiniMessageErrorKind::InvalidArgument {
+                    tag: "color".into(),
+                    arg: "nope".into(),
+                },
+            ),
+            (
+                "<click:explode:x>x</click>",
+                0,
+                MiniMessageErrorKind::InvalidArgument {
+                    tag: "click".into(),
+                    arg: "explode".into(),
+                },
+            ),
+            (
+                "<gradient:red>x</gradient>",
+                0,
+                MiniMessageErrorKind::MissingArgument("gradient".into()),
+            ),
+            // Errors inside of hover text point into the argument.
+            (
+                "<hover:show_text:'<nope>'>x</hover>",
+                18,
+                MiniMessageErrorKind::UnknownTag("nope".into()),
+            ),
+        ];
+
+        for (markup, pos, kind) in cases {
+            assert_eq!(
+                Text::from_minimessage(markup),
+                Err(MiniMessageError { pos, kind }),
+                "markup: {markup:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn minimessage_gradient_endpoints() {
+        let txt = Text::from_minimessage("<gradient:red:blue>0123456789</gradient>").unwrap();
+
+        let mut colors = vec![];
+        txt.for_each_styled(|text, style| {
+            if !text.is_empty() {
+                colors.push(style.color.unwrap());
+            }
+        });
+
+        assert_eq!(colors.len(), 10);
+        assert_eq!(colors[0], Color::RED);
+        assert_eq!(colors[9], Color::BLUE);
+        assert_eq!(txt.to_string(), "0123456789");
+    }
+}
@@ -0,0 +1,250 @@
+//! Server-side resolution of translated text.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use anyhow::Context;
+
+use super::{HoverEvent, Text, TextContent};
+
+/// A mapping from translation keys to format strings, as found in the
+/// language files of the vanilla client (e.g. `assets/minecraft/lang/en_us.json`).
+///
+/// Format strings may contain `%s` placeholders, which are filled with
+/// arguments in order, positional `%1$s` placeholders, and `%%` for a literal
+/// percent sign.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct LanguageMap {
+    map: HashMap<String, String>,
+}
+
+impl LanguageMap {
+    /// Creates an empty language map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a language map from the contents of a JSON language file.
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let map = serde_json::from_str(json).context("failed to parse language file")?;
+        Ok(Self { map })
+    }
+
+    /// Reads a language map from a JSON language file.
+    pub fn from_reader(reader: impl Read) -> anyhow::Result<Self> {
+        let map = serde_json::from_reader(reader).context("failed to read language file")?;
+        Ok(Self { map })
+    }
+
+    /// Adds or replaces a translation, returning the previous format string.
+    pub fn insert(&mut self, key: impl Into<String>, format: impl Into<String>) -> Option<String> {
+        self.map.insert(key.into(), format.into())
+    }
+
+    /// Gets the format string for a translation key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.map.get(key).map(|s| s.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl Text {
+    /// Replaces every translated component whose key is in `lang` with the
+    /// translated text, substituting the `with` arguments into the format
+    /// string. Arguments and text in hover events are resolved recursively.
+    ///
+    /// The resolved component keeps the style and children of the original.
+    /// Components with keys not found in `lang` are left unchanged. As with
+    /// the vanilla client, an invalid format string (for instance, one that
+    /// refers to a missing argument) is used as literal text.
+    ///
+    /// ```
+    /// use valence_core::text::{LanguageMap, Text};
+    ///
+    /// let mut lang = LanguageMap::new();
+    /// lang.insert("greeting", "%2$s, %1$s!");
+    ///
+    /// let txt = Text::translate("greeting", ["world".into(), "Hello".into()]);
+    ///
+    /// assert_eq!(txt.resolve_translations(&lang).to_string(), "Hello, world!");
+    /// ```
+    pub fn resolve_translations(&self, lang: &LanguageMap) -> Text {
+        let mut res = self.clone();
+        resolve_in_place(&mut res, lang);
+        res
+    }
+}
+
+fn resolve_in_place(txt: &mut Text, lang: &LanguageMap) {
+    for child in &mut txt.0.extra {
+        resolve_in_place(child, lang);
+    }
+
+    if let Some(HoverEvent::ShowText(hover)) = &mut txt.0.hover_event {
+        resolve_in_place(hover, lang);
+    }
+
+    if let TextContent::Translate { translate, with } = &mut txt.0.content {
+        for arg in with.iter_mut() {
+            resolve_in_place(arg, lang);
+        }
+
+        if let Some(format) = lang.get(translate) {
+            let parts =
+                format_parts(format, with).unwrap_or_else(|| vec![Text::text(format.to_owned())]);
+
+            txt.0.content = TextContent::Text { text: "".into() };
+            // The arguments were resolved above.
+            txt.0.extra.splice(0..0, parts);
+        }
+    }
+}
+
+/// Splits a format string into literal text and arguments. Returns `None` if
+/// the format string is invalid.
+fn format_parts(format: &str, args: &[Text]) -> Option<Vec<Text>> {
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut next_arg = 0;
+    let mut rest = format;
+
+    while let Some(idx) = rest.find('%') {
+        literal.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+
+        let arg_idx = if let Some(r) = rest.strip_prefix('%') {
+            literal.push('%');
+            rest = r;
+            continue;
+        } else if let Some(r) = rest.strip_prefix('s') {
+            rest = r;
+            next_arg += 1;
+            next_arg - 1
+        } else {
+            // Positional argument of the form `%<n>$s`.
+            let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+            let n: usize = rest[..digits].parse().ok()?;
+            rest = rest[digits..].strip_prefix("$s")?;
+            n.checked_sub(1)?
+        };
+
+        if !literal.is_empty() {
+            parts.push(Text::text(std::mem::take(&mut literal)));
+        }
+
+        parts.push(args.get(arg_idx)?.clone());
+    }
+
+    literal.push_str(rest);
+
+    if !literal.is_empty() {
+        parts.push(Text::text(literal));
+    }
+
+    Some(parts)
+}
+
+impl From<HashMap<String, String>> for LanguageMap {
+    fn from(map: HashMap<String, String>) -> Self {
+        Self { map }
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for LanguageMap {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self {
+            map: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::{Color, TextFormat};
+
+    fn lang() -> LanguageMap {
+        LanguageMap::from_json(
+            r#"{
+                "chat.type.text": "<%s> %s",
+                "commands.give.success.single": "Gave %s [%s] to %s",
+                "reordered": "%2$s then %1$s",
+                "percent": "100%% of %s",
+                "bad": "missing %3$s",
+                "nested": "[%s]",
+                "plain": "Just text"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn resolve_sequential_args() {
+        let txt = Text::translate("chat.type.text", ["Steve".into(), "hello".into()]);
+        assert_eq!(
+            txt.resolve_translations(&lang()).to_string(),
+            "<Steve> hello"
+        );
+    }
+
+    #[test]
+    fn resolve_positional_args() {
+        let txt = Text::translate("reordered", ["a".into(), "b".into()]);
+        assert_eq!(txt.resolve_translations(&lang()).to_string(), "b then a");
+    }
+
+    #[test]
+    fn resolve_percent_escape() {
+        let txt = Text::translate("percent", ["tests".into()]);
+        assert_eq!(
+            txt.resolve_translations(&lang()).to_string(),
+            "100% of tests"
+        );
+    }
+
+    #[test]
+    fn resolve_invalid_format() {
+        let txt = Text::translate("bad", ["a".into()]);
+        assert_eq!(
+            txt.resolve_translations(&lang()).to_string(),
+            "missing %3$s"
+        );
+    }
+
+    #[test]
+    fn resolve_unknown_key_unchanged() {
+        let txt = Text::translate("unknown.key", ["a".into()]).color(Color::RED);
+        assert_eq!(txt.resolve_translations(&lang()), txt);
+    }
+
+    #[test]
+    fn resolve_recursive() {
+        let txt = Text::translate(
+            "nested",
+            [Text::translate("reordered", ["x".into(), "y".into()])],
+        )
+        .color(Color::GOLD)
+        .on_hover_show_text(Text::translate("plain", []))
+            + " "
+            + Text::translate("plain", []);
+
+        let resolved = txt.resolve_translations(&lang());
+
+        assert_eq!(resolved.to_string(), "[y then x] Just text");
+        assert_eq!(resolved.0.color, Some(Color::GOLD));
+        assert_eq!(
+            resolved.0.hover_event,
+            Some(HoverEvent::ShowText("".into_text() + "Just text"))
+        );
+    }
+}
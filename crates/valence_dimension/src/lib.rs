@@ -20,7 +20,7 @@
 use std::collections::BTreeMap;
 use std::str::FromStr;
 
-use anyhow::{bail, Context};
+use anyhow::{bail, ensure, Context};
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use tracing::{error, warn};
@@ -62,10 +62,15 @@ fn update_dimension_type_registry(
     dimension_types: Query<(Entity, &DimensionType), Changed<DimensionType>>,
 ) {
     for (entity, dim) in &dimension_types {
+        if let Err(e) = dim.validate() {
+            error!("dimension type \"{}\" is invalid: {e:#}", dim.name);
+            continue;
+        }
+
         // In case the name was changed.
         reg.name_to_dimension.insert(dim.name.clone(), entity);
 
-        let dimension_type_compound = compound! {
+        let mut dimension_type_compound = compound! {
             "ambient_light" => dim.ambient_light,
             "bed_works" => dim.bed_works,
             "coordinate_scale" => dim.coordinate_scale,
@@ -78,17 +83,22 @@ fn update_dimension_type_registry(
             "logical_height" => dim.logical_height,
             "min_y" => dim.min_y,
             "monster_spawn_block_light_limit" => dim.monster_spawn_block_light_limit,
+            "monster_spawn_light_level" => dim.monster_spawn_light_level.to_nbt(),
             "natural" => dim.natural,
             "piglin_safe" => dim.piglin_safe,
             "respawn_anchor_works" => dim.respawn_anchor_works,
             "ultrawarm" => dim.ultrawarm,
         };
 
+        if let Some(fixed_time) = dim.fixed_time {
+            dimension_type_compound.insert("fixed_time", fixed_time);
+        }
+
         let dimension_type_reg = codec.registry_mut(DimensionTypeRegistry::KEY);
 
         if let Some(value) = dimension_type_reg.iter_mut().find(|v| v.name == dim.name) {
             value.name = dim.name.clone();
-            value.element.merge(dimension_type_compound);
+            value.element = dimension_type_compound;
         } else {
             dimension_type_reg.push(RegistryValue {
                 name: dim.name.clone(),
@@ -141,6 +151,12 @@ fn load_default_dimension_types(
                     bed_works: *get!("bed_works", Value::as_byte) != 0,
                     coordinate_scale: *get!("coordinate_scale", Value::as_double),
                     effects: DimensionEffects::from_str(get!("effects", Value::as_string))?,
+                    fixed_time: match value.element.get("fixed_time") {
+                        Some(fixed_time) => {
+                            Some(*fixed_time.as_long().context("invalid fixed_time")?)
+                        }
+                        None => None,
+                    },
                     has_ceiling: *get!("has_ceiling", Value::as_byte) != 0,
                     has_raids: *get!("has_raids", Value::as_byte) != 0,
                     has_skylight: *get!("has_skylight", Value::as_byte) != 0,
@@ -152,6 +168,11 @@ fn load_default_dimension_types(
                         "monster_spawn_block_light_limit",
                         Value::as_int
                     ),
+                    monster_spawn_light_level: MonsterSpawnLightLevel::from_nbt(get!(
+                        "monster_spawn_light_level",
+                        Some
+                    ))
+                    .context("invalid monster_spawn_light_level")?,
                     natural: *get!("natural", Value::as_byte) != 0,
                     piglin_safe: *get!("piglin_safe", Value::as_byte) != 0,
                     respawn_anchor_works: *get!("respawn_anchor_works", Value::as_byte) != 0,
@@ -193,22 +214,43 @@ impl DimensionTypeRegistry {
     }
 }
 
+/// A dimension type in the dimension type registry. Instances are created
+/// with a dimension type, which determines their height among other things.
+///
+/// Dimension types which fail [`DimensionType::validate`] are not added to the
+/// registry.
 #[derive(Component, Clone, PartialEq, Debug)]
 pub struct DimensionType {
     pub name: Ident<String>,
+    /// How much light the dimension has without any light sources, between 0
+    /// and 1.
     pub ambient_light: f32,
     pub bed_works: bool,
+    /// The scale applied to coordinates when traveling to the dimension with a
+    /// portal.
     pub coordinate_scale: f64,
     pub effects: DimensionEffects,
+    /// The time of day which clients always display, or `None` for the
+    /// normal day cycle.
+    pub fixed_time: Option<i64>,
     pub has_ceiling: bool,
     pub has_raids: bool,
     pub has_skylight: bool,
+    /// The number of blocks from the bottom to the top of the dimension. Must
+    /// be a multiple of 16.
     pub height: i32,
+    /// The block tag of blocks which burn forever, like
+    /// `#minecraft:infiniburn_overworld`.
     pub infiniburn: String,
+    /// The height within which portals can take players and chorus fruits can
+    /// teleport them. Must not be more than `height`.
     pub logical_height: i32,
+    /// The lowest Y coordinate of the dimension. Must be a multiple of 16.
     pub min_y: i32,
+    /// The maximum block light level at which monsters can spawn.
     pub monster_spawn_block_light_limit: i32,
-    /// TODO: monster_spawn_light_level
+    /// The maximum sky light level at which monsters can spawn.
+    pub monster_spawn_light_level: MonsterSpawnLightLevel,
     pub natural: bool,
     pub piglin_safe: bool,
     pub respawn_anchor_works: bool,
@@ -223,6 +265,7 @@ impl Default for DimensionType {
             bed_works: true,
             coordinate_scale: 1.0,
             effects: DimensionEffects::default(),
+            fixed_time: None,
             has_ceiling: false,
             has_raids: true,
             has_skylight: true,
@@ -231,6 +274,10 @@ impl Default for DimensionType {
             logical_height: 384,
             min_y: -64,
             monster_spawn_block_light_limit: 0,
+            monster_spawn_light_level: MonsterSpawnLightLevel::Uniform {
+                min_inclusive: 0,
+                max_inclusive: 7,
+            },
             natural: true,
             piglin_safe: false,
             respawn_anchor_works: true,
@@ -239,6 +286,154 @@ impl Default for DimensionType {
     }
 }
 
+impl DimensionType {
+    /// The lowest Y coordinate any dimension can have.
+    pub const LOWEST_Y: i32 = -2032;
+    /// The highest Y coordinate any dimension can have.
+    pub const HIGHEST_Y: i32 = 2031;
+    /// The maximum height of any dimension.
+    pub const MAX_HEIGHT: i32 = Self::HIGHEST_Y - Self::LOWEST_Y + 1;
+
+    /// Checks the constraints vanilla clients enforce on dimension types.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.height >= 16 && self.height % 16 == 0,
+            "height of {} is not a positive multiple of 16",
+            self.height
+        );
+        ensure!(
+            self.min_y % 16 == 0,
+            "min_y of {} is not a multiple of 16",
+            self.min_y
+        );
+        ensure!(
+            self.min_y >= Self::LOWEST_Y,
+            "min_y of {} is below {}",
+            self.min_y,
+            Self::LOWEST_Y
+        );
+        ensure!(
+            self.height <= Self::MAX_HEIGHT,
+            "height of {} is greater than {}",
+            self.height,
+            Self::MAX_HEIGHT
+        );
+        ensure!(
+            self.min_y + self.height - 1 <= Self::HIGHEST_Y,
+            "the top of the dimension at y = {} is above {}",
+            self.min_y + self.height - 1,
+            Self::HIGHEST_Y
+        );
+        ensure!(
+            (0..=self.height).contains(&self.logical_height),
+            "logical_height of {} is not between 0 and the height of {}",
+            self.logical_height,
+            self.height
+        );
+        ensure!(
+            (0.0..=1.0).contains(&self.ambient_light),
+            "ambient_light of {} is not between 0 and 1",
+            self.ambient_light
+        );
+        ensure!(
+            (1e-5..=3e7).contains(&self.coordinate_scale),
+            "coordinate_scale of {} is not between 0.00001 and 30000000",
+            self.coordinate_scale
+        );
+        ensure!(
+            (0..=15).contains(&self.monster_spawn_block_light_limit),
+            "monster_spawn_block_light_limit of {} is not between 0 and 15",
+            self.monster_spawn_block_light_limit
+        );
+
+        self.monster_spawn_light_level
+            .validate()
+            .context("invalid monster_spawn_light_level")
+    }
+}
+
+/// The maximum sky light level at which monsters can spawn, which is chosen
+/// randomly for each spawn attempt.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MonsterSpawnLightLevel {
+    Constant(i32),
+    /// A level chosen uniformly between the bounds.
+    Uniform {
+        min_inclusive: i32,
+        max_inclusive: i32,
+    },
+}
+
+impl MonsterSpawnLightLevel {
+    fn validate(self) -> anyhow::Result<()> {
+        let (min, max) = match self {
+            Self::Constant(level) => (level, level),
+            Self::Uniform {
+                min_inclusive,
+                max_inclusive,
+            } => (min_inclusive, max_inclusive),
+        };
+
+        ensure!(
+            0 <= min && min <= max && max <= 15,
+            "light level range of {min}..={max} is not within 0..=15"
+        );
+
+        Ok(())
+    }
+
+    fn to_nbt(self) -> Value {
+        match self {
+            Self::Constant(level) => level.into(),
+            Self::Uniform {
+                min_inclusive,
+                max_inclusive,
+            } => compound! {
+                "type" => "minecraft:uniform",
+                "value" => compound! {
+                    "min_inclusive" => min_inclusive,
+                    "max_inclusive" => max_inclusive,
+                },
+            }
+            .into(),
+        }
+    }
+
+    fn from_nbt(value: &Value) -> anyhow::Result<Self> {
+        match value {
+            Value::Int(level) => Ok(Self::Constant(*level)),
+            Value::Compound(provider) => {
+                let kind = provider
+                    .get("type")
+                    .and_then(Value::as_string)
+                    .context("missing type")?;
+
+                ensure!(
+                    Ident::new(kind.as_str())?.as_str() == "minecraft:uniform",
+                    "unsupported light level provider \"{kind}\""
+                );
+
+                let bounds = provider
+                    .get("value")
+                    .and_then(Value::as_compound)
+                    .context("missing value")?;
+
+                Ok(Self::Uniform {
+                    min_inclusive: *bounds
+                        .get("min_inclusive")
+                        .and_then(Value::as_int)
+                        .context("invalid min_inclusive")?,
+                    max_inclusive: *bounds
+                        .get("max_inclusive")
+                        .and_then(Value::as_int)
+                        .context("invalid max_inclusive")?,
+                })
+            }
+            _ => bail!("light level is not an int or compound"),
+        }
+    }
+}
+
 /// Determines what skybox/fog effects to use in dimensions.
 #[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
 pub enum DimensionEffects {
@@ -270,3 +465,69 @@ impl FromStr for DimensionEffects {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimension_type_constraints() {
+        let dim = DimensionType {
+            name: ident!("custom:tall").into(),
+            ambient_light: 0.5,
+            has_skylight: false,
+            min_y: 0,
+            height: 1024,
+            logical_height: 1024,
+            ..Default::default()
+        };
+
+        dim.validate().unwrap();
+
+        for invalid in [
+            DimensionType {
+                height: 1000,
+                ..dim.clone()
+            },
+            DimensionType {
+                min_y: -2048,
+                ..dim.clone()
+            },
+            DimensionType {
+                min_y: 2032 - 1008,
+                ..dim.clone()
+            },
+            DimensionType {
+                height: 4080,
+                min_y: -2032,
+                ..dim.clone()
+            },
+            DimensionType {
+                logical_height: 1040,
+                ..dim.clone()
+            },
+            DimensionType {
+                monster_spawn_light_level: MonsterSpawnLightLevel::Constant(16),
+                ..dim.clone()
+            },
+        ] {
+            assert!(invalid.validate().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn monster_spawn_light_level_nbt() {
+        for level in [
+            MonsterSpawnLightLevel::Constant(7),
+            MonsterSpawnLightLevel::Uniform {
+                min_inclusive: 0,
+                max_inclusive: 7,
+            },
+        ] {
+            assert_eq!(
+                MonsterSpawnLightLevel::from_nbt(&level.to_nbt()).unwrap(),
+                level
+            );
+        }
+    }
+}
@@ -291,6 +291,8 @@ fn build() -> anyhow::Result<TokenStream> {
     let mut entity_kind_consts = TokenStream::new();
     let mut entity_kind_fmt_args = TokenStream::new();
     let mut translation_key_arms = TokenStream::new();
+    let mut from_ident_arms = TokenStream::new();
+    let mut to_ident_arms = TokenStream::new();
     let mut modules = TokenStream::new();
     let mut systems = TokenStream::new();
     let mut system_names = vec![];
@@ -341,6 +343,16 @@ fn build() -> anyhow::Result<TokenStream> {
                 EntityKind::#stripped_shouty_entity_name_ident => #translation_key_expr,
             }]);
 
+            let entity_ident = format!("minecraft:{entity_type}");
+
+            from_ident_arms.extend([quote! {
+                #entity_ident => Some(EntityKind::#stripped_shouty_entity_name_ident),
+            }]);
+
+            to_ident_arms.extend([quote! {
+                EntityKind::#stripped_shouty_entity_name_ident => Some(ident!(#entity_ident)),
+            }]);
+
             // Create bundle type.
             let mut bundle_fields = TokenStream::new();
             let mut bundle_init_fields = TokenStream::new();
@@ -571,6 +583,24 @@ fn build() -> anyhow::Result<TokenStream> {
                     _ => None,
                 }
             }
+
+            /// Gets the entity kind from its resource identifier, such as
+            /// `minecraft:zombie`.
+            pub fn from_ident(ident: Ident<&str>) -> Option<Self> {
+                match ident.as_str() {
+                    #from_ident_arms
+                    _ => None,
+                }
+            }
+
+            /// Returns the resource identifier of this entity kind, or `None`
+            /// if the kind is unknown.
+            pub fn ident(self) -> Option<Ident<&'static str>> {
+                match self {
+                    #to_ident_arms
+                    _ => None,
+                }
+            }
         }
 
         impl std::fmt::Debug for EntityKind {
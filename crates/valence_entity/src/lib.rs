@@ -31,6 +31,8 @@ use tracing::warn;
 use uuid::Uuid;
 use valence_core::chunk_pos::ChunkPos;
 use valence_core::despawn::Despawned;
+use valence_core::ident;
+use valence_core::ident::Ident;
 use valence_core::packet::var_int::VarInt;
 use valence_core::packet::{Decode, Encode};
 use valence_core::uuid::UniqueId;
@@ -500,6 +502,8 @@ impl TrackedData {
         self.update_data.push(0xff); // Add terminator.
     }
 
+    /// Removes all the update values. The buffer keeps its capacity, so
+    /// updating the tracked data again does not allocate.
     pub fn clear_update_values(&mut self) {
         self.update_data.clear();
     }
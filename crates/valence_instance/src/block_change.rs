@@ -0,0 +1,168 @@
+use bevy_ecs::prelude::*;
+use valence_block::BlockState;
+use valence_core::block_pos::BlockPos;
+
+use crate::{Block, Instance};
+
+/// An event sent when the state of a block in an instance is changed with
+/// [`Instance::set_block`], [`Instance::set_block_with_cause`],
+/// [`Clipboard::paste`](crate::Clipboard::paste), or
+/// [`Instance::fill_blocks`] with [`FillEvents::PerBlock`].
+///
+/// Events are collected over the tick and sent at the end of it. Blocks set
+/// directly through a [`Chunk`](crate::Chunk) don't send events, and neither
+/// do changes which leave the block state the same.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BlockChangeEvent {
+    /// The instance the block was changed in.
+    pub instance: Entity,
+    pub position: BlockPos,
+    pub old_state: BlockState,
+    pub new_state: BlockState,
+    pub cause: BlockChangeCause,
+}
+
+/// An event sent when a box of blocks is filled with
+/// [`Instance::fill_blocks`] and [`FillEvents::Region`], in place of a
+/// [`BlockChangeEvent`] for every changed block.
+///
+/// Only the positions in loaded chunks and within the height of the instance
+/// were changed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BlockRegionChangeEvent {
+    /// The instance the blocks were changed in.
+    pub instance: Entity,
+    /// The corner of the box with the smallest coordinates.
+    pub min: BlockPos,
+    /// The corner of the box with the largest coordinates, inclusive.
+    pub max: BlockPos,
+    pub new_state: BlockState,
+}
+
+/// What caused a [`BlockChangeEvent`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum BlockChangeCause {
+    /// The block was set by a plugin through the instance API.
+    #[default]
+    Api,
+    /// The block was placed by a player.
+    PlayerPlace {
+        /// The client which placed the block.
+        client: Entity,
+    },
+    /// The block was broken by a player.
+    PlayerBreak {
+        /// The client which broke the block.
+        client: Entity,
+    },
+}
+
+/// Determines which events [`Instance::fill_blocks`] sends.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum FillEvents {
+    /// Send a single [`BlockRegionChangeEvent`] for the whole box.
+    #[default]
+    Region,
+    /// Send a [`BlockChangeEvent`] for every block whose state was changed.
+    /// This can be a lot of events for large boxes.
+    PerBlock,
+}
+
+/// A block change recorded by an instance which hasn't been sent as an event
+/// yet.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum PendingBlockChange {
+    Block {
+        position: BlockPos,
+        old_state: BlockState,
+        new_state: BlockState,
+        cause: BlockChangeCause,
+    },
+    Region {
+        min: BlockPos,
+        max: BlockPos,
+        new_state: BlockState,
+    },
+}
+
+impl Instance {
+    /// Like [`Instance::set_block`], but the [`BlockChangeEvent`] sent for the
+    /// change has the given cause.
+    pub fn set_block_with_cause(
+        &mut self,
+        pos: impl Into<BlockPos>,
+        block: impl Into<Block>,
+        cause: BlockChangeCause,
+    ) -> Option<Block> {
+        let pos = pos.into();
+        let (chunk_pos, x, y, z) = self.chunk_offsets(pos)?;
+
+        let block = block.into();
+        let new_state = block.state();
+        let old = self.chunk_mut(chunk_pos)?.set_block(x, y, z, block);
+
+        self.record_block_change(pos, old.state(), new_state, cause);
+
+        Some(old)
+    }
+
+    /// Records a block change to be sent as a [`BlockChangeEvent`] if the state
+    /// of the block changed.
+    pub(crate) fn record_block_change(
+        &mut self,
+        position: BlockPos,
+        old_state: BlockState,
+        new_state: BlockState,
+        cause: BlockChangeCause,
+    ) {
+        if old_state != new_state {
+            self.block_changes.push(PendingBlockChange::Block {
+                position,
+                old_state,
+                new_state,
+                cause,
+            });
+        }
+    }
+}
+
+/// Sends the block changes recorded by each instance during the tick as
+/// events.
+pub(crate) fn send_block_change_events(
+    mut instances: Query<(Entity, &mut Instance)>,
+    mut block_events: EventWriter<BlockChangeEvent>,
+    mut region_events: EventWriter<BlockRegionChangeEvent>,
+) {
+    for (instance_entity, mut instance) in &mut instances {
+        if instance.block_changes.is_empty() {
+            continue;
+        }
+
+        for change in instance.block_changes.drain(..) {
+            match change {
+                PendingBlockChange::Block {
+                    position,
+                    old_state,
+                    new_state,
+                    cause,
+                } => block_events.send(BlockChangeEvent {
+                    instance: instance_entity,
+                    position,
+                    old_state,
+                    new_state,
+                    cause,
+                }),
+                PendingBlockChange::Region {
+                    min,
+                    max,
+                    new_state,
+                } => region_events.send(BlockRegionChangeEvent {
+                    instance: instance_entity,
+                    min,
+                    max,
+                    new_state,
+                }),
+            }
+        }
+    }
+}
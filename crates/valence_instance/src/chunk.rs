@@ -1,23 +1,31 @@
 use std::borrow::Cow;
 use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use num_integer::div_ceil;
 use parking_lot::Mutex; // Using nonstandard mutex to avoid poisoning API.
 use valence_biome::BiomeId;
 use valence_block::{BlockEntityKind, BlockState};
 use valence_core::block_pos::BlockPos;
 use valence_core::chunk_pos::ChunkPos;
+use valence_core::packet::array::LengthPrefixedArray;
 use valence_core::packet::encode::{PacketWriter, WritePacket};
+use valence_core::packet::s2c::play::chunk_biome_data::ChunkBiome;
 use valence_core::packet::s2c::play::chunk_data::ChunkDataBlockEntity;
 use valence_core::packet::s2c::play::{
-    BlockEntityUpdateS2c, BlockUpdateS2c, ChunkDataS2c, ChunkDeltaUpdateS2c,
+    BlockEntityUpdateS2c, BlockUpdateS2c, ChunkBiomeDataS2c, ChunkDataS2c, ChunkDeltaUpdateS2c,
+    LightUpdateS2c,
 };
 use valence_core::packet::var_int::VarInt;
 use valence_core::packet::var_long::VarLong;
 use valence_core::packet::Encode;
-use valence_nbt::{compound, Compound};
+use valence_core::scratch::with_thread_scratch;
+use valence_nbt::Compound;
 
+use crate::heightmap::{HeightmapKind, Heightmaps};
+use crate::light::{self, LightArray, LightKind};
 use crate::paletted_container::PalettedContainer;
 use crate::{bit_width, InstanceInfo};
 
@@ -39,7 +47,23 @@ pub struct Chunk<const LOADED: bool = false> {
     viewed: AtomicBool,
     /// Block entities in this chunk
     block_entities: BTreeMap<u32, BlockEntity>,
+    /// The heightmaps of this chunk, which are kept up to date as blocks
+    /// change.
+    heightmaps: Heightmaps,
     modified_block_entities: BTreeSet<u32>,
+    /// If the light in this chunk needs to be computed from scratch.
+    relight: bool,
+    /// Indices of changed blocks that may affect the light around them.
+    light_updates: Vec<u32>,
+    /// If the light in this (loaded) chunk changed since it was last sent to
+    /// clients.
+    light_modified: bool,
+    /// If the biomes in this (loaded) chunk changed since they were last sent
+    /// to clients.
+    biomes_modified: bool,
+    /// The number of consecutive ticks this (loaded) chunk has not been in view
+    /// of any client.
+    unviewed_ticks: u32,
 }
 
 #[derive(Clone, Default, Debug)]
@@ -52,6 +76,10 @@ struct Section {
     /// Contains modifications for the update section packet. (Or the regular
     /// block update packet if len == 1).
     section_updates: Vec<VarLong>,
+    sky_light: LightArray,
+    block_light: LightArray,
+    /// If the blocks or biomes changed since the section was last optimized.
+    palettes_modified: bool,
 }
 
 /// Represents a block with an optional block entity
@@ -147,6 +175,9 @@ pub struct BlockMut<'a> {
     /// Entry into the block entity map.
     entry: Entry<'a, u32, BlockEntity>,
     modified: &'a mut BTreeSet<u32>,
+    cached_init_packets: &'a mut Vec<u8>,
+    /// If modifications to the block entity should be sent to clients.
+    track_changes: bool,
 }
 
 impl<'a> BlockMut<'a> {
@@ -164,7 +195,10 @@ impl<'a> BlockMut<'a> {
     pub fn nbt_mut(&mut self) -> Option<&mut Compound> {
         match &mut self.entry {
             Entry::Occupied(entry) => {
-                self.modified.insert(*entry.key());
+                if self.track_changes {
+                    self.modified.insert(*entry.key());
+                    self.cached_init_packets.clear();
+                }
                 Some(&mut entry.get_mut().nbt)
             }
             Entry::Vacant(_) => None,
@@ -184,9 +218,29 @@ impl BlockEntity {
     }
 }
 
-const SECTION_BLOCK_COUNT: usize = 16 * 16 * 16;
+pub(crate) const SECTION_BLOCK_COUNT: usize = 16 * 16 * 16;
 const SECTION_BIOME_COUNT: usize = 4 * 4 * 4;
 
+/// The number of block changes in a section after which the chunk is resent in
+/// full rather than with a [`ChunkDeltaUpdateS2c`]. This also bounds the
+/// memory used to track changes when large regions are modified one block at a
+/// time.
+const MAX_SECTION_UPDATES: usize = SECTION_BLOCK_COUNT / 4;
+
+/// The number of light-affecting block changes after which the light in a
+/// chunk is computed from scratch instead of incrementally.
+const MAX_LIGHT_UPDATES: usize = SECTION_BLOCK_COUNT;
+
+/// Light data in the format used by the chunk data and light update packets.
+struct LightData {
+    sky_light_mask: Vec<u64>,
+    block_light_mask: Vec<u64>,
+    empty_sky_light_mask: Vec<u64>,
+    empty_block_light_mask: Vec<u64>,
+    sky_light_arrays: Vec<LengthPrefixedArray<u8, 2048>>,
+    block_light_arrays: Vec<LengthPrefixedArray<u8, 2048>>,
+}
+
 impl Chunk<false> {
     /// Constructs a new chunk containing only [`BlockState::AIR`] and
     /// [`BiomeId::default()`] with the given number of sections. A section is a
@@ -198,7 +252,13 @@ impl Chunk<false> {
             refresh: true,
             viewed: AtomicBool::new(false),
             block_entities: BTreeMap::new(),
+            heightmaps: Heightmaps::default(),
             modified_block_entities: BTreeSet::new(),
+            relight: true,
+            light_updates: vec![],
+            light_modified: false,
+            biomes_modified: false,
+            unviewed_ticks: 0,
         };
 
         chunk.resize(section_count);
@@ -219,6 +279,15 @@ impl Chunk<false> {
                 .resize_with(new_section_count, Section::default);
         } else {
             self.sections.truncate(new_section_count);
+
+            let max_idx = (new_section_count * SECTION_BLOCK_COUNT) as u32;
+            self.block_entities.retain(|&idx, _| idx < max_idx);
+
+            for z in 0..16 {
+                for x in 0..16 {
+                    self.update_heightmaps_below(x, z, new_section_count * 16);
+                }
+            }
         }
     }
 
@@ -232,7 +301,13 @@ impl Chunk<false> {
             refresh: true,
             viewed: AtomicBool::new(false),
             block_entities: self.block_entities,
+            heightmaps: self.heightmaps,
             modified_block_entities: self.modified_block_entities,
+            relight: true,
+            light_updates: vec![],
+            light_modified: false,
+            biomes_modified: false,
+            unviewed_ticks: 0,
         }
     }
 }
@@ -251,7 +326,13 @@ impl Clone for Chunk {
             refresh: true,
             viewed: AtomicBool::new(false),
             block_entities: self.block_entities.clone(),
+            heightmaps: self.heightmaps.clone(),
             modified_block_entities: BTreeSet::new(),
+            relight: true,
+            light_updates: vec![],
+            light_modified: false,
+            biomes_modified: false,
+            unviewed_ticks: 0,
         }
     }
 }
@@ -268,6 +349,9 @@ impl Chunk<true> {
                     biomes: sect.biomes.clone(),
                     non_air_count: 0,
                     section_updates: vec![], // Don't clone the section updates.
+                    sky_light: sect.sky_light.clone(),
+                    block_light: sect.block_light.clone(),
+                    palettes_modified: true,
                 }
             })
             .collect();
@@ -278,7 +362,13 @@ impl Chunk<true> {
             refresh: true,
             viewed: AtomicBool::new(false),
             block_entities: self.block_entities.clone(),
+            heightmaps: self.heightmaps.clone(),
             modified_block_entities: BTreeSet::new(),
+            relight: true,
+            light_updates: vec![],
+            light_modified: false,
+            biomes_modified: false,
+            unviewed_ticks: 0,
         }
     }
 
@@ -303,6 +393,35 @@ impl Chunk<true> {
         self.viewed.store(true, Ordering::Relaxed);
     }
 
+    /// Returns the number of consecutive ticks up to the end of the previous
+    /// tick in which this chunk was not in view of any client.
+    pub fn unviewed_ticks(&self) -> u32 {
+        self.unviewed_ticks
+    }
+
+    /// Returns `true` if clients are sent block changes in this chunk this
+    /// tick, or are sent the whole chunk again.
+    pub fn is_block_states_changed(&self) -> bool {
+        self.refresh
+            || self
+                .sections
+                .iter()
+                .any(|sect| !sect.section_updates.is_empty())
+    }
+
+    /// Counts the current tick towards [`Self::unviewed_ticks`] and returns the
+    /// new count. Must be called once per tick after clients have marked the
+    /// chunks they are viewing.
+    pub(super) fn update_unviewed_ticks(&mut self) -> u32 {
+        if self.is_viewed_mut() {
+            self.unviewed_ticks = 0;
+        } else {
+            self.unviewed_ticks = self.unviewed_ticks.saturating_add(1);
+        }
+
+        self.unviewed_ticks
+    }
+
     pub(super) fn into_unloaded(mut self) -> Chunk<false> {
         self.cached_init_packets.get_mut().clear();
 
@@ -317,7 +436,13 @@ impl Chunk<true> {
             refresh: true,
             viewed: AtomicBool::new(false),
             block_entities: self.block_entities,
+            heightmaps: self.heightmaps,
             modified_block_entities: self.modified_block_entities,
+            relight: true,
+            light_updates: vec![],
+            light_modified: false,
+            biomes_modified: false,
+            unviewed_ticks: 0,
         }
     }
 
@@ -381,9 +506,96 @@ impl Chunk<true> {
                     data: Cow::Borrowed(&block_entity.nbt),
                 })
             }
+
+            if self.light_modified && info.lighting {
+                let light = self.light_data();
+
+                writer.write_packet(&LightUpdateS2c {
+                    chunk_x: VarInt(pos.x),
+                    chunk_z: VarInt(pos.z),
+                    trust_edges: true,
+                    sky_light_mask: light.sky_light_mask,
+                    block_light_mask: light.block_light_mask,
+                    empty_sky_light_mask: light.empty_sky_light_mask,
+                    empty_block_light_mask: light.empty_block_light_mask,
+                    sky_light_arrays: light.sky_light_arrays,
+                    block_light_arrays: light.block_light_arrays,
+                });
+            }
+
+            if self.biomes_modified {
+                scratch.clear();
+
+                for sect in &self.sections {
+                    sect.biomes
+                        .encode_mc_format(
+                            &mut *scratch,
+                            |b| b.0.into(),
+                            0,
+                            3,
+                            bit_width(info.biome_registry_len - 1),
+                        )
+                        .expect("failed to encode biome paletted container");
+                }
+
+                writer.write_packet(&ChunkBiomeDataS2c {
+                    chunks: Cow::Borrowed(&[ChunkBiome { pos, data: scratch }]),
+                });
+            }
         }
     }
 
+    /// Returns the light of this chunk in the format used by packets. The
+    /// sections directly below and above the chunk are included.
+    fn light_data(&self) -> LightData {
+        let light_section_count = self.sections.len() + 2;
+        let mask_len = div_ceil(light_section_count, 64);
+
+        let mut light = LightData {
+            sky_light_mask: vec![0; mask_len],
+            block_light_mask: vec![0; mask_len],
+            empty_sky_light_mask: vec![0; mask_len],
+            empty_block_light_mask: vec![0; mask_len],
+            sky_light_arrays: vec![],
+            block_light_arrays: vec![],
+        };
+
+        let set_bit = |mask: &mut Vec<u64>, i: usize| mask[i / 64] |= 1 << (i % 64);
+
+        // There is no light below the chunk.
+        set_bit(&mut light.empty_sky_light_mask, 0);
+        set_bit(&mut light.empty_block_light_mask, 0);
+
+        for (i, sect) in self.sections.iter().enumerate() {
+            if sect.sky_light.uniform() == Some(0) {
+                set_bit(&mut light.empty_sky_light_mask, i + 1);
+            } else {
+                set_bit(&mut light.sky_light_mask, i + 1);
+                light
+                    .sky_light_arrays
+                    .push(sect.sky_light.to_packet_array());
+            }
+
+            if sect.block_light.uniform() == Some(0) {
+                set_bit(&mut light.empty_block_light_mask, i + 1);
+            } else {
+                set_bit(&mut light.block_light_mask, i + 1);
+                light
+                    .block_light_arrays
+                    .push(sect.block_light.to_packet_array());
+            }
+        }
+
+        // The sky is fully visible above the chunk.
+        set_bit(&mut light.sky_light_mask, light_section_count - 1);
+        light
+            .sky_light_arrays
+            .push(LightArray::Uniform(15).to_packet_array());
+        set_bit(&mut light.empty_block_light_mask, light_section_count - 1);
+
+        light
+    }
+
     /// Writes the chunk data packet for this chunk with the given position.
     /// This will initialize the chunk for the client.
     #[doc(hidden)]
@@ -423,14 +635,6 @@ impl Chunk<true> {
                     .expect("failed to encode biome paletted container");
             }
 
-            let mut compression_scratch = vec![];
-
-            let mut writer = PacketWriter::new(
-                &mut lck,
-                info.compression_threshold,
-                &mut compression_scratch,
-            );
-
             let block_entities: Vec<_> = self
                 .block_entities
                 .iter()
@@ -448,22 +652,43 @@ impl Chunk<true> {
                 })
                 .collect();
 
-            let heightmaps = compound! {
-                // TODO: MOTION_BLOCKING heightmap
-            };
-
-            writer.write_packet(&ChunkDataS2c {
-                pos,
-                heightmaps: Cow::Owned(heightmaps),
-                blocks_and_biomes: scratch,
-                block_entities: Cow::Borrowed(&block_entities),
-                trust_edges: true,
-                sky_light_mask: Cow::Borrowed(&info.filler_sky_light_mask),
-                block_light_mask: Cow::Borrowed(&[]),
-                empty_sky_light_mask: Cow::Borrowed(&[]),
-                empty_block_light_mask: Cow::Borrowed(&[]),
-                sky_light_arrays: Cow::Borrowed(&info.filler_sky_light_arrays),
-                block_light_arrays: Cow::Borrowed(&[]),
+            let heightmaps = self.heightmaps.to_nbt(self.section_count());
+
+            with_thread_scratch(|compression_scratch| {
+                let mut writer =
+                    PacketWriter::new(&mut lck, info.compression_threshold, compression_scratch);
+
+                if info.lighting {
+                    let light = self.light_data();
+
+                    writer.write_packet(&ChunkDataS2c {
+                        pos,
+                        heightmaps: Cow::Owned(heightmaps),
+                        blocks_and_biomes: scratch,
+                        block_entities: Cow::Borrowed(&block_entities),
+                        trust_edges: true,
+                        sky_light_mask: Cow::Owned(light.sky_light_mask),
+                        block_light_mask: Cow::Owned(light.block_light_mask),
+                        empty_sky_light_mask: Cow::Owned(light.empty_sky_light_mask),
+                        empty_block_light_mask: Cow::Owned(light.empty_block_light_mask),
+                        sky_light_arrays: Cow::Owned(light.sky_light_arrays),
+                        block_light_arrays: Cow::Owned(light.block_light_arrays),
+                    });
+                } else {
+                    writer.write_packet(&ChunkDataS2c {
+                        pos,
+                        heightmaps: Cow::Owned(heightmaps),
+                        blocks_and_biomes: scratch,
+                        block_entities: Cow::Borrowed(&block_entities),
+                        trust_edges: true,
+                        sky_light_mask: Cow::Borrowed(&info.filler_sky_light_mask),
+                        block_light_mask: Cow::Borrowed(&[]),
+                        empty_sky_light_mask: Cow::Borrowed(&[]),
+                        empty_block_light_mask: Cow::Borrowed(&[]),
+                        sky_light_arrays: Cow::Borrowed(&info.filler_sky_light_arrays),
+                        block_light_arrays: Cow::Borrowed(&[]),
+                    });
+                }
             });
         }
 
@@ -472,6 +697,8 @@ impl Chunk<true> {
 
     pub(super) fn update_post_client(&mut self) {
         self.refresh = false;
+        self.light_modified = false;
+        self.biomes_modified = false;
 
         for sect in &mut self.sections {
             sect.section_updates.clear();
@@ -510,7 +737,9 @@ impl<const LOADED: bool> Chunk<LOADED> {
 
     /// Sets the block state at the provided offsets in the chunk. The previous
     /// block state at the position is returned.
-    /// Also, the corresponding block entity is placed.
+    ///
+    /// If the block entity at the position is not of the kind required by the
+    /// new block state, the block entity is removed.
     ///
     /// **Note**: The arguments to this function are offsets from the minimum
     /// corner of the chunk in _chunk space_ rather than _world space_.
@@ -546,11 +775,12 @@ impl<const LOADED: bool> Chunk<LOADED> {
                 _ => {}
             }
 
-            if LOADED && !self.refresh {
-                self.cached_init_packets.get_mut().clear();
-                let compact = (block.to_raw() as i64) << 12 | (x << 8 | z << 4 | (y % 16)) as i64;
-                sect.section_updates.push(VarLong(compact));
-            }
+            sect.palettes_modified = true;
+
+            self.track_block_change(x, y, z, block);
+            self.track_light_change(x, y, z, old_block, block);
+            self.update_heightmaps(x, y, z, block);
+            self.remove_incompatible_block_entity((x + z * 16 + y * 16 * 16) as u32, block);
         }
 
         old_block
@@ -560,7 +790,8 @@ impl<const LOADED: bool> Chunk<LOADED> {
     ///
     /// This is semantically equivalent to setting every block in the section
     /// with [`set_block_state`]. However, this function may be implemented more
-    /// efficiently.
+    /// efficiently. Block entities in the section that are not of the kind
+    /// required by `block` are removed.
     ///
     /// # Panics
     ///
@@ -570,54 +801,405 @@ impl<const LOADED: bool> Chunk<LOADED> {
     /// [`set_block_state`]: Self::set_block_state
     #[track_caller]
     pub fn fill_block_states(&mut self, sect_y: usize, block: BlockState) {
-        let Some(sect) = self.sections.get_mut(sect_y) else {
+        if sect_y >= self.section_count() {
             panic!(
                 "section index {sect_y} out of bounds for chunk with {} sections",
                 self.section_count()
             )
-        };
+        }
 
-        if LOADED && !self.refresh {
-            if let PalettedContainer::Single(single) = &sect.block_states {
-                if block != *single {
-                    self.cached_init_packets.get_mut().clear();
-
-                    // The whole section is being modified, so any previous modifications would be
-                    // overwritten.
-                    sect.section_updates.clear();
-
-                    // Push section updates for all the blocks in the chunk.
-                    sect.section_updates.reserve_exact(SECTION_BLOCK_COUNT);
-                    let block_bits = (block.to_raw() as i64) << 12;
-                    for z in 0..16 {
-                        for x in 0..16 {
-                            let packed = block_bits | (x << 8 | z << 4 | sect_y as i64);
-                            sect.section_updates.push(VarLong(packed));
-                        }
-                    }
+        self.fill_block_states_in([0, sect_y * 16, 0], [15, sect_y * 16 + 15, 15], block);
+    }
+
+    /// Sets every block in the box between the offsets `min` and `max`
+    /// (inclusive) to the given block state. Offsets are given as `[x, y, z]`.
+    ///
+    /// This is semantically equivalent to setting every block in the box with
+    /// [`set_block_state`], but is much faster for large regions. Sections
+    /// that are entirely covered by the box are replaced with a single-value
+    /// palette. Block entities in the box that are not of the kind required by
+    /// `block` are removed.
+    ///
+    /// Rather than sending a block update for every modified block, the chunk
+    /// is resent to clients in full if any block changed.
+    ///
+    /// **Note**: The arguments to this function are offsets from the minimum
+    /// corner of the chunk in _chunk space_ rather than _world space_.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is greater than `max` on any axis or if the offsets are
+    /// outside the bounds of the chunk. `x` and `z` must be less than 16 while
+    /// `y` must be less than `section_count() * 16`.
+    ///
+    /// [`set_block_state`]: Self::set_block_state
+    #[track_caller]
+    pub fn fill_block_states_in(&mut self, min: [usize; 3], max: [usize; 3], block: BlockState) {
+        let [min_x, min_y, min_z] = min;
+        let [max_x, max_y, max_z] = max;
+
+        assert!(
+            min_x <= max_x && min_y <= max_y && min_z <= max_z,
+            "minimum chunk block offsets of {min:?} are greater than the maximum offsets of \
+             {max:?}"
+        );
+        assert!(
+            max_x < 16 && max_y < self.section_count() * 16 && max_z < 16,
+            "chunk block offsets of {max:?} are out of bounds"
+        );
+
+        let mut changed = false;
+
+        for sect_y in min_y / 16..=max_y / 16 {
+            let sect = &mut self.sections[sect_y];
+
+            let start_y = min_y.max(sect_y * 16) % 16;
+            let end_y = max_y.min(sect_y * 16 + 15) % 16;
+
+            if [min_x, start_y, min_z] == [0, 0, 0] && [max_x, end_y, max_z] == [15, 15, 15] {
+                if !matches!(sect.block_states, PalettedContainer::Single(b) if b == block) {
+                    changed = true;
                 }
+
+                sect.block_states.fill(block);
+                sect.non_air_count = if block.is_air() {
+                    0
+                } else {
+                    SECTION_BLOCK_COUNT as u16
+                };
             } else {
-                let block_bits = (block.to_raw() as i64) << 12;
-                for z in 0..16 {
-                    for x in 0..16 {
-                        let idx = x + z * 16 + sect_y * (16 * 16);
-                        if block != sect.block_states.get(idx) {
-                            self.cached_init_packets.get_mut().clear();
-                            let packed = block_bits | (x << 8 | z << 4 | sect_y) as i64;
-                            sect.section_updates.push(VarLong(packed));
+                let mut sect_changed = false;
+
+                for y in start_y..=end_y {
+                    for z in min_z..=max_z {
+                        for x in min_x..=max_x {
+                            let old_block = sect.block_states.set(x + z * 16 + y * 16 * 16, block);
+
+                            if block != old_block {
+                                sect_changed = true;
+
+                                match (block.is_air(), old_block.is_air()) {
+                                    (true, false) => sect.non_air_count -= 1,
+                                    (false, true) => sect.non_air_count += 1,
+                                    _ => {}
+                                }
+                            }
                         }
                     }
                 }
+
+                if sect_changed {
+                    changed = true;
+                    // Convert to a single-value palette if the section became uniform.
+                    sect.block_states.optimize();
+                }
+            }
+        }
+
+        if changed {
+            for z in min_z..=max_z {
+                for x in min_x..=max_x {
+                    self.update_heightmaps_in(x, z, min_y, max_y, block);
+                }
+            }
+        }
+
+        let kind = block.block_entity_kind();
+
+        self.block_entities.retain(|&idx, block_entity| {
+            let idx = idx as usize;
+            let (x, y, z) = (idx % 16, idx / (16 * 16), idx / 16 % 16);
+
+            let in_box = (min_x..=max_x).contains(&x)
+                && (min_y..=max_y).contains(&y)
+                && (min_z..=max_z).contains(&z);
+
+            !in_box || Some(block_entity.kind) == kind
+        });
+
+        if LOADED && changed {
+            self.cached_init_packets.get_mut().clear();
+            self.refresh = true;
+            self.relight = true;
+        }
+    }
+
+    /// Returns the height of the column at the provided offsets in the chunk in
+    /// the given heightmap. This is the offset from the bottom of the chunk to
+    /// the block above the highest block counted by the heightmap, or zero if
+    /// there is no such block in the column.
+    ///
+    /// **Note**: The arguments to this function are offsets from the minimum
+    /// corner of the chunk in _chunk space_ rather than _world space_.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `z` are not less than 16.
+    #[track_caller]
+    pub fn height_at(&self, x: usize, z: usize, kind: HeightmapKind) -> usize {
+        assert!(
+            x < 16 && z < 16,
+            "chunk column offsets of ({x}, {z}) are out of bounds"
+        );
+
+        self.heightmaps.get(kind, x, z) as usize
+    }
+
+    /// Returns a heightmap packed into longs, in the format of chunk data
+    /// packets and Anvil chunks.
+    pub fn packed_heightmap(&self, kind: HeightmapKind) -> Vec<i64> {
+        self.heightmaps.to_packed(kind, self.section_count())
+    }
+
+    /// Updates the heightmaps after the block at the provided offsets changed
+    /// to `block`.
+    fn update_heightmaps(&mut self, x: usize, y: usize, z: usize, block: BlockState) {
+        self.update_heightmaps_in(x, z, y, y, block);
+    }
+
+    /// Updates the heightmaps after the blocks from `min_y` to `max_y`
+    /// (inclusive) in a column changed to `block`.
+    fn update_heightmaps_in(
+        &mut self,
+        x: usize,
+        z: usize,
+        min_y: usize,
+        max_y: usize,
+        block: BlockState,
+    ) {
+        for kind in HeightmapKind::ALL {
+            let height = self.heightmaps.get(kind, x, z) as usize;
+
+            if kind.matches(block) {
+                if max_y + 1 > height {
+                    self.heightmaps.set(kind, x, z, (max_y + 1) as u16);
+                }
+            } else if (min_y + 1..=max_y + 1).contains(&height) {
+                // The highest block was replaced, so look for the next one below.
+                self.heightmaps
+                    .set(kind, x, z, self.find_height(x, z, min_y, kind) as u16);
             }
         }
+    }
+
+    /// Computes the heightmaps of a column from the blocks below `y`.
+    fn update_heightmaps_below(&mut self, x: usize, z: usize, y: usize) {
+        for kind in HeightmapKind::ALL {
+            let height = self.find_height(x, z, y, kind);
+            self.heightmaps.set(kind, x, z, height as u16);
+        }
+    }
+
+    /// Returns the height of the highest block below `y` in a column which is
+    /// counted by the given heightmap.
+    fn find_height(&self, x: usize, z: usize, mut y: usize, kind: HeightmapKind) -> usize {
+        while y > 0 {
+            let sect_y = (y - 1) / 16;
+
+            // Skip sections filled with a single block state at once.
+            if let Some(state) = self.single_block_state(sect_y) {
+                if kind.matches(state) {
+                    return y;
+                }
+
+                y = sect_y * 16;
+                continue;
+            }
+
+            if kind.matches(self.block_state(x, y - 1, z)) {
+                return y;
+            }
+
+            y -= 1;
+        }
+
+        0
+    }
+
+    /// Records a change of the block at the provided offsets so that it is sent
+    /// to clients at the end of the tick.
+    ///
+    /// Changes are sent as a single packet per section. If too many blocks in
+    /// a section have changed, the chunk is resent in full instead.
+    fn track_block_change(&mut self, x: usize, y: usize, z: usize, block: BlockState) {
+        if !LOADED || self.refresh {
+            return;
+        }
+
+        self.cached_init_packets.get_mut().clear();
+
+        let sect = &mut self.sections[y / 16];
 
-        if !block.is_air() {
-            sect.non_air_count = SECTION_BLOCK_COUNT as u16;
+        if sect.section_updates.len() >= MAX_SECTION_UPDATES {
+            self.refresh = true;
+        } else {
+            let compact = (block.to_raw() as i64) << 12 | (x << 8 | z << 4 | (y % 16)) as i64;
+            sect.section_updates.push(VarLong(compact));
+        }
+    }
+
+    /// Records a change of the block at the provided offsets so that the light
+    /// around it is updated.
+    fn track_light_change(
+        &mut self,
+        x: usize,
+        y: usize,
+        z: usize,
+        old: BlockState,
+        new: BlockState,
+    ) {
+        if !LOADED || self.relight || !light::affects_light(old, new) {
+            return;
+        }
+
+        if self.light_updates.len() >= MAX_LIGHT_UPDATES {
+            self.relight = true;
+            self.light_updates = vec![];
         } else {
-            sect.non_air_count = 0;
+            self.light_updates.push((x + z * 16 + y * 16 * 16) as u32);
         }
+    }
+
+    /// Returns the sky light level at the provided offsets in the chunk.
+    ///
+    /// Light is only computed for chunks in an instance with lighting enabled,
+    /// and is updated at the end of the tick.
+    ///
+    /// **Note**: The arguments to this function are offsets from the minimum
+    /// corner of the chunk in _chunk space_ rather than _world space_.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offsets are outside the bounds of the chunk. `x` and `z`
+    /// must be less than 16 while `y` must be less than `section_count() * 16`.
+    #[track_caller]
+    pub fn sky_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        assert!(
+            x < 16 && y < self.section_count() * 16 && z < 16,
+            "chunk block offsets of ({x}, {y}, {z}) are out of bounds"
+        );
 
-        sect.block_states.fill(block);
+        self.light_at(LightKind::Sky, x + z * 16 + y * 16 * 16)
+    }
+
+    /// Returns the block light level at the provided offsets in the chunk.
+    ///
+    /// Light is only computed for chunks in an instance with lighting enabled,
+    /// and is updated at the end of the tick.
+    ///
+    /// **Note**: The arguments to this function are offsets from the minimum
+    /// corner of the chunk in _chunk space_ rather than _world space_.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offsets are outside the bounds of the chunk. `x` and `z`
+    /// must be less than 16 while `y` must be less than `section_count() * 16`.
+    #[track_caller]
+    pub fn block_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        assert!(
+            x < 16 && y < self.section_count() * 16 && z < 16,
+            "chunk block offsets of ({x}, {y}, {z}) are out of bounds"
+        );
+
+        self.light_at(LightKind::Block, x + z * 16 + y * 16 * 16)
+    }
+
+    /// Returns the block state at `idx`, where `idx` is `x + z * 16 + y * 16 *
+    /// 16`.
+    pub(crate) fn block_state_at(&self, idx: usize) -> BlockState {
+        self.sections[idx / SECTION_BLOCK_COUNT]
+            .block_states
+            .get(idx % SECTION_BLOCK_COUNT)
+    }
+
+    /// Returns the block state of every block in a section if they are all the
+    /// same.
+    pub(crate) fn single_block_state(&self, sect_y: usize) -> Option<BlockState> {
+        match self.sections[sect_y].block_states {
+            PalettedContainer::Single(state) => Some(state),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn light_section(&self, kind: LightKind, sect_y: usize) -> &LightArray {
+        let sect = &self.sections[sect_y];
+
+        match kind {
+            LightKind::Sky => &sect.sky_light,
+            LightKind::Block => &sect.block_light,
+        }
+    }
+
+    fn light_section_mut(&mut self, kind: LightKind, sect_y: usize) -> &mut LightArray {
+        let sect = &mut self.sections[sect_y];
+
+        match kind {
+            LightKind::Sky => &mut sect.sky_light,
+            LightKind::Block => &mut sect.block_light,
+        }
+    }
+
+    pub(crate) fn light_at(&self, kind: LightKind, idx: usize) -> u8 {
+        self.light_section(kind, idx / SECTION_BLOCK_COUNT)
+            .get(idx % SECTION_BLOCK_COUNT)
+    }
+
+    pub(crate) fn set_light_at(&mut self, kind: LightKind, idx: usize, level: u8) {
+        if self
+            .light_section_mut(kind, idx / SECTION_BLOCK_COUNT)
+            .set(idx % SECTION_BLOCK_COUNT, level)
+        {
+            self.mark_light_modified();
+        }
+    }
+
+    pub(crate) fn fill_light_section(&mut self, kind: LightKind, sect_y: usize, level: u8) {
+        let light = self.light_section_mut(kind, sect_y);
+
+        if light.uniform() != Some(level) {
+            *light = LightArray::Uniform(level);
+            self.mark_light_modified();
+        }
+    }
+
+    /// Sets the light level of every block in the chunk to zero.
+    pub(crate) fn clear_light(&mut self, kind: LightKind) {
+        for sect_y in 0..self.section_count() {
+            self.fill_light_section(kind, sect_y, 0);
+        }
+    }
+
+    fn mark_light_modified(&mut self) {
+        self.cached_init_packets.get_mut().clear();
+        self.light_modified = true;
+    }
+
+    /// Takes the pending light changes of this chunk. Returns `true` if the
+    /// light must be computed from scratch, and the indices of changed blocks
+    /// otherwise.
+    pub(crate) fn take_light_updates(&mut self) -> (bool, Vec<u32>) {
+        let relight = std::mem::take(&mut self.relight);
+        (relight, std::mem::take(&mut self.light_updates))
+    }
+
+    /// Marks the light of this chunk to be computed from scratch and the chunk
+    /// to be resent to clients.
+    pub(crate) fn reset_light(&mut self) {
+        self.cached_init_packets.get_mut().clear();
+        self.refresh = true;
+        self.relight = true;
+        self.light_updates.clear();
+    }
+
+    /// Removes the block entity at `idx` if it does not belong to `state`.
+    /// Clients remove their copy of the block entity when the block changes,
+    /// so no packet needs to be sent.
+    fn remove_incompatible_block_entity(&mut self, idx: u32, state: BlockState) {
+        if let Entry::Occupied(entry) = self.block_entities.entry(idx) {
+            if Some(entry.get().kind) != state.block_entity_kind() {
+                entry.remove();
+            }
+        }
     }
 
     /// Gets a reference to the block entity at the provided offsets in the
@@ -731,15 +1313,17 @@ impl<const LOADED: bool> Chunk<LOADED> {
                     _ => {}
                 }
 
-                if LOADED && !self.refresh {
-                    let compact =
-                        (state.to_raw() as i64) << 12 | (x << 8 | z << 4 | (y % 16)) as i64;
-                    sect.section_updates.push(VarLong(compact));
-                }
+                sect.palettes_modified = true;
             }
             old_state
         };
 
+        if state != old_state {
+            self.track_block_change(x, y, z, state);
+            self.track_light_change(x, y, z, old_state, state);
+            self.update_heightmaps(x, y, z, state);
+        }
+
         let idx = (x + z * 16 + y * 16 * 16) as _;
         let old_block_entity = match nbt.and_then(|nbt| {
             state
@@ -819,6 +1403,8 @@ impl<const LOADED: bool> Chunk<LOADED> {
             state,
             entry,
             modified: &mut self.modified_block_entities,
+            cached_init_packets: self.cached_init_packets.get_mut(),
+            track_changes: LOADED && !self.refresh,
         }
     }
 
@@ -858,13 +1444,15 @@ impl<const LOADED: bool> Chunk<LOADED> {
             "chunk biome offsets of ({x}, {y}, {z}) are out of bounds"
         );
 
-        let old_biome = self.sections[y / 4]
-            .biomes
-            .set(x + z * 4 + y % 4 * 4 * 4, biome);
+        let sect = &mut self.sections[y / 4];
+        let old_biome = sect.biomes.set(x + z * 4 + y % 4 * 4 * 4, biome);
 
-        if LOADED && biome != old_biome {
-            self.cached_init_packets.get_mut().clear();
-            self.refresh = true;
+        if biome != old_biome {
+            sect.palettes_modified = true;
+
+            if LOADED {
+                self.mark_biomes_modified();
+            }
         }
 
         old_biome
@@ -891,34 +1479,79 @@ impl<const LOADED: bool> Chunk<LOADED> {
             )
         };
 
+        if matches!(sect.biomes, PalettedContainer::Single(b) if b == biome) {
+            return;
+        }
+
         sect.biomes.fill(biome);
 
-        // TODO: this is set unconditionally, but it doesn't have to be.
+        if LOADED {
+            self.mark_biomes_modified();
+        }
+    }
+
+    fn mark_biomes_modified(&mut self) {
         self.cached_init_packets.get_mut().clear();
-        self.refresh = true;
+        self.biomes_modified = true;
     }
 
     /// Optimizes this chunk to use the minimum amount of memory possible. It
     /// has no observable effect on the contents of the chunk.
     ///
-    /// This is a potentially expensive operation. The function is most
-    /// effective when a large number of blocks and biomes have changed states
-    /// via [`Self::set_block_state`] and [`Self::set_biome`].
+    /// The palettes of the sections whose blocks or biomes changed since the
+    /// last call are rebuilt from the states which are still in use, which
+    /// also shrinks the packets sent for the sections. Sections which didn't
+    /// change are skipped, so this is cheap enough to call regularly on chunks
+    /// which are being edited.
     pub fn optimize(&mut self) {
         self.sections.shrink_to_fit();
         self.cached_init_packets.get_mut().shrink_to_fit();
 
         for sect in &mut self.sections {
             sect.section_updates.shrink_to_fit();
-            sect.block_states.optimize();
-            sect.biomes.optimize();
+
+            if sect.palettes_modified {
+                sect.block_states.optimize();
+                sect.biomes.optimize();
+                sect.palettes_modified = false;
+            }
         }
     }
+
+    /// Returns the approximate number of bytes of memory used by this chunk,
+    /// including the chunk itself but not the NBT data of block entities.
+    ///
+    /// Sections with many distinct block states use much more memory than
+    /// sections with few of them. Calling [`Self::optimize`] reclaims the
+    /// memory of sections which had many distinct states in the past.
+    pub fn memory_usage(&self) -> usize {
+        let sections: usize = self
+            .sections
+            .iter()
+            .map(|sect| {
+                sect.block_states.heap_size()
+                    + sect.biomes.heap_size()
+                    + sect.section_updates.capacity() * mem::size_of::<VarLong>()
+                    + sect.sky_light.heap_size()
+                    + sect.block_light.heap_size()
+            })
+            .sum();
+
+        mem::size_of::<Self>()
+            + self.sections.capacity() * mem::size_of::<Section>()
+            + sections
+            + self.cached_init_packets.lock().capacity()
+            + self.block_entities.len() * mem::size_of::<(u32, BlockEntity)>()
+            + self.modified_block_entities.len() * mem::size_of::<u32>()
+            + self.light_updates.capacity() * mem::size_of::<u32>()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use valence_block::{BlockEntityKind, BlockState};
+    use rand::Rng;
+    use valence_block::{BlockEntityKind, BlockState, PropName, PropValue};
+    use valence_nbt::compound;
 
     use super::*;
 
@@ -960,7 +1593,24 @@ mod tests {
         check(&chunk, 3);
 
         chunk.fill_block_states(0, BlockState::AIR);
-        check(&chunk, 6);
+        assert!(chunk.refresh, "filling should resend the chunk");
+    }
+
+    #[test]
+    fn too_many_block_changes_resend_chunk() {
+        let mut chunk = Chunk::new(5).into_loaded();
+        chunk.refresh = false;
+
+        for i in 0..MAX_SECTION_UPDATES {
+            chunk.set_block_state(i % 16, 16 + i / 256, i / 16 % 16, BlockState::STONE);
+        }
+
+        // Changes in other sections are tracked separately.
+        chunk.set_block(0, 0, 0, BlockState::STONE);
+        check(&chunk, MAX_SECTION_UPDATES + 1);
+
+        chunk.set_block(15, 31, 15, BlockState::STONE);
+        assert!(chunk.refresh);
     }
 
     #[test]
@@ -980,4 +1630,298 @@ mod tests {
         chunk.set_block(0, 0, 0, BlockState::STONE);
         assert!(chunk.block_entity(0, 0, 0).is_none());
     }
+
+    #[test]
+    fn incompatible_block_entities_removed() {
+        let mut chunk = Chunk::new(5).into_loaded();
+        chunk.refresh = false;
+
+        let chest = BlockEntity::new(BlockEntityKind::Chest, compound! { "Lock" => "key" });
+
+        chunk.set_block(0, 0, 0, BlockState::CHEST);
+        chunk.set_block_entity(0, 0, 0, chest.clone());
+
+        // Changing the block's properties keeps the block entity.
+        chunk.set_block_state(
+            0,
+            0,
+            0,
+            BlockState::CHEST.set(PropName::Facing, PropValue::East),
+        );
+        assert_eq!(chunk.block_entity(0, 0, 0), Some(&chest));
+
+        chunk.set_block_state(0, 0, 0, BlockState::STONE);
+        assert!(chunk.block_entity(0, 0, 0).is_none());
+
+        chunk.set_block(0, 16, 0, BlockState::CHEST);
+        chunk.set_block(1, 16, 0, BlockState::FURNACE);
+        chunk.set_block(0, 32, 0, BlockState::FURNACE);
+
+        chunk.fill_block_states(1, BlockState::CHEST);
+        assert!(chunk.block_entity(0, 16, 0).is_some());
+        assert!(chunk.block_entity(1, 16, 0).is_none());
+        assert!(chunk.block_entity(0, 32, 0).is_some());
+
+        let mut chunk = chunk.into_unloaded();
+
+        chunk.resize(2);
+        assert!(chunk.block_entity(0, 16, 0).is_some());
+        chunk.resize(5);
+        assert!(chunk.block_entity(0, 32, 0).is_none());
+    }
+
+    #[test]
+    fn block_mut_nbt_changes() {
+        let mut chunk = Chunk::new(5);
+        chunk.set_block(0, 0, 0, BlockState::CHEST);
+        chunk
+            .block_mut(0, 0, 0)
+            .nbt_mut()
+            .unwrap()
+            .insert("Lock", "key");
+
+        // Unloaded chunks do not track changes.
+        assert!(chunk.modified_block_entities.is_empty());
+
+        let mut chunk = chunk.into_loaded();
+        chunk.refresh = false;
+        *chunk.cached_init_packets.get_mut() = vec![1, 2, 3];
+
+        chunk
+            .block_mut(0, 0, 0)
+            .nbt_mut()
+            .unwrap()
+            .insert("Lock", "other");
+
+        assert!(chunk.modified_block_entities.contains(&0));
+        assert!(chunk.cached_init_packets.get_mut().is_empty());
+    }
+
+    #[test]
+    fn fill_block_states_in_box() {
+        let mut chunk = Chunk::new(5).into_loaded();
+        chunk.refresh = false;
+
+        chunk.set_block(3, 20, 3, BlockState::CHEST);
+        chunk.set_block(3, 40, 3, BlockState::CHEST);
+
+        chunk.fill_block_states_in([1, 2, 3], [10, 20, 12], BlockState::STONE);
+        assert!(chunk.refresh);
+
+        for (y, sect) in chunk.sections.iter().enumerate() {
+            let expected = match y {
+                0 => 10 * 14 * 10,
+                1 => 10 * 5 * 10,
+                2 => 1,
+                _ => 0,
+            };
+
+            assert_eq!(sect.non_air_count, expected, "section {y}");
+        }
+
+        assert_eq!(chunk.block_state(1, 2, 3), BlockState::STONE);
+        assert_eq!(chunk.block_state(10, 20, 12), BlockState::STONE);
+        assert_eq!(chunk.block_state(0, 2, 3), BlockState::AIR);
+        assert_eq!(chunk.block_state(10, 21, 12), BlockState::AIR);
+
+        assert!(chunk.block_entity(3, 20, 3).is_none());
+        assert!(chunk.block_entity(3, 40, 3).is_some());
+
+        // Filling the rest of the section makes it uniform again.
+        chunk.fill_block_states_in([0, 16, 0], [15, 31, 15], BlockState::AIR);
+        chunk.fill_block_states_in([0, 0, 0], [15, 1, 15], BlockState::STONE);
+        chunk.fill_block_states_in([0, 2, 0], [15, 15, 15], BlockState::STONE);
+
+        assert!(matches!(
+            chunk.sections[0].block_states,
+            PalettedContainer::Single(BlockState::STONE)
+        ));
+        assert!(matches!(
+            chunk.sections[1].block_states,
+            PalettedContainer::Single(BlockState::AIR)
+        ));
+        assert_eq!(chunk.sections[0].non_air_count, SECTION_BLOCK_COUNT as u16);
+        assert_eq!(chunk.sections[1].non_air_count, 0);
+    }
+
+    #[test]
+    fn fill_block_states_in_unchanged() {
+        let mut chunk = Chunk::new(5).into_loaded();
+        chunk.refresh = false;
+
+        chunk.fill_block_states_in([0, 0, 0], [15, 40, 15], BlockState::AIR);
+        assert!(!chunk.refresh, "the chunk should not be resent");
+    }
+
+    #[test]
+    fn biome_changes() {
+        let mut chunk = Chunk::new(5).into_loaded();
+        chunk.refresh = false;
+
+        assert_eq!(
+            chunk.set_biome(1, 2, 3, BiomeId::default()),
+            BiomeId::default()
+        );
+        assert!(
+            !chunk.biomes_modified,
+            "unchanged biomes should not be resent"
+        );
+
+        *chunk.cached_init_packets.get_mut() = vec![1, 2, 3];
+        chunk.set_biome(1, 2, 3, BiomeId(1));
+
+        assert_eq!(chunk.biome(1, 2, 3), BiomeId(1));
+        assert!(chunk.biomes_modified);
+        assert!(chunk.cached_init_packets.get_mut().is_empty());
+        assert!(!chunk.refresh, "biome changes should not resend the chunk");
+
+        chunk.update_post_client();
+        chunk.fill_biomes(1, BiomeId::default());
+        assert!(!chunk.biomes_modified);
+
+        chunk.fill_biomes(0, BiomeId(2));
+        assert!(chunk.biomes_modified);
+        assert_eq!(chunk.biome(1, 2, 3), BiomeId(2));
+    }
+
+    #[test]
+    fn many_biomes_in_section() {
+        let mut chunk = Chunk::new(2);
+
+        // More distinct biomes than fit in the indirect palette.
+        for i in 0..SECTION_BIOME_COUNT {
+            chunk.set_biome(i % 4, 4 + i / 16, i / 4 % 4, BiomeId(i as u16));
+        }
+
+        for i in 0..SECTION_BIOME_COUNT {
+            assert_eq!(chunk.biome(i % 4, 4 + i / 16, i / 4 % 4), BiomeId(i as u16));
+        }
+
+        assert!(matches!(
+            chunk.sections[1].biomes,
+            PalettedContainer::Direct(_)
+        ));
+
+        let mut buf = vec![];
+        chunk.sections[1]
+            .biomes
+            .encode_mc_format(&mut buf, |b| b.0.into(), 0, 3, 6)
+            .unwrap();
+
+        // Bits per entry, length of the data array, then 64 entries of 6 bits
+        // packed 10 to a long.
+        assert_eq!(buf[0], 6);
+        assert_eq!(buf[1], 7);
+        assert_eq!(buf.len(), 2 + 7 * 8);
+    }
+
+    #[test]
+    fn heightmap_updates() {
+        use HeightmapKind::*;
+
+        let mut chunk = Chunk::new(5);
+
+        assert_eq!(chunk.height_at(3, 4, WorldSurface), 0);
+
+        chunk.set_block_state(3, 10, 4, BlockState::STONE);
+        chunk.set_block_state(3, 20, 4, BlockState::STONE);
+        chunk.set_block_state(3, 21, 4, BlockState::DANDELION);
+
+        assert_eq!(chunk.height_at(3, 4, WorldSurface), 22);
+        assert_eq!(chunk.height_at(3, 4, MotionBlocking), 21);
+        assert_eq!(chunk.height_at(4, 4, WorldSurface), 0);
+
+        // Replacing the top block with air drops the height to the next block
+        // below.
+        chunk.set_block_state(3, 21, 4, BlockState::AIR);
+        chunk.set_block_state(3, 20, 4, BlockState::AIR);
+
+        assert_eq!(chunk.height_at(3, 4, WorldSurface), 11);
+        assert_eq!(chunk.height_at(3, 4, MotionBlocking), 11);
+
+        // Changing blocks below the top block doesn't change the height.
+        chunk.set_block_state(3, 30, 4, BlockState::WATER);
+        chunk.set_block_state(3, 10, 4, BlockState::AIR);
+
+        assert_eq!(chunk.height_at(3, 4, WorldSurface), 31);
+        assert_eq!(chunk.height_at(3, 4, MotionBlocking), 31);
+
+        chunk.set_block(3, 30, 4, BlockState::AIR);
+
+        assert_eq!(chunk.height_at(3, 4, WorldSurface), 0);
+        assert_eq!(chunk.height_at(3, 4, MotionBlocking), 0);
+    }
+
+    #[test]
+    fn heightmap_fill_and_resize() {
+        use HeightmapKind::*;
+
+        let mut chunk = Chunk::new(5);
+
+        chunk.fill_block_states(0, BlockState::STONE);
+        chunk.fill_block_states_in([0, 16, 0], [7, 40, 15], BlockState::OAK_LEAVES);
+
+        assert_eq!(chunk.height_at(0, 0, WorldSurface), 41);
+        assert_eq!(chunk.height_at(8, 0, WorldSurface), 16);
+
+        // Clearing the top of the leaves.
+        chunk.fill_block_states_in([0, 30, 0], [15, 79, 15], BlockState::AIR);
+
+        assert_eq!(chunk.height_at(0, 15, MotionBlocking), 30);
+        assert_eq!(chunk.height_at(15, 15, MotionBlocking), 16);
+
+        // Truncating the chunk below the leaves.
+        chunk.resize(1);
+
+        assert_eq!(chunk.height_at(0, 15, MotionBlocking), 16);
+
+        // Heights are kept when the chunk is inserted into an instance.
+        let chunk = chunk.into_loaded();
+
+        assert_eq!(chunk.height_at(0, 15, WorldSurface), 16);
+    }
+
+    #[test]
+    fn optimize_keeps_contents() {
+        let mut rng = rand::thread_rng();
+
+        for distinct_count in [1, 2, 16, 17, 300] {
+            let mut chunk = Chunk::new(1);
+
+            // Give every block a random state first, so that the section is
+            // stored without a palette.
+            for idx in 0..SECTION_BLOCK_COUNT {
+                let state = BlockState::from_raw(rng.gen_range(1..1000)).unwrap();
+                chunk.set_block_state(idx % 16, idx / 256, idx / 16 % 16, state);
+            }
+
+            let states: Vec<_> = (0..distinct_count)
+                .map(|i| BlockState::from_raw(i * 3).unwrap())
+                .collect();
+
+            for idx in 0..SECTION_BLOCK_COUNT {
+                let state = states[rng.gen_range(0..states.len())];
+                chunk.set_block_state(idx % 16, idx / 256, idx / 16 % 16, state);
+            }
+
+            let before: Vec<_> = (0..SECTION_BLOCK_COUNT)
+                .map(|idx| chunk.block_state_at(idx))
+                .collect();
+            let usage_before = chunk.memory_usage();
+
+            chunk.optimize();
+
+            let after: Vec<_> = (0..SECTION_BLOCK_COUNT)
+                .map(|idx| chunk.block_state_at(idx))
+                .collect();
+
+            assert_eq!(before, after, "{distinct_count} distinct states");
+
+            if distinct_count <= 16 {
+                assert!(chunk.memory_usage() < usage_before);
+            } else {
+                assert!(chunk.memory_usage() <= usage_before);
+            }
+        }
+    }
 }
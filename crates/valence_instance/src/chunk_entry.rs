@@ -104,6 +104,8 @@ impl<'a> VacantChunkEntry<'a> {
             incoming: vec![],
             outgoing: vec![],
             packet_buf: vec![],
+            movement_ranges: vec![],
+            non_essential_buf: vec![],
         });
 
         debug_assert!(cell.chunk.is_none());
@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+
+use glam::IVec3;
+use valence_biome::BiomeId;
+use valence_block::{BlockState, Mirror, Rotation};
+use valence_core::block_pos::BlockPos;
+use valence_nbt::Compound;
+
+use crate::{Block, BlockChangeCause, Instance};
+
+/// A copy of the block states, biomes and block entities in a box of an
+/// instance, which can be transformed and pasted elsewhere.
+///
+/// Positions in the clipboard are relative to its origin, which is the
+/// minimum corner of the box it was copied from. Pasting places the origin at
+/// the given position, and rotating or mirroring the clipboard transforms it
+/// around the origin.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Clipboard {
+    /// The position of the minimum corner of the box relative to the origin.
+    offset: IVec3,
+    size: IVec3,
+    /// The contents of the box ordered by Y, then Z, then X. Positions which
+    /// weren't in a loaded chunk are `None`.
+    blocks: Vec<Option<ClipboardBlock>>,
+    /// The NBT of the block entities, keyed by the index of their block.
+    block_entities: BTreeMap<usize, Compound>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct ClipboardBlock {
+    state: BlockState,
+    biome: BiomeId,
+}
+
+impl Clipboard {
+    /// Copies every block in the box between the block positions `min` and
+    /// `max` (inclusive) of an instance. The corners may be given in any
+    /// order.
+    ///
+    /// Positions which are not in a loaded chunk or are outside the height of
+    /// the instance are left out of the clipboard and are skipped when it is
+    /// pasted.
+    pub fn copy(instance: &Instance, min: impl Into<BlockPos>, max: impl Into<BlockPos>) -> Self {
+        let (a, b) = (min.into(), max.into());
+        let min = BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let max = BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+        let mut clipboard = Self {
+            offset: IVec3::ZERO,
+            size: (max - min) + IVec3::ONE,
+            blocks: vec![],
+            block_entities: BTreeMap::new(),
+        };
+
+        let mut blocks = Vec::with_capacity(clipboard.volume());
+
+        for idx in 0..clipboard.volume() {
+            let chunk = instance
+                .chunk_offsets(min + clipboard.pos(idx))
+                .and_then(|(chunk_pos, x, y, z)| Some((instance.chunk(chunk_pos)?, x, y, z)));
+
+            let Some((chunk, x, y, z)) = chunk else {
+                blocks.push(None);
+                continue;
+            };
+
+            let block = chunk.block(x, y, z);
+
+            if let Some(nbt) = block.nbt() {
+                clipboard.block_entities.insert(idx, nbt.clone());
+            }
+
+            blocks.push(Some(ClipboardBlock {
+                state: block.state(),
+                biome: chunk.biome(x / 4, y / 4, z / 4),
+            }));
+        }
+
+        clipboard.blocks = blocks;
+        clipboard
+    }
+
+    /// The position of the minimum corner of the clipboard relative to its
+    /// origin.
+    pub fn offset(&self) -> IVec3 {
+        self.offset
+    }
+
+    /// The size of the clipboard along each axis.
+    pub fn size(&self) -> IVec3 {
+        self.size
+    }
+
+    /// Returns the block state at a position relative to the origin of the
+    /// clipboard, or `None` if the position is outside of the clipboard or
+    /// wasn't in a loaded chunk.
+    pub fn block_state(&self, pos: impl Into<IVec3>) -> Option<BlockState> {
+        Some(self.blocks[self.index(pos.into())?]?.state)
+    }
+
+    /// Returns the biome at a position relative to the origin of the
+    /// clipboard.
+    pub fn biome(&self, pos: impl Into<IVec3>) -> Option<BiomeId> {
+        Some(self.blocks[self.index(pos.into())?]?.biome)
+    }
+
+    /// Returns the NBT of the block entity at a position relative to the
+    /// origin of the clipboard.
+    pub fn block_entity(&self, pos: impl Into<IVec3>) -> Option<&Compound> {
+        self.block_entities.get(&self.index(pos.into())?)
+    }
+
+    fn volume(&self) -> usize {
+        self.size.x as usize * self.size.y as usize * self.size.z as usize
+    }
+
+    /// Converts an index into the blocks to a position relative to the
+    /// minimum corner.
+    fn pos(&self, idx: usize) -> IVec3 {
+        let x = idx % self.size.x as usize;
+        let z = idx / self.size.x as usize % self.size.z as usize;
+        let y = idx / (self.size.x as usize * self.size.z as usize);
+
+        IVec3::new(x as i32, y as i32, z as i32)
+    }
+
+    /// Converts a position relative to the origin to an index into the
+    /// blocks.
+    fn index(&self, pos: IVec3) -> Option<usize> {
+        let pos = pos - self.offset;
+
+        if pos.cmplt(IVec3::ZERO).any() || pos.cmpge(self.size).any() {
+            return None;
+        }
+
+        Some(
+            pos.x as usize
+                + pos.z as usize * self.size.x as usize
+                + pos.y as usize * self.size.x as usize * self.size.z as usize,
+        )
+    }
+
+    /// Rotates the clipboard around the Y axis through its origin. The
+    /// properties of the block states are changed accordingly, like the
+    /// direction stairs are facing.
+    pub fn rotate(&mut self, rotation: Rotation) {
+        self.transform(
+            |pos| rotation.rotate_pos(pos),
+            |state| state.rotate(rotation),
+        );
+    }
+
+    /// Mirrors the clipboard along a vertical plane through its origin. The
+    /// properties of the block states are changed accordingly.
+    pub fn mirror(&mut self, mirror: Mirror) {
+        self.transform(|pos| mirror.mirror_pos(pos), |state| state.mirror(mirror));
+    }
+
+    fn transform(
+        &mut self,
+        transform_pos: impl Fn(IVec3) -> IVec3,
+        transform_state: impl Fn(BlockState) -> BlockState,
+    ) {
+        let a = transform_pos(self.offset);
+        let b = transform_pos(self.offset + self.size - IVec3::ONE);
+
+        let mut transformed = Self {
+            offset: a.min(b),
+            size: (a - b).abs() + IVec3::ONE,
+            blocks: vec![None; self.blocks.len()],
+            block_entities: BTreeMap::new(),
+        };
+
+        for (idx, block) in self.blocks.iter().enumerate() {
+            let pos = transform_pos(self.offset + self.pos(idx));
+            let new_idx = transformed.index(pos).unwrap();
+
+            transformed.blocks[new_idx] = block.map(|block| ClipboardBlock {
+                state: transform_state(block.state),
+                biome: block.biome,
+            });
+
+            if let Some(nbt) = self.block_entities.remove(&idx) {
+                transformed.block_entities.insert(new_idx, nbt);
+            }
+        }
+
+        *self = transformed;
+    }
+
+    /// Writes the contents of the clipboard to an instance with its origin at
+    /// `origin`. Air is only written if `paste_air` is `true`, so that the
+    /// blocks around the pasted ones can be kept.
+    ///
+    /// The previous contents of the box which was written to are returned as
+    /// another clipboard with the same origin. Pasting it at `origin` with
+    /// `paste_air` undoes the paste.
+    ///
+    /// Biomes are stored in 4x4x4 cells rather than for each block, so the
+    /// biomes of clipboards pasted at positions which aren't aligned to the
+    /// cells are approximated. Changes to biomes are only seen by clients
+    /// when the chunks are sent to them again.
+    ///
+    /// A [`BlockChangeEvent`](crate::BlockChangeEvent) is sent for every block
+    /// whose state was changed.
+    pub fn paste(
+        &self,
+        instance: &mut Instance,
+        origin: impl Into<BlockPos>,
+        paste_air: bool,
+    ) -> Clipboard {
+        let min = origin.into() + self.offset;
+        let max = min + (self.size - IVec3::ONE);
+
+        let mut previous = Self::copy(instance, min, max);
+        previous.offset = self.offset;
+
+        for (idx, block) in self.blocks.iter().enumerate() {
+            let Some(block) = block else {
+                continue;
+            };
+
+            if !paste_air && block.state.is_air() {
+                continue;
+            }
+
+            let pos = min + self.pos(idx);
+
+            let Some((chunk_pos, x, y, z)) = instance.chunk_offsets(pos) else {
+                continue;
+            };
+
+            let Some(chunk) = instance.chunk_mut(chunk_pos) else {
+                continue;
+            };
+
+            let new_block = match self.block_entities.get(&idx) {
+                Some(nbt) => Block::with_nbt(block.state, nbt.clone()),
+                None => Block::new(block.state),
+            };
+
+            let old = chunk.set_block(x, y, z, new_block);
+            chunk.set_biome(x / 4, y / 4, z / 4, block.biome);
+
+            instance.record_block_change(pos, old.state(), block.state, BlockChangeCause::Api);
+        }
+
+        previous
+    }
+}
@@ -0,0 +1,211 @@
+use glam::{BVec3, DVec3};
+use valence_block::BlockState;
+use valence_core::aabb::Aabb;
+use valence_core::block_pos::BlockPos;
+
+use crate::Instance;
+
+/// Boxes which are closer than this are considered to be touching rather than
+/// overlapping, which keeps entities resting on blocks from colliding with
+/// them due to rounding errors.
+const EPSILON: f64 = 1.0e-7;
+
+/// The farthest [`Instance::sweep`] moves a box along each axis.
+const MAX_SWEEP_DISTANCE: f64 = 64.0;
+
+/// The largest number of blocks along the X and Z axes whose collision shapes
+/// are checked at once. Together with the height of the instance, this bounds
+/// the work done for arbitrarily large boxes.
+const MAX_REGION_WIDTH: i32 = 128;
+
+/// Determines how blocks in unloaded chunks collide. See
+/// [`Instance::set_unloaded_chunk_collision`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum UnloadedChunkCollision {
+    /// Blocks in unloaded chunks are full cubes, so that entities don't fall
+    /// into chunks before they are loaded.
+    #[default]
+    Solid,
+    /// Blocks in unloaded chunks are air.
+    PassThrough,
+}
+
+impl Instance {
+    /// Returns whether `aabb` overlaps the collision shape of any block in the
+    /// instance. Boxes which only touch a block, like the bounding box of an
+    /// entity standing on the ground, don't collide with it.
+    ///
+    /// Blocks outside the height of the instance never collide, and blocks in
+    /// unloaded chunks collide according to
+    /// [`Instance::unloaded_chunk_collision`]. Only the first 128 blocks of
+    /// `aabb` along the X and Z axes are checked.
+    pub fn collides(&self, aabb: Aabb) -> bool {
+        self.block_boxes(aabb)
+            .into_iter()
+            .any(|bb| (0..3).all(|axis| overlaps(aabb, bb, axis)))
+    }
+
+    /// Moves `aabb` by `delta` until it hits the collision shapes of the
+    /// blocks in the instance, and returns how far it could move along each
+    /// axis together with the axes the movement was stopped along.
+    ///
+    /// Like in vanilla, the box is moved along the Y axis first, then along
+    /// the horizontal axis it moves further along, and then along the other
+    /// one. This lets entities slide along walls. Blocks which already overlap
+    /// the box don't stop it.
+    ///
+    /// The movement along each axis is limited to 64 blocks, and non-finite
+    /// components of `delta` are treated as zero. Such movements are reported
+    /// as stopped along the axes they were cut short on. Like in
+    /// [`Self::collides`], only the first 128 blocks of the swept region along
+    /// the X and Z axes are checked.
+    pub fn sweep(&self, aabb: Aabb, delta: impl Into<DVec3>) -> (DVec3, BVec3) {
+        let requested = delta.into();
+        let limit = |dist: f64| {
+            if dist.is_finite() {
+                dist.clamp(-MAX_SWEEP_DISTANCE, MAX_SWEEP_DISTANCE)
+            } else {
+                0.0
+            }
+        };
+        let delta = DVec3::new(limit(requested.x), limit(requested.y), limit(requested.z));
+
+        let region = Aabb {
+            min: aabb.min + delta.min(DVec3::ZERO),
+            max: aabb.max + delta.max(DVec3::ZERO),
+        };
+
+        let boxes = self.block_boxes(region);
+
+        let order = if delta.x.abs() < delta.z.abs() {
+            [1, 2, 0]
+        } else {
+            [1, 0, 2]
+        };
+
+        let mut aabb = aabb;
+        let mut allowed = DVec3::ZERO;
+
+        for axis in order {
+            let dist = boxes
+                .iter()
+                .fold(delta[axis], |dist, &bb| clip(aabb, bb, axis, dist));
+
+            let mut offset = DVec3::ZERO;
+            offset[axis] = dist;
+
+            aabb = aabb + offset;
+            allowed[axis] = dist;
+        }
+
+        (allowed, allowed.cmpne(requested))
+    }
+
+    /// Returns the collision shapes of the blocks which could touch `region`,
+    /// in world space.
+    ///
+    /// The region is cut down to the height of the instance and to
+    /// [`MAX_REGION_WIDTH`] blocks along the X and Z axes.
+    fn block_boxes(&self, region: Aabb) -> Vec<Aabb> {
+        let mut boxes = vec![];
+
+        if !region.min.is_finite() || !region.max.is_finite() {
+            return boxes;
+        }
+
+        // Converting to integers saturates, so this can't overflow.
+        let mut min = region.min.floor().as_ivec3();
+        let mut max = region.max.floor().as_ivec3();
+
+        // Fences and walls extend into the block above them, so the blocks
+        // below the region are included too.
+        min.y = min.y.max(self.info.min_y + 1) - 1;
+        max.y = max
+            .y
+            .min(self.info.min_y + self.info.section_count as i32 * 16 - 1);
+
+        max.x = max.x.min(min.x.saturating_add(MAX_REGION_WIDTH - 1));
+        max.z = max.z.min(min.z.saturating_add(MAX_REGION_WIDTH - 1));
+
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                for x in min.x..=max.x {
+                    let pos = BlockPos::new(x, y, z);
+                    let offset = DVec3::new(x as f64, y as f64, z as f64);
+
+                    let Some((chunk_pos, x, y, z)) = self.chunk_offsets(pos) else {
+                        continue;
+                    };
+
+                    let state = match self.chunk(chunk_pos) {
+                        Some(chunk) => chunk.block_state(x, y, z),
+                        None => match self.info.unloaded_chunk_collision {
+                            UnloadedChunkCollision::Solid => BlockState::STONE,
+                            UnloadedChunkCollision::PassThrough => continue,
+                        },
+                    };
+
+                    boxes.extend(
+                        state
+                            .collision_shapes()
+                            .map(|shape| shape + offset)
+                            .filter(|bb| bb.intersects(region)),
+                    );
+                }
+            }
+        }
+
+        boxes
+    }
+}
+
+/// Returns whether two boxes overlap along an axis by more than [`EPSILON`].
+fn overlaps(a: Aabb, b: Aabb, axis: usize) -> bool {
+    a.max[axis] > b.min[axis] + EPSILON && a.min[axis] < b.max[axis] - EPSILON
+}
+
+/// Returns how far `moving` can move along `axis` towards `dist` before it
+/// hits `fixed`.
+fn clip(moving: Aabb, fixed: Aabb, axis: usize, dist: f64) -> f64 {
+    let others_overlap = (0..3)
+        .filter(|&other| other != axis)
+        .all(|other| overlaps(moving, fixed, other));
+
+    if !others_overlap {
+        return dist;
+    }
+
+    if dist > 0.0 && moving.max[axis] <= fixed.min[axis] + EPSILON {
+        dist.min((fixed.min[axis] - moving.max[axis]).max(0.0))
+    } else if dist < 0.0 && moving.min[axis] >= fixed.max[axis] - EPSILON {
+        dist.max((fixed.max[axis] - moving.min[axis]).min(0.0))
+    } else {
+        dist
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_boxes() {
+        let fixed = Aabb::new([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+        let moving = Aabb::new([-2.0, 0.0, 0.0], [-1.0, 1.0, 1.0]);
+
+        assert_eq!(clip(moving, fixed, 0, 3.0), 1.0);
+        assert_eq!(clip(moving, fixed, 0, 0.5), 0.5);
+        assert_eq!(clip(moving, fixed, 0, -3.0), -3.0);
+
+        // Boxes which only touch along another axis don't stop each other.
+        let moving = Aabb::new([-2.0, 1.0, 0.0], [-1.0, 2.0, 1.0]);
+
+        assert_eq!(clip(moving, fixed, 0, 3.0), 3.0);
+
+        // Resting on top of the box.
+        let moving = Aabb::new([0.0, 1.0, 0.0], [1.0, 2.0, 1.0]);
+
+        assert_eq!(clip(moving, fixed, 1, -1.0), 0.0);
+        assert_eq!(clip(moving, fixed, 1, 1.0), 1.0);
+    }
+}
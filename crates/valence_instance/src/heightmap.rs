@@ -0,0 +1,126 @@
+//! Heightmaps, which track the highest blocks of a kind in each column of a
+//! chunk.
+//!
+//! Heights are stored as offsets from the bottom of the chunk to the block
+//! above the highest matching block, so a column without matching blocks has
+//! a height of zero. This is the same format vanilla uses.
+
+use valence_block::BlockState;
+use valence_nbt::{compound, Compound};
+
+use crate::bit_width;
+use crate::raycast::fluid_kind;
+
+/// The kinds of heightmaps maintained for each chunk.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum HeightmapKind {
+    /// The highest blocks which have a collision shape or contain a fluid.
+    /// Clients use this heightmap to decide where rain and snow fall.
+    MotionBlocking,
+    /// The highest blocks which are not air.
+    WorldSurface,
+}
+
+impl HeightmapKind {
+    /// Every kind of heightmap.
+    pub const ALL: [Self; 2] = [Self::MotionBlocking, Self::WorldSurface];
+
+    /// Returns the name of the heightmap in chunk data packets and Anvil
+    /// chunks.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::MotionBlocking => "MOTION_BLOCKING",
+            Self::WorldSurface => "WORLD_SURFACE",
+        }
+    }
+
+    /// Returns whether blocks with the given state are counted by this kind of
+    /// heightmap.
+    pub fn matches(self, state: BlockState) -> bool {
+        match self {
+            Self::MotionBlocking => {
+                state.collision_shapes().len() > 0 || fluid_kind(state).is_some()
+            }
+            Self::WorldSurface => !state.is_air(),
+        }
+    }
+}
+
+/// The heightmaps of a chunk, indexed by [`HeightmapKind`] and then by `x + z
+/// * 16`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Heightmaps([[u16; 256]; HeightmapKind::ALL.len()]);
+
+impl Default for Heightmaps {
+    fn default() -> Self {
+        Self([[0; 256]; HeightmapKind::ALL.len()])
+    }
+}
+
+impl Heightmaps {
+    pub(crate) fn get(&self, kind: HeightmapKind, x: usize, z: usize) -> u16 {
+        self.0[kind as usize][x + z * 16]
+    }
+
+    pub(crate) fn set(&mut self, kind: HeightmapKind, x: usize, z: usize, height: u16) {
+        self.0[kind as usize][x + z * 16] = height;
+    }
+
+    /// Packs a heightmap of a chunk with `section_count` sections into longs.
+    /// Each height takes just enough bits to store the height of the chunk,
+    /// and heights are not split across longs.
+    pub(crate) fn to_packed(&self, kind: HeightmapKind, section_count: usize) -> Vec<i64> {
+        let bits = bit_width(section_count * 16);
+        let per_long = 64 / bits;
+
+        self.0[kind as usize]
+            .chunks(per_long)
+            .map(|heights| {
+                heights
+                    .iter()
+                    .enumerate()
+                    .fold(0_u64, |long, (i, &h)| long | (h as u64) << (i * bits))
+                    as i64
+            })
+            .collect()
+    }
+
+    /// Returns every heightmap in the format of chunk data packets and Anvil
+    /// chunks.
+    pub(crate) fn to_nbt(&self, section_count: usize) -> Compound {
+        compound! {
+            HeightmapKind::MotionBlocking.name() =>
+                self.to_packed(HeightmapKind::MotionBlocking, section_count),
+            HeightmapKind::WorldSurface.name() =>
+                self.to_packed(HeightmapKind::WorldSurface, section_count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_heightmap() {
+        let mut heightmaps = Heightmaps::default();
+
+        heightmaps.set(HeightmapKind::WorldSurface, 0, 0, 1);
+        heightmaps.set(HeightmapKind::WorldSurface, 6, 0, 384);
+        heightmaps.set(HeightmapKind::WorldSurface, 7, 0, 2);
+        heightmaps.set(HeightmapKind::WorldSurface, 15, 15, 3);
+
+        // 384 needs 9 bits, so 7 heights fit in a long.
+        let packed = heightmaps.to_packed(HeightmapKind::WorldSurface, 24);
+
+        assert_eq!(packed.len(), 37);
+        assert_eq!(packed[0], 1 | 384 << 54);
+        assert_eq!(packed[1], 2);
+        assert_eq!(packed[36], 3 << 27);
+
+        assert!(heightmaps
+            .to_packed(HeightmapKind::MotionBlocking, 24)
+            .iter()
+            .all(|&long| long == 0));
+    }
+}
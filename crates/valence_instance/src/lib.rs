@@ -22,17 +22,26 @@ use std::collections::hash_map::Entry;
 use std::collections::BTreeSet;
 use std::iter::FusedIterator;
 use std::mem;
+use std::ops::Range;
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use bevy_ecs::query::WorldQuery;
+use block_change::{send_block_change_events, PendingBlockChange};
+pub use block_change::{BlockChangeCause, BlockChangeEvent, BlockRegionChangeEvent, FillEvents};
 pub use chunk::{Block, BlockEntity, BlockMut, BlockRef, Chunk};
 pub use chunk_entry::*;
+pub use clipboard::Clipboard;
+pub use collision::UnloadedChunkCollision;
 use glam::{DVec3, Vec3};
+pub use heightmap::HeightmapKind;
 use num_integer::div_ceil;
-use rustc_hash::FxHashMap;
-use valence_biome::Biome;
+pub use raycast::{BlockRaycastHit, FluidHandling};
+use rustc_hash::{FxHashMap, FxHashSet};
+use valence_biome::{Biome, BiomeId};
+use valence_block::BlockState;
 use valence_core::block_pos::BlockPos;
+use valence_core::chat::{write_player_chat, ChatSender, ChatType};
 use valence_core::chunk_pos::ChunkPos;
 use valence_core::despawn::Despawned;
 use valence_core::ident::Ident;
@@ -40,14 +49,16 @@ use valence_core::packet::array::LengthPrefixedArray;
 use valence_core::packet::byte_angle::ByteAngle;
 use valence_core::packet::encode::{PacketWriter, WritePacket};
 use valence_core::packet::s2c::play::particle::Particle;
+use valence_core::packet::s2c::play::play_sound::SoundId;
 use valence_core::packet::s2c::play::{
-    EntityAnimationS2c, EntityPositionS2c, EntitySetHeadYawS2c, EntityStatusS2c,
-    EntityTrackerUpdateS2c, EntityVelocityUpdateS2c, MoveRelative, OverlayMessageS2c, ParticleS2c,
-    PlaySoundS2c, Rotate, RotateAndMoveRelative,
+    BlockBreakingProgressS2c, ClearTitleS2c, EntityAnimationS2c, EntityPositionS2c,
+    EntitySetHeadYawS2c, EntityStatusS2c, EntityTrackerUpdateS2c, EntityVelocityUpdateS2c,
+    GameMessageS2c, MoveRelative, OverlayMessageS2c, ParticleS2c, PlaySoundS2c, Rotate,
+    RotateAndMoveRelative, StopSoundS2c, SubtitleS2c, TitleFadeS2c, TitleS2c,
 };
 use valence_core::packet::var_int::VarInt;
 use valence_core::packet::Packet;
-use valence_core::sound::{Sound, SoundCategory};
+use valence_core::sound::SoundCategory;
 use valence_core::text::Text;
 use valence_core::Server;
 use valence_dimension::DimensionType;
@@ -57,9 +68,15 @@ use valence_entity::{
     UpdateTrackedDataSet, Velocity,
 };
 
+mod block_change;
 mod chunk;
 mod chunk_entry;
+mod clipboard;
+mod collision;
+mod heightmap;
+mod light;
 mod paletted_container;
+mod raycast;
 
 pub struct InstancePlugin;
 
@@ -91,12 +108,30 @@ impl Plugin for InstancePlugin {
                 .in_base_set(CoreSet::PostUpdate)
                 .before(WriteUpdatePacketsToInstancesSet),
         )
+        .add_system(
+            update_instance_lighting
+                .in_base_set(CoreSet::PostUpdate)
+                .before(WriteUpdatePacketsToInstancesSet),
+        )
         .add_system(
             write_update_packets_to_instances
                 .after(update_entity_cell_positions)
                 .in_set(WriteUpdatePacketsToInstancesSet),
         )
-        .add_system(clear_instance_changes.in_set(ClearInstanceChangesSet));
+        .add_system(
+            unload_unviewed_chunks
+                .before(clear_instance_changes)
+                .in_set(ClearInstanceChangesSet),
+        )
+        .add_system(
+            send_block_change_events
+                .before(unload_unviewed_chunks)
+                .in_set(ClearInstanceChangesSet),
+        )
+        .add_system(clear_instance_changes.in_set(ClearInstanceChangesSet))
+        .add_event::<ChunkUnloaded>()
+        .add_event::<BlockChangeEvent>()
+        .add_event::<BlockRegionChangeEvent>();
 
         #[cfg(debug_assertions)]
         app.add_system(check_instance_invariants.in_base_set(CoreSet::PostUpdate));
@@ -163,6 +198,8 @@ fn update_entity_cell_positions(
                             incoming: vec![(entity, None)],
                             outgoing: vec![],
                             packet_buf: vec![],
+                            movement_ranges: vec![],
+                            non_essential_buf: vec![],
                         });
                     }
                 }
@@ -195,6 +232,8 @@ fn update_entity_cell_positions(
                             incoming: vec![(entity, Some(old_pos))],
                             outgoing: vec![],
                             packet_buf: vec![],
+                            movement_ranges: vec![],
+                            non_essential_buf: vec![],
                         });
                     }
                 }
@@ -206,29 +245,55 @@ fn update_entity_cell_positions(
     }
 }
 
+/// Computes the light of chunks that have changed.
+fn update_instance_lighting(mut instances: Query<&mut Instance>) {
+    for instance in &mut instances {
+        if !instance.info.lighting {
+            continue;
+        }
+
+        let instance = instance.into_inner();
+        light::update_lighting(&mut instance.partition, instance.info.section_count);
+    }
+}
+
 /// Writes update packets from entities and chunks into each cell's packet
 /// buffer.
 fn write_update_packets_to_instances(
     mut instances: Query<&mut Instance>,
     mut entities: Query<UpdateEntityQuery, (With<EntityKind>, Without<Despawned>)>,
     server: Res<Server>,
+    mut scratch_1: Local<Vec<u8>>,
+    mut scratch_2: Local<Vec<u8>>,
 ) {
-    let mut scratch_1 = vec![];
-    let mut scratch_2 = vec![];
+    // The scratch buffers are kept between ticks to avoid reallocating them.
+    let scratch_1 = &mut *scratch_1;
+    let scratch_2 = &mut *scratch_2;
 
     for instance in &mut instances {
         let instance = instance.into_inner();
 
+        if let Some(title) = instance.pending_title.take() {
+            PacketWriter::new(
+                &mut instance.packet_buf,
+                server.compression_threshold(),
+                scratch_2,
+            )
+            .write_packet(&TitleS2c {
+                title_text: title.into(),
+            });
+        }
+
         for (&pos, cell) in &mut instance.partition {
             // Cache chunk update packets into the packet buffer of this cell.
             if let Some(chunk) = &mut cell.chunk {
                 let writer = PacketWriter::new(
                     &mut cell.packet_buf,
                     server.compression_threshold(),
-                    &mut scratch_2,
+                    scratch_2,
                 );
 
-                chunk.write_update_packets(writer, &mut scratch_1, pos, &instance.info);
+                chunk.write_update_packets(writer, scratch_1, pos, &instance.info);
 
                 chunk.clear_viewed();
             }
@@ -244,7 +309,21 @@ fn write_update_packets_to_instances(
                 let writer = PacketWriter::new(
                     &mut cell.packet_buf,
                     server.compression_threshold(),
-                    &mut scratch_2,
+                    scratch_2,
+                );
+
+                entity.write_movement_packets(writer);
+
+                let movement_end = cell.packet_buf.len();
+
+                if movement_end > start {
+                    cell.movement_ranges.push(start..movement_end);
+                }
+
+                let writer = PacketWriter::new(
+                    &mut cell.packet_buf,
+                    server.compression_threshold(),
+                    scratch_2,
                 );
 
                 entity.write_update_packets(writer);
@@ -276,7 +355,9 @@ struct UpdateEntityQuery {
 }
 
 impl UpdateEntityQueryItem<'_> {
-    fn write_update_packets(&self, mut writer: impl WritePacket) {
+    /// Writes the packets updating the position, rotation and velocity of the
+    /// entity.
+    fn write_movement_packets(&self, mut writer: impl WritePacket) {
         // TODO: @RJ I saw you're using UpdateEntityPosition and UpdateEntityRotation sometimes. These two packets are actually broken on the client and will erase previous position/rotation https://bugs.mojang.com/browse/MC-255263 -Moulberry
 
         let entity_id = VarInt(self.id.get());
@@ -335,6 +416,11 @@ impl UpdateEntityQueryItem<'_> {
                 head_yaw: ByteAngle::from_degrees(self.head_yaw.0),
             });
         }
+    }
+
+    /// Writes the remaining update packets of the entity.
+    fn write_update_packets(&self, mut writer: impl WritePacket) {
+        let entity_id = VarInt(self.id.get());
 
         if let Some(update_data) = self.tracked_data.update_data() {
             writer.write_packet(&EntityTrackerUpdateS2c {
@@ -367,10 +453,50 @@ impl UpdateEntityQueryItem<'_> {
     }
 }
 
+/// Unloads chunks according to the [`ChunkRetention`] of each instance.
+fn unload_unviewed_chunks(
+    mut instances: Query<(Entity, &mut Instance)>,
+    mut events: EventWriter<ChunkUnloaded>,
+) {
+    for (instance_entity, mut instance) in &mut instances {
+        let ChunkRetention::UnloadAfter { ticks } = instance.info.chunk_retention else {
+            continue;
+        };
+
+        let instance = instance.as_mut();
+
+        for (&pos, cell) in &mut instance.partition {
+            let Some(chunk) = &mut cell.chunk else {
+                continue;
+            };
+
+            let unviewed_ticks = chunk.update_unviewed_ticks();
+
+            // Viewed chunks have a count of zero, so they are never unloaded.
+            if unviewed_ticks < ticks.max(1) || instance.pinned_chunks.contains(&pos) {
+                continue;
+            }
+
+            // No client is in view of the chunk, so there is no need to send an
+            // unload packet.
+            let chunk = cell.chunk.take().unwrap().into_unloaded();
+
+            events.send(ChunkUnloaded {
+                instance: instance_entity,
+                pos,
+                chunk,
+                entities: cell.entities.iter().copied().collect(),
+            });
+        }
+    }
+}
+
 fn clear_instance_changes(mut instances: Query<&mut Instance>) {
     for mut instance in &mut instances {
         instance.partition.retain(|_, cell| {
             cell.packet_buf.clear();
+            cell.movement_ranges.clear();
+            cell.non_essential_buf.clear();
             cell.chunk_removed = false;
             cell.incoming.clear();
             cell.outgoing.clear();
@@ -413,6 +539,51 @@ pub struct Instance {
     pub packet_buf: Vec<u8>,
     /// Scratch space for writing packets.
     scratch: Vec<u8>,
+    /// Positions of chunks that are never unloaded automatically.
+    pinned_chunks: FxHashSet<ChunkPos>,
+    /// The title written after the other packets of the tick. See
+    /// [`Instance::set_title`].
+    pending_title: Option<Text>,
+    /// Block changes made this tick which are sent as events at the end of
+    /// it.
+    block_changes: Vec<PendingBlockChange>,
+}
+
+/// Determines when the chunks of an instance are unloaded automatically. See
+/// [`Instance::set_chunk_retention`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum ChunkRetention {
+    /// Chunks stay loaded until they are removed manually.
+    #[default]
+    Keep,
+    /// Chunks are unloaded once they have not been in view of any client for
+    /// `ticks` consecutive ticks. A value of zero behaves like one.
+    UnloadAfter { ticks: u32 },
+}
+
+/// An event sent when a chunk is unloaded automatically because of the
+/// [`ChunkRetention`] of its instance.
+///
+/// Minecraft entities in the chunk are not affected. They are listed here so
+/// they can be despawned or saved along with the chunk.
+#[derive(Debug)]
+pub struct ChunkUnloaded {
+    /// The instance the chunk was unloaded from.
+    pub instance: Entity,
+    pub pos: ChunkPos,
+    pub chunk: Chunk,
+    /// Minecraft entities in the chunk when it was unloaded.
+    pub entities: Vec<Entity>,
+}
+
+/// The number of block positions affected by [`Instance::fill_blocks`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct FillBlocks {
+    /// Positions that were set to the new block state.
+    pub filled: u64,
+    /// Positions that were not set because they are not in a loaded chunk or
+    /// are outside the height of the instance.
+    pub skipped: u64,
 }
 
 #[doc(hidden)]
@@ -422,6 +593,11 @@ pub struct InstanceInfo {
     min_y: i32,
     biome_registry_len: usize,
     compression_threshold: Option<u32>,
+    /// If sky light and block light are computed for chunks. Otherwise, chunks
+    /// are sent with the filler light data.
+    lighting: bool,
+    chunk_retention: ChunkRetention,
+    unloaded_chunk_collision: UnloadedChunkCollision,
     filler_sky_light_mask: Box<[u64]>,
     /// Sending filler light data causes the vanilla client to lag
     /// less. Hopefully we can remove this in the future.
@@ -452,9 +628,25 @@ pub struct PartitionCell {
     /// at the end of the tick.
     #[doc(hidden)]
     pub packet_buf: Vec<u8>,
+    /// The ranges of `packet_buf` containing the movement packets of the
+    /// entities in this cell, in ascending order.
+    #[doc(hidden)]
+    pub movement_ranges: Vec<Range<usize>>,
+    /// A cache of non-essential packets, such as particles and sounds, to send
+    /// to the clients in view of this cell which are not in degraded mode.
+    #[doc(hidden)]
+    pub non_essential_buf: Vec<u8>,
 }
 
 impl Instance {
+    /// Creates an instance without chunks with the dimension type of the given
+    /// name. Chunks inserted into the instance are resized to the height of
+    /// the dimension type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dimension type doesn't exist or fails
+    /// [`DimensionType::validate`].
     pub fn new(
         dimension_type_name: impl Into<Ident<String>>,
         dimensions: &Query<&DimensionType>,
@@ -467,11 +659,13 @@ impl Instance {
             panic!("missing dimension type with name \"{dimension_type_name}\"")
         };
 
-        assert!(dim.height > 0, "invalid dimension height of {}", dim.height);
+        if let Err(e) = dim.validate() {
+            panic!("invalid dimension type \"{dimension_type_name}\": {e:#}");
+        }
 
         let light_section_count = (dim.height / 16 + 2) as usize;
 
-        let mut sky_light_mask = vec![0; div_ceil(light_section_count, 16)];
+        let mut sky_light_mask = vec![0; div_ceil(light_section_count, 64)];
 
         for i in 0..light_section_count {
             sky_light_mask[i / 64] |= 1 << (i % 64);
@@ -485,6 +679,9 @@ impl Instance {
                 min_y: dim.min_y,
                 biome_registry_len: biomes.iter().len(),
                 compression_threshold: server.compression_threshold(),
+                lighting: true,
+                chunk_retention: ChunkRetention::Keep,
+                unloaded_chunk_collision: UnloadedChunkCollision::Solid,
                 filler_sky_light_mask: sky_light_mask.into(),
                 filler_sky_light_arrays: vec![
                     LengthPrefixedArray([0xff; 2048]);
@@ -494,10 +691,16 @@ impl Instance {
             },
             packet_buf: vec![],
             scratch: vec![],
+            pinned_chunks: FxHashSet::default(),
+            pending_title: None,
+            block_changes: vec![],
         }
     }
 
-    /// TODO: Temporary hack for unit testing. Do not use!
+    /// Creates an instance with the height of the overworld without looking up
+    /// the dimension type, since dimension types are only loaded by a startup
+    /// system. Used by `valence::testing` so that the instance exists before
+    /// the first tick. Do not use outside of tests!
     #[doc(hidden)]
     pub fn new_unit_testing(
         dimension_type_name: impl Into<Ident<String>>,
@@ -511,11 +714,17 @@ impl Instance {
                 min_y: -64,
                 biome_registry_len: 1,
                 compression_threshold: server.compression_threshold(),
+                lighting: true,
+                chunk_retention: ChunkRetention::Keep,
+                unloaded_chunk_collision: UnloadedChunkCollision::Solid,
                 filler_sky_light_mask: vec![].into(),
                 filler_sky_light_arrays: vec![].into(),
             },
             packet_buf: vec![],
             scratch: vec![],
+            pinned_chunks: FxHashSet::default(),
+            pending_title: None,
+            block_changes: vec![],
         }
     }
 
@@ -527,6 +736,87 @@ impl Instance {
         self.info.section_count
     }
 
+    /// Returns the Y coordinate of the bottom of the chunks in this instance,
+    /// which is the `min_y` of its dimension type.
+    pub fn min_y(&self) -> i32 {
+        self.info.min_y
+    }
+
+    /// Returns whether sky light and block light are computed for the chunks in
+    /// this instance. See [`Self::set_lighting`].
+    pub fn lighting(&self) -> bool {
+        self.info.lighting
+    }
+
+    /// Sets whether sky light and block light are computed for the chunks in
+    /// this instance. Lighting is enabled by default.
+    ///
+    /// Computing light takes time whenever chunks are inserted or blocks
+    /// change. With lighting disabled, every block is sent to clients fully
+    /// lit instead.
+    pub fn set_lighting(&mut self, lighting: bool) {
+        if self.info.lighting == lighting {
+            return;
+        }
+
+        self.info.lighting = lighting;
+
+        for cell in self.partition.values_mut() {
+            if let Some(chunk) = &mut cell.chunk {
+                chunk.reset_light();
+            }
+        }
+    }
+
+    /// Returns the policy for unloading the chunks of this instance
+    /// automatically. See [`Self::set_chunk_retention`].
+    pub fn chunk_retention(&self) -> ChunkRetention {
+        self.info.chunk_retention
+    }
+
+    /// Sets the policy for unloading the chunks of this instance automatically.
+    /// Chunks are kept loaded by default.
+    ///
+    /// A [`ChunkUnloaded`] event is sent for every chunk unloaded this way.
+    /// Chunks pinned with [`Self::pin_chunk`] are never unloaded
+    /// automatically.
+    pub fn set_chunk_retention(&mut self, retention: ChunkRetention) {
+        self.info.chunk_retention = retention;
+    }
+
+    /// Returns how blocks in unloaded chunks collide in
+    /// [`Self::collides`] and [`Self::sweep`]. See
+    /// [`Self::set_unloaded_chunk_collision`].
+    pub fn unloaded_chunk_collision(&self) -> UnloadedChunkCollision {
+        self.info.unloaded_chunk_collision
+    }
+
+    /// Sets how blocks in unloaded chunks collide in [`Self::collides`] and
+    /// [`Self::sweep`]. Unloaded chunks are solid by default.
+    pub fn set_unloaded_chunk_collision(&mut self, collision: UnloadedChunkCollision) {
+        self.info.unloaded_chunk_collision = collision;
+    }
+
+    /// Prevents the chunk at the given position from being unloaded
+    /// automatically, regardless of the [`ChunkRetention`] of this instance.
+    /// The position does not need to be loaded. Returns `false` if it was
+    /// already pinned.
+    pub fn pin_chunk(&mut self, pos: impl Into<ChunkPos>) -> bool {
+        self.pinned_chunks.insert(pos.into())
+    }
+
+    /// Allows the chunk at the given position to be unloaded automatically
+    /// again. Returns `false` if it was not pinned.
+    pub fn unpin_chunk(&mut self, pos: impl Into<ChunkPos>) -> bool {
+        self.pinned_chunks.remove(&pos.into())
+    }
+
+    /// Returns whether the chunk at the given position is pinned. See
+    /// [`Self::pin_chunk`].
+    pub fn is_chunk_pinned(&self, pos: impl Into<ChunkPos>) -> bool {
+        self.pinned_chunks.contains(&pos.into())
+    }
+
     /// Get a reference to the chunk at the given position, if it is loaded.
     pub fn chunk(&self, pos: impl Into<ChunkPos>) -> Option<&Chunk<true>> {
         self.partition
@@ -614,58 +904,44 @@ impl Instance {
         self.packet_buf.shrink_to_fit();
     }
 
-    /// Gets a reference to the block at an absolute block position in world
-    /// space. Only works for blocks in loaded chunks.
-    ///
-    /// If the position is not inside of a chunk, then [`Option::None`] is
-    /// returned.
-    pub fn block(&self, pos: impl Into<BlockPos>) -> Option<BlockRef> {
-        let pos = pos.into();
-
-        let Some(y) = pos.y.checked_sub(self.info.min_y).and_then(|y| y.try_into().ok()) else {
-            return None;
-        };
+    /// Converts an absolute block position into the position of its chunk and
+    /// the block offsets within the chunk. Returns `None` if the position is
+    /// outside the height of the instance.
+    fn chunk_offsets(&self, pos: BlockPos) -> Option<(ChunkPos, usize, usize, usize)> {
+        let y: usize = pos.y.checked_sub(self.info.min_y)?.try_into().ok()?;
 
         if y >= self.info.section_count * 16 {
             return None;
         }
 
-        let Some(chunk) = self.chunk(ChunkPos::from_block_pos(pos)) else {
-            return None;
-        };
-
-        Some(chunk.block(
+        Some((
+            ChunkPos::from_block_pos(pos),
             pos.x.rem_euclid(16) as usize,
             y,
             pos.z.rem_euclid(16) as usize,
         ))
     }
 
+    /// Gets a reference to the block at an absolute block position in world
+    /// space. Only works for blocks in loaded chunks.
+    ///
+    /// If the position is not inside of a chunk, then [`Option::None`] is
+    /// returned.
+    pub fn block(&self, pos: impl Into<BlockPos>) -> Option<BlockRef> {
+        let (chunk_pos, x, y, z) = self.chunk_offsets(pos.into())?;
+
+        Some(self.chunk(chunk_pos)?.block(x, y, z))
+    }
+
     /// Gets a mutable reference to the block at an absolute block position in
     /// world space. Only works for blocks in loaded chunks.
     ///
     /// If the position is not inside of a chunk, then [`Option::None`] is
     /// returned.
     pub fn block_mut(&mut self, pos: impl Into<BlockPos>) -> Option<BlockMut> {
-        let pos = pos.into();
-
-        let Some(y) = pos.y.checked_sub(self.info.min_y).and_then(|y| y.try_into().ok()) else {
-            return None;
-        };
+        let (chunk_pos, x, y, z) = self.chunk_offsets(pos.into())?;
 
-        if y >= self.info.section_count * 16 {
-            return None;
-        }
-
-        let Some(chunk) = self.chunk_mut(ChunkPos::from_block_pos(pos)) else {
-            return None;
-        };
-
-        Some(chunk.block_mut(
-            pos.x.rem_euclid(16) as usize,
-            y,
-            pos.z.rem_euclid(16) as usize,
-        ))
+        Some(self.chunk_mut(chunk_pos)?.block_mut(x, y, z))
     }
 
     /// Sets the block at an absolute block position in world space. The
@@ -673,31 +949,262 @@ impl Instance {
     ///
     /// If the position is not within a loaded chunk or otherwise out of bounds,
     /// then [`Option::None`] is returned with no effect.
+    ///
+    /// A [`BlockChangeEvent`] with [`BlockChangeCause::Api`] is sent if the
+    /// block state changed. Use [`Instance::set_block_with_cause`] to give
+    /// another cause.
     pub fn set_block(
         &mut self,
         pos: impl Into<BlockPos>,
         block: impl Into<Block>,
     ) -> Option<Block> {
-        let pos = pos.into();
+        self.set_block_with_cause(pos, block, BlockChangeCause::Api)
+    }
 
-        let Some(y) = pos.y.checked_sub(self.info.min_y).and_then(|y| y.try_into().ok()) else {
-            return None;
-        };
+    /// Sets every block in the box between the block positions `min` and `max`
+    /// (inclusive) to the given block state. The corners may be given in any
+    /// order.
+    ///
+    /// This is much faster than calling [`Instance::set_block`] for every
+    /// position in the box because whole sections are written at once. Chunks
+    /// modified this way are resent to clients in full instead of with
+    /// individual block updates.
+    ///
+    /// Only blocks in loaded chunks and within the height of the instance are
+    /// set. The returned [`FillBlocks`] reports how many positions were filled
+    /// and how many were skipped.
+    ///
+    /// `events` determines whether a single [`BlockRegionChangeEvent`] is sent
+    /// for the box or a [`BlockChangeEvent`] for every changed block.
+    pub fn fill_blocks(
+        &mut self,
+        min: impl Into<BlockPos>,
+        max: impl Into<BlockPos>,
+        block: BlockState,
+        events: FillEvents,
+    ) -> FillBlocks {
+        let (a, b) = (min.into(), max.into());
+        let min = BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let max = BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+        let total = (max.x as i64 - min.x as i64 + 1) as u64
+            * (max.y as i64 - min.y as i64 + 1) as u64
+            * (max.z as i64 - min.z as i64 + 1) as u64;
+
+        // Clamp the box to the height of the instance.
+        let instance_max_y = self.info.min_y + self.info.section_count as i32 * 16 - 1;
+        let min_y = min.y.max(self.info.min_y);
+        let max_y = max.y.min(instance_max_y);
+
+        let instance_min_y = self.info.min_y;
+
+        let mut filled = 0;
+        let mut block_changes = vec![];
+
+        if min_y <= max_y {
+            let offset_min_y = (min_y - self.info.min_y) as usize;
+            let offset_max_y = (max_y - self.info.min_y) as usize;
+
+            for chunk_z in min.z.div_euclid(16)..=max.z.div_euclid(16) {
+                for chunk_x in min.x.div_euclid(16)..=max.x.div_euclid(16) {
+                    let Some(chunk) = self.chunk_mut([chunk_x, chunk_z]) else {
+                        continue;
+                    };
+
+                    let min_x = min.x.max(chunk_x * 16) - chunk_x * 16;
+                    let max_x = max.x.min(chunk_x * 16 + 15) - chunk_x * 16;
+                    let min_z = min.z.max(chunk_z * 16) - chunk_z * 16;
+                    let max_z = max.z.min(chunk_z * 16 + 15) - chunk_z * 16;
+
+                    if events == FillEvents::PerBlock {
+                        for y in offset_min_y..=offset_max_y {
+                            for z in min_z..=max_z {
+                                for x in min_x..=max_x {
+                                    let old_state = chunk.block_state(x as usize, y, z as usize);
+
+                                    if old_state != block {
+                                        block_changes.push(PendingBlockChange::Block {
+                                            position: BlockPos::new(
+                                                chunk_x * 16 + x,
+                                                instance_min_y + y as i32,
+                                                chunk_z * 16 + z,
+                                            ),
+                                            old_state,
+                                            new_state: block,
+                                            cause: BlockChangeCause::Api,
+                                        });
+                                    }
+                                }
+                            }
+                        }
+                    }
 
-        if y >= self.info.section_count * 16 {
-            return None;
+                    chunk.fill_block_states_in(
+                        [min_x as usize, offset_min_y, min_z as usize],
+                        [max_x as usize, offset_max_y, max_z as usize],
+                        block,
+                    );
+
+                    filled += (max_x - min_x + 1) as u64
+                        * (offset_max_y - offset_min_y + 1) as u64
+                        * (max_z - min_z + 1) as u64;
+                }
+            }
         }
 
-        let Some(chunk) = self.chunk_mut(ChunkPos::from_block_pos(pos)) else {
-            return None;
-        };
+        match events {
+            FillEvents::Region if filled > 0 => {
+                self.block_changes.push(PendingBlockChange::Region {
+                    min,
+                    max,
+                    new_state: block,
+                })
+            }
+            FillEvents::Region => {}
+            FillEvents::PerBlock => self.block_changes.append(&mut block_changes),
+        }
 
-        Some(chunk.set_block(
-            pos.x.rem_euclid(16) as usize,
-            y,
-            pos.z.rem_euclid(16) as usize,
-            block,
-        ))
+        FillBlocks {
+            filled,
+            skipped: total - filled,
+        }
+    }
+
+    /// Gets the biome at an absolute block position in world space. Biomes
+    /// are stored for 4x4x4 segments of blocks, so every block in a segment
+    /// has the same biome. Only works for blocks in loaded chunks.
+    ///
+    /// If the position is not inside of a chunk, then [`Option::None`] is
+    /// returned.
+    pub fn biome(&self, pos: impl Into<BlockPos>) -> Option<BiomeId> {
+        let (chunk_pos, x, y, z) = self.chunk_offsets(pos.into())?;
+
+        Some(self.chunk(chunk_pos)?.biome(x / 4, y / 4, z / 4))
+    }
+
+    /// Sets the biome of the 4x4x4 segment containing an absolute block
+    /// position in world space. The previous biome of the segment is
+    /// returned.
+    ///
+    /// The biomes of the chunk are resent to clients in view of it.
+    ///
+    /// If the position is not within a loaded chunk or otherwise out of bounds,
+    /// then [`Option::None`] is returned with no effect.
+    pub fn set_biome(&mut self, pos: impl Into<BlockPos>, biome: BiomeId) -> Option<BiomeId> {
+        let (chunk_pos, x, y, z) = self.chunk_offsets(pos.into())?;
+
+        Some(
+            self.chunk_mut(chunk_pos)?
+                .set_biome(x / 4, y / 4, z / 4, biome),
+        )
+    }
+
+    /// Sets the biome of every 4x4x4 segment overlapping the box between the
+    /// block positions `min` and `max` (inclusive). The corners may be given
+    /// in any order.
+    ///
+    /// Sections covered by the box are filled at once, which is much faster
+    /// than calling [`Instance::set_biome`] for every segment. Only segments
+    /// in loaded chunks and within the height of the instance are set.
+    pub fn fill_biomes(
+        &mut self,
+        min: impl Into<BlockPos>,
+        max: impl Into<BlockPos>,
+        biome: BiomeId,
+    ) {
+        let (a, b) = (min.into(), max.into());
+        let min = BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let max = BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+        // Clamp the box to the height of the instance.
+        let instance_max_y = self.info.min_y + self.info.section_count as i32 * 16 - 1;
+        let min_y = min.y.max(self.info.min_y);
+        let max_y = max.y.min(instance_max_y);
+
+        if min_y > max_y {
+            return;
+        }
+
+        // The box in biome segments, relative to the bottom of the instance.
+        let min_seg_y = ((min_y - self.info.min_y) / 4) as usize;
+        let max_seg_y = ((max_y - self.info.min_y) / 4) as usize;
+
+        for chunk_z in min.z.div_euclid(16)..=max.z.div_euclid(16) {
+            for chunk_x in min.x.div_euclid(16)..=max.x.div_euclid(16) {
+                let Some(chunk) = self.chunk_mut([chunk_x, chunk_z]) else {
+                    continue;
+                };
+
+                let min_x = (min.x.max(chunk_x * 16) - chunk_x * 16) as usize / 4;
+                let max_x = (max.x.min(chunk_x * 16 + 15) - chunk_x * 16) as usize / 4;
+                let min_z = (min.z.max(chunk_z * 16) - chunk_z * 16) as usize / 4;
+                let max_z = (max.z.min(chunk_z * 16 + 15) - chunk_z * 16) as usize / 4;
+
+                let whole_columns = min_x == 0 && max_x == 3 && min_z == 0 && max_z == 3;
+
+                for sect_y in min_seg_y / 4..=max_seg_y / 4 {
+                    let sect_min_y = min_seg_y.max(sect_y * 4);
+                    let sect_max_y = max_seg_y.min(sect_y * 4 + 3);
+
+                    if whole_columns && sect_min_y == sect_y * 4 && sect_max_y == sect_y * 4 + 3 {
+                        chunk.fill_biomes(sect_y, biome);
+                        continue;
+                    }
+
+                    for y in sect_min_y..=sect_max_y {
+                        for z in min_z..=max_z {
+                            for x in min_x..=max_x {
+                                chunk.set_biome(x, y, z, biome);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gets a reference to the block entity at an absolute block position in
+    /// world space. Only works for blocks in loaded chunks.
+    ///
+    /// If the position is not inside of a chunk or there is no block entity at
+    /// the position, then [`Option::None`] is returned.
+    pub fn block_entity(&self, pos: impl Into<BlockPos>) -> Option<&BlockEntity> {
+        let (chunk_pos, x, y, z) = self.chunk_offsets(pos.into())?;
+
+        self.chunk(chunk_pos)?.block_entity(x, y, z)
+    }
+
+    /// Gets a mutable reference to the block entity at an absolute block
+    /// position in world space. Changes made through the reference are sent to
+    /// clients. Only works for blocks in loaded chunks.
+    ///
+    /// If the position is not inside of a chunk or there is no block entity at
+    /// the position, then [`Option::None`] is returned.
+    pub fn block_entity_mut(&mut self, pos: impl Into<BlockPos>) -> Option<&mut BlockEntity> {
+        let (chunk_pos, x, y, z) = self.chunk_offsets(pos.into())?;
+
+        self.chunk_mut(chunk_pos)?.block_entity_mut(x, y, z)
+    }
+
+    /// Sets the block entity at an absolute block position in world space,
+    /// returning the block entity that was there before. Only works for blocks
+    /// in loaded chunks.
+    ///
+    /// The block entity should be of the kind required by the block at the
+    /// position, since it is removed as soon as the block is changed to one
+    /// that does not use it.
+    ///
+    /// If the position is not within a loaded chunk or otherwise out of bounds,
+    /// then [`Option::None`] is returned with no effect.
+    pub fn set_block_entity(
+        &mut self,
+        pos: impl Into<BlockPos>,
+        block_entity: BlockEntity,
+    ) -> Option<BlockEntity> {
+        let (chunk_pos, x, y, z) = self.chunk_offsets(pos.into())?;
+
+        self.chunk_mut(chunk_pos)?
+            .set_block_entity(x, y, z, block_entity)
     }
 
     /// Writes a packet into the global packet buffer of this instance. All
@@ -764,9 +1271,31 @@ impl Instance {
         }
     }
 
+    /// Like [`Self::write_packet_at`], but the packet is not essential to the
+    /// clients and is skipped for clients in degraded mode. Use this for
+    /// cosmetic packets which can be lost without consequences.
+    ///
+    /// See `OverloadPolicy::Degrade` in `valence_client` for more information.
+    pub fn write_non_essential_packet_at<'a, P>(&mut self, pkt: &P, pos: impl Into<ChunkPos>)
+    where
+        P: Packet<'a>,
+    {
+        let pos = pos.into();
+        if let Some(cell) = self.partition.get_mut(&pos) {
+            if cell.chunk.is_some() {
+                PacketWriter::new(
+                    &mut cell.non_essential_buf,
+                    self.info.compression_threshold,
+                    &mut self.scratch,
+                )
+                .write_packet(pkt);
+            }
+        }
+    }
+
     /// Puts a particle effect at the given position in the world. The particle
     /// effect is visible to all players in the instance with the
-    /// appropriate chunk in view.
+    /// appropriate chunk in view, except for clients in degraded mode.
     pub fn play_particle(
         &mut self,
         particle: &Particle,
@@ -778,7 +1307,7 @@ impl Instance {
     ) {
         let position = position.into();
 
-        self.write_packet_at(
+        self.write_non_essential_packet_at(
             &ParticleS2c {
                 particle: Cow::Borrowed(particle),
                 long_distance,
@@ -793,36 +1322,174 @@ impl Instance {
 
     /// Plays a sound effect at the given position in the world. The sound
     /// effect is audible to all players in the instance with the
-    /// appropriate chunk in view.
-    pub fn play_sound(
+    /// appropriate chunk in view, except for clients in degraded mode.
+    ///
+    /// `sound` is either a [`Sound`] or the identifier of a custom sound, such
+    /// as one from a resource pack. The seed used by the client to pick
+    /// variations of the sound is random. Use [`Self::play_sound_with_seed`]
+    /// to choose it.
+    ///
+    /// [`Sound`]: valence_core::sound::Sound
+    pub fn play_sound<'a>(
+        &mut self,
+        sound: impl Into<SoundId<'a>>,
+        category: SoundCategory,
+        position: impl Into<DVec3>,
+        volume: f32,
+        pitch: f32,
+    ) {
+        self.play_sound_with_seed(sound, category, position, volume, pitch, rand::random())
+    }
+
+    /// Like [`Self::play_sound`], but with a fixed seed.
+    pub fn play_sound_with_seed<'a>(
         &mut self,
-        sound: Sound,
+        sound: impl Into<SoundId<'a>>,
         category: SoundCategory,
         position: impl Into<DVec3>,
         volume: f32,
         pitch: f32,
+        seed: i64,
     ) {
         let position = position.into();
 
-        self.write_packet_at(
+        self.write_non_essential_packet_at(
             &PlaySoundS2c {
-                id: sound.to_id(),
+                id: sound.into(),
                 category,
                 position: (position * 8.0).as_ivec3(),
                 volume,
                 pitch,
-                seed: rand::random(),
+                seed,
             },
             ChunkPos::from_dvec3(position),
         );
     }
 
+    /// Shows the cracks of a block being broken to all players in the instance
+    /// with the appropriate chunk in view. The `stage` of the cracks goes from
+    /// `0` to `9`, and `None` removes them.
+    ///
+    /// The cracks belong to the entity `breaker`. Each entity has at most one
+    /// cracked block, so setting the stage of another block moves the cracks.
+    pub fn set_break_stage(
+        &mut self,
+        breaker: EntityId,
+        position: impl Into<BlockPos>,
+        stage: Option<u8>,
+    ) {
+        let position = position.into();
+
+        self.write_packet_at(
+            &BlockBreakingProgressS2c {
+                entity_id: VarInt(breaker.get()),
+                position,
+                // Stages outside of `0..=9` remove the cracks.
+                destroy_stage: stage.map_or(10, |s| s.min(9)),
+            },
+            ChunkPos::from_block_pos(position),
+        );
+    }
+
+    /// Stops sounds for all players in the instance. If `category` is `None`,
+    /// sounds in every category are stopped. If `sound` is `None`, all sounds
+    /// in the category are stopped.
+    pub fn stop_sound<'a>(
+        &mut self,
+        category: Option<SoundCategory>,
+        sound: Option<Ident<Cow<'a, str>>>,
+    ) {
+        self.write_packet(&StopSoundS2c {
+            source: category,
+            sound,
+        });
+    }
+
+    /// Sends a system message to all players in the instance. See
+    /// `Client::send_system_message` in `valence_client`.
+    pub fn send_system_message(&mut self, msg: impl Into<Text>) {
+        self.write_packet(&GameMessageS2c {
+            chat: msg.into().into(),
+            overlay: false,
+        });
+    }
+
+    /// Sends a system message shown above the hotbar to all players in the
+    /// instance.
+    pub fn send_overlay_message(&mut self, msg: impl Into<Text>) {
+        self.write_packet(&GameMessageS2c {
+            chat: msg.into().into(),
+            overlay: true,
+        });
+    }
+
+    /// Sends a chat message from a player to all players in the instance. See
+    /// `Client::send_player_chat` in `valence_client`.
+    pub fn send_player_chat(
+        &mut self,
+        sender: &ChatSender,
+        msg: impl Into<Text>,
+        chat_type: ChatType,
+    ) {
+        let writer = PacketWriter::new(
+            &mut self.packet_buf,
+            self.info.compression_threshold,
+            &mut self.scratch,
+        );
+
+        write_player_chat(writer, sender, &msg.into(), chat_type);
+    }
+
     /// Sets the action bar text of all players in the instance.
     pub fn set_action_bar(&mut self, text: impl Into<Text>) {
         self.write_packet(&OverlayMessageS2c {
             action_bar_text: text.into().into(),
         });
     }
+
+    /// Displays a title to all players in the instance. See
+    /// `Client::set_title` in `valence_client`.
+    ///
+    /// The title packet is written at the end of the tick, after the subtitle
+    /// and times set with [`Self::set_subtitle`] and
+    /// [`Self::set_title_times`].
+    pub fn set_title(&mut self, text: impl Into<Text>) {
+        self.pending_title = Some(text.into());
+    }
+
+    /// Sets the subtitle displayed under the title of all players in the
+    /// instance. The subtitle only appears once a title is displayed with
+    /// [`Self::set_title`].
+    pub fn set_subtitle(&mut self, text: impl Into<Text>) {
+        self.write_packet(&SubtitleS2c {
+            subtitle_text: text.into().into(),
+        });
+    }
+
+    /// Sets the number of ticks the title and subtitle of all players in the
+    /// instance spend fading in, staying on the screen, and fading out.
+    pub fn set_title_times(&mut self, fade_in: i32, stay: i32, fade_out: i32) {
+        self.write_packet(&TitleFadeS2c {
+            fade_in,
+            stay,
+            fade_out,
+        });
+    }
+
+    /// Removes the title and subtitle from the screen of all players in the
+    /// instance.
+    pub fn clear_title(&mut self) {
+        self.pending_title = None;
+        self.write_packet(&ClearTitleS2c { reset: false });
+    }
+
+    /// Removes the title and subtitle from the screen of all players in the
+    /// instance, and resets the subtitle and the title times to their default
+    /// values.
+    pub fn reset_title(&mut self) {
+        self.pending_title = None;
+        self.write_packet(&ClearTitleS2c { reset: true });
+    }
 }
 
 /// Returns the minimum number of bits needed to represent the integer `n`.
@@ -0,0 +1,818 @@
+//! Sky light and block light computation.
+//!
+//! Light is computed with a simplified version of the vanilla rules. Opaque
+//! blocks stop light entirely, liquids reduce it by one extra level, and all
+//! other blocks are transparent. Sky light travels straight down from the top
+//! of the instance without losing strength until it hits a block that is not
+//! transparent.
+//!
+//! Whole chunks are lit when they are inserted into an instance. Afterwards,
+//! light is updated incrementally around changed blocks, including across
+//! chunk borders.
+
+use std::collections::VecDeque;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use valence_block::BlockState;
+use valence_core::chunk_pos::ChunkPos;
+use valence_core::packet::array::LengthPrefixedArray;
+
+use crate::chunk::SECTION_BLOCK_COUNT;
+use crate::PartitionCell;
+
+/// The maximum light level.
+const MAX_LIGHT: u8 = 15;
+
+/// The number of bytes needed to store the light levels of a section.
+const LIGHT_ARRAY_LEN: usize = SECTION_BLOCK_COUNT / 2;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum LightKind {
+    Sky,
+    Block,
+}
+
+/// The light levels of every block in a chunk section.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum LightArray {
+    /// Every block in the section has the same light level.
+    Uniform(u8),
+    /// Each half-byte is the light level of a block. Blocks are ordered the
+    /// same way as in a paletted container.
+    Array(Box<[u8; LIGHT_ARRAY_LEN]>),
+}
+
+impl Default for LightArray {
+    fn default() -> Self {
+        Self::Uniform(0)
+    }
+}
+
+impl LightArray {
+    pub(crate) fn get(&self, idx: usize) -> u8 {
+        debug_assert!(idx < SECTION_BLOCK_COUNT);
+
+        match self {
+            Self::Uniform(level) => *level,
+            Self::Array(arr) => arr[idx / 2] >> (idx % 2 * 4) & 0xf,
+        }
+    }
+
+    /// Sets the light level of a block. Returns `true` if the level changed.
+    pub(crate) fn set(&mut self, idx: usize, level: u8) -> bool {
+        debug_assert!(idx < SECTION_BLOCK_COUNT);
+        debug_assert!(level <= MAX_LIGHT);
+
+        match self {
+            Self::Uniform(old) if *old == level => false,
+            Self::Uniform(old) => {
+                *self = Self::Array(Box::new([*old << 4 | *old; LIGHT_ARRAY_LEN]));
+                self.set(idx, level)
+            }
+            Self::Array(arr) => {
+                let shift = idx % 2 * 4;
+                let old = arr[idx / 2] >> shift & 0xf;
+                arr[idx / 2] = arr[idx / 2] & !(0xf << shift) | level << shift;
+                old != level
+            }
+        }
+    }
+
+    pub(crate) fn uniform(&self) -> Option<u8> {
+        match self {
+            Self::Uniform(level) => Some(*level),
+            Self::Array(_) => None,
+        }
+    }
+
+    /// Returns the number of bytes allocated on the heap by this array.
+    pub(crate) fn heap_size(&self) -> usize {
+        match self {
+            Self::Uniform(_) => 0,
+            Self::Array(_) => LIGHT_ARRAY_LEN,
+        }
+    }
+
+    pub(crate) fn to_packet_array(&self) -> LengthPrefixedArray<u8, LIGHT_ARRAY_LEN> {
+        match self {
+            Self::Uniform(level) => LengthPrefixedArray([*level << 4 | *level; LIGHT_ARRAY_LEN]),
+            Self::Array(arr) => LengthPrefixedArray(**arr),
+        }
+    }
+}
+
+/// If changing a block from `old` to `new` can change the light around it.
+pub(crate) fn affects_light(old: BlockState, new: BlockState) -> bool {
+    old.light_opacity() != new.light_opacity() || old.luminance() != new.luminance()
+}
+
+/// Computes the light level of a block when light of level `level` enters it
+/// from its neighbor.
+fn attenuate(kind: LightKind, level: u8, state: BlockState, downwards: bool) -> u8 {
+    let opacity = state.light_opacity();
+
+    if kind == LightKind::Sky && downwards && level == MAX_LIGHT && opacity == 0 {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(opacity.max(1))
+    }
+}
+
+/// Updates the light of every loaded chunk in the partition that has changed
+/// since the last call.
+pub(crate) fn update_lighting(
+    partition: &mut FxHashMap<ChunkPos, PartitionCell>,
+    section_count: usize,
+) {
+    let mut relit_chunks = vec![];
+    let mut changed_blocks = vec![];
+
+    for (&pos, cell) in partition.iter_mut() {
+        let Some(chunk) = &mut cell.chunk else {
+            continue;
+        };
+
+        let (relight, updates) = chunk.take_light_updates();
+
+        if relight {
+            relit_chunks.push(pos);
+        } else {
+            changed_blocks.extend(updates.into_iter().map(|idx| {
+                let idx = idx as i32;
+                [
+                    pos.x * 16 + idx % 16,
+                    idx / (16 * 16),
+                    pos.z * 16 + idx / 16 % 16,
+                ]
+            }));
+        }
+    }
+
+    if relit_chunks.is_empty() && changed_blocks.is_empty() {
+        return;
+    }
+
+    for kind in [LightKind::Sky, LightKind::Block] {
+        let mut engine = LightEngine {
+            partition: &mut *partition,
+            height: (section_count * 16) as i32,
+            kind,
+            increase: VecDeque::new(),
+            decrease: VecDeque::new(),
+            reseed: vec![],
+        };
+
+        engine.relight_chunks(&relit_chunks);
+        engine.update_blocks(&changed_blocks);
+    }
+}
+
+/// A block position where `y` is relative to the bottom of the instance.
+type Pos = [i32; 3];
+
+const DIRECTIONS: [[i32; 3]; 6] = [
+    [1, 0, 0],
+    [-1, 0, 0],
+    [0, 1, 0],
+    [0, -1, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+];
+
+fn offset([x, y, z]: Pos, [dx, dy, dz]: [i32; 3]) -> Pos {
+    [x + dx, y + dy, z + dz]
+}
+
+/// Propagates one kind of light through the loaded chunks of an instance.
+struct LightEngine<'a> {
+    partition: &'a mut FxHashMap<ChunkPos, PartitionCell>,
+    height: i32,
+    kind: LightKind,
+    /// Blocks whose light should be spread to their neighbors.
+    increase: VecDeque<(Pos, u8)>,
+    /// Blocks whose light was removed, paired with their previous level.
+    decrease: VecDeque<(Pos, u8)>,
+    /// Blocks that were darkened and might be light sources themselves.
+    reseed: Vec<Pos>,
+}
+
+impl LightEngine<'_> {
+    fn chunk_idx(&self, [x, y, z]: Pos) -> Option<(ChunkPos, usize)> {
+        if !(0..self.height).contains(&y) {
+            return None;
+        }
+
+        let idx = (x.rem_euclid(16) + z.rem_euclid(16) * 16 + y * 16 * 16) as usize;
+
+        Some((ChunkPos::new(x.div_euclid(16), z.div_euclid(16)), idx))
+    }
+
+    /// Returns the block state and light level at a position, or `None` if the
+    /// position is not in a loaded chunk.
+    fn get(&self, pos: Pos) -> Option<(BlockState, u8)> {
+        let (chunk_pos, idx) = self.chunk_idx(pos)?;
+        let chunk = self.partition.get(&chunk_pos)?.chunk.as_ref()?;
+
+        Some((chunk.block_state_at(idx), chunk.light_at(self.kind, idx)))
+    }
+
+    fn light(&self, pos: Pos) -> u8 {
+        self.get(pos).map_or(0, |(_, level)| level)
+    }
+
+    fn set_light(&mut self, pos: Pos, level: u8) {
+        if let Some((chunk_pos, idx)) = self.chunk_idx(pos) {
+            if let Some(chunk) = self
+                .partition
+                .get_mut(&chunk_pos)
+                .and_then(|cell| cell.chunk.as_mut())
+            {
+                chunk.set_light_at(self.kind, idx, level);
+            }
+        }
+    }
+
+    fn is_loaded(&self, pos: ChunkPos) -> bool {
+        self.partition
+            .get(&pos)
+            .is_some_and(|cell| cell.chunk.is_some())
+    }
+
+    /// The light level a block has on its own, regardless of its neighbors.
+    fn source_level(&self, [_, y, _]: Pos, state: BlockState) -> u8 {
+        match self.kind {
+            LightKind::Sky if y == self.height - 1 => attenuate(self.kind, MAX_LIGHT, state, true),
+            LightKind::Sky => 0,
+            LightKind::Block => state.luminance(),
+        }
+    }
+
+    /// Returns the light level of every block in a section if they are all the
+    /// same.
+    fn uniform_light(&self, chunk_pos: ChunkPos, sect_y: usize) -> Option<u8> {
+        self.partition
+            .get(&chunk_pos)?
+            .chunk
+            .as_ref()?
+            .light_section(self.kind, sect_y)
+            .uniform()
+    }
+
+    /// Spreads light from the blocks in the increase queue.
+    fn propagate_increase(&mut self) {
+        while let Some((pos, level)) = self.increase.pop_front() {
+            // Skip blocks that were darkened or brightened after being queued.
+            if level <= 1 || self.light(pos) != level {
+                continue;
+            }
+
+            for dir in DIRECTIONS {
+                let neighbor = offset(pos, dir);
+
+                let Some((state, old)) = self.get(neighbor) else {
+                    continue;
+                };
+
+                let new = attenuate(self.kind, level, state, dir[1] == -1);
+
+                if new > old {
+                    self.set_light(neighbor, new);
+                    self.increase.push_back((neighbor, new));
+                }
+            }
+        }
+    }
+
+    /// Removes light that depended on the blocks in the decrease queue. Blocks
+    /// at the edge of the darkened area are queued for increase so the area
+    /// can be lit again from the remaining sources.
+    fn propagate_decrease(&mut self) {
+        while let Some((pos, level)) = self.decrease.pop_front() {
+            for dir in DIRECTIONS {
+                let neighbor = offset(pos, dir);
+
+                let Some((_, old)) = self.get(neighbor) else {
+                    continue;
+                };
+
+                if old == 0 {
+                    continue;
+                }
+
+                let dependent = old < level
+                    || (self.kind == LightKind::Sky
+                        && dir[1] == -1
+                        && level == MAX_LIGHT
+                        && old == MAX_LIGHT);
+
+                if dependent {
+                    self.set_light(neighbor, 0);
+                    self.decrease.push_back((neighbor, old));
+                    self.reseed.push(neighbor);
+                } else {
+                    self.increase.push_back((neighbor, old));
+                }
+            }
+        }
+    }
+
+    /// Restores the light of darkened blocks that are light sources.
+    fn apply_reseeds(&mut self) {
+        for pos in std::mem::take(&mut self.reseed) {
+            let Some((state, old)) = self.get(pos) else {
+                continue;
+            };
+
+            let level = self.source_level(pos, state);
+
+            if level > old {
+                self.set_light(pos, level);
+                self.increase.push_back((pos, level));
+            }
+        }
+    }
+
+    /// Updates the light around blocks that have changed.
+    fn update_blocks(&mut self, changed: &[Pos]) {
+        for &pos in changed {
+            let level = self.light(pos);
+
+            if level > 0 {
+                self.set_light(pos, 0);
+                self.decrease.push_back((pos, level));
+            }
+
+            self.reseed.push(pos);
+        }
+
+        self.propagate_decrease();
+
+        for &pos in changed {
+            for dir in DIRECTIONS {
+                let neighbor = offset(pos, dir);
+                let level = self.light(neighbor);
+
+                if level > 0 {
+                    self.increase.push_back((neighbor, level));
+                }
+            }
+        }
+
+        self.apply_reseeds();
+        self.propagate_increase();
+    }
+
+    /// Computes the light of chunks from scratch and spreads it to and from
+    /// their neighbors.
+    fn relight_chunks(&mut self, chunks: &[ChunkPos]) {
+        let section_count = (self.height / 16) as usize;
+        let relit: FxHashSet<ChunkPos> = chunks.iter().copied().collect();
+
+        for &chunk_pos in chunks {
+            if let Some(chunk) = self
+                .partition
+                .get_mut(&chunk_pos)
+                .and_then(|cell| cell.chunk.as_mut())
+            {
+                chunk.clear_light(self.kind);
+            }
+        }
+
+        // Remove light in the neighboring chunks that may have come from the old
+        // contents of the relit chunks. Sky light at full strength always comes
+        // from above, so it is left alone.
+        let kind = self.kind;
+        let keep = |level: u8| level == 0 || kind == LightKind::Sky && level == MAX_LIGHT;
+
+        for &chunk_pos in chunks {
+            for (side, neighbor) in neighbors(chunk_pos) {
+                if relit.contains(&neighbor) || !self.is_loaded(neighbor) {
+                    continue;
+                }
+
+                for sect_y in 0..section_count {
+                    if self.uniform_light(neighbor, sect_y).is_some_and(keep) {
+                        continue;
+                    }
+
+                    for pos in border(neighbor, opposite(side), sect_y) {
+                        let level = self.light(pos);
+
+                        if !keep(level) {
+                            self.set_light(pos, 0);
+                            self.decrease.push_back((pos, level));
+                            self.reseed.push(pos);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.propagate_decrease();
+
+        for &chunk_pos in chunks {
+            match self.kind {
+                LightKind::Sky => self.light_sky_columns(chunk_pos),
+                LightKind::Block => self.light_emitters(chunk_pos),
+            }
+        }
+
+        if self.kind == LightKind::Sky {
+            for &chunk_pos in chunks {
+                self.queue_sky_spread(chunk_pos);
+            }
+        }
+
+        // Pull light in from the other neighboring chunks.
+        for &chunk_pos in chunks {
+            for (side, neighbor) in neighbors(chunk_pos) {
+                if relit.contains(&neighbor) || !self.is_loaded(neighbor) {
+                    continue;
+                }
+
+                for sect_y in 0..section_count {
+                    let theirs = self.uniform_light(neighbor, sect_y);
+                    let ours = self.uniform_light(chunk_pos, sect_y);
+
+                    // Light always loses at least one level when crossing the border.
+                    match (theirs, ours) {
+                        (Some(theirs), _) if theirs <= 1 => continue,
+                        (Some(theirs), Some(ours)) if theirs <= ours + 1 => continue,
+                        _ => {}
+                    }
+
+                    for pos in border(neighbor, opposite(side), sect_y) {
+                        let level = self.light(pos);
+
+                        if level > 1 {
+                            self.increase.push_back((pos, level));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.apply_reseeds();
+        self.propagate_increase();
+    }
+
+    /// Lights every block in the chunk that can see the sky.
+    fn light_sky_columns(&mut self, chunk_pos: ChunkPos) {
+        let section_count = (self.height / 16) as usize;
+
+        let Some(chunk) = self
+            .partition
+            .get_mut(&chunk_pos)
+            .and_then(|cell| cell.chunk.as_mut())
+        else {
+            return;
+        };
+
+        let mut column_levels = [MAX_LIGHT; 16 * 16];
+
+        for sect_y in (0..section_count).rev() {
+            if column_levels.iter().all(|&level| level == 0) {
+                break;
+            }
+
+            // Fast path for sections of transparent blocks under the open sky.
+            if let Some(state) = chunk.single_block_state(sect_y) {
+                if state.light_opacity() == 0 && column_levels.iter().all(|&l| l == MAX_LIGHT) {
+                    chunk.fill_light_section(self.kind, sect_y, MAX_LIGHT);
+                    continue;
+                }
+            }
+
+            for y in (sect_y * 16..sect_y * 16 + 16).rev() {
+                for (i, column_level) in column_levels.iter_mut().enumerate() {
+                    if *column_level == 0 {
+                        continue;
+                    }
+
+                    let idx = i + y * 16 * 16;
+                    *column_level =
+                        attenuate(self.kind, *column_level, chunk.block_state_at(idx), true);
+                    chunk.set_light_at(self.kind, idx, *column_level);
+                }
+            }
+        }
+    }
+
+    /// Queues the blocks in the chunk that can spread sky light sideways.
+    fn queue_sky_spread(&mut self, chunk_pos: ChunkPos) {
+        let section_count = (self.height / 16) as usize;
+        let base_x = chunk_pos.x * 16;
+        let base_z = chunk_pos.z * 16;
+
+        for sect_y in 0..section_count {
+            let positions: Vec<Pos> = match self.uniform_light(chunk_pos, sect_y) {
+                Some(level) if level <= 1 => continue,
+                // Only blocks on the border can be next to darker blocks.
+                Some(level) => neighbors(chunk_pos)
+                    .into_iter()
+                    .filter(|&(_, neighbor)| {
+                        // Uniform neighbors at most one level darker can't be lit further.
+                        self.is_loaded(neighbor)
+                            && !matches!(
+                                self.uniform_light(neighbor, sect_y),
+                                Some(theirs) if theirs + 1 >= level
+                            )
+                    })
+                    .flat_map(|(side, _)| border(chunk_pos, side, sect_y))
+                    .collect(),
+                None => (sect_y as i32 * 16..sect_y as i32 * 16 + 16)
+                    .flat_map(|y| (0..16 * 16).map(move |i| [base_x + i % 16, y, base_z + i / 16]))
+                    .collect(),
+            };
+
+            for pos in positions {
+                let level = self.light(pos);
+
+                if level <= 1 {
+                    continue;
+                }
+
+                let spreads = [[1, 0, 0], [-1, 0, 0], [0, 0, 1], [0, 0, -1]]
+                    .into_iter()
+                    .any(|dir| {
+                        self.get(offset(pos, dir)).is_some_and(|(state, old)| {
+                            attenuate(self.kind, level, state, false) > old
+                        })
+                    });
+
+                if spreads {
+                    self.increase.push_back((pos, level));
+                }
+            }
+        }
+    }
+
+    /// Lights every block in the chunk that emits light.
+    fn light_emitters(&mut self, chunk_pos: ChunkPos) {
+        let section_count = (self.height / 16) as usize;
+
+        let Some(chunk) = self
+            .partition
+            .get_mut(&chunk_pos)
+            .and_then(|cell| cell.chunk.as_mut())
+        else {
+            return;
+        };
+
+        let base_x = chunk_pos.x * 16;
+        let base_z = chunk_pos.z * 16;
+
+        for sect_y in 0..section_count {
+            if chunk
+                .single_block_state(sect_y)
+                .is_some_and(|state| state.luminance() == 0)
+            {
+                continue;
+            }
+
+            for idx in sect_y * SECTION_BLOCK_COUNT..(sect_y + 1) * SECTION_BLOCK_COUNT {
+                let level = chunk.block_state_at(idx).luminance();
+
+                if level > 0 {
+                    chunk.set_light_at(self.kind, idx, level);
+
+                    let idx = idx as i32;
+                    let pos = [base_x + idx % 16, idx / (16 * 16), base_z + idx / 16 % 16];
+                    self.increase.push_back((pos, level));
+                }
+            }
+        }
+    }
+}
+
+/// Returns the chunks next to a chunk, paired with the side of the chunk they
+/// are on. Sides are ordered +X, -X, +Z, -Z.
+fn neighbors(ChunkPos { x, z }: ChunkPos) -> [(usize, ChunkPos); 4] {
+    [
+        (0, ChunkPos::new(x + 1, z)),
+        (1, ChunkPos::new(x - 1, z)),
+        (2, ChunkPos::new(x, z + 1)),
+        (3, ChunkPos::new(x, z - 1)),
+    ]
+}
+
+/// Returns the side of a neighboring chunk that faces the chunk on `side`.
+fn opposite(side: usize) -> usize {
+    side ^ 1
+}
+
+/// Returns the positions of the blocks on one side of a chunk section. Sides
+/// are ordered +X, -X, +Z, -Z.
+fn border(chunk_pos: ChunkPos, side: usize, sect_y: usize) -> impl Iterator<Item = Pos> {
+    let base_x = chunk_pos.x * 16;
+    let base_z = chunk_pos.z * 16;
+    let base_y = sect_y as i32 * 16;
+
+    (base_y..base_y + 16).flat_map(move |y| {
+        (0..16).map(move |i| match side {
+            0 => [base_x + 15, y, base_z + i],
+            1 => [base_x, y, base_z + i],
+            2 => [base_x + i, y, base_z + 15],
+            _ => [base_x + i, y, base_z],
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::Chunk;
+
+    const SECTION_COUNT: usize = 4;
+
+    type Partition = FxHashMap<ChunkPos, PartitionCell>;
+
+    fn insert_chunk(partition: &mut Partition, pos: ChunkPos, mut chunk: Chunk) {
+        chunk.resize(SECTION_COUNT);
+
+        partition.insert(
+            pos,
+            PartitionCell {
+                chunk: Some(chunk.into_loaded()),
+                chunk_removed: false,
+                entities: BTreeSet::new(),
+                incoming: vec![],
+                outgoing: vec![],
+                packet_buf: vec![],
+                movement_ranges: vec![],
+                non_essential_buf: vec![],
+            },
+        );
+    }
+
+    fn set_block(partition: &mut Partition, [x, y, z]: Pos, state: BlockState) {
+        partition
+            .get_mut(&ChunkPos::new(x.div_euclid(16), z.div_euclid(16)))
+            .and_then(|cell| cell.chunk.as_mut())
+            .unwrap()
+            .set_block_state(
+                x.rem_euclid(16) as usize,
+                y as usize,
+                z.rem_euclid(16) as usize,
+                state,
+            );
+    }
+
+    fn light(partition: &Partition, kind: LightKind, [x, y, z]: Pos) -> u8 {
+        let chunk = partition[&ChunkPos::new(x.div_euclid(16), z.div_euclid(16))]
+            .chunk
+            .as_ref()
+            .unwrap();
+
+        let (x, y, z) = (
+            x.rem_euclid(16) as usize,
+            y as usize,
+            z.rem_euclid(16) as usize,
+        );
+
+        match kind {
+            LightKind::Sky => chunk.sky_light(x, y, z),
+            LightKind::Block => chunk.block_light(x, y, z),
+        }
+    }
+
+    fn roofed_chunk(roof_y: usize) -> Chunk {
+        let mut chunk = Chunk::new(SECTION_COUNT);
+        chunk.fill_block_states_in([0, roof_y, 0], [15, roof_y, 15], BlockState::STONE);
+        chunk
+    }
+
+    #[test]
+    fn light_array_nibbles() {
+        let mut arr = LightArray::Uniform(3);
+
+        assert!(!arr.set(10, 3));
+        assert!(arr.set(10, 15));
+        assert!(arr.set(11, 7));
+
+        assert_eq!(arr.get(9), 3);
+        assert_eq!(arr.get(10), 15);
+        assert_eq!(arr.get(11), 7);
+        assert_eq!(arr.to_packet_array().0[5], 0x7f);
+    }
+
+    #[test]
+    fn sky_light_through_hole() {
+        let mut partition = Partition::default();
+        insert_chunk(&mut partition, ChunkPos::new(0, 0), roofed_chunk(40));
+
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Sky, [8, 63, 8]), 15);
+        assert_eq!(light(&partition, LightKind::Sky, [8, 41, 8]), 15);
+        assert_eq!(light(&partition, LightKind::Sky, [8, 40, 8]), 0);
+        assert_eq!(light(&partition, LightKind::Sky, [8, 39, 8]), 0);
+
+        set_block(&mut partition, [8, 40, 8], BlockState::AIR);
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Sky, [8, 40, 8]), 15);
+        assert_eq!(light(&partition, LightKind::Sky, [8, 0, 8]), 15);
+        assert_eq!(light(&partition, LightKind::Sky, [9, 39, 8]), 14);
+        assert_eq!(light(&partition, LightKind::Sky, [11, 39, 8]), 12);
+        assert_eq!(light(&partition, LightKind::Sky, [11, 39, 11]), 9);
+
+        set_block(&mut partition, [8, 40, 8], BlockState::STONE);
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Sky, [8, 40, 8]), 0);
+        assert_eq!(light(&partition, LightKind::Sky, [8, 0, 8]), 0);
+        assert_eq!(light(&partition, LightKind::Sky, [9, 39, 8]), 0);
+    }
+
+    #[test]
+    fn block_light_from_emitters() {
+        let mut partition = Partition::default();
+        insert_chunk(&mut partition, ChunkPos::new(0, 0), Chunk::default());
+
+        set_block(&mut partition, [8, 20, 8], BlockState::TORCH);
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Block, [8, 20, 8]), 14);
+        assert_eq!(light(&partition, LightKind::Block, [8, 21, 8]), 13);
+        assert_eq!(light(&partition, LightKind::Block, [10, 20, 10]), 10);
+        assert_eq!(light(&partition, LightKind::Block, [8, 34, 8]), 0);
+
+        // Opaque blocks stop light.
+        set_block(&mut partition, [9, 20, 8], BlockState::STONE);
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Block, [9, 20, 8]), 0);
+        assert_eq!(light(&partition, LightKind::Block, [10, 20, 8]), 10);
+
+        set_block(&mut partition, [8, 20, 8], BlockState::AIR);
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Block, [8, 20, 8]), 0);
+        assert_eq!(light(&partition, LightKind::Block, [10, 20, 10]), 0);
+    }
+
+    #[test]
+    fn block_light_across_chunk_borders() {
+        let mut partition = Partition::default();
+        insert_chunk(&mut partition, ChunkPos::new(0, 0), Chunk::default());
+
+        set_block(&mut partition, [15, 10, 8], BlockState::TORCH);
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        // The neighbor is lit when it is inserted.
+        insert_chunk(&mut partition, ChunkPos::new(1, 0), Chunk::default());
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Block, [16, 10, 8]), 13);
+        assert_eq!(light(&partition, LightKind::Block, [20, 10, 8]), 9);
+
+        // Light spreads into loaded neighbors when blocks change.
+        set_block(&mut partition, [16, 12, 0], BlockState::TORCH);
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Block, [15, 12, 0]), 13);
+
+        // Removing the torch removes the light it spread to the neighbor.
+        set_block(&mut partition, [15, 10, 8], BlockState::AIR);
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Block, [20, 10, 8]), 0);
+        assert_eq!(light(&partition, LightKind::Block, [16, 10, 8]), 4);
+
+        // Replacing a chunk removes the light that came from its old contents.
+        insert_chunk(&mut partition, ChunkPos::new(1, 0), Chunk::default());
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Block, [15, 12, 0]), 0);
+        assert_eq!(light(&partition, LightKind::Block, [16, 12, 0]), 0);
+    }
+
+    #[test]
+    fn sky_light_across_chunk_borders() {
+        let mut partition = Partition::default();
+        insert_chunk(&mut partition, ChunkPos::new(0, 0), roofed_chunk(63));
+        insert_chunk(&mut partition, ChunkPos::new(1, 0), Chunk::default());
+
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Sky, [16, 30, 8]), 15);
+        assert_eq!(light(&partition, LightKind::Sky, [15, 30, 8]), 14);
+        assert_eq!(light(&partition, LightKind::Sky, [10, 30, 8]), 9);
+        assert_eq!(light(&partition, LightKind::Sky, [0, 30, 8]), 0);
+
+        // Walling off the border darkens the roofed chunk.
+        for y in 0..63 {
+            for z in 0..16 {
+                set_block(&mut partition, [16, y, z], BlockState::STONE);
+            }
+        }
+
+        update_lighting(&mut partition, SECTION_COUNT);
+
+        assert_eq!(light(&partition, LightKind::Sky, [15, 30, 8]), 0);
+        assert_eq!(light(&partition, LightKind::Sky, [10, 30, 8]), 0);
+    }
+}
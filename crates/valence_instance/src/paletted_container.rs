@@ -1,5 +1,5 @@
-use std::array;
 use std::io::Write;
+use std::{array, mem};
 
 use arrayvec::ArrayVec;
 use num_integer::div_ceil;
@@ -73,6 +73,10 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize>
             Self::Indirect(ind) => {
                 if let Some(old) = ind.set(idx, val) {
                     old
+                } else if ind.compact() {
+                    // Removing the unused elements made room in the palette.
+                    ind.set(idx, val)
+                        .expect("compacted palette should have room")
                 } else {
                     // Upgrade to direct.
                     *self = Self::Direct(Box::new(array::from_fn(|i| ind.get(i))));
@@ -91,19 +95,10 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize>
         match self {
             Self::Single(_) => {}
             Self::Indirect(ind) => {
-                let mut new_ind = Indirect {
-                    palette: ArrayVec::new(),
-                    indices: [0; HALF_LEN],
-                };
+                ind.compact();
 
-                for i in 0..LEN {
-                    new_ind.set(i, ind.get(i));
-                }
-
-                if new_ind.palette.len() == 1 {
-                    *self = Self::Single(new_ind.palette[0]);
-                } else {
-                    **ind = new_ind;
+                if ind.palette.len() == 1 {
+                    *self = Self::Single(ind.palette[0]);
                 }
             }
             Self::Direct(dir) => {
@@ -127,6 +122,15 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize>
         }
     }
 
+    /// Returns the number of bytes allocated on the heap by this container.
+    pub(crate) fn heap_size(&self) -> usize {
+        match self {
+            Self::Single(_) => 0,
+            Self::Indirect(_) => mem::size_of::<Indirect<T, LEN, HALF_LEN>>(),
+            Self::Direct(_) => mem::size_of::<[T; LEN]>(),
+        }
+    }
+
     /// Encodes the paletted container in the format that Minecraft expects.
     ///
     /// - **`writer`**: The [`Write`] instance to write the paletted container
@@ -255,6 +259,35 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize> Indirect<T
         *u8 = (*u8 & !(0b1111 << shift)) | ((palette_idx as u8) << shift);
         Some(old_val)
     }
+
+    /// Removes the elements of the palette which are no longer used and
+    /// reorders the rest by first use. Returns `true` if any element was
+    /// removed.
+    pub(crate) fn compact(&mut self) -> bool {
+        let mut used = [false; 16];
+
+        for i in 0..LEN {
+            used[self.indices[i / 2] as usize >> (i % 2 * 4) & 0b1111] = true;
+        }
+
+        let used_count = used.iter().filter(|&&u| u).count();
+
+        if used_count == self.palette.len() {
+            return false;
+        }
+
+        let mut new_ind = Indirect {
+            palette: ArrayVec::new(),
+            indices: [0; HALF_LEN],
+        };
+
+        for i in 0..LEN {
+            new_ind.set(i, self.get(i));
+        }
+
+        *self = new_ind;
+        true
+    }
 }
 
 #[inline]
@@ -336,4 +369,39 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn full_palette_is_compacted() {
+        const LEN: usize = 100;
+
+        let mut p = PalettedContainer::<u32, LEN, { LEN / 2 }>::new();
+        let mut a = [0; LEN];
+
+        // Fill the palette with 16 elements.
+        for (i, elem) in a.iter_mut().enumerate().take(16).skip(1) {
+            p.set(i, i as u32);
+            *elem = i as u32;
+        }
+
+        // Leave some of the elements unused.
+        for (i, elem) in a.iter_mut().enumerate().take(8).skip(1) {
+            p.set(i, 0);
+            *elem = 0;
+        }
+
+        p.set(20, 100);
+        a[20] = 100;
+
+        assert!(matches!(p, PalettedContainer::Indirect(_)));
+        assert!(check(&p, &a));
+
+        // There is no room left when every element is in use.
+        for (i, elem) in a.iter_mut().enumerate().take(8).skip(1) {
+            p.set(i, 100 + i as u32);
+            *elem = 100 + i as u32;
+        }
+
+        assert!(matches!(p, PalettedContainer::Direct(_)));
+        assert!(check(&p, &a));
+    }
 }
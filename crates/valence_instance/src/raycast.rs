@@ -0,0 +1,358 @@
+use glam::DVec3;
+use valence_block::{BlockKind, BlockState, PropName, PropValue};
+use valence_core::aabb::Aabb;
+use valence_core::block_pos::BlockPos;
+use valence_core::direction::Direction;
+
+use crate::Instance;
+
+/// Which fluids are hit by [`Instance::raycast_filtered`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum FluidHandling {
+    /// Rays pass through fluids.
+    #[default]
+    Ignore,
+    /// Rays hit source blocks of fluids, including waterlogged blocks, but
+    /// pass through flowing fluids.
+    SourceOnly,
+    /// Rays hit all fluids.
+    Any,
+}
+
+/// The block hit by a ray cast with [`Instance::raycast`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BlockRaycastHit {
+    /// The position of the block which was hit.
+    pub pos: BlockPos,
+    /// The block state which was hit.
+    pub state: BlockState,
+    /// The face of the block's shape the ray entered through. If the ray
+    /// started inside the shape, this is the face which points against the
+    /// largest component of the direction of the ray.
+    pub face: Direction,
+    /// The point where the ray hit the block.
+    pub point: DVec3,
+    /// The distance from the origin of the ray to `point`.
+    pub distance: f64,
+}
+
+impl Instance {
+    /// Casts a ray through the blocks of the instance and returns the first
+    /// block whose collision shape is hit within `max_distance` of `origin`.
+    /// Blocks without a collision shape, like air and flowers, and fluids are
+    /// passed through.
+    ///
+    /// See [`Instance::raycast_filtered`] to hit fluids or pass through more
+    /// blocks.
+    pub fn raycast(
+        &self,
+        origin: impl Into<DVec3>,
+        direction: impl Into<DVec3>,
+        max_distance: f64,
+    ) -> Option<BlockRaycastHit> {
+        self.raycast_filtered(
+            origin,
+            direction,
+            max_distance,
+            FluidHandling::Ignore,
+            |_, _| false,
+        )
+    }
+
+    /// Like [`Instance::raycast`], but fluids are hit according to `fluids`,
+    /// and blocks for which `passthrough` returns `true` are passed through,
+    /// like glass for projectiles which fly through windows.
+    ///
+    /// The blocks along the ray are visited in order, and blocks in unloaded
+    /// chunks or outside the height of the instance are passed through. The
+    /// direction doesn't need to be normalized, but `None` is returned if it
+    /// is zero.
+    pub fn raycast_filtered(
+        &self,
+        origin: impl Into<DVec3>,
+        direction: impl Into<DVec3>,
+        max_distance: f64,
+        fluids: FluidHandling,
+        mut passthrough: impl FnMut(BlockPos, BlockState) -> bool,
+    ) -> Option<BlockRaycastHit> {
+        let origin = origin.into();
+        let direction = direction.into().try_normalize()?;
+
+        // The voxel traversal algorithm of Amanatides and Woo.
+        let mut voxel = origin.floor().as_ivec3();
+        let step = direction.signum().as_ivec3();
+        let mut t_max = DVec3::ZERO;
+        let mut t_delta = DVec3::ZERO;
+
+        for i in 0..3 {
+            if direction[i] == 0.0 {
+                t_max[i] = f64::INFINITY;
+                t_delta[i] = f64::INFINITY;
+            } else {
+                let boundary = if direction[i] > 0.0 {
+                    voxel[i] as f64 + 1.0
+                } else {
+                    voxel[i] as f64
+                };
+
+                t_max[i] = (boundary - origin[i]) / direction[i];
+                t_delta[i] = 1.0 / direction[i].abs();
+            }
+        }
+
+        loop {
+            let pos = BlockPos::new(voxel.x, voxel.y, voxel.z);
+            let mut hit: Option<BlockRaycastHit> = None;
+
+            // Fences and walls are taller than a block, so they are also hit
+            // from the block above them.
+            for (pos, tall_only) in [(pos, false), (pos.get_in_direction(Direction::Down), true)] {
+                let Some(block) = self.block(pos) else {
+                    continue;
+                };
+
+                let state = block.state();
+
+                if passthrough(pos, state) {
+                    continue;
+                }
+
+                let block_hit = self
+                    .block_shapes(pos, state, fluids)
+                    .filter(|shape| !tall_only || shape.max.y > 1.0)
+                    .filter_map(|shape| ray_box_intersect(origin, direction, shape + pos_vec(pos)))
+                    .min_by(|a, b| a.0.total_cmp(&b.0));
+
+                let Some((distance, axis)) = block_hit else {
+                    continue;
+                };
+
+                if hit.map_or(false, |hit| hit.distance <= distance) {
+                    continue;
+                }
+
+                let axis = axis.unwrap_or_else(|| largest_axis(direction.abs()));
+
+                hit = Some(BlockRaycastHit {
+                    pos,
+                    state,
+                    face: entered_face(axis, direction[axis]),
+                    point: origin + direction * distance,
+                    distance,
+                });
+            }
+
+            if let Some(hit) = hit {
+                return (hit.distance <= max_distance).then_some(hit);
+            }
+
+            let axis = smallest_axis(t_max);
+
+            if t_max[axis] > max_distance {
+                return None;
+            }
+
+            voxel[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+        }
+    }
+
+    /// Returns the shapes of a block the ray can hit, relative to the block.
+    fn block_shapes(
+        &self,
+        pos: BlockPos,
+        state: BlockState,
+        fluids: FluidHandling,
+    ) -> impl Iterator<Item = Aabb> {
+        let fluid = match fluids {
+            FluidHandling::Ignore => None,
+            FluidHandling::SourceOnly => fluid_level(state).filter(|&level| level == 0),
+            FluidHandling::Any => fluid_level(state),
+        };
+
+        let fluid_shape = fluid.map(|level| {
+            let above = self
+                .block(pos.get_in_direction(Direction::Up))
+                .and_then(|block| fluid_kind(block.state()));
+
+            // Fluids fill the whole block when the same fluid is above them.
+            // Otherwise, sources and falling fluids are 8/9 of a block high.
+            let height = if above.is_some() && above == fluid_kind(state) {
+                1.0
+            } else if level >= 8 {
+                8.0 / 9.0
+            } else {
+                (8 - level) as f64 / 9.0
+            };
+
+            Aabb::new([0.0, 0.0, 0.0], [1.0, height, 1.0])
+        });
+
+        state.collision_shapes().chain(fluid_shape)
+    }
+}
+
+fn pos_vec(pos: BlockPos) -> DVec3 {
+    DVec3::new(pos.x as f64, pos.y as f64, pos.z as f64)
+}
+
+/// Returns the kind of fluid in a block, treating waterlogged blocks as water.
+pub(crate) fn fluid_kind(state: BlockState) -> Option<BlockKind> {
+    match state.to_kind() {
+        BlockKind::Lava => Some(BlockKind::Lava),
+        BlockKind::Water
+        | BlockKind::BubbleColumn
+        | BlockKind::Kelp
+        | BlockKind::KelpPlant
+        | BlockKind::Seagrass
+        | BlockKind::TallSeagrass => Some(BlockKind::Water),
+        _ if state.get(PropName::Waterlogged) == Some(PropValue::True) => Some(BlockKind::Water),
+        _ => None,
+    }
+}
+
+/// Returns the level of the fluid in a block, which is 0 for source blocks,
+/// or `None` if the block doesn't contain a fluid.
+fn fluid_level(state: BlockState) -> Option<u16> {
+    fluid_kind(state)?;
+
+    match state.to_kind() {
+        BlockKind::Water | BlockKind::Lava => state.get(PropName::Level)?.to_u16(),
+        _ => Some(0),
+    }
+}
+
+fn smallest_axis(v: DVec3) -> usize {
+    if v.x <= v.y && v.x <= v.z {
+        0
+    } else if v.y <= v.z {
+        1
+    } else {
+        2
+    }
+}
+
+fn largest_axis(v: DVec3) -> usize {
+    if v.x >= v.y && v.x >= v.z {
+        0
+    } else if v.y >= v.z {
+        1
+    } else {
+        2
+    }
+}
+
+/// Returns the face a ray moving along `axis` enters a box through.
+fn entered_face(axis: usize, direction: f64) -> Direction {
+    match (axis, direction > 0.0) {
+        (0, true) => Direction::West,
+        (0, false) => Direction::East,
+        (1, true) => Direction::Down,
+        (1, false) => Direction::Up,
+        (_, true) => Direction::North,
+        (_, false) => Direction::South,
+    }
+}
+
+/// Returns the distance along a normalized ray to where it enters a box, and
+/// the axis of the face it enters through. The axis is `None` if the ray
+/// starts inside the box.
+///
+/// A point is inside the box if it is at least the minimum and less than the
+/// maximum along every axis, so that rays moving along the boundary between
+/// two blocks only hit one of them.
+fn ray_box_intersect(origin: DVec3, direction: DVec3, bb: Aabb) -> Option<(f64, Option<usize>)> {
+    let mut near = f64::NEG_INFINITY;
+    let mut near_axis = None;
+    let mut far = f64::INFINITY;
+
+    for i in 0..3 {
+        if direction[i] == 0.0 {
+            if origin[i] < bb.min[i] || origin[i] >= bb.max[i] {
+                return None;
+            }
+
+            continue;
+        }
+
+        let t0 = (bb.min[i] - origin[i]) / direction[i];
+        let t1 = (bb.max[i] - origin[i]) / direction[i];
+
+        let (t_enter, t_exit) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+
+        if t_enter > near {
+            near = t_enter;
+            near_axis = Some(i);
+        }
+
+        far = far.min(t_exit);
+    }
+
+    if near >= far || far <= 0.0 {
+        return None;
+    }
+
+    if near <= 0.0 {
+        Some((0.0, None))
+    } else {
+        Some((near, near_axis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::new([0.0, 0.0, 0.0], [1.0, 1.0, 1.0])
+    }
+
+    #[test]
+    fn ray_box() {
+        // Entering through the west face.
+        assert_eq!(
+            ray_box_intersect(DVec3::new(-1.0, 0.5, 0.5), DVec3::X, unit_box()),
+            Some((1.0, Some(0)))
+        );
+        // Starting inside.
+        assert_eq!(
+            ray_box_intersect(DVec3::new(0.5, 0.5, 0.5), DVec3::NEG_Y, unit_box()),
+            Some((0.0, None))
+        );
+        // Pointing away.
+        assert_eq!(
+            ray_box_intersect(DVec3::new(-1.0, 0.5, 0.5), DVec3::NEG_X, unit_box()),
+            None
+        );
+        // Grazing the top face, which is outside the box.
+        assert_eq!(
+            ray_box_intersect(DVec3::new(-1.0, 1.0, 0.5), DVec3::X, unit_box()),
+            None
+        );
+        // Grazing the bottom face, which is inside the box.
+        assert_eq!(
+            ray_box_intersect(DVec3::new(-1.0, 0.0, 0.5), DVec3::X, unit_box()),
+            Some((1.0, Some(0)))
+        );
+        // Leaving through a face.
+        assert_eq!(
+            ray_box_intersect(DVec3::new(1.0, 0.5, 0.5), DVec3::X, unit_box()),
+            None
+        );
+    }
+
+    #[test]
+    fn fluid_levels() {
+        assert_eq!(fluid_level(BlockState::WATER), Some(0));
+        assert_eq!(
+            fluid_level(BlockState::LAVA.set(PropName::Level, PropValue::_3)),
+            Some(3)
+        );
+        assert_eq!(
+            fluid_level(BlockState::OAK_SLAB.set(PropName::Waterlogged, PropValue::True)),
+            Some(0)
+        );
+        assert_eq!(fluid_level(BlockState::OAK_SLAB), None);
+        assert_eq!(fluid_level(BlockState::KELP), Some(0));
+    }
+}
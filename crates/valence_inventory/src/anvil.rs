@@ -0,0 +1,195 @@
+use bevy_ecs::prelude::*;
+use valence_core::item::ItemStack;
+use valence_nbt::Value;
+
+/// The state of an inventory of kind [`InventoryKind::Anvil`]. This component
+/// is added to anvil inventories automatically.
+///
+/// When the inputs or the name of the anvil change, the result slot and
+/// `level_cost` are set to rename the first input, and [`UpdateAnvilResult`]
+/// is sent. The level cost is sent to the clients viewing the anvil.
+///
+/// Like in vanilla, every client should view its own anvil inventory.
+///
+/// [`InventoryKind::Anvil`]: crate::InventoryKind::Anvil
+#[derive(Component, Clone, PartialEq, Default, Debug)]
+pub struct AnvilState {
+    /// The name typed into the text field of the anvil screen.
+    pub item_name: String,
+    /// The cost in experience levels of taking the result. The result can't
+    /// be taken while this is `0`.
+    pub level_cost: i32,
+    /// The number of items of the second input used up by taking the result,
+    /// or `0` for all of them.
+    pub repair_item_usage: u8,
+    /// The inputs and name the result was computed for.
+    computed_for: Option<([Option<ItemStack>; 2], String)>,
+}
+
+impl AnvilState {
+    /// Returns `true` if the result needs to be computed again.
+    pub(crate) fn is_outdated(&self, inputs: [Option<&ItemStack>; 2]) -> bool {
+        match &self.computed_for {
+            Some(([first, second], name)) => {
+                [first.as_ref(), second.as_ref()] != inputs || *name != self.item_name
+            }
+            None => true,
+        }
+    }
+
+    /// Computes the default result of the anvil, which renames the first
+    /// input.
+    pub(crate) fn compute_result(&mut self, inputs: [Option<&ItemStack>; 2]) -> Option<ItemStack> {
+        self.computed_for = Some((inputs.map(|stack| stack.cloned()), self.item_name.clone()));
+        self.repair_item_usage = 0;
+
+        let result = match inputs {
+            [Some(input), None] => rename(input, &self.item_name),
+            // Combining items is left to plugins.
+            _ => None,
+        };
+
+        match result {
+            Some((result, level_cost)) => {
+                self.level_cost = level_cost;
+                Some(result)
+            }
+            None => {
+                self.level_cost = 0;
+                None
+            }
+        }
+    }
+}
+
+/// Renames the item like the vanilla anvil, returning the renamed item and the
+/// level cost. A blank name removes the custom name of the item.
+fn rename(input: &ItemStack, name: &str) -> Option<(ItemStack, i32)> {
+    let old_name = input.name().map(|name| name.to_string());
+
+    let result = if name.trim().is_empty() {
+        old_name?;
+
+        let mut result = input.clone();
+        remove_name(&mut result);
+        result
+    } else if old_name.as_deref() == Some(name) {
+        return None;
+    } else {
+        input.clone().with_name(name.to_owned())
+    };
+
+    // Renaming costs one level on top of the prior work penalty, but is never
+    // too expensive.
+    let level_cost = repair_cost(input).saturating_add(1).min(39);
+
+    Some((result, level_cost))
+}
+
+fn remove_name(stack: &mut ItemStack) {
+    let Some(nbt) = &mut stack.nbt else {
+        return;
+    };
+
+    if let Some(Value::Compound(display)) = nbt.get_mut("display") {
+        display.remove("Name");
+
+        if display.is_empty() {
+            nbt.remove("display");
+        }
+    }
+
+    if nbt.is_empty() {
+        stack.nbt = None;
+    }
+}
+
+/// Gets the prior work penalty of the item, which is added to the level cost
+/// of using it in an anvil.
+fn repair_cost(stack: &ItemStack) -> i32 {
+    match stack.nbt.as_ref().and_then(|nbt| nbt.get("RepairCost")) {
+        Some(Value::Int(cost)) => *cost,
+        _ => 0,
+    }
+}
+
+/// Removes the characters vanilla doesn't allow in item names.
+pub(crate) fn strip_invalid_chars(name: &str) -> String {
+    name.chars()
+        .filter(|&c| c != '§' && c >= ' ' && c != '\x7f')
+        .collect()
+}
+
+/// Sent when a client types a name into the text field of the anvil screen.
+/// The name has already been set in the [`AnvilState`] of the anvil.
+#[derive(Clone, Debug)]
+pub struct RenameItem {
+    pub client: Entity,
+    pub name: String,
+}
+
+/// Sent after the result of an anvil was computed because its inputs or name
+/// changed. The result slot and [`AnvilState::level_cost`] rename the first
+/// input at this point.
+///
+/// To combine items in other ways, such as merging enchantments, set the
+/// result slot and the level cost when reading this event. The level cost is
+/// still sent to clients by this crate.
+#[derive(Clone, Debug)]
+pub struct UpdateAnvilResult {
+    /// The entity with the anvil inventory.
+    pub inventory: Entity,
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_core::item::ItemKind;
+    use valence_nbt::compound;
+
+    use super::*;
+
+    #[test]
+    fn rename_costs() {
+        let sword = ItemStack::new(ItemKind::DiamondSword, 1, None);
+
+        let (result, cost) = rename(&sword, "Excalibur").unwrap();
+        assert_eq!(result, sword.clone().with_name("Excalibur"));
+        assert_eq!(cost, 1);
+
+        // Nothing changes.
+        assert_eq!(rename(&result, "Excalibur"), None);
+        assert_eq!(rename(&sword, " "), None);
+
+        let (unnamed, _) = rename(&result, "").unwrap();
+        assert_eq!(unnamed, sword);
+
+        let worked = sword.with_nbt(compound! { "RepairCost" => 50 });
+        assert_eq!(rename(&worked, "Excalibur").unwrap().1, 39);
+    }
+
+    #[test]
+    fn result_computed_once() {
+        let sword = ItemStack::new(ItemKind::DiamondSword, 1, None);
+        let mut state = AnvilState {
+            item_name: "Excalibur".into(),
+            ..Default::default()
+        };
+
+        assert!(state.is_outdated([Some(&sword), None]));
+        assert!(state.compute_result([Some(&sword), None]).is_some());
+        assert_eq!(state.level_cost, 1);
+        assert!(!state.is_outdated([Some(&sword), None]));
+
+        state.item_name.clear();
+        assert!(state.is_outdated([Some(&sword), None]));
+        assert_eq!(state.compute_result([Some(&sword), None]), None);
+        assert_eq!(state.level_cost, 0);
+
+        assert_eq!(state.compute_result([Some(&sword), Some(&sword)]), None);
+    }
+
+    #[test]
+    fn invalid_name_chars() {
+        assert_eq!(strip_invalid_chars("§cRed\n\x7fName"), "cRedName");
+    }
+}
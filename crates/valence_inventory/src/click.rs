@@ -0,0 +1,3017 @@
+//! Server-side simulation of inventory clicks. The vanilla client predicts the
+//! result of its clicks and reports the slots it changed, so the simulation
+//! needs to produce exactly the same results to keep the client in sync.
+
+use std::ops::Range;
+
+use valence_core::enchantment::Enchantment;
+use valence_core::item::{ItemKind, ItemStack};
+use valence_core::packet::c2s::play::click_slot::ClickMode;
+use valence_core::packet::c2s::play::ClickSlotC2s;
+use valence_nbt::{Compound, Value};
+
+use super::merchant::{find_trade, Trade};
+use super::recipe::{crafting_grid, Recipe, RecipeKind, RecipeRegistry};
+use super::{Inventory, InventoryKind, PLAYER_INVENTORY_MAIN_SLOTS_COUNT};
+
+/// The slot index used for clicks outside of the window.
+const OUTSIDE_SLOT_IDX: i16 = -999;
+
+/// The slot of the offhand in the player inventory.
+pub(super) const OFFHAND_SLOT: u16 = 45;
+
+/// The drag ("quick craft") the client is in the middle of. Dragging takes
+/// several packets: one to start, one for each slot dragged over, and one to
+/// distribute the items.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub(super) struct DragState {
+    /// The mouse button of the drag, or `None` if the client isn't dragging.
+    button: Option<DragButton>,
+    /// The window slots the client dragged over.
+    slots: Vec<u16>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum DragButton {
+    /// Splits the cursor item evenly between the slots.
+    Left,
+    /// Puts one item in each slot.
+    Right,
+    /// Puts a full stack in each slot. Only in creative mode.
+    Middle,
+}
+
+/// The result of a click, as the vanilla client predicts it.
+#[derive(Clone, PartialEq, Debug)]
+pub(super) struct ClickOutcome {
+    /// The window slots changed by the click and their new contents.
+    pub(super) slots: Vec<(u16, Option<ItemStack>)>,
+    /// The new offhand item of the player, if it changed while the offhand is
+    /// not part of the window.
+    pub(super) offhand: Option<Option<ItemStack>>,
+    pub(super) cursor: Option<ItemStack>,
+    /// The item stack thrown out of the window, and the window slot it was
+    /// thrown from.
+    pub(super) dropped: Option<(Option<u16>, ItemStack)>,
+    pub(super) drag: DragState,
+    /// The indices of the merchant trades used by the click, in order.
+    pub(super) trades: Vec<usize>,
+    /// Whether the result of an anvil was taken.
+    pub(super) used_anvil: bool,
+    /// A result slot which the client computes itself after the click, in ways
+    /// which aren't simulated. The server computes and sends its own result
+    /// instead.
+    pub(super) recomputed_slot: Option<u16>,
+    /// The window slots changed by crafting the recipe in the crafting grid
+    /// again after its result was shift clicked. The client only predicts the
+    /// first craft, so these slots are sent to it.
+    pub(super) crafted_slots: Vec<(u16, Option<ItemStack>)>,
+}
+
+impl ClickOutcome {
+    /// Gets the new contents of a window slot, or `None` if it didn't change.
+    pub(super) fn slot(&self, idx: u16) -> Option<Option<&ItemStack>> {
+        self.slots
+            .iter()
+            .find(|(i, _)| *i == idx)
+            .map(|(_, stack)| stack.as_ref())
+    }
+}
+
+/// The state of a window which decides the outcome of clicks, besides the
+/// slots.
+#[derive(Copy, Clone, Default, Debug)]
+pub(super) struct WindowState<'a> {
+    /// The trades of a merchant window.
+    pub(super) trades: &'a [Trade],
+    pub(super) selected_trade: usize,
+    /// The cost in levels of taking the result of an anvil window.
+    pub(super) level_cost: i32,
+    /// The number of items of the second input of an anvil window used up by
+    /// taking the result, or `0` for all of them.
+    pub(super) repair_item_usage: u8,
+    /// The experience level of the player.
+    pub(super) experience_level: i32,
+    /// The recipes for crafting the result of a crafting grid again after it
+    /// was shift clicked.
+    pub(super) recipes: Option<&'a RecipeRegistry>,
+    /// The hotbar slot (0 to 8) of the held item of the player.
+    pub(super) held_item: u8,
+}
+
+/// Simulates the click in `packet` on the window made of `player_inventory`
+/// and `open_inventory`. The packet must have been checked to be well formed.
+///
+/// Returns the possible outcomes of the click. There is more than one if the
+/// outcome depends on recipes, which the server doesn't know, and none if the
+/// outcome can't be predicted, such as in windows without a [`SlotGroup`]
+/// layout.
+pub(super) fn simulate_click(
+    packet: &ClickSlotC2s,
+    player_inventory: &Inventory,
+    open_inventory: Option<&Inventory>,
+    cursor_item: Option<&ItemStack>,
+    drag: &DragState,
+    state: WindowState,
+    creative: bool,
+) -> Vec<ClickOutcome> {
+    let mut outcomes = vec![];
+
+    for smeltable in [false, true] {
+        let Some(mut window) = ClickWindow::new(
+            player_inventory,
+            open_inventory,
+            cursor_item,
+            state,
+            creative,
+            smeltable,
+        ) else {
+            break;
+        };
+        let mut drag = drag.clone();
+
+        let clicked = window.click(packet, &mut drag).is_some();
+        let checked_smeltable = window.checked_smeltable;
+
+        if clicked {
+            outcomes.push(window.finish(player_inventory, open_inventory, drag));
+        }
+
+        if !checked_smeltable {
+            break;
+        }
+    }
+
+    outcomes
+}
+
+/// Simulates selecting the trade at `index` in a merchant window, which moves
+/// the items in the input slots back to the player inventory and fills the
+/// input slots with the items of the trade.
+///
+/// Returns `None` if the window is not a merchant window.
+pub(super) fn simulate_select_trade(
+    player_inventory: &Inventory,
+    open_inventory: &Inventory,
+    cursor_item: Option<&ItemStack>,
+    trades: &[Trade],
+    index: usize,
+) -> Option<ClickOutcome> {
+    if open_inventory.kind != InventoryKind::Merchant {
+        return None;
+    }
+
+    let state = WindowState {
+        trades,
+        selected_trade: index,
+        ..Default::default()
+    };
+
+    let mut window = ClickWindow::new(
+        player_inventory,
+        Some(open_inventory),
+        cursor_item,
+        state,
+        false,
+        false,
+    )?;
+
+    window.select_trade(index);
+
+    Some(window.finish(player_inventory, Some(open_inventory), DragState::default()))
+}
+
+/// Places the ingredients of a crafting recipe into the crafting grid of the
+/// window, like clicking the recipe in the recipe book. The items in the grid
+/// are moved back to the player inventory first. As many items as possible
+/// are placed if `make_all` is set, otherwise one more craft than the grid
+/// already holds.
+///
+/// Returns `None` if the window has no crafting grid or the items in the grid
+/// don't fit into the player inventory. Otherwise returns the outcome, and
+/// whether the player had the ingredients.
+pub(super) fn simulate_place_recipe(
+    player_inventory: &Inventory,
+    open_inventory: Option<&Inventory>,
+    recipe: &Recipe,
+    recipes: &RecipeRegistry,
+    held_item: u8,
+    make_all: bool,
+) -> Option<(ClickOutcome, bool)> {
+    let state = WindowState {
+        recipes: Some(recipes),
+        held_item,
+        ..Default::default()
+    };
+
+    let mut window = ClickWindow::new(player_inventory, open_inventory, None, state, false, false)?;
+
+    let placed = window.place_recipe(recipe, make_all)?;
+
+    Some((
+        window.finish(player_inventory, open_inventory, DragState::default()),
+        placed,
+    ))
+}
+
+/// What the slots of a [`SlotGroup`] are for. This decides which items the
+/// slots accept, and where shift clicking moves items to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum SlotKind {
+    /// Slots which accept any item, such as the slots of a chest.
+    Storage,
+    /// The output of the crafting grid, which items can only be taken from.
+    /// Taking items uses up one item in each slot of the crafting grid.
+    CraftingResult,
+    CraftingGrid,
+    /// Armor slots, which only accept armor of their type.
+    Armor,
+    Offhand,
+    SmeltingInput,
+    /// The fuel slot of a furnace, which only accepts fuel and buckets.
+    SmeltingFuel,
+    /// The output of a furnace, which items can only be taken from.
+    SmeltingResult,
+    /// The bottle slots of a brewing stand, which hold one potion each.
+    Potion,
+    BrewingIngredient,
+    /// The fuel slot of a brewing stand, which only accepts blaze powder.
+    BrewingFuel,
+    /// The slots of a merchant window the player pays for trades with.
+    MerchantInput,
+    /// The output of the trade paid for by the merchant inputs. Taking items
+    /// uses up the price of the trade.
+    MerchantResult,
+    AnvilInput,
+    /// The output of an anvil, which costs experience levels to take. Taking
+    /// it uses up the inputs.
+    AnvilResult,
+    /// The part of the player inventory which isn't the hotbar.
+    PlayerMain,
+    PlayerHotbar,
+}
+
+/// A range of window slots which are used for the same thing.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct SlotGroup {
+    kind: SlotKind,
+    slots: Range<u16>,
+}
+
+/// Gets the slot groups of a window, or `None` if clicks in the window are not
+/// simulated.
+fn window_layout(kind: InventoryKind) -> Option<Vec<SlotGroup>> {
+    let group = |kind, slots| SlotGroup { kind, slots };
+
+    let mut layout = match kind {
+        InventoryKind::Player => {
+            return Some(vec![
+                group(SlotKind::CraftingResult, 0..1),
+                group(SlotKind::CraftingGrid, 1..5),
+                group(SlotKind::Armor, 5..9),
+                group(SlotKind::PlayerMain, 9..36),
+                group(SlotKind::PlayerHotbar, 36..45),
+                group(SlotKind::Offhand, 45..46),
+            ])
+        }
+        InventoryKind::Generic9x1
+        | InventoryKind::Generic9x2
+        | InventoryKind::Generic9x3
+        | InventoryKind::Generic9x4
+        | InventoryKind::Generic9x5
+        | InventoryKind::Generic9x6
+        | InventoryKind::Generic3x3
+        | InventoryKind::Hopper
+        | InventoryKind::ShulkerBox => {
+            vec![group(SlotKind::Storage, 0..kind.slot_count() as u16)]
+        }
+        InventoryKind::Crafting => vec![
+            group(SlotKind::CraftingResult, 0..1),
+            group(SlotKind::CraftingGrid, 1..10),
+        ],
+        InventoryKind::Furnace | InventoryKind::BlastFurnace | InventoryKind::Smoker => vec![
+            group(SlotKind::SmeltingInput, 0..1),
+            group(SlotKind::SmeltingFuel, 1..2),
+            group(SlotKind::SmeltingResult, 2..3),
+        ],
+        InventoryKind::BrewingStand => vec![
+            group(SlotKind::Potion, 0..3),
+            group(SlotKind::BrewingIngredient, 3..4),
+            group(SlotKind::BrewingFuel, 4..5),
+        ],
+        InventoryKind::Merchant => vec![
+            group(SlotKind::MerchantInput, 0..2),
+            group(SlotKind::MerchantResult, 2..3),
+        ],
+        InventoryKind::Anvil => vec![
+            group(SlotKind::AnvilInput, 0..2),
+            group(SlotKind::AnvilResult, 2..3),
+        ],
+        _ => return None,
+    };
+
+    let start = kind.slot_count() as u16;
+
+    layout.push(group(SlotKind::PlayerMain, start..start + 27));
+    layout.push(group(SlotKind::PlayerHotbar, start + 27..start + 36));
+
+    Some(layout)
+}
+
+struct ClickWindow<'a> {
+    kind: InventoryKind,
+    layout: Vec<SlotGroup>,
+    /// The slots of the window.
+    slots: Vec<Option<ItemStack>>,
+    /// The offhand of the player, if it is not part of the window.
+    offhand: Option<ItemStack>,
+    cursor: Option<ItemStack>,
+    dropped: Option<(Option<u16>, ItemStack)>,
+    creative: bool,
+    /// Whether items shift clicked into a furnace are assumed to be smeltable.
+    smeltable: bool,
+    /// If the click depended on whether an item is smeltable.
+    checked_smeltable: bool,
+    /// If the click did something which isn't simulated, such as putting
+    /// items back into the player inventory outside of the window.
+    unpredictable: bool,
+    /// The trades of a merchant window, with the uses from this click.
+    trades: Vec<Trade>,
+    selected_trade: usize,
+    /// The trade paid for by the merchant inputs.
+    current_trade: Option<usize>,
+    /// The trades used by the click.
+    used_trades: Vec<usize>,
+    level_cost: i32,
+    repair_item_usage: u8,
+    experience_level: i32,
+    used_anvil: bool,
+    recomputed_slot: Option<u16>,
+    recipes: Option<&'a RecipeRegistry>,
+    held_item: u8,
+    crafted_slots: Vec<(u16, Option<ItemStack>)>,
+}
+
+impl<'a> ClickWindow<'a> {
+    fn new(
+        player_inventory: &Inventory,
+        open_inventory: Option<&Inventory>,
+        cursor_item: Option<&ItemStack>,
+        state: WindowState<'a>,
+        creative: bool,
+        smeltable: bool,
+    ) -> Option<Self> {
+        let (kind, slots, offhand) = match open_inventory {
+            Some(open_inventory) => {
+                let slots = open_inventory
+                    .slot_slice()
+                    .iter()
+                    .chain(&player_inventory.slot_slice()[9..OFFHAND_SLOT as usize])
+                    .cloned()
+                    .collect();
+
+                (
+                    open_inventory.kind,
+                    slots,
+                    player_inventory.slot(OFFHAND_SLOT).cloned(),
+                )
+            }
+            None => (
+                InventoryKind::Player,
+                player_inventory.slot_slice().to_vec(),
+                None,
+            ),
+        };
+
+        let mut window = Self {
+            kind,
+            layout: window_layout(kind)?,
+            slots,
+            offhand,
+            cursor: cursor_item.cloned(),
+            dropped: None,
+            creative,
+            smeltable,
+            checked_smeltable: false,
+            unpredictable: false,
+            trades: state.trades.to_vec(),
+            selected_trade: state.selected_trade,
+            current_trade: None,
+            used_trades: vec![],
+            level_cost: state.level_cost,
+            repair_item_usage: state.repair_item_usage,
+            experience_level: state.experience_level,
+            used_anvil: false,
+            recomputed_slot: None,
+            recipes: state.recipes,
+            held_item: state.held_item,
+            crafted_slots: vec![],
+        };
+
+        if kind == InventoryKind::Merchant {
+            window.current_trade = window.find_current_trade();
+        }
+
+        Some(window)
+    }
+
+    fn finish(
+        self,
+        player_inventory: &Inventory,
+        open_inventory: Option<&Inventory>,
+        drag: DragState,
+    ) -> ClickOutcome {
+        let old_slots = match open_inventory {
+            Some(open_inventory) => open_inventory
+                .slot_slice()
+                .iter()
+                .chain(&player_inventory.slot_slice()[9..OFFHAND_SLOT as usize])
+                .collect::<Vec<_>>(),
+            None => player_inventory.slot_slice().iter().collect(),
+        };
+
+        let slots = self
+            .slots
+            .into_iter()
+            .zip(old_slots)
+            .enumerate()
+            .filter(|(_, (new, old))| new != *old)
+            .map(|(idx, (new, _))| (idx as u16, new))
+            .collect();
+
+        let offhand = match open_inventory {
+            Some(_) if self.offhand.as_ref() != player_inventory.slot(OFFHAND_SLOT) => {
+                Some(self.offhand)
+            }
+            _ => None,
+        };
+
+        ClickOutcome {
+            slots,
+            offhand,
+            cursor: self.cursor,
+            dropped: self.dropped,
+            drag,
+            trades: self.used_trades,
+            used_anvil: self.used_anvil,
+            recomputed_slot: self.recomputed_slot,
+            crafted_slots: self.crafted_slots,
+        }
+    }
+
+    /// Returns `None` if the result of the click can't be predicted.
+    fn click(&mut self, packet: &ClickSlotC2s, drag: &mut DragState) -> Option<()> {
+        let inputs = self.inputs();
+
+        let idx = packet.slot_idx as u16;
+        let crafted = match &self.slots.get(idx as usize) {
+            Some(Some(stack))
+                if packet.mode == ClickMode::ShiftClick
+                    && self.slot_kind(idx) == SlotKind::CraftingResult =>
+            {
+                Some(stack.item)
+            }
+            _ => None,
+        };
+
+        self.click_slot(packet, drag);
+
+        // Changing the inputs of a merchant, an anvil or a crafting grid changes the
+        // result.
+        if self.inputs() != inputs {
+            match self.kind {
+                InventoryKind::Merchant => self.update_trade_result(),
+                InventoryKind::Anvil if !self.used_anvil => {
+                    self.recomputed_slot = Some(self.group(SlotKind::AnvilResult).start);
+                }
+                InventoryKind::Player | InventoryKind::Crafting => {
+                    self.recomputed_slot = Some(self.group(SlotKind::CraftingResult).start);
+                }
+                _ => {}
+            }
+        }
+
+        if self.unpredictable {
+            return None;
+        }
+
+        if let Some(item) = crafted {
+            self.craft_again(idx, item);
+        }
+
+        Some(())
+    }
+
+    /// Applies the click to the slots of the window.
+    fn click_slot(&mut self, packet: &ClickSlotC2s, drag: &mut DragState) {
+        if packet.mode == ClickMode::Drag {
+            self.drag(packet, drag);
+            return;
+        }
+
+        if drag.button.is_some() {
+            // Any other click cancels the drag.
+            *drag = DragState::default();
+            return;
+        }
+
+        let slot_idx = packet.slot_idx;
+        let idx = slot_idx as u16;
+
+        match packet.mode {
+            ClickMode::Click | ClickMode::ShiftClick if slot_idx == OUTSIDE_SLOT_IDX => {
+                let Some(cursor) = self.cursor.take() else {
+                    return;
+                };
+
+                let dropped = if packet.button == 0 {
+                    count(&cursor)
+                } else {
+                    1
+                };
+
+                self.cursor = with_count(&cursor, count(&cursor) - dropped);
+                self.dropped = with_count(&cursor, dropped).map(|stack| (None, stack));
+            }
+            ClickMode::Click => self.pickup(idx, packet.button == 0),
+            ClickMode::ShiftClick => {
+                if !self.can_take(idx) {
+                    return;
+                }
+
+                while let Some(moved) = self.quick_move(idx) {
+                    if !matches!(&self.slots[idx as usize], Some(stack) if stack.item == moved) {
+                        break;
+                    }
+                }
+            }
+            ClickMode::Hotbar => self.swap(idx, packet.button as u16),
+            ClickMode::CreativeMiddleClick => {
+                if self.creative && self.cursor.is_none() {
+                    if let Some(stack) = &self.slots[idx as usize] {
+                        self.cursor = with_count(stack, max_count(stack));
+                    }
+                }
+            }
+            ClickMode::DropKey => {
+                if self.cursor.is_none() {
+                    if let Some(stack) = &self.slots[idx as usize] {
+                        let count = if packet.button == 0 { 1 } else { count(stack) };
+
+                        if let Some(stack) = self.take(idx, count, i32::MAX) {
+                            self.dropped = Some((Some(idx), stack));
+                        }
+                    }
+                }
+            }
+            ClickMode::DoubleClick => self.pickup_all(idx, packet.button == 0),
+            ClickMode::Drag => unreachable!(),
+        }
+    }
+
+    fn drag(&mut self, packet: &ClickSlotC2s, drag: &mut DragState) {
+        let stage = packet.button & 3;
+        let button = match (packet.button >> 2) & 3 {
+            0 => DragButton::Left,
+            1 => DragButton::Right,
+            _ => DragButton::Middle,
+        };
+
+        let in_progress = drag.button.is_some();
+
+        // The stages must go start, add slots, end.
+        if (stage == 0 && in_progress) || (stage != 0 && !in_progress) || self.cursor.is_none() {
+            *drag = DragState::default();
+            return;
+        }
+
+        match stage {
+            0 => {
+                if button != DragButton::Middle || self.creative {
+                    drag.button = Some(button);
+                    drag.slots.clear();
+                }
+            }
+            1 => {
+                let idx = packet.slot_idx as u16;
+                let cursor = self.cursor.as_ref().unwrap();
+
+                if self.can_drag_into(idx, cursor)
+                    && (drag.button == Some(DragButton::Middle)
+                        || count(cursor) > drag.slots.len() as i32)
+                    && !drag.slots.contains(&idx)
+                {
+                    drag.slots.push(idx);
+                }
+            }
+            _ => {
+                let drag = std::mem::take(drag);
+                let drag_button = drag.button.unwrap();
+
+                match drag.slots.as_slice() {
+                    [] => {}
+                    &[idx] => {
+                        // Dragging over a single slot is a normal click.
+                        match drag_button {
+                            DragButton::Left => self.pickup(idx, true),
+                            DragButton::Right => self.pickup(idx, false),
+                            DragButton::Middle => {}
+                        }
+                    }
+                    slots => {
+                        let cursor = self.cursor.clone().unwrap();
+                        let mut remaining = count(&cursor);
+
+                        for &idx in slots {
+                            if !self.can_drag_into(idx, &cursor)
+                                || (drag_button != DragButton::Middle
+                                    && count(&cursor) < slots.len() as i32)
+                            {
+                                continue;
+                            }
+
+                            let old_count = self.slots[idx as usize].as_ref().map_or(0, count);
+                            let per_slot = match drag_button {
+                                DragButton::Left => count(&cursor) / slots.len() as i32,
+                                DragButton::Right => 1,
+                                DragButton::Middle => max_count(&cursor),
+                            };
+                            let new_count =
+                                (per_slot + old_count).min(self.max_count_for(idx, &cursor));
+
+                            remaining -= new_count - old_count;
+                            self.slots[idx as usize] = with_count(&cursor, new_count);
+                        }
+
+                        self.cursor = with_count(&cursor, remaining);
+                    }
+                }
+            }
+        }
+    }
+
+    fn can_drag_into(&self, idx: u16, cursor: &ItemStack) -> bool {
+        // Full stacks are allowed here. They just don't receive anything.
+        let fits = match &self.slots[idx as usize] {
+            Some(stack) if can_combine(stack, cursor) => count(stack) <= max_count(cursor),
+            Some(_) => false,
+            None => true,
+        };
+
+        fits && self.can_insert(idx, cursor)
+    }
+
+    /// A left or right click on a slot.
+    fn pickup(&mut self, idx: u16, left: bool) {
+        let slot = self.slots[idx as usize].clone();
+        let cursor = self.cursor.clone();
+
+        match (slot, cursor) {
+            (None, None) => {}
+            (None, Some(cursor)) => {
+                let count = if left { count(&cursor) } else { 1 };
+                self.cursor = self.insert(idx, cursor, count);
+            }
+            (Some(slot), None) => {
+                if self.can_take(idx) {
+                    let count = if left {
+                        count(&slot)
+                    } else {
+                        (count(&slot) + 1) / 2
+                    };
+
+                    self.cursor = self.take(idx, count, i32::MAX);
+                }
+            }
+            (Some(slot), Some(cursor)) => {
+                if !self.can_take(idx) {
+                    return;
+                }
+
+                if self.can_insert(idx, &cursor) {
+                    if can_combine(&slot, &cursor) {
+                        let count = if left { count(&cursor) } else { 1 };
+                        self.cursor = self.insert(idx, cursor, count);
+                    } else if count(&cursor) <= self.max_count_for(idx, &cursor) {
+                        // Swap the slot and the cursor.
+                        self.slots[idx as usize] = Some(cursor);
+                        self.cursor = Some(slot);
+                    }
+                } else if can_combine(&slot, &cursor) {
+                    let max = max_count(&cursor) - count(&cursor);
+
+                    if let Some(taken) = self.take(idx, count(&slot), max) {
+                        self.cursor = with_count(&cursor, count(&cursor) + count(&taken));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Puts up to `max` items of `stack` into the slot, and returns what is
+    /// left of `stack`.
+    fn insert(&mut self, idx: u16, stack: ItemStack, max: i32) -> Option<ItemStack> {
+        if !self.can_insert(idx, &stack) {
+            return Some(stack);
+        }
+
+        let old_count = self.slots[idx as usize].as_ref().map_or(0, count);
+        // This is negative for stacks larger than the slot allows, which takes
+        // items out of the slot.
+        let moved = max
+            .min(count(&stack))
+            .min(self.max_count_for(idx, &stack) - old_count);
+
+        match &self.slots[idx as usize] {
+            None => {
+                self.slots[idx as usize] = with_count(&stack, moved);
+            }
+            Some(slot) if can_combine(slot, &stack) => {
+                self.slots[idx as usize] = with_count(slot, old_count + moved);
+            }
+            Some(_) => return Some(stack),
+        }
+
+        with_count(&stack, count(&stack) - moved)
+    }
+
+    /// Takes up to `count` items out of the slot, if the slot has no more than
+    /// `max` items.
+    fn take(&mut self, idx: u16, count: i32, max: i32) -> Option<ItemStack> {
+        if !self.can_take(idx) {
+            return None;
+        }
+
+        let stack = self.slots[idx as usize].clone()?;
+
+        // Slots which don't accept their own items can't be taken from partially.
+        if !self.can_insert(idx, &stack) && max < self::count(&stack) {
+            return None;
+        }
+
+        let taken = count.min(max).min(self::count(&stack));
+
+        if taken <= 0 {
+            return None;
+        }
+
+        self.slots[idx as usize] = with_count(&stack, self::count(&stack) - taken);
+        self.on_take(idx);
+
+        with_count(&stack, taken)
+    }
+
+    /// Called after items are taken out of the slot by the player.
+    fn on_take(&mut self, idx: u16) {
+        match self.slot_kind(idx) {
+            SlotKind::CraftingResult => {}
+            SlotKind::MerchantResult => {
+                self.pay_for_trade();
+                return;
+            }
+            SlotKind::AnvilResult => {
+                self.use_anvil();
+                return;
+            }
+            _ => return,
+        }
+
+        // Crafting uses up one item in each slot of the crafting grid.
+        for idx in self.group(SlotKind::CraftingGrid) {
+            let Some(stack) = self.slots[idx as usize].clone() else {
+                continue;
+            };
+
+            self.slots[idx as usize] = with_count(&stack, count(&stack) - 1);
+
+            if let Some(remainder) = stack.item.recipe_remainder() {
+                let remainder = ItemStack::new(remainder, 1, None);
+
+                match &self.slots[idx as usize] {
+                    None => self.slots[idx as usize] = Some(remainder),
+                    Some(slot) if slot.item == remainder.item && slot.nbt.is_none() => {
+                        self.slots[idx as usize] = with_count(slot, count(slot) + 1);
+                    }
+                    _ => {
+                        if !self.add_to_player_inventory(&remainder) {
+                            // The remainder would be dropped.
+                            self.unpredictable = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Crafts the recipe in the crafting grid again and again after its result
+    /// was shift clicked, like the server does until the result changes. The
+    /// client doesn't know the next result and stops after the first craft, so
+    /// the slots changed by this are kept apart from the predicted ones.
+    fn craft_again(&mut self, idx: u16, item: ItemKind) {
+        let (Some(recipes), Some((grid, width))) = (self.recipes, crafting_grid(self.kind)) else {
+            return;
+        };
+
+        let predicted = self.slots.clone();
+
+        while let Some(result) = recipes
+            .find_crafting(&self.slots[grid.start as usize..grid.end as usize], width)
+            .map(|(_, recipe)| recipe.result.clone())
+            .filter(|result| result.item == item)
+        {
+            let (slots, offhand) = (self.slots.clone(), self.offhand.clone());
+
+            self.slots[idx as usize] = Some(result);
+
+            // Crafts which would drop items, or change the offhand outside of the window,
+            // are left out.
+            if self.quick_move(idx).is_none() || self.unpredictable || self.offhand != offhand {
+                self.slots = slots;
+                self.offhand = offhand;
+                self.unpredictable = false;
+                break;
+            }
+        }
+
+        // The result slot is computed again by the server afterwards.
+        self.slots[idx as usize] = predicted[idx as usize].clone();
+
+        self.crafted_slots = predicted
+            .iter()
+            .zip(&self.slots)
+            .enumerate()
+            .filter(|(_, (predicted, new))| predicted != new)
+            .map(|(idx, (_, new))| (idx as u16, new.clone()))
+            .collect();
+
+        self.slots = predicted;
+    }
+
+    /// Moves the items in the crafting grid back to the player inventory, then
+    /// places the ingredients of the recipe from the player inventory.
+    ///
+    /// Returns `None` if the items in the grid don't fit into the player
+    /// inventory, otherwise whether the ingredients were placed.
+    fn place_recipe(&mut self, recipe: &Recipe, make_all: bool) -> Option<bool> {
+        let recipes = self.recipes?;
+        let (grid, width) = crafting_grid(self.kind)?;
+        let grid_slots = &self.slots[grid.start as usize..grid.end as usize];
+
+        // Clicking the recipe again adds one more craft to the grid.
+        let crafts_in_grid = recipes
+            .matches_grid(recipe, grid_slots, width)
+            .then(|| grid_slots.iter().flatten().map(count).min())
+            .flatten()
+            .unwrap_or(0);
+
+        for idx in grid.clone() {
+            if let Some(stack) = self.slots[idx as usize].take() {
+                if !self.add_to_player_inventory(&stack) {
+                    return None;
+                }
+            }
+        }
+
+        let mut amount = if make_all { 64 } else { crafts_in_grid + 1 };
+
+        // Fewer crafts are placed if the player doesn't have enough items.
+        while amount > 0 {
+            let slots = self.slots.clone();
+
+            if self.fill_grid(recipe, amount) {
+                return Some(true);
+            }
+
+            self.slots = slots;
+            amount -= 1;
+        }
+
+        Some(false)
+    }
+
+    /// Puts `amount` items of each ingredient of the recipe into the crafting
+    /// grid, taken from the player inventory. Returns `false` if the player
+    /// doesn't have the items.
+    fn fill_grid(&mut self, recipe: &Recipe, amount: i32) -> bool {
+        let (Some(recipes), Some((grid, width))) = (self.recipes, crafting_grid(self.kind)) else {
+            return false;
+        };
+
+        let height = grid.len() / width;
+
+        // The grid positions of the ingredients. Patterns are put into the top left
+        // corner.
+        let ingredients: Vec<_> = match &recipe.kind {
+            RecipeKind::Shaped {
+                width: recipe_width,
+                height: recipe_height,
+                ingredients,
+            } => {
+                let recipe_width = *recipe_width as usize;
+
+                if recipe_width > width || *recipe_height as usize > height {
+                    return false;
+                }
+
+                ingredients
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, ingredient)| {
+                        let pos = i / recipe_width * width + i % recipe_width;
+                        ingredient.as_ref().map(|ingredient| (pos, ingredient))
+                    })
+                    .collect()
+            }
+            RecipeKind::Shapeless { ingredients } if ingredients.len() <= grid.len() => {
+                ingredients.iter().enumerate().collect()
+            }
+            _ => return false,
+        };
+
+        // Items with NBT, such as enchanted or named items, are not used.
+        let usable = |stack: &ItemStack| nbt_matches(stack.nbt.as_ref(), None);
+
+        for (pos, ingredient) in ingredients {
+            let items = recipes.ingredient_items(ingredient);
+
+            // Every slot gets items of one kind.
+            let available = |item: ItemKind| -> i32 {
+                (0..36)
+                    .filter_map(|i| self.inventory_stack(i))
+                    .filter(|stack| stack.item == item && usable(stack))
+                    .map(|stack| count(&stack))
+                    .sum()
+            };
+
+            let Some(item) = (0..36)
+                .filter_map(|i| self.inventory_stack(i))
+                .map(|stack| stack.item)
+                .find(|&item| {
+                    items.contains(&item)
+                        && amount <= item.max_stack() as i32
+                        && available(item) >= amount
+                })
+            else {
+                return false;
+            };
+
+            let mut remaining = amount;
+
+            for i in 0..36 {
+                match self.inventory_stack(i) {
+                    Some(stack) if stack.item == item && usable(&stack) => {
+                        let taken = remaining.min(count(&stack));
+
+                        self.set_inventory_stack(i, with_count(&stack, count(&stack) - taken));
+                        remaining -= taken;
+
+                        if remaining == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            self.slots[grid.start as usize + pos] = Some(ItemStack::new(item, amount as u8, None));
+        }
+
+        true
+    }
+
+    /// Adds the stack to the player inventory like vanilla adds items the
+    /// player gets, such as the leftovers of crafting. Returns `false` if not
+    /// all of it fits.
+    fn add_to_player_inventory(&mut self, stack: &ItemStack) -> bool {
+        let mut remaining = count(stack);
+
+        while remaining > 0 {
+            // Stacks with room are filled first, starting with the held item and the
+            // offhand, then the first empty slot is used.
+            let with_room = [self.held_item.into(), 40]
+                .into_iter()
+                .chain(0..36)
+                .find(|&i| {
+                    matches!(
+                        self.inventory_stack(i),
+                        Some(slot) if can_combine(&slot, stack) && count(&slot) < max_count(&slot)
+                    )
+                });
+
+            let Some(i) =
+                with_room.or_else(|| (0..36).find(|&i| self.inventory_stack(i).is_none()))
+            else {
+                return false;
+            };
+
+            let old_count = self.inventory_stack(i).map_or(0, |slot| count(&slot));
+            let added = remaining.min(max_count(stack) - old_count);
+
+            self.set_inventory_stack(i, with_count(stack, old_count + added));
+            remaining -= added;
+        }
+
+        true
+    }
+
+    /// Gets a stack of the player inventory by its vanilla index: 0 to 8 for
+    /// the hotbar, 9 to 35 for the rest of the main inventory and 40 for the
+    /// offhand.
+    fn inventory_stack(&self, idx: u16) -> Option<ItemStack> {
+        match idx {
+            0..=8 | 40 => self.player_stack(idx),
+            _ => self.slots[(self.group(SlotKind::PlayerMain).start + idx - 9) as usize].clone(),
+        }
+    }
+
+    fn set_inventory_stack(&mut self, idx: u16, stack: Option<ItemStack>) {
+        match idx {
+            0..=8 | 40 => self.set_player_stack(idx, stack),
+            _ => {
+                let main = self.group(SlotKind::PlayerMain);
+                self.slots[(main.start + idx - 9) as usize] = stack;
+            }
+        }
+    }
+
+    /// Uses up the price of the trade paid for by the merchant inputs, after
+    /// its output was taken.
+    fn pay_for_trade(&mut self) {
+        let Some(trade_idx) = self.current_trade else {
+            return;
+        };
+
+        let trade = &self.trades[trade_idx];
+        let inputs = self.group(SlotKind::MerchantInput);
+        let (a, b) = (inputs.start as usize, inputs.start as usize + 1);
+
+        // The inputs can pay for the trade in either order.
+        let (first, second) =
+            if trade.matches_inputs(self.slots[a].as_ref(), self.slots[b].as_ref()) {
+                (a, b)
+            } else if trade.matches_inputs(self.slots[b].as_ref(), self.slots[a].as_ref()) {
+                (b, a)
+            } else {
+                return;
+            };
+
+        let price = count(&trade.adjusted_first_input());
+        let second_price = trade.input_two.as_ref().map_or(0, count);
+
+        if let Some(stack) = self.slots[first].clone() {
+            self.slots[first] = with_count(&stack, count(&stack) - price);
+        }
+
+        if let Some(stack) = self.slots[second].clone() {
+            self.slots[second] = with_count(&stack, count(&stack) - second_price);
+        }
+
+        self.trades[trade_idx].uses += 1;
+        self.used_trades.push(trade_idx);
+
+        self.update_trade_result();
+    }
+
+    /// Uses up the inputs of an anvil after its result was taken.
+    fn use_anvil(&mut self) {
+        let inputs = self.group(SlotKind::AnvilInput);
+        let (first, second) = (inputs.start as usize, inputs.start as usize + 1);
+
+        self.slots[first] = None;
+
+        self.slots[second] = match &self.slots[second] {
+            Some(stack) if self.repair_item_usage > 0 => {
+                with_count(stack, count(stack) - i32::from(self.repair_item_usage))
+            }
+            _ => None,
+        };
+
+        // The result is gone with the first input, including items left in the
+        // slot by taking only some of them.
+        let result = self.group(SlotKind::AnvilResult).start;
+        self.slots[result as usize] = None;
+        self.used_anvil = true;
+    }
+
+    /// Gets the contents of the slots the result of the window is computed
+    /// from, which are the merchant or anvil inputs or the crafting grid.
+    fn inputs(&self) -> Vec<Option<ItemStack>> {
+        let inputs = [
+            SlotKind::MerchantInput,
+            SlotKind::AnvilInput,
+            SlotKind::CraftingGrid,
+        ]
+        .into_iter()
+        .map(|kind| self.group(kind))
+        .find(|group| !group.is_empty())
+        .unwrap_or(0..0);
+
+        inputs.map(|idx| self.slots[idx as usize].clone()).collect()
+    }
+
+    /// Finds the trade paid for by the merchant inputs. The inputs can be in
+    /// either order.
+    fn find_current_trade(&self) -> Option<usize> {
+        if self.kind != InventoryKind::Merchant {
+            return None;
+        }
+
+        let inputs = self.group(SlotKind::MerchantInput);
+        let mut first = self.slots[inputs.start as usize].as_ref();
+        let mut second = self.slots[inputs.start as usize + 1].as_ref();
+
+        if first.is_none() {
+            first = second.take();
+        }
+
+        let first = first?;
+        let usable = |idx: &usize| !self.trades[*idx].is_disabled();
+
+        find_trade(&self.trades, self.selected_trade, Some(first), second)
+            .filter(usable)
+            .or_else(|| {
+                find_trade(&self.trades, self.selected_trade, second, Some(first)).filter(usable)
+            })
+    }
+
+    /// Puts the output of the trade paid for by the merchant inputs into the
+    /// result slot.
+    fn update_trade_result(&mut self) {
+        if self.kind != InventoryKind::Merchant {
+            return;
+        }
+
+        self.current_trade = self.find_current_trade();
+
+        let result = self.group(SlotKind::MerchantResult).start;
+        let output = self
+            .current_trade
+            .map(|idx| self.trades[idx].output.clone());
+
+        self.slots[result as usize] = output;
+    }
+
+    /// Selects a trade of a merchant window. The items in the input slots are
+    /// moved back to the player inventory, and if they all fit, the input
+    /// slots are filled with the items of the trade from the player inventory.
+    fn select_trade(&mut self, trade_idx: usize) {
+        self.selected_trade = trade_idx;
+        self.update_trade_result();
+
+        let Some(trade) = self.trades.get(trade_idx).cloned() else {
+            return;
+        };
+
+        let inputs = self.group(SlotKind::MerchantInput);
+        let player = self.group(SlotKind::PlayerMain).start..self.group(SlotKind::PlayerHotbar).end;
+
+        for idx in inputs.clone() {
+            let Some(stack) = self.slots[idx as usize].clone() else {
+                continue;
+            };
+
+            let mut remaining = count(&stack);
+
+            if !self.insert_item(&stack, &mut remaining, player.clone(), true) {
+                return;
+            }
+
+            self.slots[idx as usize] = with_count(&stack, remaining);
+            self.update_trade_result();
+        }
+
+        if inputs.clone().all(|idx| self.slots[idx as usize].is_none()) {
+            self.fill_input(inputs.start, &trade.adjusted_first_input(), player.clone());
+
+            if let Some(input) = &trade.input_two {
+                self.fill_input(inputs.start + 1, input, player);
+            }
+
+            self.update_trade_result();
+        }
+    }
+
+    /// Moves items matching `stack` from the slots in `from` into the merchant
+    /// input slot, until the input slot is full.
+    fn fill_input(&mut self, idx: u16, stack: &ItemStack, from: Range<u16>) {
+        for from_idx in from {
+            let Some(from_stack) = self.slots[from_idx as usize].clone() else {
+                continue;
+            };
+
+            if !can_combine(stack, &from_stack) {
+                continue;
+            }
+
+            let old_count = self.slots[idx as usize].as_ref().map_or(0, count);
+            let moved = (max_count(stack) - old_count).min(count(&from_stack));
+
+            self.slots[from_idx as usize] = with_count(&from_stack, count(&from_stack) - moved);
+            self.slots[idx as usize] = with_count(&from_stack, old_count + moved);
+
+            if old_count + moved >= max_count(stack) {
+                break;
+            }
+        }
+    }
+
+    /// Moves the stack in the slot to the other part of the window, like
+    /// shift clicking. Returns the kind of item that was moved.
+    fn quick_move(&mut self, idx: u16) -> Option<ItemKind> {
+        let stack = self.slots[idx as usize].clone()?;
+        let mut remaining = count(&stack);
+
+        // Only the first targets which take any items are used.
+        let moved = self
+            .quick_move_targets(idx, &stack)
+            .into_iter()
+            .any(|(range, reverse)| self.insert_item(&stack, &mut remaining, range, reverse));
+
+        if !moved {
+            return None;
+        }
+
+        if self.slot_kind(idx) == SlotKind::CraftingResult && remaining > 0 {
+            // The rest of the crafted items would be dropped.
+            self.unpredictable = true;
+        }
+
+        self.slots[idx as usize] = with_count(&stack, remaining);
+
+        if remaining == count(&stack) {
+            return None;
+        }
+
+        self.on_take(idx);
+
+        Some(stack.item)
+    }
+
+    /// Gets the slots shift clicking the stack in the slot moves it to, in
+    /// order of priority. The slots in each range are filled from the end if
+    /// the range is reversed.
+    fn quick_move_targets(&mut self, idx: u16, stack: &ItemStack) -> Vec<(Range<u16>, bool)> {
+        let from = self.slot_kind(idx);
+        let main = self.group(SlotKind::PlayerMain);
+        let hotbar = self.group(SlotKind::PlayerHotbar);
+        let player = main.start..hotbar.end;
+
+        // Moving between the main inventory and the hotbar, if nothing else
+        // takes the stack.
+        let other_part = if from == SlotKind::PlayerMain {
+            (hotbar, false)
+        } else {
+            (main, false)
+        };
+
+        match (from, self.kind) {
+            (SlotKind::CraftingResult | SlotKind::MerchantResult | SlotKind::AnvilResult, _) => {
+                vec![(player, true)]
+            }
+            (
+                SlotKind::CraftingGrid
+                | SlotKind::Armor
+                | SlotKind::SmeltingInput
+                | SlotKind::SmeltingFuel
+                | SlotKind::MerchantInput
+                | SlotKind::AnvilInput,
+                _,
+            ) => vec![(player, false)],
+            (SlotKind::PlayerMain | SlotKind::PlayerHotbar | SlotKind::Offhand, kind) => {
+                match kind {
+                    InventoryKind::Player => match equipment_slot(stack.item) {
+                        Some(slot) if self.slots[slot as usize].is_none() => {
+                            vec![(slot..slot + 1, false)]
+                        }
+                        _ if from == SlotKind::Offhand => vec![(player, false)],
+                        _ => vec![other_part],
+                    },
+                    InventoryKind::Crafting => {
+                        vec![(self.group(SlotKind::CraftingGrid), false), other_part]
+                    }
+                    InventoryKind::Furnace
+                    | InventoryKind::BlastFurnace
+                    | InventoryKind::Smoker => {
+                        // Smeltable items go into the input slot, even if they're
+                        // fuel.
+                        self.checked_smeltable = true;
+
+                        if self.smeltable {
+                            vec![(self.group(SlotKind::SmeltingInput), false)]
+                        } else if stack.item.fuel_time() > 0 {
+                            vec![(self.group(SlotKind::SmeltingFuel), false)]
+                        } else {
+                            vec![other_part]
+                        }
+                    }
+                    InventoryKind::BrewingStand => {
+                        let fuel = self.group(SlotKind::BrewingFuel);
+                        let ingredient = self.group(SlotKind::BrewingIngredient);
+
+                        if stack.item == ItemKind::BlazePowder {
+                            vec![(fuel, false), (ingredient, false)]
+                        } else if is_brewing_ingredient(stack.item) {
+                            vec![(ingredient, false)]
+                        } else if is_potion(stack.item) && count(stack) == 1 {
+                            vec![(self.group(SlotKind::Potion), false)]
+                        } else {
+                            vec![other_part]
+                        }
+                    }
+                    // Items are never shift clicked into the merchant inputs.
+                    InventoryKind::Merchant => vec![other_part],
+                    InventoryKind::Anvil => vec![(self.group(SlotKind::AnvilInput), false)],
+                    // Chests and other storage.
+                    _ => vec![(self.group(SlotKind::Storage), false)],
+                }
+            }
+            // Other container slots.
+            _ => vec![(player, true)],
+        }
+    }
+
+    /// Moves `remaining` items of `stack` into the slots in `range`, first
+    /// filling up matching stacks, then the first empty slot. Returns `true`
+    /// if anything was moved.
+    fn insert_item(
+        &mut self,
+        stack: &ItemStack,
+        remaining: &mut i32,
+        range: Range<u16>,
+        reverse: bool,
+    ) -> bool {
+        let indices: Vec<u16> = if reverse {
+            range.rev().collect()
+        } else {
+            range.collect()
+        };
+
+        let max = max_count(stack);
+        let mut moved = false;
+
+        if max > 1 {
+            for &idx in &indices {
+                if *remaining <= 0 {
+                    break;
+                }
+
+                let Some(slot) = self.slots[idx as usize].clone() else {
+                    continue;
+                };
+
+                if !can_combine(&slot, stack) {
+                    continue;
+                }
+
+                let total = count(&slot) + *remaining;
+
+                if total <= max {
+                    *remaining = 0;
+                    self.slots[idx as usize] = with_count(&slot, total);
+                    moved = true;
+                } else if count(&slot) < max {
+                    *remaining -= max - count(&slot);
+                    self.slots[idx as usize] = with_count(&slot, max);
+                    moved = true;
+                }
+            }
+        }
+
+        if *remaining > 0 {
+            for &idx in &indices {
+                if self.slots[idx as usize].is_none() && self.can_insert(idx, stack) {
+                    let count = (*remaining).min(self.max_count(idx));
+
+                    *remaining -= count;
+                    self.slots[idx as usize] = with_count(stack, count);
+                    moved = true;
+                    break;
+                }
+            }
+        }
+
+        moved
+    }
+
+    /// Swaps the slot with a slot in the hotbar or the offhand, like pressing
+    /// a number key or the swap hands key.
+    fn swap(&mut self, idx: u16, button: u16) {
+        let hotbar = self.player_stack(button);
+        let slot = self.slots[idx as usize].clone();
+
+        match (hotbar, slot) {
+            (None, None) => {}
+            (None, Some(slot)) => {
+                if self.can_take(idx) {
+                    self.slots[idx as usize] = None;
+                    self.set_player_stack(button, Some(slot));
+                    self.on_take(idx);
+                }
+            }
+            (Some(hotbar), None) => {
+                if self.can_insert(idx, &hotbar) {
+                    let max = self.max_count_for(idx, &hotbar);
+
+                    self.slots[idx as usize] = with_count(&hotbar, count(&hotbar).min(max));
+                    self.set_player_stack(button, with_count(&hotbar, count(&hotbar) - max));
+                }
+            }
+            (Some(hotbar), Some(slot)) => {
+                if self.can_take(idx) && self.can_insert(idx, &hotbar) {
+                    if count(&hotbar) > self.max_count_for(idx, &hotbar) {
+                        // The rest of the stack would be put back in the
+                        // player inventory, which isn't simulated.
+                        self.unpredictable = true;
+                        return;
+                    }
+
+                    self.set_player_stack(button, Some(slot));
+                    self.slots[idx as usize] = Some(hotbar);
+                    self.on_take(idx);
+                }
+            }
+        }
+    }
+
+    /// Collects items matching the cursor item from the window, like double
+    /// clicking.
+    fn pickup_all(&mut self, idx: u16, forward: bool) {
+        let Some(mut cursor) = self.cursor.clone() else {
+            return;
+        };
+
+        if self.slots[idx as usize].is_some() && self.can_take(idx) {
+            return;
+        }
+
+        let mut indices: Vec<u16> = (0..self.slots.len() as u16).collect();
+
+        if !forward {
+            indices.reverse();
+        }
+
+        // Full stacks are only taken if there aren't enough partial stacks.
+        for take_full in [false, true] {
+            for &idx in &indices {
+                if count(&cursor) >= max_count(&cursor) {
+                    break;
+                }
+
+                let Some(slot) = &self.slots[idx as usize] else {
+                    continue;
+                };
+
+                if !can_combine(slot, &cursor)
+                    || count(slot) > max_count(&cursor)
+                    || !self.can_take(idx)
+                    || !self.can_collect_from(idx)
+                    || (!take_full && count(slot) == max_count(slot))
+                {
+                    continue;
+                }
+
+                let max = max_count(&cursor) - count(&cursor);
+
+                if let Some(taken) = self.take(idx, count(slot), max) {
+                    cursor = with_count(&cursor, count(&cursor) + count(&taken)).unwrap();
+                }
+            }
+        }
+
+        self.cursor = Some(cursor);
+    }
+
+    fn player_stack(&self, button: u16) -> Option<ItemStack> {
+        match self.player_stack_slot(button) {
+            Some(idx) => self.slots[idx as usize].clone(),
+            None => self.offhand.clone(),
+        }
+    }
+
+    fn set_player_stack(&mut self, button: u16, stack: Option<ItemStack>) {
+        match self.player_stack_slot(button) {
+            Some(idx) => self.slots[idx as usize] = stack,
+            None => self.offhand = stack,
+        }
+    }
+
+    /// Gets the window slot of a hotbar slot (0 to 8) or the offhand (40), or
+    /// `None` for the offhand if it's not part of the window.
+    fn player_stack_slot(&self, button: u16) -> Option<u16> {
+        let hotbar_start = self.slots.len() as u16 - 9;
+
+        match (self.kind, button) {
+            (InventoryKind::Player, 40) => Some(OFFHAND_SLOT),
+            (InventoryKind::Player, _) => Some(PLAYER_INVENTORY_MAIN_SLOTS_COUNT + button),
+            (_, 40) => None,
+            (_, _) => Some(hotbar_start + button),
+        }
+    }
+
+    /// Gets the kind of the slot from the window layout.
+    fn slot_kind(&self, idx: u16) -> SlotKind {
+        self.layout
+            .iter()
+            .find(|group| group.slots.contains(&idx))
+            .map_or(SlotKind::Storage, |group| group.kind)
+    }
+
+    /// Gets the slots of the group of the given kind, or an empty range if the
+    /// window doesn't have one.
+    fn group(&self, kind: SlotKind) -> Range<u16> {
+        self.layout
+            .iter()
+            .find(|group| group.kind == kind)
+            .map_or(0..0, |group| group.slots.clone())
+    }
+
+    /// The maximum number of items the slot can hold.
+    fn max_count(&self, idx: u16) -> i32 {
+        match self.slot_kind(idx) {
+            SlotKind::Armor | SlotKind::Potion => 1,
+            _ => 64,
+        }
+    }
+
+    /// The maximum number of items of the stack the slot can hold.
+    fn max_count_for(&self, idx: u16, stack: &ItemStack) -> i32 {
+        match self.slot_kind(idx) {
+            SlotKind::SmeltingFuel if stack.item == ItemKind::Bucket => 1,
+            _ => self.max_count(idx).min(max_count(stack)),
+        }
+    }
+
+    fn can_insert(&self, idx: u16, stack: &ItemStack) -> bool {
+        match self.slot_kind(idx) {
+            SlotKind::CraftingResult
+            | SlotKind::SmeltingResult
+            | SlotKind::MerchantResult
+            | SlotKind::AnvilResult => false,
+            SlotKind::Armor => equipment_slot(stack.item) == Some(idx),
+            // Shulker boxes can't be put into shulker boxes.
+            SlotKind::Storage if self.kind == InventoryKind::ShulkerBox => {
+                !is_shulker_box(stack.item)
+            }
+            // Buckets are allowed so that empty lava buckets can be taken out.
+            SlotKind::SmeltingFuel => stack.item.fuel_time() > 0 || stack.item == ItemKind::Bucket,
+            SlotKind::Potion => is_potion(stack.item),
+            SlotKind::BrewingIngredient => is_brewing_ingredient(stack.item),
+            SlotKind::BrewingFuel => stack.item == ItemKind::BlazePowder,
+            _ => true,
+        }
+    }
+
+    fn can_take(&self, idx: u16) -> bool {
+        match (self.slot_kind(idx), &self.slots[idx as usize]) {
+            (SlotKind::Armor, Some(stack)) if !self.creative => !stack
+                .enchantments()
+                .iter()
+                .any(|(ench, _)| *ench == Enchantment::BindingCurse),
+            // Players need enough levels to pay for the result of an anvil.
+            (SlotKind::AnvilResult, _) => {
+                self.level_cost > 0 && (self.creative || self.experience_level >= self.level_cost)
+            }
+            _ => true,
+        }
+    }
+
+    /// If double clicking can collect items from the slot. Nothing is collected
+    /// in merchant windows.
+    fn can_collect_from(&self, idx: u16) -> bool {
+        !matches!(
+            self.slot_kind(idx),
+            SlotKind::CraftingResult | SlotKind::AnvilResult
+        ) && self.kind != InventoryKind::Merchant
+    }
+}
+
+fn count(stack: &ItemStack) -> i32 {
+    stack.count() as i32
+}
+
+fn max_count(stack: &ItemStack) -> i32 {
+    stack.item.max_stack() as i32
+}
+
+/// Returns a copy of the stack with a different count, or `None` if the count
+/// is not positive.
+fn with_count(stack: &ItemStack, count: i32) -> Option<ItemStack> {
+    (count > 0).then(|| {
+        stack
+            .clone()
+            .with_count(count.min(ItemStack::STACK_MAX as i32) as u8)
+    })
+}
+
+/// Returns `true` if the stacks can be merged into one stack.
+fn can_combine(a: &ItemStack, b: &ItemStack) -> bool {
+    a.item == b.item && nbt_matches(a.nbt.as_ref(), b.nbt.as_ref())
+}
+
+/// Returns `true` if the stacks are the same. The client adds a `Damage` tag
+/// to items with durability, so a damage of zero is the same as no damage.
+pub(super) fn stacks_match(a: Option<&ItemStack>, b: Option<&ItemStack>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.count() == b.count() && can_combine(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn nbt_matches(a: Option<&Compound>, b: Option<&Compound>) -> bool {
+    fn normalize(nbt: Option<&Compound>) -> Compound {
+        let mut nbt = nbt.cloned().unwrap_or_default();
+
+        if matches!(nbt.get("Damage"), Some(Value::Int(0))) {
+            nbt.remove("Damage");
+        }
+
+        nbt
+    }
+
+    a == b || normalize(a) == normalize(b)
+}
+
+/// Gets the player inventory slot the item is equipped in when shift clicked.
+fn equipment_slot(item: ItemKind) -> Option<u16> {
+    match item {
+        ItemKind::LeatherHelmet
+        | ItemKind::ChainmailHelmet
+        | ItemKind::IronHelmet
+        | ItemKind::GoldenHelmet
+        | ItemKind::DiamondHelmet
+        | ItemKind::NetheriteHelmet
+        | ItemKind::TurtleHelmet
+        | ItemKind::CarvedPumpkin
+        | ItemKind::SkeletonSkull
+        | ItemKind::WitherSkeletonSkull
+        | ItemKind::PlayerHead
+        | ItemKind::ZombieHead
+        | ItemKind::CreeperHead
+        | ItemKind::DragonHead
+        | ItemKind::PiglinHead => Some(5),
+        ItemKind::LeatherChestplate
+        | ItemKind::ChainmailChestplate
+        | ItemKind::IronChestplate
+        | ItemKind::GoldenChestplate
+        | ItemKind::DiamondChestplate
+        | ItemKind::NetheriteChestplate
+        | ItemKind::Elytra => Some(6),
+        ItemKind::LeatherLeggings
+        | ItemKind::ChainmailLeggings
+        | ItemKind::IronLeggings
+        | ItemKind::GoldenLeggings
+        | ItemKind::DiamondLeggings
+        | ItemKind::NetheriteLeggings => Some(7),
+        ItemKind::LeatherBoots
+        | ItemKind::ChainmailBoots
+        | ItemKind::IronBoots
+        | ItemKind::GoldenBoots
+        | ItemKind::DiamondBoots
+        | ItemKind::NetheriteBoots => Some(8),
+        ItemKind::Shield => Some(OFFHAND_SLOT),
+        _ => None,
+    }
+}
+
+/// Returns `true` if the item can be put into the bottle slots of a brewing
+/// stand.
+fn is_potion(item: ItemKind) -> bool {
+    matches!(
+        item,
+        ItemKind::Potion
+            | ItemKind::SplashPotion
+            | ItemKind::LingeringPotion
+            | ItemKind::GlassBottle
+    )
+}
+
+/// Returns `true` if the item is used in a brewing recipe.
+fn is_brewing_ingredient(item: ItemKind) -> bool {
+    matches!(
+        item,
+        ItemKind::NetherWart
+            | ItemKind::Redstone
+            | ItemKind::GlowstoneDust
+            | ItemKind::FermentedSpiderEye
+            | ItemKind::Gunpowder
+            | ItemKind::DragonBreath
+            | ItemKind::Sugar
+            | ItemKind::RabbitFoot
+            | ItemKind::GlisteringMelonSlice
+            | ItemKind::SpiderEye
+            | ItemKind::Pufferfish
+            | ItemKind::MagmaCream
+            | ItemKind::GoldenCarrot
+            | ItemKind::BlazePowder
+            | ItemKind::GhastTear
+            | ItemKind::TurtleHelmet
+            | ItemKind::PhantomMembrane
+    )
+}
+
+fn is_shulker_box(item: ItemKind) -> bool {
+    matches!(
+        item,
+        ItemKind::ShulkerBox
+            | ItemKind::WhiteShulkerBox
+            | ItemKind::OrangeShulkerBox
+            | ItemKind::MagentaShulkerBox
+            | ItemKind::LightBlueShulkerBox
+            | ItemKind::YellowShulkerBox
+            | ItemKind::LimeShulkerBox
+            | ItemKind::PinkShulkerBox
+            | ItemKind::GrayShulkerBox
+            | ItemKind::LightGrayShulkerBox
+            | ItemKind::CyanShulkerBox
+            | ItemKind::PurpleShulkerBox
+            | ItemKind::BlueShulkerBox
+            | ItemKind::BrownShulkerBox
+            | ItemKind::GreenShulkerBox
+            | ItemKind::RedShulkerBox
+            | ItemKind::BlackShulkerBox
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{thread_rng, Rng, SeedableRng};
+    use valence_core::ident;
+    use valence_core::packet::var_int::VarInt;
+
+    use super::*;
+    use crate::convert_to_player_slot_id;
+
+    fn click(slot_idx: i16, button: i8, mode: ClickMode) -> ClickSlotC2s {
+        ClickSlotC2s {
+            window_id: 0,
+            state_id: VarInt(0),
+            slot_idx,
+            button,
+            mode,
+            slot_changes: vec![],
+            carried_item: None,
+        }
+    }
+
+    /// Simulates a series of clicks and applies their outcomes. If a click has
+    /// several possible outcomes, the first one is used.
+    fn simulate_clicks(
+        player_inventory: &mut Inventory,
+        open_inventory: Option<&mut Inventory>,
+        cursor_item: &mut Option<ItemStack>,
+        clicks: &[ClickSlotC2s],
+    ) -> Vec<ClickOutcome> {
+        simulate_trading_clicks(
+            player_inventory,
+            open_inventory,
+            cursor_item,
+            &mut [],
+            clicks,
+        )
+    }
+
+    /// Like [`simulate_clicks`], but also counts the uses of the merchant
+    /// trades of the window.
+    fn simulate_trading_clicks(
+        player_inventory: &mut Inventory,
+        mut open_inventory: Option<&mut Inventory>,
+        cursor_item: &mut Option<ItemStack>,
+        trades: &mut [Trade],
+        clicks: &[ClickSlotC2s],
+    ) -> Vec<ClickOutcome> {
+        let mut drag = DragState::default();
+        let mut outcomes = vec![];
+
+        for packet in clicks {
+            let state = WindowState {
+                trades: &*trades,
+                ..Default::default()
+            };
+
+            let outcome = simulate_click(
+                packet,
+                player_inventory,
+                open_inventory.as_deref(),
+                cursor_item.as_ref(),
+                &drag,
+                state,
+                false,
+            )
+            .into_iter()
+            .next()
+            .expect("click should be predictable");
+
+            for &trade in &outcome.trades {
+                trades[trade].uses += 1;
+            }
+
+            for (idx, stack) in outcome.slots.clone() {
+                match open_inventory.as_deref_mut() {
+                    Some(inv) if idx < inv.slot_count() => inv.set_slot(idx, stack),
+                    Some(inv) => {
+                        player_inventory.set_slot(convert_to_player_slot_id(inv.kind, idx), stack)
+                    }
+                    None => player_inventory.set_slot(idx, stack),
+                }
+            }
+
+            if let Some(offhand) = outcome.offhand.clone() {
+                player_inventory.set_slot(OFFHAND_SLOT, offhand);
+            }
+
+            *cursor_item = outcome.cursor.clone();
+            drag = outcome.drag.clone();
+            outcomes.push(outcome);
+        }
+
+        outcomes
+    }
+
+    fn drag_clicks(button: i8, slots: &[i16]) -> Vec<ClickSlotC2s> {
+        let mut clicks = vec![click(OUTSIDE_SLOT_IDX, button << 2, ClickMode::Drag)];
+
+        for &idx in slots {
+            clicks.push(click(idx, button << 2 | 1, ClickMode::Drag));
+        }
+
+        clicks.push(click(OUTSIDE_SLOT_IDX, button << 2 | 2, ClickMode::Drag));
+        clicks
+    }
+
+    #[test]
+    fn drag_left_splits_evenly() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(11, ItemStack::new(ItemKind::Diamond, 60, None));
+        let mut cursor = Some(ItemStack::new(ItemKind::Diamond, 64, None));
+
+        simulate_clicks(
+            &mut player_inventory,
+            None,
+            &mut cursor,
+            &drag_clicks(0, &[9, 10, 11]),
+        );
+
+        assert_eq!(
+            player_inventory.slot(9),
+            Some(&ItemStack::new(ItemKind::Diamond, 21, None))
+        );
+        assert_eq!(
+            player_inventory.slot(10),
+            Some(&ItemStack::new(ItemKind::Diamond, 21, None))
+        );
+        // The full slot only takes what fits.
+        assert_eq!(
+            player_inventory.slot(11),
+            Some(&ItemStack::new(ItemKind::Diamond, 64, None))
+        );
+        assert_eq!(cursor, Some(ItemStack::new(ItemKind::Diamond, 18, None)));
+    }
+
+    #[test]
+    fn drag_right_places_one_each() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(10, ItemStack::new(ItemKind::Stone, 64, None));
+        let mut cursor = Some(ItemStack::new(ItemKind::Diamond, 2, None));
+
+        simulate_clicks(
+            &mut player_inventory,
+            None,
+            &mut cursor,
+            // Slot 10 holds another item, and there are only enough items for
+            // two slots.
+            &drag_clicks(1, &[9, 10, 11, 12]),
+        );
+
+        assert_eq!(
+            player_inventory.slot(9),
+            Some(&ItemStack::new(ItemKind::Diamond, 1, None))
+        );
+        assert_eq!(
+            player_inventory.slot(11),
+            Some(&ItemStack::new(ItemKind::Diamond, 1, None))
+        );
+        assert_eq!(player_inventory.slot(12), None);
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn drag_is_cancelled_by_other_clicks() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        let mut cursor = Some(ItemStack::new(ItemKind::Diamond, 64, None));
+
+        let mut clicks = drag_clicks(0, &[9, 10]);
+        clicks.insert(2, click(20, 0, ClickMode::Click));
+
+        let outcomes = simulate_clicks(&mut player_inventory, None, &mut cursor, &clicks);
+
+        assert!(outcomes.iter().all(|outcome| outcome.slots.is_empty()));
+        assert_eq!(outcomes.last().unwrap().drag, DragState::default());
+        assert_eq!(cursor, Some(ItemStack::new(ItemKind::Diamond, 64, None)));
+    }
+
+    #[test]
+    fn double_click_collects_partial_stacks_first() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(9, ItemStack::new(ItemKind::Diamond, 64, None));
+        player_inventory.set_slot(20, ItemStack::new(ItemKind::Diamond, 10, None));
+        player_inventory.set_slot(30, ItemStack::new(ItemKind::Diamond, 60, None));
+        let mut cursor = Some(ItemStack::new(ItemKind::Diamond, 1, None));
+
+        simulate_clicks(
+            &mut player_inventory,
+            None,
+            &mut cursor,
+            &[click(36, 0, ClickMode::DoubleClick)],
+        );
+
+        assert_eq!(
+            player_inventory.slot(9),
+            Some(&ItemStack::new(ItemKind::Diamond, 64, None))
+        );
+        assert_eq!(player_inventory.slot(20), None);
+        assert_eq!(
+            player_inventory.slot(30),
+            Some(&ItemStack::new(ItemKind::Diamond, 7, None))
+        );
+        assert_eq!(cursor, Some(ItemStack::new(ItemKind::Diamond, 64, None)));
+    }
+
+    #[test]
+    fn hotbar_swap_with_offhand_in_open_window() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(OFFHAND_SLOT, ItemStack::new(ItemKind::Shield, 1, None));
+        let mut open_inventory = Inventory::new(InventoryKind::Generic9x3);
+        open_inventory.set_slot(4, ItemStack::new(ItemKind::Diamond, 5, None));
+        let mut cursor = None;
+
+        let outcomes = simulate_clicks(
+            &mut player_inventory,
+            Some(&mut open_inventory),
+            &mut cursor,
+            &[click(4, 40, ClickMode::Hotbar)],
+        );
+
+        assert_eq!(
+            outcomes[0].slots,
+            vec![(4, Some(ItemStack::new(ItemKind::Shield, 1, None)))]
+        );
+        assert_eq!(
+            player_inventory.slot(OFFHAND_SLOT),
+            Some(&ItemStack::new(ItemKind::Diamond, 5, None))
+        );
+    }
+
+    #[test]
+    fn hotbar_swap_in_open_window() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(38, ItemStack::new(ItemKind::Stone, 3, None));
+        let mut open_inventory = Inventory::new(InventoryKind::Generic9x1);
+        open_inventory.set_slot(0, ItemStack::new(ItemKind::Diamond, 5, None));
+        let mut cursor = None;
+
+        simulate_clicks(
+            &mut player_inventory,
+            Some(&mut open_inventory),
+            &mut cursor,
+            &[click(0, 2, ClickMode::Hotbar)],
+        );
+
+        assert_eq!(
+            open_inventory.slot(0),
+            Some(&ItemStack::new(ItemKind::Stone, 3, None))
+        );
+        assert_eq!(
+            player_inventory.slot(38),
+            Some(&ItemStack::new(ItemKind::Diamond, 5, None))
+        );
+    }
+
+    #[test]
+    fn drop_key_with_cursor_item_does_nothing() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(9, ItemStack::new(ItemKind::Diamond, 5, None));
+        let mut cursor = Some(ItemStack::new(ItemKind::Stone, 1, None));
+
+        let outcomes = simulate_clicks(
+            &mut player_inventory,
+            None,
+            &mut cursor,
+            &[click(9, 1, ClickMode::DropKey)],
+        );
+
+        assert!(outcomes[0].slots.is_empty());
+        assert_eq!(outcomes[0].dropped, None);
+    }
+
+    #[test]
+    fn right_click_outside_drops_one() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        let mut cursor = Some(ItemStack::new(ItemKind::Diamond, 5, None));
+
+        let outcomes = simulate_clicks(
+            &mut player_inventory,
+            None,
+            &mut cursor,
+            &[click(OUTSIDE_SLOT_IDX, 1, ClickMode::Click)],
+        );
+
+        assert_eq!(
+            outcomes[0].dropped,
+            Some((None, ItemStack::new(ItemKind::Diamond, 1, None)))
+        );
+        assert_eq!(cursor, Some(ItemStack::new(ItemKind::Diamond, 4, None)));
+    }
+
+    #[test]
+    fn shift_click_equips_armor() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(20, ItemStack::new(ItemKind::IronHelmet, 1, None));
+        let mut cursor = None;
+
+        simulate_clicks(
+            &mut player_inventory,
+            None,
+            &mut cursor,
+            &[click(20, 0, ClickMode::ShiftClick)],
+        );
+
+        assert_eq!(player_inventory.slot(20), None);
+        assert_eq!(
+            player_inventory.slot(5),
+            Some(&ItemStack::new(ItemKind::IronHelmet, 1, None))
+        );
+    }
+
+    fn shift_click(
+        player_inventory: &Inventory,
+        open_inventory: &Inventory,
+        slot_idx: i16,
+    ) -> Vec<ClickOutcome> {
+        simulate_click(
+            &click(slot_idx, 0, ClickMode::ShiftClick),
+            player_inventory,
+            Some(open_inventory),
+            None,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+    }
+
+    #[test]
+    fn furnace_shift_click_depends_on_smeltable() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(9, ItemStack::new(ItemKind::Coal, 10, None));
+        player_inventory.set_slot(10, ItemStack::new(ItemKind::Stone, 10, None));
+        let furnace = Inventory::new(InventoryKind::Furnace);
+
+        // Coal is fuel, but it could also be smeltable.
+        let outcomes = shift_click(&player_inventory, &furnace, 3);
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(
+            outcomes[0].slot(1),
+            Some(Some(&ItemStack::new(ItemKind::Coal, 10, None)))
+        );
+        assert_eq!(
+            outcomes[1].slot(0),
+            Some(Some(&ItemStack::new(ItemKind::Coal, 10, None)))
+        );
+
+        // Items which aren't fuel go to the hotbar if they aren't smeltable.
+        let outcomes = shift_click(&player_inventory, &furnace, 4);
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(
+            outcomes[0].slot(30),
+            Some(Some(&ItemStack::new(ItemKind::Stone, 10, None)))
+        );
+        assert_eq!(
+            outcomes[1].slot(0),
+            Some(Some(&ItemStack::new(ItemKind::Stone, 10, None)))
+        );
+    }
+
+    #[test]
+    fn furnace_result_and_fuel_slots() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        let mut furnace = Inventory::new(InventoryKind::Furnace);
+        furnace.set_slot(2, ItemStack::new(ItemKind::IronIngot, 5, None));
+
+        // The result goes to the end of the hotbar first.
+        let outcomes = shift_click(&player_inventory, &furnace, 2);
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(
+            outcomes[0].slots,
+            vec![
+                (2, None),
+                (38, Some(ItemStack::new(ItemKind::IronIngot, 5, None)))
+            ]
+        );
+
+        // Items can't be put into the result slot.
+        let mut cursor = Some(ItemStack::new(ItemKind::Stone, 1, None));
+        let outcomes = simulate_clicks(
+            &mut player_inventory,
+            Some(&mut furnace),
+            &mut cursor,
+            &[click(2, 0, ClickMode::Click)],
+        );
+        assert!(outcomes[0].slots.is_empty());
+
+        // The fuel slot only takes fuel, and one bucket.
+        let mut cursor = Some(ItemStack::new(ItemKind::Stone, 1, None));
+        let outcomes = simulate_clicks(
+            &mut player_inventory,
+            Some(&mut furnace),
+            &mut cursor,
+            &[click(1, 0, ClickMode::Click)],
+        );
+        assert!(outcomes[0].slots.is_empty());
+
+        let mut cursor = Some(ItemStack::new(ItemKind::Bucket, 16, None));
+        simulate_clicks(
+            &mut player_inventory,
+            Some(&mut furnace),
+            &mut cursor,
+            &[click(1, 0, ClickMode::Click)],
+        );
+        assert_eq!(
+            furnace.slot(1),
+            Some(&ItemStack::new(ItemKind::Bucket, 1, None))
+        );
+        assert_eq!(cursor, Some(ItemStack::new(ItemKind::Bucket, 15, None)));
+    }
+
+    #[test]
+    fn brewing_stand_shift_click() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(9, ItemStack::new(ItemKind::BlazePowder, 10, None));
+        player_inventory.set_slot(10, ItemStack::new(ItemKind::Potion, 1, None));
+        player_inventory.set_slot(11, ItemStack::new(ItemKind::GlassBottle, 3, None));
+        let mut brewing_stand = Inventory::new(InventoryKind::BrewingStand);
+
+        // Blaze powder is fuel first.
+        let outcomes = shift_click(&player_inventory, &brewing_stand, 5);
+        assert_eq!(
+            outcomes[0].slot(4),
+            Some(Some(&ItemStack::new(ItemKind::BlazePowder, 10, None)))
+        );
+
+        // Then an ingredient if there is no room for more fuel.
+        brewing_stand.set_slot(4, ItemStack::new(ItemKind::BlazePowder, 64, None));
+        let outcomes = shift_click(&player_inventory, &brewing_stand, 5);
+        assert_eq!(
+            outcomes[0].slot(3),
+            Some(Some(&ItemStack::new(ItemKind::BlazePowder, 10, None)))
+        );
+
+        let outcomes = shift_click(&player_inventory, &brewing_stand, 6);
+        assert_eq!(
+            outcomes[0].slot(0),
+            Some(Some(&ItemStack::new(ItemKind::Potion, 1, None)))
+        );
+
+        // Only single bottles go into the bottle slots.
+        let outcomes = shift_click(&player_inventory, &brewing_stand, 7);
+        assert_eq!(
+            outcomes[0].slot(32),
+            Some(Some(&ItemStack::new(ItemKind::GlassBottle, 3, None)))
+        );
+    }
+
+    #[test]
+    fn crafting_result_uses_up_ingredients() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        let mut crafting_table = Inventory::new(InventoryKind::Crafting);
+        crafting_table.set_slot(0, ItemStack::new(ItemKind::Cake, 1, None));
+        crafting_table.set_slot(1, ItemStack::new(ItemKind::MilkBucket, 1, None));
+        crafting_table.set_slot(4, ItemStack::new(ItemKind::Sugar, 5, None));
+        let mut cursor = None;
+
+        let outcomes = simulate_clicks(
+            &mut player_inventory,
+            Some(&mut crafting_table),
+            &mut cursor,
+            &[click(0, 0, ClickMode::ShiftClick)],
+        );
+
+        // The client doesn't know the next result, so only one craft is
+        // predicted.
+        assert_eq!(outcomes[0].slot(0), Some(None));
+        assert_eq!(
+            player_inventory.slot(44),
+            Some(&ItemStack::new(ItemKind::Cake, 1, None))
+        );
+        assert_eq!(
+            crafting_table.slot(1),
+            Some(&ItemStack::new(ItemKind::Bucket, 1, None))
+        );
+        assert_eq!(
+            crafting_table.slot(4),
+            Some(&ItemStack::new(ItemKind::Sugar, 4, None))
+        );
+    }
+
+    #[test]
+    fn crafting_result_is_taken_whole() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(0, ItemStack::new(ItemKind::OakPlanks, 4, None));
+        player_inventory.set_slot(1, ItemStack::new(ItemKind::OakLog, 2, None));
+        let mut cursor = Some(ItemStack::new(ItemKind::OakPlanks, 62, None));
+
+        let outcomes = simulate_clicks(
+            &mut player_inventory,
+            None,
+            &mut cursor,
+            &[click(0, 0, ClickMode::Click)],
+        );
+        assert!(outcomes[0].slots.is_empty());
+
+        cursor = Some(ItemStack::new(ItemKind::OakPlanks, 60, None));
+
+        simulate_clicks(
+            &mut player_inventory,
+            None,
+            &mut cursor,
+            &[click(0, 0, ClickMode::Click)],
+        );
+        assert_eq!(player_inventory.slot(0), None);
+        assert_eq!(
+            player_inventory.slot(1),
+            Some(&ItemStack::new(ItemKind::OakLog, 1, None))
+        );
+        assert_eq!(cursor, Some(ItemStack::new(ItemKind::OakPlanks, 64, None)));
+    }
+
+    fn crafting_recipes() -> RecipeRegistry {
+        let mut recipes = RecipeRegistry::default();
+
+        recipes.insert(
+            ident!("oak_planks"),
+            Recipe::shapeless(
+                [ItemKind::OakLog.into()],
+                ItemStack::new(ItemKind::OakPlanks, 4, None),
+            ),
+        );
+        recipes.insert(
+            ident!("sugar"),
+            Recipe::shapeless(
+                [ItemKind::HoneyBottle.into()],
+                ItemStack::new(ItemKind::Sugar, 3, None),
+            ),
+        );
+        recipes.insert(
+            ident!("cake"),
+            Recipe::shaped(
+                ["AAA", "BEB", "CCC"],
+                [
+                    ('A', ItemKind::MilkBucket.into()),
+                    ('B', ItemKind::Sugar.into()),
+                    ('E', ItemKind::Egg.into()),
+                    ('C', ItemKind::Wheat.into()),
+                ],
+                ItemStack::new(ItemKind::Cake, 1, None),
+            ),
+        );
+
+        recipes
+    }
+
+    fn simulate_crafting_click(
+        packet: &ClickSlotC2s,
+        player_inventory: &Inventory,
+        open_inventory: Option<&Inventory>,
+        recipes: &RecipeRegistry,
+        held_item: u8,
+    ) -> ClickOutcome {
+        let state = WindowState {
+            recipes: Some(recipes),
+            held_item,
+            ..Default::default()
+        };
+
+        simulate_click(
+            packet,
+            player_inventory,
+            open_inventory,
+            None,
+            &DragState::default(),
+            state,
+            false,
+        )
+        .into_iter()
+        .next()
+        .expect("click should be predictable")
+    }
+
+    #[test]
+    fn shift_click_crafts_as_much_as_possible() {
+        let recipes = crafting_recipes();
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(0, ItemStack::new(ItemKind::OakPlanks, 4, None));
+        player_inventory.set_slot(1, ItemStack::new(ItemKind::OakLog, 20, None));
+
+        let outcome = simulate_crafting_click(
+            &click(0, 0, ClickMode::ShiftClick),
+            &player_inventory,
+            None,
+            &recipes,
+            0,
+        );
+
+        // The client only predicts the first craft.
+        assert_eq!(outcome.recomputed_slot, Some(0));
+        assert_eq!(
+            outcome.slot(1),
+            Some(Some(&ItemStack::new(ItemKind::OakLog, 19, None)))
+        );
+        assert_eq!(
+            outcome.slot(44),
+            Some(Some(&ItemStack::new(ItemKind::OakPlanks, 4, None)))
+        );
+
+        // The server crafts until the logs are used up, filling the hotbar from the
+        // end.
+        let crafted: Vec<_> = outcome.crafted_slots.iter().map(|(idx, _)| *idx).collect();
+        assert_eq!(crafted, vec![1, 43, 44]);
+        assert_eq!(outcome.crafted_slots[0].1, None);
+        assert_eq!(
+            outcome.crafted_slots[1].1,
+            Some(ItemStack::new(ItemKind::OakPlanks, 16, None))
+        );
+        assert_eq!(
+            outcome.crafted_slots[2].1,
+            Some(ItemStack::new(ItemKind::OakPlanks, 64, None))
+        );
+    }
+
+    #[test]
+    fn crafting_stops_when_leftovers_stay_in_grid() {
+        let recipes = crafting_recipes();
+        let player_inventory = Inventory::new(InventoryKind::Player);
+        let mut crafting_table = Inventory::new(InventoryKind::Crafting);
+        crafting_table.set_slot(0, ItemStack::new(ItemKind::Cake, 1, None));
+
+        for idx in 1..4 {
+            crafting_table.set_slot(idx, ItemStack::new(ItemKind::MilkBucket, 1, None));
+        }
+
+        crafting_table.set_slot(4, ItemStack::new(ItemKind::Sugar, 4, None));
+        crafting_table.set_slot(5, ItemStack::new(ItemKind::Egg, 2, None));
+        crafting_table.set_slot(6, ItemStack::new(ItemKind::Sugar, 4, None));
+
+        for idx in 7..10 {
+            crafting_table.set_slot(idx, ItemStack::new(ItemKind::Wheat, 2, None));
+        }
+
+        let outcome = simulate_crafting_click(
+            &click(0, 0, ClickMode::ShiftClick),
+            &player_inventory,
+            Some(&crafting_table),
+            &recipes,
+            0,
+        );
+
+        for idx in 1..4 {
+            assert_eq!(
+                outcome.slot(idx),
+                Some(Some(&ItemStack::new(ItemKind::Bucket, 1, None)))
+            );
+        }
+
+        // The buckets don't match the recipe, so nothing else is crafted.
+        assert!(outcome.crafted_slots.is_empty());
+    }
+
+    #[test]
+    fn crafting_leftovers_go_to_player_inventory() {
+        let recipes = crafting_recipes();
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(0, ItemStack::new(ItemKind::Sugar, 3, None));
+        player_inventory.set_slot(1, ItemStack::new(ItemKind::HoneyBottle, 2, None));
+        player_inventory.set_slot(37, ItemStack::new(ItemKind::GlassBottle, 1, None));
+        player_inventory.set_slot(41, ItemStack::new(ItemKind::GlassBottle, 1, None));
+
+        let outcome = simulate_crafting_click(
+            &click(0, 0, ClickMode::Click),
+            &player_inventory,
+            None,
+            &recipes,
+            5,
+        );
+
+        assert_eq!(
+            outcome.cursor,
+            Some(ItemStack::new(ItemKind::Sugar, 3, None))
+        );
+        assert_eq!(
+            outcome.slot(1),
+            Some(Some(&ItemStack::new(ItemKind::HoneyBottle, 1, None)))
+        );
+        // The bottle is added to the held stack first.
+        assert_eq!(
+            outcome.slot(41),
+            Some(Some(&ItemStack::new(ItemKind::GlassBottle, 2, None)))
+        );
+        assert_eq!(outcome.slot(37), None);
+
+        // Shift clicking crafts the last honey bottle too, which leaves its bottle in
+        // the grid.
+        let outcome = simulate_crafting_click(
+            &click(0, 0, ClickMode::ShiftClick),
+            &player_inventory,
+            None,
+            &recipes,
+            5,
+        );
+
+        assert_eq!(
+            outcome.crafted_slots,
+            vec![
+                (1, Some(ItemStack::new(ItemKind::GlassBottle, 1, None))),
+                (44, Some(ItemStack::new(ItemKind::Sugar, 6, None))),
+            ]
+        );
+    }
+
+    #[test]
+    fn place_recipe_from_recipe_book() {
+        let recipes = crafting_recipes();
+        let planks = recipes.get(ident!("oak_planks")).unwrap();
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(9, ItemStack::new(ItemKind::OakLog, 3, None));
+        player_inventory.set_slot(36, ItemStack::new(ItemKind::OakLog, 2, None));
+
+        let place = |player_inventory: &mut Inventory, make_all| {
+            let (outcome, placed) =
+                simulate_place_recipe(player_inventory, None, planks, &recipes, 0, make_all)
+                    .unwrap();
+
+            for (idx, stack) in outcome.slots {
+                player_inventory.set_slot(idx, stack);
+            }
+
+            placed
+        };
+
+        // The hotbar is used first.
+        assert!(place(&mut player_inventory, false));
+        assert_eq!(
+            player_inventory.slot(1),
+            Some(&ItemStack::new(ItemKind::OakLog, 1, None))
+        );
+        assert_eq!(
+            player_inventory.slot(36),
+            Some(&ItemStack::new(ItemKind::OakLog, 1, None))
+        );
+
+        // Clicking the recipe again adds one more craft.
+        assert!(place(&mut player_inventory, false));
+        assert_eq!(
+            player_inventory.slot(1),
+            Some(&ItemStack::new(ItemKind::OakLog, 2, None))
+        );
+
+        assert!(place(&mut player_inventory, true));
+        assert_eq!(
+            player_inventory.slot(1),
+            Some(&ItemStack::new(ItemKind::OakLog, 5, None))
+        );
+        assert_eq!(player_inventory.slot(9), None);
+        assert_eq!(player_inventory.slot(36), None);
+
+        // Without the ingredients, the grid is only emptied.
+        let cake = recipes.get(ident!("cake")).unwrap();
+        let (outcome, placed) =
+            simulate_place_recipe(&player_inventory, None, cake, &recipes, 0, false).unwrap();
+
+        assert!(!placed);
+        assert_eq!(outcome.slot(1), Some(None));
+        assert_eq!(
+            outcome.slot(36),
+            Some(Some(&ItemStack::new(ItemKind::OakLog, 5, None)))
+        );
+    }
+
+    fn bread_trade() -> Trade {
+        Trade::new(
+            ItemStack::new(ItemKind::Emerald, 3, None),
+            ItemStack::new(ItemKind::Bread, 6, None),
+        )
+    }
+
+    #[test]
+    fn merchant_result_uses_up_inputs() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        let mut merchant = Inventory::new(InventoryKind::Merchant);
+        let mut trades = [bread_trade()];
+        let mut cursor = Some(ItemStack::new(ItemKind::Emerald, 5, None));
+
+        let outcomes = simulate_trading_clicks(
+            &mut player_inventory,
+            Some(&mut merchant),
+            &mut cursor,
+            &mut trades,
+            &[click(0, 0, ClickMode::Click), click(2, 0, ClickMode::Click)],
+        );
+
+        assert_eq!(
+            outcomes[0].slot(2),
+            Some(Some(&ItemStack::new(ItemKind::Bread, 6, None)))
+        );
+        assert_eq!(outcomes[1].trades, vec![0]);
+        assert_eq!(cursor, Some(ItemStack::new(ItemKind::Bread, 6, None)));
+        assert_eq!(
+            merchant.slot(0),
+            Some(&ItemStack::new(ItemKind::Emerald, 2, None))
+        );
+        // Two emeralds don't pay for another trade.
+        assert_eq!(merchant.slot(2), None);
+        assert_eq!(trades[0].uses, 1);
+    }
+
+    #[test]
+    fn merchant_shift_click_trades_repeatedly() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        let mut merchant = Inventory::new(InventoryKind::Merchant);
+        merchant.set_slot(1, ItemStack::new(ItemKind::Emerald, 10, None));
+        merchant.set_slot(2, ItemStack::new(ItemKind::Bread, 6, None));
+        let mut trades = [bread_trade().with_max_uses(2)];
+        let mut cursor = None;
+
+        let outcomes = simulate_trading_clicks(
+            &mut player_inventory,
+            Some(&mut merchant),
+            &mut cursor,
+            &mut trades,
+            &[click(2, 0, ClickMode::ShiftClick)],
+        );
+
+        // The trade is disabled after two uses.
+        assert_eq!(outcomes[0].trades, vec![0, 0]);
+        assert_eq!(
+            player_inventory.slot(44),
+            Some(&ItemStack::new(ItemKind::Bread, 12, None))
+        );
+        assert_eq!(
+            merchant.slot(1),
+            Some(&ItemStack::new(ItemKind::Emerald, 4, None))
+        );
+        assert_eq!(merchant.slot(2), None);
+    }
+
+    #[test]
+    fn select_trade_fills_inputs() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(9, ItemStack::new(ItemKind::Emerald, 40, None));
+        player_inventory.set_slot(36, ItemStack::new(ItemKind::Emerald, 40, None));
+        let mut merchant = Inventory::new(InventoryKind::Merchant);
+        merchant.set_slot(0, ItemStack::new(ItemKind::Dirt, 1, None));
+        let trades = [
+            Trade::new(
+                ItemStack::new(ItemKind::Diamond, 1, None),
+                ItemStack::new(ItemKind::Apple, 1, None),
+            ),
+            bread_trade(),
+        ];
+
+        let outcome =
+            simulate_select_trade(&player_inventory, &merchant, None, &trades, 1).unwrap();
+
+        // The dirt goes back to the player inventory, and the emeralds fill up
+        // the first input.
+        assert_eq!(
+            outcome.slot(0),
+            Some(Some(&ItemStack::new(ItemKind::Emerald, 64, None)))
+        );
+        assert_eq!(
+            outcome.slot(2),
+            Some(Some(&ItemStack::new(ItemKind::Bread, 6, None)))
+        );
+        assert_eq!(outcome.slot(3), Some(None));
+        assert_eq!(
+            outcome.slot(30),
+            Some(Some(&ItemStack::new(ItemKind::Emerald, 16, None)))
+        );
+        assert_eq!(
+            outcome.slot(38),
+            Some(Some(&ItemStack::new(ItemKind::Dirt, 1, None)))
+        );
+    }
+
+    #[test]
+    fn anvil_result_costs_levels() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        let mut anvil = Inventory::new(InventoryKind::Anvil);
+        anvil.set_slot(0, ItemStack::new(ItemKind::DiamondSword, 1, None));
+        anvil.set_slot(1, ItemStack::new(ItemKind::Diamond, 5, None));
+        anvil.set_slot(
+            2,
+            ItemStack::new(ItemKind::DiamondSword, 1, None).with_name("Excalibur"),
+        );
+
+        let take = |experience_level, creative| {
+            let state = WindowState {
+                level_cost: 3,
+                repair_item_usage: 2,
+                experience_level,
+                ..Default::default()
+            };
+
+            simulate_click(
+                &click(2, 0, ClickMode::ShiftClick),
+                &player_inventory,
+                Some(&anvil),
+                None,
+                &DragState::default(),
+                state,
+                creative,
+            )
+            .remove(0)
+        };
+
+        // Nothing happens without enough levels.
+        assert!(take(2, false).slots.is_empty());
+        assert!(take(0, true).used_anvil);
+
+        let outcome = take(3, false);
+        assert!(outcome.used_anvil);
+        assert_eq!(outcome.slot(0), Some(None));
+        assert_eq!(
+            outcome.slot(1),
+            Some(Some(&ItemStack::new(ItemKind::Diamond, 3, None)))
+        );
+        assert_eq!(outcome.slot(2), Some(None));
+        assert_eq!(
+            outcome.slot(38),
+            Some(Some(
+                &ItemStack::new(ItemKind::DiamondSword, 1, None).with_name("Excalibur")
+            ))
+        );
+
+        // The client computes the result itself when the inputs change.
+        let mut cursor = Some(ItemStack::new(ItemKind::Diamond, 1, None));
+        let outcomes = simulate_clicks(
+            &mut player_inventory,
+            Some(&mut anvil),
+            &mut cursor,
+            &[click(1, 0, ClickMode::Click)],
+        );
+        assert_eq!(outcomes[0].recomputed_slot, Some(2));
+        assert!(!outcomes[0].used_anvil);
+    }
+
+    fn total_items(
+        player_inventory: &Inventory,
+        open_inventory: &Inventory,
+        cursor: &Option<ItemStack>,
+    ) -> i32 {
+        player_inventory
+            .slots()
+            .chain(open_inventory.slots())
+            .chain([cursor.as_ref()])
+            .flatten()
+            .map(count)
+            .sum()
+    }
+
+    /// A model of how the vanilla client changes the slots of a chest window
+    /// with three rows, which has 27 container slots followed by the 36 slots
+    /// of the player inventory. It is written separately from [`ClickWindow`]
+    /// to check the simulation against.
+    #[derive(Clone, PartialEq, Debug)]
+    struct ChestModel {
+        slots: Vec<Option<ItemStack>>,
+        cursor: Option<ItemStack>,
+        dropped: Vec<ItemStack>,
+    }
+
+    const CHEST_MODEL_CONTAINER_SLOTS: usize = 27;
+    const CHEST_MODEL_HOTBAR_START: usize = 54;
+
+    fn same_item(a: &ItemStack, b: &ItemStack) -> bool {
+        a.item == b.item && a.nbt == b.nbt
+    }
+
+    /// Takes up to `n` items out of `stack`.
+    fn take_items(stack: &mut Option<ItemStack>, n: u8) -> Option<ItemStack> {
+        let s = stack.as_mut()?;
+        let n = n.min(s.count());
+
+        if n == 0 {
+            return None;
+        }
+
+        let taken = s.clone().with_count(n);
+
+        if n == s.count() {
+            *stack = None;
+        } else {
+            s.set_count(s.count() - n);
+        }
+
+        Some(taken)
+    }
+
+    /// Moves up to `n` items from `from` to `to` if `to` is empty or holds the
+    /// same item, without exceeding the maximum stack size.
+    fn put_items(from: &mut Option<ItemStack>, to: &mut Option<ItemStack>, n: u8) {
+        let Some(item) = from.clone() else {
+            return;
+        };
+
+        let n = match to {
+            None => n.min(item.item.max_stack()),
+            Some(t) if same_item(t, &item) => {
+                n.min(item.item.max_stack().saturating_sub(t.count()))
+            }
+            Some(_) => return,
+        };
+
+        if let Some(moved) = take_items(from, n) {
+            match to {
+                Some(t) => t.set_count(t.count() + moved.count()),
+                None => *to = Some(moved),
+            }
+        }
+    }
+
+    impl ChestModel {
+        fn click(&mut self, idx: i16, button: i8) {
+            let n = if button == 0 { u8::MAX } else { 1 };
+
+            if idx == OUTSIDE_SLOT_IDX {
+                self.dropped.extend(take_items(&mut self.cursor, n));
+                return;
+            }
+
+            let slot = &mut self.slots[idx as usize];
+
+            match (slot.as_ref(), self.cursor.as_ref()) {
+                (Some(stack), None) => {
+                    let n = if button == 0 {
+                        stack.count()
+                    } else {
+                        stack.count().div_ceil(2)
+                    };
+
+                    self.cursor = take_items(slot, n);
+                }
+                (Some(stack), Some(cursor)) if !same_item(stack, cursor) => {
+                    std::mem::swap(slot, &mut self.cursor)
+                }
+                _ => put_items(&mut self.cursor, slot, n),
+            }
+        }
+
+        fn shift_click(&mut self, idx: i16) {
+            let idx = idx as usize;
+            let mut stack = self.slots[idx].take();
+
+            let targets: Vec<usize> = if idx < CHEST_MODEL_CONTAINER_SLOTS {
+                (CHEST_MODEL_CONTAINER_SLOTS..self.slots.len())
+                    .rev()
+                    .collect()
+            } else {
+                (0..CHEST_MODEL_CONTAINER_SLOTS).collect()
+            };
+
+            // Stacks of the same item are filled first, then the first empty
+            // slot takes the rest.
+            for &i in &targets {
+                if self.slots[i].is_some() {
+                    put_items(&mut stack, &mut self.slots[i], u8::MAX);
+                }
+            }
+
+            if let Some(&i) = targets.iter().find(|&&i| self.slots[i].is_none()) {
+                self.slots[i] = stack.take();
+            }
+
+            self.slots[idx] = stack;
+        }
+
+        fn swap_hotbar(&mut self, idx: i16, button: i8) {
+            self.slots
+                .swap(idx as usize, CHEST_MODEL_HOTBAR_START + button as usize);
+        }
+
+        fn double_click(&mut self, idx: i16) {
+            if self.cursor.is_none() || self.slots[idx as usize].is_some() {
+                return;
+            }
+
+            // Stacks which aren't full are collected before full stacks.
+            for collect_full in [false, true] {
+                for i in 0..self.slots.len() {
+                    let cursor = self.cursor.as_ref().unwrap();
+
+                    if cursor.count() >= cursor.item.max_stack() {
+                        return;
+                    }
+
+                    match &self.slots[i] {
+                        Some(stack)
+                            if same_item(stack, cursor)
+                                && (collect_full || stack.count() < stack.item.max_stack()) =>
+                        {
+                            put_items(&mut self.slots[i], &mut self.cursor, u8::MAX)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        fn drop_key(&mut self, idx: i16, button: i8) {
+            if self.cursor.is_none() {
+                let n = if button == 0 { 1 } else { u8::MAX };
+                self.dropped
+                    .extend(take_items(&mut self.slots[idx as usize], n));
+            }
+        }
+
+        fn drag(&mut self, button: i8, slots: &[i16]) {
+            let Some(cursor) = self.cursor.clone() else {
+                return;
+            };
+
+            let mut dragged: Vec<usize> = vec![];
+
+            for &idx in slots {
+                let idx = idx as usize;
+                let fits = self.slots[idx]
+                    .as_ref()
+                    .map_or(true, |stack| same_item(stack, &cursor));
+
+                if fits && cursor.count() as usize > dragged.len() && !dragged.contains(&idx) {
+                    dragged.push(idx);
+                }
+            }
+
+            match dragged.len() {
+                0 => {}
+                // Dragging over a single slot is the same as clicking it.
+                1 => self.click(dragged[0] as i16, button),
+                n => {
+                    let per_slot = if button == 0 {
+                        cursor.count() / n as u8
+                    } else {
+                        1
+                    };
+                    let mut remaining = cursor.count();
+
+                    for idx in dragged {
+                        let existing = self.slots[idx].as_ref().map_or(0, |s| s.count());
+                        let new = (existing + per_slot).min(cursor.item.max_stack());
+
+                        remaining -= new - existing;
+                        self.slots[idx] = Some(cursor.clone().with_count(new));
+                    }
+
+                    self.cursor = (remaining > 0).then(|| cursor.with_count(remaining));
+                }
+            }
+        }
+
+        /// Checks that the window holds the same items as the model.
+        fn assert_matches(
+            &self,
+            player_inventory: &Inventory,
+            open_inventory: &Inventory,
+            cursor: &Option<ItemStack>,
+            seed: u64,
+        ) {
+            for (idx, expected) in self.slots.iter().enumerate() {
+                let idx = idx as u16;
+                let actual = if idx < open_inventory.slot_count() {
+                    open_inventory.slot(idx)
+                } else {
+                    player_inventory.slot(convert_to_player_slot_id(open_inventory.kind, idx))
+                };
+
+                assert_eq!(actual, expected.as_ref(), "slot {idx} (seed {seed})");
+            }
+
+            assert_eq!(cursor, &self.cursor, "cursor (seed {seed})");
+        }
+    }
+
+    #[test]
+    fn random_clicks_match_model() {
+        // Replace with the printed seed to reproduce a failure.
+        let seed: u64 = thread_rng().gen();
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let items = [ItemKind::Diamond, ItemKind::EnderPearl, ItemKind::IronSword];
+
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        let mut open_inventory = Inventory::new(InventoryKind::Generic9x3);
+
+        for idx in 9..45 {
+            if rng.gen_bool(0.5) {
+                let item = items[rng.gen_range(0..items.len())];
+                let count = rng.gen_range(1..=item.max_stack());
+                player_inventory.set_slot(idx, ItemStack::new(item, count, None));
+            }
+        }
+
+        let window_size = open_inventory.slot_count() as i16 + 36;
+
+        let mut model = ChestModel {
+            slots: (0..window_size as u16)
+                .map(|idx| {
+                    if idx < open_inventory.slot_count() {
+                        None
+                    } else {
+                        player_inventory
+                            .slot(convert_to_player_slot_id(open_inventory.kind, idx))
+                            .cloned()
+                    }
+                })
+                .collect(),
+            cursor: None,
+            dropped: vec![],
+        };
+
+        let mut cursor = None;
+        let mut total = total_items(&player_inventory, &open_inventory, &cursor);
+
+        for _ in 0..1000 {
+            let idx = rng.gen_range(0..window_size);
+
+            let clicks = match rng.gen_range(0..6) {
+                0 => {
+                    let button = rng.gen_range(0..2);
+                    // Sometimes click outside of the window to drop the cursor.
+                    let idx = if rng.gen_bool(0.05) {
+                        OUTSIDE_SLOT_IDX
+                    } else {
+                        idx
+                    };
+
+                    model.click(idx, button);
+                    vec![click(idx, button, ClickMode::Click)]
+                }
+                1 => {
+                    model.shift_click(idx);
+                    vec![click(idx, 0, ClickMode::ShiftClick)]
+                }
+                2 => {
+                    let button = rng.gen_range(0..9);
+                    model.swap_hotbar(idx, button);
+                    vec![click(idx, button, ClickMode::Hotbar)]
+                }
+                3 => {
+                    model.double_click(idx);
+                    vec![click(idx, 0, ClickMode::DoubleClick)]
+                }
+                4 => {
+                    let button = rng.gen_range(0..2);
+                    model.drop_key(idx, button);
+                    vec![click(idx, button, ClickMode::DropKey)]
+                }
+                _ => {
+                    let button = rng.gen_range(0..2);
+                    let slots = (0..rng.gen_range(1..5))
+                        .map(|_| rng.gen_range(0..window_size))
+                        .collect::<Vec<_>>();
+
+                    model.drag(button, &slots);
+                    drag_clicks(button, &slots)
+                }
+            };
+
+            let outcomes = simulate_clicks(
+                &mut player_inventory,
+                Some(&mut open_inventory),
+                &mut cursor,
+                &clicks,
+            );
+
+            let dropped: Vec<_> = outcomes
+                .iter()
+                .filter_map(|outcome| outcome.dropped.clone())
+                .map(|(_, stack)| stack)
+                .collect();
+
+            assert_eq!(
+                dropped, model.dropped,
+                "dropped items of {clicks:?} (seed {seed})"
+            );
+
+            for stack in model.dropped.drain(..) {
+                total -= count(&stack);
+            }
+
+            model.assert_matches(&player_inventory, &open_inventory, &cursor, seed);
+
+            assert_eq!(
+                total_items(&player_inventory, &open_inventory, &cursor),
+                total,
+                "seed {seed}"
+            );
+        }
+    }
+}
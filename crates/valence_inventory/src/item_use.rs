@@ -0,0 +1,243 @@
+use bevy_ecs::prelude::*;
+use valence_client::action::ReleaseUseItem;
+use valence_client::health::Food;
+use valence_client::misc::InteractItem;
+use valence_core::game_mode::GameMode;
+use valence_core::hand::Hand;
+use valence_core::item::{ItemKind, ItemStack};
+use valence_entity::living::LivingFlags;
+
+use crate::click::OFFHAND_SLOT;
+use crate::{HeldItem, Inventory};
+
+/// The number of ticks items which are used until they are released, like
+/// bows and shields, can be used for.
+const USE_UNTIL_RELEASED: i32 = 72000;
+
+/// The item a client is using, such as food it is eating or a bow it is
+/// drawing. This component is added to clients when they start using an item
+/// which takes time to use, and removed when they stop.
+///
+/// While the component is present, the living flags of the client show the
+/// other players that the item is being used.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct UsingItem {
+    pub hand: Hand,
+    pub item: ItemKind,
+    /// The number of ticks the item has been used for.
+    pub use_ticks: i32,
+}
+
+/// Sent when a client finishes using an item, either because it was used for
+/// long enough, like food and potions, or because the client released it,
+/// like bows. Consuming the item is left to the handlers of this event.
+///
+/// Clients stop using items without this event being sent when the item in
+/// their hand changes.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FinishUsingItem {
+    pub client: Entity,
+    pub hand: Hand,
+    pub item: ItemKind,
+    /// The number of ticks the item was used for, such as the number of ticks
+    /// a bow was drawn.
+    pub use_ticks: i32,
+    /// Whether the client released the item. Food is released if the client
+    /// stops eating before it is done.
+    pub released: bool,
+}
+
+impl FinishUsingItem {
+    /// Returns how far a bow was drawn from `0.0` to `1.0`, which vanilla
+    /// multiplies the speed of the arrow by. A bow is fully drawn after 20
+    /// ticks.
+    pub fn bow_pull_progress(&self) -> f32 {
+        let seconds = self.use_ticks as f32 / 20.0;
+
+        ((seconds * seconds + seconds * 2.0) / 3.0).min(1.0)
+    }
+}
+
+/// Returns the number of ticks it takes to use an item, or `None` if the item
+/// is used instantly. Items which are used until they are released, like
+/// bows, are used for 72000 ticks.
+pub fn use_duration(item: ItemKind) -> Option<i32> {
+    match item {
+        ItemKind::HoneyBottle => Some(40),
+        ItemKind::Potion | ItemKind::MilkBucket => Some(32),
+        ItemKind::Bow
+        | ItemKind::Crossbow
+        | ItemKind::Trident
+        | ItemKind::Shield
+        | ItemKind::Spyglass => Some(USE_UNTIL_RELEASED),
+        ItemKind::GoatHorn => Some(140),
+        _ => item
+            .food_component()
+            .map(|food| if food.snack { 16 } else { 32 }),
+    }
+}
+
+fn stack_in_hand<'a>(
+    inventory: &'a Inventory,
+    held_item: &HeldItem,
+    hand: Hand,
+) -> Option<&'a ItemStack> {
+    match hand {
+        Hand::Main => inventory.slot(held_item.slot()),
+        Hand::Off => inventory.slot(OFFHAND_SLOT),
+    }
+}
+
+fn set_using_flags(flags: &mut LivingFlags, using: Option<Hand>) {
+    flags.set_using_item(using.is_some());
+    flags.set_off_hand_active(using == Some(Hand::Off));
+}
+
+/// Finishes using the items clients released, counts the ticks of the other
+/// items being used, and finishes using the items which were used for long
+/// enough.
+pub(crate) fn update_using_items(
+    mut events: EventReader<ReleaseUseItem>,
+    mut clients: Query<(
+        Entity,
+        &mut UsingItem,
+        &Inventory,
+        &HeldItem,
+        &mut LivingFlags,
+    )>,
+    mut finish_events: EventWriter<FinishUsingItem>,
+    mut commands: Commands,
+) {
+    let mut released = vec![];
+
+    for event in events.iter() {
+        let Ok((client, using, _, _, mut flags)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        if released.contains(&client) {
+            continue;
+        }
+
+        finish_events.send(FinishUsingItem {
+            client,
+            hand: using.hand,
+            item: using.item,
+            use_ticks: using.use_ticks,
+            released: true,
+        });
+
+        commands.entity(client).remove::<UsingItem>();
+        set_using_flags(&mut flags, None);
+        released.push(client);
+    }
+
+    for (client, mut using, inventory, held_item, mut flags) in &mut clients {
+        if released.contains(&client) {
+            continue;
+        }
+
+        let in_hand = stack_in_hand(inventory, held_item, using.hand).map(|stack| stack.item);
+
+        if in_hand != Some(using.item) {
+            commands.entity(client).remove::<UsingItem>();
+            set_using_flags(&mut flags, None);
+            continue;
+        }
+
+        using.use_ticks += 1;
+
+        if Some(using.use_ticks) >= use_duration(using.item) {
+            finish_events.send(FinishUsingItem {
+                client,
+                hand: using.hand,
+                item: using.item,
+                use_ticks: using.use_ticks,
+                released: false,
+            });
+
+            commands.entity(client).remove::<UsingItem>();
+            set_using_flags(&mut flags, None);
+        }
+    }
+}
+
+pub(crate) fn start_using_items(
+    mut events: EventReader<InteractItem>,
+    mut clients: Query<(
+        &Inventory,
+        &HeldItem,
+        &GameMode,
+        Option<&Food>,
+        &mut LivingFlags,
+    )>,
+    mut commands: Commands,
+) {
+    for event in events.iter() {
+        let Ok((inventory, held_item, game_mode, food, mut flags)) = clients.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        let Some(stack) = stack_in_hand(inventory, held_item, event.hand) else {
+            continue;
+        };
+
+        if use_duration(stack.item).is_none() {
+            continue;
+        }
+
+        // Like in vanilla, food can only be eaten when the client is hungry. Honey
+        // bottles are drunk like potions instead.
+        let food_component = stack
+            .item
+            .food_component()
+            .filter(|_| stack.item != ItemKind::HoneyBottle);
+
+        if let Some(food_component) = food_component {
+            let hungry = food.map_or(true, |food| food.0 < 20);
+
+            if !hungry && !food_component.always_edible && *game_mode != GameMode::Creative {
+                continue;
+            }
+        }
+
+        commands.entity(event.client).insert(UsingItem {
+            hand: event.hand,
+            item: stack.item,
+            use_ticks: 0,
+        });
+        set_using_flags(&mut flags, Some(event.hand));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn use_durations() {
+        assert_eq!(use_duration(ItemKind::Apple), Some(32));
+        assert_eq!(use_duration(ItemKind::DriedKelp), Some(16));
+        assert_eq!(use_duration(ItemKind::HoneyBottle), Some(40));
+        assert_eq!(use_duration(ItemKind::Bow), Some(72000));
+        assert_eq!(use_duration(ItemKind::Stone), None);
+        assert_eq!(use_duration(ItemKind::EnderPearl), None);
+    }
+
+    #[test]
+    fn bow_pull() {
+        let finish = |use_ticks| FinishUsingItem {
+            client: Entity::PLACEHOLDER,
+            hand: Hand::Main,
+            item: ItemKind::Bow,
+            use_ticks,
+            released: true,
+        };
+
+        assert_eq!(finish(0).bow_pull_progress(), 0.0);
+        assert_eq!(finish(10).bow_pull_progress(), 1.25 / 3.0);
+        assert_eq!(finish(20).bow_pull_progress(), 1.0);
+        assert_eq!(finish(100).bow_pull_progress(), 1.0);
+    }
+}
@@ -24,24 +24,47 @@ use std::ops::Range;
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
 use tracing::{debug, warn};
 use valence_client::event_loop::{EventLoopSchedule, EventLoopSet, PacketEvent, RunEventLoopSet};
-use valence_client::{Client, FlushPacketsSet, SpawnClientsSet};
+use valence_client::experience::ExperienceQuery;
+use valence_client::{Client, FlushPacketsSet, SpawnClientsSet, UpdateClientsSet};
 use valence_core::game_mode::GameMode;
 use valence_core::item::ItemStack;
 use valence_core::packet::c2s::play::click_slot::{ClickMode, Slot};
 use valence_core::packet::c2s::play::{
-    ClickSlotC2s, CloseHandledScreenC2s, CreativeInventoryActionC2s, PlayerActionC2s,
-    UpdateSelectedSlotC2s,
+    ClickSlotC2s, CloseHandledScreenC2s, CraftRequestC2s, CreativeInventoryActionC2s,
+    PlayerActionC2s, RenameItemC2s, SelectMerchantTradeC2s, UpdateSelectedSlotC2s,
 };
 use valence_core::packet::encode::WritePacket;
+use valence_core::packet::raw::RawBytes;
 use valence_core::packet::s2c::play::open_screen::WindowType;
+use valence_core::packet::s2c::play::unlock_recipes::UpdateRecipeBookAction;
 use valence_core::packet::s2c::play::{
-    CloseScreenS2c, InventoryS2c, OpenScreenS2c, ScreenHandlerSlotUpdateS2c,
+    CloseScreenS2c, CraftFailedResponseS2c, InventoryS2c, OpenScreenS2c,
+    ScreenHandlerPropertyUpdateS2c, ScreenHandlerSlotUpdateS2c, SynchronizeRecipesS2c,
+    UnlockRecipesS2c, UpdateSelectedSlotS2c,
 };
 use valence_core::packet::var_int::VarInt;
 use valence_core::text::Text;
-
+use valence_core::DEFAULT_TPS;
+use valence_entity::item::{self, ItemEntityBundle};
+use valence_entity::{Location, Look, Position, Velocity};
+
+pub use crate::anvil::{AnvilState, RenameItem, UpdateAnvilResult};
+use crate::click::{DragState, WindowState};
+pub use crate::item_use::{use_duration, FinishUsingItem, UsingItem};
+pub use crate::merchant::{SelectMerchantTrade, Trade, TradeList};
+pub use crate::nbt::UnknownItemPolicy;
+use crate::recipe::{crafting_grid, CraftingState};
+pub use crate::recipe::{Ingredient, Recipe, RecipeKind, RecipeRegistry};
+
+mod anvil;
+mod click;
+mod item_use;
+mod merchant;
+mod nbt;
+mod recipe;
 mod validate;
 
 pub struct InventoryPlugin;
@@ -56,9 +79,30 @@ impl Plugin for InventoryPlugin {
         )
         .add_systems(
             (
+                record_client_slots,
+                update_anvil_results.after(record_client_slots),
+                update_crafting_results.after(record_client_slots),
+            )
+                .in_base_set(CoreSet::PreUpdate)
+                .after(RunEventLoopSet),
+        )
+        .add_systems(
+            (
+                item_use::update_using_items,
+                item_use::start_using_items.after(item_use::update_using_items),
+            )
+                .in_base_set(CoreSet::PreUpdate)
+                .after(RunEventLoopSet),
+        )
+        .add_systems(
+            (
+                init_window_states,
+                send_recipes,
                 update_open_inventories,
                 update_client_on_close_inventory.after(update_open_inventories),
-                update_player_inventories,
+                resync_player_inventories_on_respawn.after(UpdateClientsSet),
+                update_player_inventories.after(resync_player_inventories_on_respawn),
+                update_held_items.after(resync_player_inventories_on_respawn),
             )
                 .in_base_set(CoreSet::PostUpdate)
                 .before(FlushPacketsSet),
@@ -70,14 +114,22 @@ impl Plugin for InventoryPlugin {
                 handle_creative_inventory_action,
                 handle_close_handled_screen,
                 handle_player_actions,
+                handle_select_merchant_trade,
+                handle_rename_item,
+                handle_craft_request,
             )
                 .in_base_set(EventLoopSet::PreUpdate)
                 .in_schedule(EventLoopSchedule),
         )
         .init_resource::<InventorySettings>()
+        .init_resource::<RecipeRegistry>()
         .add_event::<ClickSlot>()
-        .add_event::<DropItemStack>()
-        .add_event::<CreativeInventoryAction>()
+        .add_event::<DropItemEvent>()
+        .add_event::<FinishUsingItem>()
+        .add_event::<CreativeItemSetEvent>()
+        .add_event::<SelectMerchantTrade>()
+        .add_event::<RenameItem>()
+        .add_event::<UpdateAnvilResult>()
         .add_event::<UpdateSelectedSlot>();
     }
 }
@@ -334,19 +386,25 @@ pub struct ClientInventoryState {
     /// Tracks what slots have been changed by this client in this tick, so we
     /// don't need to send updates for them.
     slots_changed: u64,
+    /// The contents of the slots in `slots_changed` after the client changed
+    /// them. Slots changed again by the server in the same tick are still
+    /// sent.
+    client_slots: Vec<(u16, Option<ItemStack>)>,
     /// Whether the client has updated the cursor item in this tick. This is not
     /// on the `CursorItem` component to make maintaining accurate change
     /// detection for end users easier.
     client_updated_cursor_item: bool,
-    // TODO: make this a separate modifiable component.
-    held_item_slot: u16,
+    /// Whether the open window was already closed on the client, so no close
+    /// packet needs to be sent when `OpenInventory` is removed.
+    window_closed: bool,
+    /// The slot of the held item as the client knows it, which differs from
+    /// [`HeldItem`] if it was changed by the server.
+    client_held_item_slot: u16,
+    /// The drag the client is in the middle of, which spans several clicks.
+    drag: DragState,
 }
 
 impl ClientInventoryState {
-    pub fn held_item_slot(&self) -> u16 {
-        self.held_item_slot
-    }
-
     #[doc(hidden)]
     pub fn window_id(&self) -> u8 {
         self.window_id
@@ -363,21 +421,121 @@ impl ClientInventoryState {
 #[derive(Component, Clone, PartialEq, Default, Debug)]
 pub struct CursorItem(pub Option<ItemStack>);
 
+/// The slot of the hotbar the client is holding. Changing it selects the slot
+/// on the client.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct HeldItem {
+    held_item_slot: u16,
+}
+
+impl HeldItem {
+    /// The slot ID of the held item in the player inventory, between 36 and
+    /// 44.
+    pub fn slot(&self) -> u16 {
+        self.held_item_slot
+    }
+
+    /// The index of the held item in the hotbar, between 0 and 8.
+    pub fn hotbar_idx(&self) -> u8 {
+        (self.held_item_slot - PLAYER_INVENTORY_MAIN_SLOTS_COUNT) as u8
+    }
+
+    /// Selects the slot at the index in the hotbar.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than 8.
+    pub fn set_hotbar_idx(&mut self, hotbar_idx: u8) {
+        assert!(hotbar_idx <= 8, "hotbar index out of bounds");
+
+        self.held_item_slot = convert_hotbar_slot_id(hotbar_idx as u16);
+    }
+}
+
+impl Default for HeldItem {
+    fn default() -> Self {
+        Self {
+            // First slot of the hotbar.
+            held_item_slot: PLAYER_INVENTORY_MAIN_SLOTS_COUNT,
+        }
+    }
+}
+
 /// Used to indicate that the client with this component is currently viewing
 /// an inventory.
+///
+/// Inserting this component while the client is viewing another inventory
+/// replaces the open window.
 #[derive(Component, Clone, Debug)]
 pub struct OpenInventory {
     /// The entity with the `Inventory` component that the client is currently
     /// viewing.
     pub entity: Entity,
+    /// Whether the window was opened on the client.
+    opened: bool,
+    /// Tracks what slots have been changed by the client in this tick, like
+    /// [`ClientInventoryState`] does for the player inventory.
     client_changed: u64,
+    client_slots: Vec<(u16, Option<ItemStack>)>,
+    properties: Vec<i16>,
+    /// Contains a set bit for each modified property in `properties`.
+    properties_changed: u64,
+    /// The trade selected by the client, if the inventory is a merchant.
+    selected_trade: usize,
 }
 
 impl OpenInventory {
     pub fn new(entity: Entity) -> Self {
         OpenInventory {
             entity,
+            opened: false,
             client_changed: 0,
+            client_slots: vec![],
+            properties: vec![],
+            properties_changed: 0,
+            selected_trade: 0,
+        }
+    }
+
+    /// Gets the value of a property of the window. Properties which were never
+    /// set are `0`.
+    pub fn property(&self, idx: u16) -> i16 {
+        self.properties.get(idx as usize).copied().unwrap_or(0)
+    }
+
+    /// Sets a property of the window, such as the progress bars of furnaces.
+    /// The property is sent to the client if its value changed.
+    ///
+    /// The meaning of each property depends on the kind of the inventory:
+    ///
+    /// - Furnace, blast furnace and smoker: `0` is the remaining fuel time, `1`
+    ///   the total fuel time of the burning fuel, `2` the cooking progress and
+    ///   `3` the total cooking time, all in ticks.
+    /// - Brewing stand: `0` is the remaining brewing time in ticks, from 400 to
+    ///   0, and `1` the fuel, from 0 to 20.
+    /// - Beacon: `0` is the level of the pyramid, `1` and `2` the raw IDs of
+    ///   the primary and secondary effects, or `-1` for none.
+    /// - Enchanting table: `0` to `2` are the level requirements of the offered
+    ///   enchantments, `3` the enchantment seed, `4` to `6` the raw IDs of the
+    ///   shown enchantments and `7` to `9` their levels, or `-1` for none.
+    /// - Anvil: `0` is the repair cost in levels, which is set from the
+    ///   [`AnvilState`] of the anvil.
+    /// - Lectern: `0` is the page number.
+    /// - Loom: `0` is the selected pattern.
+    /// - Stonecutter: `0` is the selected recipe.
+    #[track_caller]
+    pub fn set_property(&mut self, idx: u16, value: i16) {
+        assert!(idx < 64, "property index of {idx} out of bounds");
+
+        let idx = idx as usize;
+
+        if idx >= self.properties.len() {
+            self.properties.resize(idx + 1, 0);
+        }
+
+        if self.properties[idx] != value {
+            self.properties[idx] = value;
+            self.properties_changed |= 1 << idx;
         }
     }
 }
@@ -533,14 +691,51 @@ fn init_new_client_inventories(clients: Query<Entity, Added<Client>>, mut comman
                 window_id: 0,
                 state_id: Wrapping(0),
                 slots_changed: 0,
+                client_slots: vec![],
                 client_updated_cursor_item: false,
-                // First slot of the hotbar.
-                held_item_slot: 36,
+                window_closed: false,
+                client_held_item_slot: HeldItem::default().slot(),
+                drag: DragState::default(),
             },
+            HeldItem::default(),
         ));
     }
 }
 
+/// Sends the whole player inventory and the held item again to clients which
+/// respawned, since the client empties its inventory when respawning.
+fn resync_player_inventories_on_respawn(
+    mut clients: Query<(&mut Client, &mut Inventory, &HeldItem), Changed<Location>>,
+) {
+    for (mut client, mut inventory, held_item) in &mut clients {
+        if client.is_added() {
+            // The inventory of joining clients is empty on both sides.
+            continue;
+        }
+
+        inventory.changed = u64::MAX;
+
+        client.write_packet(&UpdateSelectedSlotS2c {
+            slot: held_item.hotbar_idx(),
+        });
+    }
+}
+
+/// Selects the held item on clients when it was changed by the server.
+fn update_held_items(
+    mut clients: Query<(&mut Client, &HeldItem, &mut ClientInventoryState), Changed<HeldItem>>,
+) {
+    for (mut client, held_item, mut inv_state) in &mut clients {
+        if held_item.slot() != inv_state.client_held_item_slot {
+            client.write_packet(&UpdateSelectedSlotS2c {
+                slot: held_item.hotbar_idx(),
+            });
+
+            inv_state.client_held_item_slot = held_item.slot();
+        }
+    }
+}
+
 /// Send updates for each client's player inventory.
 fn update_player_inventories(
     mut query: Query<
@@ -572,6 +767,7 @@ fn update_player_inventories(
 
             inventory.changed = 0;
             inv_state.slots_changed = 0;
+            inv_state.client_slots.clear();
 
             // Skip updating the cursor item because we just updated the whole inventory.
             continue;
@@ -579,7 +775,8 @@ fn update_player_inventories(
             // Send the modified slots.
 
             // The slots that were NOT modified by this client, and they need to be sent
-            let changed_filtered = inventory.changed & !inv_state.slots_changed;
+            let changed_filtered =
+                inventory.changed & !unchanged_client_slots(&inventory, &inv_state.client_slots);
 
             if changed_filtered != 0 {
                 inv_state.state_id += 1;
@@ -598,6 +795,7 @@ fn update_player_inventories(
 
             inventory.changed = 0;
             inv_state.slots_changed = 0;
+            inv_state.client_slots.clear();
         }
 
         if cursor_item.is_changed() && !inv_state.client_updated_cursor_item {
@@ -617,6 +815,46 @@ fn update_player_inventories(
     }
 }
 
+/// Records what the clients changed their slots to while their packets were
+/// handled. If the server changes these slots again in the same tick, such as
+/// when a plugin sets a slot the client just clicked, the slots are sent so
+/// that the client doesn't keep showing its own prediction.
+fn record_client_slots(
+    mut clients: Query<(
+        &Inventory,
+        &mut ClientInventoryState,
+        Option<&mut OpenInventory>,
+    )>,
+    inventories: Query<&Inventory>,
+) {
+    for (client_inv, mut inv_state, open_inventory) in &mut clients {
+        inv_state.client_slots = slots_in_mask(client_inv, inv_state.slots_changed);
+
+        if let Some(mut open_inventory) = open_inventory {
+            if let Ok(inventory) = inventories.get(open_inventory.entity) {
+                open_inventory.client_slots =
+                    slots_in_mask(inventory, open_inventory.client_changed);
+            }
+        }
+    }
+}
+
+fn slots_in_mask(inventory: &Inventory, mask: u64) -> Vec<(u16, Option<ItemStack>)> {
+    (0..inventory.slot_count().min(64))
+        .filter(|&idx| (mask >> idx) & 1 == 1)
+        .map(|idx| (idx, inventory.slot(idx).cloned()))
+        .collect()
+}
+
+/// Returns a mask of the slots which still contain what the client changed
+/// them to, so they don't need to be sent.
+fn unchanged_client_slots(inventory: &Inventory, client_slots: &[(u16, Option<ItemStack>)]) -> u64 {
+    client_slots
+        .iter()
+        .filter(|(idx, stack)| inventory.slot(*idx) == stack.as_ref())
+        .fold(0, |mask, (idx, _)| mask | 1 << idx)
+}
+
 /// Handles the `OpenInventory` component being added to a client, which
 /// indicates that the client is now viewing an inventory, and sends inventory
 /// updates to the client when the inventory is modified.
@@ -629,6 +867,8 @@ fn update_open_inventories(
         &mut OpenInventory,
     )>,
     mut inventories: Query<&mut Inventory>,
+    trade_lists: Query<Ref<TradeList>>,
+    anvils: Query<Ref<AnvilState>>,
     mut commands: Commands,
 ) {
     // These operations need to happen in this order.
@@ -645,13 +885,30 @@ fn update_open_inventories(
                 window_id: inv_state.window_id,
             });
 
+            inv_state.window_closed = true;
+
             continue;
         };
 
-        if open_inventory.is_added() {
+        if let Ok(anvil) = anvils.get(open_inventory.entity) {
+            if anvil.is_changed() {
+                // The client computes its own level cost when the inputs change, so the cost is
+                // sent whenever the anvil changes.
+                open_inventory.set_property(0, anvil.level_cost as i16);
+                open_inventory.properties_changed |= 1;
+            }
+        }
+
+        if !open_inventory.opened {
             // Send the inventory to the client if the client just opened the inventory.
+            // This replaces the window the client was viewing before, if any.
+            open_inventory.opened = true;
             inv_state.window_id = inv_state.window_id % 100 + 1;
+            inv_state.window_closed = false;
+            inv_state.drag = DragState::default();
             open_inventory.client_changed = 0;
+            open_inventory.client_slots.clear();
+            inv_state.state_id += 1;
 
             client.write_packet(&OpenScreenS2c {
                 window_id: VarInt(inv_state.window_id.into()),
@@ -665,6 +922,21 @@ fn update_open_inventories(
                 slots: Cow::Borrowed(inventory.slot_slice()),
                 carried_item: Cow::Borrowed(&cursor_item.0),
             });
+
+            // Properties start at zero on the client, so only the others need to be sent.
+            for (i, &value) in open_inventory.properties.iter().enumerate() {
+                if value != 0 {
+                    client.write_packet(&ScreenHandlerPropertyUpdateS2c {
+                        window_id: inv_state.window_id,
+                        property: i as i16,
+                        value,
+                    });
+                }
+            }
+
+            if let Ok(trade_list) = trade_lists.get(open_inventory.entity) {
+                client.write_packet(&trade_list.to_packet(inv_state.window_id));
+            }
         } else {
             // The client is already viewing the inventory.
 
@@ -683,7 +955,8 @@ fn update_open_inventories(
                 // Send the changed slots.
 
                 // The slots that were NOT changed by this client, and they need to be sent.
-                let changed_filtered = inventory.changed & !open_inventory.client_changed;
+                let changed_filtered = inventory.changed
+                    & !unchanged_client_slots(&inventory, &open_inventory.client_slots);
 
                 if changed_filtered != 0 {
                     inv_state.state_id += 1;
@@ -700,22 +973,57 @@ fn update_open_inventories(
                     }
                 }
             }
+
+            // Send the changed properties.
+            if open_inventory.properties_changed != 0 {
+                for (i, &value) in open_inventory.properties.iter().enumerate() {
+                    if (open_inventory.properties_changed >> i) & 1 == 1 {
+                        client.write_packet(&ScreenHandlerPropertyUpdateS2c {
+                            window_id: inv_state.window_id,
+                            property: i as i16,
+                            value,
+                        });
+                    }
+                }
+            }
+
+            // Send the trades again if they were changed.
+            if let Ok(trade_list) = trade_lists.get(open_inventory.entity) {
+                if trade_list.is_changed() {
+                    client.write_packet(&trade_list.to_packet(inv_state.window_id));
+                }
+            }
         }
 
         open_inventory.client_changed = 0;
+        open_inventory.client_slots.clear();
+        open_inventory.properties_changed = 0;
         inv_state.slots_changed = 0;
+        inv_state.client_slots.clear();
         inv_state.client_updated_cursor_item = false;
         inventory.changed = 0;
     }
 }
 
 /// Handles clients telling the server that they are closing an inventory.
-fn handle_close_handled_screen(mut packets: EventReader<PacketEvent>, mut commands: Commands) {
+fn handle_close_handled_screen(
+    mut packets: EventReader<PacketEvent>,
+    mut clients: Query<&mut ClientInventoryState, With<OpenInventory>>,
+    mut commands: Commands,
+) {
     for packet in packets.iter() {
-        if packet.decode::<CloseHandledScreenC2s>().is_some() {
-            if let Some(mut entity) = commands.get_entity(packet.client) {
-                entity.remove::<OpenInventory>();
+        if let Some(pkt) = packet.decode::<CloseHandledScreenC2s>() {
+            let Ok(mut inv_state) = clients.get_mut(packet.client) else {
+                continue
+            };
+
+            if pkt.window_id != inv_state.window_id as i8 {
+                // The client closed a window which was already replaced, ignore.
+                continue;
             }
+
+            inv_state.window_closed = true;
+            commands.entity(packet.client).remove::<OpenInventory>();
         }
     }
 }
@@ -724,13 +1032,27 @@ fn handle_close_handled_screen(mut packets: EventReader<PacketEvent>, mut comman
 /// indicates that the client is no longer viewing an inventory.
 fn update_client_on_close_inventory(
     mut removals: RemovedComponents<OpenInventory>,
-    mut clients: Query<(&mut Client, &ClientInventoryState)>,
+    mut clients: Query<(
+        &mut Client,
+        &mut ClientInventoryState,
+        Option<&OpenInventory>,
+    )>,
 ) {
     for entity in &mut removals {
-        if let Ok((mut client, inv_state)) = clients.get_mut(entity) {
-            client.write_packet(&CloseScreenS2c {
-                window_id: inv_state.window_id,
-            })
+        if let Ok((mut client, mut inv_state, open_inventory)) = clients.get_mut(entity) {
+            if open_inventory.is_some() {
+                // Another inventory was opened in the same tick, which replaced the window.
+                continue;
+            }
+
+            if !inv_state.window_closed {
+                client.write_packet(&CloseScreenS2c {
+                    window_id: inv_state.window_id,
+                });
+            }
+
+            inv_state.window_closed = false;
+            inv_state.drag = DragState::default();
         }
     }
 }
@@ -748,24 +1070,86 @@ pub struct ClickSlot {
     pub carried_item: Option<ItemStack>,
 }
 
+/// Sent when a client drops items, either with the drop key or by clicking
+/// outside of its inventory window. The dropped items have already been
+/// removed from the inventory or cursor of the client.
+///
+/// No item entity is spawned for the dropped items. Use [`spawn_item_entity`]
+/// to spawn one.
 #[derive(Clone, Debug)]
-pub struct DropItemStack {
+pub struct DropItemEvent {
     pub client: Entity,
+    /// The slot of the player inventory the items were dropped from, or `None`
+    /// if they were dropped from the cursor.
     pub from_slot: Option<u16>,
     pub stack: ItemStack,
+    /// Whether the client dropped the entire stack, rather than a single item.
+    pub full_stack: bool,
+}
+
+/// Spawns an item entity holding `stack` in `instance`.
+///
+/// To throw the item like a player dropping it, spawn the entity at
+/// [`ITEM_DROP_HEIGHT`] above the feet of the player with the velocity from
+/// [`item_drop_velocity`].
+pub fn spawn_item_entity(
+    commands: &mut Commands,
+    instance: Entity,
+    position: Position,
+    velocity: Velocity,
+    stack: ItemStack,
+) -> Entity {
+    commands
+        .spawn(ItemEntityBundle {
+            item_stack: item::Stack(stack),
+            location: Location(instance),
+            position,
+            velocity,
+            ..Default::default()
+        })
+        .id()
+}
+
+/// The height above the feet of a player at which items dropped by the player
+/// appear.
+pub const ITEM_DROP_HEIGHT: f64 = 1.32;
+
+/// Gets the velocity of items thrown by a player looking in the direction of
+/// `look`.
+pub fn item_drop_velocity(look: Look) -> Velocity {
+    let mut velocity = look.vec() * 0.3;
+    velocity.y += 0.1;
+
+    // The velocity above is in blocks per tick.
+    Velocity(velocity * DEFAULT_TPS.get() as f32)
+}
+
+type ClickSlotClientQuery = (
+    &'static mut Client,
+    &'static mut Inventory,
+    &'static mut ClientInventoryState,
+    Option<&'static mut OpenInventory>,
+    &'static mut CursorItem,
+    &'static GameMode,
+    &'static HeldItem,
+);
+
+/// The state of windows with special slots, needed to predict the outcome of
+/// clicks in them.
+#[derive(SystemParam)]
+struct WindowQueries<'w, 's> {
+    trade_lists: Query<'w, 's, &'static mut TradeList>,
+    anvils: Query<'w, 's, &'static mut AnvilState>,
+    experience: Query<'w, 's, ExperienceQuery>,
+    recipes: Res<'w, RecipeRegistry>,
 }
 
 fn handle_click_slot(
     mut packets: EventReader<PacketEvent>,
-    mut clients: Query<(
-        &mut Client,
-        &mut Inventory,
-        &mut ClientInventoryState,
-        Option<&mut OpenInventory>,
-        &mut CursorItem,
-    )>,
+    mut clients: Query<ClickSlotClientQuery>,
     mut inventories: Query<&mut Inventory, Without<Client>>,
-    mut drop_item_stack_events: EventWriter<DropItemStack>,
+    mut windows: WindowQueries,
+    mut drop_item_events: EventWriter<DropItemEvent>,
     mut click_slot_events: EventWriter<ClickSlot>,
 ) {
     for packet in packets.iter() {
@@ -778,146 +1162,134 @@ fn handle_click_slot(
             mut client,
             mut client_inv,
             mut inv_state,
-            open_inventory,
-            mut cursor_item
+            mut open_inventory,
+            mut cursor_item,
+            game_mode,
+            held_item,
         )) = clients.get_mut(packet.client) else {
             // The client does not exist, ignore.
             continue;
         };
 
-        let open_inv = open_inventory
+        let mut open_inv = open_inventory
             .as_ref()
             .and_then(|open| inventories.get_mut(open.entity).ok());
 
-        if let Err(e) = validate::validate_click_slot_packet(
+        let trade_list = open_inventory
+            .as_ref()
+            .and_then(|open| windows.trade_lists.get(open.entity).ok());
+
+        let anvil = open_inventory
+            .as_ref()
+            .and_then(|open| windows.anvils.get(open.entity).ok());
+
+        let window_state = WindowState {
+            trades: trade_list.map_or(&[][..], |list| list.trades.as_slice()),
+            selected_trade: open_inventory
+                .as_ref()
+                .map_or(0, |open| open.selected_trade),
+            level_cost: anvil.map_or(0, |anvil| anvil.level_cost),
+            repair_item_usage: anvil.map_or(0, |anvil| anvil.repair_item_usage),
+            experience_level: windows
+                .experience
+                .get(packet.client)
+                .map_or(0, |exp| exp.level.0),
+            recipes: Some(&windows.recipes),
+            held_item: held_item.hotbar_idx(),
+        };
+
+        let outcome = match validate::validate_click_slot_packet(
             &pkt,
             &client_inv,
             open_inv.as_deref(),
             &cursor_item,
+            &inv_state.drag,
+            window_state,
+            *game_mode == GameMode::Creative,
         ) {
-            debug!(
-                "failed to validate click slot packet for client {:#?}: \"{e:#}\" {pkt:#?}",
-                packet.client
-            );
-
-            // Resync the inventory.
-
-            client.write_packet(&InventoryS2c {
-                window_id: if open_inv.is_some() {
-                    inv_state.window_id
+            Ok(outcome) if inv_state.state_id.0 == pkt.state_id.0 => outcome,
+            result => {
+                if let Err(e) = result {
+                    debug!(
+                        "failed to validate click slot packet for client {:#?}: \"{e:#}\" {pkt:#?}",
+                        packet.client
+                    );
                 } else {
-                    0
-                },
-                state_id: VarInt(inv_state.state_id.0),
-                slots: Cow::Borrowed(open_inv.unwrap_or(client_inv).slot_slice()),
-                carried_item: Cow::Borrowed(&cursor_item.0),
-            });
+                    // Client is out of sync.
+                    debug!("Client state id mismatch, resyncing");
+                }
 
-            continue;
-        }
+                // Resync the inventory and ignore the click.
 
-        if pkt.slot_idx < 0 && pkt.mode == ClickMode::Click {
-            // The client is dropping the cursor item by clicking outside the window.
+                inv_state.state_id += 1;
+                inv_state.drag = DragState::default();
 
-            if let Some(stack) = cursor_item.0.take() {
-                drop_item_stack_events.send(DropItemStack {
-                    client: packet.client,
-                    from_slot: None,
-                    stack,
+                client.write_packet(&InventoryS2c {
+                    window_id: if open_inv.is_some() {
+                        inv_state.window_id
+                    } else {
+                        0
+                    },
+                    state_id: VarInt(inv_state.state_id.0),
+                    slots: Cow::Borrowed(open_inv.unwrap_or(client_inv).slot_slice()),
+                    carried_item: Cow::Borrowed(&cursor_item.0),
                 });
-            }
-        } else if pkt.mode == ClickMode::DropKey {
-            // The client is dropping an item by pressing the drop key.
 
-            let entire_stack = pkt.button == 1;
-
-            // Needs to open the inventory for if the player is dropping an item while
-            // having an inventory open.
-            if let Some(open_inventory) = open_inventory {
-                // The player is interacting with an inventory that is open.
+                continue;
+            }
+        };
 
-                let Ok(mut target_inventory) = inventories.get_mut(open_inventory.entity) else {
-                    // The inventory does not exist, ignore.
-                    continue;
-                };
+        let Some(outcome) = outcome else {
+            // The outcome of clicks in this window can't be predicted, so the changes of
+            // the client are used as is.
+            let (Some(mut open_inventory), Some(mut target_inventory)) = (open_inventory, open_inv) else {
+                continue;
+            };
 
-                if inv_state.state_id.0 != pkt.state_id.0 {
-                    // Client is out of sync. Resync and ignore click.
+            if pkt.slot_idx < 0 && pkt.mode == ClickMode::Click {
+                // The client is dropping the cursor item by clicking outside the window.
 
-                    debug!("Client state id mismatch, resyncing");
+                let full_stack = pkt.button == 0;
 
-                    inv_state.state_id += 1;
+                if let Some(mut stack) = cursor_item.0.take() {
+                    if !full_stack && stack.count() > 1 {
+                        cursor_item.0 = Some(stack.clone().with_count(stack.count() - 1));
+                        stack.set_count(1);
+                    }
 
-                    client.write_packet(&InventoryS2c {
-                        window_id: inv_state.window_id,
-                        state_id: VarInt(inv_state.state_id.0),
-                        slots: Cow::Borrowed(target_inventory.slot_slice()),
-                        carried_item: Cow::Borrowed(&cursor_item.0),
+                    drop_item_events.send(DropItemEvent {
+                        client: packet.client,
+                        from_slot: None,
+                        stack,
+                        full_stack,
                     });
-
-                    continue;
                 }
 
-                if (0i16..target_inventory.slot_count() as i16).contains(&pkt.slot_idx) {
-                    // The player is dropping an item from another inventory.
+                continue;
+            }
 
-                    if let Some(stack) = target_inventory.slot(pkt.slot_idx as u16) {
-                        let dropped = if entire_stack || stack.count() == 1 {
-                            target_inventory.replace_slot(pkt.slot_idx as u16, None)
-                        } else {
-                            let mut stack = stack.clone();
-                            stack.set_count(stack.count() - 1);
-                            let mut old_slot =
-                                target_inventory.replace_slot(pkt.slot_idx as u16, Some(stack));
-                            // we already checked that the slot was not empty and that the
-                            // stack count is > 1
-                            old_slot.as_mut().unwrap().set_count(1);
-                            old_slot
-                        }
-                        .expect("dropped item should exist"); // we already checked that the slot was not empty
+            if pkt.mode == ClickMode::DropKey {
+                // The client is dropping an item by pressing the drop key.
 
-                        drop_item_stack_events.send(DropItemStack {
-                            client: packet.client,
-                            from_slot: Some(pkt.slot_idx as u16),
-                            stack: dropped,
-                        });
-                    }
+                let entire_stack = pkt.button == 1;
+
+                let (inv, slot_id) = if pkt.slot_idx < target_inventory.slot_count() as i16 {
+                    // The player is dropping an item from another inventory.
+                    (&mut *target_inventory, pkt.slot_idx as u16)
                 } else {
                     // The player is dropping an item from their inventory.
                     let slot_id =
                         convert_to_player_slot_id(target_inventory.kind, pkt.slot_idx as u16);
-                    if let Some(stack) = client_inv.slot(slot_id) {
-                        let dropped = if entire_stack || stack.count() == 1 {
-                            client_inv.replace_slot(slot_id, None)
-                        } else {
-                            let mut stack = stack.clone();
-                            stack.set_count(stack.count() - 1);
-                            let mut old_slot = client_inv.replace_slot(slot_id, Some(stack));
-                            // we already checked that the slot was not empty and that the
-                            // stack count is > 1
-                            old_slot.as_mut().unwrap().set_count(1);
-                            old_slot
-                        }
-                        .expect("dropped item should exist"); // we already checked that the slot was not empty
+                    (&mut *client_inv, slot_id)
+                };
 
-                        drop_item_stack_events.send(DropItemStack {
-                            client: packet.client,
-                            from_slot: Some(slot_id),
-                            stack: dropped,
-                        });
-                    }
-                }
-            } else {
-                // The player has no inventory open and is dropping an item from their
-                // inventory.
-                if let Some(stack) = client_inv.slot(pkt.slot_idx as u16) {
+                if let Some(stack) = inv.slot(slot_id) {
                     let dropped = if entire_stack || stack.count() == 1 {
-                        client_inv.replace_slot(pkt.slot_idx as u16, None)
+                        inv.replace_slot(slot_id, None)
                     } else {
                         let mut stack = stack.clone();
                         stack.set_count(stack.count() - 1);
-                        let mut old_slot =
-                            client_inv.replace_slot(pkt.slot_idx as u16, Some(stack));
+                        let mut old_slot = inv.replace_slot(slot_id, Some(stack));
                         // we already checked that the slot was not empty and that the
                         // stack count is > 1
                         old_slot.as_mut().unwrap().set_count(1);
@@ -925,105 +1297,148 @@ fn handle_click_slot(
                     }
                     .expect("dropped item should exist"); // we already checked that the slot was not empty
 
-                    drop_item_stack_events.send(DropItemStack {
+                    drop_item_events.send(DropItemEvent {
                         client: packet.client,
-                        from_slot: Some(pkt.slot_idx as u16),
+                        from_slot: Some(slot_id),
                         stack: dropped,
+                        full_stack: entire_stack,
                     });
                 }
-            }
-        } else {
-            // The player is clicking a slot in an inventory.
-
-            // Validate the window id.
-            if (pkt.window_id == 0) != open_inventory.is_none() {
-                warn!(
-                    "Client sent a click with an invalid window id for current state: window_id = \
-                     {}, open_inventory present = {}",
-                    pkt.window_id,
-                    open_inventory.is_some()
-                );
+
                 continue;
             }
 
-            if let Some(mut open_inventory) = open_inventory {
-                // The player is interacting with an inventory that is open.
-
-                let Ok(mut target_inventory) = inventories.get_mut(open_inventory.entity) else {
-                    // The inventory does not exist, ignore.
-                    continue;
-                };
+            cursor_item.set_if_neq(CursorItem(pkt.carried_item.clone()));
 
-                if inv_state.state_id.0 != pkt.state_id.0 {
-                    // Client is out of sync. Resync and ignore click.
+            for slot in pkt.slot_changes.clone() {
+                if (0i16..target_inventory.slot_count() as i16).contains(&slot.idx) {
+                    // The client is interacting with a slot in the target inventory.
+                    target_inventory.set_slot(slot.idx as u16, slot.item);
+                    open_inventory.client_changed |= 1 << slot.idx;
+                } else {
+                    // The client is interacting with a slot in their own inventory.
+                    let slot_id = convert_to_player_slot_id(target_inventory.kind, slot.idx as u16);
+                    client_inv.set_slot(slot_id, slot.item);
+                    inv_state.slots_changed |= 1 << slot_id;
+                }
+            }
 
-                    debug!("Client state id mismatch, resyncing");
+            click_slot_events.send(ClickSlot {
+                client: packet.client,
+                window_id: pkt.window_id,
+                state_id: pkt.state_id.0,
+                slot_id: pkt.slot_idx,
+                button: pkt.button,
+                mode: pkt.mode,
+                slot_changes: pkt.slot_changes,
+                carried_item: pkt.carried_item,
+            });
 
-                    inv_state.state_id += 1;
+            continue;
+        };
 
-                    client.write_packet(&InventoryS2c {
-                        window_id: inv_state.window_id,
-                        state_id: VarInt(inv_state.state_id.0),
-                        slots: Cow::Borrowed(target_inventory.slot_slice()),
-                        carried_item: Cow::Borrowed(&cursor_item.0),
-                    });
+        // The client predicted the outcome of the click correctly, so it doesn't need
+        // to be sent any updates.
 
-                    continue;
-                }
+        // Gets the inventory and slot a window slot is in.
+        let player_slot = |open_inv: &Option<Mut<Inventory>>, idx: u16| match open_inv {
+            Some(target_inventory) if idx >= target_inventory.slot_count() => {
+                Some(convert_to_player_slot_id(target_inventory.kind, idx))
+            }
+            Some(_) => None,
+            None => Some(idx),
+        };
 
-                cursor_item.set_if_neq(CursorItem(pkt.carried_item.clone()));
+        for (idx, stack) in outcome.slots {
+            if outcome.recomputed_slot == Some(idx) {
+                // The result is computed again by the server.
+                continue;
+            }
 
-                for slot in pkt.slot_changes.clone() {
-                    if (0i16..target_inventory.slot_count() as i16).contains(&slot.idx) {
-                        // The client is interacting with a slot in the target inventory.
-                        target_inventory.set_slot(slot.idx as u16, slot.item);
-                        open_inventory.client_changed |= 1 << slot.idx;
-                    } else {
-                        // The client is interacting with a slot in their own inventory.
-                        let slot_id =
-                            convert_to_player_slot_id(target_inventory.kind, slot.idx as u16);
-                        client_inv.set_slot(slot_id, slot.item);
-                        inv_state.slots_changed |= 1 << slot_id;
+            match player_slot(&open_inv, idx) {
+                Some(slot_id) => {
+                    client_inv.set_slot(slot_id, stack);
+                    inv_state.slots_changed |= 1 << slot_id;
+                }
+                None => {
+                    open_inv.as_mut().unwrap().set_slot(idx, stack);
+                    if let Some(open_inventory) = &mut open_inventory {
+                        open_inventory.client_changed |= 1 << idx;
                     }
                 }
-            } else {
-                // The client is interacting with their own inventory.
-
-                if inv_state.state_id.0 != pkt.state_id.0 {
-                    // Client is out of sync. Resync and ignore the click.
-
-                    debug!("Client state id mismatch, resyncing");
-
-                    inv_state.state_id += 1;
+            }
+        }
 
-                    client.write_packet(&InventoryS2c {
-                        window_id: inv_state.window_id,
-                        state_id: VarInt(inv_state.state_id.0),
-                        slots: Cow::Borrowed(client_inv.slot_slice()),
-                        carried_item: Cow::Borrowed(&cursor_item.0),
-                    });
+        if let Some(offhand) = outcome.offhand {
+            client_inv.set_slot(click::OFFHAND_SLOT, offhand);
+            inv_state.slots_changed |= 1 << click::OFFHAND_SLOT;
+        }
 
-                    continue;
+        // The client doesn't predict the crafts made after the first one, so these
+        // slots are sent to it.
+        for (idx, stack) in outcome.crafted_slots {
+            match player_slot(&open_inv, idx) {
+                Some(slot_id) => {
+                    client_inv.set_slot(slot_id, stack);
+                    inv_state.slots_changed &= !(1 << slot_id);
                 }
+                None => {
+                    open_inv.as_mut().unwrap().set_slot(idx, stack);
+                    if let Some(open_inventory) = &mut open_inventory {
+                        open_inventory.client_changed &= !(1 << idx);
+                    }
+                }
+            }
+        }
 
-                cursor_item.set_if_neq(CursorItem(pkt.carried_item.clone()));
-                inv_state.client_updated_cursor_item = true;
+        if !outcome.trades.is_empty() {
+            if let Some(mut trade_list) = open_inventory
+                .as_ref()
+                .and_then(|open| windows.trade_lists.get_mut(open.entity).ok())
+            {
+                // The client counts the uses of trades itself, so the trades don't need to be
+                // sent again.
+                let trade_list = trade_list.bypass_change_detection();
+
+                for &idx in &outcome.trades {
+                    trade_list.trades[idx].uses += 1;
+                }
+            }
+        }
 
-                for slot in pkt.slot_changes.clone() {
-                    if (0i16..client_inv.slot_count() as i16).contains(&slot.idx) {
-                        client_inv.set_slot(slot.idx as u16, slot.item);
-                        inv_state.slots_changed |= 1 << slot.idx;
-                    } else {
-                        // The client is trying to interact with a slot that does not exist,
-                        // ignore.
-                        warn!(
-                            "Client attempted to interact with slot {} which does not exist",
-                            slot.idx
-                        );
+        if outcome.used_anvil {
+            if let Some(mut anvil) = open_inventory
+                .as_ref()
+                .and_then(|open| windows.anvils.get_mut(open.entity).ok())
+            {
+                if *game_mode != GameMode::Creative {
+                    if let Ok(mut exp) = windows.experience.get_mut(packet.client) {
+                        exp.add_levels(-anvil.level_cost);
                     }
                 }
+
+                anvil.level_cost = 0;
             }
+        }
+
+        cursor_item.set_if_neq(CursorItem(outcome.cursor));
+        inv_state.client_updated_cursor_item = true;
+        inv_state.drag = outcome.drag;
+
+        if let Some((from_slot, stack)) = outcome.dropped {
+            let full_stack = if pkt.mode == ClickMode::DropKey {
+                pkt.button == 1
+            } else {
+                pkt.button == 0
+            };
 
+            drop_item_events.send(DropItemEvent {
+                client: packet.client,
+                from_slot: from_slot.map(|idx| player_slot(&open_inv, idx).unwrap_or(idx)),
+                stack,
+                full_stack,
+            });
+        } else if pkt.mode != ClickMode::DropKey && pkt.slot_idx >= 0 {
             click_slot_events.send(ClickSlot {
                 client: packet.client,
                 window_id: pkt.window_id,
@@ -1038,10 +1453,301 @@ fn handle_click_slot(
     }
 }
 
+/// Handles clients selecting a trade in the merchant screen, which moves the
+/// items of the trade into the input slots.
+fn handle_select_merchant_trade(
+    mut packets: EventReader<PacketEvent>,
+    mut clients: Query<
+        (
+            &mut Inventory,
+            &mut ClientInventoryState,
+            &mut OpenInventory,
+            &CursorItem,
+        ),
+        With<Client>,
+    >,
+    mut inventories: Query<&mut Inventory, Without<Client>>,
+    trade_lists: Query<&TradeList>,
+    mut select_trade_events: EventWriter<SelectMerchantTrade>,
+) {
+    for packet in packets.iter() {
+        let Some(pkt) = packet.decode::<SelectMerchantTradeC2s>() else {
+            continue
+        };
+
+        let Ok((mut client_inv, mut inv_state, mut open_inventory, cursor_item)) = clients.get_mut(packet.client) else {
+            continue
+        };
+
+        let Ok(trade) = usize::try_from(pkt.selected_slot.0) else {
+            continue
+        };
+
+        let (Ok(mut target_inventory), Ok(trade_list)) = (
+            inventories.get_mut(open_inventory.entity),
+            trade_lists.get(open_inventory.entity),
+        ) else {
+            continue
+        };
+
+        let Some(outcome) = click::simulate_select_trade(
+            &client_inv,
+            &target_inventory,
+            cursor_item.0.as_ref(),
+            &trade_list.trades,
+            trade,
+        ) else {
+            continue
+        };
+
+        open_inventory.selected_trade = trade;
+
+        // The client moves the items itself, like when clicking.
+        for (idx, stack) in outcome.slots {
+            if idx < target_inventory.slot_count() {
+                target_inventory.set_slot(idx, stack);
+                open_inventory.client_changed |= 1 << idx;
+            } else {
+                let slot_id = convert_to_player_slot_id(target_inventory.kind, idx);
+                client_inv.set_slot(slot_id, stack);
+                inv_state.slots_changed |= 1 << slot_id;
+            }
+        }
+
+        if trade < trade_list.trades.len() {
+            select_trade_events.send(SelectMerchantTrade {
+                client: packet.client,
+                trade,
+            });
+        }
+    }
+}
+
+/// Handles clients typing a name into the text field of the anvil screen.
+fn handle_rename_item(
+    mut packets: EventReader<PacketEvent>,
+    clients: Query<&OpenInventory>,
+    mut anvils: Query<&mut AnvilState>,
+    mut rename_item_events: EventWriter<RenameItem>,
+) {
+    for packet in packets.iter() {
+        let Some(pkt) = packet.decode::<RenameItemC2s>() else {
+            continue
+        };
+
+        let Ok(open_inventory) = clients.get(packet.client) else {
+            continue
+        };
+
+        let Ok(mut anvil) = anvils.get_mut(open_inventory.entity) else {
+            continue
+        };
+
+        let name = anvil::strip_invalid_chars(pkt.item_name);
+
+        // Vanilla ignores names which are too long.
+        if name.chars().count() > 50 {
+            continue;
+        }
+
+        anvil.item_name.clone_from(&name);
+
+        rename_item_events.send(RenameItem {
+            client: packet.client,
+            name,
+        });
+    }
+}
+
+/// Adds the [`AnvilState`] to new anvil inventories, and keeps track of the
+/// crafting grid of new inventories which have one.
+fn init_window_states(
+    inventories: Query<(Entity, &Inventory), Added<Inventory>>,
+    mut commands: Commands,
+) {
+    for (entity, inventory) in &inventories {
+        if inventory.kind == InventoryKind::Anvil {
+            commands.entity(entity).insert(AnvilState::default());
+        }
+
+        if let Some((grid, _)) = crafting_grid(inventory.kind) {
+            let grid = &inventory.slot_slice()[grid.start as usize..grid.end as usize];
+            commands.entity(entity).insert(CraftingState::new(grid));
+        }
+    }
+}
+
+/// Computes the result of anvils when their inputs or name changed.
+fn update_anvil_results(
+    mut anvils: Query<(Entity, &mut Inventory, &mut AnvilState)>,
+    mut update_anvil_events: EventWriter<UpdateAnvilResult>,
+) {
+    for (entity, mut inventory, mut anvil) in &mut anvils {
+        let inputs = [inventory.slot(0), inventory.slot(1)];
+
+        if !anvil.is_outdated(inputs) {
+            continue;
+        }
+
+        let result = anvil.compute_result(inputs);
+
+        inventory.set_slot(2, result);
+        // The client may have computed a different result, so the result is always
+        // sent.
+        inventory.changed |= 1 << 2;
+
+        update_anvil_events.send(UpdateAnvilResult { inventory: entity });
+    }
+}
+
+/// Puts the result of the recipe matching the crafting grid into the result
+/// slot when the grid changed.
+fn update_crafting_results(
+    mut inventories: Query<(&mut Inventory, &mut CraftingState)>,
+    recipes: Res<RecipeRegistry>,
+) {
+    for (mut inventory, mut state) in &mut inventories {
+        let Some((grid, width)) = crafting_grid(inventory.kind) else {
+            continue;
+        };
+
+        let grid = &inventory.slot_slice()[grid.start as usize..grid.end as usize];
+
+        if !state.is_outdated(grid) {
+            continue;
+        }
+
+        let result = state.compute_result(grid, width, &recipes);
+
+        inventory.set_slot(0, result);
+        // The client doesn't compute results, and may have taken the last one.
+        inventory.changed |= 1;
+    }
+}
+
+/// Sends the recipes to joining clients and unlocks them in the recipe book.
+/// The recipes are sent to every client again when they changed.
+fn send_recipes(mut clients: Query<&mut Client>, recipes: Res<RecipeRegistry>) {
+    let mut bytes = None;
+
+    for mut client in &mut clients {
+        if !recipes.is_changed() && !client.is_added() {
+            continue;
+        }
+
+        if bytes.is_none() {
+            match recipes.encode_recipes() {
+                Ok(encoded) => bytes = Some(encoded),
+                Err(e) => {
+                    warn!("failed to encode recipes: {e:#}");
+                    return;
+                }
+            }
+        }
+
+        client.write_packet(&SynchronizeRecipesS2c {
+            recipes: RawBytes(bytes.as_deref().unwrap()),
+        });
+
+        client.write_packet(&UnlockRecipesS2c {
+            action: UpdateRecipeBookAction::Init { recipe_ids: vec![] },
+            crafting_recipe_book_open: false,
+            crafting_recipe_book_filter_active: false,
+            smelting_recipe_book_open: false,
+            smelting_recipe_book_filter_active: false,
+            blast_furnace_recipe_book_open: false,
+            blast_furnace_recipe_book_filter_active: false,
+            smoker_recipe_book_open: false,
+            smoker_recipe_book_filter_active: false,
+            recipe_ids: recipes.recipe_ids(),
+        });
+    }
+}
+
+/// Handles clients clicking a recipe in the recipe book, which moves the
+/// ingredients of the recipe into the crafting grid.
+fn handle_craft_request(
+    mut packets: EventReader<PacketEvent>,
+    mut clients: Query<(
+        &mut Client,
+        &mut Inventory,
+        &ClientInventoryState,
+        Option<&OpenInventory>,
+        &HeldItem,
+    )>,
+    mut inventories: Query<&mut Inventory, Without<Client>>,
+    recipes: Res<RecipeRegistry>,
+) {
+    for packet in packets.iter() {
+        let Some(pkt) = packet.decode::<CraftRequestC2s>() else {
+            continue
+        };
+
+        let Ok((mut client, mut client_inv, inv_state, open_inventory, held_item)) = clients.get_mut(packet.client) else {
+            continue
+        };
+
+        let mut open_inv = match open_inventory {
+            Some(open) => match inventories.get_mut(open.entity) {
+                Ok(inv) => Some(inv),
+                Err(_) => continue,
+            },
+            None => None,
+        };
+
+        let window_id = if open_inv.is_some() {
+            inv_state.window_id
+        } else {
+            0
+        };
+
+        if pkt.window_id as u8 != window_id {
+            continue;
+        }
+
+        let Some(recipe) = recipes.get(pkt.recipe.as_str_ident()).filter(|r| r.is_crafting()) else {
+            continue
+        };
+
+        let Some((outcome, placed)) = click::simulate_place_recipe(
+            &client_inv,
+            open_inv.as_deref(),
+            recipe,
+            &recipes,
+            held_item.hotbar_idx(),
+            pkt.make_all,
+        ) else {
+            continue
+        };
+
+        // The client doesn't move the items itself, so the changed slots are sent to
+        // it.
+        for (idx, stack) in outcome.slots {
+            match &mut open_inv {
+                Some(inv) if idx < inv.slot_count() => inv.set_slot(idx, stack),
+                Some(inv) => client_inv.set_slot(convert_to_player_slot_id(inv.kind, idx), stack),
+                None => client_inv.set_slot(idx, stack),
+            }
+        }
+
+        if let Some(offhand) = outcome.offhand {
+            client_inv.set_slot(click::OFFHAND_SLOT, offhand);
+        }
+
+        if !placed {
+            // Shows the recipe in the crafting grid without the items.
+            client.write_packet(&CraftFailedResponseS2c {
+                window_id,
+                recipe: pkt.recipe,
+            });
+        }
+    }
+}
+
 fn handle_player_actions(
     mut packets: EventReader<PacketEvent>,
-    mut clients: Query<(&mut Inventory, &mut ClientInventoryState)>,
-    mut drop_item_stack_events: EventWriter<DropItemStack>,
+    mut clients: Query<(&mut Inventory, &mut ClientInventoryState, &HeldItem)>,
+    mut drop_item_events: EventWriter<DropItemEvent>,
 ) {
     for packet in packets.iter() {
         if let Some(pkt) = packet.decode::<PlayerActionC2s>() {
@@ -1049,37 +1755,40 @@ fn handle_player_actions(
 
             match pkt.action {
                 Action::DropAllItems => {
-                    if let Ok((mut inv, mut inv_state)) = clients.get_mut(packet.client) {
-                        if let Some(stack) = inv.replace_slot(inv_state.held_item_slot, None) {
-                            inv_state.slots_changed |= 1 << inv_state.held_item_slot;
+                    if let Ok((mut inv, mut inv_state, held_item)) = clients.get_mut(packet.client)
+                    {
+                        if let Some(stack) = inv.replace_slot(held_item.slot(), None) {
+                            inv_state.slots_changed |= 1 << held_item.slot();
 
-                            drop_item_stack_events.send(DropItemStack {
+                            drop_item_events.send(DropItemEvent {
                                 client: packet.client,
-                                from_slot: Some(inv_state.held_item_slot),
+                                from_slot: Some(held_item.slot()),
                                 stack,
+                                full_stack: true,
                             });
                         }
                     }
                 }
                 Action::DropItem => {
-                    if let Ok((mut inv, mut inv_state)) = clients.get_mut(packet.client) {
-                        if let Some(mut stack) = inv.replace_slot(inv_state.held_item_slot(), None)
-                        {
+                    if let Ok((mut inv, mut inv_state, held_item)) = clients.get_mut(packet.client)
+                    {
+                        if let Some(mut stack) = inv.replace_slot(held_item.slot(), None) {
                             if stack.count() > 1 {
                                 inv.set_slot(
-                                    inv_state.held_item_slot(),
+                                    held_item.slot(),
                                     stack.clone().with_count(stack.count() - 1),
                                 );
 
                                 stack.set_count(1);
                             }
 
-                            inv_state.slots_changed |= 1 << inv_state.held_item_slot();
+                            inv_state.slots_changed |= 1 << held_item.slot();
 
-                            drop_item_stack_events.send(DropItemStack {
+                            drop_item_events.send(DropItemEvent {
                                 client: packet.client,
-                                from_slot: Some(inv_state.held_item_slot()),
+                                from_slot: Some(held_item.slot()),
                                 stack,
+                                full_stack: false,
                             })
                         }
                     }
@@ -1093,12 +1802,40 @@ fn handle_player_actions(
     }
 }
 
-// TODO: make this event user friendly.
+/// Sent when a client in creative mode sets a slot of its inventory to an
+/// arbitrary item.
+///
+/// The new item has already been put in the slot when this event is read. To
+/// veto the change, set the slot back to `previous` with
+/// [`Inventory::set_slot`], which will send the slot to the client again.
 #[derive(Clone, Debug)]
-pub struct CreativeInventoryAction {
+pub struct CreativeItemSetEvent {
     pub client: Entity,
-    pub slot: i16,
-    pub clicked_item: Option<ItemStack>,
+    /// The index of the slot in the player inventory.
+    pub slot: u16,
+    /// The item which was in the slot before the change.
+    pub previous: Option<ItemStack>,
+    /// The item which is now in the slot.
+    pub item: Option<ItemStack>,
+}
+
+/// Returns the stack a creative mode client is allowed to create from
+/// `stack`, or `None` if the stack is invalid.
+fn validate_creative_stack(
+    mut stack: ItemStack,
+    settings: &InventorySettings,
+) -> Option<ItemStack> {
+    if stack.count() > stack.item.max_stack() {
+        return None;
+    }
+
+    if let (Some(max_size), Some(nbt)) = (settings.max_creative_nbt_size, &stack.nbt) {
+        if nbt.written_size("") > max_size {
+            stack.nbt = None;
+        }
+    }
+
+    Some(stack)
 }
 
 fn handle_creative_inventory_action(
@@ -1109,8 +1846,9 @@ fn handle_creative_inventory_action(
         &mut ClientInventoryState,
         &GameMode,
     )>,
-    mut inv_action_events: EventWriter<CreativeInventoryAction>,
-    mut drop_item_stack_events: EventWriter<DropItemStack>,
+    settings: Res<InventorySettings>,
+    mut creative_item_set_events: EventWriter<CreativeItemSetEvent>,
+    mut drop_item_events: EventWriter<DropItemEvent>,
 ) {
     for packet in packets.iter() {
         if let Some(pkt) = packet.decode::<CreativeInventoryActionC2s>() {
@@ -1123,12 +1861,34 @@ fn handle_creative_inventory_action(
                 continue;
             }
 
+            let clicked_item = match pkt.clicked_item {
+                Some(stack) => match validate_creative_stack(stack, &settings) {
+                    Some(stack) => Some(stack),
+                    None => {
+                        // The client created an invalid stack, resync the slot.
+                        if pkt.slot >= 0 && pkt.slot < inventory.slot_count() as i16 {
+                            inv_state.state_id += 1;
+
+                            client.write_packet(&ScreenHandlerSlotUpdateS2c {
+                                window_id: 0,
+                                state_id: VarInt(inv_state.state_id.0),
+                                slot_idx: pkt.slot,
+                                slot_data: Cow::Borrowed(&inventory.slots[pkt.slot as usize]),
+                            });
+                        }
+                        continue;
+                    }
+                },
+                None => None,
+            };
+
             if pkt.slot == -1 {
-                if let Some(stack) = pkt.clicked_item.clone() {
-                    drop_item_stack_events.send(DropItemStack {
+                if let Some(stack) = clicked_item {
+                    drop_item_events.send(DropItemEvent {
                         client: packet.client,
                         from_slot: None,
                         stack,
+                        full_stack: true,
                     });
                 }
                 continue;
@@ -1140,7 +1900,10 @@ fn handle_creative_inventory_action(
             }
 
             // Set the slot without marking it as changed.
-            inventory.slots[pkt.slot as usize] = pkt.clicked_item.clone();
+            let previous = std::mem::replace(
+                &mut inventory.slots[pkt.slot as usize],
+                clicked_item.clone(),
+            );
 
             inv_state.state_id += 1;
 
@@ -1152,39 +1915,57 @@ fn handle_creative_inventory_action(
                 window_id: 0,
                 state_id: VarInt(inv_state.state_id.0),
                 slot_idx: pkt.slot,
-                slot_data: Cow::Borrowed(&pkt.clicked_item),
+                slot_data: Cow::Borrowed(&clicked_item),
             });
 
-            inv_action_events.send(CreativeInventoryAction {
+            creative_item_set_events.send(CreativeItemSetEvent {
                 client: packet.client,
-                slot: pkt.slot,
-                clicked_item: pkt.clicked_item,
+                slot: pkt.slot as u16,
+                previous,
+                item: clicked_item,
             });
         }
     }
 }
 
+/// Sent when a client selects another slot of its hotbar. This is not sent when
+/// the client selects the slot the server changed its [`HeldItem`] to.
 #[derive(Clone, Debug)]
 pub struct UpdateSelectedSlot {
     pub client: Entity,
+    /// The index of the selected slot in the hotbar, between 0 and 8.
     pub slot: i16,
 }
 
 fn handle_update_selected_slot(
     mut packets: EventReader<PacketEvent>,
-    mut clients: Query<&mut ClientInventoryState>,
+    mut clients: Query<(&mut HeldItem, &mut ClientInventoryState)>,
     mut events: EventWriter<UpdateSelectedSlot>,
 ) {
     for packet in packets.iter() {
         if let Some(pkt) = packet.decode::<UpdateSelectedSlotC2s>() {
-            if let Ok(mut inv_state) = clients.get_mut(packet.client) {
-                // TODO: validate this.
-                inv_state.held_item_slot = convert_hotbar_slot_id(pkt.slot as u16);
+            if let Ok((mut held_item, mut inv_state)) = clients.get_mut(packet.client) {
+                if !(0..=8).contains(&pkt.slot) {
+                    warn!(
+                        "invalid selected slot {} from client {:?}",
+                        pkt.slot, packet.client
+                    );
+                    continue;
+                }
 
-                events.send(UpdateSelectedSlot {
-                    client: packet.client,
-                    slot: pkt.slot,
-                });
+                let slot = convert_hotbar_slot_id(pkt.slot as u16);
+
+                inv_state.client_held_item_slot = slot;
+
+                // Also ignores the client confirming a slot selected by the server.
+                if held_item.slot() != slot {
+                    held_item.held_item_slot = slot;
+
+                    events.send(UpdateSelectedSlot {
+                        client: packet.client,
+                        slot: pkt.slot,
+                    });
+                }
             }
         }
     }
@@ -1225,6 +2006,7 @@ pub enum InventoryKind {
     Loom,
     Merchant,
     ShulkerBox,
+    LegacySmithing,
     Smithing,
     Smoker,
     Cartography,
@@ -1244,7 +2026,7 @@ impl InventoryKind {
             InventoryKind::Generic9x5 => 9 * 5,
             InventoryKind::Generic9x6 => 9 * 6,
             InventoryKind::Generic3x3 => 3 * 3,
-            InventoryKind::Anvil => 4,
+            InventoryKind::Anvil => 3,
             InventoryKind::Beacon => 1,
             InventoryKind::BlastFurnace => 3,
             InventoryKind::BrewingStand => 5,
@@ -1257,7 +2039,8 @@ impl InventoryKind {
             InventoryKind::Loom => 4,
             InventoryKind::Merchant => 3,
             InventoryKind::ShulkerBox => 27,
-            InventoryKind::Smithing => 3,
+            InventoryKind::LegacySmithing => 3,
+            InventoryKind::Smithing => 4,
             InventoryKind::Smoker => 3,
             InventoryKind::Cartography => 3,
             InventoryKind::Stonecutter => 2,
@@ -1289,6 +2072,7 @@ impl From<InventoryKind> for WindowType {
             InventoryKind::Loom => WindowType::Loom,
             InventoryKind::Merchant => WindowType::Merchant,
             InventoryKind::ShulkerBox => WindowType::ShulkerBox,
+            InventoryKind::LegacySmithing => WindowType::LegacySmithing,
             InventoryKind::Smithing => WindowType::Smithing,
             InventoryKind::Smoker => WindowType::Smoker,
             InventoryKind::Cartography => WindowType::Cartography,
@@ -1323,6 +2107,7 @@ impl From<WindowType> for InventoryKind {
             WindowType::Loom => InventoryKind::Loom,
             WindowType::Merchant => InventoryKind::Merchant,
             WindowType::ShulkerBox => InventoryKind::ShulkerBox,
+            WindowType::LegacySmithing => InventoryKind::LegacySmithing,
             WindowType::Smithing => InventoryKind::Smithing,
             WindowType::Smoker => InventoryKind::Smoker,
             WindowType::Cartography => InventoryKind::Cartography,
@@ -1334,12 +2119,17 @@ impl From<WindowType> for InventoryKind {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Resource)]
 pub struct InventorySettings {
     pub validate_actions: bool,
+    /// The largest NBT, in bytes, creative mode clients are allowed to put on
+    /// the items they create. The NBT of larger items is removed. `None`
+    /// means there is no limit.
+    pub max_creative_nbt_size: Option<usize>,
 }
 
 impl Default for InventorySettings {
     fn default() -> Self {
         Self {
             validate_actions: true,
+            max_creative_nbt_size: None,
         }
     }
 }
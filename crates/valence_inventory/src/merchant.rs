@@ -0,0 +1,331 @@
+use bevy_ecs::prelude::*;
+use valence_core::item::ItemStack;
+use valence_core::packet::s2c::play::set_trade_offers::TradeOffer;
+use valence_core::packet::s2c::play::SetTradeOffersS2c;
+use valence_core::packet::var_int::VarInt;
+use valence_nbt::{Compound, Value};
+
+/// The trades of an inventory of kind [`InventoryKind::Merchant`]. Clients
+/// viewing the inventory are shown the trades in the merchant screen, and can
+/// pay for a trade with the items in the two input slots to get its output in
+/// the result slot.
+///
+/// The trades are sent to clients again when this component changes. Clients
+/// keep track of the uses of trades themselves, so the uses counted when
+/// clients trade are updated without triggering change detection.
+///
+/// Like in vanilla, every client should view its own merchant inventory. Items
+/// left in the input slots stay there when the window is closed.
+///
+/// ```
+/// # use bevy_ecs::prelude::*;
+/// # use valence_core::item::{ItemKind, ItemStack};
+/// # use valence_inventory::*;
+/// # fn open_shop(mut commands: Commands, client: Entity) {
+/// let shop = commands
+///     .spawn((
+///         Inventory::with_title(InventoryKind::Merchant, "Shop"),
+///         TradeList {
+///             trades: vec![Trade::new(
+///                 ItemStack::new(ItemKind::Emerald, 3, None),
+///                 ItemStack::new(ItemKind::Bread, 6, None),
+///             )],
+///             ..Default::default()
+///         },
+///     ))
+///     .id();
+///
+/// commands.entity(client).insert(OpenInventory::new(shop));
+/// # }
+/// ```
+///
+/// [`InventoryKind::Merchant`]: crate::InventoryKind::Merchant
+#[derive(Component, Clone, PartialEq, Default, Debug)]
+pub struct TradeList {
+    pub trades: Vec<Trade>,
+    /// The level of the merchant, from 1 (novice) to 5 (master).
+    pub villager_level: i32,
+    /// The experience of the merchant, shown in the experience bar of the
+    /// screen.
+    pub experience: i32,
+    /// Whether the level and the experience bar of the merchant are shown.
+    pub is_regular_villager: bool,
+    /// Whether the tooltip of disabled trades says that they are restocked.
+    pub can_restock: bool,
+}
+
+impl TradeList {
+    pub(crate) fn to_packet(&self, window_id: u8) -> SetTradeOffersS2c {
+        SetTradeOffersS2c {
+            window_id: VarInt(window_id.into()),
+            trades: self.trades.iter().map(Trade::to_offer).collect(),
+            villager_level: VarInt(self.villager_level),
+            experience: VarInt(self.experience),
+            is_regular_villager: self.is_regular_villager,
+            can_restock: self.can_restock,
+        }
+    }
+}
+
+/// A trade offered in the merchant screen.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Trade {
+    /// The first item to pay with. The number of items the player actually
+    /// pays is adjusted by `demand`, `price_multiplier` and `special_price`.
+    pub input_one: ItemStack,
+    pub input_two: Option<ItemStack>,
+    pub output: ItemStack,
+    /// The number of times the trade was used.
+    pub uses: i32,
+    /// The number of times the trade can be used before it is disabled.
+    pub max_uses: i32,
+    /// The experience the merchant gets from the trade.
+    pub xp: i32,
+    /// How much the price goes up with `demand`.
+    pub price_multiplier: f32,
+    /// Added to the price, which is negative for discounts.
+    pub special_price: i32,
+    pub demand: i32,
+}
+
+impl Trade {
+    /// Creates a trade of `input` for `output` which can be used any number of
+    /// times.
+    pub fn new(input: ItemStack, output: ItemStack) -> Self {
+        Self {
+            input_one: input,
+            input_two: None,
+            output,
+            uses: 0,
+            max_uses: i32::MAX,
+            xp: 0,
+            price_multiplier: 0.0,
+            special_price: 0,
+            demand: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_second_input(mut self, input: impl Into<Option<ItemStack>>) -> Self {
+        self.input_two = input.into();
+        self
+    }
+
+    #[must_use]
+    pub fn with_max_uses(mut self, max_uses: i32) -> Self {
+        self.max_uses = max_uses;
+        self
+    }
+
+    /// Returns `true` if the trade was used up.
+    pub fn is_disabled(&self) -> bool {
+        self.uses >= self.max_uses
+    }
+
+    /// Gets the first input with the number of items the player pays, after
+    /// demand and special prices are applied.
+    pub fn adjusted_first_input(&self) -> ItemStack {
+        let base = self.input_one.count() as i32;
+        let demand_bonus =
+            (((base * self.demand) as f32 * self.price_multiplier).floor() as i32).max(0);
+        let count = (base + demand_bonus + self.special_price)
+            .clamp(1, self.input_one.item.max_stack() as i32);
+
+        self.input_one.clone().with_count(count as u8)
+    }
+
+    /// Returns `true` if the items in the input slots can pay for the trade.
+    ///
+    /// Like in vanilla, the input items may have more NBT than the items of
+    /// the trade, and items with durability match trade items without NBT no
+    /// matter their damage.
+    pub fn matches_inputs(&self, first: Option<&ItemStack>, second: Option<&ItemStack>) -> bool {
+        let first_input = self.adjusted_first_input();
+
+        accepts_input(first, Some(&first_input))
+            && first.map_or(0, |s| s.count()) >= first_input.count()
+            && accepts_input(second, self.input_two.as_ref())
+            && second.map_or(0, |s| s.count()) >= self.input_two.as_ref().map_or(0, |s| s.count())
+    }
+
+    fn to_offer(&self) -> TradeOffer {
+        TradeOffer {
+            input_one: Some(self.input_one.clone()),
+            output_item: Some(self.output.clone()),
+            input_two: self.input_two.clone(),
+            trade_disabled: self.is_disabled(),
+            number_of_trade_uses: self.uses,
+            max_trade_uses: self.max_uses,
+            xp: self.xp,
+            special_price: self.special_price,
+            price_multiplier: self.price_multiplier,
+            demand: self.demand,
+        }
+    }
+}
+
+/// Finds the trade the input items pay for, preferring the selected trade.
+pub(crate) fn find_trade(
+    trades: &[Trade],
+    selected: usize,
+    first: Option<&ItemStack>,
+    second: Option<&ItemStack>,
+) -> Option<usize> {
+    // The first trade is never preferred, which is what vanilla does.
+    if selected > 0 && selected < trades.len() {
+        return trades[selected]
+            .matches_inputs(first, second)
+            .then_some(selected);
+    }
+
+    trades
+        .iter()
+        .position(|trade| trade.matches_inputs(first, second))
+}
+
+/// Returns `true` if the given input item can be used to pay with the
+/// `expected` item.
+fn accepts_input(given: Option<&ItemStack>, expected: Option<&ItemStack>) -> bool {
+    let (given, expected) = match (given, expected) {
+        (None, None) => return true,
+        (Some(given), Some(expected)) => (given, expected),
+        _ => return false,
+    };
+
+    if given.item != expected.item {
+        return false;
+    }
+
+    let Some(expected_nbt) = &expected.nbt else {
+        return true;
+    };
+
+    let mut given_nbt = given.nbt.clone();
+
+    // Items with durability always have a damage when compared.
+    if given.item.max_durability() > 0 {
+        let nbt = given_nbt.get_or_insert_with(Compound::new);
+        let damage = match nbt.get("Damage") {
+            Some(Value::Int(damage)) => (*damage).max(0),
+            _ => 0,
+        };
+
+        nbt.insert("Damage", damage);
+    }
+
+    given_nbt.map_or(false, |nbt| compound_matches(expected_nbt, &nbt))
+}
+
+/// Returns `true` if every tag of `expected` is in `given`. Compounds are
+/// compared the same way, and other values must be equal.
+fn compound_matches(expected: &Compound, given: &Compound) -> bool {
+    expected
+        .iter()
+        .all(|(key, expected)| match (expected, given.get(key)) {
+            (Value::Compound(expected), Some(Value::Compound(given))) => {
+                compound_matches(expected, given)
+            }
+            (expected, Some(given)) => expected == given,
+            (_, None) => false,
+        })
+}
+
+/// Sent when a client selects a trade in the merchant screen. The inputs of the
+/// trade have already been moved to the input slots from the player inventory,
+/// if the input slots could be emptied.
+#[derive(Clone, Debug)]
+pub struct SelectMerchantTrade {
+    pub client: Entity,
+    /// The index of the trade in the [`TradeList`].
+    pub trade: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_core::item::ItemKind;
+    use valence_nbt::compound;
+
+    use super::*;
+
+    #[test]
+    fn adjusted_price() {
+        let mut trade = Trade::new(
+            ItemStack::new(ItemKind::Emerald, 10, None),
+            ItemStack::new(ItemKind::Bread, 1, None),
+        );
+
+        assert_eq!(trade.adjusted_first_input().count(), 10);
+
+        trade.demand = 4;
+        trade.price_multiplier = 0.05;
+        assert_eq!(trade.adjusted_first_input().count(), 12);
+
+        trade.special_price = -20;
+        assert_eq!(trade.adjusted_first_input().count(), 1);
+
+        trade.special_price = 100;
+        assert_eq!(trade.adjusted_first_input().count(), 64);
+    }
+
+    #[test]
+    fn input_matching() {
+        let trade = Trade::new(
+            ItemStack::new(ItemKind::DiamondSword, 1, None),
+            ItemStack::new(ItemKind::Emerald, 1, None),
+        )
+        .with_second_input(ItemStack::new(ItemKind::Stick, 2, None));
+
+        let sword = ItemStack::new(ItemKind::DiamondSword, 1, None);
+        let damaged_sword = sword.clone().with_nbt(compound! { "Damage" => 100 });
+        let sticks = ItemStack::new(ItemKind::Stick, 3, None);
+
+        assert!(trade.matches_inputs(Some(&sword), Some(&sticks)));
+        assert!(trade.matches_inputs(Some(&damaged_sword), Some(&sticks)));
+        assert!(!trade.matches_inputs(Some(&sword), None));
+        assert!(!trade.matches_inputs(Some(&sticks), Some(&sword)));
+        assert!(!trade.matches_inputs(
+            Some(&sword),
+            Some(&ItemStack::new(ItemKind::Stick, 1, None))
+        ));
+
+        // The inputs need all the NBT of the trade items, but can have more.
+        let trade = Trade::new(
+            ItemStack::new(ItemKind::DiamondSword, 1, None)
+                .with_nbt(compound! { "Damage" => 0, "display" => compound! { "Name" => "a" } }),
+            ItemStack::new(ItemKind::Emerald, 1, None),
+        );
+
+        let named_sword = sword.clone().with_nbt(compound! {
+            "display" => compound! { "Name" => "a", "Lore" => "b" },
+        });
+
+        assert!(trade.matches_inputs(Some(&named_sword), None));
+        assert!(!trade.matches_inputs(Some(&sword), None));
+        assert!(!trade.matches_inputs(
+            Some(&named_sword.with_nbt(compound! {
+                "Damage" => 1,
+                "display" => compound! { "Name" => "a" },
+            })),
+            None
+        ));
+    }
+
+    #[test]
+    fn selected_trade_preferred() {
+        let emerald = ItemStack::new(ItemKind::Emerald, 1, None);
+        let trades = vec![
+            Trade::new(emerald.clone(), ItemStack::new(ItemKind::Bread, 1, None)),
+            Trade::new(emerald.clone(), ItemStack::new(ItemKind::Apple, 1, None)),
+            Trade::new(
+                ItemStack::new(ItemKind::Diamond, 1, None),
+                ItemStack::new(ItemKind::Cake, 1, None),
+            ),
+        ];
+
+        assert_eq!(find_trade(&trades, 0, Some(&emerald), None), Some(0));
+        assert_eq!(find_trade(&trades, 1, Some(&emerald), None), Some(1));
+        // Only the selected trade is considered.
+        assert_eq!(find_trade(&trades, 2, Some(&emerald), None), None);
+        assert_eq!(find_trade(&trades, 3, Some(&emerald), None), Some(0));
+    }
+}
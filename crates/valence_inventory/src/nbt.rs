@@ -0,0 +1,322 @@
+use tracing::warn;
+use valence_core::ident::Ident;
+use valence_core::item::{ItemKind, ItemStack};
+use valence_nbt::{compound, Compound, Value};
+
+use crate::{CursorItem, Inventory, InventoryKind};
+
+/// What to do with items of an unknown kind, such as items added by mods,
+/// when reading NBT with [`Inventory::from_nbt`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum UnknownItemPolicy {
+    /// The items are skipped with a warning.
+    #[default]
+    Skip,
+    /// The NBT of the items is returned unchanged, so that it can be written
+    /// back with the rest of the inventory.
+    Keep,
+}
+
+impl Inventory {
+    /// Converts the items of the inventory to the list of items vanilla saves,
+    /// like the `Inventory` list of player data or the `Items` list of a
+    /// chest. Each item has its slot number in a `Slot` byte.
+    ///
+    /// The slots of player inventories are numbered like in vanilla player
+    /// data: 0 to 8 for the hotbar, 9 to 35 for the main inventory, 100 to 103
+    /// for the armor from feet to head and -106 for the offhand. The crafting
+    /// grid of player inventories isn't saved. The slots of other inventories
+    /// are numbered by slot index.
+    pub fn to_nbt(&self) -> Vec<Compound> {
+        let slots: Vec<(u16, i8)> = if self.kind == InventoryKind::Player {
+            (0..36)
+                .chain(100..104)
+                .chain([-106])
+                .filter_map(|saved_slot| Some((player_slot(saved_slot)?, saved_slot)))
+                .collect()
+        } else {
+            (0..self.slot_count().min(i8::MAX as u16 + 1))
+                .map(|idx| (idx, idx as i8))
+                .collect()
+        };
+
+        slots
+            .into_iter()
+            .filter_map(|(idx, saved_slot)| {
+                let stack = self.slot(idx)?;
+
+                let mut item = stack_to_nbt(stack)?;
+                item.insert("Slot", saved_slot);
+
+                Some(item)
+            })
+            .collect()
+    }
+
+    /// Creates an inventory from a list of items created by
+    /// [`Inventory::to_nbt`] or saved by vanilla.
+    ///
+    /// Items which are invalid or in slots the inventory doesn't have are
+    /// skipped with a warning. Items of an unknown kind are handled according
+    /// to `unknown_items`. The NBT of the items which were kept is returned
+    /// with the inventory. Append it to the list created by
+    /// [`Inventory::to_nbt`] to save it again.
+    pub fn from_nbt(
+        kind: InventoryKind,
+        items: &[Compound],
+        unknown_items: UnknownItemPolicy,
+    ) -> (Self, Vec<Compound>) {
+        let mut inventory = Self::new(kind);
+        let mut unknown = vec![];
+
+        for item in items {
+            let Some(&Value::Byte(saved_slot)) = item.get("Slot") else {
+                warn!("skipping item without a slot");
+                continue;
+            };
+
+            let idx = if kind == InventoryKind::Player {
+                player_slot(saved_slot)
+            } else {
+                u16::try_from(saved_slot)
+                    .ok()
+                    .filter(|&idx| idx < inventory.slot_count())
+            };
+
+            let Some(idx) = idx else {
+                warn!("skipping item in invalid slot {saved_slot}");
+                continue;
+            };
+
+            match stack_from_nbt(item) {
+                Ok(stack) => inventory.set_slot(idx, stack),
+                Err(StackFromNbtError::UnknownKind(id))
+                    if unknown_items == UnknownItemPolicy::Keep =>
+                {
+                    warn!("keeping item of unknown kind \"{id}\" in slot {saved_slot}");
+                    unknown.push(item.clone());
+                }
+                Err(e) => warn!("skipping item in slot {saved_slot}: {e}"),
+            }
+        }
+
+        (inventory, unknown)
+    }
+}
+
+impl CursorItem {
+    /// Converts the cursor item to NBT in the format of the items of
+    /// [`Inventory::to_nbt`], without a slot number. Returns `None` if the
+    /// cursor is empty.
+    ///
+    /// Vanilla doesn't save the cursor item, but drops it when the inventory
+    /// is closed.
+    pub fn to_nbt(&self) -> Option<Compound> {
+        self.0.as_ref().and_then(stack_to_nbt)
+    }
+
+    /// The inverse of [`CursorItem::to_nbt`]. The cursor is empty if the item
+    /// is invalid or of an unknown kind, which is logged as a warning.
+    pub fn from_nbt(item: &Compound) -> Self {
+        match stack_from_nbt(item) {
+            Ok(stack) => Self(Some(stack)),
+            Err(e) => {
+                warn!("skipping cursor item: {e}");
+                Self(None)
+            }
+        }
+    }
+}
+
+/// Converts a slot number of vanilla player data to the index of the slot in
+/// a player inventory.
+fn player_slot(saved_slot: i8) -> Option<u16> {
+    match saved_slot {
+        // Hotbar.
+        0..=8 => Some(saved_slot as u16 + 36),
+        // Main inventory.
+        9..=35 => Some(saved_slot as u16),
+        // Armor from feet to head.
+        100..=103 => Some(108 - saved_slot as u16),
+        // Offhand.
+        -106 => Some(45),
+        _ => None,
+    }
+}
+
+fn stack_to_nbt(stack: &ItemStack) -> Option<Compound> {
+    if stack.item == ItemKind::Air {
+        return None;
+    }
+
+    let mut item = compound! {
+        "id" => format!("minecraft:{}", stack.item.to_str()),
+        "Count" => stack.count() as i8,
+    };
+
+    if let Some(tag) = &stack.nbt {
+        item.insert("tag", tag.clone());
+    }
+
+    Some(item)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum StackFromNbtError {
+    MissingId,
+    UnknownKind(String),
+}
+
+impl std::fmt::Display for StackFromNbtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingId => write!(f, "missing item ident"),
+            Self::UnknownKind(id) => write!(f, "unknown item kind \"{id}\""),
+        }
+    }
+}
+
+fn stack_from_nbt(item: &Compound) -> Result<ItemStack, StackFromNbtError> {
+    let Some(Value::String(id)) = item.get("id") else {
+        return Err(StackFromNbtError::MissingId);
+    };
+
+    let kind = Ident::new(&id[..])
+        .ok()
+        .filter(|ident| ident.namespace() == "minecraft")
+        .and_then(|ident| ItemKind::from_str(ident.path()))
+        .filter(|&kind| kind != ItemKind::Air)
+        .ok_or_else(|| StackFromNbtError::UnknownKind(id.clone()))?;
+
+    let count = match item.get("Count") {
+        Some(&Value::Byte(count)) => count.max(1) as u8,
+        _ => 1,
+    };
+
+    let tag = match item.get("tag") {
+        Some(Value::Compound(tag)) => Some(tag.clone()),
+        _ => None,
+    };
+
+    Ok(ItemStack::new(kind, count, tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_nbt::snbt::from_snbt_str;
+    use valence_nbt::List;
+
+    use super::*;
+
+    /// The `Inventory` list of a player data file saved by a vanilla 1.19.4
+    /// server, with an item of a mod added at the end.
+    const PLAYER_INVENTORY: &str = r#"[
+        {Count: 1b, Slot: 0b, id: "minecraft:diamond_sword", tag: {Damage: 12, Enchantments: [{id: "minecraft:sharpness", lvl: 5s}]}},
+        {Count: 63b, Slot: 1b, id: "minecraft:torch"},
+        {Count: 64b, Slot: 9b, id: "minecraft:cobblestone"},
+        {Count: 1b, Slot: 35b, id: "minecraft:written_book", tag: {author: "Dev", pages: ['"Hello"'], resolved: 1b, title: "Notes"}},
+        {Count: 1b, Slot: 100b, id: "minecraft:iron_boots", tag: {Damage: 3}},
+        {Count: 1b, Slot: 103b, id: "minecraft:turtle_helmet", tag: {Damage: 0}},
+        {Count: 16b, Slot: -106b, id: "minecraft:bread"},
+        {Count: 1b, Slot: 2b, id: "examplemod:gadget", tag: {Charge: 7}}
+    ]"#;
+
+    fn read_items(snbt: &str) -> Vec<Compound> {
+        match from_snbt_str(snbt).unwrap() {
+            Value::List(List::Compound(items)) => items,
+            other => panic!("expected a list of compounds, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn player_inventory_round_trip() {
+        let items = read_items(PLAYER_INVENTORY);
+
+        let (inventory, unknown) =
+            Inventory::from_nbt(InventoryKind::Player, &items, UnknownItemPolicy::Keep);
+
+        assert_eq!(inventory.slot(36).unwrap().item, ItemKind::DiamondSword);
+        assert_eq!(
+            inventory.slot(37),
+            Some(&ItemStack::new(ItemKind::Torch, 63, None))
+        );
+        assert_eq!(inventory.slot(9).unwrap().item, ItemKind::Cobblestone);
+        assert_eq!(inventory.slot(35).unwrap().item, ItemKind::WrittenBook);
+        assert_eq!(inventory.slot(8).unwrap().item, ItemKind::IronBoots);
+        assert_eq!(inventory.slot(5).unwrap().item, ItemKind::TurtleHelmet);
+        assert_eq!(
+            inventory.slot(45),
+            Some(&ItemStack::new(ItemKind::Bread, 16, None))
+        );
+        assert_eq!(inventory.slot(38), None);
+        assert_eq!(unknown, &items[7..]);
+
+        let mut saved = inventory.to_nbt();
+        saved.extend(unknown);
+
+        assert_eq!(saved, items);
+    }
+
+    #[test]
+    fn unknown_items_are_skipped() {
+        let items = read_items(PLAYER_INVENTORY);
+
+        let (inventory, unknown) =
+            Inventory::from_nbt(InventoryKind::Player, &items, UnknownItemPolicy::Skip);
+
+        assert!(unknown.is_empty());
+        assert_eq!(inventory.to_nbt(), &items[..7]);
+    }
+
+    #[test]
+    fn container_slots() {
+        let items = read_items(
+            r#"[
+                {Count: 2b, Slot: 0b, id: "minecraft:apple"},
+                {Count: 1b, Slot: 26b, id: "minecraft:shears"},
+                {Count: 1b, Slot: 27b, id: "minecraft:stick"},
+                {Count: 1b, Slot: -1b, id: "minecraft:stick"},
+                {Count: 1b, id: "minecraft:stick"}
+            ]"#,
+        );
+
+        let (inventory, _) =
+            Inventory::from_nbt(InventoryKind::Generic9x3, &items, UnknownItemPolicy::Skip);
+
+        assert_eq!(
+            inventory.slot(0),
+            Some(&ItemStack::new(ItemKind::Apple, 2, None))
+        );
+        assert_eq!(inventory.slot(26).unwrap().item, ItemKind::Shears);
+        // The items in invalid slots are skipped.
+        assert_eq!(inventory.to_nbt(), &items[..2]);
+    }
+
+    #[test]
+    fn player_crafting_grid_not_saved() {
+        let mut inventory = Inventory::new(InventoryKind::Player);
+        inventory.set_slot(0, ItemStack::new(ItemKind::OakPlanks, 4, None));
+        inventory.set_slot(1, ItemStack::new(ItemKind::OakLog, 1, None));
+
+        assert!(inventory.to_nbt().is_empty());
+    }
+
+    #[test]
+    fn cursor_item_round_trip() {
+        let cursor = CursorItem(Some(ItemStack::new(
+            ItemKind::Diamond,
+            3,
+            Some(compound! { "Custom" => 1_i32 }),
+        )));
+
+        let nbt = cursor.to_nbt().unwrap();
+        assert_eq!(nbt.get("Slot"), None);
+        assert_eq!(CursorItem::from_nbt(&nbt), cursor);
+
+        assert_eq!(CursorItem(None).to_nbt(), None);
+        assert_eq!(
+            CursorItem::from_nbt(&compound! { "id" => "examplemod:gadget" }),
+            CursorItem(None)
+        );
+    }
+}
@@ -0,0 +1,637 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use bevy_ecs::prelude::*;
+use valence_core::ident::Ident;
+use valence_core::item::{ItemKind, ItemStack};
+use valence_core::packet::s2c::play::synchronize_recipes::{self, CraftingCategory, SmeltCategory};
+use valence_core::packet::var_int::VarInt;
+use valence_core::packet::Encode;
+
+use crate::InventoryKind;
+
+/// The recipes known to the server. The recipes are sent to clients when they
+/// join, and are all unlocked in their recipe book. They are sent again when
+/// the registry changes.
+///
+/// The result slot of crafting grids shows the result of the crafting recipe
+/// matching the grid. Smelting recipes are only shown in the recipe book.
+///
+/// ```
+/// # use bevy_ecs::prelude::*;
+/// # use valence_core::ident;
+/// # use valence_core::item::{ItemKind, ItemStack};
+/// # use valence_inventory::*;
+/// # fn add_recipes(mut recipes: ResMut<RecipeRegistry>) {
+/// recipes.insert_tag(
+///     ident!("planks"),
+///     [ItemKind::OakPlanks, ItemKind::SprucePlanks],
+/// );
+///
+/// recipes.insert(
+///     ident!("crafting_table"),
+///     Recipe::shaped(
+///         ["##", "##"],
+///         [('#', Ingredient::Tag(ident!("planks").into()))],
+///         ItemStack::new(ItemKind::CraftingTable, 1, None),
+///     ),
+/// );
+/// # }
+/// ```
+#[derive(Resource, Clone, PartialEq, Default, Debug)]
+pub struct RecipeRegistry {
+    recipes: BTreeMap<Ident<String>, Recipe>,
+    tags: BTreeMap<Ident<String>, Vec<ItemKind>>,
+}
+
+impl RecipeRegistry {
+    /// Adds a recipe, replacing the recipe with the same ID.
+    pub fn insert(&mut self, id: impl Into<Ident<String>>, recipe: Recipe) -> Option<Recipe> {
+        self.recipes.insert(id.into(), recipe)
+    }
+
+    pub fn remove(&mut self, id: Ident<&str>) -> Option<Recipe> {
+        self.recipes.remove(id.as_str())
+    }
+
+    pub fn get(&self, id: Ident<&str>) -> Option<&Recipe> {
+        self.recipes.get(id.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Ident<&str>, &Recipe)> + '_ {
+        self.recipes
+            .iter()
+            .map(|(id, recipe)| (id.as_str_ident(), recipe))
+    }
+
+    /// Sets the items of the item tag used by [`Ingredient::Tag`], replacing
+    /// the items the tag had.
+    pub fn insert_tag(
+        &mut self,
+        name: impl Into<Ident<String>>,
+        items: impl IntoIterator<Item = ItemKind>,
+    ) -> Option<Vec<ItemKind>> {
+        self.tags.insert(name.into(), items.into_iter().collect())
+    }
+
+    /// Gets the items of an item tag.
+    pub fn tag(&self, name: Ident<&str>) -> Option<&[ItemKind]> {
+        self.tags.get(name.as_str()).map(Vec::as_slice)
+    }
+
+    /// Gets the items an ingredient accepts. Tags which don't exist accept no
+    /// items.
+    pub fn ingredient_items<'a>(&'a self, ingredient: &'a Ingredient) -> &'a [ItemKind] {
+        match ingredient {
+            Ingredient::Items(items) => items,
+            Ingredient::Tag(name) => self.tag(name.as_str_ident()).unwrap_or_default(),
+        }
+    }
+
+    /// Finds the crafting recipe matching the items in a crafting grid. The
+    /// slots of the grid are given row by row.
+    pub fn find_crafting(
+        &self,
+        grid: &[Option<ItemStack>],
+        width: usize,
+    ) -> Option<(Ident<&str>, &Recipe)> {
+        if grid.iter().all(Option::is_none) {
+            return None;
+        }
+
+        self.iter()
+            .find(|(_, recipe)| self.matches_grid(recipe, grid, width))
+    }
+
+    /// Finds the smelting recipe of an item.
+    pub fn find_smelting(&self, item: ItemKind) -> Option<(Ident<&str>, &Recipe)> {
+        self.iter().find(|(_, recipe)| match &recipe.kind {
+            RecipeKind::Smelting { ingredient, .. } => self.accepts(ingredient, item),
+            _ => false,
+        })
+    }
+
+    /// Returns `true` if the crafting recipe matches the items in the crafting
+    /// grid.
+    pub(crate) fn matches_grid(
+        &self,
+        recipe: &Recipe,
+        grid: &[Option<ItemStack>],
+        width: usize,
+    ) -> bool {
+        match &recipe.kind {
+            RecipeKind::Shaped {
+                width: recipe_width,
+                height: recipe_height,
+                ingredients,
+            } => self.matches_pattern(
+                *recipe_width as usize,
+                *recipe_height as usize,
+                ingredients,
+                grid,
+                width,
+            ),
+            RecipeKind::Shapeless { ingredients } => {
+                let items: Vec<ItemKind> = grid.iter().flatten().map(|stack| stack.item).collect();
+
+                items.len() == ingredients.len()
+                    && self.matches_unordered(ingredients, &items, &mut vec![false; items.len()])
+            }
+            RecipeKind::Smelting { .. } => false,
+        }
+    }
+
+    fn accepts(&self, ingredient: &Ingredient, item: ItemKind) -> bool {
+        self.ingredient_items(ingredient).contains(&item)
+    }
+
+    /// Returns `true` if the pattern is somewhere in the grid, possibly
+    /// mirrored, and the rest of the grid is empty.
+    fn matches_pattern(
+        &self,
+        width: usize,
+        height: usize,
+        ingredients: &[Option<Ingredient>],
+        grid: &[Option<ItemStack>],
+        grid_width: usize,
+    ) -> bool {
+        let grid_height = grid.len() / grid_width;
+
+        if width > grid_width || height > grid_height {
+            return false;
+        }
+
+        let matches_at = |left: usize, top: usize, mirrored: bool| {
+            grid.iter().enumerate().all(|(idx, stack)| {
+                let (x, y) = (idx % grid_width, idx / grid_width);
+
+                let ingredient =
+                    if (left..left + width).contains(&x) && (top..top + height).contains(&y) {
+                        let x = if mirrored {
+                            left + width - 1 - x
+                        } else {
+                            x - left
+                        };
+
+                        ingredients[(y - top) * width + x].as_ref()
+                    } else {
+                        None
+                    };
+
+                match (ingredient, stack) {
+                    (Some(ingredient), Some(stack)) => self.accepts(ingredient, stack.item),
+                    (None, None) => true,
+                    _ => false,
+                }
+            })
+        };
+
+        (0..=grid_height - height).any(|top| {
+            (0..=grid_width - width)
+                .any(|left| matches_at(left, top, false) || matches_at(left, top, true))
+        })
+    }
+
+    /// Returns `true` if every item can be used for a different ingredient.
+    fn matches_unordered(
+        &self,
+        ingredients: &[Ingredient],
+        items: &[ItemKind],
+        used: &mut [bool],
+    ) -> bool {
+        let Some((ingredient, rest)) = ingredients.split_first() else {
+            return true;
+        };
+
+        for (idx, &item) in items.iter().enumerate() {
+            if used[idx] || !self.accepts(ingredient, item) {
+                continue;
+            }
+
+            used[idx] = true;
+
+            if self.matches_unordered(rest, items, used) {
+                return true;
+            }
+
+            used[idx] = false;
+        }
+
+        false
+    }
+
+    /// Encodes the recipes for the `recipes` of the
+    /// [`SynchronizeRecipesS2c`] packet.
+    ///
+    /// [`SynchronizeRecipesS2c`]: valence_core::packet::s2c::play::SynchronizeRecipesS2c
+    pub(crate) fn encode_recipes(&self) -> anyhow::Result<Vec<u8>> {
+        let recipes: Vec<_> = self
+            .recipes
+            .iter()
+            .map(|(id, recipe)| recipe.to_packet_recipe(id.as_str_ident(), self))
+            .collect();
+
+        let mut bytes = vec![];
+        recipes.encode(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    pub(crate) fn recipe_ids(&self) -> Vec<Ident<Cow<str>>> {
+        self.recipes
+            .keys()
+            .map(|id| id.as_str_ident().into())
+            .collect()
+    }
+}
+
+/// A crafting or smelting recipe in the [`RecipeRegistry`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct Recipe {
+    pub kind: RecipeKind,
+    pub result: ItemStack,
+    /// Recipes with the same group are shown as one entry in the recipe book.
+    pub group: String,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum RecipeKind {
+    /// A crafting recipe with the ingredients in a pattern. The pattern can be
+    /// anywhere in the crafting grid, and can be mirrored.
+    Shaped {
+        width: u8,
+        height: u8,
+        /// The ingredients of the pattern, row by row. `None` is an empty
+        /// slot.
+        ingredients: Vec<Option<Ingredient>>,
+    },
+    /// A crafting recipe with the ingredients anywhere in the crafting grid.
+    Shapeless { ingredients: Vec<Ingredient> },
+    /// A furnace recipe.
+    Smelting {
+        ingredient: Ingredient,
+        /// The experience the player gets for the result.
+        experience: f32,
+        /// The number of ticks smelting takes.
+        cooking_time: i32,
+    },
+}
+
+impl Recipe {
+    /// Creates a shaped crafting recipe from the rows of a pattern like in
+    /// vanilla data packs. Each character of the pattern is an ingredient from
+    /// `key`, or an empty slot if it is a space.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is bigger than 3x3, if its rows have different
+    /// lengths, or if a character is missing from `key`.
+    pub fn shaped<'a>(
+        pattern: impl IntoIterator<Item = &'a str>,
+        key: impl IntoIterator<Item = (char, Ingredient)>,
+        result: ItemStack,
+    ) -> Self {
+        let key: BTreeMap<char, Ingredient> = key.into_iter().collect();
+        let rows: Vec<Vec<char>> = pattern
+            .into_iter()
+            .map(|row| row.chars().collect())
+            .collect();
+
+        let width = rows.first().map_or(0, Vec::len);
+        let height = rows.len();
+
+        assert!(
+            width <= 3 && height <= 3,
+            "pattern must not be bigger than 3x3"
+        );
+        assert!(
+            rows.iter().all(|row| row.len() == width),
+            "rows of the pattern must have the same length"
+        );
+
+        let ingredients = rows
+            .iter()
+            .flatten()
+            .map(|c| match c {
+                ' ' => None,
+                c => Some(
+                    key.get(c)
+                        .unwrap_or_else(|| panic!("'{c}' is missing from the key"))
+                        .clone(),
+                ),
+            })
+            .collect();
+
+        Self {
+            kind: RecipeKind::Shaped {
+                width: width as u8,
+                height: height as u8,
+                ingredients,
+            },
+            result,
+            group: String::new(),
+        }
+    }
+
+    pub fn shapeless(ingredients: impl IntoIterator<Item = Ingredient>, result: ItemStack) -> Self {
+        Self {
+            kind: RecipeKind::Shapeless {
+                ingredients: ingredients.into_iter().collect(),
+            },
+            result,
+            group: String::new(),
+        }
+    }
+
+    /// Creates a furnace recipe which takes 200 ticks, like most vanilla
+    /// furnace recipes.
+    pub fn smelting(ingredient: impl Into<Ingredient>, result: ItemStack, experience: f32) -> Self {
+        Self {
+            kind: RecipeKind::Smelting {
+                ingredient: ingredient.into(),
+                experience,
+                cooking_time: 200,
+            },
+            result,
+            group: String::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = group.into();
+        self
+    }
+
+    /// Returns `true` if this is a crafting recipe.
+    pub fn is_crafting(&self) -> bool {
+        matches!(
+            self.kind,
+            RecipeKind::Shaped { .. } | RecipeKind::Shapeless { .. }
+        )
+    }
+
+    fn to_packet_recipe<'a>(
+        &'a self,
+        id: Ident<&'a str>,
+        registry: &RecipeRegistry,
+    ) -> synchronize_recipes::Recipe<'a> {
+        let ingredient = |ingredient: &Ingredient| {
+            registry
+                .ingredient_items(ingredient)
+                .iter()
+                .map(|&item| Some(ItemStack::new(item, 1, None)))
+                .collect()
+        };
+
+        let recipe_id = id.into();
+        let group = self.group.as_str();
+        let result = Some(self.result.clone());
+
+        match &self.kind {
+            RecipeKind::Shaped {
+                width,
+                height,
+                ingredients,
+            } => synchronize_recipes::Recipe::CraftingShaped {
+                recipe_id,
+                width: VarInt((*width).into()),
+                height: VarInt((*height).into()),
+                group,
+                category: CraftingCategory::Misc,
+                ingredients: ingredients
+                    .iter()
+                    .map(|slot| slot.as_ref().map_or_else(Vec::new, ingredient))
+                    .collect(),
+                result,
+            },
+            RecipeKind::Shapeless { ingredients } => {
+                synchronize_recipes::Recipe::CraftingShapeless {
+                    recipe_id,
+                    group,
+                    category: CraftingCategory::Misc,
+                    ingredients: ingredients.iter().map(ingredient).collect(),
+                    result,
+                }
+            }
+            RecipeKind::Smelting {
+                ingredient: smelted,
+                experience,
+                cooking_time,
+            } => synchronize_recipes::Recipe::Smelting {
+                recipe_id,
+                group,
+                category: SmeltCategory::Misc,
+                ingredient: ingredient(smelted),
+                result,
+                experience: *experience,
+                cooking_time: VarInt(*cooking_time),
+            },
+        }
+    }
+}
+
+/// The items which can be used for an ingredient of a [`Recipe`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Ingredient {
+    /// Any of the items.
+    Items(Vec<ItemKind>),
+    /// Any item of a tag set with [`RecipeRegistry::insert_tag`].
+    Tag(Ident<String>),
+}
+
+impl From<ItemKind> for Ingredient {
+    fn from(item: ItemKind) -> Self {
+        Self::Items(vec![item])
+    }
+}
+
+/// Gets the slots of the crafting grid of an inventory and the width of the
+/// grid, or `None` if the inventory has no crafting grid. The result slot is
+/// slot 0.
+pub(crate) fn crafting_grid(kind: InventoryKind) -> Option<(Range<u16>, usize)> {
+    match kind {
+        InventoryKind::Player => Some((1..5, 2)),
+        InventoryKind::Crafting => Some((1..10, 3)),
+        _ => None,
+    }
+}
+
+/// The contents of the crafting grid the result slot of an inventory was last
+/// computed for.
+#[derive(Component, Clone, PartialEq, Default, Debug)]
+pub(crate) struct CraftingState {
+    computed_for: Vec<Option<ItemStack>>,
+}
+
+impl CraftingState {
+    pub(crate) fn new(grid: &[Option<ItemStack>]) -> Self {
+        Self {
+            computed_for: grid.to_vec(),
+        }
+    }
+
+    /// Returns `true` if the result needs to be computed again.
+    pub(crate) fn is_outdated(&self, grid: &[Option<ItemStack>]) -> bool {
+        self.computed_for != grid
+    }
+
+    /// Computes the result of the crafting grid.
+    pub(crate) fn compute_result(
+        &mut self,
+        grid: &[Option<ItemStack>],
+        width: usize,
+        recipes: &RecipeRegistry,
+    ) -> Option<ItemStack> {
+        self.computed_for = grid.to_vec();
+
+        recipes
+            .find_crafting(grid, width)
+            .map(|(_, recipe)| recipe.result.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_core::ident;
+
+    use super::*;
+
+    fn stack(item: ItemKind) -> Option<ItemStack> {
+        Some(ItemStack::new(item, 1, None))
+    }
+
+    #[test]
+    fn shaped_recipe_anywhere_in_grid() {
+        let mut recipes = RecipeRegistry::default();
+        recipes.insert(
+            ident!("stick"),
+            Recipe::shaped(
+                ["#", "#"],
+                [('#', ItemKind::OakPlanks.into())],
+                ItemStack::new(ItemKind::Stick, 4, None),
+            ),
+        );
+
+        let planks = stack(ItemKind::OakPlanks);
+
+        let grid = [None, planks.clone(), None, planks.clone()];
+        assert_eq!(recipes.find_crafting(&grid, 2).unwrap().0, ident!("stick"));
+
+        let mut grid = vec![None; 9];
+        grid[5] = planks.clone();
+        grid[8] = planks.clone();
+        assert!(recipes.find_crafting(&grid, 3).is_some());
+
+        // Other items aren't allowed in the rest of the grid.
+        let grid = [planks.clone(), planks.clone(), None, planks];
+        assert!(recipes.find_crafting(&grid, 2).is_none());
+    }
+
+    #[test]
+    fn shaped_recipe_mirrored() {
+        let mut recipes = RecipeRegistry::default();
+        recipes.insert(
+            ident!("shears"),
+            Recipe::shaped(
+                [" #", "# "],
+                [('#', ItemKind::IronIngot.into())],
+                ItemStack::new(ItemKind::Shears, 1, None),
+            ),
+        );
+
+        let iron = stack(ItemKind::IronIngot);
+
+        assert!(recipes
+            .find_crafting(&[None, iron.clone(), iron.clone(), None], 2)
+            .is_some());
+        assert!(recipes
+            .find_crafting(&[iron.clone(), None, None, iron.clone()], 2)
+            .is_some());
+        assert!(recipes
+            .find_crafting(&[iron.clone(), iron, None, None], 2)
+            .is_none());
+    }
+
+    #[test]
+    fn shapeless_recipe_with_tag() {
+        let mut recipes = RecipeRegistry::default();
+        recipes.insert_tag(ident!("logs"), [ItemKind::OakLog, ItemKind::BirchLog]);
+        recipes.insert(
+            ident!("charcoal_mix"),
+            Recipe::shapeless(
+                [
+                    Ingredient::Tag(ident!("logs").into()),
+                    ItemKind::Coal.into(),
+                ],
+                ItemStack::new(ItemKind::Charcoal, 2, None),
+            ),
+        );
+
+        let grid = [stack(ItemKind::Coal), None, None, stack(ItemKind::BirchLog)];
+        assert!(recipes.find_crafting(&grid, 2).is_some());
+
+        let grid = [stack(ItemKind::Coal), None, None, stack(ItemKind::Coal)];
+        assert!(recipes.find_crafting(&grid, 2).is_none());
+
+        // An empty tag matches nothing.
+        recipes.insert_tag(ident!("logs"), []);
+        let grid = [stack(ItemKind::Coal), None, None, stack(ItemKind::BirchLog)];
+        assert!(recipes.find_crafting(&grid, 2).is_none());
+    }
+
+    #[test]
+    fn smelting_recipes_are_not_crafted() {
+        let mut recipes = RecipeRegistry::default();
+        recipes.insert(
+            ident!("iron_ingot"),
+            Recipe::smelting(
+                ItemKind::RawIron,
+                ItemStack::new(ItemKind::IronIngot, 1, None),
+                0.7,
+            ),
+        );
+
+        assert!(recipes
+            .find_crafting(&[stack(ItemKind::RawIron), None, None, None], 2)
+            .is_none());
+        assert_eq!(
+            recipes.find_smelting(ItemKind::RawIron).unwrap().0,
+            ident!("iron_ingot")
+        );
+    }
+
+    #[test]
+    fn recipes_encode() {
+        let mut recipes = RecipeRegistry::default();
+        recipes.insert(
+            ident!("torch"),
+            Recipe::shaped(
+                ["c", "#"],
+                [
+                    (
+                        'c',
+                        Ingredient::Items(vec![ItemKind::Coal, ItemKind::Charcoal]),
+                    ),
+                    ('#', ItemKind::Stick.into()),
+                ],
+                ItemStack::new(ItemKind::Torch, 4, None),
+            ),
+        );
+        recipes.insert(
+            ident!("glass"),
+            Recipe::smelting(
+                ItemKind::Sand,
+                ItemStack::new(ItemKind::Glass, 1, None),
+                0.1,
+            ),
+        );
+
+        assert!(recipes.encode_recipes().is_ok());
+        assert_eq!(
+            recipes.recipe_ids(),
+            [ident!("glass"), ident!("torch")]
+        );
+    }
+}
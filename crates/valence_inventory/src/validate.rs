@@ -3,15 +3,23 @@ use valence_core::item::ItemStack;
 use valence_core::packet::c2s::play::click_slot::ClickMode;
 use valence_core::packet::c2s::play::ClickSlotC2s;
 
+use super::click::{simulate_click, stacks_match, ClickOutcome, DragState, WindowState};
 use super::{CursorItem, Inventory, InventoryWindow, PLAYER_INVENTORY_MAIN_SLOTS_COUNT};
 
 /// Validates a click slot packet enforcing that all fields are valid.
+///
+/// If the outcome of the click can be predicted, the changes made by the
+/// client must match one of the possible outcomes exactly, and that outcome is
+/// returned. Otherwise, the changes are only checked for duplicated items.
 pub(super) fn validate_click_slot_packet(
     packet: &ClickSlotC2s,
     player_inventory: &Inventory,
     open_inventory: Option<&Inventory>,
     cursor_item: &CursorItem,
-) -> anyhow::Result<()> {
+    drag: &DragState,
+    state: WindowState,
+    creative: bool,
+) -> anyhow::Result<Option<ClickOutcome>> {
     ensure!(
         (packet.window_id == 0) == open_inventory.is_none(),
         "window id and open inventory mismatch: window_id: {} open_inventory: {}",
@@ -27,7 +35,7 @@ pub(super) fn validate_click_slot_packet(
     // check all slot ids and item counts are valid
     ensure!(
         packet.slot_changes.iter().all(|s| {
-            if !(0..=max_slot).contains(&(s.idx as u16)) {
+            if !(0..max_slot).contains(&(s.idx as u16)) {
                 return false;
             }
             if let Some(slot) = s.item.as_ref() {
@@ -63,43 +71,35 @@ pub(super) fn validate_click_slot_packet(
         ClickMode::Click => {
             ensure!((0..=1).contains(&packet.button), "invalid button");
             ensure!(
-                (0..=max_slot).contains(&(packet.slot_idx as u16)) || packet.slot_idx == -999,
+                (0..max_slot).contains(&(packet.slot_idx as u16)) || packet.slot_idx == -999,
                 "invalid slot index"
             )
         }
         ClickMode::ShiftClick => {
             ensure!((0..=1).contains(&packet.button), "invalid button");
             ensure!(
-                packet.carried_item.is_none(),
-                "carried item must be empty for a hotbar swap"
-            );
-            ensure!(
-                (0..=max_slot).contains(&(packet.slot_idx as u16)),
+                (0..max_slot).contains(&(packet.slot_idx as u16)),
                 "invalid slot index"
             )
         }
         ClickMode::Hotbar => {
             ensure!(matches!(packet.button, 0..=8 | 40), "invalid button");
             ensure!(
-                packet.carried_item.is_none(),
-                "carried item must be empty for a hotbar swap"
-            );
+                (0..max_slot).contains(&(packet.slot_idx as u16)),
+                "invalid slot index"
+            )
         }
         ClickMode::CreativeMiddleClick => {
             ensure!(packet.button == 2, "invalid button");
             ensure!(
-                (0..=max_slot).contains(&(packet.slot_idx as u16)),
+                (0..max_slot).contains(&(packet.slot_idx as u16)),
                 "invalid slot index"
             )
         }
         ClickMode::DropKey => {
             ensure!((0..=1).contains(&packet.button), "invalid button");
             ensure!(
-                packet.carried_item.is_none(),
-                "carried item must be empty for an item drop"
-            );
-            ensure!(
-                (0..=max_slot).contains(&(packet.slot_idx as u16)),
+                (0..max_slot).contains(&(packet.slot_idx as u16)),
                 "invalid slot index"
             )
         }
@@ -109,20 +109,63 @@ pub(super) fn validate_click_slot_packet(
                 "invalid button"
             );
             ensure!(
-                (0..=max_slot).contains(&(packet.slot_idx as u16)) || packet.slot_idx == -999,
+                (0..max_slot).contains(&(packet.slot_idx as u16)) || packet.slot_idx == -999,
+                "invalid slot index"
+            )
+        }
+        ClickMode::DoubleClick => {
+            ensure!(packet.button == 0, "invalid button");
+            ensure!(
+                (0..max_slot).contains(&(packet.slot_idx as u16)),
                 "invalid slot index"
             )
         }
-        ClickMode::DoubleClick => ensure!(packet.button == 0, "invalid button"),
     }
 
-    // Check that items aren't being duplicated, i.e. conservation of mass.
-
     let window = InventoryWindow {
         player_inventory,
         open_inventory,
     };
 
+    let mut outcomes = simulate_click(
+        packet,
+        player_inventory,
+        open_inventory,
+        cursor_item.0.as_ref(),
+        drag,
+        state,
+        creative,
+    )
+    .into_iter();
+
+    if let Some(outcome) = outcomes.next() {
+        let Err(e) = validate_click_outcome(packet, &window, &outcome) else {
+            return Ok(Some(outcome));
+        };
+
+        // The client may have predicted one of the other possible outcomes.
+        for outcome in outcomes {
+            if validate_click_outcome(packet, &window, &outcome).is_ok() {
+                return Ok(Some(outcome));
+            }
+        }
+
+        return Err(e);
+    }
+
+    // Check that items aren't being duplicated, i.e. conservation of mass.
+
+    if matches!(
+        packet.mode,
+        ClickMode::ShiftClick | ClickMode::Hotbar | ClickMode::DropKey
+    ) {
+        ensure!(
+            packet.carried_item.is_none(),
+            "carried item must be empty for a {:?} click",
+            packet.mode
+        );
+    }
+
     match packet.mode {
         ClickMode::Click => {
             if packet.slot_idx == -999 {
@@ -318,6 +361,49 @@ pub(super) fn validate_click_slot_packet(
         }
     }
 
+    Ok(None)
+}
+
+/// Checks that the changes made by the client match the predicted outcome of
+/// the click.
+fn validate_click_outcome(
+    packet: &ClickSlotC2s,
+    window: &InventoryWindow,
+    outcome: &ClickOutcome,
+) -> anyhow::Result<()> {
+    ensure!(
+        stacks_match(packet.carried_item.as_ref(), outcome.cursor.as_ref()),
+        "carried item does not match: expected {:?}, got {:?}",
+        outcome.cursor,
+        packet.carried_item
+    );
+
+    for slot in &packet.slot_changes {
+        if outcome.recomputed_slot == Some(slot.idx as u16) {
+            // The server sends its own contents for the slot.
+            continue;
+        }
+
+        let expected = outcome
+            .slot(slot.idx as u16)
+            .unwrap_or_else(|| window.slot(slot.idx as u16));
+
+        ensure!(
+            stacks_match(slot.item.as_ref(), expected),
+            "slot {} does not match: expected {:?}, got {:?}",
+            slot.idx,
+            expected,
+            slot.item
+        );
+    }
+
+    for (idx, _) in &outcome.slots {
+        ensure!(
+            packet.slot_changes.iter().any(|s| s.idx as u16 == *idx),
+            "slot {idx} was changed by the click, but not by the client"
+        );
+    }
+
     Ok(())
 }
 
@@ -456,8 +542,16 @@ mod tests {
             carried_item: inventory.slot(0).cloned(),
         };
 
-        validate_click_slot_packet(&packet, &player_inventory, Some(&inventory), &cursor_item)
-            .expect("packet should be valid");
+        validate_click_slot_packet(
+            &packet,
+            &player_inventory,
+            Some(&inventory),
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect("packet should be valid");
     }
 
     #[test]
@@ -492,11 +586,27 @@ mod tests {
             carried_item: None,
         };
 
-        validate_click_slot_packet(&packet1, &player_inventory, Some(&inventory1), &cursor_item)
-            .expect("packet should be valid");
-
-        validate_click_slot_packet(&packet2, &player_inventory, Some(&inventory2), &cursor_item)
-            .expect("packet should be valid");
+        validate_click_slot_packet(
+            &packet1,
+            &player_inventory,
+            Some(&inventory1),
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect("packet should be valid");
+
+        validate_click_slot_packet(
+            &packet2,
+            &player_inventory,
+            Some(&inventory2),
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect("packet should be valid");
     }
 
     #[test]
@@ -518,8 +628,54 @@ mod tests {
             carried_item: Some(ItemStack::new(ItemKind::Diamond, 20, None)),
         };
 
-        validate_click_slot_packet(&packet, &player_inventory, Some(&inventory), &cursor_item)
-            .expect("packet should be valid");
+        validate_click_slot_packet(
+            &packet,
+            &player_inventory,
+            Some(&inventory),
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect("packet should be valid");
+    }
+
+    #[test]
+    fn click_filled_slot_respects_max_stack() {
+        let player_inventory = Inventory::new(InventoryKind::Player);
+        let mut inventory = Inventory::new(InventoryKind::Generic9x1);
+        inventory.set_slot(0, ItemStack::new(ItemKind::EnderPearl, 10, None));
+        let cursor_item = CursorItem(Some(ItemStack::new(ItemKind::EnderPearl, 10, None)));
+
+        let packet = |slot_count, carried_count| ClickSlotC2s {
+            window_id: 1,
+            button: 0,
+            mode: ClickMode::Click,
+            state_id: VarInt(0),
+            slot_idx: 0,
+            slot_changes: vec![Slot {
+                idx: 0,
+                item: Some(ItemStack::new(ItemKind::EnderPearl, slot_count, None)),
+            }],
+            carried_item: (carried_count > 0)
+                .then(|| ItemStack::new(ItemKind::EnderPearl, carried_count, None)),
+        };
+
+        let validate = |packet| {
+            validate_click_slot_packet(
+                &packet,
+                &player_inventory,
+                Some(&inventory),
+                &cursor_item,
+                &DragState::default(),
+                WindowState::default(),
+                false,
+            )
+        };
+
+        // Ender pearls stack up to 16, so the slot can't hold all of them.
+        validate(packet(16, 4)).expect("packet should be valid");
+        validate(packet(20, 0)).expect_err("packet should not be valid");
     }
 
     #[test]
@@ -541,8 +697,16 @@ mod tests {
             carried_item: Some(ItemStack::new(ItemKind::IronIngot, 2, None)),
         };
 
-        validate_click_slot_packet(&packet, &player_inventory, Some(&inventory), &cursor_item)
-            .expect("packet should be valid");
+        validate_click_slot_packet(
+            &packet,
+            &player_inventory,
+            Some(&inventory),
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect("packet should be valid");
     }
 
     #[test]
@@ -595,14 +759,38 @@ mod tests {
             carried_item: None,
         };
 
-        validate_click_slot_packet(&packet1, &player_inventory, Some(&inventory1), &cursor_item)
-            .expect_err("packet 1 should fail item duplication check");
-
-        validate_click_slot_packet(&packet2, &player_inventory, Some(&inventory2), &cursor_item)
-            .expect_err("packet 2 should fail item duplication check");
-
-        validate_click_slot_packet(&packet3, &player_inventory, Some(&inventory1), &cursor_item)
-            .expect_err("packet 3 should fail item duplication check");
+        validate_click_slot_packet(
+            &packet1,
+            &player_inventory,
+            Some(&inventory1),
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect_err("packet 1 should fail item duplication check");
+
+        validate_click_slot_packet(
+            &packet2,
+            &player_inventory,
+            Some(&inventory2),
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect_err("packet 2 should fail item duplication check");
+
+        validate_click_slot_packet(
+            &packet3,
+            &player_inventory,
+            Some(&inventory1),
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect_err("packet 3 should fail item duplication check");
     }
 
     #[test]
@@ -668,9 +856,18 @@ mod tests {
         ];
 
         for (i, packet) in packets.iter().enumerate() {
-            validate_click_slot_packet(packet, &player_inventory, None, &cursor_item).expect_err(
-                &format!("packet {i} passed item duplication check when it should have failed"),
-            );
+            validate_click_slot_packet(
+                packet,
+                &player_inventory,
+                None,
+                &cursor_item,
+                &DragState::default(),
+                WindowState::default(),
+                false,
+            )
+            .expect_err(&format!(
+                "packet {i} passed item duplication check when it should have failed"
+            ));
         }
     }
 
@@ -701,8 +898,16 @@ mod tests {
             carried_item: None,
         };
 
-        validate_click_slot_packet(&packet, &player_inventory, None, &cursor_item)
-            .expect("packet should be valid");
+        validate_click_slot_packet(
+            &packet,
+            &player_inventory,
+            None,
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect("packet should be valid");
     }
 
     #[test]
@@ -721,8 +926,16 @@ mod tests {
             carried_item: Some(ItemStack::new(ItemKind::Apple, 100, None)),
         };
 
-        validate_click_slot_packet(&packet, &player_inventory, None, &cursor_item)
-            .expect("packet should be valid");
+        validate_click_slot_packet(
+            &packet,
+            &player_inventory,
+            None,
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect("packet should be valid");
     }
 
     #[test]
@@ -743,7 +956,15 @@ mod tests {
             carried_item: Some(ItemStack::new(ItemKind::Apple, 36, None)),
         };
 
-        validate_click_slot_packet(&packet, &player_inventory, None, &cursor_item)
-            .expect("packet should be valid");
+        validate_click_slot_packet(
+            &packet,
+            &player_inventory,
+            None,
+            &cursor_item,
+            &DragState::default(),
+            WindowState::default(),
+            false,
+        )
+        .expect("packet should be valid");
     }
 }
@@ -19,7 +19,11 @@
 
 pub use compound::Compound;
 pub use error::Error;
-pub use from_binary_slice::from_binary_slice;
+pub use from_binary_slice::{from_binary_slice, from_binary_slice_with_limits};
+pub use path::PathError;
+pub use reader::DecodeLimits;
+#[cfg(feature = "serde")]
+pub use self::serde::{from_value, to_value};
 pub use tag::Tag;
 pub use to_binary_writer::to_binary_writer;
 pub use value::{List, Value};
@@ -28,6 +32,10 @@ pub mod compound;
 mod error;
 mod from_binary_slice;
 mod modified_utf8;
+mod path;
+pub mod reader;
+#[cfg(feature = "serde")]
+pub mod serde;
 pub mod snbt;
 mod to_binary_writer;
 pub mod value;
@@ -0,0 +1,795 @@
+//! Querying and modifying nested values with NBT paths.
+
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::fmt;
+
+use crate::tag::Tag;
+use crate::{Compound, List, Value};
+
+/// Errors that can occur when using an NBT path with
+/// [`Compound::set_path`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PathError {
+    /// The path is malformed.
+    Syntax {
+        /// The byte offset in the path where the error was found.
+        pos: usize,
+        reason: &'static str,
+    },
+    /// A list index is not within the bounds of the list.
+    IndexOutOfBounds {
+        /// The path to the list.
+        path: String,
+        index: i32,
+        len: usize,
+    },
+    /// A value along the path does not have the expected type.
+    TypeMismatch {
+        /// The path to the value with the wrong type.
+        path: String,
+        expected: Tag,
+        found: Tag,
+    },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Syntax { pos, reason } => {
+                write!(f, "invalid NBT path at position {pos}: {reason}")
+            }
+            PathError::IndexOutOfBounds { path, index, len } => write!(
+                f,
+                "index {index} is out of bounds for `{path}` (length {len})"
+            ),
+            PathError::TypeMismatch {
+                path,
+                expected,
+                found,
+            } => write!(f, "expected `{path}` to be a {expected} (found {found})"),
+        }
+    }
+}
+
+impl StdError for PathError {}
+
+type Result<T> = std::result::Result<T, PathError>;
+
+impl Compound {
+    /// Gets the value at an NBT path.
+    ///
+    /// Paths use the same syntax as the vanilla `/data` command: compound
+    /// keys are separated by dots and list elements are selected with
+    /// `[index]`, where negative indices count from the end of the list. Keys
+    /// containing special characters can be quoted with `"` or `'`. Filters
+    /// such as `{...}` and `[]` are not supported.
+    ///
+    /// Returns `None` if the path is malformed, if nothing exists at the path,
+    /// or if the path ends with a list index. List elements are not stored as
+    /// [`Value`]s, so get the list itself in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use valence_nbt::{compound, List, Value};
+    ///
+    /// let c = compound! {
+    ///     "Inventory" => List::Compound(vec![compound! {
+    ///         "tag" => compound! {
+    ///             "display" => compound! {
+    ///                 "Name" => "Excalibur",
+    ///             },
+    ///         },
+    ///     }]),
+    ///     "weird.key" => 5,
+    /// };
+    ///
+    /// assert_eq!(
+    ///     c.get_path("Inventory[0].tag.display.Name"),
+    ///     Some(&Value::String("Excalibur".into()))
+    /// );
+    /// assert_eq!(
+    ///     c.get_path("Inventory[-1].tag.display.Name"),
+    ///     c.get_path("Inventory[0].tag.display.Name")
+    /// );
+    /// assert_eq!(c.get_path(r#""weird.key""#), Some(&Value::Int(5)));
+    /// assert_eq!(c.get_path("Inventory[1].tag"), None);
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        let segs = parse(path).ok()?;
+        let (first, rest) = segs.split_first()?;
+
+        let mut value = self.get(first.key()?)?;
+
+        let mut rest = rest.iter();
+
+        while let Some(seg) = rest.next() {
+            value = match (value, &seg.kind) {
+                (Value::Compound(c), SegmentKind::Key(k)) => c.get(k.as_ref())?,
+                (Value::List(l), SegmentKind::Index(i)) => {
+                    // Descend through nested lists until we reach a compound.
+                    let mut list = l;
+                    let mut idx = resolve_index(*i, list.len())?;
+
+                    loop {
+                        match list {
+                            List::Compound(v) => {
+                                let key = rest.next()?.key()?;
+                                break v[idx].get(key)?;
+                            }
+                            List::List(v) => {
+                                list = &v[idx];
+                                let SegmentKind::Index(i) = rest.next()?.kind else {
+                                    return None;
+                                };
+                                idx = resolve_index(i, list.len())?;
+                            }
+                            _ => return None,
+                        }
+                    }
+                }
+                _ => return None,
+            };
+        }
+
+        Some(value)
+    }
+
+    /// Gets a mutable reference to the value at an NBT path.
+    ///
+    /// See [`Compound::get_path`] for the path syntax.
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Value> {
+        let segs = parse(path).ok()?;
+        let (first, rest) = segs.split_first()?;
+
+        let mut value = self.get_mut(first.key()?)?;
+
+        let mut rest = rest.iter();
+
+        while let Some(seg) = rest.next() {
+            value = match (value, &seg.kind) {
+                (Value::Compound(c), SegmentKind::Key(k)) => c.get_mut(k.as_ref())?,
+                (Value::List(l), SegmentKind::Index(i)) => {
+                    let mut list = l;
+                    let mut idx = resolve_index(*i, list.len())?;
+
+                    loop {
+                        match list {
+                            List::Compound(v) => {
+                                let key = rest.next()?.key()?;
+                                break v[idx].get_mut(key)?;
+                            }
+                            List::List(v) => {
+                                list = &mut v[idx];
+                                let SegmentKind::Index(i) = rest.next()?.kind else {
+                                    return None;
+                                };
+                                idx = resolve_index(i, list.len())?;
+                            }
+                            _ => return None,
+                        }
+                    }
+                }
+                _ => return None,
+            };
+        }
+
+        Some(value)
+    }
+
+    /// Sets the value at an NBT path, returning the previous value if there
+    /// was one.
+    ///
+    /// Missing compounds and lists along the path are created. A list index
+    /// equal to the length of the list appends a new element, so `Items[0]`
+    /// creates the list `Items` if it does not exist. When setting a list
+    /// element, the value must have the same type as the other elements of
+    /// the list.
+    ///
+    /// See [`Compound::get_path`] for the path syntax. If an error is
+    /// returned, the compound is left unmodified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use valence_nbt::{compound, Compound, List};
+    ///
+    /// let mut c = Compound::new();
+    ///
+    /// c.set_path("Inventory[0].tag.display.Name", "Excalibur")
+    ///     .unwrap();
+    /// c.set_path("Inventory[0].Count", 1_i8).unwrap();
+    /// c.set_path("Pos[0]", 1.5).unwrap();
+    /// c.set_path("Pos[1]", 64.0).unwrap();
+    ///
+    /// assert_eq!(
+    ///     c,
+    ///     compound! {
+    ///         "Inventory" => List::Compound(vec![compound! {
+    ///             "tag" => compound! {
+    ///                 "display" => compound! {
+    ///                     "Name" => "Excalibur",
+    ///                 },
+    ///             },
+    ///             "Count" => 1_i8,
+    ///         }]),
+    ///         "Pos" => List::Double(vec![1.5, 64.0]),
+    ///     }
+    /// );
+    ///
+    /// assert!(c.set_path("Pos[5]", 0.0).is_err());
+    /// assert!(c.set_path("Pos[0]", "not a double").is_err());
+    /// ```
+    pub fn set_path(&mut self, path: &str, value: impl Into<Value>) -> Result<Option<Value>> {
+        let segs = parse(path)?;
+        set_in_compound(self, path, &segs, value.into())
+    }
+}
+
+#[derive(Debug)]
+struct Segment<'a> {
+    kind: SegmentKind<'a>,
+    /// Byte offset of the separator (`.` or `[`) before this segment.
+    start: usize,
+    /// Byte offset just past the end of this segment.
+    end: usize,
+}
+
+#[derive(Debug)]
+enum SegmentKind<'a> {
+    Key(Cow<'a, str>),
+    Index(i32),
+}
+
+impl Segment<'_> {
+    fn key(&self) -> Option<&str> {
+        match &self.kind {
+            SegmentKind::Key(k) => Some(k),
+            SegmentKind::Index(_) => None,
+        }
+    }
+}
+
+fn parse(path: &str) -> Result<Vec<Segment<'_>>> {
+    let mut segs = vec![];
+    let mut pos = 0;
+
+    let syntax = |pos, reason| PathError::Syntax { pos, reason };
+
+    while pos < path.len() {
+        let start = pos;
+        let rest = &path[pos..];
+
+        let kind = if let Some(rest) = rest.strip_prefix('[') {
+            if segs.is_empty() {
+                return Err(syntax(pos, "path must start with a key"));
+            }
+
+            let Some(close) = rest.find(']') else {
+                return Err(syntax(pos, "unclosed `[`"));
+            };
+
+            let idx = &rest[..close];
+
+            if idx.is_empty() {
+                return Err(syntax(pos, "`[]` is not supported"));
+            }
+
+            if idx.starts_with('{') {
+                return Err(syntax(pos, "compound filters are not supported"));
+            }
+
+            let idx = idx
+                .parse()
+                .map_err(|_| syntax(pos + 1, "invalid list index"))?;
+
+            pos += close + 2;
+            SegmentKind::Index(idx)
+        } else {
+            let key_pos = if segs.is_empty() {
+                pos
+            } else if rest.starts_with('.') {
+                pos + 1
+            } else {
+                return Err(syntax(pos, "expected `.` or `[`"));
+            };
+
+            let (key, len) =
+                parse_key(&path[key_pos..]).map_err(|(p, r)| syntax(key_pos + p, r))?;
+
+            pos = key_pos + len;
+            SegmentKind::Key(key)
+        };
+
+        segs.push(Segment {
+            kind,
+            start,
+            end: pos,
+        });
+    }
+
+    if segs.is_empty() {
+        return Err(syntax(0, "empty path"));
+    }
+
+    Ok(segs)
+}
+
+/// Parses a quoted or unquoted key at the start of `s`, returning the key and
+/// the number of bytes consumed.
+fn parse_key(s: &str) -> std::result::Result<(Cow<'_, str>, usize), (usize, &'static str)> {
+    match s.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let mut key = String::new();
+            let mut chars = s.char_indices().skip(1);
+
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => match chars.next() {
+                        Some((_, c)) if c == '\\' || c == quote => key.push(c),
+                        _ => return Err((i, "invalid escape sequence")),
+                    },
+                    c if c == quote => return Ok((Cow::Owned(key), i + 1)),
+                    c => key.push(c),
+                }
+            }
+
+            Err((0, "unclosed quote"))
+        }
+        _ => {
+            let len = s
+                .find(|c: char| {
+                    matches!(c, '.' | '[' | ']' | '{' | '}' | '"' | '\'') || c.is_whitespace()
+                })
+                .unwrap_or(s.len());
+
+            if len == 0 {
+                if s.starts_with('{') {
+                    return Err((0, "compound filters are not supported"));
+                }
+
+                return Err((0, "expected a key"));
+            }
+
+            Ok((Cow::Borrowed(&s[..len]), len))
+        }
+    }
+}
+
+/// Converts a possibly negative index into an index into a list of length
+/// `len`.
+fn resolve_index(idx: i32, len: usize) -> Option<usize> {
+    let idx = if idx < 0 {
+        len.checked_sub(idx.unsigned_abs() as usize)?
+    } else {
+        idx as usize
+    };
+
+    (idx < len).then_some(idx)
+}
+
+fn set_in_compound(
+    compound: &mut Compound,
+    path: &str,
+    segs: &[Segment],
+    value: Value,
+) -> Result<Option<Value>> {
+    let (seg, rest) = segs.split_first().expect("path must not be empty");
+
+    let SegmentKind::Key(key) = &seg.kind else {
+        return Err(PathError::TypeMismatch {
+            path: path[..seg.start].into(),
+            expected: Tag::List,
+            found: Tag::Compound,
+        });
+    };
+
+    if rest.is_empty() {
+        return Ok(compound.insert(key.as_ref(), value));
+    }
+
+    match compound.get_mut(key.as_ref()) {
+        Some(Value::Compound(c)) => set_in_compound(c, path, rest, value),
+        Some(Value::List(l)) => set_in_list(l, path, rest, value),
+        Some(other) => Err(PathError::TypeMismatch {
+            path: path[..seg.end].into(),
+            expected: container_tag(&rest[0]),
+            found: other.get_tag(),
+        }),
+        None => {
+            check_creatable(path, rest)?;
+            let mut new = create_container(&rest[0]);
+            let res = set_in_value(&mut new, path, rest, value);
+            compound.insert(key.as_ref(), new);
+            res
+        }
+    }
+}
+
+fn set_in_value(
+    container: &mut Value,
+    path: &str,
+    segs: &[Segment],
+    value: Value,
+) -> Result<Option<Value>> {
+    match container {
+        Value::Compound(c) => set_in_compound(c, path, segs, value),
+        Value::List(l) => set_in_list(l, path, segs, value),
+        _ => unreachable!("containers are compounds or lists"),
+    }
+}
+
+fn set_in_list(
+    list: &mut List,
+    path: &str,
+    segs: &[Segment],
+    value: Value,
+) -> Result<Option<Value>> {
+    let (seg, rest) = segs.split_first().expect("path must not be empty");
+
+    let SegmentKind::Index(i) = seg.kind else {
+        return Err(PathError::TypeMismatch {
+            path: path[..seg.start].into(),
+            expected: Tag::Compound,
+            found: Tag::List,
+        });
+    };
+
+    let len = list.len();
+
+    // An index equal to the length appends a new element.
+    let idx = match resolve_index(i, len) {
+        Some(idx) => idx,
+        None if i >= 0 && i as usize == len => len,
+        None => {
+            return Err(PathError::IndexOutOfBounds {
+                path: path[..seg.start].into(),
+                index: i,
+                len,
+            })
+        }
+    };
+
+    if rest.is_empty() {
+        let expected = list.element_tag();
+
+        return set_list_element(list, idx, value).map_err(|value| PathError::TypeMismatch {
+            path: path[..seg.end].into(),
+            expected,
+            found: value.get_tag(),
+        });
+    }
+
+    if idx == len {
+        check_creatable(path, rest)?;
+    }
+
+    if let List::End = list {
+        *list = match &rest[0].kind {
+            SegmentKind::Key(_) => List::Compound(vec![]),
+            SegmentKind::Index(_) => List::List(vec![]),
+        };
+    }
+
+    match (list, &rest[0].kind) {
+        (List::Compound(v), SegmentKind::Key(_)) => {
+            if idx == len {
+                v.push(Compound::new());
+            }
+            set_in_compound(&mut v[idx], path, rest, value)
+        }
+        (List::List(v), SegmentKind::Index(_)) => {
+            if idx == len {
+                v.push(List::End);
+            }
+            set_in_list(&mut v[idx], path, rest, value)
+        }
+        (list, _) => Err(PathError::TypeMismatch {
+            path: path[..seg.end].into(),
+            expected: container_tag(&rest[0]),
+            found: list.element_tag(),
+        }),
+    }
+}
+
+/// Returns an error if a list index in `segs` could not refer to the first
+/// element of a newly created list.
+fn check_creatable(path: &str, segs: &[Segment]) -> Result<()> {
+    for seg in segs {
+        if let SegmentKind::Index(i) = seg.kind {
+            if i != 0 {
+                return Err(PathError::IndexOutOfBounds {
+                    path: path[..seg.start].into(),
+                    index: i,
+                    len: 0,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the type of container that `seg` can be applied to.
+fn container_tag(seg: &Segment) -> Tag {
+    match seg.kind {
+        SegmentKind::Key(_) => Tag::Compound,
+        SegmentKind::Index(_) => Tag::List,
+    }
+}
+
+fn create_container(seg: &Segment) -> Value {
+    match seg.kind {
+        SegmentKind::Key(_) => Value::Compound(Compound::new()),
+        SegmentKind::Index(_) => Value::List(List::End),
+    }
+}
+
+/// Replaces the list element at `idx`, or appends to the list if `idx` is the
+/// length of the list. The value is returned back if its type does not match
+/// the list's element type.
+fn set_list_element(
+    list: &mut List,
+    idx: usize,
+    value: Value,
+) -> std::result::Result<Option<Value>, Value> {
+    fn replace_or_push<T>(vec: &mut Vec<T>, idx: usize, elem: T) -> Option<T> {
+        if idx == vec.len() {
+            vec.push(elem);
+            None
+        } else {
+            Some(std::mem::replace(&mut vec[idx], elem))
+        }
+    }
+
+    macro_rules! set {
+        ($($variant:ident),*) => {
+            match (list, value) {
+                (list @ List::End, value) => {
+                    *list = match value {
+                        $(Value::$variant(elem) => List::$variant(vec![elem]),)*
+                    };
+                    Ok(None)
+                }
+                $(
+                    (List::$variant(vec), Value::$variant(elem)) => {
+                        Ok(replace_or_push(vec, idx, elem).map(Value::$variant))
+                    }
+                )*
+                (_, value) => Err(value),
+            }
+        };
+    }
+
+    set!(
+        Byte, Short, Int, Long, Float, Double, ByteArray, String, List, Compound, IntArray,
+        LongArray
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compound;
+
+    fn example() -> Compound {
+        compound! {
+            "Inventory" => List::Compound(vec![
+                compound! {
+                    "id" => "minecraft:diamond_sword",
+                    "tag" => compound! {
+                        "display" => compound! {
+                            "Name" => "Excalibur",
+                        },
+                    },
+                },
+                compound! {
+                    "id" => "minecraft:stone",
+                },
+            ]),
+            "Pos" => List::Double(vec![1.0, 64.0, -3.0]),
+            "Grid" => List::List(vec![
+                List::Compound(vec![compound! { "x" => 1 }]),
+                List::Int(vec![1, 2, 3]),
+            ]),
+            "with space" => compound! {
+                "a.b" => 5,
+            },
+        }
+    }
+
+    #[test]
+    fn get_path() {
+        let c = example();
+
+        let cases = [
+            (
+                "Inventory[0].tag.display.Name",
+                Some(Value::from("Excalibur")),
+            ),
+            ("Inventory[-1].id", Some(Value::from("minecraft:stone"))),
+            (
+                "Inventory[-2].id",
+                Some(Value::from("minecraft:diamond_sword")),
+            ),
+            ("Inventory[2].id", None),
+            ("Inventory[-3].id", None),
+            ("Inventory[1].tag", None),
+            ("Inventory[0]", None),
+            (
+                "Pos",
+                Some(Value::List(List::Double(vec![1.0, 64.0, -3.0]))),
+            ),
+            ("Pos[0]", None),
+            ("Pos.x", None),
+            ("Grid[0][0].x", Some(Value::Int(1))),
+            ("Grid[1][0].x", None),
+            (r#""with space"."a.b""#, Some(Value::Int(5))),
+            (r#"'with space'.'a.b'"#, Some(Value::Int(5))),
+            ("with space.a.b", None),
+            ("Inventory[0", None),
+        ];
+
+        for (path, expected) in cases {
+            assert_eq!(c.get_path(path), expected.as_ref(), "{path}");
+        }
+    }
+
+    #[test]
+    fn get_path_mut() {
+        let mut c = example();
+
+        *c.get_path_mut("Inventory[0].tag.display.Name").unwrap() = "Mjolnir".into();
+        *c.get_path_mut("Grid[0][-1].x").unwrap() = 2.into();
+
+        assert_eq!(
+            c.get_path("Inventory[0].tag.display.Name"),
+            Some(&Value::from("Mjolnir"))
+        );
+        assert_eq!(c.get_path("Grid[0][0].x"), Some(&Value::Int(2)));
+        assert!(c.get_path_mut("Inventory[5].id").is_none());
+    }
+
+    #[test]
+    fn set_path() {
+        let mut c = example();
+
+        assert_eq!(c.set_path("Inventory[1].Count", 64_i8), Ok(None));
+        assert_eq!(c.set_path("Inventory[2].id", "minecraft:dirt"), Ok(None));
+        assert_eq!(c.set_path("Pos[-1]", 5.0), Ok(Some(Value::Double(-3.0))));
+        assert_eq!(c.set_path("Pos[3]", 7.0), Ok(None));
+        assert_eq!(c.set_path("a.b[0][0].c", 1), Ok(None));
+        assert_eq!(c.set_path("Tags[0]", "foo"), Ok(None));
+
+        assert_eq!(c.get_path("Inventory[1].Count"), Some(&Value::Byte(64)));
+        assert_eq!(
+            c.get_path("Inventory[2].id"),
+            Some(&Value::from("minecraft:dirt"))
+        );
+        assert_eq!(
+            c.get_path("Pos"),
+            Some(&Value::List(List::Double(vec![1.0, 64.0, 5.0, 7.0])))
+        );
+        assert_eq!(
+            c.get_path("a"),
+            Some(&Value::Compound(compound! {
+                "b" => List::List(vec![List::Compound(vec![compound! { "c" => 1 }])]),
+            }))
+        );
+        assert_eq!(
+            c.get_path("Tags"),
+            Some(&Value::List(List::String(vec!["foo".into()])))
+        );
+    }
+
+    #[test]
+    fn set_path_errors() {
+        let mut c = example();
+        let original = c.clone();
+
+        let cases = [
+            (
+                "Pos[4]",
+                Value::Double(0.0),
+                PathError::IndexOutOfBounds {
+                    path: "Pos".into(),
+                    index: 4,
+                    len: 3,
+                },
+            ),
+            (
+                "Pos[-4]",
+                Value::Double(0.0),
+                PathError::IndexOutOfBounds {
+                    path: "Pos".into(),
+                    index: -4,
+                    len: 3,
+                },
+            ),
+            (
+                "new.list[1]",
+                Value::Int(0),
+                PathError::IndexOutOfBounds {
+                    path: "new.list".into(),
+                    index: 1,
+                    len: 0,
+                },
+            ),
+            (
+                "Pos[0]",
+                Value::Float(0.0),
+                PathError::TypeMismatch {
+                    path: "Pos[0]".into(),
+                    expected: Tag::Double,
+                    found: Tag::Float,
+                },
+            ),
+            (
+                "Pos[0].x",
+                Value::Int(0),
+                PathError::TypeMismatch {
+                    path: "Pos[0]".into(),
+                    expected: Tag::Compound,
+                    found: Tag::Double,
+                },
+            ),
+            (
+                "Pos.x",
+                Value::Int(0),
+                PathError::TypeMismatch {
+                    path: "Pos".into(),
+                    expected: Tag::Compound,
+                    found: Tag::List,
+                },
+            ),
+            (
+                "Inventory[0].id.x",
+                Value::Int(0),
+                PathError::TypeMismatch {
+                    path: "Inventory[0].id".into(),
+                    expected: Tag::Compound,
+                    found: Tag::String,
+                },
+            ),
+            (
+                r#""with space"[0]"#,
+                Value::Int(0),
+                PathError::TypeMismatch {
+                    path: r#""with space""#.into(),
+                    expected: Tag::List,
+                    found: Tag::Compound,
+                },
+            ),
+        ];
+
+        for (path, value, err) in cases {
+            assert_eq!(c.set_path(path, value), Err(err), "{path}");
+            assert_eq!(c, original, "{path}");
+        }
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let cases = [
+            ("", 0),
+            ("[0]", 0),
+            ("a.", 2),
+            ("a..b", 2),
+            ("a[x]", 2),
+            ("a[]", 1),
+            ("a[0", 1),
+            ("a{b:1}", 1),
+            ("a.\"b", 2),
+            ("a.'b\\n'", 4),
+            ("a[0]b", 4),
+        ];
+
+        for (path, pos) in cases {
+            match parse(path) {
+                Err(PathError::Syntax { pos: p, .. }) => assert_eq!(p, pos, "{path}"),
+                res => panic!("expected syntax error for {path:?}, got {res:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! A streaming reader for NBT binary data.
+//!
+//! [`Reader`] decodes NBT one [`Event`] at a time without building a tree in
+//! memory and without recursion, which makes it suitable for inspecting or
+//! validating untrusted data. The amount of work it will do is bounded by
+//! [`DecodeLimits`].
+
+use std::borrow::Cow;
+use std::mem;
+
+use byteorder::{BigEndian, ReadBytesExt};
+use cesu8::Cesu8DecodingError;
+
+use crate::tag::Tag;
+use crate::{Compound, Error, List, Result};
+
+/// Limits on the amount of data accepted when decoding NBT.
+///
+/// Exceeding any limit causes decoding to fail with an error.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DecodeLimits {
+    /// The maximum number of compounds and lists that can be nested inside
+    /// the root compound.
+    pub max_depth: usize,
+    /// The maximum number of values (including list elements, but not array
+    /// elements) that can be decoded.
+    pub max_elements: usize,
+    /// The maximum approximate size in bytes of the decoded data. Numbers
+    /// count as their width, and strings and arrays count as the size of
+    /// their contents.
+    pub max_size: usize,
+}
+
+impl DecodeLimits {
+    /// The limits used by [`from_binary_slice`](crate::from_binary_slice).
+    /// Only the depth is limited.
+    pub const DEFAULT: Self = Self {
+        max_depth: 512,
+        max_elements: usize::MAX,
+        max_size: usize::MAX,
+    };
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// An item of NBT data produced by a [`Reader`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum Event<'a> {
+    /// The name of an entry in a compound, or the name of the root compound.
+    /// The next event is the value of the entry.
+    Key(Cow<'a, str>),
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(Cow<'a, str>),
+    /// The start of a list. `len` values of type `elem` follow, and then
+    /// [`Event::ListEnd`].
+    ListStart {
+        elem: Tag,
+        len: usize,
+    },
+    ListEnd,
+    /// The start of a compound. Pairs of [`Event::Key`] and a value follow
+    /// until [`Event::CompoundEnd`].
+    CompoundStart,
+    CompoundEnd,
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+/// A streaming reader for uncompressed NBT binary data.
+///
+/// The first event is the [`Event::Key`] containing the name of the root
+/// compound, followed by the events for the root compound itself. If the data
+/// is an empty root (a single `TAG_End`), no events are produced.
+///
+/// # Examples
+///
+/// ```
+/// use valence_nbt::reader::{Event, Reader};
+/// use valence_nbt::{compound, to_binary_writer, DecodeLimits};
+///
+/// let mut buf = vec![];
+/// to_binary_writer(&mut buf, &compound! { "int" => 5 }, "root").unwrap();
+///
+/// let mut reader = Reader::new(&buf, DecodeLimits::default());
+/// let mut events = vec![];
+///
+/// while let Some(event) = reader.next_event().unwrap() {
+///     events.push(event);
+/// }
+///
+/// assert_eq!(
+///     events,
+///     [
+///         Event::Key("root".into()),
+///         Event::CompoundStart,
+///         Event::Key("int".into()),
+///         Event::Int(5),
+///         Event::CompoundEnd,
+///     ]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Reader<'a> {
+    slice: &'a [u8],
+    limits: DecodeLimits,
+    /// The compounds and lists currently being read.
+    stack: Vec<Frame>,
+    /// The type of the value following a key that was just read.
+    pending: Option<Tag>,
+    state: State,
+    elements: usize,
+    size: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Frame {
+    Compound,
+    List { elem: Tag, remaining: usize },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum State {
+    Start,
+    Reading,
+    Done,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(slice: &'a [u8], limits: DecodeLimits) -> Self {
+        Self {
+            slice,
+            limits,
+            stack: vec![],
+            pending: None,
+            state: State::Start,
+            elements: 0,
+            size: 0,
+        }
+    }
+
+    /// Returns the input that has not been read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.slice
+    }
+
+    /// Reads the next event, or returns `None` once the root compound has
+    /// been read.
+    ///
+    /// After an error is returned, the reader should not be used any further.
+    pub fn next_event(&mut self) -> Result<Option<Event<'a>>> {
+        if let Some(tag) = self.pending.take() {
+            return self.read_value(tag).map(Some);
+        }
+
+        match self.state {
+            State::Start => {
+                self.state = State::Reading;
+
+                match self.read_tag()? {
+                    // For cases such as Block Entity Data in the
+                    // ChunkUpdateAndUpdateLight Packet
+                    // https://wiki.vg/Protocol#Chunk_Data_and_Update_Light
+                    Tag::End => {
+                        self.state = State::Done;
+                        Ok(None)
+                    }
+                    Tag::Compound => {
+                        let name = self.read_string()?;
+                        self.pending = Some(Tag::Compound);
+                        Ok(Some(Event::Key(name)))
+                    }
+                    tag => Err(Error::new_owned(format!(
+                        "expected root tag for compound (got {tag})",
+                    ))),
+                }
+            }
+            State::Reading => match self.stack.last_mut() {
+                Some(Frame::Compound) => {
+                    let tag = self.read_tag()?;
+
+                    if tag == Tag::End {
+                        self.pop();
+                        return Ok(Some(Event::CompoundEnd));
+                    }
+
+                    let name = self.read_string()?;
+                    self.add_size(name.len())?;
+                    self.pending = Some(tag);
+                    Ok(Some(Event::Key(name)))
+                }
+                Some(Frame::List { elem, remaining }) => {
+                    if *remaining == 0 {
+                        self.pop();
+                        return Ok(Some(Event::ListEnd));
+                    }
+
+                    *remaining -= 1;
+                    let elem = *elem;
+                    self.read_value(elem).map(Some)
+                }
+                None => unreachable!("the root compound has not been read"),
+            },
+            State::Done => Ok(None),
+        }
+    }
+
+    fn pop(&mut self) {
+        self.stack.pop();
+
+        if self.stack.is_empty() {
+            self.state = State::Done;
+        }
+    }
+
+    fn push(&mut self, frame: Frame) -> Result<()> {
+        // The root compound does not count towards the depth.
+        if self.stack.len() > self.limits.max_depth {
+            return Err(Error::new_owned(format!(
+                "reached maximum depth of {}",
+                self.limits.max_depth
+            )));
+        }
+
+        self.stack.push(frame);
+        Ok(())
+    }
+
+    fn add_size(&mut self, size: usize) -> Result<()> {
+        self.size = self.size.saturating_add(size);
+
+        if self.size > self.limits.max_size {
+            return Err(Error::new_owned(format!(
+                "decoded data exceeds maximum size of {} bytes",
+                self.limits.max_size
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Adds the size of a number unless it is a list element, in which case
+    /// its size was added when the list was started.
+    fn add_number_size(&mut self, size: usize) -> Result<()> {
+        if let Some(Frame::List { .. }) = self.stack.last() {
+            Ok(())
+        } else {
+            self.add_size(size)
+        }
+    }
+
+    fn read_value(&mut self, tag: Tag) -> Result<Event<'a>> {
+        self.elements += 1;
+
+        if self.elements > self.limits.max_elements {
+            return Err(Error::new_owned(format!(
+                "reached maximum element count of {}",
+                self.limits.max_elements
+            )));
+        }
+
+        match tag {
+            Tag::End => unreachable!("illegal TAG_End argument"),
+            Tag::Byte => {
+                self.add_number_size(1)?;
+                Ok(Event::Byte(self.slice.read_i8()?))
+            }
+            Tag::Short => {
+                self.add_number_size(2)?;
+                Ok(Event::Short(self.slice.read_i16::<BigEndian>()?))
+            }
+            Tag::Int => {
+                self.add_number_size(4)?;
+                Ok(Event::Int(self.read_int()?))
+            }
+            Tag::Long => {
+                self.add_number_size(8)?;
+                Ok(Event::Long(self.read_long()?))
+            }
+            Tag::Float => {
+                self.add_number_size(4)?;
+                Ok(Event::Float(self.slice.read_f32::<BigEndian>()?))
+            }
+            Tag::Double => {
+                self.add_number_size(8)?;
+                Ok(Event::Double(self.slice.read_f64::<BigEndian>()?))
+            }
+            Tag::ByteArray => {
+                let len = self.read_array_len(Tag::ByteArray, 1)?;
+
+                let (left, right) = self.slice.split_at(len);
+                self.slice = right;
+
+                Ok(Event::ByteArray(left.iter().map(|b| *b as i8).collect()))
+            }
+            Tag::String => {
+                let string = self.read_string()?;
+                self.add_size(string.len())?;
+                Ok(Event::String(string))
+            }
+            Tag::List => self.read_list_start(),
+            Tag::Compound => {
+                self.push(Frame::Compound)?;
+                Ok(Event::CompoundStart)
+            }
+            Tag::IntArray => {
+                let len = self.read_array_len(Tag::IntArray, mem::size_of::<i32>())?;
+
+                let mut array = Vec::with_capacity(len);
+                for _ in 0..len {
+                    array.push(self.read_int()?);
+                }
+
+                Ok(Event::IntArray(array))
+            }
+            Tag::LongArray => {
+                let len = self.read_array_len(Tag::LongArray, mem::size_of::<i64>())?;
+
+                let mut array = Vec::with_capacity(len);
+                for _ in 0..len {
+                    array.push(self.read_long()?);
+                }
+
+                Ok(Event::LongArray(array))
+            }
+        }
+    }
+
+    fn read_list_start(&mut self) -> Result<Event<'a>> {
+        let elem = self.read_tag()?;
+        let len = self.read_int()?;
+
+        if len.is_negative() {
+            return Err(Error::new_owned(format!(
+                "negative {elem} list length of {len}",
+            )));
+        }
+
+        let len = len as usize;
+
+        if elem == Tag::End && len != 0 {
+            return Err(Error::new_owned(format!(
+                "TAG_End list with nonzero length of {len}"
+            )));
+        }
+
+        // Ensure the length is plausible given the size of the remaining
+        // input. Lists of compounds need at least a TAG_End per element.
+        let min_elem_size = match elem {
+            Tag::End => 0,
+            Tag::Byte | Tag::Compound => 1,
+            Tag::Short | Tag::String => 2,
+            Tag::Int | Tag::Float | Tag::ByteArray | Tag::IntArray | Tag::LongArray => 4,
+            Tag::Long | Tag::Double => 8,
+            Tag::List => 5,
+        };
+
+        if len as u64 * min_elem_size as u64 > self.slice.len() as u64 {
+            return Err(Error::new_owned(format!(
+                "{elem} list of length {len} exceeds remainder of input"
+            )));
+        }
+
+        let elem_size = match elem {
+            Tag::End => 0,
+            Tag::Byte => 1,
+            Tag::Short => 2,
+            Tag::Int | Tag::Float => 4,
+            Tag::Long | Tag::Double => 8,
+            Tag::ByteArray | Tag::IntArray | Tag::LongArray => mem::size_of::<Vec<i8>>(),
+            Tag::String => mem::size_of::<String>(),
+            Tag::List => mem::size_of::<List>(),
+            Tag::Compound => mem::size_of::<Compound>(),
+        };
+
+        self.add_size(len.saturating_mul(elem_size))?;
+        self.push(Frame::List {
+            elem,
+            remaining: len,
+        })?;
+
+        Ok(Event::ListStart { elem, len })
+    }
+
+    /// Reads the length of an array and checks it against the remaining input
+    /// and the size limit.
+    fn read_array_len(&mut self, tag: Tag, elem_size: usize) -> Result<usize> {
+        let len = self.read_int()?;
+
+        if len.is_negative() {
+            return Err(Error::new_owned(format!("negative {tag} length of {len}")));
+        }
+
+        let len = len as usize;
+
+        if len as u64 * elem_size as u64 > self.slice.len() as u64 {
+            return Err(Error::new_owned(format!(
+                "{tag} of length {len} exceeds remainder of input"
+            )));
+        }
+
+        self.add_size(len * elem_size)?;
+
+        Ok(len)
+    }
+
+    fn read_tag(&mut self) -> Result<Tag> {
+        match self.slice.read_u8()? {
+            0 => Ok(Tag::End),
+            1 => Ok(Tag::Byte),
+            2 => Ok(Tag::Short),
+            3 => Ok(Tag::Int),
+            4 => Ok(Tag::Long),
+            5 => Ok(Tag::Float),
+            6 => Ok(Tag::Double),
+            7 => Ok(Tag::ByteArray),
+            8 => Ok(Tag::String),
+            9 => Ok(Tag::List),
+            10 => Ok(Tag::Compound),
+            11 => Ok(Tag::IntArray),
+            12 => Ok(Tag::LongArray),
+            byte => Err(Error::new_owned(format!("invalid tag byte of {byte:#x}"))),
+        }
+    }
+
+    fn read_int(&mut self) -> Result<i32> {
+        Ok(self.slice.read_i32::<BigEndian>()?)
+    }
+
+    fn read_long(&mut self) -> Result<i64> {
+        Ok(self.slice.read_i64::<BigEndian>()?)
+    }
+
+    fn read_string(&mut self) -> Result<Cow<'a, str>> {
+        let len = self.slice.read_u16::<BigEndian>()?.into();
+
+        if len > self.slice.len() {
+            return Err(Error::new_owned(format!(
+                "string of length {len} exceeds remainder of input"
+            )));
+        }
+
+        let (left, right) = self.slice.split_at(len);
+
+        match cesu8::from_java_cesu8(left) {
+            Ok(cow) => {
+                self.slice = right;
+                Ok(cow)
+            }
+            Err(Cesu8DecodingError) => {
+                Err(Error::new_static("could not convert CESU-8 data to UTF-8"))
+            }
+        }
+    }
+}
@@ -0,0 +1,646 @@
+//! Support for converting Rust types to and from NBT with [serde].
+//!
+//! This module is only available with the `serde` feature enabled.
+//!
+//! # Data model
+//!
+//! | Rust / serde                    | NBT                                          |
+//! |---------------------------------|----------------------------------------------|
+//! | `bool`                          | byte (`0` or `1`)                            |
+//! | `i8`, `i16`, `i32`, `i64`       | byte, short, int, long                       |
+//! | `u8`, `u16`, `u32`, `u64`       | byte, short, int, long (if in range)         |
+//! | `f32`, `f64`                    | float, double                                |
+//! | `char`, `String`                | string                                       |
+//! | bytes (`serialize_bytes`)       | byte array                                   |
+//! | [`ByteArray`], [`IntArray`], [`LongArray`] | byte array, int array, long array |
+//! | sequences and tuples            | list                                         |
+//! | structs and maps                | compound                                     |
+//! | unit structs                    | empty compound                               |
+//! | `Option<T>`                     | `T`, or omitted from the compound if `None`  |
+//! | unit enum variants              | string containing the variant name           |
+//! | other enum variants             | compound with the variant name as its only key |
+//!
+//! Note that a `Vec<i8>` becomes a list of bytes, not a byte array. Use the
+//! array wrappers to produce the array tags. When deserializing, arrays and
+//! lists of numbers are interchangeable.
+//!
+//! Lists must be homogeneous, and map keys must be strings or unit enum
+//! variants. `None` and `()` have no NBT representation of their own, so they
+//! may only appear as compound fields, where they are omitted.
+//!
+//! Errors report the path to the value that caused them, such as
+//! `inventory[2].id`.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use valence_nbt::serde::IntArray;
+//! use valence_nbt::{compound, from_value, to_value, List};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Player {
+//!     name: String,
+//!     health: f32,
+//!     uuid: IntArray,
+//!     inventory: Vec<Item>,
+//! }
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Item {
+//!     id: String,
+//!     count: i8,
+//! }
+//!
+//! let player = Player {
+//!     name: "Steve".into(),
+//!     health: 20.0,
+//!     uuid: IntArray(vec![1, 2, 3, 4]),
+//!     inventory: vec![Item {
+//!         id: "minecraft:stone".into(),
+//!         count: 64,
+//!     }],
+//! };
+//!
+//! let value = to_value(&player).unwrap();
+//!
+//! assert_eq!(
+//!     value,
+//!     compound! {
+//!         "health" => 20.0_f32,
+//!         "inventory" => List::Compound(vec![compound! {
+//!             "count" => 64_i8,
+//!             "id" => "minecraft:stone",
+//!         }]),
+//!         "name" => "Steve",
+//!         "uuid" => vec![1, 2, 3, 4],
+//!     }
+//!     .into()
+//! );
+//!
+//! assert_eq!(from_value::<Player>(value).unwrap(), player);
+//! ```
+//!
+//! [serde]: https://serde.rs
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use self::de::ValueDeserializer;
+use self::ser::ValueSerializer;
+use crate::Value;
+
+mod de;
+mod ser;
+
+/// Converts a serializable value into an NBT [`Value`].
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+    value
+        .serialize(ValueSerializer)?
+        .ok_or_else(|| Error::new("value has no NBT representation"))
+}
+
+/// Converts an NBT [`Value`] into a deserializable value.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    T::deserialize(ValueDeserializer(value))
+}
+
+/// Encodes a serializable value as uncompressed NBT binary data.
+///
+/// The value must serialize to a compound, which is written as the root
+/// compound with the name `root_name`. This is the serde counterpart of
+/// [`crate::to_binary_writer`].
+pub fn to_binary_writer<W, T>(writer: W, value: &T, root_name: &str) -> Result<(), Error>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    match to_value(value)? {
+        Value::Compound(c) => Ok(crate::to_binary_writer(writer, &c, root_name)?),
+        other => Err(Error::new(format!(
+            "root value must be a compound (got {})",
+            other.get_tag()
+        ))),
+    }
+}
+
+/// Decodes a deserializable value from uncompressed NBT binary data.
+///
+/// The string returned is the name of the root compound. This is the serde
+/// counterpart of [`crate::from_binary_slice`].
+pub fn from_binary_slice<T: DeserializeOwned>(slice: &mut &[u8]) -> Result<(T, String), Error> {
+    let (compound, root_name) = crate::from_binary_slice(slice)?;
+    Ok((from_value(Value::Compound(compound))?, root_name))
+}
+
+/// Errors that can occur when converting between Rust values and NBT.
+#[derive(Debug)]
+pub struct Error {
+    /// Box this to keep the size of `Result<T, Error>` small.
+    inner: Box<ErrorInner>,
+}
+
+#[derive(Debug)]
+struct ErrorInner {
+    message: Box<str>,
+    /// The path to the value that caused the error, innermost segment first.
+    path: Vec<PathSegment>,
+    source: Option<crate::Error>,
+}
+
+#[derive(Debug)]
+enum PathSegment {
+    Field(String),
+    Index(usize),
+}
+
+impl Error {
+    pub(crate) fn new(msg: impl Into<Box<str>>) -> Self {
+        Self {
+            inner: Box::new(ErrorInner {
+                message: msg.into(),
+                path: vec![],
+                source: None,
+            }),
+        }
+    }
+
+    /// Records that this error occurred within the field `name`.
+    pub(crate) fn in_field(mut self, name: &str) -> Self {
+        self.inner.path.push(PathSegment::Field(name.into()));
+        self
+    }
+
+    /// Records that this error occurred within the list element at `idx`.
+    pub(crate) fn in_index(mut self, idx: usize) -> Self {
+        self.inner.path.push(PathSegment::Index(idx));
+        self
+    }
+
+    /// Returns the path to the value that caused this error, such as
+    /// `inventory[2].id`. The path is empty if the error occurred at the root.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+
+        for seg in self.inner.path.iter().rev() {
+            match seg {
+                PathSegment::Field(name) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(name);
+                }
+                PathSegment::Index(idx) => {
+                    path.push('[');
+                    path.push_str(&idx.to_string());
+                    path.push(']');
+                }
+            }
+        }
+
+        path
+    }
+
+    /// Returns the error message without the path.
+    pub fn message(&self) -> &str {
+        &self.inner.message
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.inner.path.is_empty() {
+            write!(f, "{}", self.inner.message)
+        } else {
+            write!(f, "{}: {}", self.path(), self.inner.message)
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match &self.inner.source {
+            Some(e) => Some(e),
+            None => None,
+        }
+    }
+}
+
+impl From<crate::Error> for Error {
+    fn from(e: crate::Error) -> Self {
+        let mut res = Self::new(e.to_string());
+        res.inner.source = Some(e);
+        res
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::new(msg.to_string())
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::new(msg.to_string())
+    }
+}
+
+/// Newtype struct names used to tell the NBT serializer to produce an array
+/// tag instead of a list.
+const BYTE_ARRAY_NAME: &str = "__valence_nbt_byte_array";
+const INT_ARRAY_NAME: &str = "__valence_nbt_int_array";
+const LONG_ARRAY_NAME: &str = "__valence_nbt_long_array";
+
+macro_rules! array_wrapper {
+    ($(#[$attr:meta])* $name:ident, $elem:ty, $magic:ident) => {
+        $(#[$attr])*
+        ///
+        /// Other serde formats see this as a plain sequence.
+        #[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
+        pub struct $name(pub Vec<$elem>);
+
+        impl Deref for $name {
+            type Target = Vec<$elem>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl DerefMut for $name {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+
+        impl From<Vec<$elem>> for $name {
+            fn from(v: Vec<$elem>) -> Self {
+                Self(v)
+            }
+        }
+
+        impl From<$name> for Vec<$elem> {
+            fn from(v: $name) -> Self {
+                v.0
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_newtype_struct($magic, &self.0)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct ArrayVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for ArrayVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str(concat!("a sequence of ", stringify!($elem)))
+                    }
+
+                    fn visit_newtype_struct<D: Deserializer<'de>>(
+                        self,
+                        deserializer: D,
+                    ) -> Result<Self::Value, D::Error> {
+                        Vec::deserialize(deserializer).map($name)
+                    }
+
+                    fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                        self,
+                        mut seq: A,
+                    ) -> Result<Self::Value, A::Error> {
+                        let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                        while let Some(elem) = seq.next_element()? {
+                            vec.push(elem);
+                        }
+                        Ok($name(vec))
+                    }
+                }
+
+                deserializer.deserialize_newtype_struct($magic, ArrayVisitor)
+            }
+        }
+    };
+}
+
+array_wrapper!(
+    /// A `Vec<i8>` that is serialized as an NBT byte array rather than a list.
+    ByteArray,
+    i8,
+    BYTE_ARRAY_NAME
+);
+
+array_wrapper!(
+    /// A `Vec<i32>` that is serialized as an NBT int array rather than a list.
+    IntArray,
+    i32,
+    INT_ARRAY_NAME
+);
+
+array_wrapper!(
+    /// A `Vec<i64>` that is serialized as an NBT long array rather than a list.
+    LongArray,
+    i64,
+    LONG_ARRAY_NAME
+);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{compound, Compound, List};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Player {
+        name: String,
+        on_ground: bool,
+        xp: u32,
+        pos: [f64; 3],
+        uuid: IntArray,
+        seen_blocks: LongArray,
+        skin: ByteArray,
+        inventory: Vec<Item>,
+        spawn: Option<(i32, i32, i32)>,
+        game_mode: GameMode,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Item {
+        id: String,
+        count: i8,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum GameMode {
+        Survival,
+        Creative,
+    }
+
+    fn player() -> Player {
+        Player {
+            name: "Steve".into(),
+            on_ground: true,
+            xp: 1337,
+            pos: [1.0, 64.0, -3.5],
+            uuid: IntArray(vec![1, 2, 3, 4]),
+            seen_blocks: LongArray(vec![]),
+            skin: ByteArray(vec![-1, 0, 1]),
+            inventory: vec![
+                Item {
+                    id: "minecraft:stone".into(),
+                    count: 64,
+                },
+                Item {
+                    id: "minecraft:dirt".into(),
+                    count: 1,
+                },
+            ],
+            spawn: None,
+            game_mode: GameMode::Creative,
+        }
+    }
+
+    #[test]
+    fn struct_to_value() {
+        let expected = compound! {
+            "name" => "Steve",
+            "on_ground" => true,
+            "xp" => 1337,
+            "pos" => List::Double(vec![1.0, 64.0, -3.5]),
+            "uuid" => vec![1, 2, 3, 4],
+            "seen_blocks" => Vec::<i64>::new(),
+            "skin" => vec![-1_i8, 0, 1],
+            "inventory" => List::Compound(vec![
+                compound! {
+                    "id" => "minecraft:stone",
+                    "count" => 64_i8,
+                },
+                compound! {
+                    "id" => "minecraft:dirt",
+                    "count" => 1_i8,
+                },
+            ]),
+            "game_mode" => "Creative",
+        };
+
+        assert_eq!(to_value(&player()).unwrap(), Value::Compound(expected));
+    }
+
+    #[test]
+    fn struct_round_trip() {
+        let mut player = player();
+
+        let value = to_value(&player).unwrap();
+        assert_eq!(from_value::<Player>(value).unwrap(), player);
+
+        player.spawn = Some((10, 70, -20));
+
+        let value = to_value(&player).unwrap();
+        assert_eq!(
+            value.as_compound().unwrap().get("spawn"),
+            Some(&Value::List(List::Int(vec![10, 70, -20])))
+        );
+        assert_eq!(from_value::<Player>(value).unwrap(), player);
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let player = player();
+
+        let mut buf = vec![];
+        to_binary_writer(&mut buf, &player, "player").unwrap();
+
+        let (compound, root_name) = crate::from_binary_slice(&mut buf.as_slice()).unwrap();
+        assert_eq!(root_name, "player");
+        assert_eq!(Value::Compound(compound), to_value(&player).unwrap());
+
+        let (decoded, root_name) = from_binary_slice::<Player>(&mut buf.as_slice()).unwrap();
+        assert_eq!(root_name, "player");
+        assert_eq!(decoded, player);
+
+        let err = to_binary_writer(&mut buf, &5_i32, "").unwrap_err();
+        assert_eq!(err.to_string(), "root value must be a compound (got int)");
+    }
+
+    #[test]
+    fn enums() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        enum Shape {
+            Point,
+            Circle(f32),
+            Line(i32, i32),
+            Rect { width: i32, height: i32 },
+        }
+
+        let cases = [
+            (Shape::Point, Value::from("Point")),
+            (Shape::Circle(1.5), compound! { "Circle" => 1.5_f32 }.into()),
+            (
+                Shape::Line(1, 2),
+                compound! { "Line" => List::Int(vec![1, 2]) }.into(),
+            ),
+            (
+                Shape::Rect {
+                    width: 3,
+                    height: 4,
+                },
+                compound! { "Rect" => compound! { "width" => 3, "height" => 4 } }.into(),
+            ),
+        ];
+
+        for (shape, value) in cases {
+            assert_eq!(to_value(&shape).unwrap(), value);
+            assert_eq!(from_value::<Shape>(value).unwrap(), shape);
+        }
+
+        let err = from_value::<Shape>(compound! { "Point" => 1 }.into()).unwrap_err();
+        assert_eq!(err.path(), "Point");
+
+        let err = from_value::<Shape>(compound! { "a" => 1, "b" => 2 }.into()).unwrap_err();
+        assert!(err.path().is_empty());
+    }
+
+    #[test]
+    fn nested_maps() {
+        let mut map = HashMap::new();
+        map.insert(
+            "overworld".to_owned(),
+            BTreeMap::from([("spawn_x".to_owned(), 10), ("spawn_z".to_owned(), -5)]),
+        );
+        map.insert("nether".to_owned(), BTreeMap::new());
+
+        let value = to_value(&map).unwrap();
+        assert_eq!(
+            value,
+            compound! {
+                "overworld" => compound! { "spawn_x" => 10, "spawn_z" => -5 },
+                "nether" => Compound::new(),
+            }
+            .into()
+        );
+
+        assert_eq!(
+            from_value::<HashMap<String, BTreeMap<String, i32>>>(value).unwrap(),
+            map
+        );
+
+        let err = to_value(&HashMap::from([(1, 2)])).unwrap_err();
+        assert_eq!(err.to_string(), "map keys must be strings");
+    }
+
+    #[test]
+    fn options_in_lists() {
+        let value = to_value(&vec![Some(1), None]).unwrap_err();
+        assert_eq!(value.path(), "[1]");
+
+        assert!(to_value(&None::<i32>).is_err());
+        assert_eq!(to_value(&Some(5_i16)).unwrap(), Value::Short(5));
+    }
+
+    #[test]
+    fn serialize_error_paths() {
+        #[derive(Serialize)]
+        struct Outer {
+            inner: Vec<Inner>,
+        }
+
+        #[derive(Serialize)]
+        struct Inner {
+            values: Vec<Number>,
+        }
+
+        #[derive(Serialize)]
+        #[serde(untagged)]
+        enum Number {
+            Int(i32),
+            Byte(u8),
+        }
+
+        let outer = Outer {
+            inner: vec![
+                Inner { values: vec![] },
+                Inner {
+                    values: vec![Number::Int(1), Number::Byte(200)],
+                },
+            ],
+        };
+
+        let err = to_value(&outer).unwrap_err();
+        assert_eq!(err.path(), "inner[1].values[1]");
+        assert_eq!(
+            err.to_string(),
+            "inner[1].values[1]: unsigned integer 200 is out of range for its NBT type"
+        );
+
+        let outer = Outer {
+            inner: vec![Inner {
+                values: vec![Number::Int(1), Number::Byte(2)],
+            }],
+        };
+
+        let err = to_value(&outer).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "inner[0].values[1]: list elements must have the same type (expected int, got \
+             byte)"
+        );
+    }
+
+    #[test]
+    fn deserialize_error_paths() {
+        let mut value = to_value(&player()).unwrap();
+
+        items_mut(&mut value)[1].insert("count", "lots");
+
+        let err = from_value::<Player>(value.clone()).unwrap_err();
+        assert_eq!(err.path(), "inventory[1].count");
+        assert_eq!(err.message(), "invalid type: string \"lots\", expected i8");
+
+        items_mut(&mut value)[1].insert("count", 1_i8);
+        items_mut(&mut value)[1].remove("id");
+
+        let err = from_value::<Player>(value).unwrap_err();
+        assert_eq!(err.to_string(), "inventory[1]: missing field `id`");
+
+        let err = from_value::<Item>(compound! { "id" => "a", "count" => 300 }.into()).unwrap_err();
+        assert_eq!(err.path(), "count");
+    }
+
+    fn items_mut(value: &mut Value) -> &mut Vec<Compound> {
+        match value.as_compound_mut().unwrap().get_mut("inventory") {
+            Some(Value::List(List::Compound(items))) => items,
+            _ => panic!("expected a list of compounds"),
+        }
+    }
+
+    #[test]
+    fn arrays_and_lists_are_interchangeable() {
+        let value = Value::from(vec![1_i8, 2, 3]);
+        assert_eq!(from_value::<Vec<i8>>(value.clone()).unwrap(), vec![1, 2, 3]);
+        assert_eq!(from_value::<Vec<i32>>(value).unwrap(), vec![1, 2, 3]);
+
+        let value = Value::List(List::Long(vec![5, 6]));
+        assert_eq!(
+            from_value::<LongArray>(value).unwrap(),
+            LongArray(vec![5, 6])
+        );
+
+        assert_eq!(
+            to_value(&vec![1_i8, 2]).unwrap(),
+            Value::List(List::Byte(vec![1, 2]))
+        );
+    }
+}
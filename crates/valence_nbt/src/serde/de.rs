@@ -0,0 +1,297 @@
+use std::vec;
+
+use serde::de::value::StringDeserializer;
+use serde::de::{self, DeserializeSeed, Deserializer, IntoDeserializer, Unexpected, Visitor};
+use serde::forward_to_deserialize_any;
+
+use super::Error;
+use crate::{compound, List, Value};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Deserializes Rust values from an owned NBT value.
+pub(super) struct ValueDeserializer(pub(super) Value);
+
+impl<'de> Deserializer<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Value::Byte(v) => visitor.visit_i8(v),
+            Value::Short(v) => visitor.visit_i16(v),
+            Value::Int(v) => visitor.visit_i32(v),
+            Value::Long(v) => visitor.visit_i64(v),
+            Value::Float(v) => visitor.visit_f32(v),
+            Value::Double(v) => visitor.visit_f64(v),
+            Value::ByteArray(v) => visit_seq(v.into_iter().map(Value::Byte).collect(), visitor),
+            Value::String(v) => visitor.visit_string(v),
+            Value::List(v) => visit_seq(list_into_values(v), visitor),
+            Value::Compound(v) => visitor.visit_map(MapDeserializer {
+                iter: v.into_iter(),
+                value: None,
+            }),
+            Value::IntArray(v) => visit_seq(v.into_iter().map(Value::Int).collect(), visitor),
+            Value::LongArray(v) => visit_seq(v.into_iter().map(Value::Long).collect(), visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Value::Byte(v) => visitor.visit_bool(v != 0),
+            other => ValueDeserializer(other).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Value::ByteArray(v) => visitor.visit_byte_buf(v.into_iter().map(|b| b as u8).collect()),
+            other => ValueDeserializer(other).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        // Absent values are handled by the compound they would appear in.
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.0 {
+            Value::Compound(c) if c.is_empty() => visitor.visit_unit(),
+            other => ValueDeserializer(other).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.0 {
+            Value::String(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+            }),
+            Value::Compound(c) if c.len() == 1 => {
+                let (variant, value) = c.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            other => Err(de::Error::invalid_type(
+                unexpected(&other),
+                &"a string or a compound with a single key",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+fn visit_seq<'de, V: Visitor<'de>>(values: Vec<Value>, visitor: V) -> Result<V::Value> {
+    visitor.visit_seq(SeqDeserializer {
+        iter: values.into_iter(),
+        idx: 0,
+    })
+}
+
+fn list_into_values(list: List) -> Vec<Value> {
+    macro_rules! values {
+        ($v:expr, $variant:ident) => {
+            $v.into_iter().map(Value::$variant).collect()
+        };
+    }
+
+    match list {
+        List::End => vec![],
+        List::Byte(v) => values!(v, Byte),
+        List::Short(v) => values!(v, Short),
+        List::Int(v) => values!(v, Int),
+        List::Long(v) => values!(v, Long),
+        List::Float(v) => values!(v, Float),
+        List::Double(v) => values!(v, Double),
+        List::ByteArray(v) => values!(v, ByteArray),
+        List::String(v) => values!(v, String),
+        List::List(v) => values!(v, List),
+        List::Compound(v) => values!(v, Compound),
+        List::IntArray(v) => values!(v, IntArray),
+        List::LongArray(v) => values!(v, LongArray),
+    }
+}
+
+fn unexpected(value: &Value) -> Unexpected<'_> {
+    match value {
+        Value::Byte(v) => Unexpected::Signed(*v as i64),
+        Value::Short(v) => Unexpected::Signed(*v as i64),
+        Value::Int(v) => Unexpected::Signed(*v as i64),
+        Value::Long(v) => Unexpected::Signed(*v),
+        Value::Float(v) => Unexpected::Float(*v as f64),
+        Value::Double(v) => Unexpected::Float(*v),
+        Value::String(v) => Unexpected::Str(v),
+        Value::Compound(_) => Unexpected::Map,
+        Value::ByteArray(_) | Value::List(_) | Value::IntArray(_) | Value::LongArray(_) => {
+            Unexpected::Seq
+        }
+    }
+}
+
+struct SeqDeserializer {
+    iter: vec::IntoIter<Value>,
+    idx: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        let Some(value) = self.iter.next() else {
+            return Ok(None);
+        };
+
+        let idx = self.idx;
+        self.idx += 1;
+
+        seed.deserialize(ValueDeserializer(value))
+            .map(Some)
+            .map_err(|e| e.in_index(idx))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapDeserializer {
+    iter: compound::IntoIter,
+    value: Option<(String, Value)>,
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let Some((key, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+
+        let res = seed.deserialize(key_deserializer(key.clone()))?;
+        self.value = Some((key, value));
+        Ok(Some(res))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| Error::new("map value requested before its key"))?;
+
+        seed.deserialize(ValueDeserializer(value))
+            .map_err(|e| e.in_field(&key))
+    }
+}
+
+fn key_deserializer(key: String) -> StringDeserializer<Error> {
+    key.into_deserializer()
+}
+
+struct EnumDeserializer {
+    variant: String,
+    /// The contents of the variant, or `None` for unit variants.
+    value: Option<Value>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let variant = seed.deserialize(key_deserializer(self.variant.clone()))?;
+
+        Ok((
+            variant,
+            VariantDeserializer {
+                variant: self.variant,
+                value: self.value,
+            },
+        ))
+    }
+}
+
+struct VariantDeserializer {
+    variant: String,
+    value: Option<Value>,
+}
+
+impl VariantDeserializer {
+    fn into_value(self, expected: &str) -> Result<(String, Value)> {
+        match self.value {
+            Some(value) => Ok((self.variant, value)),
+            None => Err(de::Error::invalid_type(Unexpected::UnitVariant, &expected)),
+        }
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.value {
+            None => Ok(()),
+            Some(value) => {
+                let err: Error = de::Error::invalid_type(unexpected(&value), &"unit variant");
+                Err(err.in_field(&self.variant))
+            }
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        let (variant, value) = self.into_value("newtype variant")?;
+
+        seed.deserialize(ValueDeserializer(value))
+            .map_err(|e| e.in_field(&variant))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        let (variant, value) = self.into_value("tuple variant")?;
+
+        ValueDeserializer(value)
+            .deserialize_seq(visitor)
+            .map_err(|e| e.in_field(&variant))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let (variant, value) = self.into_value("struct variant")?;
+
+        ValueDeserializer(value)
+            .deserialize_map(visitor)
+            .map_err(|e| e.in_field(&variant))
+    }
+}
@@ -0,0 +1,559 @@
+use serde::ser::{self, Impossible, Serialize};
+
+use super::{Error, BYTE_ARRAY_NAME, INT_ARRAY_NAME, LONG_ARRAY_NAME};
+use crate::{Compound, List, Tag, Value};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Serializes Rust values into NBT values.
+///
+/// `None` is produced for values without an NBT representation (`None` and
+/// `()`). These are omitted from compounds and rejected everywhere else.
+pub(super) struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeVariant<SerializeList>;
+    type SerializeMap = SerializeCompound;
+    type SerializeStruct = SerializeCompound;
+    type SerializeStructVariant = SerializeVariant<SerializeCompound>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        Ok(Some(Value::Byte(v as i8)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        Ok(Some(Value::Byte(v)))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        Ok(Some(Value::Short(v)))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        Ok(Some(Value::Int(v)))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        Ok(Some(Value::Long(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        unsigned(v).map(|v| Some(Value::Byte(v)))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        unsigned(v).map(|v| Some(Value::Short(v)))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        unsigned(v).map(|v| Some(Value::Int(v)))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        unsigned(v).map(|v| Some(Value::Long(v)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        Ok(Some(Value::Float(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        Ok(Some(Value::Double(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        Ok(Some(Value::String(v.into())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        Ok(Some(Value::String(v.into())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        Ok(Some(Value::ByteArray(v.iter().map(|&b| b as i8).collect())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        Ok(Some(Value::Compound(Compound::new())))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Ok(Some(Value::String(variant.into())))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        let value = value.serialize(self)?;
+
+        let expected = match name {
+            BYTE_ARRAY_NAME => "byte",
+            INT_ARRAY_NAME => "int",
+            LONG_ARRAY_NAME => "long",
+            _ => return Ok(value),
+        };
+
+        let array = match (name, value) {
+            (BYTE_ARRAY_NAME, Some(Value::List(List::Byte(v)) | Value::ByteArray(v))) => {
+                Value::ByteArray(v)
+            }
+            (INT_ARRAY_NAME, Some(Value::List(List::Int(v)))) => Value::IntArray(v),
+            (LONG_ARRAY_NAME, Some(Value::List(List::Long(v)))) => Value::LongArray(v),
+            (BYTE_ARRAY_NAME, Some(Value::List(List::End))) => Value::ByteArray(vec![]),
+            (INT_ARRAY_NAME, Some(Value::List(List::End))) => Value::IntArray(vec![]),
+            (LONG_ARRAY_NAME, Some(Value::List(List::End))) => Value::LongArray(vec![]),
+            _ => return Err(Error::new(format!("expected a sequence of {expected}s"))),
+        };
+
+        Ok(Some(array))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        let value = required(value.serialize(self)).map_err(|e| e.in_field(variant))?;
+
+        let mut compound = Compound::new();
+        compound.insert(variant, value);
+        Ok(Some(Value::Compound(compound)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SerializeList {
+            elems: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SerializeCompound {
+            compound: Compound::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+fn unsigned<U, I>(v: U) -> Result<I>
+where
+    U: Copy + std::fmt::Display,
+    I: TryFrom<U>,
+{
+    I::try_from(v).map_err(|_| {
+        Error::new(format!(
+            "unsigned integer {v} is out of range for its NBT type"
+        ))
+    })
+}
+
+/// Rejects values without an NBT representation in positions where they
+/// cannot be omitted.
+fn required(value: Result<Option<Value>>) -> Result<Value> {
+    value?.ok_or_else(|| Error::new("value has no NBT representation"))
+}
+
+pub(super) struct SerializeList {
+    elems: Vec<Value>,
+}
+
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let idx = self.elems.len();
+        let value = required(value.serialize(ValueSerializer)).map_err(|e| e.in_index(idx))?;
+        self.elems.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<List> {
+        list_from_values(self.elems)
+    }
+}
+
+/// Builds a list out of values which must all have the same type.
+fn list_from_values(values: Vec<Value>) -> Result<List> {
+    let Some(first) = values.first() else {
+        return Ok(List::End);
+    };
+
+    let tag = first.get_tag();
+
+    macro_rules! collect {
+        ($variant:ident) => {
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(idx, value)| match value {
+                    Value::$variant(v) => Ok(v),
+                    other => Err(Error::new(format!(
+                        "list elements must have the same type (expected {tag}, got {})",
+                        other.get_tag()
+                    ))
+                    .in_index(idx)),
+                })
+                .collect::<Result<_>>()
+                .map(List::$variant)
+        };
+    }
+
+    match tag {
+        Tag::End => unreachable!("values never have the end tag"),
+        Tag::Byte => collect!(Byte),
+        Tag::Short => collect!(Short),
+        Tag::Int => collect!(Int),
+        Tag::Long => collect!(Long),
+        Tag::Float => collect!(Float),
+        Tag::Double => collect!(Double),
+        Tag::ByteArray => collect!(ByteArray),
+        Tag::String => collect!(String),
+        Tag::List => collect!(List),
+        Tag::Compound => collect!(Compound),
+        Tag::IntArray => collect!(IntArray),
+        Tag::LongArray => collect!(LongArray),
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(Value::List(self.finish()?)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub(super) struct SerializeCompound {
+    compound: Compound,
+    next_key: Option<String>,
+}
+
+impl SerializeCompound {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<()> {
+        // Fields without an NBT representation are omitted.
+        if let Some(value) = value
+            .serialize(ValueSerializer)
+            .map_err(|e| e.in_field(&key))?
+        {
+            self.compound.insert(key, value);
+        }
+
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for SerializeCompound {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.next_key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| Error::new("map value serialized before its key"))?;
+
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(Value::Compound(self.compound)))
+    }
+}
+
+impl ser::SerializeStruct for SerializeCompound {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.into(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(Value::Compound(self.compound)))
+    }
+}
+
+/// Wraps the contents of a tuple or struct variant in a compound with the
+/// variant name as its only key.
+pub(super) struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeList> {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let variant = self.variant;
+        self.inner.push(value).map_err(|e| e.in_field(variant))
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        let variant = self.variant;
+        let list = self.inner.finish().map_err(|e| e.in_field(variant))?;
+
+        let mut compound = Compound::new();
+        compound.insert(variant, Value::List(list));
+        Ok(Some(Value::Compound(compound)))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeCompound> {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let variant = self.variant;
+        self.inner
+            .insert(key.into(), value)
+            .map_err(|e| e.in_field(variant))
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        let mut compound = Compound::new();
+        compound.insert(self.variant, self.inner.compound);
+        Ok(Some(Value::Compound(compound)))
+    }
+}
+
+/// Serializes map keys, which must be strings.
+struct KeySerializer;
+
+macro_rules! unsupported_keys {
+    ($($method:ident($($arg:ty),*)),* $(,)?) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<Self::Ok> {
+                Err(key_must_be_a_string())
+            }
+        )*
+    };
+}
+
+fn key_must_be_a_string() -> Error {
+    Error::new("map keys must be strings")
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    unsupported_keys! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        Ok(v.into())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        Ok(v.into())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<Self::Ok> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Ok(variant.into())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(key_must_be_a_string())
+    }
+}
@@ -1,5 +1,5 @@
 use std::error::Error;
-use std::fmt::{Display, Formatter};
+use std::fmt::{Display, Formatter, LowerExp, Write};
 use std::iter::Peekable;
 use std::str::Chars;
 
@@ -451,130 +451,269 @@ pub fn from_snbt_str(snbt: &str) -> Result<Value> {
     SnbtReader::new(snbt).read()
 }
 
+/// Writes values in SNBT format.
+///
+/// The output follows the formatting of the vanilla game. String values are
+/// always quoted, while compound keys are only quoted when necessary. The
+/// output can always be parsed back into the same value with
+/// [`from_snbt_str`], with the exception of empty lists, which lose their
+/// element type, and compounds with empty keys, which cannot be parsed.
 pub struct SnbtWriter<'a> {
     output: &'a mut String,
+    pretty: bool,
+    depth: usize,
 }
 
+/// The indentation used by the pretty printer for each level of nesting.
+const PRETTY_INDENT: &str = "    ";
+
 impl<'a> SnbtWriter<'a> {
+    /// Creates a writer that produces compact output without whitespace.
     pub fn new(output: &'a mut String) -> Self {
-        Self { output }
+        Self {
+            output,
+            pretty: false,
+            depth: 0,
+        }
+    }
+
+    /// Creates a writer that puts every entry of a compound (and every
+    /// element of a list of compounds or lists) on its own indented line.
+    pub fn new_pretty(output: &'a mut String) -> Self {
+        Self {
+            output,
+            pretty: true,
+            depth: 0,
+        }
     }
 
-    fn write_string(&mut self, s: &str) {
-        let mut need_quote = false;
+    /// Writes a string using the same quotes as the vanilla game: double
+    /// quotes, unless the string contains a double quote before any single
+    /// quote.
+    fn write_quoted_string(&mut self, s: &str) {
+        let quote = match s.find(['"', '\'']) {
+            Some(i) if s.as_bytes()[i] == b'"' => '\'',
+            _ => '"',
+        };
+
+        self.output.push(quote);
         for c in s.chars() {
-            if !matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '+' | '.') {
-                need_quote = true;
-                break;
+            if c == quote || c == '\\' {
+                self.output.push('\\');
             }
+            self.output.push(c);
         }
+        self.output.push(quote);
+    }
+
+    fn write_key(&mut self, key: &str) {
+        let need_quote = key.is_empty()
+            || !key
+                .chars()
+                .all(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '+' | '.'));
+
         if need_quote {
-            self.output.push('"');
-            for c in s.chars() {
-                match c {
-                    '"' => self.output.push_str("\\\""),
-                    '\\' => self.output.push_str("\\\\"),
-                    _ => self.output.push(c),
-                }
-            }
-            self.output.push('"');
+            self.write_quoted_string(key);
         } else {
-            self.output.push_str(s);
+            self.output.push_str(key);
+        }
+    }
+
+    fn write_separator(&mut self) {
+        self.output.push(',');
+        if self.pretty {
+            self.output.push(' ');
         }
     }
 
-    fn write_primitive_array<'b>(
+    fn write_newline(&mut self) {
+        self.output.push('\n');
+        for _ in 0..self.depth {
+            self.output.push_str(PRETTY_INDENT);
+        }
+    }
+
+    /// Writes a sequence of elements between brackets. If `multiline` is true
+    /// and the writer is pretty, every element is on its own line.
+    fn write_seq<T>(
         &mut self,
         prefix: &str,
-        iter: impl Iterator<Item = &'b (impl Into<Value> + 'b + Copy)>,
+        items: impl ExactSizeIterator<Item = T>,
+        multiline: bool,
+        mut write: impl FnMut(&mut Self, T),
     ) {
         self.output.push('[');
         self.output.push_str(prefix);
-        let mut first = true;
-        for v in iter {
-            if !first {
-                self.output.push(',');
+
+        if items.len() > 0 && !prefix.is_empty() && self.pretty {
+            self.output.push(' ');
+        }
+
+        let multiline = multiline && self.pretty && items.len() > 0;
+
+        if multiline {
+            self.depth += 1;
+        }
+
+        for (i, item) in items.enumerate() {
+            if i > 0 {
+                if multiline {
+                    self.output.push(',');
+                } else {
+                    self.write_separator();
+                }
             }
-            first = false;
-            self.write_element(&(*v).into());
+
+            if multiline {
+                self.write_newline();
+            }
+
+            write(self, item);
         }
+
+        if multiline {
+            self.depth -= 1;
+            self.write_newline();
+        }
+
         self.output.push(']');
     }
 
-    fn write_primitive(&mut self, postfix: &str, value: impl ToString) {
-        self.output.push_str(&value.to_string());
-        self.output.push_str(postfix);
-    }
+    /// Writes a float the way Java's `Float.toString` and `Double.toString`
+    /// do, using the shortest digits which round-trip in the type of `v`.
+    fn write_float<F>(&mut self, v: F, suffix: char)
+    where
+        F: Copy + Display + LowerExp + Into<f64>,
+    {
+        let abs = v.into().abs();
 
-    fn write_list(&mut self, list: &List) {
-        macro_rules! variant_impl {
-            ($v:expr, $handle:expr) => {{
-                self.output.push('[');
-                let mut first = true;
-                for v in $v.iter() {
-                    if !first {
-                        self.output.push(',');
-                    }
-                    first = false;
-                    $handle(v);
-                }
-                self.output.push(']');
-            }};
-        }
-        match list {
-            List::Byte(v) => variant_impl!(v, |v| self.write_primitive("b", v)),
-            List::Short(v) => variant_impl!(v, |v| self.write_primitive("s", v)),
-            List::Int(v) => variant_impl!(v, |v| self.write_primitive("", v)),
-            List::Long(v) => variant_impl!(v, |v| self.write_primitive("l", v)),
-            List::Float(v) => variant_impl!(v, |v| self.write_primitive("f", v)),
-            List::Double(v) => variant_impl!(v, |v| self.write_primitive("d", v)),
-            List::ByteArray(v) => {
-                variant_impl!(v, |v: &Vec<i8>| self.write_primitive_array("B", v.iter()))
+        if v.into().is_nan() {
+            self.output.push_str("NaN");
+        } else if v.into().is_infinite() {
+            // Java spells infinities out, unlike Rust's `inf`.
+            if v.into() < 0.0 {
+                self.output.push('-');
             }
-            List::IntArray(v) => {
-                variant_impl!(v, |v: &Vec<i32>| self.write_primitive_array("", v.iter()))
+            self.output.push_str("Infinity");
+        } else if abs == 0.0 || (1e-3..1e7).contains(&abs) {
+            let start = self.output.len();
+            write!(self.output, "{v}").unwrap();
+
+            // Match Java's formatting of floating point numbers so that the type
+            // is apparent without the suffix.
+            if !self.output[start..].contains('.') {
+                self.output.push_str(".0");
             }
-            List::LongArray(v) => {
-                variant_impl!(v, |v: &Vec<i64>| self.write_primitive_array("L", v.iter()))
+        } else {
+            // Java uses scientific notation like `1.0E-5` for very large and
+            // very small magnitudes.
+            let sci = format!("{v:e}");
+            let (mantissa, exp) = sci.split_once('e').unwrap();
+
+            self.output.push_str(mantissa);
+            if !mantissa.contains('.') {
+                self.output.push_str(".0");
             }
-            List::String(v) => variant_impl!(v, |v| self.write_string(v)),
-            List::List(v) => variant_impl!(v, |v| self.write_list(v)),
-            List::Compound(v) => variant_impl!(v, |v| self.write_compound(v)),
+            self.output.push('E');
+            self.output.push_str(exp);
+        }
+
+        self.output.push(suffix);
+    }
+
+    fn write_list(&mut self, list: &List) {
+        match list {
             List::End => self.output.push_str("[]"),
+            List::Byte(v) => self.write_seq("", v.iter(), false, |w, v| {
+                write!(w.output, "{v}b").unwrap()
+            }),
+            List::Short(v) => self.write_seq("", v.iter(), false, |w, v| {
+                write!(w.output, "{v}s").unwrap()
+            }),
+            List::Int(v) => {
+                self.write_seq("", v.iter(), false, |w, v| write!(w.output, "{v}").unwrap())
+            }
+            List::Long(v) => self.write_seq("", v.iter(), false, |w, v| {
+                write!(w.output, "{v}L").unwrap()
+            }),
+            List::Float(v) => self.write_seq("", v.iter(), false, |w, &v| w.write_float(v, 'f')),
+            List::Double(v) => self.write_seq("", v.iter(), false, |w, &v| w.write_float(v, 'd')),
+            List::ByteArray(v) => self.write_seq("", v.iter(), false, |w, v| w.write_byte_array(v)),
+            List::IntArray(v) => self.write_seq("", v.iter(), false, |w, v| w.write_int_array(v)),
+            List::LongArray(v) => self.write_seq("", v.iter(), false, |w, v| w.write_long_array(v)),
+            List::String(v) => self.write_seq("", v.iter(), false, |w, v| w.write_quoted_string(v)),
+            List::List(v) => self.write_seq("", v.iter(), true, |w, v| w.write_list(v)),
+            List::Compound(v) => self.write_seq("", v.iter(), true, |w, v| w.write_compound(v)),
         }
     }
 
+    fn write_byte_array(&mut self, v: &[i8]) {
+        self.write_seq("B;", v.iter(), false, |w, v| {
+            write!(w.output, "{v}B").unwrap()
+        })
+    }
+
+    fn write_int_array(&mut self, v: &[i32]) {
+        self.write_seq("I;", v.iter(), false, |w, v| {
+            write!(w.output, "{v}").unwrap()
+        })
+    }
+
+    fn write_long_array(&mut self, v: &[i64]) {
+        self.write_seq("L;", v.iter(), false, |w, v| {
+            write!(w.output, "{v}L").unwrap()
+        })
+    }
+
     fn write_compound(&mut self, compound: &Compound) {
         self.output.push('{');
-        let mut first = true;
-        for (k, v) in compound.iter() {
-            if !first {
+
+        if self.pretty && !compound.is_empty() {
+            self.depth += 1;
+        }
+
+        for (i, (k, v)) in compound.iter().enumerate() {
+            if i > 0 {
                 self.output.push(',');
             }
-            first = false;
-            self.write_string(k);
+
+            if self.pretty {
+                self.write_newline();
+            }
+
+            self.write_key(k);
             self.output.push(':');
+
+            if self.pretty {
+                self.output.push(' ');
+            }
+
             self.write_element(v);
         }
+
+        if self.pretty && !compound.is_empty() {
+            self.depth -= 1;
+            self.write_newline();
+        }
+
         self.output.push('}');
     }
 
     /// Write a value to the output.
     pub fn write_element(&mut self, value: &Value) {
-        use Value::*;
         match value {
-            Byte(v) => self.write_primitive("b", v),
-            Short(v) => self.write_primitive("s", v),
-            Int(v) => self.write_primitive("", v),
-            Long(v) => self.write_primitive("l", v),
-            Float(v) => self.write_primitive("f", v),
-            Double(v) => self.write_primitive("d", v),
-            ByteArray(v) => self.write_primitive_array("B;", v.iter()),
-            IntArray(v) => self.write_primitive_array("I;", v.iter()),
-            LongArray(v) => self.write_primitive_array("L;", v.iter()),
-            String(v) => self.write_string(v),
-            List(v) => self.write_list(v),
-            Compound(v) => self.write_compound(v),
+            Value::Byte(v) => write!(self.output, "{v}b").unwrap(),
+            Value::Short(v) => write!(self.output, "{v}s").unwrap(),
+            Value::Int(v) => write!(self.output, "{v}").unwrap(),
+            Value::Long(v) => write!(self.output, "{v}L").unwrap(),
+            Value::Float(v) => self.write_float(*v, 'f'),
+            Value::Double(v) => self.write_float(*v, 'd'),
+            Value::ByteArray(v) => self.write_byte_array(v),
+            Value::IntArray(v) => self.write_int_array(v),
+            Value::LongArray(v) => self.write_long_array(v),
+            Value::String(v) => self.write_quoted_string(v),
+            Value::List(v) => self.write_list(v),
+            Value::Compound(v) => self.write_compound(v),
         }
     }
 }
@@ -587,12 +726,86 @@ pub fn to_snbt_string(value: &Value) -> String {
     output
 }
 
+/// Convert a value to a string in SNBT format, with compounds and nested lists
+/// spread over multiple indented lines.
+///
+/// # Example
+///
+/// ```
+/// use valence_nbt::snbt::to_snbt_string_pretty;
+/// use valence_nbt::{compound, List, Value};
+///
+/// let value = Value::Compound(compound! {
+///     "name" => "Steve",
+///     "pos" => List::Double(vec![1.5, 64.0, -3.0]),
+///     "tags" => compound! {},
+/// });
+///
+/// assert_eq!(
+///     to_snbt_string_pretty(&value),
+///     "{\n    name: \"Steve\",\n    pos: [1.5d, 64.0d, -3.0d],\n    tags: {}\n}"
+/// );
+/// ```
+pub fn to_snbt_string_pretty(value: &Value) -> String {
+    let mut output = String::new();
+    let mut writer = SnbtWriter::new_pretty(&mut output);
+    writer.write_element(value);
+    output
+}
+
 impl Display for SnbtWriter<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.output)
     }
 }
 
+impl Value {
+    /// Converts this value to a compact string in SNBT format. See
+    /// [`to_snbt_string`].
+    pub fn to_snbt(&self) -> String {
+        to_snbt_string(self)
+    }
+
+    /// Converts this value to an indented string in SNBT format. See
+    /// [`to_snbt_string_pretty`].
+    pub fn to_snbt_pretty(&self) -> String {
+        to_snbt_string_pretty(self)
+    }
+}
+
+impl Compound {
+    /// Converts this compound to a compact string in SNBT format.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valence_nbt::compound;
+    ///
+    /// let c = compound! {
+    ///     "Count" => 1_i8,
+    ///     "display name" => "x",
+    ///     "id" => "minecraft:stone",
+    /// };
+    ///
+    /// assert_eq!(
+    ///     c.to_snbt(),
+    ///     r#"{Count:1b,"display name":"x",id:"minecraft:stone"}"#
+    /// );
+    /// ```
+    pub fn to_snbt(&self) -> String {
+        let mut output = String::new();
+        SnbtWriter::new(&mut output).write_compound(self);
+        output
+    }
+
+    /// Converts this compound to an indented string in SNBT format.
+    pub fn to_snbt_pretty(&self) -> String {
+        let mut output = String::new();
+        SnbtWriter::new_pretty(&mut output).write_compound(self);
+        output
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -700,9 +913,253 @@ mod tests {
         #[cfg(feature = "preserve_order")]
         assert_eq!(
             to_snbt_string(&value),
-            "{foo:1,bar:1d,baz:1f,\"hello'\":\"hello \
-             world\",world:\"hello\\\"world\",1.5f:1.5d,3b:2f,bool:0b,more:{iarr:[I;1,2,3],larr:\
-             [L;1l,2l,3l]},empty:[Bibabo]}"
+            "{foo:1,bar:1.0d,baz:1.0f,\"hello'\":\"hello \
+             world\",world:'hello\"world',1.5f:1.5d,3b:2.0f,bool:0b,more:{iarr:[I;1,2,3],larr:\
+             [L;1L,2L,3L]},empty:[\"Bibabo\"]}"
         );
     }
+
+    /// A small deterministic random number generator, so that the round trip
+    /// tests are reproducible.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> usize {
+            (self.next() % n) as usize
+        }
+
+        fn string(&mut self) -> String {
+            const CHARS: &[char] = &[
+                'a', 'Z', '0', '_', '-', '+', '.', ' ', '"', '\'', '\\', ':', ',', '{', ']', '\n',
+                'é', '❤',
+            ];
+
+            (0..self.below(6))
+                .map(|_| CHARS[self.below(CHARS.len() as u64)])
+                .collect()
+        }
+
+        fn float(&mut self) -> f64 {
+            match self.below(8) {
+                0 => f64::NAN,
+                1 => f64::INFINITY,
+                2 => f64::NEG_INFINITY,
+                3 => -0.0,
+                4 => self.next() as i64 as f64,
+                _ => f64::from_bits(self.next()),
+            }
+        }
+
+        fn list(&mut self, depth: usize) -> List {
+            let len = 1 + self.below(3);
+
+            match self.below(if depth == 0 { 10 } else { 12 }) {
+                0 => List::Byte((0..len).map(|_| self.next() as i8).collect()),
+                1 => List::Short((0..len).map(|_| self.next() as i16).collect()),
+                2 => List::Int((0..len).map(|_| self.next() as i32).collect()),
+                3 => List::Long((0..len).map(|_| self.next() as i64).collect()),
+                4 => List::Float((0..len).map(|_| self.float() as f32).collect()),
+                5 => List::Double((0..len).map(|_| self.float()).collect()),
+                6 => List::String((0..len).map(|_| self.string()).collect()),
+                7 => List::ByteArray((0..len).map(|_| vec![self.next() as i8]).collect()),
+                8 => List::IntArray((0..len).map(|_| vec![]).collect()),
+                9 => List::End,
+                10 => List::List((0..len).map(|_| self.list(depth - 1)).collect()),
+                _ => List::Compound((0..len).map(|_| self.compound(depth - 1)).collect()),
+            }
+        }
+
+        fn compound(&mut self, depth: usize) -> Compound {
+            (0..self.below(4))
+                .map(|_| {
+                    // Empty keys cannot be parsed.
+                    let key = self.string() + "k";
+                    (key, self.value(depth))
+                })
+                .collect()
+        }
+
+        fn value(&mut self, depth: usize) -> Value {
+            match self.below(if depth == 0 { 11 } else { 13 }) {
+                0 => Value::Byte(self.next() as i8),
+                1 => Value::Short(self.next() as i16),
+                2 => Value::Int(self.next() as i32),
+                3 => Value::Long(self.next() as i64),
+                4 => Value::Float(self.float() as f32),
+                5 => Value::Double(self.float()),
+                6 => Value::String(self.string()),
+                7 => Value::ByteArray((0..self.below(3)).map(|_| self.next() as i8).collect()),
+                8 => Value::IntArray((0..self.below(3)).map(|_| self.next() as i32).collect()),
+                9 => Value::LongArray((0..self.below(3)).map(|_| self.next() as i64).collect()),
+                10 => Value::String(String::new()),
+                11 => Value::List(self.list(depth - 1)),
+                _ => Value::Compound(self.compound(depth - 1)),
+            }
+        }
+    }
+
+    /// Compares values, treating NaNs with the same bits as equal.
+    fn same(a: &Value, b: &Value) -> bool {
+        use std::iter::zip;
+
+        fn same_list(a: &List, b: &List) -> bool {
+            match (a, b) {
+                (List::Float(a), List::Float(b)) => {
+                    zip(a, b).all(|(a, b)| a.to_bits() == b.to_bits()) && a.len() == b.len()
+                }
+                (List::Double(a), List::Double(b)) => {
+                    zip(a, b).all(|(a, b)| a.to_bits() == b.to_bits()) && a.len() == b.len()
+                }
+                (List::List(a), List::List(b)) => {
+                    a.len() == b.len() && zip(a, b).all(|(a, b)| same_list(a, b))
+                }
+                (List::Compound(a), List::Compound(b)) => {
+                    a.len() == b.len()
+                        && zip(a, b).all(|(a, b)| same(&a.clone().into(), &b.clone().into()))
+                }
+                (a, b) => a == b,
+            }
+        }
+
+        match (a, b) {
+            (Value::Float(a), Value::Float(b)) => {
+                a.to_bits() == b.to_bits() || a.is_nan() && b.is_nan()
+            }
+            (Value::Double(a), Value::Double(b)) => {
+                a.to_bits() == b.to_bits() || a.is_nan() && b.is_nan()
+            }
+            (Value::List(a), Value::List(b)) => same_list(a, b),
+            (Value::Compound(a), Value::Compound(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(k, v)| b.get(k).is_some_and(|bv| same(v, bv)))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+
+        for _ in 0..2000 {
+            let value = rng.value(3);
+
+            for snbt in [value.to_snbt(), value.to_snbt_pretty()] {
+                let parsed = from_snbt_str(&snbt)
+                    .unwrap_or_else(|e| panic!("failed to parse {snbt:?}: {e}"));
+
+                assert!(same(&value, &parsed), "{value:?} != {parsed:?} ({snbt:?})");
+            }
+        }
+    }
+
+    #[test]
+    fn special_floats() {
+        for (value, snbt) in [
+            (Value::Float(f32::NAN), "NaNf"),
+            (Value::Float(f32::INFINITY), "Infinityf"),
+            (Value::Float(f32::NEG_INFINITY), "-Infinityf"),
+            (Value::Double(f64::NAN), "NaNd"),
+            (Value::Double(f64::INFINITY), "Infinityd"),
+            (Value::Double(f64::NEG_INFINITY), "-Infinityd"),
+            (Value::Double(-0.0), "-0.0d"),
+            (Value::Float(1.0), "1.0f"),
+            (Value::Double(0.1), "0.1d"),
+            (Value::Float(0.1), "0.1f"),
+            (Value::Float(1e7), "1.0E7f"),
+            (Value::Float(1.5e-5), "1.5E-5f"),
+            (Value::Double(123456789.0), "1.23456789E8d"),
+            (Value::Double(0.001), "0.001d"),
+        ] {
+            assert_eq!(value.to_snbt(), snbt);
+            assert!(same(&from_snbt_str(snbt).unwrap(), &value), "{snbt}");
+        }
+
+        for value in [
+            Value::List(List::Float(vec![
+                f32::INFINITY,
+                f32::NAN,
+                f32::NEG_INFINITY,
+            ])),
+            Value::List(List::Double(vec![
+                f64::NEG_INFINITY,
+                f64::NAN,
+                f64::INFINITY,
+            ])),
+        ] {
+            let snbt = value.to_snbt();
+            assert!(same(&from_snbt_str(&snbt).unwrap(), &value), "{snbt}");
+        }
+    }
+
+    #[test]
+    fn empty_arrays_and_lists() {
+        for (value, snbt) in [
+            (Value::ByteArray(vec![]), "[B;]"),
+            (Value::IntArray(vec![]), "[I;]"),
+            (Value::LongArray(vec![]), "[L;]"),
+            (Value::List(List::End), "[]"),
+            (Value::Compound(Compound::new()), "{}"),
+        ] {
+            assert_eq!(value.to_snbt(), snbt);
+            assert_eq!(value.to_snbt_pretty(), snbt);
+            assert_eq!(from_snbt_str(snbt).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn vanilla_formatting() {
+        let mut c = Compound::new();
+        c.insert("Count", Value::Byte(1));
+        c.insert("id", "minecraft:diamond_sword");
+        c.insert("Damage", Value::Long(5));
+        c.insert("with space", "it's");
+        c.insert("quote", "say \"hi\"");
+        c.insert("bytes", vec![1_i8, -2]);
+        c.insert("longs", vec![3_i64]);
+        c.insert(
+            "Enchantments",
+            List::Compound(vec![{
+                let mut e = Compound::new();
+                e.insert("id", "minecraft:sharpness");
+                e.insert("lvl", Value::Short(5));
+                e
+            }]),
+        );
+
+        #[cfg(not(feature = "preserve_order"))]
+        {
+            assert_eq!(
+                c.to_snbt(),
+                r#"{Count:1b,Damage:5L,Enchantments:[{id:"minecraft:sharpness",lvl:5s}],bytes:[B;1B,-2B],id:"minecraft:diamond_sword",longs:[L;3L],quote:'say "hi"',"with space":"it's"}"#
+            );
+
+            assert_eq!(
+                c.to_snbt_pretty(),
+                r#"{
+    Count: 1b,
+    Damage: 5L,
+    Enchantments: [
+        {
+            id: "minecraft:sharpness",
+            lvl: 5s
+        }
+    ],
+    bytes: [B; 1B, -2B],
+    id: "minecraft:diamond_sword",
+    longs: [L; 3L],
+    quote: 'say "hi"',
+    "with space": "it's"
+}"#
+            );
+        }
+    }
 }
@@ -1,5 +1,9 @@
+use crate::reader::{Event, Reader};
 use crate::tag::Tag;
-use crate::{compound, from_binary_slice, to_binary_writer, Compound, List, Value};
+use crate::{
+    compound, from_binary_slice, from_binary_slice_with_limits, to_binary_writer, Compound,
+    DecodeLimits, List, Value,
+};
 
 const ROOT_NAME: &str = "The root name‽";
 
@@ -87,6 +91,122 @@ fn deeply_nested_list_decode() {
     let _ = from_binary_slice(&mut buf.as_slice());
 }
 
+fn list_bomb(depth: usize) -> Vec<u8> {
+    let mut buf = vec![Tag::Compound as u8, 0, 0, Tag::List as u8, 0, 0];
+
+    for _ in 0..depth - 1 {
+        buf.extend([Tag::List as u8, 0, 0, 0, 1]);
+    }
+
+    buf.extend([Tag::Byte as u8, 0, 0, 0, 0]);
+    buf.push(Tag::End as u8);
+    buf
+}
+
+#[test]
+fn list_bomb_exceeds_max_depth() {
+    let buf = list_bomb(10_000);
+
+    let err = from_binary_slice(&mut buf.as_slice()).unwrap_err();
+    assert_eq!(err.to_string(), "reached maximum depth of 512");
+
+    let limits = DecodeLimits {
+        max_depth: 100,
+        ..Default::default()
+    };
+
+    assert!(from_binary_slice_with_limits(&mut list_bomb(100).as_slice(), limits).is_ok());
+    assert!(from_binary_slice_with_limits(&mut list_bomb(101).as_slice(), limits).is_err());
+
+    // The streaming reader does not recurse, so it can read the whole bomb.
+    let limits = DecodeLimits {
+        max_depth: usize::MAX,
+        ..Default::default()
+    };
+
+    let mut reader = Reader::new(&buf, limits);
+    let mut lists = 0;
+
+    while let Some(event) = reader.next_event().unwrap() {
+        if let Event::ListStart { .. } = event {
+            lists += 1;
+        }
+    }
+
+    assert_eq!(lists, 10_000);
+    assert!(reader.remaining().is_empty());
+}
+
+#[test]
+fn huge_length_rejected() {
+    const LEN: [u8; 4] = 2_000_000_000_i32.to_be_bytes();
+
+    for tag in [Tag::ByteArray, Tag::IntArray, Tag::LongArray] {
+        let mut buf = vec![Tag::Compound as u8, 0, 0, tag as u8, 0, 0];
+        buf.extend(LEN);
+        buf.extend([0; 16]);
+
+        let err = from_binary_slice(&mut buf.as_slice()).unwrap_err();
+        assert!(
+            err.to_string().contains("exceeds remainder of input"),
+            "{tag}: {err}"
+        );
+    }
+
+    for tag in [Tag::Byte, Tag::Compound, Tag::List] {
+        let mut buf = vec![Tag::Compound as u8, 0, 0, Tag::List as u8, 0, 0, tag as u8];
+        buf.extend(LEN);
+        buf.extend([0; 16]);
+
+        let err = from_binary_slice(&mut buf.as_slice()).unwrap_err();
+        assert!(
+            err.to_string().contains("exceeds remainder of input"),
+            "{tag}: {err}"
+        );
+    }
+}
+
+#[test]
+fn size_and_element_limits() {
+    let c = compound! {
+        "longs" => vec![0_i64; 100],
+        "strings" => List::String(vec!["hello".into(); 10]),
+    };
+
+    let mut buf = vec![];
+    to_binary_writer(&mut buf, &c, "").unwrap();
+
+    let limits = DecodeLimits {
+        max_size: 512,
+        ..Default::default()
+    };
+
+    let err = from_binary_slice_with_limits(&mut buf.as_slice(), limits).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "decoded data exceeds maximum size of 512 bytes"
+    );
+
+    let limits = DecodeLimits {
+        max_elements: 5,
+        ..Default::default()
+    };
+
+    let err = from_binary_slice_with_limits(&mut buf.as_slice(), limits).unwrap_err();
+    assert_eq!(err.to_string(), "reached maximum element count of 5");
+
+    let limits = DecodeLimits {
+        max_size: 4096,
+        max_elements: 13,
+        max_depth: 1,
+    };
+
+    let mut slice = buf.as_slice();
+    let (decoded, _) = from_binary_slice_with_limits(&mut slice, limits).unwrap();
+    assert_eq!(decoded, c);
+    assert!(slice.is_empty());
+}
+
 #[test]
 fn correct_length() {
     let c = example_compound();
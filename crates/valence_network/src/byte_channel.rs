@@ -6,6 +6,7 @@ use std::sync::{Arc, Mutex};
 
 use bytes::BytesMut;
 use thiserror::Error;
+use tokio::pin;
 use tokio::sync::Notify;
 
 pub(crate) fn byte_channel(limit: usize) -> (ByteSender, ByteReceiver) {
@@ -86,6 +87,12 @@ impl ByteSender {
 
     pub(crate) async fn send_async(&mut self, mut bytes: BytesMut) -> Result<(), SendError> {
         loop {
+            let notified = self.shared.notify.notified();
+            pin!(notified);
+            // Wait for notifications before checking the channel, so that none
+            // are missed in between.
+            notified.as_mut().enable();
+
             {
                 let mut lck = self.shared.mtx.lock().unwrap();
 
@@ -111,7 +118,7 @@ impl ByteSender {
                 }
             }
 
-            self.shared.notify.notified().await;
+            notified.await;
         }
     }
 
@@ -119,6 +126,11 @@ impl ByteSender {
         self.shared.mtx.lock().unwrap().disconnected
     }
 
+    /// Returns the number of bytes in the channel.
+    pub(crate) fn len(&self) -> usize {
+        self.shared.mtx.lock().unwrap().bytes.len()
+    }
+
     pub(crate) fn limit(&self) -> usize {
         self.shared.limit
     }
@@ -161,6 +173,10 @@ impl ByteReceiver {
 
     pub(crate) async fn recv_async(&mut self) -> Result<BytesMut, RecvError> {
         loop {
+            let notified = self.shared.notify.notified();
+            pin!(notified);
+            notified.as_mut().enable();
+
             {
                 let mut lck = self.shared.mtx.lock().unwrap();
 
@@ -174,7 +190,7 @@ impl ByteReceiver {
                 }
             }
 
-            self.shared.notify.notified().await;
+            notified.await;
         }
     }
 
@@ -204,12 +220,14 @@ pub(crate) enum RecvError {
 impl Drop for ByteSender {
     fn drop(&mut self) {
         self.shared.mtx.lock().unwrap().disconnected = true;
+        self.shared.notify.notify_waiters();
     }
 }
 
 impl Drop for ByteReceiver {
     fn drop(&mut self) {
         self.shared.mtx.lock().unwrap().disconnected = true;
+        self.shared.notify.notify_waiters();
     }
 }
 
@@ -251,4 +269,27 @@ mod tests {
 
         assert!(sender.is_disconnected());
     }
+
+    #[tokio::test]
+    async fn byte_channel_drained_after_disconnect() {
+        let (mut sender, mut receiver) = byte_channel(16);
+
+        let t = tokio::spawn(async move {
+            let mut received = vec![];
+
+            while let Ok(bytes) = receiver.recv_async().await {
+                received.extend_from_slice(&bytes);
+            }
+
+            received
+        });
+
+        tokio::task::yield_now().await;
+
+        sender.try_send("hello".as_bytes().into()).unwrap();
+        drop(sender);
+
+        // The receiver is woken up and gets the remaining bytes.
+        assert_eq!(t.await.unwrap(), b"hello");
+    }
 }
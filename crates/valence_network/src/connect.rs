@@ -16,7 +16,7 @@ use serde_json::{json, Value};
 use sha1::Sha1;
 use sha2::{Digest, Sha256};
 use tokio::net::{TcpListener, TcpStream};
-use tracing::{error, info, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 use uuid::Uuid;
 use valence_client::is_valid_username;
 use valence_core::packet::c2s::handshake::handshake::NextState;
@@ -34,10 +34,15 @@ use valence_core::packet::var_int::VarInt;
 use valence_core::packet::Decode;
 use valence_core::property::Property;
 use valence_core::text::Text;
-use valence_core::{ident, translation_key, MINECRAFT_VERSION, PROTOCOL_VERSION};
+use valence_core::{ident, translation_key, PROTOCOL_VERSION};
 
+use crate::legacy_ping::handle_legacy_ping;
 use crate::packet_io::PacketIo;
-use crate::{CleanupOnDrop, ConnectionMode, NewClientInfo, ServerListPing, SharedNetworkState};
+use crate::proxy_protocol::read_proxy_header;
+use crate::throttle::ThrottleGuard;
+use crate::{
+    CleanupOnDrop, ConnectionMode, HandshakeData, NewClientInfo, ServerListPing, SharedNetworkState,
+};
 
 /// Accepts new connections to the server as they occur.
 pub(super) async fn do_accept_loop(shared: SharedNetworkState) {
@@ -49,23 +54,27 @@ pub(super) async fn do_accept_loop(shared: SharedNetworkState) {
         }
     };
 
+    // Connections are always accepted, and the connection limits are checked
+    // afterwards in `handle_stream`. This lets connections over the limit be
+    // sent a disconnect message instead of waiting in the listen backlog.
     loop {
-        match shared.0.connection_sema.clone().acquire_owned().await {
-            Ok(permit) => match listener.accept().await {
-                Ok((stream, remote_addr)) => {
-                    let shared = shared.clone();
-
-                    tokio::spawn(async move {
-                        handle_connection(shared, stream, remote_addr).await;
-                        drop(permit);
-                    });
-                }
-                Err(e) => {
-                    error!("failed to accept incoming connection: {e}");
-                }
-            },
-            // Closed semaphore indicates server shutdown.
-            Err(_) => return,
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            // Stop accepting new connections.
+            _ = shared.shutdown_reason() => return,
+        };
+
+        match accepted {
+            Ok((stream, remote_addr)) => {
+                let shared = shared.clone();
+
+                tokio::spawn(async move {
+                    handle_connection(shared, stream, remote_addr).await;
+                });
+            }
+            Err(e) => {
+                error!("failed to accept incoming connection: {e}");
+            }
         }
     }
 }
@@ -77,16 +86,7 @@ async fn handle_connection(shared: SharedNetworkState, stream: TcpStream, remote
         error!("failed to set TCP_NODELAY: {e}");
     }
 
-    let conn = PacketIo::new(
-        stream,
-        PacketEncoder::new(),
-        PacketDecoder::new(),
-        Duration::from_secs(5),
-    );
-
-    // TODO: peek stream for 0xFE legacy ping
-
-    if let Err(e) = handle_handshake(shared, conn, remote_addr).await {
+    if let Err(e) = handle_stream(shared, stream, remote_addr).await {
         // EOF can happen if the client disconnects while joining, which isn't
         // very erroneous.
         if let Some(e) = e.downcast_ref::<io::Error>() {
@@ -98,10 +98,69 @@ async fn handle_connection(shared: SharedNetworkState, stream: TcpStream, remote
     }
 }
 
-struct HandshakeData {
-    protocol_version: i32,
-    server_address: String,
-    next_state: NextState,
+async fn handle_stream(
+    shared: SharedNetworkState,
+    mut stream: TcpStream,
+    remote_addr: SocketAddr,
+) -> anyhow::Result<()> {
+    // Load balancers may send the address of the client before the handshake.
+    let remote_addr = read_proxy_header(shared.0.proxy_protocol, &mut stream, remote_addr).await?;
+
+    // Connections exceeding the limits are closed before anything else is read.
+    let _guard = match ThrottleGuard::open(&shared, remote_addr.ip()) {
+        Ok(guard) => guard,
+        Err(e) => {
+            debug!("rejected connection from {remote_addr}: {e}");
+
+            if shared.connection_limits().send_disconnect_message {
+                let conn = PacketIo::new(
+                    stream,
+                    PacketEncoder::new(),
+                    PacketDecoder::new(),
+                    Duration::from_secs(5),
+                );
+
+                send_throttled_disconnect(conn).await?;
+            }
+
+            return Ok(());
+        }
+    };
+
+    // Clients before Minecraft 1.7 and some server scanners send a legacy ping
+    // instead of a handshake.
+    if handle_legacy_ping(&shared, &mut stream, remote_addr)
+        .await
+        .context("error handling legacy ping")?
+    {
+        return Ok(());
+    }
+
+    let conn = PacketIo::new(
+        stream,
+        PacketEncoder::new(),
+        PacketDecoder::new(),
+        Duration::from_secs(5),
+    );
+
+    handle_handshake(shared, conn, remote_addr)
+        .await
+        .with_context(|| format!("connection from {remote_addr}"))
+}
+
+/// Sends a disconnect message to a client exceeding the connection limits if
+/// it is logging in.
+async fn send_throttled_disconnect(mut io: PacketIo) -> anyhow::Result<()> {
+    let handshake = io.recv_packet::<HandshakeC2s>().await?;
+
+    if handshake.next_state == NextState::Login {
+        io.send_packet(&LoginDisconnectS2c {
+            reason: Text::from("Connection throttled! Please wait before reconnecting.").into(),
+        })
+        .await?;
+    }
+
+    Ok(())
 }
 
 async fn handle_handshake(
@@ -111,33 +170,49 @@ async fn handle_handshake(
 ) -> anyhow::Result<()> {
     let handshake = io.recv_packet::<HandshakeC2s>().await?;
 
+    let next_state = handshake.next_state;
     let handshake = HandshakeData {
         protocol_version: handshake.protocol_version.0,
         server_address: handshake.server_address.to_owned(),
-        next_state: handshake.next_state,
+        server_port: handshake.server_port,
     };
 
     ensure!(
-        matches!(&shared.0.connection_mode, ConnectionMode::BungeeCord)
+        matches!(&shared.0.connection_mode, ConnectionMode::BungeeCord { .. })
             || handshake.server_address.chars().count() <= 255,
         "handshake server address is too long"
     );
 
-    match handshake.next_state {
+    match next_state {
         NextState::Status => handle_status(shared, io, remote_addr, handshake)
             .await
             .context("error handling status"),
         NextState::Login => {
-            match handle_login(&shared, &mut io, remote_addr, handshake)
-                .await
-                .context("error handling login")?
-            {
+            let login = tokio::select! {
+                login = handle_login(&shared, &mut io, remote_addr, handshake) => Ok(login),
+                reason = shared.shutdown_reason() => Err(reason),
+            };
+
+            let login = match login {
+                Ok(login) => login.context("error handling login")?,
+                Err(reason) => {
+                    // The login was aborted because the server is shutting down.
+                    io.send_packet(&LoginDisconnectS2c {
+                        reason: reason.into(),
+                    })
+                    .await?;
+                    return Ok(());
+                }
+            };
+
+            match login {
                 Some((info, cleanup)) => {
                     let client = io.into_client_args(
                         info,
                         shared.0.incoming_byte_limit,
-                        shared.0.outgoing_byte_limit,
+                        shared.0.send_queue_limit,
                         cleanup,
+                        shared.0.writer_token.clone(),
                     );
 
                     let _ = shared.0.new_clients_send.send_async(client).await;
@@ -158,45 +233,21 @@ async fn handle_status(
 ) -> anyhow::Result<()> {
     io.recv_packet::<QueryRequestC2s>().await?;
 
-    match shared
+    let ping = shared
         .0
         .callbacks
         .inner
-        .server_list_ping(&shared, remote_addr, handshake.protocol_version)
-        .await
-    {
-        ServerListPing::Respond {
-            online_players,
-            max_players,
-            player_sample,
-            description,
-            favicon_png,
-        } => {
-            let mut json = json!({
-                "version": {
-                    "name": MINECRAFT_VERSION,
-                    "protocol": PROTOCOL_VERSION
-                },
-                "players": {
-                    "online": online_players,
-                    "max": max_players,
-                    "sample": player_sample,
-                },
-                "description": description,
-            });
-
-            if !favicon_png.is_empty() {
-                let mut buf = "data:image/png;base64,".to_owned();
-                BASE64_STANDARD.encode_string(favicon_png, &mut buf);
-                json["favicon"] = Value::String(buf);
-            }
+        .server_list_ping(&shared, remote_addr, &handshake)
+        .await;
 
+    match server_list_ping_json(&ping) {
+        Some(json) => {
             io.send_packet(&QueryResponseS2c {
                 json: &json.to_string(),
             })
             .await?;
         }
-        ServerListPing::Ignore => return Ok(()),
+        None => return Ok(()),
     }
 
     let QueryPingC2s { payload } = io.recv_packet().await?;
@@ -206,6 +257,84 @@ async fn handle_status(
     Ok(())
 }
 
+/// Creates the JSON response to a server list ping, or returns `None` if the
+/// ping is ignored.
+fn server_list_ping_json(ping: &ServerListPing) -> Option<Value> {
+    let (
+        players,
+        description,
+        favicon_png,
+        version_name,
+        protocol_version,
+        enforces_secure_chat,
+        previews_chat,
+    ) = match ping {
+        ServerListPing::Respond {
+            online_players,
+            max_players,
+            player_sample,
+            description,
+            favicon_png,
+            version_name,
+            protocol_version,
+            enforces_secure_chat,
+            previews_chat,
+        } => (
+            Some(json!({
+                "online": online_players,
+                "max": max_players,
+                "sample": player_sample,
+            })),
+            description,
+            favicon_png,
+            version_name,
+            protocol_version,
+            enforces_secure_chat,
+            previews_chat,
+        ),
+        ServerListPing::RespondWithoutPlayers {
+            description,
+            favicon_png,
+            version_name,
+            protocol_version,
+            enforces_secure_chat,
+            previews_chat,
+        } => (
+            None,
+            description,
+            favicon_png,
+            version_name,
+            protocol_version,
+            enforces_secure_chat,
+            previews_chat,
+        ),
+        ServerListPing::Ignore => return None,
+    };
+
+    let mut json = json!({
+        "version": {
+            "name": version_name,
+            "protocol": protocol_version,
+        },
+        "description": description,
+        "enforcesSecureChat": enforces_secure_chat,
+        "previewsChat": previews_chat,
+    });
+
+    // Clients display `???` instead of the player count if it is missing.
+    if let Some(players) = players {
+        json["players"] = players;
+    }
+
+    if !favicon_png.is_empty() {
+        let mut buf = "data:image/png;base64,".to_owned();
+        BASE64_STANDARD.encode_string(favicon_png, &mut buf);
+        json["favicon"] = Value::String(buf);
+    }
+
+    Some(json)
+}
+
 /// Handle the login process and return the new client's data if successful.
 async fn handle_login(
     shared: &SharedNetworkState,
@@ -230,10 +359,29 @@ async fn handle_login(
     let info = match shared.connection_mode() {
         ConnectionMode::Online { .. } => login_online(shared, conn, remote_addr, username).await?,
         ConnectionMode::Offline => login_offline(remote_addr, username)?,
-        ConnectionMode::BungeeCord => login_bungeecord(&handshake.server_address, username)?,
+        ConnectionMode::BungeeCord {
+            bungee_guard_tokens,
+        } => {
+            login_bungeecord(
+                conn,
+                &handshake.server_address,
+                username,
+                bungee_guard_tokens,
+            )
+            .await?
+        }
         ConnectionMode::Velocity { secret } => login_velocity(conn, username, secret).await?,
     };
 
+    if let Err(reason) = shared.0.callbacks.inner.check_login(shared, &info).await {
+        info!("disconnect at login: \"{reason}\"");
+        conn.send_packet(&LoginDisconnectS2c {
+            reason: reason.into(),
+        })
+        .await?;
+        return Ok(None);
+    }
+
     if let Some(threshold) = shared.0.compression_threshold {
         conn.send_packet(&LoginCompressionS2c {
             threshold: VarInt(threshold as i32),
@@ -387,43 +535,97 @@ fn login_offline(remote_addr: SocketAddr, username: String) -> anyhow::Result<Ne
 }
 
 /// Login procedure for BungeeCord.
-fn login_bungeecord(server_address: &str, username: String) -> anyhow::Result<NewClientInfo> {
+async fn login_bungeecord(
+    io: &mut PacketIo,
+    server_address: &str,
+    username: String,
+    bungee_guard_tokens: &[String],
+) -> anyhow::Result<NewClientInfo> {
+    match parse_bungeecord_server_address(server_address, username, bungee_guard_tokens) {
+        Ok(info) => Ok(info),
+        Err(e) => {
+            io.send_packet(&LoginDisconnectS2c {
+                reason: Text::from(
+                    "If you wish to use IP forwarding, please enable it in your BungeeCord config \
+                     as well!",
+                )
+                .into(),
+            })
+            .await?;
+            Err(e)
+        }
+    }
+}
+
+/// The name of the property containing the token added by BungeeGuard.
+const BUNGEE_GUARD_TOKEN_PROPERTY: &str = "bungeeguard-token";
+
+/// Reads the player data BungeeCord appends to the server address of the
+/// handshake, in the form `host\0client_ip\0uuid\0properties`. The properties
+/// are omitted for players without any.
+fn parse_bungeecord_server_address(
+    server_address: &str,
+    username: String,
+    bungee_guard_tokens: &[String],
+) -> anyhow::Result<NewClientInfo> {
     // Get data from server_address field of the handshake
-    let [_, client_ip, uuid, properties]: [&str; 4] = server_address
-        .split('\0')
-        .take(4)
-        .collect::<Vec<_>>()
-        .try_into()
-        .map_err(|_| anyhow!("malformed BungeeCord server address data"))?;
+    let (client_ip, uuid, properties) = match server_address.split('\0').collect::<Vec<_>>()[..] {
+        [_, client_ip, uuid] => (client_ip, uuid, None),
+        [_, client_ip, uuid, properties] => (client_ip, uuid, Some(properties)),
+        _ => bail!("malformed BungeeCord server address data"),
+    };
 
     // Read properties and get textures
-    let properties: Vec<Property> =
-        serde_json::from_str(properties).context("failed to parse BungeeCord player properties")?;
+    let properties: Vec<Property> = match properties {
+        Some(properties) => serde_json::from_str(properties)
+            .context("failed to parse BungeeCord player properties")?,
+        None => vec![],
+    };
+
+    // The tokens must not be sent to other clients, so they are removed from
+    // the properties whether or not they are checked.
+    let (tokens, properties): (Vec<_>, Vec<_>) = properties
+        .into_iter()
+        .partition(|prop| prop.name == BUNGEE_GUARD_TOKEN_PROPERTY);
+
+    if !bungee_guard_tokens.is_empty() {
+        ensure!(
+            matches!(&tokens[..], [token] if bungee_guard_tokens.contains(&token.value)),
+            "missing or invalid BungeeGuard token"
+        );
+    }
 
     Ok(NewClientInfo {
-        uuid: uuid.parse()?,
+        // BungeeCord sends the UUID without dashes.
+        uuid: uuid
+            .parse()
+            .context("failed to parse BungeeCord player UUID")?,
         username,
         properties: properties.into(),
-        ip: client_ip.parse()?,
+        ip: client_ip
+            .parse()
+            .context("failed to parse BungeeCord client address")?,
     })
 }
 
+/// The highest version of Velocity's modern forwarding protocol which is
+/// understood. Velocity responds with the lower of this and its own highest
+/// version.
+const VELOCITY_MAX_SUPPORTED_VERSION: u8 = 1;
+
 /// Login procedure for Velocity.
 async fn login_velocity(
     io: &mut PacketIo,
     username: String,
     velocity_secret: &str,
 ) -> anyhow::Result<NewClientInfo> {
-    const VELOCITY_MIN_SUPPORTED_VERSION: u8 = 1;
-    const VELOCITY_MODERN_FORWARDING_WITH_KEY_V2: i32 = 3;
-
     let message_id: i32 = 0; // TODO: make this random?
 
     // Send Player Info Request into the Plugin Channel
     io.send_packet(&LoginQueryRequestS2c {
         message_id: VarInt(message_id),
         channel: ident!("velocity:player_info").into(),
-        data: RawBytes(&[VELOCITY_MIN_SUPPORTED_VERSION]),
+        data: RawBytes(&[VELOCITY_MAX_SUPPORTED_VERSION]),
     })
     .await?;
 
@@ -436,26 +638,58 @@ async fn login_velocity(
         plugin_response.message_id.0,
     );
 
-    let data = plugin_response
-        .data
-        .context("missing plugin response data")?
-        .0;
+    // Clients which do not understand the request respond without data, which
+    // means they are not connecting through Velocity.
+    let Some(RawBytes(data)) = plugin_response.data else {
+        io.send_packet(&LoginDisconnectS2c {
+            reason: Text::from("This server requires you to connect with Velocity.").into(),
+        })
+        .await?;
+        bail!("missing plugin response data");
+    };
+
+    match parse_velocity_player_info(data, velocity_secret, username) {
+        Ok(info) => Ok(info),
+        Err(e) => {
+            io.send_packet(&LoginDisconnectS2c {
+                reason: Text::from("Unable to verify player details.").into(),
+            })
+            .await?;
+            Err(e)
+        }
+    }
+}
 
+/// Verifies the signature of the player info forwarded by Velocity and reads
+/// the player data from it.
+fn parse_velocity_player_info(
+    data: &[u8],
+    velocity_secret: &str,
+    username: String,
+) -> anyhow::Result<NewClientInfo> {
     ensure!(data.len() >= 32, "invalid plugin response data length");
     let (signature, mut data_without_signature) = data.split_at(32);
 
     // Verify signature
     let mut mac = Hmac::<Sha256>::new_from_slice(velocity_secret.as_bytes())?;
     Mac::update(&mut mac, data_without_signature);
-    mac.verify_slice(signature)?;
+    mac.verify_slice(signature)
+        .map_err(|_| anyhow!("invalid velocity player info signature"))?;
 
     // Check Velocity version
     let version = VarInt::decode(&mut data_without_signature)
         .context("failed to decode velocity version")?
         .0;
 
+    ensure!(
+        (1..=VELOCITY_MAX_SUPPORTED_VERSION as i32).contains(&version),
+        "unsupported velocity forwarding version {version}"
+    );
+
     // Get client address
-    let remote_addr = String::decode(&mut data_without_signature)?.parse()?;
+    let remote_addr = String::decode(&mut data_without_signature)?
+        .parse()
+        .context("failed to parse velocity client address")?;
 
     // Get UUID
     let uuid = Uuid::decode(&mut data_without_signature)?;
@@ -470,10 +704,6 @@ async fn login_velocity(
     let properties = Vec::<Property>::decode(&mut data_without_signature)
         .context("decoding velocity game profile properties")?;
 
-    if version >= VELOCITY_MODERN_FORWARDING_WITH_KEY_V2 {
-        // TODO
-    }
-
     Ok(NewClientInfo {
         uuid,
         username,
@@ -484,10 +714,223 @@ async fn login_velocity(
 
 #[cfg(test)]
 mod tests {
+    use std::net::IpAddr;
+
     use sha1::Digest;
+    use valence_core::packet::Encode;
 
     use super::*;
 
+    const VELOCITY_SECRET: &str = "s3cr3t";
+
+    /// Creates the player info Velocity sends in response to the login plugin
+    /// request, signed with `secret`.
+    fn velocity_player_info(secret: &str, version: i32, username: &str) -> Vec<u8> {
+        let mut data = vec![];
+
+        VarInt(version).encode(&mut data).unwrap();
+        "203.0.113.7".encode(&mut data).unwrap();
+        Uuid::from_u128(0x1234).encode(&mut data).unwrap();
+        username.encode(&mut data).unwrap();
+        vec![Property {
+            name: "textures".to_owned(),
+            value: "skin".to_owned(),
+            signature: Some("signature".to_owned()),
+        }]
+        .encode(&mut data)
+        .unwrap();
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        Mac::update(&mut mac, &data);
+
+        let mut signed = mac.finalize().into_bytes().to_vec();
+        signed.extend_from_slice(&data);
+        signed
+    }
+
+    /// Logs in through a local connection, with the proxy responding to the
+    /// login plugin request with `response`. Returns the result of the login
+    /// and whether the proxy was disconnected with a reason.
+    async fn login_through_proxy(
+        response: Option<Vec<u8>>,
+    ) -> (anyhow::Result<NewClientInfo>, bool) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let proxy = tokio::spawn(async move {
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let mut io = PacketIo::new(
+                stream,
+                PacketEncoder::new(),
+                PacketDecoder::new(),
+                Duration::from_secs(5),
+            );
+
+            let request: LoginQueryRequestS2c = io.recv_packet().await.unwrap();
+            assert_eq!(request.channel.as_str(), "velocity:player_info");
+            assert_eq!(request.data.0, [VELOCITY_MAX_SUPPORTED_VERSION]);
+            let message_id = request.message_id;
+
+            io.send_packet(&LoginQueryResponseC2s {
+                message_id,
+                data: response.as_deref().map(RawBytes),
+            })
+            .await
+            .unwrap();
+
+            io.recv_packet::<LoginDisconnectS2c>().await.is_ok()
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut io = PacketIo::new(
+            stream,
+            PacketEncoder::new(),
+            PacketDecoder::new(),
+            Duration::from_secs(5),
+        );
+
+        let res = login_velocity(&mut io, "Alice".into(), VELOCITY_SECRET).await;
+        drop(io);
+
+        (res, proxy.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn velocity_login_forwards_player_info() {
+        let response = velocity_player_info(VELOCITY_SECRET, 1, "Alice");
+        let (res, disconnected) = login_through_proxy(Some(response)).await;
+
+        let info = res.unwrap();
+        assert!(!disconnected);
+        assert_eq!(info.username, "Alice");
+        assert_eq!(info.uuid, Uuid::from_u128(0x1234));
+        assert_eq!(info.ip, "203.0.113.7".parse::<IpAddr>().unwrap());
+        assert_eq!(info.properties.textures().unwrap().value, "skin");
+    }
+
+    #[tokio::test]
+    async fn velocity_login_rejects_bad_signature() {
+        let response = velocity_player_info("wrong secret", 1, "Alice");
+        let (res, disconnected) = login_through_proxy(Some(response)).await;
+
+        assert!(res.is_err());
+        assert!(disconnected);
+    }
+
+    #[tokio::test]
+    async fn velocity_login_rejects_direct_connection() {
+        let (res, disconnected) = login_through_proxy(None).await;
+
+        assert!(res.is_err());
+        assert!(disconnected);
+    }
+
+    #[test]
+    fn bungeecord_server_address_parsed() {
+        let address = [
+            "example.com",
+            "203.0.113.7",
+            "00000000000000000000000000001234",
+            concat!(
+                r#"[{"name":"textures","value":"skin","signature":"sig"},"#,
+                r#"{"name":"bungeeguard-token","value":"token"}]"#,
+            ),
+        ]
+        .join("\0");
+        let address = address.as_str();
+
+        let info = parse_bungeecord_server_address(address, "Alice".into(), &[]).unwrap();
+
+        assert_eq!(info.username, "Alice");
+        assert_eq!(info.uuid, Uuid::from_u128(0x1234));
+        assert_eq!(info.ip, "203.0.113.7".parse::<IpAddr>().unwrap());
+        // The BungeeGuard token is removed.
+        assert_eq!(info.properties.len(), 1);
+        assert_eq!(info.properties.textures().unwrap().value, "skin");
+
+        let tokens = ["token".to_owned()];
+        assert!(parse_bungeecord_server_address(address, "Alice".into(), &tokens).is_ok());
+
+        let tokens = ["other token".to_owned()];
+        assert!(parse_bungeecord_server_address(address, "Alice".into(), &tokens).is_err());
+
+        // The properties are omitted for players without any.
+        let address = ["example.com", "::1", "00000000-0000-0000-0000-000000001234"].join("\0");
+        let address = address.as_str();
+
+        let info = parse_bungeecord_server_address(address, "Alice".into(), &[]).unwrap();
+
+        assert_eq!(info.uuid, Uuid::from_u128(0x1234));
+        assert_eq!(info.ip, "::1".parse::<IpAddr>().unwrap());
+        assert!(info.properties.is_empty());
+
+        assert!(parse_bungeecord_server_address(address, "Alice".into(), &tokens).is_err());
+
+        // Direct connections have no forwarded data.
+        assert!(parse_bungeecord_server_address("example.com", "Alice".into(), &[]).is_err());
+    }
+
+    #[test]
+    fn server_list_ping_json_created() {
+        let json = server_list_ping_json(&ServerListPing::Respond {
+            online_players: 3,
+            max_players: 20,
+            player_sample: vec![],
+            description: "A Valence Server".into(),
+            favicon_png: &[1, 2, 3],
+            version_name: "Valence".into(),
+            protocol_version: 1000,
+            enforces_secure_chat: true,
+            previews_chat: false,
+        })
+        .unwrap();
+
+        assert_eq!(
+            json["version"],
+            json!({ "name": "Valence", "protocol": 1000 })
+        );
+        assert_eq!(
+            json["players"],
+            json!({ "online": 3, "max": 20, "sample": [] })
+        );
+        assert_eq!(json["favicon"], "data:image/png;base64,AQID");
+        assert_eq!(json["enforcesSecureChat"], true);
+        assert_eq!(json["previewsChat"], false);
+
+        let json = server_list_ping_json(&ServerListPing::RespondWithoutPlayers {
+            description: "A Valence Server".into(),
+            favicon_png: &[],
+            version_name: "Valence".into(),
+            protocol_version: 1000,
+            enforces_secure_chat: false,
+            previews_chat: false,
+        })
+        .unwrap();
+
+        assert!(json.get("players").is_none());
+        assert!(json.get("favicon").is_none());
+
+        assert!(server_list_ping_json(&ServerListPing::Ignore).is_none());
+    }
+
+    #[test]
+    fn velocity_player_info_checked() {
+        let parse = |data: &[u8]| parse_velocity_player_info(data, VELOCITY_SECRET, "Alice".into());
+
+        assert!(parse(&velocity_player_info(VELOCITY_SECRET, 1, "Alice")).is_ok());
+        // Newer versions were not requested.
+        assert!(parse(&velocity_player_info(VELOCITY_SECRET, 2, "Alice")).is_err());
+        assert!(parse(&velocity_player_info(VELOCITY_SECRET, 0, "Alice")).is_err());
+        assert!(parse(&velocity_player_info(VELOCITY_SECRET, 1, "Bob")).is_err());
+
+        // Tampering with the data invalidates the signature.
+        let mut data = velocity_player_info(VELOCITY_SECRET, 1, "Alice");
+        *data.last_mut().unwrap() ^= 1;
+        assert!(parse(&data).is_err());
+
+        assert!(parse(&[0; 16]).is_err());
+    }
+
     #[test]
     fn auth_digest_usernames() {
         assert_eq!(
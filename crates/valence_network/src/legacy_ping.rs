@@ -0,0 +1,272 @@
+//! Responds to the server list pings sent by clients before Minecraft 1.7.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::ensure;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use valence_core::text::Text;
+
+use crate::{HandshakeData, ServerListPing, SharedNetworkState};
+
+/// The protocol version sent in responses to legacy pings. Like in vanilla,
+/// this does not match any client so that the server is displayed as
+/// incompatible.
+const LEGACY_PROTOCOL_VERSION: i32 = 127;
+
+/// The kinds of legacy server list pings.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum LegacyPing {
+    /// Sent by Minecraft Beta 1.8 to 1.3 as a single `0xFE` byte.
+    Beta,
+    /// Sent by Minecraft 1.4 and 1.5 as the bytes `0xFE 0x01`.
+    V1_4,
+    /// Sent by Minecraft 1.6 as the bytes `0xFE 0x01 0xFA` followed by an
+    /// `MC|PingHost` plugin message.
+    V1_6,
+}
+
+impl LegacyPing {
+    /// Detects a legacy ping from the first bytes received on a connection,
+    /// which are a prefix of a normal handshake otherwise.
+    pub(crate) fn detect(first_bytes: &[u8]) -> Option<Self> {
+        match first_bytes {
+            [0xfe] => Some(Self::Beta),
+            [0xfe, 0x01] => Some(Self::V1_4),
+            [0xfe, 0x01, 0xfa, ..] => Some(Self::V1_6),
+            _ => None,
+        }
+    }
+
+    /// Reads the complete ping from the stream, so that the connection can be
+    /// closed without discarding the response. The address the client
+    /// connected to is only sent by Minecraft 1.6.
+    pub(crate) async fn read(self, mut r: impl AsyncRead + Unpin) -> anyhow::Result<HandshakeData> {
+        let mut handshake = HandshakeData {
+            protocol_version: -1,
+            server_address: String::new(),
+            server_port: 0,
+        };
+
+        match self {
+            Self::Beta => {
+                r.read_u8().await?;
+            }
+            Self::V1_4 => {
+                r.read_u16().await?;
+            }
+            Self::V1_6 => {
+                let mut header = [0; 3];
+                r.read_exact(&mut header).await?;
+
+                let channel_len = r.read_u16().await? as usize;
+                let mut channel = vec![0; channel_len * 2];
+                r.read_exact(&mut channel).await?;
+
+                ensure!(
+                    decode_utf16_be(&channel) == "MC|PingHost",
+                    "unexpected legacy ping channel"
+                );
+
+                // The protocol version of the client, and the address and port
+                // it connected to.
+                let data_len = r.read_u16().await? as usize;
+                let mut data = vec![0; data_len];
+                r.read_exact(&mut data).await?;
+
+                if let Some((server_address, server_port)) = parse_ping_host(&data) {
+                    handshake.server_address = server_address;
+                    handshake.server_port = server_port;
+                }
+            }
+        }
+
+        Ok(handshake)
+    }
+
+    /// Creates the response to the ping, which is a kick packet with the
+    /// server information encoded in the reason. Hidden player counts are
+    /// sent as `-1`.
+    pub(crate) fn response(
+        self,
+        online_players: i32,
+        max_players: i32,
+        description: &Text,
+        version_name: &str,
+    ) -> Vec<u8> {
+        let reason = match self {
+            // The fields are separated by `§`, so the description cannot contain
+            // formatting codes.
+            Self::Beta => format!(
+                "{}§{online_players}§{max_players}",
+                description.to_string().replace('§', "")
+            ),
+            Self::V1_4 | Self::V1_6 => format!(
+                "§1\0{}\0{}\0{}\0{}\0{}",
+                LEGACY_PROTOCOL_VERSION,
+                version_name,
+                description.to_legacy_lossy(),
+                online_players,
+                max_players
+            ),
+        };
+
+        let units: Vec<u16> = reason.encode_utf16().collect();
+
+        let mut buf = vec![0xff];
+        buf.extend_from_slice(&(units.len() as u16).to_be_bytes());
+
+        for unit in units {
+            buf.extend_from_slice(&unit.to_be_bytes());
+        }
+
+        buf
+    }
+}
+
+/// Parses the address and port from the data of an `MC|PingHost` plugin
+/// message, after the protocol version of the client.
+fn parse_ping_host(data: &[u8]) -> Option<(String, u16)> {
+    let [_protocol_version, len_hi, len_lo, rest @ ..] = data else {
+        return None;
+    };
+
+    let address_len = u16::from_be_bytes([*len_hi, *len_lo]) as usize * 2;
+    let address = rest.get(..address_len)?;
+    let port = i32::from_be_bytes(rest.get(address_len..)?.try_into().ok()?);
+
+    Some((decode_utf16_be(address), port.try_into().ok()?))
+}
+
+fn decode_utf16_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect();
+
+    String::from_utf16_lossy(&units)
+}
+
+/// Responds to a legacy ping if the connection begins with one. Returns
+/// whether a legacy ping was handled, in which case the connection should be
+/// closed.
+pub(crate) async fn handle_legacy_ping(
+    shared: &SharedNetworkState,
+    stream: &mut TcpStream,
+    remote_addr: SocketAddr,
+) -> anyhow::Result<bool> {
+    let mut first_bytes = [0; 3];
+    let len = timeout(Duration::from_secs(5), stream.peek(&mut first_bytes)).await??;
+
+    let Some(ping) = LegacyPing::detect(&first_bytes[..len]) else {
+        return Ok(false);
+    };
+
+    let handshake = timeout(Duration::from_secs(5), ping.read(&mut *stream)).await??;
+
+    let response = match shared
+        .0
+        .callbacks
+        .inner
+        .server_list_ping(shared, remote_addr, &handshake)
+        .await
+    {
+        ServerListPing::Respond {
+            online_players,
+            max_players,
+            description,
+            version_name,
+            ..
+        } => ping.response(online_players, max_players, &description, &version_name),
+        ServerListPing::RespondWithoutPlayers {
+            description,
+            version_name,
+            ..
+        } => ping.response(-1, -1, &description, &version_name),
+        ServerListPing::Ignore => return Ok(true),
+    };
+
+    stream.write_all(&response).await?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_core::text::{Color, TextFormat};
+    use valence_core::MINECRAFT_VERSION;
+
+    use super::*;
+
+    /// Encodes a string as a length-prefixed UTF-16 string.
+    fn utf16_be(s: &str) -> Vec<u8> {
+        let units: Vec<u16> = s.encode_utf16().collect();
+
+        let mut buf = (units.len() as u16).to_be_bytes().to_vec();
+        buf.extend(units.iter().flat_map(|unit| unit.to_be_bytes()));
+        buf
+    }
+
+    #[tokio::test]
+    async fn legacy_pings_read() {
+        assert_eq!(LegacyPing::detect(&[0xfe]), Some(LegacyPing::Beta));
+        assert_eq!(LegacyPing::detect(&[0xfe, 0x01]), Some(LegacyPing::V1_4));
+
+        // Sent by a 1.6.4 client connecting to localhost:25565.
+        let mut v1_6 = vec![0xfe, 0x01, 0xfa];
+        v1_6.extend(utf16_be("MC|PingHost"));
+        v1_6.extend([0x00, 0x19, 0x4e]);
+        v1_6.extend(utf16_be("localhost"));
+        v1_6.extend([0x00, 0x00, 0x63, 0xdd]);
+
+        assert_eq!(LegacyPing::detect(&v1_6[..3]), Some(LegacyPing::V1_6));
+
+        let mut r = v1_6.as_slice();
+        assert_eq!(
+            LegacyPing::V1_6.read(&mut r).await.unwrap(),
+            HandshakeData {
+                protocol_version: -1,
+                server_address: "localhost".into(),
+                server_port: 25565,
+            }
+        );
+        assert!(r.is_empty());
+
+        // A truncated ping is an error.
+        assert!(LegacyPing::V1_6.read(&v1_6[..20]).await.is_err());
+
+        // The beginning of a handshake with a length of 254.
+        assert_eq!(LegacyPing::detect(&[0xfe, 0x01, 0x00]), None);
+        // The beginning of a handshake with a length of 16.
+        assert_eq!(LegacyPing::detect(&[0x10, 0x00, 0xfb]), None);
+    }
+
+    #[test]
+    fn legacy_ping_responses() {
+        let description = Text::from("A ") + "Valence".color(Color::GOLD) + " Server";
+
+        let mut expected = vec![0xff];
+        expected.extend(utf16_be("A Valence Server§3§20"));
+
+        assert_eq!(
+            LegacyPing::Beta.response(3, 20, &description, MINECRAFT_VERSION),
+            expected
+        );
+
+        let mut expected = vec![0xff];
+        expected.extend(utf16_be(&format!(
+            "§1\x00127\x00{MINECRAFT_VERSION}\x00A §6Valence§r Server\x003\x0020"
+        )));
+
+        assert_eq!(
+            LegacyPing::V1_4.response(3, 20, &description, MINECRAFT_VERSION),
+            expected
+        );
+        assert_eq!(
+            LegacyPing::V1_6.response(3, 20, &description, MINECRAFT_VERSION),
+            expected
+        );
+    }
+}
@@ -19,11 +19,17 @@
 
 mod byte_channel;
 mod connect;
+mod legacy_ping;
 mod packet_io;
+mod proxy_protocol;
+mod rcon;
+mod shutdown;
+mod throttle;
 
+use std::future::pending;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
 pub use async_trait::async_trait;
@@ -32,15 +38,26 @@ use bevy_ecs::prelude::*;
 use connect::do_accept_loop;
 use flume::{Receiver, Sender};
 use rand::rngs::OsRng;
+use rcon::do_rcon_accept_loop;
+pub use rcon::RconSettings;
 use rsa::{PublicKeyParts, RsaPrivateKey};
 use serde::Serialize;
+use shutdown::shutdown_server;
+pub use shutdown::ShutdownServer;
+use throttle::ConnectionThrottle;
+pub use throttle::{ConnectionLimits, ConnectionRateLimit, ConnectionStats};
 use tokio::runtime::{Handle, Runtime};
-use tokio::sync::Semaphore;
+use tokio::sync::watch;
 use tracing::error;
 use uuid::Uuid;
-use valence_client::{ClientBundle, ClientBundleArgs, Properties, SpawnClientsSet};
+use valence_client::{
+    ClientBundle, ClientBundleArgs, FlushPacketsSet, OverloadPolicy, Properties, SendQueueLimit,
+    SpawnClientsSet,
+};
+use valence_core::packet::encode::WritePacket;
+use valence_core::packet::s2c::play::DisconnectS2c;
 use valence_core::text::Text;
-use valence_core::Server;
+use valence_core::{Server, MINECRAFT_VERSION, PROTOCOL_VERSION};
 
 pub struct NetworkPlugin;
 
@@ -86,13 +103,16 @@ fn build_plugin(app: &mut App) -> anyhow::Result<()> {
         callbacks: settings.callbacks.clone(),
         address: settings.address,
         incoming_byte_limit: settings.incoming_byte_limit,
-        outgoing_byte_limit: settings.outgoing_byte_limit,
-        connection_sema: Arc::new(Semaphore::new(
-            settings.max_connections.min(Semaphore::MAX_PERMITS),
-        )),
+        send_queue_limit: SendQueueLimit {
+            max_bytes: settings.outgoing_byte_limit,
+            policy: settings.overload_policy,
+        },
+        throttle: Mutex::new(ConnectionThrottle::new(settings.connection_limits)),
         player_count: AtomicUsize::new(0),
         max_players: settings.max_players,
         connection_mode: settings.connection_mode.clone(),
+        proxy_protocol: settings.proxy_protocol,
+        rcon: settings.rcon.clone(),
         compression_threshold,
         tokio_handle,
         _tokio_runtime: runtime,
@@ -101,25 +121,43 @@ fn build_plugin(app: &mut App) -> anyhow::Result<()> {
         rsa_key,
         public_key_der,
         http_client: reqwest::Client::new(),
+        shutdown: watch::channel(None).0,
+        writer_token: Arc::new(()),
     }));
 
     app.insert_resource(shared.clone());
 
-    // System for starting the accept loop.
+    // System for starting the accept loops.
     let start_accept_loop = move |shared: Res<SharedNetworkState>| {
         let _guard = shared.0.tokio_handle.enter();
 
         // Start accepting new connections.
         tokio::spawn(do_accept_loop(shared.clone()));
+
+        if let Some(rcon) = shared.0.rcon.clone() {
+            tokio::spawn(do_rcon_accept_loop(shared.clone(), rcon));
+        }
     };
 
     // System for spawning new clients.
     let spawn_new_clients = move |world: &mut World| {
         for _ in 0..shared.0.new_clients_recv.len() {
             match shared.0.new_clients_recv.try_recv() {
-                Ok(args) => world.spawn(ClientBundle::new(args)),
+                Ok(mut args) => {
+                    // Clients which logged in while the server was shutting down
+                    // are disconnected instead.
+                    if let Some(reason) = shared.0.shutdown.borrow().clone() {
+                        args.enc.write_packet(&DisconnectS2c {
+                            reason: reason.into(),
+                        });
+                        _ = args.conn.try_send(args.enc.take());
+                        continue;
+                    }
+
+                    world.spawn(ClientBundle::new(args));
+                }
                 Err(_) => break,
-            };
+            }
         }
     };
 
@@ -134,6 +172,12 @@ fn build_plugin(app: &mut App) -> anyhow::Result<()> {
     // Spawn new clients before the event loop starts.
     app.add_system(spawn_new_clients.in_set(SpawnClientsSet));
 
+    app.add_event::<ShutdownServer>().add_system(
+        shutdown_server
+            .in_base_set(CoreSet::PostUpdate)
+            .before(FlushPacketsSet),
+    );
+
     Ok(())
 }
 
@@ -152,19 +196,60 @@ impl SharedNetworkState {
     pub fn max_players(&self) -> usize {
         self.0.max_players
     }
+
+    /// Returns the current limits on connections. See
+    /// [`NetworkSettings::connection_limits`].
+    pub fn connection_limits(&self) -> ConnectionLimits {
+        self.0.throttle.lock().unwrap().limits()
+    }
+
+    /// Changes the limits on connections. Connections which are already open
+    /// are not affected.
+    pub fn set_connection_limits(&self, limits: ConnectionLimits) {
+        self.0.throttle.lock().unwrap().set_limits(limits);
+    }
+
+    /// Returns counters of the connections handled by the server.
+    pub fn connection_stats(&self) -> ConnectionStats {
+        self.0.throttle.lock().unwrap().stats()
+    }
+
+    /// Returns whether the server is shutting down after a [`ShutdownServer`]
+    /// event.
+    pub fn is_shutting_down(&self) -> bool {
+        self.0.shutdown.borrow().is_some()
+    }
+
+    /// Waits until the server is shutting down, and returns the reason for
+    /// disconnecting the clients.
+    pub(crate) async fn shutdown_reason(&self) -> Text {
+        let mut recv = self.0.shutdown.subscribe();
+
+        loop {
+            if let Some(reason) = recv.borrow_and_update().clone() {
+                return reason;
+            }
+
+            if recv.changed().await.is_err() {
+                // The sender is never dropped while `self` is alive.
+                pending::<()>().await;
+            }
+        }
+    }
 }
 struct SharedNetworkStateInner {
     callbacks: ErasedNetworkCallbacks,
     address: SocketAddr,
     incoming_byte_limit: usize,
-    outgoing_byte_limit: usize,
-    /// Limits the number of simultaneous connections to the server before the
-    /// play state.
-    connection_sema: Arc<Semaphore>,
+    send_queue_limit: SendQueueLimit,
+    /// Limits the connections to the server before the play state.
+    throttle: Mutex<ConnectionThrottle>,
     //// The number of clients in the play state, past the login state.
     player_count: AtomicUsize,
     max_players: usize,
     connection_mode: ConnectionMode,
+    proxy_protocol: ProxyProtocolMode,
+    rcon: Option<RconSettings>,
     compression_threshold: Option<u32>,
     tokio_handle: Handle,
     // Holding a runtime handle is not enough to keep tokio working. We need
@@ -181,6 +266,27 @@ struct SharedNetworkStateInner {
     public_key_der: Box<[u8]>,
     /// For session server requests.
     http_client: reqwest::Client,
+    /// Contains the disconnect reason once the server is shutting down.
+    shutdown: watch::Sender<Option<Text>>,
+    /// Cloned by the tasks sending packets to the clients in the play state,
+    /// to wait for them to finish when shutting down.
+    writer_token: Arc<()>,
+}
+
+/// The information sent by a client at the beginning of a connection. See
+/// [`NetworkCallbacks::server_list_ping`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct HandshakeData {
+    /// The protocol version of the client, or `-1` for legacy pings.
+    pub protocol_version: i32,
+    /// The host name or IP address the client used to connect.
+    ///
+    /// Proxies and mod loaders may append additional data to the address,
+    /// separated by nul bytes.
+    pub server_address: String,
+    /// The port the client used to connect, or `0` if it is unknown.
+    pub server_port: u16,
 }
 
 /// Contains information about a new client joining the server.
@@ -210,15 +316,14 @@ pub struct NetworkSettings {
     ///
     /// `None`
     pub tokio_handle: Option<Handle>,
-    /// The maximum number of simultaneous initial connections to the server.
-    ///
-    /// This only considers the connections _before_ the play state where the
-    /// client is spawned into the world..
+    /// The limits on connections to the server _before_ the play state where
+    /// the client is spawned into the world. These can be changed after the
+    /// plugin is built with [`SharedNetworkState::set_connection_limits`].
     ///
     /// # Default Value
     ///
-    /// The default value is left unspecified and may change in future versions.
-    pub max_connections: usize,
+    /// [`ConnectionLimits::default()`]
+    pub connection_limits: ConnectionLimits,
     /// # Default Value
     ///
     /// `20`
@@ -239,6 +344,26 @@ pub struct NetworkSettings {
     ///
     /// [`ConnectionMode::Online`]
     pub connection_mode: ConnectionMode,
+    /// Whether connections begin with a [PROXY protocol] header containing
+    /// the address of the client, as sent by load balancers like HAProxy.
+    /// The address from the header is used as the remote address of the
+    /// client everywhere.
+    ///
+    /// # Default Value
+    ///
+    /// [`ProxyProtocolMode::Disabled`]
+    ///
+    /// [PROXY protocol]: https://www.haproxy.org/download/2.8/doc/proxy-protocol.txt
+    pub proxy_protocol: ProxyProtocolMode,
+    /// The settings of the [RCON] server, or `None` to disable it. Commands
+    /// received over RCON are passed to [`NetworkCallbacks::rcon_command`].
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    ///
+    /// [RCON]: https://developer.valvesoftware.com/wiki/Source_RCON_Protocol
+    pub rcon: Option<RconSettings>,
     /// The maximum capacity (in bytes) of the buffer used to hold incoming
     /// packet data.
     ///
@@ -251,7 +376,8 @@ pub struct NetworkSettings {
     /// The default value is left unspecified and may change in future versions.
     pub incoming_byte_limit: usize,
     /// The maximum capacity (in bytes) of the buffer used to hold outgoing
-    /// packet data.
+    /// packet data. This is the initial [`SendQueueLimit::max_bytes`] of the
+    /// clients.
     ///
     /// A larger capacity reduces the chance that a client needs to be
     /// disconnected due to a full buffer, but increases potential
@@ -261,6 +387,13 @@ pub struct NetworkSettings {
     ///
     /// The default value is left unspecified and may change in future versions.
     pub outgoing_byte_limit: usize,
+    /// How to handle clients which do not read their packets fast enough. This
+    /// is the initial [`SendQueueLimit::policy`] of the clients.
+    ///
+    /// # Default Value
+    ///
+    /// [`OverloadPolicy::Disconnect`]
+    pub overload_policy: OverloadPolicy,
 }
 
 impl Default for NetworkSettings {
@@ -268,14 +401,17 @@ impl Default for NetworkSettings {
         Self {
             callbacks: ErasedNetworkCallbacks::default(),
             tokio_handle: None,
-            max_connections: 1024,
+            connection_limits: ConnectionLimits::default(),
             max_players: 20,
             address: SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), 25565).into(),
             connection_mode: ConnectionMode::Online {
                 prevent_proxy_connections: false,
             },
+            proxy_protocol: ProxyProtocolMode::Disabled,
+            rcon: None,
             incoming_byte_limit: 2097152, // 2 MiB
             outgoing_byte_limit: 8388608, // 8 MiB
+            overload_policy: OverloadPolicy::Disconnect,
         }
     }
 }
@@ -314,6 +450,13 @@ impl<T: NetworkCallbacks> From<T> for ErasedNetworkCallbacks {
 pub trait NetworkCallbacks: Send + Sync + 'static {
     /// Called when the server receives a Server List Ping query.
     /// Data for the response can be provided or the query can be ignored.
+    /// The `handshake` contains the protocol version of the client and the
+    /// address it connected to, which allows different responses for each
+    /// version or domain name.
+    ///
+    /// Legacy pings sent by clients before Minecraft 1.7 are also handled
+    /// here, with a protocol version of `-1`. Only the player counts, the
+    /// description and the version name are sent in response to them.
     ///
     /// This function is called from within a tokio runtime.
     ///
@@ -324,7 +467,7 @@ pub trait NetworkCallbacks: Send + Sync + 'static {
         &self,
         shared: &SharedNetworkState,
         remote_addr: SocketAddr,
-        protocol_version: i32,
+        handshake: &HandshakeData,
     ) -> ServerListPing {
         #![allow(unused_variables)]
 
@@ -334,9 +477,36 @@ pub trait NetworkCallbacks: Send + Sync + 'static {
             player_sample: vec![],
             description: "A Valence Server".into(),
             favicon_png: &[],
+            version_name: MINECRAFT_VERSION.to_owned(),
+            protocol_version: PROTOCOL_VERSION,
+            enforces_secure_chat: false,
+            previews_chat: false,
         }
     }
 
+    /// Called for each client once its username, UUID and properties are
+    /// known, before [`NetworkCallbacks::login`]. If `Err(reason)` is
+    /// returned, the client is immediately disconnected with `reason` as the
+    /// displayed message.
+    ///
+    /// This is the place to implement whitelists, bans, maintenance mode and
+    /// other checks which do not affect the player count. It is called from
+    /// within a tokio runtime and not from the main tick, so it can await
+    /// database queries or HTTP requests without slowing down the server.
+    ///
+    /// # Default Implementation
+    ///
+    /// Every client is allowed to join.
+    async fn check_login(
+        &self,
+        shared: &SharedNetworkState,
+        info: &NewClientInfo,
+    ) -> Result<(), Text> {
+        #![allow(unused_variables)]
+
+        Ok(())
+    }
+
     /// Called for each client (after successful authentication if online mode
     /// is enabled) to determine if they can join the server.
     /// - If `Err(reason)` is returned, then the client is immediately
@@ -352,11 +522,16 @@ pub trait NetworkCallbacks: Send + Sync + 'static {
     ///
     /// This method is called from within a tokio runtime, and is the
     /// appropriate place to perform asynchronous operations such as
-    /// database queries which may take some time to complete.
+    /// database queries which may take some time to complete. Checks which
+    /// only decide whether the client may join are better implemented in
+    /// [`NetworkCallbacks::check_login`], which keeps this default
+    /// implementation.
     ///
     /// # Default Implementation
     ///
-    /// TODO
+    /// The client is counted in [`SharedNetworkState::player_count`] until it
+    /// is dropped, and rejected if [`SharedNetworkState::max_players`] is
+    /// reached.
     ///
     /// [`Client`]: valence::client::Client
     async fn login(
@@ -426,6 +601,30 @@ pub trait NetworkCallbacks: Send + Sync + 'static {
             format!("https://sessionserver.mojang.com/session/minecraft/hasJoined?username={username}&serverId={auth_digest}")
         }
     }
+
+    /// Called for each command received by the [RCON server] from an
+    /// authenticated client. The returned text is sent back to the client
+    /// without formatting, split over several packets if needed.
+    ///
+    /// This method is called from within a tokio runtime. Commands which need
+    /// access to the world can be sent to a system through a channel, with
+    /// another channel to send back the output.
+    ///
+    /// # Default Implementation
+    ///
+    /// Responds that the command is unknown.
+    ///
+    /// [RCON server]: NetworkSettings::rcon
+    async fn rcon_command(
+        &self,
+        shared: &SharedNetworkState,
+        remote_addr: SocketAddr,
+        command: &str,
+    ) -> Text {
+        #![allow(unused_variables)]
+
+        format!("Unknown command: {command}").into()
+    }
 }
 
 /// A callback function called when the associated client is dropped. See
@@ -484,19 +683,29 @@ pub enum ConnectionMode {
     ///   forwarding mode.
     ///
     /// All player data (username, UUID, and properties) is fetched from the
-    /// proxy, but no attempt is made to stop connections originating from
-    /// elsewhere. As a result, you must ensure clients connect through the
-    /// proxy and are unable to connect to the server directly. Otherwise,
-    /// clients can use any username or UUID they choose similar to
-    /// [`ConnectionMode::Offline`].
+    /// proxy. Clients connecting directly without the forwarded data are
+    /// rejected, but nothing stops them from forging it. As a result, you must
+    /// ensure clients connect through the proxy and are unable to connect to
+    /// the server directly. Otherwise, clients can use any username or UUID
+    /// they choose similar to [`ConnectionMode::Offline`].
     ///
-    /// To protect against this, a firewall can be used. However,
-    /// [`ConnectionMode::Velocity`] is recommended as a secure alternative.
+    /// To protect against this, a firewall or [BungeeGuard] can be used.
+    /// However, [`ConnectionMode::Velocity`] is recommended as a secure
+    /// alternative.
     ///
     /// [BungeeCord]: https://www.spigotmc.org/wiki/bungeecord/
     /// [Waterfall]: https://github.com/PaperMC/Waterfall
     /// [Velocity]: https://velocitypowered.com/
-    BungeeCord,
+    /// [BungeeGuard]: https://github.com/lucko/BungeeGuard
+    BungeeCord {
+        /// The tokens configured in the [BungeeGuard] plugin of the proxy.
+        /// If this is not empty, clients are rejected unless the proxy
+        /// forwards one of these tokens. The token is never included in the
+        /// properties of the client.
+        ///
+        /// [BungeeGuard]: https://github.com/lucko/BungeeGuard
+        bungee_guard_tokens: Vec<String>,
+    },
     /// This mode is used when the server is behind a [Velocity] proxy
     /// configured with the forwarding mode `modern`.
     ///
@@ -512,6 +721,26 @@ pub enum ConnectionMode {
     },
 }
 
+/// Describes whether connections begin with a [PROXY protocol] header. Both
+/// versions 1 and 2 of the header are supported.
+///
+/// **NOTE:** Unless the mode is [`ProxyProtocolMode::Require`], clients
+/// connecting directly can send a header with any address. The server must be
+/// reachable only through the load balancer in that case.
+///
+/// [PROXY protocol]: https://www.haproxy.org/download/2.8/doc/proxy-protocol.txt
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum ProxyProtocolMode {
+    /// Headers are not read, and connections with a header fail to log in.
+    #[default]
+    Disabled,
+    /// Connections may begin with a header. Connections without one use the
+    /// address of the socket.
+    Accept,
+    /// Connections must begin with a header, and are dropped otherwise.
+    Require,
+}
+
 /// The result of the Server List Ping [callback].
 ///
 /// [callback]: NetworkCallbacks::server_list_ping
@@ -535,6 +764,36 @@ pub enum ServerListPing<'a> {
         ///
         /// No icon is used if the slice is empty.
         favicon_png: &'a [u8],
+        /// The name of the server version, displayed by clients in red if
+        /// `protocol_version` is different from theirs.
+        version_name: String,
+        /// The protocol version of the server. Clients show the server as
+        /// incompatible if this is different from theirs, but can still try
+        /// to join it. Proxies translating between versions can set this to
+        /// the protocol version of the client.
+        protocol_version: i32,
+        /// Whether the server requires chat messages to be signed. Clients
+        /// show a warning if this is `false`.
+        enforces_secure_chat: bool,
+        /// Whether the server previews chat messages. This is ignored by
+        /// Minecraft 1.19.3 and later.
+        previews_chat: bool,
+    },
+    /// Responds to the server list ping like [`ServerListPing::Respond`], but
+    /// the number of players is hidden and displayed as `???`.
+    RespondWithoutPlayers {
+        /// See [`ServerListPing::Respond`].
+        description: Text,
+        /// See [`ServerListPing::Respond`].
+        favicon_png: &'a [u8],
+        /// See [`ServerListPing::Respond`].
+        version_name: String,
+        /// See [`ServerListPing::Respond`].
+        protocol_version: i32,
+        /// See [`ServerListPing::Respond`].
+        enforces_secure_chat: bool,
+        /// See [`ServerListPing::Respond`].
+        previews_chat: bool,
     },
     /// Ignores the query and disconnects from the client.
     #[default]
@@ -1,4 +1,5 @@
 use std::io::ErrorKind;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{io, mem};
@@ -7,11 +8,14 @@ use anyhow::bail;
 use bytes::{Buf, BytesMut};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::runtime::Handle;
 use tokio::sync::Semaphore;
 use tokio::task::JoinHandle;
 use tokio::time::timeout;
 use tracing::{debug, warn};
-use valence_client::{ClientBundleArgs, ClientConnection, ReceivedPacket};
+use valence_client::{
+    ClientBundleArgs, ClientConnection, ReceivedPacket, ReceivedStats, SendQueueLimit,
+};
 use valence_core::packet::decode::{decode_packet, PacketDecoder};
 use valence_core::packet::encode::PacketEncoder;
 use valence_core::packet::var_int::VarInt;
@@ -30,6 +34,14 @@ pub(crate) struct PacketIo {
 
 const READ_BUF_SIZE: usize = 4096;
 
+/// The maximum time spent sending the remaining packets to a client after it
+/// is dropped, before the connection is closed.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Room left in the outgoing buffer beyond the [`SendQueueLimit`], for the
+/// disconnect message sent to clients exceeding it.
+const DISCONNECT_RESERVE: usize = 4096;
+
 impl PacketIo {
     pub(crate) fn new(
         stream: TcpStream,
@@ -98,8 +110,9 @@ impl PacketIo {
         mut self,
         info: NewClientInfo,
         incoming_byte_limit: usize,
-        outgoing_byte_limit: usize,
+        send_queue_limit: SendQueueLimit,
         cleanup: CleanupOnDrop,
+        writer_token: Arc<()>,
     ) -> ClientBundleArgs {
         let (incoming_sender, incoming_receiver) = flume::unbounded();
 
@@ -110,6 +123,9 @@ impl PacketIo {
 
         let (mut reader, mut writer) = self.stream.into_split();
 
+        let bytes_received = Arc::new(AtomicU64::new(0));
+        let bytes_received_clone = bytes_received.clone();
+
         let reader_task = tokio::spawn(async move {
             let mut buf = BytesMut::new();
 
@@ -122,7 +138,9 @@ impl PacketIo {
                         buf.reserve(READ_BUF_SIZE);
                         match reader.read_buf(&mut buf).await {
                             Ok(0) => break, // Reader is at EOF.
-                            Ok(_) => {}
+                            Ok(n) => {
+                                bytes_received.fetch_add(n as u64, Ordering::Relaxed);
+                            }
                             Err(e) => {
                                 debug!("error reading data from stream: {e}");
                                 break;
@@ -193,22 +211,24 @@ impl PacketIo {
             }
         });
 
+        let outgoing_byte_limit =
+            usize::saturating_add(send_queue_limit.max_bytes, DISCONNECT_RESERVE);
         let (outgoing_sender, mut outgoing_receiver) = byte_channel(outgoing_byte_limit);
 
         let writer_task = tokio::spawn(async move {
-            loop {
-                let bytes = match outgoing_receiver.recv_async().await {
-                    Ok(bytes) => bytes,
-                    Err(e) => {
-                        debug!("error receiving packet data: {e}");
-                        break;
-                    }
-                };
+            // Held until the task ends, to let the server know when every
+            // connection is closed.
+            let _writer_token = writer_token;
 
+            // Stops once the connection is dropped and all the data was sent.
+            while let Ok(bytes) = outgoing_receiver.recv_async().await {
                 if let Err(e) = writer.write_all(&bytes).await {
                     debug!("error writing data to stream: {e}");
+                    return;
                 }
             }
+
+            _ = writer.shutdown().await;
         });
 
         ClientBundleArgs {
@@ -220,11 +240,16 @@ impl PacketIo {
                 send: outgoing_sender,
                 recv: incoming_receiver,
                 recv_sem: recv_sem_clone,
+                bytes_received: bytes_received_clone,
+                received_packets: 0,
+                received_uncompressed_bytes: 0,
                 reader_task,
-                writer_task,
+                writer_task: Some(writer_task),
+                runtime: Handle::current(),
                 _cleanup: cleanup,
             }),
             enc: self.enc,
+            send_queue_limit,
         }
     }
 }
@@ -235,9 +260,14 @@ struct RealClientConnection {
     /// Limits the amount of data queued in the `recv` channel. Each permit
     /// represents one byte.
     recv_sem: Arc<Semaphore>,
+    /// The number of bytes read from the stream by the reader task.
+    bytes_received: Arc<AtomicU64>,
+    received_packets: u64,
+    received_uncompressed_bytes: u64,
     _cleanup: CleanupOnDrop,
     reader_task: JoinHandle<()>,
-    writer_task: JoinHandle<()>,
+    writer_task: Option<JoinHandle<()>>,
+    runtime: Handle,
 }
 
 impl ClientConnection for RealClientConnection {
@@ -260,6 +290,10 @@ impl ClientConnection for RealClientConnection {
                 // Add the permits back that we removed eariler.
                 self.recv_sem.add_permits(cost);
 
+                self.received_packets += 1;
+                self.received_uncompressed_bytes +=
+                    (VarInt(packet.id).written_size() + packet.data.len()) as u64;
+
                 Ok(Some(packet))
             }
             Err(flume::TryRecvError::Empty) => Ok(None),
@@ -270,11 +304,32 @@ impl ClientConnection for RealClientConnection {
     fn len(&self) -> usize {
         self.recv.len()
     }
+
+    fn received_stats(&self) -> ReceivedStats {
+        ReceivedStats {
+            bytes: self.bytes_received.load(Ordering::Relaxed),
+            uncompressed_bytes: self.received_uncompressed_bytes,
+            packets: self.received_packets,
+        }
+    }
+
+    fn send_queue_len(&self) -> usize {
+        self.send.len()
+    }
 }
 
 impl Drop for RealClientConnection {
     fn drop(&mut self) {
-        self.writer_task.abort();
         self.reader_task.abort();
+
+        // Give the writer some time to send the remaining packets, such as a
+        // disconnect message.
+        if let Some(mut writer_task) = self.writer_task.take() {
+            self.runtime.spawn(async move {
+                if timeout(FLUSH_TIMEOUT, &mut writer_task).await.is_err() {
+                    writer_task.abort();
+                }
+            });
+        }
     }
 }
@@ -0,0 +1,351 @@
+//! Reads the [PROXY protocol] header sent by load balancers before the
+//! handshake, which contains the address of the client.
+//!
+//! [PROXY protocol]: https://www.haproxy.org/download/2.8/doc/proxy-protocol.txt
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout};
+
+use crate::ProxyProtocolMode;
+
+const V1_SIGNATURE: &[u8] = b"PROXY ";
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// The longest possible version 1 header, including the line break.
+const V1_MAX_LEN: usize = 107;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Detected {
+    V1,
+    V2,
+    /// The first bytes are the beginning of a signature, but more bytes are
+    /// needed to tell.
+    Incomplete,
+    None,
+}
+
+/// Detects a header from the first bytes received on a connection. Neither
+/// signature can be the beginning of a handshake or legacy ping.
+fn detect(first_bytes: &[u8]) -> Detected {
+    if first_bytes.starts_with(V1_SIGNATURE) {
+        Detected::V1
+    } else if first_bytes.starts_with(V2_SIGNATURE) {
+        Detected::V2
+    } else if V1_SIGNATURE.starts_with(first_bytes) || V2_SIGNATURE.starts_with(first_bytes) {
+        Detected::Incomplete
+    } else {
+        Detected::None
+    }
+}
+
+/// Reads the PROXY protocol header at the start of the stream according to
+/// `mode`. Returns the address of the client, which is `remote_addr` if there
+/// is no header or it does not contain an address.
+pub(crate) async fn read_proxy_header(
+    mode: ProxyProtocolMode,
+    stream: &mut TcpStream,
+    remote_addr: SocketAddr,
+) -> anyhow::Result<SocketAddr> {
+    if mode == ProxyProtocolMode::Disabled {
+        return Ok(remote_addr);
+    }
+
+    let addr = timeout(Duration::from_secs(5), async {
+        let mut first_bytes = [0; V2_SIGNATURE.len()];
+
+        let detected = loop {
+            let len = stream.peek(&mut first_bytes).await?;
+
+            if len == 0 {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+
+            match detect(&first_bytes[..len]) {
+                // Wait for the rest of the signature to arrive.
+                Detected::Incomplete => sleep(Duration::from_millis(10)).await,
+                detected => break detected,
+            }
+        };
+
+        match detected {
+            Detected::V1 => read_v1(&mut *stream).await,
+            Detected::V2 => read_v2(&mut *stream).await,
+            _ if mode == ProxyProtocolMode::Require => {
+                bail!("missing PROXY protocol header from {remote_addr}")
+            }
+            _ => Ok(None),
+        }
+    })
+    .await
+    .context("timed out reading PROXY protocol header")??;
+
+    Ok(addr.unwrap_or(remote_addr))
+}
+
+/// Reads a human-readable version 1 header, without reading past its end.
+async fn read_v1(mut r: impl AsyncRead + Unpin) -> anyhow::Result<Option<SocketAddr>> {
+    let mut line = vec![];
+
+    while !line.ends_with(b"\r\n") {
+        ensure!(line.len() < V1_MAX_LEN, "PROXY protocol header is too long");
+        line.push(r.read_u8().await?);
+    }
+
+    let line = std::str::from_utf8(&line[..line.len() - 2])
+        .context("PROXY protocol header is not valid UTF-8")?;
+
+    parse_v1(line)
+}
+
+fn parse_v1(line: &str) -> anyhow::Result<Option<SocketAddr>> {
+    let mut fields = line.split(' ');
+
+    ensure!(
+        fields.next() == Some("PROXY"),
+        "malformed PROXY protocol header"
+    );
+
+    let is_ipv4 = match fields.next() {
+        Some("TCP4") => true,
+        Some("TCP6") => false,
+        // The connection was not proxied, or the protocol is not supported
+        // by the proxy.
+        Some("UNKNOWN") => return Ok(None),
+        _ => bail!("unknown PROXY protocol header protocol"),
+    };
+
+    let [src_addr, _dst_addr, src_port, _dst_port]: [&str; 4] = fields
+        .collect::<Vec<_>>()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("malformed PROXY protocol header addresses"))?;
+
+    let ip: IpAddr = src_addr
+        .parse()
+        .context("malformed PROXY protocol header source address")?;
+
+    ensure!(
+        ip.is_ipv4() == is_ipv4,
+        "mismatched PROXY protocol header address family"
+    );
+
+    let port: u16 = src_port
+        .parse()
+        .context("malformed PROXY protocol header source port")?;
+
+    Ok(Some(SocketAddr::new(ip, port)))
+}
+
+/// Reads a binary version 2 header, without reading past its end.
+async fn read_v2(mut r: impl AsyncRead + Unpin) -> anyhow::Result<Option<SocketAddr>> {
+    let mut header = [0; 16];
+    r.read_exact(&mut header).await?;
+
+    ensure!(
+        &header[..12] == V2_SIGNATURE,
+        "invalid PROXY protocol header signature"
+    );
+
+    let version_command = header[12];
+    let family = header[13];
+    let len = u16::from_be_bytes([header[14], header[15]]) as usize;
+
+    let mut payload = vec![0; len];
+    r.read_exact(&mut payload).await?;
+
+    parse_v2(version_command, family, &payload)
+}
+
+fn parse_v2(version_command: u8, family: u8, payload: &[u8]) -> anyhow::Result<Option<SocketAddr>> {
+    ensure!(
+        version_command >> 4 == 2,
+        "unsupported PROXY protocol version {}",
+        version_command >> 4
+    );
+
+    match version_command & 0xf {
+        // The connection was made by the proxy itself, like for health checks.
+        0 => return Ok(None),
+        1 => {}
+        command => bail!("unknown PROXY protocol command {command}"),
+    }
+
+    // The rest of the payload contains the destination address and optional
+    // extensions, which are ignored.
+    match family >> 4 {
+        // IPv4
+        1 => {
+            ensure!(payload.len() >= 12, "truncated PROXY protocol addresses");
+
+            let ip = Ipv4Addr::from(<[u8; 4]>::try_from(&payload[..4]).unwrap());
+            let port = u16::from_be_bytes([payload[8], payload[9]]);
+
+            Ok(Some(SocketAddr::new(ip.into(), port)))
+        }
+        // IPv6
+        2 => {
+            ensure!(payload.len() >= 36, "truncated PROXY protocol addresses");
+
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(&payload[..16]).unwrap());
+            let port = u16::from_be_bytes([payload[32], payload[33]]);
+
+            Ok(Some(SocketAddr::new(ip.into(), port)))
+        }
+        // Unspecified or Unix socket addresses.
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Sends `bytes` over a local connection and reads the header on the other
+    /// end. Returns the address read and the next byte of the stream.
+    async fn read_over_socket(
+        mode: ProxyProtocolMode,
+        bytes: &'static [u8],
+    ) -> (anyhow::Result<SocketAddr>, SocketAddr, Option<u8>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(bytes).await.unwrap();
+            stream
+        });
+
+        let (mut stream, remote_addr) = listener.accept().await.unwrap();
+        let res = read_proxy_header(mode, &mut stream, remote_addr).await;
+        let _client = client.await.unwrap();
+
+        let next = if res.is_ok() {
+            Some(stream.read_u8().await.unwrap())
+        } else {
+            None
+        };
+
+        (res, remote_addr, next)
+    }
+
+    #[tokio::test]
+    async fn proxy_header_modes() {
+        const HEADER: &[u8] = b"PROXY TCP4 203.0.113.7 192.0.2.1 56324 25565\r\n\x10";
+        let client_addr = "203.0.113.7:56324".parse().unwrap();
+
+        for mode in [ProxyProtocolMode::Accept, ProxyProtocolMode::Require] {
+            let (res, _, next) = read_over_socket(mode, HEADER).await;
+            assert_eq!(res.unwrap(), client_addr);
+            assert_eq!(next, Some(0x10));
+        }
+
+        // The header is not read when disabled.
+        let (res, remote_addr, next) = read_over_socket(ProxyProtocolMode::Disabled, HEADER).await;
+        assert_eq!(res.unwrap(), remote_addr);
+        assert_eq!(next, Some(b'P'));
+
+        let (res, remote_addr, next) = read_over_socket(ProxyProtocolMode::Accept, b"\x10").await;
+        assert_eq!(res.unwrap(), remote_addr);
+        assert_eq!(next, Some(0x10));
+
+        let (res, _, _) = read_over_socket(ProxyProtocolMode::Require, b"\x10").await;
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn headers_detected() {
+        assert_eq!(detect(b"PROXY TCP4"), Detected::V1);
+        assert_eq!(detect(V2_SIGNATURE), Detected::V2);
+        assert_eq!(detect(b"PRO"), Detected::Incomplete);
+        assert_eq!(detect(b"\r\n\r\n"), Detected::Incomplete);
+        // A handshake with a length of 16.
+        assert_eq!(detect(&[0x10, 0x00, 0xfb, 0x05]), Detected::None);
+        // A handshake with a length of 80, which begins with the same byte as
+        // the version 1 signature.
+        assert_eq!(detect(&[b'P', 0x00, 0xfb, 0x05]), Detected::None);
+        // A legacy ping.
+        assert_eq!(detect(&[0xfe, 0x01]), Detected::None);
+    }
+
+    #[tokio::test]
+    async fn v1_header_read() {
+        let mut r: &[u8] = b"PROXY TCP4 203.0.113.7 192.0.2.1 56324 25565\r\n\x10\x00";
+
+        assert_eq!(
+            read_v1(&mut r).await.unwrap(),
+            Some("203.0.113.7:56324".parse().unwrap())
+        );
+        // Nothing after the header is read.
+        assert_eq!(r, b"\x10\x00");
+
+        let mut r: &[u8] = b"PROXY TCP6 2001:db8::7 2001:db8::1 56324 25565\r\n";
+
+        assert_eq!(
+            read_v1(&mut r).await.unwrap(),
+            Some("[2001:db8::7]:56324".parse().unwrap())
+        );
+
+        let mut r: &[u8] = b"PROXY UNKNOWN\r\n";
+        assert_eq!(read_v1(&mut r).await.unwrap(), None);
+
+        for malformed in [
+            &b"PROXY TCP4 203.0.113.7 192.0.2.1 56324\r\n"[..],
+            b"PROXY TCP4 2001:db8::7 2001:db8::1 56324 25565\r\n",
+            b"PROXY TCP4 203.0.113.7 192.0.2.1 99999 25565\r\n",
+            b"PROXY UDP4 203.0.113.7 192.0.2.1 56324 25565\r\n",
+            b"PROXY TCP4 203.0.113.7 192.0.2.1 56324 25565",
+            &[b'P'; 200],
+        ] {
+            assert!(read_v1(malformed).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn v2_header_read() {
+        // A client at 203.0.113.7:56324 connected to 192.0.2.1:25565, with a
+        // trailing no-op extension.
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend([0x21, 0x11, 0x00, 0x10]);
+        header.extend([203, 0, 113, 7, 192, 0, 2, 1, 0xdc, 0x04, 0x63, 0xdd]);
+        header.extend([0x04, 0x00, 0x01, 0x00]);
+        header.extend([0x10, 0x00]);
+
+        let mut r = header.as_slice();
+
+        assert_eq!(
+            read_v2(&mut r).await.unwrap(),
+            Some("203.0.113.7:56324".parse().unwrap())
+        );
+        assert_eq!(r, [0x10, 0x00]);
+
+        let mut ipv6 = [0; 36];
+        ipv6[..16].copy_from_slice(&"2001:db8::7".parse::<Ipv6Addr>().unwrap().octets());
+        ipv6[32..34].copy_from_slice(&56324_u16.to_be_bytes());
+
+        assert_eq!(
+            parse_v2(0x21, 0x21, &ipv6).unwrap(),
+            Some("[2001:db8::7]:56324".parse().unwrap())
+        );
+
+        // Health checks from the proxy itself.
+        assert_eq!(parse_v2(0x20, 0x00, &[]).unwrap(), None);
+        // Unix socket addresses.
+        assert_eq!(parse_v2(0x21, 0x31, &[0; 216]).unwrap(), None);
+
+        // Unsupported version, unknown command and truncated addresses.
+        assert!(parse_v2(0x11, 0x11, &[0; 12]).is_err());
+        assert!(parse_v2(0x22, 0x11, &[0; 12]).is_err());
+        assert!(parse_v2(0x21, 0x11, &[0; 8]).is_err());
+        assert!(parse_v2(0x21, 0x21, &[0; 12]).is_err());
+
+        // The payload is shorter than its length.
+        assert!(read_v2(&header[..20]).await.is_err());
+    }
+}
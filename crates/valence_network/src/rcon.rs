@@ -0,0 +1,476 @@
+//! A server for the [RCON] protocol, which allows executing commands
+//! remotely.
+//!
+//! [RCON]: https://developer.valvesoftware.com/wiki/Source_RCON_Protocol
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use tracing::{debug, error, info, warn};
+
+use crate::SharedNetworkState;
+
+/// Settings for the RCON server. See [`NetworkSettings::rcon`].
+///
+/// [`NetworkSettings::rcon`]: crate::NetworkSettings::rcon
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RconSettings {
+    /// The socket address the RCON server will be bound to.
+    pub address: SocketAddr,
+    /// The password clients must send before executing commands. The RCON
+    /// server is not started if this is empty.
+    ///
+    /// **NOTE:** The password is sent in plain text. RCON should only be
+    /// reachable from trusted networks.
+    pub password: String,
+    /// The time after which connections are closed if no complete packet is
+    /// received.
+    pub timeout: Duration,
+}
+
+/// The packet type sent by the client to authenticate.
+const SERVERDATA_AUTH: i32 = 3;
+/// The packet type sent by the server in response to [`SERVERDATA_AUTH`].
+const SERVERDATA_AUTH_RESPONSE: i32 = 2;
+/// The packet type sent by the client to execute a command. This is the same
+/// as [`SERVERDATA_AUTH_RESPONSE`].
+const SERVERDATA_EXECCOMMAND: i32 = 2;
+/// The packet type of the output of commands.
+const SERVERDATA_RESPONSE_VALUE: i32 = 0;
+
+/// The request ID sent in the response to a failed authentication.
+const AUTH_FAILURE_ID: i32 = -1;
+
+/// The maximum length of the body of packets sent by clients, which is the
+/// same as in vanilla.
+const MAX_INCOMING_BODY_LEN: usize = 1446;
+/// The maximum length of the body of packets sent by the server. Longer
+/// responses are split over several packets.
+const MAX_OUTGOING_BODY_LEN: usize = 4096;
+/// The length of the fields of a packet other than the body: the request ID,
+/// the type and two nul bytes.
+const PACKET_OVERHEAD: usize = 10;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct RconPacket {
+    request_id: i32,
+    kind: i32,
+    body: String,
+}
+
+impl RconPacket {
+    /// Reads a packet with a body of at most `max_body_len` bytes from the
+    /// stream, or returns `None` if the stream ended between packets.
+    async fn read(
+        mut r: impl AsyncRead + Unpin,
+        max_body_len: usize,
+    ) -> anyhow::Result<Option<Self>> {
+        let len = match r.read_i32_le().await {
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        ensure!(
+            (PACKET_OVERHEAD as i32..=(max_body_len + PACKET_OVERHEAD) as i32).contains(&len),
+            "invalid RCON packet length of {len}"
+        );
+
+        let request_id = r.read_i32_le().await?;
+        let kind = r.read_i32_le().await?;
+
+        let mut body = vec![0; len as usize - 8];
+        r.read_exact(&mut body).await?;
+
+        ensure!(
+            body.ends_with(&[0, 0]),
+            "RCON packet is not terminated by two nul bytes"
+        );
+        body.truncate(body.len() - 2);
+
+        Ok(Some(Self {
+            request_id,
+            kind,
+            body: String::from_utf8(body).context("RCON packet body is not valid UTF-8")?,
+        }))
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        let len = self.body.len() + PACKET_OVERHEAD;
+
+        buf.extend_from_slice(&(len as i32).to_le_bytes());
+        buf.extend_from_slice(&self.request_id.to_le_bytes());
+        buf.extend_from_slice(&self.kind.to_le_bytes());
+        buf.extend_from_slice(self.body.as_bytes());
+        buf.extend_from_slice(&[0, 0]);
+    }
+}
+
+/// Encodes the output of a command, split over as many packets as needed.
+/// Clients which need to know where long responses end can send an empty
+/// [`SERVERDATA_RESPONSE_VALUE`] packet after the command, which is sent back
+/// once the output was sent.
+fn encode_response(request_id: i32, mut output: &str) -> Vec<u8> {
+    let mut buf = vec![];
+
+    loop {
+        let mut split = output.len().min(MAX_OUTGOING_BODY_LEN);
+        while !output.is_char_boundary(split) {
+            split -= 1;
+        }
+
+        let (body, rest) = output.split_at(split);
+
+        RconPacket {
+            request_id,
+            kind: SERVERDATA_RESPONSE_VALUE,
+            body: body.to_owned(),
+        }
+        .encode(&mut buf);
+
+        if rest.is_empty() {
+            break buf;
+        }
+
+        output = rest;
+    }
+}
+
+/// Accepts new RCON connections as they occur.
+pub(super) async fn do_rcon_accept_loop(shared: SharedNetworkState, settings: RconSettings) {
+    if settings.password.is_empty() {
+        error!("not starting RCON server because the password is empty");
+        return;
+    }
+
+    let listener = match TcpListener::bind(settings.address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("failed to start RCON listener: {e}");
+            return;
+        }
+    };
+
+    info!("RCON server listening on {}", settings.address);
+
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = shared.shutdown_reason() => return,
+        };
+
+        match accepted {
+            Ok((stream, remote_addr)) => {
+                let shared = shared.clone();
+                let settings = settings.clone();
+
+                tokio::spawn(async move {
+                    handle_rcon_connection(shared, settings, stream, remote_addr).await;
+                });
+            }
+            Err(e) => {
+                error!("failed to accept incoming RCON connection: {e}");
+            }
+        }
+    }
+}
+
+async fn handle_rcon_connection(
+    shared: SharedNetworkState,
+    settings: RconSettings,
+    stream: TcpStream,
+    remote_addr: SocketAddr,
+) {
+    let shared = &shared;
+
+    let res = serve_rcon(stream, &settings, |command| async move {
+        shared
+            .0
+            .callbacks
+            .inner
+            .rcon_command(shared, remote_addr, &command)
+            .await
+            .to_string()
+    })
+    .await;
+
+    if let Err(e) = res {
+        warn!("RCON connection from {remote_addr} ended with error: {e:#}");
+    }
+}
+
+/// Handles the packets of an RCON connection until it is closed, executing
+/// commands with `execute`.
+async fn serve_rcon<S, F, Fut>(
+    mut stream: S,
+    settings: &RconSettings,
+    mut execute: F,
+) -> anyhow::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = String>,
+{
+    let mut authenticated = false;
+
+    loop {
+        let Some(packet) = timeout(
+            settings.timeout,
+            RconPacket::read(&mut stream, MAX_INCOMING_BODY_LEN),
+        )
+        .await
+        .context("RCON connection timed out")??
+        else {
+            return Ok(());
+        };
+
+        let response = match packet.kind {
+            SERVERDATA_AUTH => {
+                if !password_matches(&packet.body, &settings.password) {
+                    debug!("RCON authentication failed");
+                    send_auth_failure(&mut stream).await?;
+                    return Ok(());
+                }
+
+                authenticated = true;
+
+                let mut buf = vec![];
+                RconPacket {
+                    request_id: packet.request_id,
+                    kind: SERVERDATA_AUTH_RESPONSE,
+                    body: String::new(),
+                }
+                .encode(&mut buf);
+                buf
+            }
+            _ if !authenticated => {
+                send_auth_failure(&mut stream).await?;
+                bail!("RCON client sent a packet before authenticating");
+            }
+            SERVERDATA_EXECCOMMAND => {
+                debug!("executing RCON command \"{}\"", packet.body);
+
+                let output = execute(packet.body).await;
+                encode_response(packet.request_id, &output)
+            }
+            SERVERDATA_RESPONSE_VALUE => encode_response(packet.request_id, ""),
+            kind => encode_response(packet.request_id, &format!("Unknown request {kind:x}")),
+        };
+
+        timeout(settings.timeout, stream.write_all(&response))
+            .await
+            .context("RCON connection timed out")??;
+    }
+}
+
+async fn send_auth_failure(mut w: impl AsyncWrite + Unpin) -> anyhow::Result<()> {
+    let mut buf = vec![];
+    RconPacket {
+        request_id: AUTH_FAILURE_ID,
+        kind: SERVERDATA_AUTH_RESPONSE,
+        body: String::new(),
+    }
+    .encode(&mut buf);
+
+    w.write_all(&buf).await?;
+    w.flush().await?;
+
+    Ok(())
+}
+
+/// Compares the passwords in constant time, to avoid leaking the length of
+/// the correct prefix.
+fn password_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{duplex, DuplexStream};
+
+    use super::*;
+
+    const PASSWORD: &str = "hunter2";
+
+    fn settings() -> RconSettings {
+        RconSettings {
+            address: "127.0.0.1:0".parse().unwrap(),
+            password: PASSWORD.into(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Starts serving RCON on one end of a pipe, with commands echoed back
+    /// repeated a number of times given after the command.
+    fn start_server() -> DuplexStream {
+        let (client, server) = duplex(64 * 1024);
+
+        tokio::spawn(async move {
+            let _ = serve_rcon(server, &settings(), |command| async move {
+                match command.split_once(' ') {
+                    Some((command, count)) => command.repeat(count.parse().unwrap()),
+                    None => command,
+                }
+            })
+            .await;
+        });
+
+        client
+    }
+
+    async fn send(client: &mut DuplexStream, request_id: i32, kind: i32, body: &str) {
+        let mut buf = vec![];
+        RconPacket {
+            request_id,
+            kind,
+            body: body.into(),
+        }
+        .encode(&mut buf);
+
+        client.write_all(&buf).await.unwrap();
+    }
+
+    async fn recv(client: &mut DuplexStream) -> Option<RconPacket> {
+        RconPacket::read(client, MAX_OUTGOING_BODY_LEN)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rcon_commands_executed() {
+        let mut client = start_server();
+
+        send(&mut client, 7, SERVERDATA_AUTH, PASSWORD).await;
+        assert_eq!(
+            recv(&mut client).await,
+            Some(RconPacket {
+                request_id: 7,
+                kind: SERVERDATA_AUTH_RESPONSE,
+                body: String::new(),
+            })
+        );
+
+        send(&mut client, 8, SERVERDATA_EXECCOMMAND, "list").await;
+        assert_eq!(
+            recv(&mut client).await,
+            Some(RconPacket {
+                request_id: 8,
+                kind: SERVERDATA_RESPONSE_VALUE,
+                body: "list".into(),
+            })
+        );
+
+        // Long output is split, followed by the response to the empty packet
+        // marking the end.
+        send(&mut client, 9, SERVERDATA_EXECCOMMAND, "abc 2000").await;
+        send(&mut client, 10, SERVERDATA_RESPONSE_VALUE, "").await;
+
+        let mut output = String::new();
+        let mut packets = 0;
+
+        loop {
+            let packet = recv(&mut client).await.unwrap();
+            assert_eq!(packet.kind, SERVERDATA_RESPONSE_VALUE);
+
+            if packet.request_id == 10 {
+                assert!(packet.body.is_empty());
+                break;
+            }
+
+            assert_eq!(packet.request_id, 9);
+            assert!(packet.body.len() <= MAX_OUTGOING_BODY_LEN);
+            output += &packet.body;
+            packets += 1;
+        }
+
+        assert_eq!(output, "abc".repeat(2000));
+        assert_eq!(packets, 2);
+    }
+
+    #[tokio::test]
+    async fn rcon_auth_failure_closes_connection() {
+        let mut client = start_server();
+
+        send(&mut client, 7, SERVERDATA_AUTH, "hunter3").await;
+        assert_eq!(
+            recv(&mut client).await,
+            Some(RconPacket {
+                request_id: AUTH_FAILURE_ID,
+                kind: SERVERDATA_AUTH_RESPONSE,
+                body: String::new(),
+            })
+        );
+        assert_eq!(recv(&mut client).await, None);
+
+        // Commands are not executed before authenticating.
+        let mut client = start_server();
+
+        send(&mut client, 7, SERVERDATA_EXECCOMMAND, "stop").await;
+        assert_eq!(recv(&mut client).await.unwrap().request_id, AUTH_FAILURE_ID);
+        assert_eq!(recv(&mut client).await, None);
+    }
+
+    #[tokio::test]
+    async fn rcon_oversized_packet_rejected() {
+        let (mut client, server) = duplex(64 * 1024);
+
+        let body = "a".repeat(MAX_INCOMING_BODY_LEN + 1);
+        send(&mut client, 7, SERVERDATA_AUTH, &body).await;
+
+        assert!(
+            serve_rcon(server, &settings(), |command| async move { command })
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn rcon_idle_connection_timed_out() {
+        let (_client, server) = duplex(64 * 1024);
+
+        let settings = RconSettings {
+            timeout: Duration::from_millis(50),
+            ..settings()
+        };
+
+        assert!(
+            serve_rcon(server, &settings, |command| async move { command })
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rcon_response_split_on_char_boundary() {
+        // The two-byte characters start at odd indices.
+        let output = format!("a{}", "é".repeat(MAX_OUTGOING_BODY_LEN));
+        let buf = encode_response(1, &output);
+
+        let mut bodies = vec![];
+        let mut rest = buf.as_slice();
+
+        while !rest.is_empty() {
+            let len = i32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+            let body = &rest[12..len + 2];
+            bodies.push(std::str::from_utf8(body).unwrap());
+            rest = &rest[len + 4..];
+        }
+
+        assert_eq!(
+            bodies.iter().map(|body| body.len()).collect::<Vec<_>>(),
+            [MAX_OUTGOING_BODY_LEN - 1, MAX_OUTGOING_BODY_LEN, 2]
+        );
+        assert_eq!(bodies.concat(), output);
+    }
+}
@@ -0,0 +1,65 @@
+//! Shuts down the server gracefully, disconnecting clients with a reason.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bevy_app::AppExit;
+use bevy_ecs::prelude::*;
+use tracing::{info, warn};
+use valence_client::{Client, DisconnectClient};
+use valence_core::text::Text;
+
+use crate::SharedNetworkState;
+
+/// An event which shuts down the server gracefully. New connections are no
+/// longer accepted, clients logging in or playing are disconnected with
+/// `reason`, and the app exits once all the remaining packets were sent to
+/// the clients, or after `timeout`.
+///
+/// Only the first event has an effect.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ShutdownServer {
+    /// The reason displayed to the disconnected clients.
+    pub reason: Text,
+    /// The maximum time to wait for the connections to be closed.
+    pub timeout: Duration,
+}
+
+pub(crate) fn shutdown_server(
+    mut events: EventReader<ShutdownServer>,
+    shared: Res<SharedNetworkState>,
+    clients: Query<Entity, With<Client>>,
+    mut commands: Commands,
+    mut deadline: Local<Option<Instant>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(deadline) = *deadline else {
+        if let Some(event) = events.iter().next() {
+            info!("shutting down the server");
+
+            // Stops the listeners and the logins in progress.
+            shared.0.shutdown.send_replace(Some(event.reason.clone()));
+
+            for client in &clients {
+                commands.add(DisconnectClient {
+                    client,
+                    reason: event.reason.clone(),
+                });
+            }
+
+            *deadline = Some(Instant::now() + event.timeout);
+        }
+
+        return;
+    };
+
+    events.clear();
+
+    // The writer tasks of the connections each hold a clone of the token.
+    if Arc::strong_count(&shared.0.writer_token) == 1 {
+        exit.send(AppExit);
+    } else if Instant::now() >= deadline {
+        warn!("timed out waiting for connections to close");
+        exit.send(AppExit);
+    }
+}
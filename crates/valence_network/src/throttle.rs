@@ -0,0 +1,389 @@
+//! Limits the number of connections to the server, before the handshake is
+//! read.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Instant;
+
+use thiserror::Error;
+
+use crate::SharedNetworkState;
+
+/// Limits on the connections to the server before the play state, where the
+/// client is spawned into the world. Connections exceeding a limit are closed
+/// immediately.
+///
+/// The limits can be changed at runtime with
+/// [`SharedNetworkState::set_connection_limits`].
+///
+/// [`SharedNetworkState::set_connection_limits`]: crate::SharedNetworkState::set_connection_limits
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ConnectionLimits {
+    /// The maximum number of simultaneous connections. Connections over the
+    /// limit are still accepted from the listener, but are closed right
+    /// away.
+    ///
+    /// # Default Value
+    ///
+    /// The default value is left unspecified and may change in future versions.
+    pub max_connections: usize,
+    /// The maximum number of simultaneous connections from one IP address.
+    /// When the [PROXY protocol] is used, this is the address of the client
+    /// and not the load balancer.
+    ///
+    /// Behind a BungeeCord or Velocity proxy without the PROXY protocol,
+    /// every player connects from the address of the proxy, so this should be
+    /// left unlimited.
+    ///
+    /// # Default Value
+    ///
+    /// `usize::MAX` (no limit)
+    ///
+    /// [PROXY protocol]: crate::ProxyProtocolMode
+    pub max_connections_per_ip: usize,
+    /// Limits how quickly one IP address can open new connections, or `None`
+    /// for no limit. Like [`Self::max_connections_per_ip`], this should not be
+    /// set behind a proxy which hides the addresses of the clients.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub rate_limit_per_ip: Option<ConnectionRateLimit>,
+    /// Whether clients exceeding a limit while logging in are sent a
+    /// disconnect message instead of having the connection closed
+    /// immediately. This requires reading the handshake of the rejected
+    /// connections.
+    ///
+    /// # Default Value
+    ///
+    /// `false`
+    pub send_disconnect_message: bool,
+}
+
+impl ConnectionLimits {
+    /// Limits which never close a connection, for testing with many clients
+    /// on the same machine.
+    pub const UNLIMITED: Self = Self {
+        max_connections: usize::MAX,
+        max_connections_per_ip: usize::MAX,
+        rate_limit_per_ip: None,
+        send_disconnect_message: false,
+    };
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            max_connections: 1024,
+            // Per-IP limits would reject players arriving through a proxy.
+            max_connections_per_ip: usize::MAX,
+            rate_limit_per_ip: None,
+            send_disconnect_message: false,
+        }
+    }
+}
+
+/// A token bucket limiting the rate of new connections. See
+/// [`ConnectionLimits::rate_limit_per_ip`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ConnectionRateLimit {
+    /// The number of new connections allowed per second on average.
+    pub per_second: f64,
+    /// The number of new connections allowed at once after no connections
+    /// were made for a while.
+    pub burst: u32,
+}
+
+/// Counters of the connections handled by the server. See
+/// [`SharedNetworkState::connection_stats`].
+///
+/// [`SharedNetworkState::connection_stats`]: crate::SharedNetworkState::connection_stats
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ConnectionStats {
+    /// The number of connections currently open before the play state.
+    pub open_connections: usize,
+    /// The total number of connections accepted.
+    pub accepted_connections: u64,
+    /// The total number of connections closed because of
+    /// [`ConnectionLimits::max_connections`].
+    pub rejected_max_connections: u64,
+    /// The total number of connections closed because of
+    /// [`ConnectionLimits::max_connections_per_ip`].
+    pub rejected_max_connections_per_ip: u64,
+    /// The total number of connections closed because of
+    /// [`ConnectionLimits::rate_limit_per_ip`].
+    pub rejected_rate_limit: u64,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Error)]
+pub(crate) enum ThrottleError {
+    #[error("too many connections to the server")]
+    MaxConnections,
+    #[error("too many connections from the same IP address")]
+    MaxConnectionsPerIp,
+    #[error("new connections from the same IP address are too frequent")]
+    RateLimit,
+}
+
+/// Tracks the open connections and the rate limits of each IP address.
+#[derive(Debug)]
+pub(crate) struct ConnectionThrottle {
+    limits: ConnectionLimits,
+    stats: ConnectionStats,
+    ips: HashMap<IpAddr, IpState>,
+    /// The number of tracked IP addresses at which unused entries are removed
+    /// next.
+    prune_at: usize,
+}
+
+#[derive(Debug)]
+struct IpState {
+    open_connections: usize,
+    /// The number of tokens left in the bucket of the rate limit.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+const MIN_PRUNE_AT: usize = 1024;
+
+impl ConnectionThrottle {
+    pub(crate) fn new(limits: ConnectionLimits) -> Self {
+        Self {
+            limits,
+            stats: ConnectionStats::default(),
+            ips: HashMap::new(),
+            prune_at: MIN_PRUNE_AT,
+        }
+    }
+
+    pub(crate) fn limits(&self) -> ConnectionLimits {
+        self.limits
+    }
+
+    pub(crate) fn set_limits(&mut self, limits: ConnectionLimits) {
+        self.limits = limits;
+    }
+
+    pub(crate) fn stats(&self) -> ConnectionStats {
+        self.stats
+    }
+
+    /// Checks the limits for a new connection from `ip`, and counts it as
+    /// open if none are exceeded. Every successful call must be followed by a
+    /// call to [`Self::close`] once the connection is closed.
+    pub(crate) fn open(&mut self, ip: IpAddr, now: Instant) -> Result<(), ThrottleError> {
+        if self.ips.len() >= self.prune_at {
+            self.prune(now);
+        }
+
+        if self.stats.open_connections >= self.limits.max_connections {
+            self.stats.rejected_max_connections += 1;
+            return Err(ThrottleError::MaxConnections);
+        }
+
+        let state = self.ips.entry(ip).or_insert(IpState {
+            open_connections: 0,
+            tokens: f64::INFINITY,
+            last_refill: now,
+        });
+
+        if state.open_connections >= self.limits.max_connections_per_ip {
+            self.stats.rejected_max_connections_per_ip += 1;
+            return Err(ThrottleError::MaxConnectionsPerIp);
+        }
+
+        if let Some(rate_limit) = self.limits.rate_limit_per_ip {
+            state.refill(rate_limit, now);
+
+            if state.tokens < 1.0 {
+                self.stats.rejected_rate_limit += 1;
+                return Err(ThrottleError::RateLimit);
+            }
+
+            state.tokens -= 1.0;
+        }
+
+        state.open_connections += 1;
+        self.stats.open_connections += 1;
+        self.stats.accepted_connections += 1;
+
+        Ok(())
+    }
+
+    /// Counts a connection from `ip` as closed.
+    pub(crate) fn close(&mut self, ip: IpAddr) {
+        if let Some(state) = self.ips.get_mut(&ip) {
+            state.open_connections = state.open_connections.saturating_sub(1);
+            self.stats.open_connections = self.stats.open_connections.saturating_sub(1);
+        }
+    }
+
+    /// Removes the IP addresses without open connections and with a full
+    /// bucket, which are the same as untracked addresses.
+    fn prune(&mut self, now: Instant) {
+        let rate_limit = self.limits.rate_limit_per_ip;
+
+        self.ips.retain(|_, state| {
+            if state.open_connections > 0 {
+                return true;
+            }
+
+            match rate_limit {
+                Some(rate_limit) => {
+                    state.refill(rate_limit, now);
+                    state.tokens < rate_limit.burst as f64
+                }
+                None => false,
+            }
+        });
+
+        self.prune_at = (self.ips.len() * 2).max(MIN_PRUNE_AT);
+    }
+}
+
+/// Counts a connection as open in the [`ConnectionThrottle`] of the server
+/// until dropped.
+pub(crate) struct ThrottleGuard {
+    shared: SharedNetworkState,
+    ip: IpAddr,
+}
+
+impl ThrottleGuard {
+    pub(crate) fn open(shared: &SharedNetworkState, ip: IpAddr) -> Result<Self, ThrottleError> {
+        shared.0.throttle.lock().unwrap().open(ip, Instant::now())?;
+
+        Ok(Self {
+            shared: shared.clone(),
+            ip,
+        })
+    }
+}
+
+impl Drop for ThrottleGuard {
+    fn drop(&mut self) {
+        self.shared.0.throttle.lock().unwrap().close(self.ip);
+    }
+}
+
+impl IpState {
+    fn refill(&mut self, rate_limit: ConnectionRateLimit, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * rate_limit.per_second).min(rate_limit.burst as f64);
+        self.last_refill = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    use super::*;
+
+    const A: IpAddr = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7));
+    const B: IpAddr = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 8));
+
+    #[test]
+    fn connection_limits() {
+        let mut throttle = ConnectionThrottle::new(ConnectionLimits {
+            max_connections: 3,
+            max_connections_per_ip: 2,
+            rate_limit_per_ip: None,
+            send_disconnect_message: false,
+        });
+
+        let now = Instant::now();
+
+        assert_eq!(throttle.open(A, now), Ok(()));
+        assert_eq!(throttle.open(A, now), Ok(()));
+        assert_eq!(
+            throttle.open(A, now),
+            Err(ThrottleError::MaxConnectionsPerIp)
+        );
+        assert_eq!(throttle.open(B, now), Ok(()));
+        assert_eq!(throttle.open(B, now), Err(ThrottleError::MaxConnections));
+
+        throttle.close(A);
+
+        assert_eq!(throttle.open(B, now), Ok(()));
+
+        assert_eq!(
+            throttle.stats(),
+            ConnectionStats {
+                open_connections: 3,
+                accepted_connections: 4,
+                rejected_max_connections: 1,
+                rejected_max_connections_per_ip: 1,
+                rejected_rate_limit: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn connection_rate_limit() {
+        let mut throttle = ConnectionThrottle::new(ConnectionLimits {
+            rate_limit_per_ip: Some(ConnectionRateLimit {
+                per_second: 2.0,
+                burst: 3,
+            }),
+            ..ConnectionLimits::UNLIMITED
+        });
+
+        let start = Instant::now();
+
+        // The burst is allowed at once.
+        for _ in 0..3 {
+            assert_eq!(throttle.open(A, start), Ok(()));
+        }
+        assert_eq!(throttle.open(A, start), Err(ThrottleError::RateLimit));
+
+        // Other addresses have their own bucket.
+        assert_eq!(throttle.open(B, start), Ok(()));
+
+        // One connection is allowed every half second.
+        let later = start + Duration::from_millis(600);
+        assert_eq!(throttle.open(A, later), Ok(()));
+        assert_eq!(throttle.open(A, later), Err(ThrottleError::RateLimit));
+
+        // The bucket never holds more than the burst.
+        let much_later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert_eq!(throttle.open(A, much_later), Ok(()));
+        }
+        assert_eq!(throttle.open(A, much_later), Err(ThrottleError::RateLimit));
+
+        assert_eq!(throttle.stats().rejected_rate_limit, 3);
+    }
+
+    #[test]
+    fn unused_addresses_pruned() {
+        let mut throttle = ConnectionThrottle::new(ConnectionLimits {
+            rate_limit_per_ip: Some(ConnectionRateLimit {
+                per_second: 1.0,
+                burst: 1,
+            }),
+            ..ConnectionLimits::UNLIMITED
+        });
+
+        let start = Instant::now();
+
+        for i in 0..MIN_PRUNE_AT as u32 {
+            let ip = IpAddr::V4(Ipv4Addr::from(i));
+            throttle.open(ip, start).unwrap();
+
+            // Addresses with open connections are kept.
+            if i > 0 {
+                throttle.close(ip);
+            }
+        }
+
+        // The buckets of the other addresses are full again.
+        throttle.open(A, start + Duration::from_secs(2)).unwrap();
+
+        assert_eq!(throttle.ips.len(), 2);
+        assert_eq!(throttle.stats().open_connections, 2);
+    }
+}
@@ -22,7 +22,7 @@ use std::borrow::Cow;
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use uuid::Uuid;
-use valence_client::{Client, Ping, Properties, Username};
+use valence_client::{broadcast_packet_cached, Client, Ping, Properties, Username};
 use valence_core::despawn::Despawned;
 use valence_core::game_mode::GameMode;
 use valence_core::packet::encode::{PacketWriter, WritePacket};
@@ -35,9 +35,10 @@ use valence_instance::WriteUpdatePacketsToInstancesSet;
 
 pub struct PlayerListPlugin;
 
+/// The [`SystemSet`] in [`CoreSet::PostUpdate`] where the player list is sent
+/// to clients.
 #[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
-
-struct PlayerListSet;
+pub struct PlayerListSet;
 
 impl Plugin for PlayerListPlugin {
     fn build(&self, app: &mut App) {
@@ -96,6 +97,8 @@ impl PlayerList {
         &self.footer
     }
 
+    /// Sets the text displayed above the player list of all clients,
+    /// including the ones which join later.
     pub fn set_header(&mut self, txt: impl Into<Text>) {
         let txt = txt.into();
 
@@ -106,6 +109,8 @@ impl PlayerList {
         self.header = txt;
     }
 
+    /// Sets the text displayed below the player list of all clients,
+    /// including the ones which join later.
     pub fn set_footer(&mut self, txt: impl Into<Text>) {
         let txt = txt.into();
 
@@ -141,11 +146,11 @@ pub struct PlayerListEntryBundle {
 pub struct PlayerListEntry;
 
 /// Displayed name for a player list entry. Appears as [`Username`] if `None`.
-#[derive(Component, Default, Debug)]
+#[derive(Component, Clone, PartialEq, Default, Debug)]
 pub struct DisplayName(pub Option<Text>);
 
 /// If a player list entry is visible. Defaults to `true`.
-#[derive(Component, Copy, Clone, Debug)]
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Listed(pub bool);
 
 impl Default for Listed {
@@ -206,45 +211,53 @@ fn init_player_list_for_clients(
         With<PlayerListEntry>,
     >,
 ) {
-    if player_list.manage_clients {
-        for mut client in &mut clients {
-            let actions = Actions::new()
-                .with_add_player(true)
-                .with_update_game_mode(true)
-                .with_update_listed(true)
-                .with_update_latency(true)
-                .with_update_display_name(true);
-
-            let entries: Vec<_> = entries
-                .iter()
-                .map(
-                    |(uuid, username, props, game_mode, ping, display_name, listed)| Entry {
-                        player_uuid: uuid.0,
-                        username: &username.0,
-                        properties: Cow::Borrowed(&props.0),
-                        chat_data: None,
-                        listed: listed.0,
-                        ping: ping.0,
-                        game_mode: *game_mode,
-                        display_name: display_name.0.as_ref().map(Cow::Borrowed),
-                    },
-                )
-                .collect();
+    if !player_list.manage_clients || clients.is_empty() {
+        return;
+    }
 
-            if !entries.is_empty() {
-                client.write_packet(&PlayerListS2c {
-                    actions,
-                    entries: Cow::Owned(entries),
-                });
-            }
+    let actions = Actions::new()
+        .with_add_player(true)
+        .with_update_game_mode(true)
+        .with_update_listed(true)
+        .with_update_latency(true)
+        .with_update_display_name(true);
+
+    let entries: Vec<_> = entries
+        .iter()
+        .map(
+            |(uuid, username, props, game_mode, ping, display_name, listed)| Entry {
+                player_uuid: uuid.0,
+                username: &username.0,
+                properties: Cow::Borrowed(&props.0),
+                chat_data: None,
+                listed: listed.0,
+                ping: ping.0,
+                game_mode: *game_mode,
+                display_name: display_name.0.as_ref().map(Cow::Borrowed),
+            },
+        )
+        .collect();
+
+    // The packets are the same for every new client, so they are only encoded
+    // once.
+    if !entries.is_empty() {
+        broadcast_packet_cached(
+            &PlayerListS2c {
+                actions,
+                entries: Cow::Owned(entries),
+            },
+            &mut clients,
+        );
+    }
 
-            if !player_list.header.is_empty() || !player_list.footer.is_empty() {
-                client.write_packet(&PlayerListHeaderS2c {
-                    header: Cow::Borrowed(&player_list.header),
-                    footer: Cow::Borrowed(&player_list.footer),
-                });
-            }
-        }
+    if !player_list.header.is_empty() || !player_list.footer.is_empty() {
+        broadcast_packet_cached(
+            &PlayerListHeaderS2c {
+                header: Cow::Borrowed(&player_list.header),
+                footer: Cow::Borrowed(&player_list.footer),
+            },
+            &mut clients,
+        );
     }
 }
 
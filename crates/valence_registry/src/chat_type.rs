@@ -0,0 +1,208 @@
+//! Chat types, which decide how clients decorate and narrate player chat
+//! messages.
+//!
+//! Custom chat types are added to the registry codec with
+//! [`RegistryCodec::add_chat_type`], which returns the [`ChatType`] to pass to
+//! `send_player_chat`.
+
+use valence_core::chat::ChatType;
+use valence_core::ident;
+use valence_core::ident::Ident;
+use valence_nbt::{compound, Compound, List};
+
+use crate::{RegistryCodec, RegistryError};
+
+/// The key of the chat type registry.
+pub const CHAT_TYPE_KEY: Ident<&str> = ident!("minecraft:chat_type");
+
+/// The chat types which clients expect to exist.
+const VANILLA_CHAT_TYPES: [Ident<&str>; 7] = [
+    ident!("minecraft:chat"),
+    ident!("minecraft:emote_command"),
+    ident!("minecraft:msg_command_incoming"),
+    ident!("minecraft:msg_command_outgoing"),
+    ident!("minecraft:say_command"),
+    ident!("minecraft:team_msg_command_incoming"),
+    ident!("minecraft:team_msg_command_outgoing"),
+];
+
+/// An entry of the chat type registry.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChatTypeEntry {
+    /// How messages are displayed in chat.
+    pub chat: ChatDecoration,
+    /// How messages are read by the narrator.
+    pub narration: ChatDecoration,
+}
+
+impl ChatTypeEntry {
+    /// Creates a chat type which is narrated like it is displayed.
+    pub fn new(chat: ChatDecoration) -> Self {
+        Self {
+            narration: chat.clone(),
+            chat,
+        }
+    }
+
+    pub fn with_narration(mut self, narration: ChatDecoration) -> Self {
+        self.narration = narration;
+        self
+    }
+
+    fn to_nbt(&self) -> Compound {
+        compound! {
+            "chat" => self.chat.to_nbt(),
+            "narration" => self.narration.to_nbt(),
+        }
+    }
+}
+
+/// The format of a chat message, as a translation with the parameters of the
+/// message as arguments.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChatDecoration {
+    /// The translation key of the format, like `chat.type.text`. Clients
+    /// without a translation for the key use the key itself as the format, so
+    /// a custom format like `%s shouts: %s` can be used directly.
+    pub translation_key: String,
+    /// The parameters which replace each `%s` in the format.
+    pub parameters: Vec<ChatParameter>,
+    /// The style of the whole message, like `{color: "gray", italic: 1b}`.
+    pub style: Option<Compound>,
+}
+
+impl ChatDecoration {
+    pub fn new(
+        translation_key: impl Into<String>,
+        parameters: impl IntoIterator<Item = ChatParameter>,
+    ) -> Self {
+        Self {
+            translation_key: translation_key.into(),
+            parameters: parameters.into_iter().collect(),
+            style: None,
+        }
+    }
+
+    pub fn with_style(mut self, style: Compound) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    fn to_nbt(&self) -> Compound {
+        let mut decoration = compound! {
+            "translation_key" => self.translation_key.as_str(),
+            "parameters" => List::String(
+                self.parameters.iter().map(|p| p.as_str().to_owned()).collect(),
+            ),
+        };
+
+        if let Some(style) = &self.style {
+            decoration.insert("style", style.clone());
+        }
+
+        decoration
+    }
+}
+
+/// A part of a player chat message which can be shown by a
+/// [`ChatDecoration`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ChatParameter {
+    /// The name of the sender.
+    Sender,
+    /// The name of the receiver of the message.
+    Target,
+    /// The message itself.
+    Content,
+}
+
+impl ChatParameter {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Sender => "sender",
+            Self::Target => "target",
+            Self::Content => "content",
+        }
+    }
+}
+
+impl RegistryCodec {
+    /// Adds a chat type to the registry codec, returning the [`ChatType`] to
+    /// send messages with it.
+    ///
+    /// Chat types should be added while building the app, since clients only
+    /// receive the registry codec when joining. An error is returned if a chat
+    /// type with the name already exists or a vanilla chat type is missing.
+    pub fn add_chat_type(
+        &mut self,
+        name: impl Into<Ident<String>>,
+        entry: ChatTypeEntry,
+    ) -> Result<ChatType, RegistryError> {
+        self.add_entry(
+            CHAT_TYPE_KEY,
+            name.into(),
+            entry.to_nbt(),
+            &VANILLA_CHAT_TYPES,
+        )
+        .map(ChatType)
+    }
+
+    /// Returns the chat type with the given name.
+    pub fn chat_type(&self, name: Ident<&str>) -> Option<ChatType> {
+        self.entry_id(CHAT_TYPE_KEY, name).map(ChatType)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vanilla_chat_types() {
+        let codec = RegistryCodec::default();
+
+        for (id, name) in VANILLA_CHAT_TYPES.into_iter().enumerate() {
+            assert_eq!(codec.chat_type(name), Some(ChatType(id as i32)));
+        }
+
+        assert_eq!(
+            codec.chat_type(ident!("minecraft:emote_command")),
+            Some(ChatType::EMOTE_COMMAND)
+        );
+    }
+
+    #[test]
+    fn add_chat_type() {
+        let mut codec = RegistryCodec::default();
+
+        let entry = ChatTypeEntry::new(ChatDecoration::new(
+            "%s shouts: %s",
+            [ChatParameter::Sender, ChatParameter::Content],
+        ));
+
+        let shout = codec
+            .add_chat_type(ident!("custom:shout"), entry.clone())
+            .unwrap();
+
+        assert_eq!(shout, ChatType(7));
+        assert_eq!(codec.chat_type(ident!("custom:shout")), Some(shout));
+
+        assert_eq!(
+            codec.add_chat_type(ident!("custom:shout"), entry.clone()),
+            Err(RegistryError::DuplicateEntry {
+                registry: CHAT_TYPE_KEY.into(),
+                name: ident!("custom:shout").into(),
+            })
+        );
+
+        codec.registry_mut(CHAT_TYPE_KEY).remove(0);
+
+        assert_eq!(
+            codec.add_chat_type(ident!("custom:whisper"), entry),
+            Err(RegistryError::MissingVanillaEntry {
+                registry: CHAT_TYPE_KEY.into(),
+                name: ident!("minecraft:chat").into(),
+            })
+        );
+    }
+}
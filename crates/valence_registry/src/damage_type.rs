@@ -0,0 +1,273 @@
+//! Damage types, which decide how damage scales with difficulty and which
+//! death message is shown when a player dies from it.
+//!
+//! Custom damage types are added to the registry codec with
+//! [`RegistryCodec::add_damage_type`], which returns the [`DamageType`] to
+//! refer to them with.
+
+use valence_core::ident;
+use valence_core::ident::Ident;
+use valence_nbt::{compound, Compound};
+
+use crate::{RegistryCodec, RegistryError};
+
+/// The key of the damage type registry.
+pub const DAMAGE_TYPE_KEY: Ident<&str> = ident!("minecraft:damage_type");
+
+/// The damage types which clients expect to exist.
+const VANILLA_DAMAGE_TYPES: [Ident<&str>; 42] = [
+    ident!("minecraft:arrow"),
+    ident!("minecraft:bad_respawn_point"),
+    ident!("minecraft:cactus"),
+    ident!("minecraft:cramming"),
+    ident!("minecraft:dragon_breath"),
+    ident!("minecraft:drown"),
+    ident!("minecraft:dry_out"),
+    ident!("minecraft:explosion"),
+    ident!("minecraft:fall"),
+    ident!("minecraft:falling_anvil"),
+    ident!("minecraft:falling_block"),
+    ident!("minecraft:falling_stalactite"),
+    ident!("minecraft:fireball"),
+    ident!("minecraft:fireworks"),
+    ident!("minecraft:fly_into_wall"),
+    ident!("minecraft:freeze"),
+    ident!("minecraft:generic"),
+    ident!("minecraft:hot_floor"),
+    ident!("minecraft:in_fire"),
+    ident!("minecraft:in_wall"),
+    ident!("minecraft:indirect_magic"),
+    ident!("minecraft:lava"),
+    ident!("minecraft:lightning_bolt"),
+    ident!("minecraft:magic"),
+    ident!("minecraft:mob_attack"),
+    ident!("minecraft:mob_attack_no_aggro"),
+    ident!("minecraft:mob_projectile"),
+    ident!("minecraft:on_fire"),
+    ident!("minecraft:out_of_world"),
+    ident!("minecraft:player_attack"),
+    ident!("minecraft:player_explosion"),
+    ident!("minecraft:sonic_boom"),
+    ident!("minecraft:stalagmite"),
+    ident!("minecraft:starve"),
+    ident!("minecraft:sting"),
+    ident!("minecraft:sweet_berry_bush"),
+    ident!("minecraft:thorns"),
+    ident!("minecraft:thrown"),
+    ident!("minecraft:trident"),
+    ident!("minecraft:unattributed_fireball"),
+    ident!("minecraft:wither"),
+    ident!("minecraft:wither_skull"),
+];
+
+/// An entry of the `minecraft:damage_type` registry, identified by its ID in
+/// the registry codec.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DamageType(pub i32);
+
+/// The definition of a damage type in the registry codec.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DamageTypeEntry {
+    /// The part of the translation key of the death message after
+    /// `death.attack.`. Clients without a translation for the key show the
+    /// key itself.
+    pub message_id: String,
+    pub scaling: DamageScaling,
+    /// The amount of exhaustion the damage causes.
+    pub exhaustion: f32,
+    pub effects: DamageEffects,
+    pub death_message_type: DeathMessageType,
+}
+
+impl DamageTypeEntry {
+    /// Creates a damage type which is scaled like most vanilla damage types.
+    pub fn new(message_id: impl Into<String>) -> Self {
+        Self {
+            message_id: message_id.into(),
+            scaling: DamageScaling::WhenCausedByLivingNonPlayer,
+            exhaustion: 0.0,
+            effects: DamageEffects::Hurt,
+            death_message_type: DeathMessageType::Default,
+        }
+    }
+
+    pub fn with_scaling(mut self, scaling: DamageScaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    pub fn with_exhaustion(mut self, exhaustion: f32) -> Self {
+        self.exhaustion = exhaustion;
+        self
+    }
+
+    pub fn with_effects(mut self, effects: DamageEffects) -> Self {
+        self.effects = effects;
+        self
+    }
+
+    pub fn with_death_message_type(mut self, death_message_type: DeathMessageType) -> Self {
+        self.death_message_type = death_message_type;
+        self
+    }
+
+    fn to_nbt(&self) -> Compound {
+        let mut element = compound! {
+            "message_id" => self.message_id.as_str(),
+            "scaling" => self.scaling.as_str(),
+            "exhaustion" => self.exhaustion,
+        };
+
+        // The defaults are left out like in the vanilla registry codec.
+        if self.effects != DamageEffects::Hurt {
+            element.insert("effects", self.effects.as_str());
+        }
+
+        if self.death_message_type != DeathMessageType::Default {
+            element.insert("death_message_type", self.death_message_type.as_str());
+        }
+
+        element
+    }
+}
+
+/// Whether damage scales with the difficulty.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum DamageScaling {
+    Never,
+    /// Scales with the difficulty if the damage is caused by a living entity
+    /// which is not a player.
+    WhenCausedByLivingNonPlayer,
+    Always,
+}
+
+impl DamageScaling {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Never => "never",
+            Self::WhenCausedByLivingNonPlayer => "when_caused_by_living_non_player",
+            Self::Always => "always",
+        }
+    }
+}
+
+/// The sound played when an entity takes damage.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub enum DamageEffects {
+    #[default]
+    Hurt,
+    Thorns,
+    Drowning,
+    Burning,
+    Poking,
+    Freezing,
+}
+
+impl DamageEffects {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Hurt => "hurt",
+            Self::Thorns => "thorns",
+            Self::Drowning => "drowning",
+            Self::Burning => "burning",
+            Self::Poking => "poking",
+            Self::Freezing => "freezing",
+        }
+    }
+}
+
+/// How the death message of a damage type is chosen.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub enum DeathMessageType {
+    /// Uses the message of the damage type.
+    #[default]
+    Default,
+    /// Uses the fall damage messages, which depend on what the player fell
+    /// from.
+    FallVariants,
+    /// Uses the message of beds exploding in the nether.
+    IntentionalGameDesign,
+}
+
+impl DeathMessageType {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::FallVariants => "fall_variants",
+            Self::IntentionalGameDesign => "intentional_game_design",
+        }
+    }
+}
+
+impl RegistryCodec {
+    /// Adds a damage type to the registry codec, returning the
+    /// [`DamageType`] to refer to it with.
+    ///
+    /// Damage types should be added while building the app, since clients
+    /// only receive the registry codec when joining. An error is returned if a
+    /// damage type with the name already exists or a vanilla damage type is
+    /// missing.
+    pub fn add_damage_type(
+        &mut self,
+        name: impl Into<Ident<String>>,
+        entry: DamageTypeEntry,
+    ) -> Result<DamageType, RegistryError> {
+        self.add_entry(
+            DAMAGE_TYPE_KEY,
+            name.into(),
+            entry.to_nbt(),
+            &VANILLA_DAMAGE_TYPES,
+        )
+        .map(DamageType)
+    }
+
+    /// Returns the damage type with the given name.
+    pub fn damage_type(&self, name: Ident<&str>) -> Option<DamageType> {
+        self.entry_id(DAMAGE_TYPE_KEY, name).map(DamageType)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_damage_type() {
+        let mut codec = RegistryCodec::default();
+
+        for name in VANILLA_DAMAGE_TYPES {
+            assert!(codec.damage_type(name).is_some(), "missing {name}");
+        }
+
+        let entry = DamageTypeEntry::new("custom.lasers")
+            .with_scaling(DamageScaling::Never)
+            .with_effects(DamageEffects::Burning);
+
+        let lasers = codec
+            .add_damage_type(ident!("custom:lasers"), entry.clone())
+            .unwrap();
+
+        assert_eq!(lasers, DamageType(42));
+        assert_eq!(codec.damage_type(ident!("custom:lasers")), Some(lasers));
+
+        let value = codec.registry(DAMAGE_TYPE_KEY).last().unwrap();
+
+        assert_eq!(
+            value.element,
+            compound! {
+                "message_id" => "custom.lasers",
+                "scaling" => "never",
+                "exhaustion" => 0.0_f32,
+                "effects" => "burning",
+            }
+        );
+
+        assert_eq!(
+            codec.add_damage_type(ident!("minecraft:generic"), entry),
+            Err(RegistryError::DuplicateEntry {
+                registry: DAMAGE_TYPE_KEY.into(),
+                name: ident!("minecraft:generic").into(),
+            })
+        );
+    }
+}
@@ -21,10 +21,19 @@ use std::collections::BTreeMap;
 
 use bevy_app::prelude::*;
 pub use bevy_ecs::prelude::*;
+use thiserror::Error;
 use tracing::error;
 use valence_core::ident::Ident;
 use valence_nbt::{compound, Compound, List, Value};
 
+pub mod chat_type;
+pub mod damage_type;
+pub mod tags;
+
+pub use chat_type::{ChatDecoration, ChatParameter, ChatTypeEntry};
+pub use damage_type::{DamageType, DamageTypeEntry};
+pub use tags::TagsRegistry;
+
 pub struct RegistryPlugin;
 
 /// The [`SystemSet`] where the [`RegistryCodec`] cache is rebuilt. Systems that
@@ -35,6 +44,7 @@ pub struct RegistryCodecSet;
 impl Plugin for RegistryPlugin {
     fn build(&self, app: &mut bevy_app::App) {
         app.init_resource::<RegistryCodec>()
+            .init_resource::<TagsRegistry>()
             .configure_set(RegistryCodecSet.in_base_set(CoreSet::PostUpdate))
             .add_system(cache_registry_codec.in_set(RegistryCodecSet));
     }
@@ -101,6 +111,72 @@ impl RegistryCodec {
             .get_mut(registry_key.as_str())
             .unwrap_or_else(|| panic!("missing registry for {registry_key}"))
     }
+
+    /// Appends an entry to a registry and returns its ID. `vanilla_entries`
+    /// are the entries of the registry which clients require.
+    pub(crate) fn add_entry(
+        &mut self,
+        registry_key: Ident<&str>,
+        name: Ident<String>,
+        element: Compound,
+        vanilla_entries: &[Ident<&str>],
+    ) -> Result<i32, RegistryError> {
+        let registry = self
+            .registries
+            .get_mut(registry_key.as_str())
+            .ok_or_else(|| RegistryError::MissingRegistry(registry_key.into()))?;
+
+        for &vanilla_name in vanilla_entries {
+            if !registry
+                .iter()
+                .any(|v| v.name.as_str() == vanilla_name.as_str())
+            {
+                return Err(RegistryError::MissingVanillaEntry {
+                    registry: registry_key.into(),
+                    name: vanilla_name.into(),
+                });
+            }
+        }
+
+        if registry.iter().any(|v| v.name == name) {
+            return Err(RegistryError::DuplicateEntry {
+                registry: registry_key.into(),
+                name,
+            });
+        }
+
+        registry.push(RegistryValue { name, element });
+
+        Ok(registry.len() as i32 - 1)
+    }
+
+    /// Returns the ID of the entry with the given name in a registry.
+    pub(crate) fn entry_id(&self, registry_key: Ident<&str>, name: Ident<&str>) -> Option<i32> {
+        self.registries
+            .get(registry_key.as_str())?
+            .iter()
+            .position(|v| v.name.as_str() == name.as_str())
+            .map(|id| id as i32)
+    }
+}
+
+/// An error returned when an entry can't be added to the registry codec.
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+pub enum RegistryError {
+    #[error("the registry codec has no registry named \"{0}\"")]
+    MissingRegistry(Ident<String>),
+    #[error("registry \"{registry}\" already has an entry named \"{name}\"")]
+    DuplicateEntry {
+        registry: Ident<String>,
+        name: Ident<String>,
+    },
+    #[error(
+        "registry \"{registry}\" is missing the vanilla entry \"{name}\" which clients require"
+    )]
+    MissingVanillaEntry {
+        registry: Ident<String>,
+        name: Ident<String>,
+    },
 }
 
 impl Default for RegistryCodec {
@@ -118,7 +194,7 @@ impl Default for RegistryCodec {
 
             let Value::Compound(mut outer) = v else {
                 error!("registry {reg_name} is not a compound");
-                continue
+                continue;
             };
 
             let values = match outer.remove("value") {
@@ -133,7 +209,7 @@ impl Default for RegistryCodec {
             for mut value in values {
                 let Some(Value::String(name)) = value.remove("name") else {
                     error!("missing \"name\" string in value for {reg_name}");
-                    continue
+                    continue;
                 };
 
                 let name = match Ident::new(name) {
@@ -146,7 +222,7 @@ impl Default for RegistryCodec {
 
                 let Some(Value::Compound(element)) = value.remove("element") else {
                     error!("missing \"element\" compound in value for {reg_name}");
-                    continue
+                    continue;
                 };
 
                 reg_values.push(RegistryValue { name, element });
@@ -0,0 +1,186 @@
+//! Tags, which are named sets of entries in a registry, like the
+//! `minecraft:wool` tag of blocks.
+//!
+//! Clients use tags for things like deciding which blocks can be climbed or
+//! which items can be used to breed an animal, so custom tags are sent to
+//! clients as well.
+
+use std::collections::BTreeMap;
+
+use bevy_ecs::prelude::*;
+use valence_block::{BlockKind, BlockTag};
+use valence_core::ident;
+use valence_core::ident::Ident;
+use valence_core::item::{ItemKind, ItemTag};
+use valence_core::packet::s2c::play::synchronize_tags::{Tag, TagGroup};
+use valence_core::packet::s2c::play::SynchronizeTagsS2c;
+use valence_core::packet::var_int::VarInt;
+
+/// Contains the tags of every registry, which are sent to all players while
+/// joining and again whenever the tags are changed.
+///
+/// Tags map to the raw IDs of the entries in a registry. The vanilla block and
+/// item tags are included by default.
+#[derive(Resource, Clone, Debug)]
+pub struct TagsRegistry {
+    registries: BTreeMap<Ident<String>, BTreeMap<Ident<String>, Vec<i32>>>,
+}
+
+impl TagsRegistry {
+    /// The key of the block registry.
+    pub const BLOCK: Ident<&str> = ident!("minecraft:block");
+    /// The key of the item registry.
+    pub const ITEM: Ident<&str> = ident!("minecraft:item");
+
+    /// Returns the raw IDs of the entries in a tag, or `None` if the tag
+    /// doesn't exist.
+    pub fn tag(&self, registry_key: Ident<&str>, tag: Ident<&str>) -> Option<&[i32]> {
+        self.registries
+            .get(registry_key.as_str())?
+            .get(tag.as_str())
+            .map(|entries| entries.as_slice())
+    }
+
+    /// Returns whether the entry with the given raw ID is in a tag.
+    pub fn contains(&self, registry_key: Ident<&str>, tag: Ident<&str>, id: i32) -> bool {
+        self.tag(registry_key, tag)
+            .map_or(false, |entries| entries.contains(&id))
+    }
+
+    /// Returns whether the kind of block is in a block tag.
+    pub fn block_has_tag(&self, kind: BlockKind, tag: Ident<&str>) -> bool {
+        self.contains(Self::BLOCK, tag, kind.to_raw() as i32)
+    }
+
+    /// Returns whether the kind of item is in an item tag.
+    pub fn item_has_tag(&self, kind: ItemKind, tag: Ident<&str>) -> bool {
+        self.contains(Self::ITEM, tag, kind.to_raw() as i32)
+    }
+
+    /// Inserts or replaces a tag with the given raw IDs, returning the
+    /// entries of the tag it replaced.
+    pub fn insert(
+        &mut self,
+        registry_key: Ident<&str>,
+        tag: Ident<&str>,
+        entries: impl IntoIterator<Item = i32>,
+    ) -> Option<Vec<i32>> {
+        self.registries
+            .entry(registry_key.into())
+            .or_default()
+            .insert(tag.into(), entries.into_iter().collect())
+    }
+
+    /// Removes a tag, returning its entries if it existed.
+    pub fn remove(&mut self, registry_key: Ident<&str>, tag: Ident<&str>) -> Option<Vec<i32>> {
+        self.registries
+            .get_mut(registry_key.as_str())?
+            .remove(tag.as_str())
+    }
+
+    /// Returns an iterator over the names and entries of the tags of a
+    /// registry.
+    pub fn tags(
+        &self,
+        registry_key: Ident<&str>,
+    ) -> impl Iterator<Item = (Ident<&str>, &[i32])> + '_ {
+        self.registries
+            .get(registry_key.as_str())
+            .into_iter()
+            .flatten()
+            .map(|(name, entries)| (name.as_str_ident(), entries.as_slice()))
+    }
+
+    /// Returns the packet which sends every tag to a client.
+    pub fn sync_packet(&self) -> SynchronizeTagsS2c<'_> {
+        SynchronizeTagsS2c {
+            tags: self
+                .registries
+                .iter()
+                .map(|(kind, tags)| TagGroup {
+                    kind: kind.as_str_ident().into(),
+                    tags: tags
+                        .iter()
+                        .map(|(name, entries)| Tag {
+                            name: name.as_str_ident().into(),
+                            entries: entries.iter().map(|&id| VarInt(id)).collect(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Default for TagsRegistry {
+    fn default() -> Self {
+        let mut tags = Self {
+            registries: BTreeMap::new(),
+        };
+
+        for tag in BlockTag::ALL {
+            let entries = tag.blocks().iter().map(|kind| kind.to_raw() as i32);
+            tags.insert(Self::BLOCK, tag.ident(), entries);
+        }
+
+        for tag in ItemTag::ALL {
+            let entries = tag.items().iter().map(|kind| kind.to_raw() as i32);
+            tags.insert(Self::ITEM, tag.ident(), entries);
+        }
+
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vanilla_tags() {
+        let tags = TagsRegistry::default();
+
+        assert!(tags.block_has_tag(BlockKind::WhiteWool, ident!("minecraft:wool")));
+        assert!(!tags.block_has_tag(BlockKind::Stone, ident!("minecraft:wool")));
+        assert!(tags.item_has_tag(ItemKind::RedBed, ident!("minecraft:beds")));
+        assert!(tags
+            .tag(TagsRegistry::BLOCK, ident!("minecraft:nonexistent"))
+            .is_none());
+
+        let packet = tags.sync_packet();
+
+        assert_eq!(packet.tags.len(), 2);
+        assert_eq!(packet.tags[0].tags.len(), BlockTag::ALL.len());
+        assert_eq!(packet.tags[1].tags.len(), ItemTag::ALL.len());
+    }
+
+    #[test]
+    fn custom_tags() {
+        let mut tags = TagsRegistry::default();
+
+        let kinds = [BlockKind::Stone, BlockKind::Dirt];
+
+        assert!(tags
+            .insert(
+                TagsRegistry::BLOCK,
+                ident!("custom:ground"),
+                kinds.map(|k| k.to_raw() as i32)
+            )
+            .is_none());
+
+        assert!(tags.block_has_tag(BlockKind::Dirt, ident!("custom:ground")));
+        assert!(!tags.block_has_tag(BlockKind::WhiteWool, ident!("custom:ground")));
+
+        assert!(tags
+            .remove(TagsRegistry::BLOCK, ident!("minecraft:wool"))
+            .is_some());
+        assert!(!tags.block_has_tag(BlockKind::WhiteWool, ident!("minecraft:wool")));
+
+        tags.insert(ident!("custom:registry"), ident!("custom:tag"), [1, 2, 3]);
+
+        assert_eq!(
+            tags.tags(ident!("custom:registry")).collect::<Vec<_>>(),
+            [(ident!("custom:tag"), [1, 2, 3].as_slice())]
+        );
+    }
+}
@@ -0,0 +1,647 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use flate2::bufread::GzDecoder;
+use glam::IVec3;
+use thiserror::Error;
+use valence_block::BlockState;
+pub use valence_block::{Mirror, Rotation};
+use valence_core::block_pos::BlockPos;
+use valence_instance::{Block, Instance};
+use valence_nbt::{Compound, List, Value};
+
+/// The blocks and block entities of a Sponge schematic.
+///
+/// See the [crate documentation](crate) for more information.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Schematic {
+    width: u16,
+    height: u16,
+    length: u16,
+    offset: IVec3,
+    palette: Vec<BlockState>,
+    /// Indices into the palette, ordered by Y, then Z, then X.
+    blocks: Box<[u32]>,
+    /// The NBT of the block entities, keyed by the index of their block.
+    block_entities: BTreeMap<usize, Compound>,
+    warnings: Vec<String>,
+}
+
+/// Errors that can occur when loading a [`Schematic`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum LoadSchematicError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Nbt(#[from] valence_nbt::Error),
+    #[error("unsupported schematic version {0}")]
+    UnsupportedVersion(i32),
+    #[error("missing or invalid field \"{0}\"")]
+    InvalidField(&'static str),
+    #[error("block data doesn't match the dimensions of the schematic")]
+    InvalidBlockData,
+    #[error("block data refers to missing palette index {0}")]
+    MissingPaletteIndex(u32),
+}
+
+impl Schematic {
+    /// Loads a schematic from a gzip compressed `.schem` file.
+    ///
+    /// Block states in the palette which are unknown are replaced with
+    /// `fallback`. See [`Schematic::from_nbt`].
+    pub fn load(path: impl AsRef<Path>, fallback: BlockState) -> Result<Self, LoadSchematicError> {
+        let file = BufReader::new(File::open(path)?);
+
+        let mut buf = vec![];
+        GzDecoder::new(file).read_to_end(&mut buf)?;
+
+        let (nbt, _) = valence_nbt::from_binary_slice(&mut buf.as_slice())?;
+
+        Self::from_nbt(&nbt, fallback)
+    }
+
+    /// Reads a schematic from the uncompressed root compound of a `.schem`
+    /// file.
+    ///
+    /// Block states in the palette which are unknown, such as blocks added by
+    /// mods, are replaced with `fallback` and a warning is recorded for each
+    /// of them. Block entities outside of the schematic are skipped with a
+    /// warning.
+    pub fn from_nbt(nbt: &Compound, fallback: BlockState) -> Result<Self, LoadSchematicError> {
+        // Version 3 schematics are wrapped in a compound named "Schematic".
+        let nbt = match nbt.get("Schematic") {
+            Some(Value::Compound(inner)) => inner,
+            _ => nbt,
+        };
+
+        let version = match nbt.get("Version") {
+            Some(&Value::Int(version)) => version,
+            _ => return Err(LoadSchematicError::InvalidField("Version")),
+        };
+
+        let (palette, data, block_entities) = match version {
+            1 | 2 => (
+                nbt.get("Palette"),
+                nbt.get("BlockData"),
+                nbt.get("BlockEntities").or_else(|| nbt.get("TileEntities")),
+            ),
+            3 => {
+                let Some(Value::Compound(blocks)) = nbt.get("Blocks") else {
+                    return Err(LoadSchematicError::InvalidField("Blocks"));
+                };
+
+                (
+                    blocks.get("Palette"),
+                    blocks.get("Data"),
+                    blocks.get("BlockEntities"),
+                )
+            }
+            _ => return Err(LoadSchematicError::UnsupportedVersion(version)),
+        };
+
+        let width = read_dimension(nbt, "Width")?;
+        let height = read_dimension(nbt, "Height")?;
+        let length = read_dimension(nbt, "Length")?;
+
+        let offset = match nbt.get("Offset") {
+            Some(Value::IntArray(offset)) => match offset[..] {
+                [x, y, z] => IVec3::new(x, y, z),
+                _ => return Err(LoadSchematicError::InvalidField("Offset")),
+            },
+            None => IVec3::ZERO,
+            Some(_) => return Err(LoadSchematicError::InvalidField("Offset")),
+        };
+
+        let mut warnings = vec![];
+
+        let Some(Value::Compound(palette)) = palette else {
+            return Err(LoadSchematicError::InvalidField("Palette"));
+        };
+
+        let mut states = vec![None; palette.len()];
+
+        for (state_str, idx) in palette {
+            let idx = match idx {
+                &Value::Int(idx) => usize::try_from(idx).ok(),
+                _ => None,
+            };
+
+            let Some(slot) = idx.and_then(|idx| states.get_mut(idx)) else {
+                return Err(LoadSchematicError::InvalidField("Palette"));
+            };
+
+            *slot = Some(state_str.parse().unwrap_or_else(|_| {
+                warnings.push(format!("unknown block state \"{state_str}\""));
+                fallback
+            }));
+        }
+
+        let palette: Vec<BlockState> = states
+            .into_iter()
+            .map(|state| state.unwrap_or(fallback))
+            .collect();
+
+        let Some(Value::ByteArray(data)) = data else {
+            return Err(LoadSchematicError::InvalidField("BlockData"));
+        };
+
+        let volume = width as usize * height as usize * length as usize;
+        let mut blocks = Vec::with_capacity(volume);
+        let mut data = data.as_slice();
+
+        while !data.is_empty() {
+            let idx = read_var_int(&mut data).ok_or(LoadSchematicError::InvalidBlockData)?;
+
+            if idx as usize >= palette.len() {
+                return Err(LoadSchematicError::MissingPaletteIndex(idx));
+            }
+
+            blocks.push(idx);
+        }
+
+        if blocks.len() != volume {
+            return Err(LoadSchematicError::InvalidBlockData);
+        }
+
+        let mut schematic = Self {
+            width,
+            height,
+            length,
+            offset,
+            palette,
+            blocks: blocks.into(),
+            block_entities: BTreeMap::new(),
+            warnings,
+        };
+
+        match block_entities {
+            Some(Value::List(List::Compound(block_entities))) => {
+                for block_entity in block_entities {
+                    schematic.read_block_entity(block_entity, version);
+                }
+            }
+            Some(Value::List(List::End)) | None => {}
+            Some(_) => return Err(LoadSchematicError::InvalidField("BlockEntities")),
+        }
+
+        Ok(schematic)
+    }
+
+    fn read_block_entity(&mut self, block_entity: &Compound, version: i32) {
+        let pos = match block_entity.get("Pos") {
+            Some(Value::IntArray(pos)) => match pos[..] {
+                [x, y, z] => self.index(x, y, z),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let Some(idx) = pos else {
+            self.warnings
+                .push("skipping block entity with an invalid position".into());
+            return;
+        };
+
+        // Version 3 keeps the vanilla data in a separate compound, while older
+        // versions put it next to the position and ID.
+        let nbt = if version >= 3 {
+            match block_entity.get("Data") {
+                Some(Value::Compound(data)) => data.clone(),
+                _ => Compound::new(),
+            }
+        } else {
+            let mut nbt = block_entity.clone();
+            nbt.remove("Pos");
+            nbt.remove("Id");
+            nbt
+        };
+
+        self.block_entities.insert(idx, nbt);
+    }
+
+    /// The size of the schematic along the X axis.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// The size of the schematic along the Y axis.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The size of the schematic along the Z axis.
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    /// The position of the minimum corner of the schematic relative to the
+    /// origin it is pasted at.
+    pub fn offset(&self) -> IVec3 {
+        self.offset
+    }
+
+    /// The warnings recorded while the schematic was loaded, such as unknown
+    /// block states which were replaced with the fallback block state.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns the block state at a position relative to the minimum corner
+    /// of the schematic, or `None` if the position is outside of the
+    /// schematic.
+    pub fn block_state(&self, pos: impl Into<IVec3>) -> Option<BlockState> {
+        let pos = pos.into();
+        let idx = self.index(pos.x, pos.y, pos.z)?;
+
+        Some(self.palette[self.blocks[idx] as usize])
+    }
+
+    /// Returns the NBT of the block entity at a position relative to the
+    /// minimum corner of the schematic.
+    pub fn block_entity(&self, pos: impl Into<IVec3>) -> Option<&Compound> {
+        let pos = pos.into();
+
+        self.block_entities.get(&self.index(pos.x, pos.y, pos.z)?)
+    }
+
+    fn index(&self, x: i32, y: i32, z: i32) -> Option<usize> {
+        let in_bounds = (0..self.width as i32).contains(&x)
+            && (0..self.height as i32).contains(&y)
+            && (0..self.length as i32).contains(&z);
+
+        in_bounds.then(|| {
+            x as usize
+                + z as usize * self.width as usize
+                + y as usize * self.width as usize * self.length as usize
+        })
+    }
+
+    /// Writes the blocks and block entities of the schematic to an instance.
+    /// The minimum corner of the schematic is placed at `origin` plus the
+    /// offset of the schematic, transformed by `options`.
+    ///
+    /// Only blocks in loaded chunks are written.
+    pub fn paste(
+        &self,
+        instance: &mut Instance,
+        origin: impl Into<BlockPos>,
+        options: PasteOptions,
+    ) {
+        let origin = origin.into();
+
+        let palette: Vec<BlockState> = self
+            .palette
+            .iter()
+            .map(|&state| options.transform_state(state))
+            .collect();
+
+        for y in 0..self.height as i32 {
+            for z in 0..self.length as i32 {
+                for x in 0..self.width as i32 {
+                    let idx = self.index(x, y, z).unwrap();
+                    let state = palette[self.blocks[idx] as usize];
+
+                    if options.skip_air && state.is_air() {
+                        continue;
+                    }
+
+                    let rel = options.transform_pos(self.offset + IVec3::new(x, y, z));
+                    let pos = BlockPos::new(origin.x + rel.x, origin.y + rel.y, origin.z + rel.z);
+
+                    let block = match self.block_entities.get(&idx) {
+                        Some(nbt) => Block::with_nbt(state, nbt.clone()),
+                        None => Block::new(state),
+                    };
+
+                    instance.set_block(pos, block);
+                }
+            }
+        }
+    }
+}
+
+/// How a [`Schematic`] is transformed when it is pasted. The schematic is
+/// mirrored first and rotated around the origin afterwards.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct PasteOptions {
+    pub rotation: Rotation,
+    pub mirror: Mirror,
+    /// Whether air, cave air and void air in the schematic should be skipped
+    /// instead of replacing the blocks in the instance.
+    pub skip_air: bool,
+}
+
+impl PasteOptions {
+    fn transform_pos(self, pos: IVec3) -> IVec3 {
+        self.rotation.rotate_pos(self.mirror.mirror_pos(pos))
+    }
+
+    fn transform_state(self, state: BlockState) -> BlockState {
+        state.mirror(self.mirror).rotate(self.rotation)
+    }
+}
+
+fn read_dimension(nbt: &Compound, name: &'static str) -> Result<u16, LoadSchematicError> {
+    match nbt.get(name) {
+        // Dimensions are unsigned shorts.
+        Some(&Value::Short(n)) => Ok(n as u16),
+        _ => Err(LoadSchematicError::InvalidField(name)),
+    }
+}
+
+fn read_var_int(data: &mut &[i8]) -> Option<u32> {
+    let mut val = 0;
+
+    for i in 0..5 {
+        let (&byte, rest) = data.split_first()?;
+        *data = rest;
+
+        val |= (byte as u32 & 0x7f) << (i * 7);
+
+        if byte >= 0 {
+            return Some(val);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use valence_block::{PropName, PropValue};
+    use valence_nbt::compound;
+
+    use super::*;
+
+    const STAIRS: &str =
+        "minecraft:oak_stairs[facing=north,half=bottom,shape=inner_left,waterlogged=false]";
+
+    /// A version 2 schematic of a row of three blocks along the X axis: stairs
+    /// facing north, a chest with a diamond in it, and a block of a mod.
+    fn fixture() -> Compound {
+        compound! {
+            "Version" => 2,
+            "DataVersion" => 3337,
+            "Width" => 3_i16,
+            "Height" => 1_i16,
+            "Length" => 1_i16,
+            "Offset" => vec![-1, 0, 0],
+            "PaletteMax" => 3,
+            "Palette" => compound! {
+                STAIRS => 0,
+                "minecraft:chest[facing=west,type=left,waterlogged=false]" => 1,
+                "examplemod:gadget" => 2,
+            },
+            "BlockData" => vec![0_i8, 1, 2],
+            "BlockEntities" => List::Compound(vec![compound! {
+                "Pos" => vec![1, 0, 0],
+                "Id" => "minecraft:chest",
+                "Items" => List::Compound(vec![compound! {
+                    "Slot" => 0_i8,
+                    "id" => "minecraft:diamond",
+                    "Count" => 1_i8,
+                }]),
+            }]),
+        }
+    }
+
+    fn chest() -> BlockState {
+        BlockState::CHEST
+            .set(PropName::Facing, PropValue::West)
+            .set(PropName::Type, PropValue::Left)
+    }
+
+    fn stairs(facing: PropValue, shape: PropValue) -> BlockState {
+        BlockState::OAK_STAIRS
+            .set(PropName::Facing, facing)
+            .set(PropName::Shape, shape)
+    }
+
+    #[test]
+    fn load_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.schem");
+
+        let mut encoder = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+        valence_nbt::to_binary_writer(&mut encoder, &fixture(), "Schematic").unwrap();
+        encoder.finish().unwrap().flush().unwrap();
+
+        let schem = Schematic::load(&path, BlockState::STONE).unwrap();
+
+        assert_eq!((schem.width(), schem.height(), schem.length()), (3, 1, 1));
+        assert_eq!(schem.offset(), IVec3::new(-1, 0, 0));
+        assert_eq!(
+            schem.block_state([0, 0, 0]),
+            Some(stairs(PropValue::North, PropValue::InnerLeft))
+        );
+        assert_eq!(schem.block_state([1, 0, 0]), Some(chest()));
+        assert_eq!(schem.block_state([2, 0, 0]), Some(BlockState::STONE));
+        assert_eq!(schem.block_state([3, 0, 0]), None);
+        assert_eq!(
+            schem.block_entity([1, 0, 0]),
+            Some(&compound! {
+                "Items" => List::Compound(vec![compound! {
+                    "Slot" => 0_i8,
+                    "id" => "minecraft:diamond",
+                    "Count" => 1_i8,
+                }]),
+            })
+        );
+        assert_eq!(
+            schem.warnings(),
+            ["unknown block state \"examplemod:gadget\""]
+        );
+    }
+
+    #[test]
+    fn load_version_3() {
+        let nbt = compound! {
+            "Schematic" => compound! {
+                "Version" => 3,
+                "DataVersion" => 3337,
+                "Width" => 1_i16,
+                "Height" => 2_i16,
+                "Length" => 1_i16,
+                "Blocks" => compound! {
+                    "Palette" => compound! {
+                        "minecraft:air" => 0,
+                        "minecraft:oak_sign[rotation=4,waterlogged=false]" => 1,
+                    },
+                    "Data" => vec![1_i8, 0],
+                    "BlockEntities" => List::Compound(vec![compound! {
+                        "Pos" => vec![0, 0, 0],
+                        "Id" => "minecraft:sign",
+                        "Data" => compound! { "GlowingText" => 1_i8 },
+                    }]),
+                },
+            },
+        };
+
+        let schem = Schematic::from_nbt(&nbt, BlockState::STONE).unwrap();
+
+        assert_eq!(schem.offset(), IVec3::ZERO);
+        assert_eq!(
+            schem.block_state([0, 0, 0]),
+            Some(BlockState::OAK_SIGN.set(PropName::Rotation, PropValue::_4))
+        );
+        assert_eq!(schem.block_state([0, 1, 0]), Some(BlockState::AIR));
+        assert_eq!(
+            schem.block_entity([0, 0, 0]),
+            Some(&compound! { "GlowingText" => 1_i8 })
+        );
+        assert!(schem.warnings().is_empty());
+    }
+
+    #[test]
+    fn invalid_block_data() {
+        let mut nbt = fixture();
+        nbt.insert("BlockData", vec![0_i8, 1]);
+
+        assert!(matches!(
+            Schematic::from_nbt(&nbt, BlockState::STONE),
+            Err(LoadSchematicError::InvalidBlockData)
+        ));
+
+        nbt.insert("BlockData", vec![0_i8, 1, 3]);
+
+        assert!(matches!(
+            Schematic::from_nbt(&nbt, BlockState::STONE),
+            Err(LoadSchematicError::MissingPaletteIndex(3))
+        ));
+    }
+
+    #[test]
+    fn var_ints() {
+        let data = [0x01, 0x7f, -0x80, 0x01, -0x01, -0x01, 0x03];
+        let mut data = &data[..];
+
+        assert_eq!(read_var_int(&mut data), Some(1));
+        assert_eq!(read_var_int(&mut data), Some(127));
+        assert_eq!(read_var_int(&mut data), Some(128));
+        assert_eq!(read_var_int(&mut data), Some(65535));
+        assert_eq!(read_var_int(&mut data), None);
+    }
+
+    #[test]
+    fn rotate_stairs() {
+        let schem = Schematic::from_nbt(&fixture(), BlockState::STONE).unwrap();
+        let state = schem.block_state([0, 0, 0]).unwrap();
+
+        let rotated = |rotation| {
+            PasteOptions {
+                rotation,
+                ..Default::default()
+            }
+            .transform_state(state)
+        };
+
+        assert_eq!(
+            rotated(Rotation::Clockwise90),
+            stairs(PropValue::East, PropValue::InnerLeft)
+        );
+        assert_eq!(
+            rotated(Rotation::Clockwise180),
+            stairs(PropValue::South, PropValue::InnerLeft)
+        );
+        assert_eq!(
+            rotated(Rotation::Counterclockwise90),
+            stairs(PropValue::West, PropValue::InnerLeft)
+        );
+    }
+
+    #[test]
+    fn mirror_blocks() {
+        let options = PasteOptions {
+            mirror: Mirror::LeftRight,
+            ..Default::default()
+        };
+
+        // The corner of the stairs is on the other side when mirrored.
+        assert_eq!(
+            options.transform_state(stairs(PropValue::North, PropValue::InnerLeft)),
+            stairs(PropValue::South, PropValue::InnerRight)
+        );
+        assert_eq!(
+            options.transform_state(chest()),
+            chest().set(PropName::Type, PropValue::Right)
+        );
+        assert_eq!(
+            options.transform_pos(IVec3::new(1, 2, 3)),
+            IVec3::new(1, 2, -3)
+        );
+    }
+
+    #[test]
+    fn transform_properties() {
+        let options = PasteOptions {
+            rotation: Rotation::Clockwise90,
+            ..Default::default()
+        };
+
+        let fence = BlockState::OAK_FENCE
+            .set(PropName::North, PropValue::True)
+            .set(PropName::West, PropValue::True);
+        assert_eq!(
+            options.transform_state(fence),
+            BlockState::OAK_FENCE
+                .set(PropName::East, PropValue::True)
+                .set(PropName::North, PropValue::True)
+        );
+
+        let log = BlockState::OAK_LOG.set(PropName::Axis, PropValue::X);
+        assert_eq!(
+            options.transform_state(log),
+            log.set(PropName::Axis, PropValue::Z)
+        );
+
+        let rail = BlockState::RAIL.set(PropName::Shape, PropValue::NorthWest);
+        assert_eq!(
+            options.transform_state(rail),
+            rail.set(PropName::Shape, PropValue::NorthEast)
+        );
+
+        let sign = BlockState::OAK_SIGN.set(PropName::Rotation, PropValue::_14);
+        assert_eq!(
+            options.transform_state(sign),
+            sign.set(PropName::Rotation, PropValue::_2)
+        );
+
+        let jigsaw = BlockState::JIGSAW.set(PropName::Orientation, PropValue::UpNorth);
+        assert_eq!(
+            options.transform_state(jigsaw),
+            jigsaw.set(PropName::Orientation, PropValue::UpEast)
+        );
+
+        assert_eq!(
+            options.transform_pos(IVec3::new(1, 2, -3)),
+            IVec3::new(3, 2, 1)
+        );
+    }
+}
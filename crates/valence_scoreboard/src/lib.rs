@@ -0,0 +1,335 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::WorldQuery;
+use valence_client::{Client, FlushPacketsSet};
+use valence_core::despawn::Despawned;
+use valence_core::packet::encode::WritePacket;
+use valence_core::packet::s2c::play::scoreboard_display::ScoreboardDisplayS2c;
+pub use valence_core::packet::s2c::play::scoreboard_display::ScoreboardPosition as DisplaySlot;
+pub use valence_core::packet::s2c::play::scoreboard_objective_update::RenderType as ObjectiveRenderType;
+use valence_core::packet::s2c::play::scoreboard_objective_update::{
+    Mode, ScoreboardObjectiveUpdateS2c,
+};
+use valence_core::packet::s2c::play::scoreboard_player_update::{
+    Action, ScoreboardPlayerUpdateS2c,
+};
+use valence_core::packet::var_int::VarInt;
+use valence_core::text::Text;
+use valence_entity::Location;
+
+pub mod team;
+
+pub struct ScoreboardPlugin;
+
+/// The [`SystemSet`] in [`CoreSet::PostUpdate`] where the objectives and
+/// teams are sent to their viewers. Changes made to them after this set are
+/// sent the next tick.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UpdateScoreboardsSet;
+
+impl Plugin for ScoreboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_set(
+            UpdateScoreboardsSet
+                .in_base_set(CoreSet::PostUpdate)
+                .before(FlushPacketsSet),
+        )
+        .add_systems((update_objectives, team::update_teams).in_set(UpdateScoreboardsSet));
+    }
+}
+
+/// The bundle of components for a scoreboard objective. All components are
+/// required.
+///
+/// Every client has its own scoreboard, which contains the objectives it
+/// views. To show different scores to each player, spawn an objective for
+/// each of them with a single client in [`ObjectiveViewers::clients`].
+/// Objectives viewed by the same client must have distinct
+/// [`ObjectiveName`]s.
+///
+/// The objective is removed from the scoreboard of its viewers once the
+/// entity has the [`Despawned`] component.
+#[derive(Bundle, Default, Debug)]
+pub struct ObjectiveBundle {
+    pub name: ObjectiveName,
+    pub display: ObjectiveDisplay,
+    pub display_slot: ObjectiveDisplaySlot,
+    pub scores: ObjectiveScores,
+    pub viewers: ObjectiveViewers,
+    pub old_display_slot: OldObjectiveDisplaySlot,
+    pub old_scores: OldObjectiveScores,
+    pub old_viewers: OldObjectiveViewers,
+}
+
+/// The unique name identifying an objective on the client. It must not be
+/// longer than 16 characters. Careful not to modify this!
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ObjectiveName(pub String);
+
+/// How the objective is displayed to its viewers.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct ObjectiveDisplay {
+    /// The title of the objective, shown at the top of the sidebar.
+    pub display_name: Text,
+    /// Whether the scores are displayed as numbers or hearts. Hearts are only
+    /// used in the player list.
+    pub render_type: ObjectiveRenderType,
+}
+
+impl Default for ObjectiveDisplay {
+    fn default() -> Self {
+        Self {
+            display_name: Text::default(),
+            render_type: ObjectiveRenderType::Integer,
+        }
+    }
+}
+
+/// Where the objective is displayed on the screen of its viewers, if
+/// anywhere.
+///
+/// A display slot holds a single objective. Displaying an objective in a slot
+/// replaces the objective which was previously displayed there.
+#[derive(Component, Copy, Clone, PartialEq, Default, Debug)]
+pub struct ObjectiveDisplaySlot(pub Option<DisplaySlot>);
+
+/// The scores of the objective, keyed by the name of their holder. The name
+/// must not be longer than 40 characters.
+///
+/// In the sidebar, the names are the lines of text and the scores are the
+/// numbers next to them. In the list and below the name, the names are the
+/// usernames of the players the scores belong to.
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ObjectiveScores(pub BTreeMap<String, i32>);
+
+/// The clients viewing an objective.
+///
+/// Clients which reconnect are new entities, so they need to be added to
+/// [`Self::clients`] again. Viewers from [`Self::instance`] are tracked
+/// automatically.
+#[derive(Component, Clone, Default, Debug)]
+pub struct ObjectiveViewers {
+    /// The client entities viewing the objective.
+    pub clients: BTreeSet<Entity>,
+    /// If set, all the clients located in this instance also view the
+    /// objective.
+    pub instance: Option<Entity>,
+}
+
+/// The display slot of the objective, as of the last
+/// [`UpdateScoreboardsSet`].
+#[derive(Component, Copy, Clone, Default, Debug)]
+pub struct OldObjectiveDisplaySlot(Option<DisplaySlot>);
+
+impl OldObjectiveDisplaySlot {
+    pub fn get(&self) -> Option<DisplaySlot> {
+        self.0
+    }
+}
+
+/// The scores of the objective, as of the last [`UpdateScoreboardsSet`].
+#[derive(Component, Clone, Default, Debug)]
+pub struct OldObjectiveScores(BTreeMap<String, i32>);
+
+impl OldObjectiveScores {
+    pub fn get(&self) -> &BTreeMap<String, i32> {
+        &self.0
+    }
+}
+
+/// The clients which have the objective in their scoreboard, as of the last
+/// [`UpdateScoreboardsSet`].
+#[derive(Component, Clone, Default, Debug)]
+pub struct OldObjectiveViewers(BTreeSet<Entity>);
+
+impl OldObjectiveViewers {
+    pub fn get(&self) -> &BTreeSet<Entity> {
+        &self.0
+    }
+}
+
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+struct ObjectiveQuery {
+    name: &'static ObjectiveName,
+    display: Ref<'static, ObjectiveDisplay>,
+    display_slot: Ref<'static, ObjectiveDisplaySlot>,
+    scores: Ref<'static, ObjectiveScores>,
+    viewers: &'static ObjectiveViewers,
+    old_display_slot: &'static mut OldObjectiveDisplaySlot,
+    old_scores: &'static mut OldObjectiveScores,
+    old_viewers: &'static mut OldObjectiveViewers,
+    despawned: Option<&'static Despawned>,
+}
+
+fn update_objectives(
+    mut objectives: Query<ObjectiveQuery>,
+    mut clients: Query<(Entity, &mut Client, &Location)>,
+    mut new_viewers: Local<BTreeSet<Entity>>,
+) {
+    for mut obj in &mut objectives {
+        new_viewers.clear();
+
+        if obj.despawned.is_none() {
+            for (entity, client, loc) in &mut clients {
+                // Clients which were just added have not joined the game yet.
+                // The objective is sent to them the next tick.
+                if client.is_added() {
+                    continue;
+                }
+
+                if obj.viewers.clients.contains(&entity) || obj.viewers.instance == Some(loc.0) {
+                    new_viewers.insert(entity);
+                }
+            }
+        }
+
+        let name = obj.name.0.as_str();
+        let old_viewers = &obj.old_viewers.0;
+
+        for &entity in old_viewers.difference(&new_viewers) {
+            // Disconnected clients no longer have the component. The client
+            // removes the scores and display slot of the objective along with
+            // it.
+            if let Ok((_, mut client, _)) = clients.get_mut(entity) {
+                client.write_packet(&ScoreboardObjectiveUpdateS2c {
+                    objective_name: name,
+                    mode: Mode::Remove,
+                });
+            }
+        }
+
+        // The scores which were changed since the last tick, or `None` for
+        // the removed ones.
+        let mut changed_scores = vec![];
+
+        if obj.scores.is_changed() {
+            let old_scores = &obj.old_scores.0;
+
+            for (holder, &score) in &obj.scores.0 {
+                if old_scores.get(holder) != Some(&score) {
+                    changed_scores.push((holder.as_str(), Some(score)));
+                }
+            }
+
+            for holder in old_scores.keys() {
+                if !obj.scores.0.contains_key(holder) {
+                    changed_scores.push((holder.as_str(), None));
+                }
+            }
+        }
+
+        let old_slot = obj.old_display_slot.0;
+        let new_slot = obj.display_slot.0;
+
+        for &entity in new_viewers.iter() {
+            let Ok((_, mut client, _)) = clients.get_mut(entity) else {
+                continue;
+            };
+
+            if !old_viewers.contains(&entity) {
+                client.write_packet(&ScoreboardObjectiveUpdateS2c {
+                    objective_name: name,
+                    mode: Mode::Create {
+                        objective_display_name: obj.display.display_name.clone(),
+                        render_type: obj.display.render_type,
+                    },
+                });
+
+                for (holder, &score) in &obj.scores.0 {
+                    client.write_packet(&ScoreboardPlayerUpdateS2c {
+                        entity_name: holder,
+                        action: Action::Update {
+                            objective_name: name,
+                            objective_score: VarInt(score),
+                        },
+                    });
+                }
+
+                if let Some(slot) = new_slot {
+                    client.write_packet(&ScoreboardDisplayS2c {
+                        position: slot,
+                        score_name: name,
+                    });
+                }
+
+                continue;
+            }
+
+            if obj.display.is_changed() {
+                client.write_packet(&ScoreboardObjectiveUpdateS2c {
+                    objective_name: name,
+                    mode: Mode::Update {
+                        objective_display_name: obj.display.display_name.clone(),
+                        render_type: obj.display.render_type,
+                    },
+                });
+            }
+
+            for &(holder, score) in &changed_scores {
+                client.write_packet(&ScoreboardPlayerUpdateS2c {
+                    entity_name: holder,
+                    action: match score {
+                        Some(score) => Action::Update {
+                            objective_name: name,
+                            objective_score: VarInt(score),
+                        },
+                        None => Action::Remove {
+                            objective_name: name,
+                        },
+                    },
+                });
+            }
+
+            if old_slot != new_slot {
+                // An empty name clears the display slot.
+                if let Some(slot) = old_slot {
+                    client.write_packet(&ScoreboardDisplayS2c {
+                        position: slot,
+                        score_name: "",
+                    });
+                }
+
+                if let Some(slot) = new_slot {
+                    client.write_packet(&ScoreboardDisplayS2c {
+                        position: slot,
+                        score_name: name,
+                    });
+                }
+            }
+        }
+
+        if !changed_scores.is_empty() {
+            obj.old_scores.0.clone_from(&obj.scores.0);
+        }
+
+        if old_slot != new_slot {
+            obj.old_display_slot.0 = new_slot;
+        }
+
+        if obj.old_viewers.0 != *new_viewers {
+            obj.old_viewers.0.clone_from(&new_viewers);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Teams, which change the color of the name tag and the glowing outline of
+//! their members, and the way they collide with each other.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::WorldQuery;
+use valence_client::{Client, Username};
+use valence_core::despawn::Despawned;
+use valence_core::packet::encode::WritePacket;
+pub use valence_core::packet::s2c::play::team::{CollisionRule, NameTagVisibility, TeamColor};
+use valence_core::packet::s2c::play::team::{Mode, TeamFlags, TeamS2c};
+use valence_core::text::Text;
+use valence_core::uuid::UniqueId;
+
+/// The bundle of components for a team. All components are required.
+///
+/// Teams are sent to every client. The team is removed from the screen of the
+/// clients once the entity has the [`Despawned`] component.
+#[derive(Bundle, Default, Debug)]
+pub struct TeamBundle {
+    pub name: TeamName,
+    pub display: TeamDisplay,
+    pub rules: TeamRules,
+    pub members: TeamMembers,
+    pub old_members: OldTeamMembers,
+    pub old_viewers: OldTeamViewers,
+}
+
+/// The unique name identifying a team on the client. Careful not to modify
+/// this!
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
+pub struct TeamName(pub String);
+
+/// How the team and the names of its members are displayed.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct TeamDisplay {
+    pub display_name: Text,
+    /// The text displayed before the names of the members.
+    pub prefix: Text,
+    /// The text displayed after the names of the members.
+    pub suffix: Text,
+    /// The color of the names and the glowing outline of the members.
+    pub color: TeamColor,
+}
+
+impl Default for TeamDisplay {
+    fn default() -> Self {
+        Self {
+            display_name: Text::default(),
+            prefix: Text::default(),
+            suffix: Text::default(),
+            color: TeamColor::Reset,
+        }
+    }
+}
+
+/// How the members of the team interact with each other and with other
+/// players.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TeamRules {
+    /// Whether the members can attack each other.
+    pub friendly_fire: bool,
+    /// Whether the members see invisible members as translucent.
+    pub see_invisible_teammates: bool,
+    pub name_tag_visibility: NameTagVisibility,
+    pub collision_rule: CollisionRule,
+}
+
+impl Default for TeamRules {
+    fn default() -> Self {
+        Self {
+            friendly_fire: true,
+            see_invisible_teammates: true,
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+        }
+    }
+}
+
+impl TeamRules {
+    fn to_packet_flags(self) -> TeamFlags {
+        TeamFlags::new()
+            .with_friendly_fire(self.friendly_fire)
+            .with_see_invisible_teammates(self.see_invisible_teammates)
+    }
+}
+
+/// The entities in the team.
+///
+/// Clients are identified by their [`Username`], and other entities by their
+/// [`UniqueId`]. Entities with neither are ignored. Members are removed from
+/// the team once they have the [`Despawned`] component.
+#[derive(Component, Clone, Default, Debug)]
+pub struct TeamMembers(pub BTreeSet<Entity>);
+
+/// The members of the team and the names identifying them on the client, as
+/// of the last [`UpdateScoreboardsSet`](crate::UpdateScoreboardsSet).
+#[derive(Component, Clone, Default, Debug)]
+pub struct OldTeamMembers(BTreeMap<Entity, String>);
+
+impl OldTeamMembers {
+    pub fn get(&self) -> &BTreeMap<Entity, String> {
+        &self.0
+    }
+}
+
+/// The clients which have the team on their screen, as of the last
+/// [`UpdateScoreboardsSet`](crate::UpdateScoreboardsSet).
+#[derive(Component, Clone, Default, Debug)]
+pub struct OldTeamViewers(BTreeSet<Entity>);
+
+impl OldTeamViewers {
+    pub fn get(&self) -> &BTreeSet<Entity> {
+        &self.0
+    }
+}
+
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub(crate) struct TeamQuery {
+    name: &'static TeamName,
+    display: Ref<'static, TeamDisplay>,
+    rules: Ref<'static, TeamRules>,
+    members: &'static mut TeamMembers,
+    old_members: &'static mut OldTeamMembers,
+    old_viewers: &'static mut OldTeamViewers,
+    despawned: Option<&'static Despawned>,
+}
+
+pub(crate) fn update_teams(
+    mut teams: Query<TeamQuery>,
+    mut clients: Query<(Entity, &mut Client)>,
+    members: Query<(Option<&Username>, Option<&UniqueId>), Without<Despawned>>,
+    mut new_viewers: Local<BTreeSet<Entity>>,
+) {
+    for mut team in &mut teams {
+        new_viewers.clear();
+
+        if team.despawned.is_none() {
+            for (entity, client) in &mut clients {
+                // Clients which were just added have not joined the game yet.
+                // The team is sent to them the next tick.
+                if !client.is_added() {
+                    new_viewers.insert(entity);
+                }
+            }
+        }
+
+        // Forget the members which were despawned.
+        if team.members.0.iter().any(|&e| !members.contains(e)) {
+            team.members.0.retain(|&e| members.contains(e));
+        }
+
+        let mut removed_members = vec![];
+
+        team.old_members.0.retain(|entity, name| {
+            if team.members.0.contains(entity) {
+                true
+            } else {
+                removed_members.push(std::mem::take(name));
+                false
+            }
+        });
+
+        let mut added_members = vec![];
+
+        for &entity in &team.members.0 {
+            if team.old_members.0.contains_key(&entity) {
+                continue;
+            }
+
+            let name = match members.get(entity) {
+                Ok((Some(username), _)) => username.0.clone(),
+                Ok((None, Some(uuid))) => uuid.0.to_string(),
+                _ => continue,
+            };
+
+            added_members.push((entity, name));
+        }
+
+        for (entity, name) in added_members.iter().cloned() {
+            team.old_members.0.insert(entity, name);
+        }
+
+        let name = team.name.0.as_str();
+        let old_viewers = &team.old_viewers.0;
+
+        for &entity in old_viewers.difference(&new_viewers) {
+            // Disconnected clients no longer have the component.
+            if let Ok((_, mut client)) = clients.get_mut(entity) {
+                client.write_packet(&TeamS2c {
+                    team_name: name,
+                    mode: Mode::RemoveTeam,
+                });
+            }
+        }
+
+        for &entity in new_viewers.iter() {
+            let Ok((_, mut client)) = clients.get_mut(entity) else {
+                continue;
+            };
+
+            if !old_viewers.contains(&entity) {
+                client.write_packet(&TeamS2c {
+                    team_name: name,
+                    mode: Mode::CreateTeam {
+                        team_display_name: Cow::Borrowed(&team.display.display_name),
+                        friendly_flags: team.rules.to_packet_flags(),
+                        name_tag_visibility: team.rules.name_tag_visibility,
+                        collision_rule: team.rules.collision_rule,
+                        team_color: team.display.color,
+                        team_prefix: Cow::Borrowed(&team.display.prefix),
+                        team_suffix: Cow::Borrowed(&team.display.suffix),
+                        entities: team.old_members.0.values().map(|n| n.as_str()).collect(),
+                    },
+                });
+
+                continue;
+            }
+
+            if team.display.is_changed() || team.rules.is_changed() {
+                client.write_packet(&TeamS2c {
+                    team_name: name,
+                    mode: Mode::UpdateTeamInfo {
+                        team_display_name: Cow::Borrowed(&team.display.display_name),
+                        friendly_flags: team.rules.to_packet_flags(),
+                        name_tag_visibility: team.rules.name_tag_visibility,
+                        collision_rule: team.rules.collision_rule,
+                        team_color: team.display.color,
+                        team_prefix: Cow::Borrowed(&team.display.prefix),
+                        team_suffix: Cow::Borrowed(&team.display.suffix),
+                    },
+                });
+            }
+
+            if !removed_members.is_empty() {
+                client.write_packet(&TeamS2c {
+                    team_name: name,
+                    mode: Mode::RemoveEntities {
+                        entities: removed_members.iter().map(|n| n.as_str()).collect(),
+                    },
+                });
+            }
+
+            if !added_members.is_empty() {
+                client.write_packet(&TeamS2c {
+                    team_name: name,
+                    mode: Mode::AddEntities {
+                        entities: added_members.iter().map(|(_, n)| n.as_str()).collect(),
+                    },
+                });
+            }
+        }
+
+        if team.old_viewers.0 != *new_viewers {
+            team.old_viewers.0.clone_from(&new_viewers);
+        }
+    }
+}
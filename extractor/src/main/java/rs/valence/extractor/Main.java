@@ -92,6 +92,18 @@ public class Main implements ModInitializer {
                 LOGGER.error("Extractor for \"" + codecExtractor.fileName() + "\" failed.", e);
             }
 
+            var tagsExtractor = new Tags();
+
+            try {
+                var out = outputDirectory.resolve(tagsExtractor.fileName());
+                var fileWriter = new FileWriter(out.toFile(), StandardCharsets.UTF_8);
+                gson.toJson(tagsExtractor.extract(), fileWriter);
+                fileWriter.close();
+                LOGGER.info("Wrote " + out.toAbsolutePath());
+            } catch (Exception e) {
+                LOGGER.error("Extractor for \"" + tagsExtractor.fileName() + "\" failed.", e);
+            }
+
             LOGGER.info("Done.");
             server.shutdown();
         });
@@ -69,6 +69,7 @@ public class Blocks implements Main.Extractor {
                 stateJson.addProperty("id", id);
                 stateJson.addProperty("luminance", state.getLuminance());
                 stateJson.addProperty("opaque", state.isOpaque());
+                stateJson.addProperty("light_opacity", state.getOpacity(EmptyBlockView.INSTANCE, BlockPos.ORIGIN));
                 stateJson.addProperty("replaceable", state.getMaterial().isReplaceable());
 
                 if (block.getDefaultState().equals(state)) {
@@ -3,9 +3,12 @@ package rs.valence.extractor.extractors;
 import com.google.gson.JsonArray;
 import com.google.gson.JsonElement;
 import com.google.gson.JsonObject;
+import net.minecraft.item.ItemStack;
 import net.minecraft.registry.Registries;
 import rs.valence.extractor.Main;
 
+import java.util.Locale;
+
 public class Enchants implements Main.Extractor {
     public Enchants() {
     }
@@ -39,6 +42,25 @@ public class Enchants implements Main.Extractor {
 
             enchantJson.add("sources", enchantmentSources);
 
+            enchantJson.addProperty("target", enchant.target.name().toLowerCase(Locale.ROOT));
+
+            // Some enchantments accept more items than their target, such as sharpness on axes.
+            var acceptableItemsJson = new JsonArray();
+            for (var item : Registries.ITEM) {
+                if (enchant.isAcceptableItem(new ItemStack(item))) {
+                    acceptableItemsJson.add(Registries.ITEM.getId(item).getPath());
+                }
+            }
+            enchantJson.add("acceptable_items", acceptableItemsJson);
+
+            var incompatibleJson = new JsonArray();
+            for (var other : Registries.ENCHANTMENT) {
+                if (other != enchant && !enchant.canCombine(other)) {
+                    incompatibleJson.add(Registries.ENCHANTMENT.getId(other).getPath());
+                }
+            }
+            enchantJson.add("incompatible", incompatibleJson);
+
             enchantsJson.add(enchantJson);
         }
 
@@ -3,6 +3,7 @@ package rs.valence.extractor.extractors;
 import com.google.gson.JsonArray;
 import com.google.gson.JsonElement;
 import com.google.gson.JsonObject;
+import net.minecraft.block.entity.AbstractFurnaceBlockEntity;
 import net.minecraft.registry.Registries;
 import rs.valence.extractor.Main;
 
@@ -18,6 +19,7 @@ public class Items implements Main.Extractor {
     @Override
     public JsonElement extract() throws Exception {
         var itemsJson = new JsonArray();
+        var fuelTimes = AbstractFurnaceBlockEntity.createFuelTimeMap();
 
         for (var item : Registries.ITEM) {
             var itemJson = new JsonObject();
@@ -28,6 +30,10 @@ public class Items implements Main.Extractor {
             itemJson.addProperty("max_durability", item.getMaxDamage());
             itemJson.addProperty("enchantability", item.getEnchantability());
             itemJson.addProperty("fireproof", item.isFireproof());
+            itemJson.addProperty("fuel_time", fuelTimes.getOrDefault(item, 0));
+
+            var remainder = item.getRecipeRemainder();
+            itemJson.addProperty("recipe_remainder", remainder == null ? null : Registries.ITEM.getId(remainder).getPath());
 
             if (item.getFoodComponent() != null) {
                 var foodJson = new JsonObject();
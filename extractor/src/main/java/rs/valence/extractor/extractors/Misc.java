@@ -129,6 +129,12 @@ public class Misc implements Main.Extractor {
         }
         miscJson.add("tracked_data_handler", trackedDataHandlerJson);
 
+        var customStatJson = new JsonObject();
+        for (var id : Registries.CUSTOM_STAT) {
+            customStatJson.addProperty(id.getPath(), Registries.CUSTOM_STAT.getRawId(id));
+        }
+        miscJson.add("custom_stat", customStatJson);
+
         return miscJson;
     }
 }
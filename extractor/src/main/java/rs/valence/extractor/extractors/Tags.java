@@ -0,0 +1,53 @@
+package rs.valence.extractor.extractors;
+
+import com.google.gson.JsonArray;
+import com.google.gson.JsonElement;
+import com.google.gson.JsonObject;
+import net.minecraft.registry.Registries;
+import net.minecraft.registry.Registry;
+import net.minecraft.registry.tag.TagKey;
+import rs.valence.extractor.Main;
+
+import java.util.Comparator;
+
+public class Tags implements Main.Extractor {
+    public Tags() {
+    }
+
+    @Override
+    public String fileName() {
+        return "tags.json";
+    }
+
+    @Override
+    public JsonElement extract() {
+        var tagsJson = new JsonObject();
+
+        tagsJson.add("block", extractTags(Registries.BLOCK));
+        tagsJson.add("item", extractTags(Registries.ITEM));
+
+        return tagsJson;
+    }
+
+    /**
+     * Writes every tag of a registry with the tags it references flattened into their entries.
+     * Tags are only bound once the data packs are loaded, so this must run after the server is created.
+     */
+    private static <T> JsonObject extractTags(Registry<T> registry) {
+        var tagsJson = new JsonObject();
+
+        registry.streamTags()
+                .sorted(Comparator.comparing(tag -> tag.id().toString()))
+                .forEach((TagKey<T> tag) -> {
+                    var entriesJson = new JsonArray();
+
+                    for (var entry : registry.iterateEntries(tag)) {
+                        entriesJson.add(registry.getId(entry.value()).getPath());
+                    }
+
+                    tagsJson.add(tag.id().getPath(), entriesJson);
+                });
+
+        return tagsJson;
+    }
+}